@@ -245,6 +245,36 @@ impl ActivityIndicator {
             });
         }
 
+        // Show any git fetch, pull or push in progress.
+        let mut git_remote_operations = self.project.read(cx).git_remote_operations(cx);
+        if let Some(status) = git_remote_operations.next() {
+            let mut message = status.operation.progress_label().to_string();
+            if let Some(progress_message) = status.message.as_ref() {
+                message.push_str(": ");
+                message.push_str(progress_message);
+            }
+
+            let additional_operation_count = git_remote_operations.count();
+            if additional_operation_count > 0 {
+                write!(&mut message, " + {} more", additional_operation_count).unwrap();
+            }
+
+            return Some(Content {
+                icon: Some(
+                    Icon::new(IconName::ArrowCircle)
+                        .size(IconSize::Small)
+                        .with_animation(
+                            "arrow-circle",
+                            Animation::new(Duration::from_secs(2)).repeat(),
+                            |icon, delta| icon.transform(Transformation::rotate(percentage(delta))),
+                        )
+                        .into_any_element(),
+                ),
+                message,
+                on_click: None,
+            });
+        }
+
         // Show any language server installation info.
         let mut downloading = SmallVec::<[_; 3]>::new();
         let mut checking_for_update = SmallVec::<[_; 3]>::new();
@@ -2438,6 +2438,7 @@ impl Context {
                             }
                             StopReason::EndTurn => {}
                             StopReason::MaxTokens => {}
+                            StopReason::StopSequence => {}
                         }
                     }
                 })
@@ -2477,6 +2478,7 @@ impl Context {
             tools: Vec::new(),
             stop: Vec::new(),
             temperature: None,
+            max_tokens: None,
         };
         for message in self.messages(cx) {
             if message.status != MessageStatus::Done {
@@ -802,6 +802,7 @@ impl PromptLibrary {
                                     tools: Vec::new(),
                                     stop: Vec::new(),
                                     temperature: None,
+                                    max_tokens: None,
                                 },
                                 cx,
                             )
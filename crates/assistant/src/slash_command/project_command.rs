@@ -10,7 +10,7 @@ use gpui::{AppContext, Task, WeakView, WindowContext};
 use language::{Anchor, CodeLabel, LspAdapterDelegate};
 use language_model::{LanguageModelRegistry, LanguageModelTool};
 use schemars::JsonSchema;
-use semantic_index::SemanticDb;
+use semantic_index::{SearchMode, SemanticDb};
 use serde::Deserialize;
 
 pub struct ProjectSlashCommandFeatureFlag;
@@ -114,6 +114,7 @@ impl SlashCommand for ProjectSlashCommand {
                         tools: vec![],
                         stop: vec![],
                         temperature: None,
+                        max_tokens: None,
                     },
                     cx.deref_mut(),
                 )
@@ -122,7 +123,7 @@ impl SlashCommand for ProjectSlashCommand {
 
             let results = project_index
                 .read_with(&cx, |project_index, cx| {
-                    project_index.search(search_queries.clone(), 25, cx)
+                    project_index.search(search_queries.clone(), 25, SearchMode::Semantic, cx)
                 })?
                 .await?;
 
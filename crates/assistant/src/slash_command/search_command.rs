@@ -6,7 +6,7 @@ use assistant_slash_command::{
 use feature_flags::FeatureFlag;
 use gpui::{AppContext, Task, WeakView};
 use language::{CodeLabel, LspAdapterDelegate};
-use semantic_index::{LoadedSearchResult, SemanticDb};
+use semantic_index::{LoadedSearchResult, SearchMode, SemanticDb};
 use std::{
     fmt::Write,
     sync::{atomic::AtomicBool, Arc},
@@ -35,11 +35,11 @@ impl SlashCommand for SearchSlashCommand {
     }
 
     fn label(&self, cx: &AppContext) -> CodeLabel {
-        create_label_for_command("search", &["--n"], cx)
+        create_label_for_command("search", &["--n", "--semantic|--lexical"], cx)
     }
 
     fn description(&self) -> String {
-        "Search your project semantically".into()
+        "Search your project by meaning and keywords".into()
     }
 
     fn icon(&self) -> IconName {
@@ -81,6 +81,7 @@ impl SlashCommand for SearchSlashCommand {
         };
 
         let mut limit = None;
+        let mut mode = SearchMode::Hybrid;
         let mut query = String::new();
         for part in arguments {
             if let Some(parameter) = part.strip_prefix("--") {
@@ -88,6 +89,17 @@ impl SlashCommand for SearchSlashCommand {
                     limit = Some(count);
                     continue;
                 }
+                match parameter {
+                    "semantic" => {
+                        mode = SearchMode::Semantic;
+                        continue;
+                    }
+                    "lexical" => {
+                        mode = SearchMode::Lexical;
+                        continue;
+                    }
+                    _ => {}
+                }
             }
 
             query.push_str(part);
@@ -110,7 +122,7 @@ impl SlashCommand for SearchSlashCommand {
         cx.spawn(|cx| async move {
             let results = project_index
                 .read_with(&cx, |project_index, cx| {
-                    project_index.search(vec![query.clone()], limit.unwrap_or(5), cx)
+                    project_index.search(vec![query.clone()], limit.unwrap_or(5), mode, cx)
                 })?
                 .await?;
 
@@ -68,6 +68,10 @@ impl ActiveThread {
         this
     }
 
+    pub fn thread(&self) -> &Model<Thread> {
+        &self.thread
+    }
+
     pub fn is_empty(&self) -> bool {
         self.messages.is_empty()
     }
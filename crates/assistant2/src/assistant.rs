@@ -10,6 +10,7 @@ mod context_strip;
 mod inline_assistant;
 mod inline_prompt_editor;
 mod message_editor;
+mod persistence;
 mod prompts;
 mod streaming_diff;
 mod terminal_codegen;
@@ -42,6 +43,7 @@ actions!(
         ToggleContextPicker,
         ToggleModelSelector,
         OpenHistory,
+        ExportThreadAsMarkdown,
         Chat,
         CycleNextInlineAssist,
         CyclePreviousInlineAssist
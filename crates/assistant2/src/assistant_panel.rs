@@ -3,6 +3,7 @@ use std::sync::Arc;
 use anyhow::Result;
 use assistant_tool::ToolWorkingSet;
 use client::zed_urls;
+use editor::Editor;
 use fs::Fs;
 use gpui::{
     prelude::*, px, svg, Action, AnyElement, AppContext, AsyncWindowContext, EventEmitter,
@@ -13,16 +14,17 @@ use language::LanguageRegistry;
 use settings::Settings;
 use time::UtcOffset;
 use ui::{prelude::*, KeyBinding, Tab, Tooltip};
+use util::ResultExt as _;
 use workspace::dock::{DockPosition, Panel, PanelEvent};
 use workspace::Workspace;
 
 use crate::active_thread::ActiveThread;
 use crate::assistant_settings::{AssistantDockPosition, AssistantSettings};
 use crate::message_editor::MessageEditor;
-use crate::thread::{ThreadError, ThreadId};
+use crate::thread::{Thread, ThreadError, ThreadId};
 use crate::thread_history::{PastThread, ThreadHistory};
 use crate::thread_store::ThreadStore;
-use crate::{NewThread, OpenHistory, ToggleFocus};
+use crate::{ExportThreadAsMarkdown, NewThread, OpenHistory, ToggleFocus};
 
 pub fn init(cx: &mut AppContext) {
     cx.observe_new_views(
@@ -42,6 +44,11 @@ pub fn init(cx: &mut AppContext) {
                         workspace.focus_panel::<AssistantPanel>(cx);
                         panel.update(cx, |panel, cx| panel.open_history(cx));
                     }
+                })
+                .register_action(|workspace, _: &ExportThreadAsMarkdown, cx| {
+                    if let Some(panel) = workspace.panel::<AssistantPanel>(cx) {
+                        panel.update(cx, |panel, cx| panel.export_thread_as_markdown(cx));
+                    }
                 });
         },
     )
@@ -177,13 +184,18 @@ impl AssistantPanel {
     }
 
     pub(crate) fn open_thread(&mut self, thread_id: &ThreadId, cx: &mut ViewContext<Self>) {
-        let Some(thread) = self
+        let open_thread = self
             .thread_store
-            .update(cx, |this, cx| this.open_thread(thread_id, cx))
-        else {
-            return;
-        };
+            .update(cx, |this, cx| this.open_thread(thread_id, cx));
+
+        cx.spawn(|this, mut cx| async move {
+            let thread = open_thread.await?;
+            this.update(&mut cx, |this, cx| this.set_active_thread(thread, cx))
+        })
+        .detach_and_log_err(cx);
+    }
 
+    fn set_active_thread(&mut self, thread: Model<Thread>, cx: &mut ViewContext<Self>) {
         self.active_view = ActiveView::Thread;
         self.thread = cx.new_view(|cx| {
             ActiveThread::new(
@@ -210,6 +222,37 @@ impl AssistantPanel {
         self.thread_store
             .update(cx, |this, cx| this.delete_thread(thread_id, cx));
     }
+
+    fn export_thread_as_markdown(&mut self, cx: &mut ViewContext<Self>) {
+        let Some(workspace) = self.workspace.upgrade() else {
+            return;
+        };
+        let markdown = self
+            .thread
+            .read(cx)
+            .thread()
+            .read(cx)
+            .serialize()
+            .to_markdown();
+        let project = workspace.read(cx).project().clone();
+        let create_buffer = project.update(cx, |project, cx| project.create_buffer(cx));
+        let markdown_language = self.language_registry.language_for_name("Markdown");
+
+        cx.spawn(|_, mut cx| async move {
+            let buffer = create_buffer.await?;
+            let markdown_language = markdown_language.await.log_err();
+            buffer.update(&mut cx, |buffer, cx| {
+                buffer.set_text(markdown, cx);
+                buffer.set_language(markdown_language, cx);
+            })?;
+            workspace.update(&mut cx, |workspace, cx| {
+                let editor =
+                    cx.new_view(|cx| Editor::for_buffer(buffer, Some(project.clone()), cx));
+                workspace.add_item_to_active_pane(Box::new(editor), None, true, cx);
+            })
+        })
+        .detach_and_log_err(cx);
+    }
 }
 
 impl FocusableView for AssistantPanel {
@@ -347,6 +390,26 @@ impl AssistantPanel {
                                 cx.dispatch_action(OpenHistory.boxed_clone());
                             }),
                     )
+                    .child(
+                        IconButton::new("export-thread", IconName::FileText)
+                            .icon_size(IconSize::Small)
+                            .style(ButtonStyle::Subtle)
+                            .disabled(self.thread.read(cx).is_empty())
+                            .tooltip({
+                                let focus_handle = focus_handle.clone();
+                                move |cx| {
+                                    Tooltip::for_action_in(
+                                        "Export as Markdown",
+                                        &ExportThreadAsMarkdown,
+                                        &focus_handle,
+                                        cx,
+                                    )
+                                }
+                            })
+                            .on_click(move |_event, cx| {
+                                cx.dispatch_action(ExportThreadAsMarkdown.boxed_clone());
+                            }),
+                    )
                     .child(
                         IconButton::new("configure-assistant", IconName::Settings)
                             .icon_size(IconSize::Small)
@@ -368,9 +431,7 @@ impl AssistantPanel {
     }
 
     fn render_thread_empty_state(&self, cx: &mut ViewContext<Self>) -> impl IntoElement {
-        let recent_threads = self
-            .thread_store
-            .update(cx, |this, cx| this.recent_threads(3, cx));
+        let recent_threads = self.thread_store.read(cx).recent_threads(3);
 
         v_flex()
             .gap_2()
@@ -593,6 +654,9 @@ impl Render for AssistantPanel {
             .on_action(cx.listener(|this, _: &OpenHistory, cx| {
                 this.open_history(cx);
             }))
+            .on_action(cx.listener(|this, _: &ExportThreadAsMarkdown, cx| {
+                this.export_thread_as_markdown(cx);
+            }))
             .child(self.render_toolbar(cx))
             .map(|parent| match self.active_view {
                 ActiveView::Thread => parent
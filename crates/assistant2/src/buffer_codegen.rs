@@ -428,6 +428,7 @@ impl CodegenAlternative {
             tools: Vec::new(),
             stop: Vec::new(),
             temperature: None,
+            max_tokens: None,
             messages: vec![request_message],
         })
     }
@@ -13,7 +13,7 @@ impl ContextId {
 }
 
 /// Some context attached to a message in a thread.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Context {
     pub id: ContextId,
     pub name: SharedString,
@@ -21,12 +21,14 @@ pub struct Context {
     pub text: SharedString,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum ContextKind {
     File,
     Directory,
     FetchedUrl,
     Thread,
+    /// A resource provided by a context server.
+    Resource,
 }
 
 pub fn attach_context_to_message(
@@ -37,6 +39,7 @@ pub fn attach_context_to_message(
     let mut directory_context = String::new();
     let mut fetch_context = String::new();
     let mut thread_context = String::new();
+    let mut resource_context = String::new();
 
     for context in context.into_iter() {
         match context.kind {
@@ -60,6 +63,12 @@ pub fn attach_context_to_message(
                 thread_context.push_str(&context.text);
                 thread_context.push('\n');
             }
+            ContextKind::Resource => {
+                resource_context.push_str(&context.name);
+                resource_context.push('\n');
+                resource_context.push_str(&context.text);
+                resource_context.push('\n');
+            }
         }
     }
 
@@ -84,6 +93,11 @@ pub fn attach_context_to_message(
         context_text.push_str(&thread_context);
     }
 
+    if !resource_context.is_empty() {
+        context_text.push_str("The following context server resources are available\n");
+        context_text.push_str(&resource_context);
+    }
+
     if !context_text.is_empty() {
         message.content.push(MessageContent::Text(context_text));
     }
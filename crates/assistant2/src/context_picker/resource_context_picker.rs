@@ -0,0 +1,572 @@
+use std::fmt::Write as _;
+use std::sync::Arc;
+
+use collections::HashMap;
+use context_server::manager::ContextServerManager;
+use context_server::protocol::ServerCapability;
+use fuzzy::StringMatchCandidate;
+use gpui::{
+    AppContext, DismissEvent, FocusHandle, FocusableView, Model, Task, View, WeakModel, WeakView,
+};
+use picker::{Picker, PickerDelegate};
+use ui::{prelude::*, ListItem};
+use url::Url;
+use util::ResultExt as _;
+
+use crate::context_picker::{ConfirmBehavior, ContextPicker};
+use crate::context_store::ContextStore;
+
+pub struct ResourceContextPicker {
+    picker: View<Picker<ResourceContextPickerDelegate>>,
+}
+
+impl ResourceContextPicker {
+    pub fn new(
+        context_server_manager: Model<ContextServerManager>,
+        context_picker: WeakView<ContextPicker>,
+        context_store: WeakModel<ContextStore>,
+        confirm_behavior: ConfirmBehavior,
+        cx: &mut ViewContext<Self>,
+    ) -> Self {
+        let delegate = ResourceContextPickerDelegate::new(
+            context_server_manager,
+            context_picker,
+            context_store,
+            confirm_behavior,
+        );
+        let picker = cx.new_view(|cx| Picker::uniform_list(delegate, cx));
+        picker.update(cx, |picker, cx| picker.delegate.load_entries(cx));
+
+        ResourceContextPicker { picker }
+    }
+}
+
+impl FocusableView for ResourceContextPicker {
+    fn focus_handle(&self, cx: &AppContext) -> FocusHandle {
+        self.picker.focus_handle(cx)
+    }
+}
+
+impl Render for ResourceContextPicker {
+    fn render(&mut self, _cx: &mut ViewContext<Self>) -> impl IntoElement {
+        self.picker.clone()
+    }
+}
+
+#[derive(Debug, Clone)]
+enum ResourceEntry {
+    Resource {
+        server_id: Arc<str>,
+        name: SharedString,
+        uri: Url,
+    },
+    /// A template for resources that depend on arguments, e.g. `file:///{path}`.
+    Template {
+        server_id: Arc<str>,
+        name: SharedString,
+        uri_template: SharedString,
+    },
+}
+
+impl ResourceEntry {
+    fn name(&self) -> &SharedString {
+        match self {
+            ResourceEntry::Resource { name, .. } | ResourceEntry::Template { name, .. } => name,
+        }
+    }
+}
+
+/// The arguments of a resource template that are being entered.
+struct TemplateArguments {
+    server_id: Arc<str>,
+    name: SharedString,
+    uri_template: SharedString,
+    variables: Vec<String>,
+    values: HashMap<String, String>,
+}
+
+impl TemplateArguments {
+    fn current_variable(&self) -> Option<&String> {
+        self.variables.get(self.values.len())
+    }
+}
+
+pub struct ResourceContextPickerDelegate {
+    context_server_manager: Model<ContextServerManager>,
+    context_picker: WeakView<ContextPicker>,
+    context_store: WeakModel<ContextStore>,
+    confirm_behavior: ConfirmBehavior,
+    entries: Option<Vec<ResourceEntry>>,
+    matches: Vec<ResourceEntry>,
+    selected_index: usize,
+    template_arguments: Option<TemplateArguments>,
+    query: String,
+    _load_entries: Task<()>,
+}
+
+impl ResourceContextPickerDelegate {
+    pub fn new(
+        context_server_manager: Model<ContextServerManager>,
+        context_picker: WeakView<ContextPicker>,
+        context_store: WeakModel<ContextStore>,
+        confirm_behavior: ConfirmBehavior,
+    ) -> Self {
+        ResourceContextPickerDelegate {
+            context_server_manager,
+            context_picker,
+            context_store,
+            confirm_behavior,
+            entries: None,
+            matches: Vec::new(),
+            selected_index: 0,
+            template_arguments: None,
+            query: String::new(),
+            _load_entries: Task::ready(()),
+        }
+    }
+
+    /// Lists the resources and resource templates of the running context servers.
+    fn load_entries(&mut self, cx: &mut ViewContext<Picker<Self>>) {
+        let clients = self
+            .context_server_manager
+            .read(cx)
+            .servers()
+            .into_iter()
+            .filter_map(|server| {
+                let client = server.client()?;
+                client
+                    .capable(ServerCapability::Resources)
+                    .then(|| (server.id(), client))
+            })
+            .collect::<Vec<_>>();
+
+        self._load_entries = cx.spawn(|this, mut cx| async move {
+            let mut entries = Vec::new();
+            for (server_id, client) in clients {
+                if let Some(response) = client.list_resources().await.log_err() {
+                    entries.extend(response.resources.into_iter().map(|resource| {
+                        ResourceEntry::Resource {
+                            server_id: server_id.clone(),
+                            name: resource.name.into(),
+                            uri: resource.uri,
+                        }
+                    }));
+                }
+                // Templates are optional, so servers without any may not support listing them.
+                if let Ok(response) = client.list_resource_templates().await {
+                    entries.extend(response.resource_templates.into_iter().map(|template| {
+                        ResourceEntry::Template {
+                            server_id: server_id.clone(),
+                            name: template.name.into(),
+                            uri_template: template.uri_template.into(),
+                        }
+                    }));
+                }
+            }
+
+            this.update(&mut cx, |this, cx| {
+                this.delegate.entries = Some(entries);
+                this.refresh(cx);
+            })
+            .ok();
+        });
+    }
+
+    fn insert_resource(
+        &mut self,
+        server_id: Arc<str>,
+        name: SharedString,
+        uri: Url,
+        cx: &mut ViewContext<Picker<Self>>,
+    ) {
+        let Some(context_store) = self.context_store.upgrade() else {
+            return;
+        };
+        let insert_resource = context_store.update(cx, |context_store, cx| {
+            context_store.insert_resource(
+                self.context_server_manager.clone(),
+                server_id,
+                name,
+                uri,
+                cx,
+            )
+        });
+
+        let confirm_behavior = self.confirm_behavior;
+        cx.spawn(|this, mut cx| async move {
+            insert_resource.await?;
+            this.update(&mut cx, |this, cx| match confirm_behavior {
+                ConfirmBehavior::KeepOpen => {}
+                ConfirmBehavior::Close => this.delegate.dismissed(cx),
+            })
+        })
+        .detach_and_log_err(cx);
+    }
+
+    fn reset_query(cx: &mut ViewContext<Picker<Self>>) {
+        cx.defer(|picker, cx| {
+            picker.set_query("", cx);
+            picker.refresh_placeholder(cx);
+        });
+    }
+}
+
+impl PickerDelegate for ResourceContextPickerDelegate {
+    type ListItem = ListItem;
+
+    fn match_count(&self) -> usize {
+        if self.template_arguments.is_some() {
+            1
+        } else {
+            self.matches.len()
+        }
+    }
+
+    fn no_matches_text(&self, _cx: &mut WindowContext) -> SharedString {
+        if self.entries.is_none() {
+            "Loading resources…".into()
+        } else {
+            "No resources found".into()
+        }
+    }
+
+    fn selected_index(&self) -> usize {
+        self.selected_index
+    }
+
+    fn set_selected_index(&mut self, ix: usize, _cx: &mut ViewContext<Picker<Self>>) {
+        self.selected_index = ix;
+    }
+
+    fn placeholder_text(&self, _cx: &mut WindowContext) -> Arc<str> {
+        match self
+            .template_arguments
+            .as_ref()
+            .and_then(|arguments| Some((arguments, arguments.current_variable()?)))
+        {
+            Some((arguments, variable)) => {
+                format!("Enter the {variable} of {}…", arguments.name).into()
+            }
+            None => "Search resources…".into(),
+        }
+    }
+
+    fn update_matches(&mut self, query: String, cx: &mut ViewContext<Picker<Self>>) -> Task<()> {
+        self.query = query.clone();
+        if self.template_arguments.is_some() {
+            return Task::ready(());
+        }
+        let Some(entries) = self.entries.clone() else {
+            return Task::ready(());
+        };
+
+        let executor = cx.background_executor().clone();
+        let search_task = cx.background_executor().spawn(async move {
+            if query.is_empty() {
+                entries
+            } else {
+                let candidates = entries
+                    .iter()
+                    .enumerate()
+                    .map(|(id, entry)| StringMatchCandidate::new(id, entry.name()))
+                    .collect::<Vec<_>>();
+                let matches = fuzzy::match_strings(
+                    &candidates,
+                    &query,
+                    false,
+                    100,
+                    &Default::default(),
+                    executor,
+                )
+                .await;
+
+                matches
+                    .into_iter()
+                    .map(|mat| entries[mat.candidate_id].clone())
+                    .collect()
+            }
+        });
+
+        cx.spawn(|this, mut cx| async move {
+            let matches = search_task.await;
+            this.update(&mut cx, |this, cx| {
+                this.delegate.matches = matches;
+                this.delegate.selected_index = 0;
+                cx.notify();
+            })
+            .ok();
+        })
+    }
+
+    fn confirm(&mut self, _secondary: bool, cx: &mut ViewContext<Picker<Self>>) {
+        if let Some(arguments) = self.template_arguments.as_mut() {
+            let Some(variable) = arguments.current_variable().cloned() else {
+                return;
+            };
+            arguments.values.insert(variable, self.query.clone());
+            if arguments.current_variable().is_some() {
+                Self::reset_query(cx);
+                return;
+            }
+
+            let Some(arguments) = self.template_arguments.take() else {
+                return;
+            };
+            Self::reset_query(cx);
+            let uri = expand_uri_template(&arguments.uri_template, &arguments.values);
+            match Url::parse(&uri) {
+                Ok(uri) => {
+                    self.insert_resource(arguments.server_id, uri.to_string().into(), uri, cx)
+                }
+                Err(error) => log::error!("invalid resource URI {uri}: {error}"),
+            }
+            return;
+        }
+
+        let Some(entry) = self.matches.get(self.selected_index).cloned() else {
+            return;
+        };
+        match entry {
+            ResourceEntry::Resource {
+                server_id,
+                name,
+                uri,
+            } => self.insert_resource(server_id, name, uri, cx),
+            ResourceEntry::Template {
+                server_id,
+                name,
+                uri_template,
+            } => {
+                let variables = uri_template_variables(&uri_template);
+                if variables.is_empty() {
+                    if let Some(uri) = Url::parse(&uri_template).log_err() {
+                        self.insert_resource(server_id, name, uri, cx);
+                    }
+                    return;
+                }
+                self.template_arguments = Some(TemplateArguments {
+                    server_id,
+                    name,
+                    uri_template,
+                    variables,
+                    values: HashMap::default(),
+                });
+                Self::reset_query(cx);
+            }
+        }
+    }
+
+    fn dismissed(&mut self, cx: &mut ViewContext<Picker<Self>>) {
+        self.context_picker
+            .update(cx, |this, cx| {
+                this.reset_mode();
+                cx.emit(DismissEvent);
+            })
+            .ok();
+    }
+
+    fn render_match(
+        &self,
+        ix: usize,
+        selected: bool,
+        _cx: &mut ViewContext<Picker<Self>>,
+    ) -> Option<Self::ListItem> {
+        if let Some(arguments) = &self.template_arguments {
+            let mut values = arguments.values.clone();
+            if let Some(variable) = arguments.current_variable() {
+                values.insert(variable.clone(), self.query.clone());
+            }
+            let preview = expand_uri_template(&arguments.uri_template, &values);
+
+            return Some(
+                ListItem::new(ix)
+                    .inset(true)
+                    .toggle_state(selected)
+                    .child(Label::new(preview).single_line()),
+            );
+        }
+
+        let entry = &self.matches[ix];
+        let uri: SharedString = match entry {
+            ResourceEntry::Resource { uri, .. } => uri.to_string().into(),
+            ResourceEntry::Template { uri_template, .. } => uri_template.clone(),
+        };
+
+        Some(
+            ListItem::new(ix).inset(true).toggle_state(selected).child(
+                h_flex()
+                    .gap_2()
+                    .child(Label::new(entry.name().clone()).single_line())
+                    .child(
+                        Label::new(uri)
+                            .size(LabelSize::Small)
+                            .color(Color::Muted)
+                            .single_line(),
+                    ),
+            ),
+        )
+    }
+}
+
+/// The operators of RFC 6570 URI template expressions, by how they expand.
+struct ExpressionOperator {
+    prefix: &'static str,
+    separator: &'static str,
+    named: bool,
+    /// The suffix of a named variable with an empty value.
+    if_empty: &'static str,
+    allow_reserved: bool,
+}
+
+impl ExpressionOperator {
+    fn parse(expression: &str) -> (Self, &str) {
+        let operator = |prefix, separator, named, if_empty, allow_reserved| ExpressionOperator {
+            prefix,
+            separator,
+            named,
+            if_empty,
+            allow_reserved,
+        };
+        let mut chars = expression.chars();
+        let parsed = match chars.next() {
+            Some('+') => operator("", ",", false, "", true),
+            Some('#') => operator("#", ",", false, "", true),
+            Some('.') => operator(".", ".", false, "", false),
+            Some('/') => operator("/", "/", false, "", false),
+            Some(';') => operator(";", ";", true, "", false),
+            Some('?') => operator("?", "&", true, "=", false),
+            Some('&') => operator("&", "&", true, "=", false),
+            _ => return (operator("", ",", false, "", false), expression),
+        };
+        (parsed, chars.as_str())
+    }
+}
+
+/// Splits a variable specification like `path:3` or `list*` into the variable name and the
+/// maximum length of its value.
+fn parse_variable(spec: &str) -> (&str, Option<usize>) {
+    let spec = spec.trim_end_matches('*');
+    match spec.split_once(':') {
+        Some((name, max_length)) => (name, max_length.parse().ok()),
+        None => (spec, None),
+    }
+}
+
+fn uri_template_expressions(template: &str) -> impl Iterator<Item = &str> {
+    template
+        .split('{')
+        .skip(1)
+        .filter_map(|part| part.split_once('}').map(|(expression, _)| expression))
+}
+
+/// The names of the variables in a URI template, in the order they appear.
+fn uri_template_variables(template: &str) -> Vec<String> {
+    let mut variables = Vec::new();
+    for expression in uri_template_expressions(template) {
+        let (_, variable_list) = ExpressionOperator::parse(expression);
+        for spec in variable_list.split(',') {
+            let (name, _) = parse_variable(spec);
+            if !name.is_empty() && !variables.iter().any(|variable| variable == name) {
+                variables.push(name.to_string());
+            }
+        }
+    }
+    variables
+}
+
+/// Expands a URI template with the given values, following RFC 6570 for string values.
+fn expand_uri_template(template: &str, values: &HashMap<String, String>) -> String {
+    let mut uri = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let Some(end) = rest[start..].find('}').map(|end| start + end) else {
+            break;
+        };
+        uri.push_str(&rest[..start]);
+
+        let (operator, variable_list) = ExpressionOperator::parse(&rest[start + 1..end]);
+        let mut parts = Vec::new();
+        for spec in variable_list.split(',') {
+            let (name, max_length) = parse_variable(spec);
+            let Some(value) = values.get(name) else {
+                continue;
+            };
+            let value = match max_length {
+                Some(max_length) => value.chars().take(max_length).collect(),
+                None => value.clone(),
+            };
+            let value = percent_encode(&value, operator.allow_reserved);
+            parts.push(if !operator.named {
+                value
+            } else if value.is_empty() {
+                format!("{name}{}", operator.if_empty)
+            } else {
+                format!("{name}={value}")
+            });
+        }
+        if !parts.is_empty() {
+            uri.push_str(operator.prefix);
+            uri.push_str(&parts.join(operator.separator));
+        }
+
+        rest = &rest[end + 1..];
+    }
+    uri.push_str(rest);
+    uri
+}
+
+fn percent_encode(value: &str, allow_reserved: bool) -> String {
+    const RESERVED: &[u8] = b":/?#[]@!$&'()*+,;=";
+
+    let mut encoded = String::new();
+    for byte in value.bytes() {
+        if byte.is_ascii_alphanumeric()
+            || b"-._~".contains(&byte)
+            || (allow_reserved && RESERVED.contains(&byte))
+        {
+            encoded.push(byte as char);
+        } else {
+            write!(encoded, "%{byte:02X}").ok();
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand_uri_template() {
+        let values = HashMap::from_iter([
+            ("path".to_string(), "src/main.rs".to_string()),
+            ("query".to_string(), "hello world".to_string()),
+            ("empty".to_string(), String::new()),
+        ]);
+
+        assert_eq!(
+            uri_template_variables("file:///{+path}{?query,empty,missing}"),
+            ["path", "query", "empty", "missing"]
+        );
+        assert_eq!(
+            expand_uri_template("file:///{path}", &values),
+            "file:///src%2Fmain.rs"
+        );
+        assert_eq!(
+            expand_uri_template("file:///{+path}", &values),
+            "file:///src/main.rs"
+        );
+        assert_eq!(
+            expand_uri_template("search://{?query,empty,missing}", &values),
+            "search://?query=hello%20world&empty="
+        );
+        assert_eq!(
+            expand_uri_template("repo://zed{/path:3}{.missing}", &values),
+            "repo://zed/src"
+        );
+        assert_eq!(
+            expand_uri_template("docs://{;query}#top", &values),
+            "docs://;query=hello%20world#top"
+        );
+    }
+}
@@ -100,15 +100,12 @@ impl PickerDelegate for ThreadContextPickerDelegate {
     }
 
     fn update_matches(&mut self, query: String, cx: &mut ViewContext<Picker<Self>>) -> Task<()> {
-        let Ok(threads) = self.thread_store.update(cx, |this, cx| {
-            this.threads(cx)
+        let Ok(threads) = self.thread_store.read_with(cx, |this, _cx| {
+            this.threads()
                 .into_iter()
-                .map(|thread| {
-                    const DEFAULT_SUMMARY: SharedString = SharedString::new_static("New Thread");
-
-                    let id = thread.read(cx).id().clone();
-                    let summary = thread.read(cx).summary().unwrap_or(DEFAULT_SUMMARY);
-                    ThreadContextEntry { id, summary }
+                .map(|thread| ThreadContextEntry {
+                    id: thread.id,
+                    summary: thread.summary,
                 })
                 .collect::<Vec<_>>()
         }) else {
@@ -162,13 +159,13 @@ impl PickerDelegate for ThreadContextPickerDelegate {
             return;
         };
 
-        let Some(thread) = thread_store.update(cx, |this, cx| this.open_thread(&entry.id, cx))
-        else {
-            return;
-        };
+        let open_thread = thread_store.update(cx, |this, cx| this.open_thread(&entry.id, cx));
+        let context_store = self.context_store.clone();
+        let summary = entry.summary.clone();
 
-        self.context_store
-            .update(cx, |context_store, cx| {
+        cx.spawn(|_, mut cx| async move {
+            let thread = open_thread.await?;
+            context_store.update(&mut cx, |context_store, cx| {
                 let text = thread.update(cx, |thread, _cx| {
                     let mut text = String::new();
 
@@ -187,9 +184,10 @@ impl PickerDelegate for ThreadContextPickerDelegate {
                     text
                 });
 
-                context_store.insert_context(ContextKind::Thread, entry.summary.clone(), text);
+                context_store.insert_context(ContextKind::Thread, summary, text);
             })
-            .ok();
+        })
+        .detach_and_log_err(cx);
 
         match self.confirm_behavior {
             ConfirmBehavior::KeepOpen => {}
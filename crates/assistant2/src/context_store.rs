@@ -1,10 +1,24 @@
-use gpui::SharedString;
+use std::sync::{Arc, Weak};
+
+use anyhow::{anyhow, Result};
+use collections::HashMap;
+use context_server::manager::{ContextServerManager, Event as ContextServerEvent};
+use context_server::protocol::InitializedContextServerProtocol;
+use context_server::types::{ResourceContentsType, ResourcesReadResponse};
+use gpui::{ForegroundExecutor, Model, ModelContext, SharedString, Subscription, Task};
+use url::Url;
+use util::ResultExt as _;
 
 use crate::context::{Context, ContextId, ContextKind};
 
 pub struct ContextStore {
     context: Vec<Context>,
     next_context_id: ContextId,
+    /// The context server resources that are kept up to date while they're attached.
+    watched_resources: HashMap<ContextId, WatchedResource>,
+    /// The server's subscription to each resource, shared by every context attaching it, so that
+    /// it's only unsubscribed from once none of them are left.
+    resource_subscriptions: HashMap<(Arc<str>, Url), Weak<ResourceSubscription>>,
 }
 
 impl ContextStore {
@@ -12,6 +26,8 @@ impl ContextStore {
         Self {
             context: Vec::new(),
             next_context_id: ContextId(0),
+            watched_resources: HashMap::default(),
+            resource_subscriptions: HashMap::default(),
         }
     }
 
@@ -20,10 +36,12 @@ impl ContextStore {
     }
 
     pub fn drain(&mut self) -> Vec<Context> {
+        self.watched_resources.clear();
         self.context.drain(..).collect()
     }
 
     pub fn clear(&mut self) {
+        self.watched_resources.clear();
         self.context.clear();
     }
 
@@ -32,16 +50,295 @@ impl ContextStore {
         kind: ContextKind,
         name: impl Into<SharedString>,
         text: impl Into<SharedString>,
-    ) {
+    ) -> ContextId {
+        let id = self.next_context_id.post_inc();
         self.context.push(Context {
-            id: self.next_context_id.post_inc(),
+            id,
             name: name.into(),
             kind,
             text: text.into(),
         });
+        id
+    }
+
+    /// Attaches the contents of a context server's resource. If the server supports it, the
+    /// contents are fetched again whenever the server signals that the resource changed.
+    pub fn insert_resource(
+        &mut self,
+        context_server_manager: Model<ContextServerManager>,
+        server_id: Arc<str>,
+        name: SharedString,
+        uri: Url,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<()>> {
+        let Some(client) = context_server_manager
+            .read(cx)
+            .get_server(&server_id)
+            .and_then(|server| server.client())
+        else {
+            return Task::ready(Err(anyhow!("context server {server_id} isn't running")));
+        };
+        self.resource_subscriptions
+            .retain(|_, subscription| subscription.strong_count() > 0);
+        let key = (server_id.clone(), uri.clone());
+        let existing_subscription = self.resource_subscription(&key, &client);
+
+        cx.spawn(|this, mut cx| async move {
+            let text = resource_text(client.read_resource(uri.clone()).await?);
+            let subscribed = existing_subscription.is_some()
+                || (client.can_subscribe_to_resources()
+                    && client
+                        .subscribe_to_resource(uri.clone())
+                        .await
+                        .log_err()
+                        .is_some());
+
+            this.update(&mut cx, |this, cx| {
+                let id = this.insert_context(ContextKind::Resource, name, text);
+                if subscribed {
+                    // Another context may have subscribed to the resource in the meantime.
+                    let resource_subscription = existing_subscription
+                        .or_else(|| this.resource_subscription(&key, &client))
+                        .unwrap_or_else(|| {
+                            let resource_subscription = Arc::new(ResourceSubscription {
+                                client: Arc::downgrade(&client),
+                                uri: uri.clone(),
+                                executor: cx.foreground_executor().clone(),
+                            });
+                            this.resource_subscriptions
+                                .insert(key, Arc::downgrade(&resource_subscription));
+                            resource_subscription
+                        });
+                    let subscription = cx.subscribe(&context_server_manager, {
+                        let uri = uri.clone();
+                        move |this, _, event, cx| {
+                            if let ContextServerEvent::ResourceUpdated {
+                                server_id: updated_server_id,
+                                uri: updated_uri,
+                            } = event
+                            {
+                                if *updated_server_id == server_id && *updated_uri == uri {
+                                    this.refresh_resource(id, cx);
+                                }
+                            }
+                        }
+                    });
+                    this.watched_resources.insert(
+                        id,
+                        WatchedResource {
+                            resource_subscription,
+                            _subscription: subscription,
+                        },
+                    );
+                }
+                cx.notify();
+            })
+        })
+    }
+
+    /// The subscription to the resource made through the given client, which is a new one if the
+    /// server was restarted.
+    fn resource_subscription(
+        &self,
+        key: &(Arc<str>, Url),
+        client: &Arc<InitializedContextServerProtocol>,
+    ) -> Option<Arc<ResourceSubscription>> {
+        self.resource_subscriptions
+            .get(key)?
+            .upgrade()
+            .filter(|subscription| subscription.client.ptr_eq(&Arc::downgrade(client)))
     }
 
     pub fn remove_context(&mut self, id: &ContextId) {
+        self.watched_resources.remove(id);
         self.context.retain(|context| context.id != *id);
     }
+
+    fn refresh_resource(&mut self, id: ContextId, cx: &mut ModelContext<Self>) {
+        let Some(resource) = self.watched_resources.get(&id) else {
+            return;
+        };
+        let Some(client) = resource.resource_subscription.client.upgrade() else {
+            return;
+        };
+        let uri = resource.resource_subscription.uri.clone();
+
+        cx.spawn(|this, mut cx| async move {
+            let text = resource_text(client.read_resource(uri).await?);
+            this.update(&mut cx, |this, cx| {
+                if let Some(context) = this.context.iter_mut().find(|context| context.id == id) {
+                    context.text = text.into();
+                    cx.notify();
+                }
+            })
+        })
+        .detach_and_log_err(cx);
+    }
+}
+
+/// A resource context that's refreshed when the context server notifies about changes to it.
+struct WatchedResource {
+    resource_subscription: Arc<ResourceSubscription>,
+    _subscription: Subscription,
+}
+
+/// A resource that the context server notifies about changes to, until it's dropped.
+struct ResourceSubscription {
+    /// The server is stopped when its client is dropped, so this mustn't keep it alive.
+    client: Weak<InitializedContextServerProtocol>,
+    uri: Url,
+    executor: ForegroundExecutor,
+}
+
+impl Drop for ResourceSubscription {
+    fn drop(&mut self) {
+        let Some(client) = self.client.upgrade() else {
+            return;
+        };
+        let uri = self.uri.clone();
+        self.executor
+            .spawn(async move { client.unsubscribe_from_resource(uri).await.log_err() })
+            .detach();
+    }
+}
+
+fn resource_text(response: ResourcesReadResponse) -> String {
+    response
+        .contents
+        .into_iter()
+        .map(|contents| match contents {
+            ResourceContentsType::Text(contents) => contents.text,
+            ResourceContentsType::Blob(contents) => {
+                format!(
+                    "({} has binary contents, which aren't supported)",
+                    contents.uri
+                )
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use context_server::client::{Client, ContextServerId};
+    use context_server::types::{self, NotificationType, RequestType};
+    use context_server::{ContextServerFactoryRegistry, ContextServerSettings};
+    use gpui::{Context as _, SemanticVersion, TestAppContext};
+    use parking_lot::Mutex;
+    use project::{FakeFs, Project};
+    use serde_json::json;
+    use settings::{Settings as _, SettingsStore};
+
+    #[gpui::test]
+    async fn test_insert_resource(cx: &mut TestAppContext) {
+        cx.update(|cx| {
+            let settings_store = SettingsStore::test(cx);
+            cx.set_global(settings_store);
+            release_channel::init(SemanticVersion::default(), cx);
+            language::init(cx);
+            Project::init_settings(cx);
+            ContextServerSettings::register(cx);
+        });
+        let project = Project::test(FakeFs::new(cx.executor()), [], cx).await;
+        let registry = cx.update(ContextServerFactoryRegistry::default_global);
+        let manager = cx.new_model(|cx| ContextServerManager::new(registry, project, cx));
+
+        let (client, server) = Client::fake(ContextServerId("fake".into()), cx.to_async());
+        let text = Arc::new(Mutex::new("v1".to_string()));
+        let subscription_requests = Arc::new(Mutex::new(Vec::new()));
+        server.on_request(RequestType::Initialize.as_str(), |_, _| {
+            Task::ready(Ok(json!({
+                "protocolVersion": types::LATEST_PROTOCOL_VERSION,
+                "capabilities": { "resources": { "subscribe": true } },
+                "serverInfo": { "name": "fake", "version": "1" },
+            })))
+        });
+        server.on_request(RequestType::ResourcesRead.as_str(), {
+            let text = text.clone();
+            move |params, _| {
+                Task::ready(Ok(json!({
+                    "contents": [{ "uri": params["uri"], "text": text.lock().clone() }],
+                })))
+            }
+        });
+        for method in [
+            RequestType::ResourcesSubscribe.as_str(),
+            RequestType::ResourcesUnsubscribe.as_str(),
+        ] {
+            let subscription_requests = subscription_requests.clone();
+            server.on_request(method, move |_, _| {
+                subscription_requests.lock().push(method);
+                Task::ready(Ok(json!({})))
+            });
+        }
+        manager
+            .update(cx, |manager, cx| {
+                manager.add_fake_server("fake".into(), client, cx)
+            })
+            .await
+            .unwrap();
+
+        // Attach the same resource twice.
+        let store = cx.new_model(|_| ContextStore::new());
+        let uri = Url::parse("file:///notes.md").unwrap();
+        for _ in 0..2 {
+            store
+                .update(cx, |store, cx| {
+                    store.insert_resource(
+                        manager.clone(),
+                        "fake".into(),
+                        "notes.md".into(),
+                        uri.clone(),
+                        cx,
+                    )
+                })
+                .await
+                .unwrap();
+        }
+        let context = |cx: &mut TestAppContext| {
+            store.read_with(cx, |store, _| {
+                store
+                    .context()
+                    .iter()
+                    .map(|context| (context.id, context.text.to_string()))
+                    .collect::<Vec<_>>()
+            })
+        };
+        let ids = context(cx)
+            .into_iter()
+            .map(|(id, _)| id)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            context(cx),
+            [(ids[0], "v1".to_string()), (ids[1], "v1".to_string())]
+        );
+        assert_eq!(*subscription_requests.lock(), ["resources/subscribe"]);
+
+        // Both contexts are refreshed when the server notifies that the resource changed.
+        *text.lock() = "v2".to_string();
+        server
+            .notify(
+                NotificationType::ResourcesUpdated.as_str(),
+                json!({ "uri": uri }),
+            )
+            .unwrap();
+        cx.run_until_parked();
+        assert_eq!(
+            context(cx),
+            [(ids[0], "v2".to_string()), (ids[1], "v2".to_string())]
+        );
+
+        // The resource is only unsubscribed from once neither context is attached.
+        store.update(cx, |store, _| store.remove_context(&ids[0]));
+        cx.run_until_parked();
+        assert_eq!(*subscription_requests.lock(), ["resources/subscribe"]);
+        store.update(cx, |store, _| store.remove_context(&ids[1]));
+        cx.run_until_parked();
+        assert_eq!(
+            *subscription_requests.lock(),
+            ["resources/subscribe", "resources/unsubscribe"]
+        );
+    }
 }
@@ -0,0 +1,247 @@
+use anyhow::Result;
+use chrono::{TimeZone as _, Utc};
+use db::{define_connection, query, sqlez_macros::sql};
+
+use crate::thread::{ThreadId, DEFAULT_SUMMARY};
+use crate::thread_store::{SerializedThread, SerializedThreadMetadata};
+
+define_connection!(
+    // Current schema shape using pseudo-rust syntax:
+    // assistant_threads(
+    //   id: String,
+    //   summary: Option<String>,
+    //   updated_at: i64, // Milliseconds since the Unix epoch
+    //   data: String, // A `SerializedThread` as JSON
+    // )
+    //
+    // assistant_threads_search( // A full-text search index
+    //   id: String,
+    //   summary: String,
+    //   content: String,
+    // )
+    pub static ref THREADS_DB: ThreadsDb<()> =
+        &[sql!(
+            CREATE TABLE assistant_threads(
+                id TEXT PRIMARY KEY,
+                summary TEXT,
+                updated_at INTEGER NOT NULL,
+                data TEXT NOT NULL
+            ) STRICT;
+
+            CREATE VIRTUAL TABLE assistant_threads_search USING fts5(
+                id UNINDEXED,
+                summary,
+                content
+            );
+        )];
+);
+
+impl ThreadsDb {
+    query! {
+        fn get_thread_rows() -> Result<Vec<(String, Option<String>, i64)>> {
+            SELECT id, summary, updated_at FROM assistant_threads
+            ORDER BY updated_at DESC
+        }
+    }
+
+    query! {
+        fn get_thread_data(id: &str) -> Result<Option<String>> {
+            SELECT data FROM assistant_threads
+            WHERE id = ?
+        }
+    }
+
+    query! {
+        fn get_matching_thread_ids(query: String) -> Result<Vec<String>> {
+            SELECT id FROM assistant_threads_search
+            WHERE assistant_threads_search MATCH ?
+            ORDER BY rank
+        }
+    }
+
+    /// The saved threads, most recently updated first.
+    pub fn list_threads(&self) -> Result<Vec<SerializedThreadMetadata>> {
+        Ok(self
+            .get_thread_rows()?
+            .into_iter()
+            .map(|(id, summary, updated_at)| SerializedThreadMetadata {
+                id: ThreadId::from(id.as_str()),
+                summary: summary.map_or(DEFAULT_SUMMARY, Into::into),
+                updated_at: Utc
+                    .timestamp_millis_opt(updated_at)
+                    .single()
+                    .unwrap_or_default(),
+            })
+            .collect())
+    }
+
+    pub fn load_thread(&self, id: &ThreadId) -> Result<Option<SerializedThread>> {
+        self.get_thread_data(&id.to_string())?
+            .map(|data| SerializedThread::from_json(&data))
+            .transpose()
+    }
+
+    /// The IDs of the threads whose summary or messages contain all the words of the query,
+    /// best matches first.
+    pub fn search_threads(&self, query: &str) -> Result<Vec<ThreadId>> {
+        let Some(query) = full_text_query(query) else {
+            return Ok(Vec::new());
+        };
+        Ok(self
+            .get_matching_thread_ids(query)?
+            .iter()
+            .map(|id| ThreadId::from(id.as_str()))
+            .collect())
+    }
+
+    pub async fn save_thread(&self, id: ThreadId, thread: SerializedThread) -> Result<()> {
+        let id = id.to_string();
+        let summary = thread.summary.as_ref().map(|summary| summary.to_string());
+        let updated_at = thread.updated_at.timestamp_millis();
+        let content = thread.search_text();
+        let data = serde_json::to_string(&thread)?;
+        self.write(move |conn| {
+            conn.with_savepoint("save_assistant_thread", || {
+                conn.exec_bound(sql!(
+                    INSERT INTO assistant_threads(id, summary, updated_at, data)
+                    VALUES (?1, ?2, ?3, ?4)
+                    ON CONFLICT DO UPDATE SET
+                        summary = ?2,
+                        updated_at = ?3,
+                        data = ?4
+                ))?((id.as_str(), summary.clone(), updated_at, data))?;
+                conn.exec_bound(sql!(
+                    DELETE FROM assistant_threads_search WHERE id = ?
+                ))?(id.as_str())?;
+                conn.exec_bound(sql!(
+                    INSERT INTO assistant_threads_search(id, summary, content)
+                    VALUES (?, ?, ?)
+                ))?((id.as_str(), summary.unwrap_or_default(), content))?;
+                Ok(())
+            })
+        })
+        .await
+    }
+
+    pub async fn delete_thread(&self, id: ThreadId) -> Result<()> {
+        let id = id.to_string();
+        self.write(move |conn| {
+            conn.with_savepoint("delete_assistant_thread", || {
+                conn.exec_bound(sql!(
+                    DELETE FROM assistant_threads WHERE id = ?
+                ))?(id.as_str())?;
+                conn.exec_bound(sql!(
+                    DELETE FROM assistant_threads_search WHERE id = ?
+                ))?(id.as_str())?;
+                Ok(())
+            })
+        })
+        .await
+    }
+}
+
+/// Turns what the user typed into an FTS5 query that matches all of its words, the last one
+/// as a prefix so that results show up while typing.
+fn full_text_query(query: &str) -> Option<String> {
+    let mut words = query
+        .split_whitespace()
+        .map(|word| format!("\"{}\"", word.replace('"', "\"\"")))
+        .collect::<Vec<_>>();
+    words.last_mut()?.push('*');
+    Some(words.join(" "))
+}
+
+#[cfg(test)]
+mod tests {
+    use language_model::Role;
+
+    use super::*;
+    use crate::thread::MessageId;
+    use crate::thread_store::SerializedMessage;
+
+    fn thread(summary: &str, updated_at: i64, messages: &[(Role, &str)]) -> SerializedThread {
+        SerializedThread {
+            version: SerializedThread::VERSION.to_string(),
+            summary: Some(summary.to_string().into()),
+            updated_at: Utc.timestamp_millis_opt(updated_at).unwrap(),
+            messages: messages
+                .iter()
+                .enumerate()
+                .map(|(ix, (role, text))| SerializedMessage {
+                    id: MessageId(ix),
+                    role: *role,
+                    text: text.to_string(),
+                    context: Vec::new(),
+                    tool_uses: Vec::new(),
+                    tool_results: Vec::new(),
+                })
+                .collect(),
+        }
+    }
+
+    #[gpui::test]
+    async fn test_save_load_and_search_threads() {
+        let db = ThreadsDb(db::open_test_db("test_save_load_and_search_threads").await);
+        assert_eq!(db.list_threads().unwrap(), []);
+
+        let rust = thread(
+            "Async Rust",
+            1_000,
+            &[
+                (Role::User, "How do futures work?"),
+                (Role::Assistant, "They are polled by an executor."),
+            ],
+        );
+        let quantum = thread(
+            "Quantum computing",
+            2_000,
+            &[(Role::User, "Explain \"qubits\" to me")],
+        );
+        db.save_thread("rust".into(), rust.clone()).await.unwrap();
+        db.save_thread("quantum".into(), quantum).await.unwrap();
+
+        assert_eq!(
+            db.list_threads()
+                .unwrap()
+                .into_iter()
+                .map(|metadata| (metadata.id.to_string(), metadata.summary.to_string()))
+                .collect::<Vec<_>>(),
+            [
+                ("quantum".to_string(), "Quantum computing".to_string()),
+                ("rust".to_string(), "Async Rust".to_string())
+            ]
+        );
+        assert_eq!(db.load_thread(&"rust".into()).unwrap(), Some(rust));
+        assert_eq!(db.load_thread(&"missing".into()).unwrap(), None);
+
+        assert_eq!(
+            db.search_threads("executor").unwrap(),
+            [ThreadId::from("rust")]
+        );
+        assert_eq!(
+            db.search_threads("quan").unwrap(),
+            [ThreadId::from("quantum")]
+        );
+        assert_eq!(
+            db.search_threads("\"qubits").unwrap(),
+            [ThreadId::from("quantum")]
+        );
+        assert_eq!(db.search_threads("rust quantum").unwrap(), []);
+        assert_eq!(db.search_threads("  ").unwrap(), []);
+
+        // Saving a thread again replaces it in the search index.
+        let rust = thread("Async Rust", 3_000, &[(Role::User, "What is a waker?")]);
+        db.save_thread("rust".into(), rust).await.unwrap();
+        assert_eq!(db.search_threads("executor").unwrap(), []);
+        assert_eq!(
+            db.search_threads("waker").unwrap(),
+            [ThreadId::from("rust")]
+        );
+        assert_eq!(db.list_threads().unwrap()[0].id, ThreadId::from("rust"));
+
+        db.delete_thread("rust".into()).await.unwrap();
+        assert_eq!(db.load_thread(&"rust".into()).unwrap(), None);
+        assert_eq!(db.search_threads("waker").unwrap(), []);
+        assert_eq!(db.list_threads().unwrap().len(), 1);
+    }
+}
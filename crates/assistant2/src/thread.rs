@@ -18,6 +18,7 @@ use util::{post_inc, TryFutureExt as _};
 use uuid::Uuid;
 
 use crate::context::{attach_context_to_message, Context};
+use crate::thread_store::{SerializedMessage, SerializedThread};
 
 #[derive(Debug, Clone, Copy)]
 pub enum RequestKind {
@@ -33,6 +34,12 @@ impl ThreadId {
     }
 }
 
+impl From<&str> for ThreadId {
+    fn from(value: &str) -> Self {
+        Self(value.into())
+    }
+}
+
 impl std::fmt::Display for ThreadId {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", self.0)
@@ -40,7 +47,7 @@ impl std::fmt::Display for ThreadId {
 }
 
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, Serialize, Deserialize)]
-pub struct MessageId(usize);
+pub struct MessageId(pub(crate) usize);
 
 impl MessageId {
     fn post_inc(&mut self) -> Self {
@@ -56,6 +63,9 @@ pub struct Message {
     pub text: String,
 }
 
+/// The summary of threads that haven't been summarized yet.
+pub const DEFAULT_SUMMARY: SharedString = SharedString::new_static("New Thread");
+
 /// A thread of conversation with the LLM.
 pub struct Thread {
     id: ThreadId,
@@ -92,6 +102,72 @@ impl Thread {
         }
     }
 
+    /// Restores a thread saved with [`Thread::serialize`].
+    pub fn from_serialized(
+        id: ThreadId,
+        serialized: SerializedThread,
+        tools: Arc<ToolWorkingSet>,
+        cx: &mut ModelContext<Self>,
+    ) -> Self {
+        let mut this = Self::new(tools, cx);
+        this.id = id;
+        this.updated_at = serialized.updated_at;
+        this.summary = serialized.summary;
+        for message in serialized.messages {
+            this.next_message_id = MessageId(this.next_message_id.0.max(message.id.0 + 1));
+            if !message.context.is_empty() {
+                this.context_by_message.insert(message.id, message.context);
+            }
+            if !message.tool_uses.is_empty() {
+                this.tool_uses_by_message
+                    .insert(message.id, message.tool_uses);
+            }
+            if !message.tool_results.is_empty() {
+                // Tool results are sent along with the message after the one that used the tools.
+                this.tool_results_by_message
+                    .insert(MessageId(message.id.0 + 1), message.tool_results);
+            }
+            this.messages.push(Message {
+                id: message.id,
+                role: message.role,
+                text: message.text,
+            });
+        }
+        this
+    }
+
+    pub fn serialize(&self) -> SerializedThread {
+        SerializedThread {
+            version: SerializedThread::VERSION.to_string(),
+            summary: self.summary.clone(),
+            updated_at: self.updated_at,
+            messages: self
+                .messages
+                .iter()
+                .map(|message| SerializedMessage {
+                    id: message.id,
+                    role: message.role,
+                    text: message.text.clone(),
+                    context: self
+                        .context_by_message
+                        .get(&message.id)
+                        .cloned()
+                        .unwrap_or_default(),
+                    tool_uses: self
+                        .tool_uses_by_message
+                        .get(&message.id)
+                        .cloned()
+                        .unwrap_or_default(),
+                    tool_results: self
+                        .tool_results_by_message
+                        .get(&MessageId(message.id.0 + 1))
+                        .cloned()
+                        .unwrap_or_default(),
+                })
+                .collect(),
+        }
+    }
+
     pub fn id(&self) -> &ThreadId {
         &self.id
     }
@@ -174,6 +250,7 @@ impl Thread {
             tools: Vec::new(),
             stop: Vec::new(),
             temperature: None,
+            max_tokens: None,
         };
 
         for message in &self.messages {
@@ -308,6 +385,7 @@ impl Thread {
                         }
                         StopReason::EndTurn => {}
                         StopReason::MaxTokens => {}
+                        StopReason::StopSequence => {}
                     },
                     Err(error) => {
                         if error.is::<PaymentRequiredError>() {
@@ -1,18 +1,25 @@
+use editor::{Editor, EditorEvent};
 use gpui::{
-    uniform_list, AppContext, FocusHandle, FocusableView, Model, UniformListScrollHandle, WeakView,
+    uniform_list, AppContext, FocusHandle, FocusableView, Model, Subscription, Task,
+    UniformListScrollHandle, View, WeakView,
 };
 use time::{OffsetDateTime, UtcOffset};
 use ui::{prelude::*, IconButtonShape, ListItem, ListItemSpacing, Tooltip};
+use util::ResultExt as _;
 
-use crate::thread::Thread;
-use crate::thread_store::ThreadStore;
+use crate::thread::ThreadId;
+use crate::thread_store::{SerializedThreadMetadata, ThreadStore};
 use crate::AssistantPanel;
 
 pub struct ThreadHistory {
-    focus_handle: FocusHandle,
     assistant_panel: WeakView<AssistantPanel>,
     thread_store: Model<ThreadStore>,
     scroll_handle: UniformListScrollHandle,
+    search_editor: View<Editor>,
+    /// The threads matching the search query, best matches first, if there's a query.
+    search_results: Option<Vec<ThreadId>>,
+    search: Task<()>,
+    _subscriptions: Vec<Subscription>,
 }
 
 impl ThreadHistory {
@@ -21,75 +28,142 @@ impl ThreadHistory {
         thread_store: Model<ThreadStore>,
         cx: &mut ViewContext<Self>,
     ) -> Self {
+        let search_editor = cx.new_view(|cx| {
+            let mut editor = Editor::single_line(cx);
+            editor.set_placeholder_text("Search threads…", cx);
+            editor
+        });
+        let subscriptions = vec![
+            cx.observe(&thread_store, |_, _, cx| cx.notify()),
+            cx.subscribe(&search_editor, |this, _, event, cx| {
+                if let EditorEvent::BufferEdited = event {
+                    this.search(cx);
+                }
+            }),
+        ];
+
         Self {
-            focus_handle: cx.focus_handle(),
             assistant_panel,
             thread_store,
             scroll_handle: UniformListScrollHandle::default(),
+            search_editor,
+            search_results: None,
+            search: Task::ready(()),
+            _subscriptions: subscriptions,
+        }
+    }
+
+    fn search(&mut self, cx: &mut ViewContext<Self>) {
+        let query = self.search_editor.read(cx).text(cx);
+        if query.trim().is_empty() {
+            self.search_results = None;
+            self.search = Task::ready(());
+            cx.notify();
+            return;
+        }
+
+        let search = self.thread_store.read(cx).search_threads(query, cx);
+        self.search = cx.spawn(|this, mut cx| async move {
+            let Some(results) = search.await.log_err() else {
+                return;
+            };
+            this.update(&mut cx, |this, cx| {
+                this.search_results = Some(results);
+                cx.notify();
+            })
+            .ok();
+        });
+    }
+
+    fn threads(&self, cx: &AppContext) -> Vec<SerializedThreadMetadata> {
+        let thread_store = self.thread_store.read(cx);
+        match &self.search_results {
+            Some(results) => results
+                .iter()
+                .filter_map(|id| thread_store.thread_metadata(id).cloned())
+                .collect(),
+            None => thread_store.threads(),
         }
     }
 }
 
 impl FocusableView for ThreadHistory {
-    fn focus_handle(&self, _cx: &AppContext) -> FocusHandle {
-        self.focus_handle.clone()
+    fn focus_handle(&self, cx: &AppContext) -> FocusHandle {
+        self.search_editor.focus_handle(cx)
     }
 }
 
 impl Render for ThreadHistory {
     fn render(&mut self, cx: &mut ViewContext<Self>) -> impl IntoElement {
-        let threads = self.thread_store.update(cx, |this, cx| this.threads(cx));
+        let threads = self.threads(cx);
+        let empty_message = if self.search_results.is_some() {
+            "No threads match your search."
+        } else {
+            "You don't have any past threads yet."
+        };
 
         v_flex()
             .id("thread-history-container")
-            .track_focus(&self.focus_handle)
-            .overflow_y_scroll()
             .size_full()
-            .p_1()
-            .map(|history| {
-                if threads.is_empty() {
-                    history
-                        .justify_center()
-                        .child(
-                            h_flex().w_full().justify_center().child(
-                                Label::new("You don't have any past threads yet.")
-                                    .size(LabelSize::Small),
-                            ),
-                        )
-                } else {
-                    history.child(
-                        uniform_list(
-                            cx.view().clone(),
-                            "thread-history",
-                            threads.len(),
-                            move |history, range, _cx| {
-                                threads[range]
-                                    .iter()
-                                    .map(|thread| {
-                                        h_flex().w_full().pb_1().child(PastThread::new(
-                                            thread.clone(),
-                                            history.assistant_panel.clone(),
-                                        ))
-                                    })
-                                    .collect()
-                            },
-                        )
-                        .track_scroll(self.scroll_handle.clone())
-                        .flex_grow(),
-                    )
-                }
-            })
+            .child(
+                div()
+                    .px_2()
+                    .py_1()
+                    .border_b_1()
+                    .border_color(cx.theme().colors().border_variant)
+                    .child(self.search_editor.clone()),
+            )
+            .child(
+                v_flex()
+                    .overflow_y_scroll()
+                    .flex_grow()
+                    .p_1()
+                    .map(|history| {
+                        if threads.is_empty() {
+                            history.justify_center().child(
+                                h_flex()
+                                    .w_full()
+                                    .justify_center()
+                                    .child(Label::new(empty_message).size(LabelSize::Small)),
+                            )
+                        } else {
+                            history.child(
+                                uniform_list(
+                                    cx.view().clone(),
+                                    "thread-history",
+                                    threads.len(),
+                                    move |history, range, _cx| {
+                                        threads[range]
+                                            .iter()
+                                            .map(|thread| {
+                                                h_flex().w_full().pb_1().child(PastThread::new(
+                                                    thread.clone(),
+                                                    history.assistant_panel.clone(),
+                                                ))
+                                            })
+                                            .collect()
+                                    },
+                                )
+                                .track_scroll(self.scroll_handle.clone())
+                                .flex_grow(),
+                            )
+                        }
+                    }),
+            )
     }
 }
 
 #[derive(IntoElement)]
 pub struct PastThread {
-    thread: Model<Thread>,
+    thread: SerializedThreadMetadata,
     assistant_panel: WeakView<AssistantPanel>,
 }
 
 impl PastThread {
-    pub fn new(thread: Model<Thread>, assistant_panel: WeakView<AssistantPanel>) -> Self {
+    pub fn new(
+        thread: SerializedThreadMetadata,
+        assistant_panel: WeakView<AssistantPanel>,
+    ) -> Self {
         Self {
             thread,
             assistant_panel,
@@ -99,18 +173,11 @@ impl PastThread {
 
 impl RenderOnce for PastThread {
     fn render(self, cx: &mut WindowContext) -> impl IntoElement {
-        let (id, summary) = {
-            const DEFAULT_SUMMARY: SharedString = SharedString::new_static("New Thread");
-            let thread = self.thread.read(cx);
-            (
-                thread.id().clone(),
-                thread.summary().unwrap_or(DEFAULT_SUMMARY),
-            )
-        };
+        let id = self.thread.id;
+        let summary = self.thread.summary;
 
         let thread_timestamp = time_format::format_localized_timestamp(
-            OffsetDateTime::from_unix_timestamp(self.thread.read(cx).updated_at().timestamp())
-                .unwrap(),
+            OffsetDateTime::from_unix_timestamp(self.thread.updated_at.timestamp()).unwrap(),
             OffsetDateTime::now_utc(),
             self.assistant_panel
                 .update(cx, |this, _cx| this.local_timezone())
@@ -118,7 +185,7 @@ impl RenderOnce for PastThread {
             time_format::TimestampFormat::EnhancedAbsolute,
         );
 
-        ListItem::new(("past-thread", self.thread.entity_id()))
+        ListItem::new(SharedString::from(format!("past-thread-{id}")))
             .outlined()
             .start_slot(
                 Icon::new(IconName::MessageCircle)
@@ -1,16 +1,27 @@
+use std::cmp::Reverse;
+use std::fmt::Write as _;
 use std::sync::Arc;
+use std::time::Duration;
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use assistant_tool::{ToolId, ToolWorkingSet};
+use chrono::{DateTime, Utc};
 use collections::HashMap;
 use context_server::manager::ContextServerManager;
 use context_server::{ContextServerFactoryRegistry, ContextServerTool};
-use gpui::{prelude::*, AppContext, Model, ModelContext, Task};
+use gpui::{prelude::*, AppContext, Model, ModelContext, SharedString, Subscription, Task};
+use language_model::{LanguageModelToolResult, LanguageModelToolUse, Role};
 use project::Project;
-use unindent::Unindent;
+use serde::{Deserialize, Serialize};
 use util::ResultExt as _;
 
-use crate::thread::{Thread, ThreadId};
+use crate::context::Context;
+use crate::persistence::THREADS_DB;
+use crate::thread::{MessageId, Thread, ThreadEvent, ThreadId, DEFAULT_SUMMARY};
+
+/// How long a thread has to stay unchanged before it's saved, so that streamed completions
+/// aren't saved after every chunk.
+const SAVE_DEBOUNCE: Duration = Duration::from_millis(500);
 
 pub struct ThreadStore {
     #[allow(unused)]
@@ -18,7 +29,12 @@ pub struct ThreadStore {
     tools: Arc<ToolWorkingSet>,
     context_server_manager: Model<ContextServerManager>,
     context_server_tool_ids: HashMap<Arc<str>, Vec<ToolId>>,
+    /// The threads loaded in memory. Saved threads are only loaded once they're opened.
     threads: Vec<Model<Thread>>,
+    /// The non-empty threads, loaded or not.
+    thread_metadata: Vec<SerializedThreadMetadata>,
+    thread_subscriptions: HashMap<ThreadId, Subscription>,
+    pending_saves: HashMap<ThreadId, Task<()>>,
 }
 
 impl ThreadStore {
@@ -28,6 +44,13 @@ impl ThreadStore {
         cx: &mut AppContext,
     ) -> Task<Result<Model<Self>>> {
         cx.spawn(|mut cx| async move {
+            let thread_metadata = cx
+                .background_executor()
+                .spawn(async move { THREADS_DB.list_threads() })
+                .await
+                .log_err()
+                .unwrap_or_default();
+
             let this = cx.new_model(|cx: &mut ModelContext<Self>| {
                 let context_server_factory_registry =
                     ContextServerFactoryRegistry::default_global(cx);
@@ -35,14 +58,16 @@ impl ThreadStore {
                     ContextServerManager::new(context_server_factory_registry, project.clone(), cx)
                 });
 
-                let mut this = Self {
+                let this = Self {
                     project,
                     tools,
                     context_server_manager,
                     context_server_tool_ids: HashMap::default(),
                     threads: Vec::new(),
+                    thread_metadata,
+                    thread_subscriptions: HashMap::default(),
+                    pending_saves: HashMap::default(),
                 };
-                this.mock_recent_threads(cx);
                 this.register_context_server_handlers(cx);
 
                 this
@@ -52,36 +77,145 @@ impl ThreadStore {
         })
     }
 
-    pub fn threads(&self, cx: &ModelContext<Self>) -> Vec<Model<Thread>> {
-        let mut threads = self
-            .threads
-            .iter()
-            .filter(|thread| !thread.read(cx).is_empty())
-            .cloned()
-            .collect::<Vec<_>>();
-        threads.sort_unstable_by_key(|thread| std::cmp::Reverse(thread.read(cx).updated_at()));
+    pub fn context_server_manager(&self) -> Model<ContextServerManager> {
+        self.context_server_manager.clone()
+    }
+
+    /// The non-empty threads, most recently updated first.
+    pub fn threads(&self) -> Vec<SerializedThreadMetadata> {
+        let mut threads = self.thread_metadata.clone();
+        threads.sort_unstable_by_key(|thread| Reverse(thread.updated_at));
         threads
     }
 
-    pub fn recent_threads(&self, limit: usize, cx: &ModelContext<Self>) -> Vec<Model<Thread>> {
-        self.threads(cx).into_iter().take(limit).collect()
+    pub fn recent_threads(&self, limit: usize) -> Vec<SerializedThreadMetadata> {
+        self.threads().into_iter().take(limit).collect()
     }
 
     pub fn create_thread(&mut self, cx: &mut ModelContext<Self>) -> Model<Thread> {
         let thread = cx.new_model(|cx| Thread::new(self.tools.clone(), cx));
-        self.threads.push(thread.clone());
+        self.track_thread(thread.clone(), cx);
         thread
     }
 
-    pub fn open_thread(&self, id: &ThreadId, cx: &mut ModelContext<Self>) -> Option<Model<Thread>> {
+    /// Opens a thread, loading it from the database unless it's already loaded.
+    pub fn open_thread(
+        &mut self,
+        id: &ThreadId,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<Model<Thread>>> {
+        if let Some(thread) = self.loaded_thread(id, cx) {
+            return Task::ready(Ok(thread));
+        }
+
+        let id = id.clone();
+        let load = cx.background_executor().spawn({
+            let id = id.clone();
+            async move { THREADS_DB.load_thread(&id) }
+        });
+        cx.spawn(|this, mut cx| async move {
+            let serialized = load
+                .await?
+                .ok_or_else(|| anyhow!("thread {id} was not found"))?;
+            this.update(&mut cx, |this, cx| {
+                // The thread may have been opened again while it was loading.
+                if let Some(thread) = this.loaded_thread(&id, cx) {
+                    return thread;
+                }
+                let thread = cx.new_model(|cx| {
+                    Thread::from_serialized(id, serialized, this.tools.clone(), cx)
+                });
+                this.track_thread(thread.clone(), cx);
+                thread
+            })
+        })
+    }
+
+    pub fn delete_thread(&mut self, id: &ThreadId, cx: &mut ModelContext<Self>) {
+        self.threads.retain(|thread| thread.read(cx).id() != id);
+        self.thread_metadata.retain(|thread| &thread.id != id);
+        self.thread_subscriptions.remove(id);
+        self.pending_saves.remove(id);
+
+        let id = id.clone();
+        cx.background_executor()
+            .spawn(async move { THREADS_DB.delete_thread(id).await.log_err() })
+            .detach();
+        cx.notify();
+    }
+
+    /// Searches the summaries and messages of the saved threads, best matches first.
+    pub fn search_threads(&self, query: String, cx: &AppContext) -> Task<Result<Vec<ThreadId>>> {
+        cx.background_executor()
+            .spawn(async move { THREADS_DB.search_threads(&query) })
+    }
+
+    pub fn thread_metadata(&self, id: &ThreadId) -> Option<&SerializedThreadMetadata> {
+        self.thread_metadata.iter().find(|thread| &thread.id == id)
+    }
+
+    fn loaded_thread(&self, id: &ThreadId, cx: &AppContext) -> Option<Model<Thread>> {
         self.threads
             .iter()
             .find(|thread| thread.read(cx).id() == id)
             .cloned()
     }
 
-    pub fn delete_thread(&mut self, id: &ThreadId, cx: &mut ModelContext<Self>) {
-        self.threads.retain(|thread| thread.read(cx).id() != id);
+    fn track_thread(&mut self, thread: Model<Thread>, cx: &mut ModelContext<Self>) {
+        let id = thread.read(cx).id().clone();
+        self.thread_subscriptions
+            .insert(id, cx.subscribe(&thread, Self::handle_thread_event));
+        self.threads.push(thread);
+    }
+
+    fn handle_thread_event(
+        &mut self,
+        thread: Model<Thread>,
+        event: &ThreadEvent,
+        cx: &mut ModelContext<Self>,
+    ) {
+        match event {
+            ThreadEvent::MessageAdded(_)
+            | ThreadEvent::StreamedCompletion
+            | ThreadEvent::SummaryChanged
+            | ThreadEvent::ToolFinished { .. } => self.save_thread(thread, cx),
+            ThreadEvent::ShowError(_)
+            | ThreadEvent::StreamedAssistantText(..)
+            | ThreadEvent::UsePendingTools => {}
+        }
+    }
+
+    fn save_thread(&mut self, thread: Model<Thread>, cx: &mut ModelContext<Self>) {
+        let metadata = {
+            let thread = thread.read(cx);
+            if thread.is_empty() {
+                return;
+            }
+            SerializedThreadMetadata {
+                id: thread.id().clone(),
+                summary: thread.summary().unwrap_or(DEFAULT_SUMMARY),
+                updated_at: thread.updated_at(),
+            }
+        };
+        let id = metadata.id.clone();
+        match self.thread_metadata.iter_mut().find(|saved| saved.id == id) {
+            Some(saved) => *saved = metadata,
+            None => self.thread_metadata.push(metadata),
+        }
+        cx.notify();
+
+        let thread = thread.downgrade();
+        let save = cx.spawn({
+            let id = id.clone();
+            |_, cx| async move {
+                cx.background_executor().timer(SAVE_DEBOUNCE).await;
+                let Ok(serialized) = thread.read_with(&cx, |thread, _| thread.serialize()) else {
+                    return;
+                };
+                THREADS_DB.save_thread(id, serialized).await.log_err();
+            }
+        });
+        self.pending_saves.insert(id, save);
     }
 
     fn register_context_server_handlers(&self, cx: &mut ModelContext<Self>) {
@@ -147,96 +281,121 @@ impl ThreadStore {
                     tool_working_set.remove(&tool_ids);
                 }
             }
+            context_server::manager::Event::ResourceUpdated { .. } => {}
         }
     }
 }
 
-impl ThreadStore {
-    /// Creates some mocked recent threads for testing purposes.
-    fn mock_recent_threads(&mut self, cx: &mut ModelContext<Self>) {
-        use language_model::Role;
-
-        self.threads.push(cx.new_model(|cx| {
-            let mut thread = Thread::new(self.tools.clone(), cx);
-            thread.set_summary("Introduction to quantum computing", cx);
-            thread.insert_user_message("Hello! Can you help me understand quantum computing?", Vec::new(), cx);
-            thread.insert_message(Role::Assistant, "Of course! I'd be happy to help you understand quantum computing. Quantum computing is a fascinating field that uses the principles of quantum mechanics to process information. Unlike classical computers that use bits (0s and 1s), quantum computers use quantum bits or 'qubits'. These qubits can exist in multiple states simultaneously, a property called superposition. This allows quantum computers to perform certain calculations much faster than classical computers. What specific aspect of quantum computing would you like to know more about?", cx);
-            thread.insert_user_message("That's interesting! Can you explain how quantum entanglement is used in quantum computing?", Vec::new(), cx);
-            thread.insert_message(Role::Assistant, "Certainly! Quantum entanglement is a key principle used in quantum computing. When two qubits become entangled, the state of one qubit is directly related to the state of the other, regardless of the distance between them. This property is used in quantum computing to create complex quantum states and to perform operations on multiple qubits simultaneously. Entanglement allows quantum computers to process information in ways that classical computers cannot, potentially solving certain problems much more efficiently. For example, it's crucial in quantum error correction and in algorithms like quantum teleportation, which is important for quantum communication.", cx);
-            thread
-        }));
-
-        self.threads.push(cx.new_model(|cx| {
-            let mut thread = Thread::new(self.tools.clone(), cx);
-            thread.set_summary("Rust web development and async programming", cx);
-            thread.insert_user_message("Can you show me an example of Rust code for a simple web server?", Vec::new(), cx);
-            thread.insert_message(Role::Assistant, "Certainly! Here's an example of a simple web server in Rust using the `actix-web` framework:
-
-        ```rust
-        use actix_web::{web, App, HttpResponse, HttpServer, Responder};
-
-        async fn hello() -> impl Responder {
-            HttpResponse::Ok().body(\"Hello, World!\")
-        }
-
-        #[actix_web::main]
-        async fn main() -> std::io::Result<()> {
-            HttpServer::new(|| {
-                App::new()
-                    .route(\"/\", web::get().to(hello))
-            })
-            .bind(\"127.0.0.1:8080\")?
-            .run()
-            .await
-        }
-        ```
-
-        This code creates a basic web server that responds with 'Hello, World!' when you access the root URL. Here's a breakdown of what's happening:
-
-        1. We import necessary items from the `actix-web` crate.
-        2. We define an async `hello` function that returns a simple HTTP response.
-        3. In the `main` function, we set up the server to listen on `127.0.0.1:8080`.
-        4. We configure the app to respond to GET requests on the root path with our `hello` function.
-
-        To run this, you'd need to add `actix-web` to your `Cargo.toml` dependencies:
-
-        ```toml
-        [dependencies]
-        actix-web = \"4.0\"
-        ```
-
-        Then you can run the server with `cargo run` and access it at `http://localhost:8080`.".unindent(), cx);
-            thread.insert_user_message("That's great! Can you explain more about async functions in Rust?", Vec::new(), cx);
-            thread.insert_message(Role::Assistant, "Certainly! Async functions are a key feature in Rust for writing efficient, non-blocking code, especially for I/O-bound operations. Here's an overview:
-
-        1. **Syntax**: Async functions are declared using the `async` keyword:
-
-           ```rust
-           async fn my_async_function() -> Result<(), Error> {
-               // Asynchronous code here
-           }
-           ```
+/// What the thread history shows of a thread, without loading it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SerializedThreadMetadata {
+    pub id: ThreadId,
+    pub summary: SharedString,
+    pub updated_at: DateTime<Utc>,
+}
 
-        2. **Futures**: Async functions return a `Future`. A `Future` represents a value that may not be available yet but will be at some point.
+/// A thread as it's saved in the database.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SerializedThread {
+    pub version: String,
+    pub summary: Option<SharedString>,
+    pub updated_at: DateTime<Utc>,
+    pub messages: Vec<SerializedMessage>,
+}
 
-        3. **Await**: Inside an async function, you can use the `.await` syntax to wait for other async operations to complete:
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SerializedMessage {
+    pub id: MessageId,
+    pub role: Role,
+    pub text: String,
+    #[serde(default)]
+    pub context: Vec<Context>,
+    #[serde(default)]
+    pub tool_uses: Vec<LanguageModelToolUse>,
+    /// The outputs of the tools in `tool_uses`.
+    #[serde(default)]
+    pub tool_results: Vec<LanguageModelToolResult>,
+}
 
-           ```rust
-           async fn fetch_data() -> Result<String, Error> {
-               let response = make_http_request().await?;
-               let data = process_response(response).await?;
-               Ok(data)
-           }
-           ```
+impl SerializedThread {
+    pub const VERSION: &'static str = "0.1.0";
+
+    pub fn from_json(json: &str) -> Result<Self> {
+        let saved_thread_json = serde_json::from_str::<serde_json::Value>(json)?;
+        match saved_thread_json
+            .get("version")
+            .ok_or_else(|| anyhow!("version not found"))?
+        {
+            serde_json::Value::String(version) => match version.as_str() {
+                SerializedThread::VERSION => Ok(serde_json::from_value::<SerializedThread>(
+                    saved_thread_json,
+                )?),
+                _ => Err(anyhow!("unrecognized saved thread version: {}", version)),
+            },
+            _ => Err(anyhow!("version not found on saved thread")),
+        }
+    }
 
-        4. **Non-blocking**: Async functions allow the runtime to work on other tasks while waiting for I/O or other operations to complete, making efficient use of system resources.
+    /// The text that the thread can be found by.
+    pub fn search_text(&self) -> String {
+        let mut text = String::new();
+        for message in &self.messages {
+            text.push_str(&message.text);
+            text.push('\n');
+        }
+        text
+    }
 
-        5. **Runtime**: To execute async code, you need a runtime like `tokio` or `async-std`. Actix-web, which we used in the previous example, includes its own runtime.
+    pub fn to_markdown(&self) -> String {
+        let mut markdown = String::new();
+        writeln!(
+            markdown,
+            "# {}\n",
+            self.summary.as_ref().unwrap_or(&DEFAULT_SUMMARY)
+        )
+        .ok();
+        for message in &self.messages {
+            let role = match message.role {
+                Role::User => "User",
+                Role::Assistant => "Assistant",
+                Role::System => "System",
+            };
+            writeln!(markdown, "## {role}\n").ok();
+
+            if !message.context.is_empty() {
+                writeln!(markdown, "### Context\n").ok();
+                for context in &message.context {
+                    writeln!(markdown, "- {}", context.name).ok();
+                }
+                markdown.push('\n');
+            }
 
-        6. **Error Handling**: Async functions work well with Rust's `?` operator for error handling.
+            if !message.text.is_empty() {
+                writeln!(markdown, "{}\n", message.text.trim_end()).ok();
+            }
 
-        Async programming in Rust provides a powerful way to write concurrent code that's both safe and efficient. It's particularly useful for servers, network programming, and any application that deals with many concurrent operations.".unindent(), cx);
-            thread
-        }));
+            for tool_use in &message.tool_uses {
+                writeln!(markdown, "**Use Tool: {}**\n", tool_use.name).ok();
+                writeln!(markdown, "```json\n{}\n```\n", tool_use.input).ok();
+                if let Some(result) = message
+                    .tool_results
+                    .iter()
+                    .find(|result| result.tool_use_id == tool_use.id.to_string())
+                {
+                    let label = if result.is_error {
+                        "Tool Error"
+                    } else {
+                        "Tool Result"
+                    };
+                    writeln!(
+                        markdown,
+                        "**{label}**\n\n```\n{}\n```\n",
+                        result.content.trim_end()
+                    )
+                    .ok();
+                }
+            }
+        }
+        markdown
     }
 }
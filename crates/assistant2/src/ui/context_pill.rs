@@ -37,6 +37,7 @@ impl RenderOnce for ContextPill {
             ContextKind::Directory => IconName::Folder,
             ContextKind::FetchedUrl => IconName::Globe,
             ContextKind::Thread => IconName::MessageCircle,
+            ContextKind::Resource => IconName::DatabaseZap,
         };
 
         h_flex()
@@ -1,13 +1,187 @@
+mod diagnostics_tool;
+mod edit_files_tool;
+mod list_directory_tool;
 mod now_tool;
+mod read_file_tool;
+mod search_project_tool;
 
+use std::path::{Component, Path};
+
+use anyhow::{anyhow, Result};
 use assistant_tool::ToolRegistry;
-use gpui::AppContext;
+use gpui::{AppContext, Model, WeakView};
+use project::{Project, ProjectPath};
+use serde::de::DeserializeOwned;
+use workspace::Workspace;
 
+use crate::diagnostics_tool::DiagnosticsTool;
+use crate::edit_files_tool::EditFilesTool;
+use crate::list_directory_tool::ListDirectoryTool;
 use crate::now_tool::NowTool;
+use crate::read_file_tool::ReadFileTool;
+use crate::search_project_tool::SearchProjectTool;
 
 pub fn init(cx: &mut AppContext) {
     assistant_tool::init(cx);
 
     let registry = ToolRegistry::global(cx);
     registry.register_tool(NowTool);
+    registry.register_tool(ReadFileTool);
+    registry.register_tool(ListDirectoryTool);
+    registry.register_tool(SearchProjectTool);
+    registry.register_tool(DiagnosticsTool);
+    registry.register_tool(EditFilesTool);
+}
+
+/// Parses a tool's input, and gets the project of the workspace the tool is run in.
+pub(crate) fn parse_input<T: DeserializeOwned>(
+    input: serde_json::Value,
+    workspace: &WeakView<Workspace>,
+    cx: &AppContext,
+) -> Result<(T, Model<Project>)> {
+    let input = serde_json::from_value(input)?;
+    let project = workspace
+        .upgrade()
+        .map(|workspace| workspace.read(cx).project().clone())
+        .ok_or_else(|| anyhow!("workspace was dropped"))?;
+    Ok((input, project))
+}
+
+/// Resolves a path given by the model, which starts with the name of one of the project's
+/// folders, to a path in the project.
+///
+/// Absolute paths are accepted as long as they're inside one of the project's folders, but
+/// paths that would escape them are rejected.
+pub(crate) fn resolve_project_path(
+    project: &Project,
+    path: &str,
+    cx: &AppContext,
+) -> Result<ProjectPath> {
+    let path = Path::new(path);
+    if path.is_absolute() {
+        return project
+            .visible_worktrees(cx)
+            .find_map(|worktree| {
+                let worktree = worktree.read(cx);
+                let relative_path = path.strip_prefix(worktree.abs_path()).ok()?;
+                is_within_root(relative_path).then(|| ProjectPath {
+                    worktree_id: worktree.id(),
+                    path: relative_path.into(),
+                })
+            })
+            .ok_or_else(|| anyhow!("{} is outside of the project", path.display()));
+    }
+
+    if !is_within_root(path) {
+        return Err(anyhow!("{} is outside of the project", path.display()));
+    }
+    project
+        .find_project_path(path, cx)
+        .ok_or_else(|| anyhow!("{} was not found in the project", path.display()))
+}
+
+fn is_within_root(relative_path: &Path) -> bool {
+    relative_path
+        .components()
+        .all(|component| matches!(component, Component::Normal(_) | Component::CurDir))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::read_file_tool::select_lines;
+    use gpui::TestAppContext;
+    use project::FakeFs;
+    use serde_json::json;
+    use settings::SettingsStore;
+
+    #[gpui::test]
+    async fn test_resolve_project_path(cx: &mut TestAppContext) {
+        cx.update(|cx| {
+            let settings_store = SettingsStore::test(cx);
+            cx.set_global(settings_store);
+            language::init(cx);
+            Project::init_settings(cx);
+        });
+        let fs = FakeFs::new(cx.executor());
+        fs.insert_tree(
+            "/root",
+            json!({
+                "zed": { "src": { "main.rs": "fn main() {\n    println!(\"hi\");\n}" } },
+                "docs": { "README.md": "# Docs" },
+                "secrets": { "key": "hunter2" },
+            }),
+        )
+        .await;
+        let project =
+            Project::test(fs, [Path::new("/root/zed"), Path::new("/root/docs")], cx).await;
+
+        let resolve = |path: &str, cx: &mut TestAppContext| {
+            project.read_with(cx, |project, cx| {
+                let project_path = resolve_project_path(project, path, cx)?;
+                let worktree = project
+                    .worktree_for_id(project_path.worktree_id, cx)
+                    .unwrap();
+                anyhow::Ok(Path::new(worktree.read(cx).root_name()).join(&project_path.path))
+            })
+        };
+        // Relative paths start with the name of one of the root folders.
+        assert_eq!(
+            resolve("zed/src/main.rs", cx).unwrap(),
+            Path::new("zed/src/main.rs")
+        );
+        assert_eq!(
+            resolve("docs/README.md", cx).unwrap(),
+            Path::new("docs/README.md")
+        );
+        assert_eq!(
+            resolve("src/main.rs", cx).unwrap(),
+            Path::new("zed/src/main.rs")
+        );
+        assert!(resolve("secrets/key", cx).is_err());
+        // Absolute paths are only accepted inside the root folders.
+        assert_eq!(
+            resolve("/root/docs/README.md", cx).unwrap(),
+            Path::new("docs/README.md")
+        );
+        assert!(resolve("/root/secrets/key", cx).is_err());
+        // Paths can't escape the root folders.
+        assert!(resolve("zed/../secrets/key", cx).is_err());
+        assert!(resolve("/root/zed/../secrets/key", cx).is_err());
+
+        let project_path = project
+            .read_with(cx, |project, cx| {
+                resolve_project_path(project, "zed/src/main.rs", cx)
+            })
+            .unwrap();
+        let buffer = project
+            .update(cx, |project, cx| project.open_buffer(project_path, cx))
+            .await
+            .unwrap();
+        let text = buffer.read_with(cx, |buffer, _| buffer.text());
+        assert_eq!(select_lines(text.clone(), None, None).unwrap(), text);
+        assert_eq!(
+            select_lines(text.clone(), Some(2), Some(2)).unwrap(),
+            "    println!(\"hi\");"
+        );
+        assert_eq!(
+            select_lines(text.clone(), None, Some(1)).unwrap(),
+            "fn main() {"
+        );
+        assert_eq!(
+            select_lines(text.clone(), Some(2), None).unwrap(),
+            "    println!(\"hi\");\n}"
+        );
+        assert!(select_lines(text, Some(3), Some(2)).is_err());
+    }
+
+    #[test]
+    fn test_is_within_root() {
+        assert!(is_within_root(Path::new("")));
+        assert!(is_within_root(Path::new("zed/src/main.rs")));
+        assert!(is_within_root(Path::new("./zed/src")));
+        assert!(!is_within_root(Path::new("zed/../../etc/passwd")));
+        assert!(!is_within_root(Path::new("..")));
+        assert!(!is_within_root(Path::new("/etc/passwd")));
+    }
 }
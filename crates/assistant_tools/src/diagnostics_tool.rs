@@ -0,0 +1,109 @@
+use std::fmt::Write as _;
+use std::path::Path;
+use std::sync::Arc;
+
+use anyhow::Result;
+use assistant_tool::Tool;
+use gpui::{Task, WeakView, WindowContext};
+use language::{DiagnosticSeverity, Point};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use workspace::Workspace;
+
+use crate::{parse_input, resolve_project_path};
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct DiagnosticsToolInput {
+    /// The path of a file to get the diagnostics of, starting with the name of one of the
+    /// project's root folders. When omitted, the number of errors and warnings in each file
+    /// of the project is returned instead.
+    #[serde(default)]
+    path: Option<String>,
+}
+
+pub struct DiagnosticsTool;
+
+impl Tool for DiagnosticsTool {
+    fn name(&self) -> String {
+        "diagnostics".into()
+    }
+
+    fn description(&self) -> String {
+        "Returns the errors and warnings reported by language servers, either for a single file or as a summary of the whole project.".into()
+    }
+
+    fn input_schema(&self) -> serde_json::Value {
+        let schema = schemars::schema_for!(DiagnosticsToolInput);
+        serde_json::to_value(&schema).unwrap()
+    }
+
+    fn run(
+        self: Arc<Self>,
+        input: serde_json::Value,
+        workspace: WeakView<Workspace>,
+        cx: &mut WindowContext,
+    ) -> Task<Result<String>> {
+        let (input, project) = match parse_input::<DiagnosticsToolInput>(input, &workspace, cx) {
+            Ok(input_and_project) => input_and_project,
+            Err(err) => return Task::ready(Err(err)),
+        };
+
+        let Some(path) = input.path else {
+            let mut output = String::new();
+            let project = project.read(cx);
+            for (project_path, _, summary) in project.diagnostic_summaries(false, cx) {
+                let Some(worktree) = project.worktree_for_id(project_path.worktree_id, cx) else {
+                    continue;
+                };
+                let path = Path::new(worktree.read(cx).root_name()).join(&project_path.path);
+                writeln!(
+                    output,
+                    "{}: {} error(s), {} warning(s)",
+                    path.display(),
+                    summary.error_count,
+                    summary.warning_count
+                )
+                .ok();
+            }
+            if output.is_empty() {
+                output = "The project has no errors or warnings.".into();
+            }
+            return Task::ready(Ok(output));
+        };
+
+        let project_path = match resolve_project_path(project.read(cx), &path, cx) {
+            Ok(project_path) => project_path,
+            Err(err) => return Task::ready(Err(err)),
+        };
+        let open_buffer = project.update(cx, |project, cx| project.open_buffer(project_path, cx));
+        cx.spawn(|cx| async move {
+            let buffer = open_buffer.await?;
+            let snapshot = buffer.read_with(&cx, |buffer, _| buffer.snapshot())?;
+
+            let mut output = String::new();
+            for entry in snapshot.diagnostics_in_range::<_, Point>(0..snapshot.len(), false) {
+                if !entry.diagnostic.is_primary {
+                    continue;
+                }
+                let severity = match entry.diagnostic.severity {
+                    DiagnosticSeverity::ERROR => "error",
+                    DiagnosticSeverity::WARNING => "warning",
+                    _ => continue,
+                };
+                writeln!(
+                    output,
+                    "{}:{}: {}: {}",
+                    entry.range.start.row + 1,
+                    entry.range.start.column + 1,
+                    severity,
+                    entry.diagnostic.message
+                )
+                .ok();
+            }
+            if output.is_empty() {
+                output = format!("{path} has no errors or warnings.");
+            }
+            Ok(output)
+        })
+    }
+}
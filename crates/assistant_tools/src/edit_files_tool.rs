@@ -0,0 +1,159 @@
+use std::ops::Range;
+use std::sync::Arc;
+
+use anyhow::{anyhow, Result};
+use assistant_tool::Tool;
+use editor::{ProposedChangeLocation, ProposedChangesEditor};
+use gpui::{Model, Task, WeakView, WindowContext};
+use language::Buffer;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use workspace::Workspace;
+
+use crate::{parse_input, resolve_project_path};
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct EditFilesToolInput {
+    /// A short title describing the changes, shown to the user while they review them.
+    title: String,
+    /// The edits to propose.
+    edits: Vec<EditFilesToolEdit>,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct EditFilesToolEdit {
+    /// The path of the file to edit, starting with the name of one of the project's root
+    /// folders.
+    path: String,
+    /// The text to replace. It must appear exactly once in the file, so include enough
+    /// surrounding lines to make it unique.
+    old_text: String,
+    /// The text to replace `old_text` with.
+    new_text: String,
+}
+
+pub struct EditFilesTool;
+
+impl Tool for EditFilesTool {
+    fn name(&self) -> String {
+        "edit-files".into()
+    }
+
+    fn description(&self) -> String {
+        "Proposes edits to files in the project by replacing text. The edits are opened in an editor where the user reviews them, and they are only applied if the user accepts them. Read the files before editing them.".into()
+    }
+
+    fn input_schema(&self) -> serde_json::Value {
+        let schema = schemars::schema_for!(EditFilesToolInput);
+        serde_json::to_value(&schema).unwrap()
+    }
+
+    fn run(
+        self: Arc<Self>,
+        input: serde_json::Value,
+        workspace: WeakView<Workspace>,
+        cx: &mut WindowContext,
+    ) -> Task<Result<String>> {
+        let (input, project) = match parse_input::<EditFilesToolInput>(input, &workspace, cx) {
+            Ok(input_and_project) => input_and_project,
+            Err(err) => return Task::ready(Err(err)),
+        };
+
+        cx.spawn(|mut cx| async move {
+            let mut edits_by_buffer: Vec<(Model<Buffer>, Vec<(Range<usize>, String)>)> =
+                Vec::new();
+            for edit in input.edits {
+                let project_path = project
+                    .read_with(&cx, |project, cx| resolve_project_path(project, &edit.path, cx))??;
+                let buffer = project
+                    .update(&mut cx, |project, cx| project.open_buffer(project_path, cx))?
+                    .await?;
+                let text = buffer.read_with(&cx, |buffer, _| buffer.text())?;
+                let range = find_unique(&text, &edit.old_text).map_err(|err| {
+                    anyhow!("{err} in {}", edit.path)
+                })?;
+
+                match edits_by_buffer
+                    .iter_mut()
+                    .find(|(edited_buffer, _)| *edited_buffer == buffer)
+                {
+                    Some((_, edits)) => edits.push((range, edit.new_text)),
+                    None => edits_by_buffer.push((buffer, vec![(range, edit.new_text)])),
+                }
+            }
+            if edits_by_buffer.is_empty() {
+                return Err(anyhow!("no edits were given"));
+            }
+            for (_, edits) in &mut edits_by_buffer {
+                edits.sort_by_key(|(range, _)| range.start);
+                if edits
+                    .windows(2)
+                    .any(|pair| pair[0].0.end > pair[1].0.start)
+                {
+                    return Err(anyhow!("edits to the same file must not overlap"));
+                }
+            }
+
+            let edit_count = edits_by_buffer
+                .iter()
+                .map(|(_, edits)| edits.len())
+                .sum::<usize>();
+            let file_count = edits_by_buffer.len();
+
+            workspace.update(&mut cx, |workspace, cx| {
+                let locations = edits_by_buffer
+                    .iter()
+                    .map(|(buffer, edits)| ProposedChangeLocation {
+                        buffer: buffer.clone(),
+                        ranges: edits.iter().map(|(range, _)| range.clone()).collect(),
+                    })
+                    .collect();
+                let editor = cx.new_view(|cx| {
+                    ProposedChangesEditor::new(input.title, locations, Some(project.clone()), cx)
+                });
+                editor.update(cx, |editor, cx| {
+                    for (buffer, edits) in edits_by_buffer {
+                        if let Some(branch) = editor.branch_buffer_for_base(&buffer) {
+                            branch.update(cx, |branch, cx| branch.edit(edits, None, cx));
+                        }
+                    }
+                    editor.recalculate_all_buffer_diffs();
+                });
+                workspace.add_item_to_active_pane(Box::new(editor), None, false, cx);
+            })?;
+
+            Ok(format!(
+                "Proposed {edit_count} edit(s) in {file_count} file(s). The user is reviewing them and hasn't applied them yet."
+            ))
+        })
+    }
+}
+
+/// Finds the only occurrence of `old_text` in `text`.
+fn find_unique(text: &str, old_text: &str) -> Result<Range<usize>> {
+    if old_text.is_empty() {
+        return Err(anyhow!("old_text is empty"));
+    }
+    let mut matches = text.match_indices(old_text);
+    let Some((start, _)) = matches.next() else {
+        return Err(anyhow!("old_text was not found"));
+    };
+    if matches.next().is_some() {
+        return Err(anyhow!("old_text was found more than once"));
+    }
+    Ok(start..start + old_text.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_unique() {
+        let text = "fn a() {}\nfn b() {}\n";
+        assert_eq!(find_unique(text, "fn b").unwrap(), 10..14);
+        assert!(find_unique(text, "fn c").is_err());
+        assert!(find_unique(text, "() {}").is_err());
+        assert!(find_unique(text, "").is_err());
+    }
+}
@@ -0,0 +1,79 @@
+use std::fmt::Write as _;
+use std::path::Path;
+use std::sync::Arc;
+
+use anyhow::{anyhow, Result};
+use assistant_tool::Tool;
+use gpui::{Task, WeakView, WindowContext};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use workspace::Workspace;
+
+use crate::{parse_input, resolve_project_path};
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct ListDirectoryToolInput {
+    /// The path of the directory to list, starting with the name of one of the project's root
+    /// folders, e.g. `zed/src`. The name of a root folder lists that folder.
+    path: String,
+}
+
+pub struct ListDirectoryTool;
+
+impl Tool for ListDirectoryTool {
+    fn name(&self) -> String {
+        "list-directory".into()
+    }
+
+    fn description(&self) -> String {
+        "Lists the files and directories in a directory of the project. Directories are listed with a trailing slash.".into()
+    }
+
+    fn input_schema(&self) -> serde_json::Value {
+        let schema = schemars::schema_for!(ListDirectoryToolInput);
+        serde_json::to_value(&schema).unwrap()
+    }
+
+    fn run(
+        self: Arc<Self>,
+        input: serde_json::Value,
+        workspace: WeakView<Workspace>,
+        cx: &mut WindowContext,
+    ) -> Task<Result<String>> {
+        let (input, project) = match parse_input::<ListDirectoryToolInput>(input, &workspace, cx) {
+            Ok(input_and_project) => input_and_project,
+            Err(err) => return Task::ready(Err(err)),
+        };
+        let project = project.read(cx);
+        let project_path = match resolve_project_path(project, &input.path, cx) {
+            Ok(project_path) => project_path,
+            Err(err) => return Task::ready(Err(err)),
+        };
+        let Some(worktree) = project.worktree_for_id(project_path.worktree_id, cx) else {
+            return Task::ready(Err(anyhow!("{} was not found", input.path)));
+        };
+        let worktree = worktree.read(cx);
+
+        match worktree.entry_for_path(&project_path.path) {
+            Some(entry) if entry.is_dir() => {}
+            Some(_) => return Task::ready(Err(anyhow!("{} is not a directory", input.path))),
+            None => return Task::ready(Err(anyhow!("{} was not found", input.path))),
+        }
+
+        let root_name = Path::new(worktree.root_name());
+        let mut output = String::new();
+        for entry in worktree.child_entries(&project_path.path) {
+            let path = root_name.join(&entry.path);
+            if entry.is_dir() {
+                writeln!(output, "{}/", path.display()).ok();
+            } else {
+                writeln!(output, "{}", path.display()).ok();
+            }
+        }
+        if output.is_empty() {
+            output = format!("{} is empty.", input.path);
+        }
+
+        Task::ready(Ok(output))
+    }
+}
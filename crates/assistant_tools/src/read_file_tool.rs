@@ -0,0 +1,88 @@
+use std::sync::Arc;
+
+use anyhow::{anyhow, Result};
+use assistant_tool::Tool;
+use gpui::{Task, WeakView, WindowContext};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use workspace::Workspace;
+
+use crate::{parse_input, resolve_project_path};
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct ReadFileToolInput {
+    /// The path of the file to read, starting with the name of one of the project's root
+    /// folders, e.g. `zed/src/main.rs`.
+    path: String,
+    /// The line to start reading at, starting from 1.
+    #[serde(default)]
+    start_line: Option<usize>,
+    /// The last line to read, inclusive.
+    #[serde(default)]
+    end_line: Option<usize>,
+}
+
+pub struct ReadFileTool;
+
+impl Tool for ReadFileTool {
+    fn name(&self) -> String {
+        "read-file".into()
+    }
+
+    fn description(&self) -> String {
+        "Reads the content of a file in the project, or some of its lines. Includes unsaved changes made in the editor.".into()
+    }
+
+    fn input_schema(&self) -> serde_json::Value {
+        let schema = schemars::schema_for!(ReadFileToolInput);
+        serde_json::to_value(&schema).unwrap()
+    }
+
+    fn run(
+        self: Arc<Self>,
+        input: serde_json::Value,
+        workspace: WeakView<Workspace>,
+        cx: &mut WindowContext,
+    ) -> Task<Result<String>> {
+        let (input, project) = match parse_input::<ReadFileToolInput>(input, &workspace, cx) {
+            Ok(input_and_project) => input_and_project,
+            Err(err) => return Task::ready(Err(err)),
+        };
+        let project_path = match resolve_project_path(project.read(cx), &input.path, cx) {
+            Ok(project_path) => project_path,
+            Err(err) => return Task::ready(Err(err)),
+        };
+
+        let open_buffer = project.update(cx, |project, cx| project.open_buffer(project_path, cx));
+        cx.spawn(|cx| async move {
+            let buffer = open_buffer.await?;
+            let text = buffer.read_with(&cx, |buffer, _| buffer.text())?;
+            select_lines(text, input.start_line, input.end_line)
+        })
+    }
+}
+
+/// The lines of the text between the given 1-based lines, inclusive, or all of it if neither is
+/// given.
+pub(crate) fn select_lines(
+    text: String,
+    start_line: Option<usize>,
+    end_line: Option<usize>,
+) -> Result<String> {
+    if start_line.is_none() && end_line.is_none() {
+        return Ok(text);
+    }
+    let start_line = start_line.unwrap_or(1).max(1);
+    let end_line = end_line.unwrap_or(usize::MAX);
+    if start_line > end_line {
+        return Err(anyhow!(
+            "start_line {start_line} is after end_line {end_line}"
+        ));
+    }
+    Ok(text
+        .lines()
+        .skip(start_line - 1)
+        .take(end_line - start_line + 1)
+        .collect::<Vec<_>>()
+        .join("\n"))
+}
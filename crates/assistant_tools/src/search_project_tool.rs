@@ -0,0 +1,149 @@
+use std::fmt::Write as _;
+use std::sync::Arc;
+
+use anyhow::Result;
+use assistant_tool::Tool;
+use gpui::{Task, WeakView, WindowContext};
+use language::{Point, ToPoint as _};
+use project::search::{SearchQuery, SearchResult};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use util::paths::PathMatcher;
+use workspace::Workspace;
+
+use crate::parse_input;
+
+/// The most lines that are returned, so that broad queries don't fill up the context window.
+const MAX_MATCHING_LINES: usize = 200;
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct SearchProjectToolInput {
+    /// The text or regular expression to search for.
+    query: String,
+    /// Whether the query is a regular expression.
+    #[serde(default)]
+    regex: bool,
+    /// Whether the search is case sensitive.
+    #[serde(default)]
+    case_sensitive: bool,
+    /// Glob patterns that the paths of the searched files must match, e.g. `zed/src/**/*.rs`.
+    /// All files are searched when empty.
+    #[serde(default)]
+    include: Vec<String>,
+}
+
+pub struct SearchProjectTool;
+
+impl Tool for SearchProjectTool {
+    fn name(&self) -> String {
+        "search-project".into()
+    }
+
+    fn description(&self) -> String {
+        "Searches the files of the project for text or a regular expression. Returns the matching lines with their paths and line numbers. Files ignored by Git are not searched.".into()
+    }
+
+    fn input_schema(&self) -> serde_json::Value {
+        let schema = schemars::schema_for!(SearchProjectToolInput);
+        serde_json::to_value(&schema).unwrap()
+    }
+
+    fn run(
+        self: Arc<Self>,
+        input: serde_json::Value,
+        workspace: WeakView<Workspace>,
+        cx: &mut WindowContext,
+    ) -> Task<Result<String>> {
+        let (input, project) = match parse_input::<SearchProjectToolInput>(input, &workspace, cx) {
+            Ok(input_and_project) => input_and_project,
+            Err(err) => return Task::ready(Err(err)),
+        };
+        let query = match search_query(&input) {
+            Ok(query) => query,
+            Err(err) => return Task::ready(Err(err)),
+        };
+
+        let results = project.update(cx, |project, cx| project.search(query, cx));
+        cx.spawn(|cx| async move {
+            let mut output = String::new();
+            let mut matching_lines = 0;
+            let mut limit_reached = false;
+            'results: while let Ok(result) = results.recv().await {
+                let (buffer, ranges) = match result {
+                    SearchResult::Buffer { buffer, ranges } => (buffer, ranges),
+                    SearchResult::LimitReached => {
+                        limit_reached = true;
+                        break;
+                    }
+                };
+                let (path, snapshot) = buffer.read_with(&cx, |buffer, cx| {
+                    let path = buffer
+                        .file()
+                        .map(|file| file.full_path(cx).display().to_string());
+                    (path, buffer.snapshot())
+                })?;
+                let Some(path) = path else {
+                    continue;
+                };
+
+                let mut last_row = None;
+                for range in ranges {
+                    let row = range.start.to_point(&snapshot).row;
+                    if last_row == Some(row) {
+                        continue;
+                    }
+                    last_row = Some(row);
+
+                    if matching_lines == MAX_MATCHING_LINES {
+                        limit_reached = true;
+                        break 'results;
+                    }
+                    matching_lines += 1;
+
+                    let line = snapshot
+                        .text_for_range(Point::new(row, 0)..Point::new(row, snapshot.line_len(row)))
+                        .collect::<String>();
+                    writeln!(output, "{}:{}: {}", path, row + 1, line.trim()).ok();
+                }
+            }
+
+            if output.is_empty() {
+                return Ok("No matches found.".into());
+            }
+            if limit_reached {
+                writeln!(
+                    output,
+                    "\nThere are more matches. Narrow down the query or the included paths to see them."
+                )
+                .ok();
+            }
+            Ok(output)
+        })
+    }
+}
+
+fn search_query(input: &SearchProjectToolInput) -> Result<SearchQuery> {
+    let files_to_include = PathMatcher::new(&input.include)?;
+    let files_to_exclude = PathMatcher::default();
+    if input.regex {
+        SearchQuery::regex(
+            &input.query,
+            false,
+            input.case_sensitive,
+            false,
+            files_to_include,
+            files_to_exclude,
+            None,
+        )
+    } else {
+        SearchQuery::text(
+            &input.query,
+            false,
+            input.case_sensitive,
+            false,
+            files_to_include,
+            files_to_exclude,
+            None,
+        )
+    }
+}
@@ -0,0 +1,589 @@
+use std::ops::Range;
+
+use anyhow::Result;
+use editor::{
+    actions::{ShowIncomingCalls, ShowOutgoingCalls},
+    scroll::Autoscroll,
+    Editor, EditorMode,
+};
+use gpui::{
+    actions, uniform_list, AppContext, ClickEvent, EventEmitter, FocusHandle, FocusableView, Model,
+    ScrollStrategy, Task, UniformListScrollHandle, View, WeakView,
+};
+use language::{Buffer, ToPoint};
+use menu::{Confirm, SecondaryConfirm, SelectNext, SelectPrev};
+use project::{CallHierarchyCall, CallHierarchyItem, Location, Project};
+use ui::{prelude::*, ListItem, Tooltip};
+use workspace::{
+    item::{Item, ItemEvent},
+    notifications::NotifyTaskExt,
+    Pane, SplitDirection, Workspace,
+};
+
+actions!(call_hierarchy, [ExpandSelectedEntry, CollapseSelectedEntry]);
+
+pub fn init(cx: &mut AppContext) {
+    cx.observe_new_views(register).detach();
+}
+
+fn register(editor: &mut Editor, cx: &mut ViewContext<Editor>) {
+    if editor.mode() != EditorMode::Full {
+        return;
+    }
+
+    let handle = cx.view().downgrade();
+    editor
+        .register_action({
+            let handle = handle.clone();
+            move |_: &ShowIncomingCalls, cx| {
+                if let Some(editor) = handle.upgrade() {
+                    show_call_hierarchy(editor, CallHierarchyDirection::Incoming, cx);
+                }
+            }
+        })
+        .detach();
+    editor
+        .register_action(move |_: &ShowOutgoingCalls, cx| {
+            if let Some(editor) = handle.upgrade() {
+                show_call_hierarchy(editor, CallHierarchyDirection::Outgoing, cx);
+            }
+        })
+        .detach();
+}
+
+/// Resolves the call hierarchy item under the editor's newest cursor and shows it,
+/// reusing an already open call hierarchy if there is one.
+fn show_call_hierarchy(
+    editor: View<Editor>,
+    direction: CallHierarchyDirection,
+    cx: &mut WindowContext,
+) {
+    let Some(workspace) = editor.read(cx).workspace() else {
+        return;
+    };
+    let Some((buffer, position)) = editor.update(cx, |editor, cx| {
+        let head = editor.selections.newest_anchor().head();
+        editor.buffer().read(cx).text_anchor_for_position(head, cx)
+    }) else {
+        return;
+    };
+
+    let project = workspace.read(cx).project().clone();
+    let origin_pane = workspace.read(cx).active_pane().downgrade();
+    let prepare = project.update(cx, |project, cx| {
+        project.prepare_call_hierarchy(&buffer, position, cx)
+    });
+    cx.spawn(|mut cx| async move {
+        let Some(root) = prepare.await?.into_iter().next() else {
+            return Ok(());
+        };
+        workspace.update(&mut cx, |workspace, cx| {
+            let existing_view = workspace.items_of_type::<CallHierarchyView>(cx).next();
+            if let Some(view) = existing_view {
+                view.update(cx, |view, cx| {
+                    view.origin_pane = origin_pane;
+                    view.set_root(root, direction, cx);
+                });
+                workspace.activate_item(&view, true, true, cx);
+            } else {
+                let view = cx.new_view(|cx| {
+                    CallHierarchyView::new(
+                        root,
+                        direction,
+                        project,
+                        workspace.weak_handle(),
+                        origin_pane,
+                        cx,
+                    )
+                });
+                workspace.split_item(SplitDirection::Right, Box::new(view), cx);
+            }
+        })
+    })
+    .detach_and_notify_err(cx);
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CallHierarchyDirection {
+    /// Show the functions calling the root item.
+    Incoming,
+    /// Show the functions called by the root item.
+    Outgoing,
+}
+
+struct CallHierarchyEntry {
+    item: CallHierarchyItem,
+    /// Where this entry calls its parent (for incoming calls) or is called by it (for outgoing calls).
+    call_sites: Vec<Location>,
+    parent: Option<usize>,
+    depth: usize,
+    expanded: bool,
+    children: Children,
+}
+
+/// The children of an entry are only requested from the language server once it's expanded.
+enum Children {
+    Unresolved,
+    Loading { _task: Task<()> },
+    Resolved(Vec<usize>),
+    Failed,
+}
+
+pub struct CallHierarchyView {
+    project: Model<Project>,
+    workspace: WeakView<Workspace>,
+    /// The pane the hierarchy was requested from, where entries are opened.
+    origin_pane: WeakView<Pane>,
+    direction: CallHierarchyDirection,
+    /// All entries resolved so far. The first one is the root of the hierarchy.
+    entries: Vec<CallHierarchyEntry>,
+    visible_entries: Vec<usize>,
+    selected_entry: Option<usize>,
+    scroll_handle: UniformListScrollHandle,
+    focus_handle: FocusHandle,
+}
+
+impl CallHierarchyView {
+    pub fn new(
+        root: CallHierarchyItem,
+        direction: CallHierarchyDirection,
+        project: Model<Project>,
+        workspace: WeakView<Workspace>,
+        origin_pane: WeakView<Pane>,
+        cx: &mut ViewContext<Self>,
+    ) -> Self {
+        let mut this = Self {
+            project,
+            workspace,
+            origin_pane,
+            direction,
+            entries: Vec::new(),
+            visible_entries: Vec::new(),
+            selected_entry: None,
+            scroll_handle: UniformListScrollHandle::new(),
+            focus_handle: cx.focus_handle(),
+        };
+        this.set_root(root, direction, cx);
+        this
+    }
+
+    pub fn set_root(
+        &mut self,
+        root: CallHierarchyItem,
+        direction: CallHierarchyDirection,
+        cx: &mut ViewContext<Self>,
+    ) {
+        self.direction = direction;
+        self.entries = vec![CallHierarchyEntry {
+            item: root,
+            call_sites: Vec::new(),
+            parent: None,
+            depth: 0,
+            expanded: false,
+            children: Children::Unresolved,
+        }];
+        self.selected_entry = Some(0);
+        self.expand(0, cx);
+        cx.emit(ItemEvent::UpdateTab);
+    }
+
+    fn set_direction(&mut self, direction: CallHierarchyDirection, cx: &mut ViewContext<Self>) {
+        if self.direction == direction {
+            return;
+        }
+        if let Some(root) = self.entries.first().map(|root| root.item.clone()) {
+            self.set_root(root, direction, cx);
+        }
+    }
+
+    fn title(&self) -> String {
+        let name = self
+            .entries
+            .first()
+            .map(|root| root.item.name.as_str())
+            .unwrap_or_default();
+        match self.direction {
+            CallHierarchyDirection::Incoming => format!("Callers of {name}"),
+            CallHierarchyDirection::Outgoing => format!("Callees of {name}"),
+        }
+    }
+
+    fn expand(&mut self, entry_ix: usize, cx: &mut ViewContext<Self>) {
+        let entry = &mut self.entries[entry_ix];
+        entry.expanded = true;
+        if matches!(entry.children, Children::Unresolved | Children::Failed) {
+            self.resolve_children(entry_ix, cx);
+        }
+        self.update_visible_entries(cx);
+    }
+
+    fn collapse(&mut self, entry_ix: usize, cx: &mut ViewContext<Self>) {
+        self.entries[entry_ix].expanded = false;
+        self.update_visible_entries(cx);
+    }
+
+    fn toggle_expanded(&mut self, entry_ix: usize, cx: &mut ViewContext<Self>) {
+        if self.entries[entry_ix].expanded {
+            self.collapse(entry_ix, cx);
+        } else {
+            self.expand(entry_ix, cx);
+        }
+    }
+
+    fn resolve_children(&mut self, entry_ix: usize, cx: &mut ViewContext<Self>) {
+        let item = self.entries[entry_ix].item.clone();
+        let direction = self.direction;
+        let calls = self.project.update(cx, |project, cx| match direction {
+            CallHierarchyDirection::Incoming => project.incoming_calls(item, cx),
+            CallHierarchyDirection::Outgoing => project.outgoing_calls(item, cx),
+        });
+        let task = cx.spawn(|this, mut cx| async move {
+            let calls = calls.await;
+            this.update(&mut cx, |this, cx| {
+                this.children_resolved(entry_ix, calls, cx);
+            })
+            .ok();
+        });
+        self.entries[entry_ix].children = Children::Loading { _task: task };
+    }
+
+    fn children_resolved(
+        &mut self,
+        entry_ix: usize,
+        calls: Result<Vec<CallHierarchyCall>>,
+        cx: &mut ViewContext<Self>,
+    ) {
+        let children = match calls {
+            Ok(calls) => {
+                let depth = self.entries[entry_ix].depth + 1;
+                let mut children = Vec::with_capacity(calls.len());
+                for call in calls {
+                    children.push(self.entries.len());
+                    self.entries.push(CallHierarchyEntry {
+                        item: call.item,
+                        call_sites: call.call_sites,
+                        parent: Some(entry_ix),
+                        depth,
+                        expanded: false,
+                        children: Children::Unresolved,
+                    });
+                }
+                Children::Resolved(children)
+            }
+            Err(error) => {
+                log::error!("failed to resolve call hierarchy: {error:#}");
+                Children::Failed
+            }
+        };
+        self.entries[entry_ix].children = children;
+        self.update_visible_entries(cx);
+    }
+
+    fn update_visible_entries(&mut self, cx: &mut ViewContext<Self>) {
+        self.visible_entries.clear();
+        let mut stack = if self.entries.is_empty() {
+            Vec::new()
+        } else {
+            vec![0]
+        };
+        while let Some(entry_ix) = stack.pop() {
+            self.visible_entries.push(entry_ix);
+            let entry = &self.entries[entry_ix];
+            if let (true, Children::Resolved(children)) = (entry.expanded, &entry.children) {
+                stack.extend(children.iter().rev());
+            }
+        }
+        cx.notify();
+    }
+
+    fn select_entry(&mut self, entry_ix: usize, cx: &mut ViewContext<Self>) {
+        self.selected_entry = Some(entry_ix);
+        if let Some(visible_ix) = self.visible_entries.iter().position(|ix| *ix == entry_ix) {
+            self.scroll_handle
+                .scroll_to_item(visible_ix, ScrollStrategy::Center);
+        }
+        cx.notify();
+    }
+
+    fn selected_visible_ix(&self) -> Option<usize> {
+        let selected_entry = self.selected_entry?;
+        self.visible_entries
+            .iter()
+            .position(|entry_ix| *entry_ix == selected_entry)
+    }
+
+    fn select_next(&mut self, _: &SelectNext, cx: &mut ViewContext<Self>) {
+        let next_ix = self
+            .selected_visible_ix()
+            .map_or(0, |visible_ix| visible_ix + 1);
+        if let Some(entry_ix) = self.visible_entries.get(next_ix).copied() {
+            self.select_entry(entry_ix, cx);
+        }
+    }
+
+    fn select_prev(&mut self, _: &SelectPrev, cx: &mut ViewContext<Self>) {
+        let prev_ix = self
+            .selected_visible_ix()
+            .map_or(0, |visible_ix| visible_ix.saturating_sub(1));
+        if let Some(entry_ix) = self.visible_entries.get(prev_ix).copied() {
+            self.select_entry(entry_ix, cx);
+        }
+    }
+
+    fn expand_selected_entry(&mut self, _: &ExpandSelectedEntry, cx: &mut ViewContext<Self>) {
+        let Some(entry_ix) = self.selected_entry else {
+            return;
+        };
+        let entry = &self.entries[entry_ix];
+        if !entry.expanded {
+            self.expand(entry_ix, cx);
+        } else if let Children::Resolved(children) = &entry.children {
+            if let Some(first_child) = children.first().copied() {
+                self.select_entry(first_child, cx);
+            }
+        }
+    }
+
+    fn collapse_selected_entry(&mut self, _: &CollapseSelectedEntry, cx: &mut ViewContext<Self>) {
+        let Some(entry_ix) = self.selected_entry else {
+            return;
+        };
+        let entry = &self.entries[entry_ix];
+        if entry.expanded {
+            self.collapse(entry_ix, cx);
+        } else if let Some(parent) = entry.parent {
+            self.select_entry(parent, cx);
+        }
+    }
+
+    fn confirm(&mut self, _: &Confirm, cx: &mut ViewContext<Self>) {
+        if let Some(entry_ix) = self.selected_entry {
+            self.open_call_sites(entry_ix, cx);
+        }
+    }
+
+    fn secondary_confirm(&mut self, _: &SecondaryConfirm, cx: &mut ViewContext<Self>) {
+        if let Some(entry_ix) = self.selected_entry {
+            self.open_definition(entry_ix, cx);
+        }
+    }
+
+    /// Opens the calls between the entry and its parent, selecting all of them.
+    fn open_call_sites(&mut self, entry_ix: usize, cx: &mut ViewContext<Self>) {
+        let entry = &self.entries[entry_ix];
+        let Some(buffer) = entry
+            .call_sites
+            .first()
+            .map(|call_site| call_site.buffer.clone())
+        else {
+            self.open_definition(entry_ix, cx);
+            return;
+        };
+        let ranges = entry
+            .call_sites
+            .iter()
+            .filter(|call_site| call_site.buffer == buffer)
+            .map(|call_site| call_site.range.clone())
+            .collect();
+        self.open_ranges(buffer, ranges, cx);
+    }
+
+    fn open_definition(&mut self, entry_ix: usize, cx: &mut ViewContext<Self>) {
+        let item = &self.entries[entry_ix].item;
+        self.open_ranges(item.buffer.clone(), vec![item.selection_range.clone()], cx);
+    }
+
+    fn open_ranges(
+        &self,
+        buffer: Model<Buffer>,
+        ranges: Vec<Range<language::Anchor>>,
+        cx: &mut ViewContext<Self>,
+    ) {
+        let Some(workspace) = self.workspace.upgrade() else {
+            return;
+        };
+        let origin_pane = self.origin_pane.upgrade();
+        workspace.update(cx, |workspace, cx| {
+            let pane = origin_pane
+                .filter(|pane| workspace.panes().contains(pane))
+                .unwrap_or_else(|| workspace.adjacent_pane(cx));
+            let editor =
+                workspace.open_project_item::<Editor>(pane, buffer.clone(), true, true, cx);
+            editor.update(cx, |editor, cx| {
+                let snapshot = buffer.read(cx);
+                let ranges = ranges
+                    .into_iter()
+                    .map(|range| range.start.to_point(snapshot)..range.end.to_point(snapshot))
+                    .collect::<Vec<_>>();
+                editor.change_selections(Some(Autoscroll::center()), cx, |selections| {
+                    selections.select_ranges(ranges)
+                });
+            });
+        });
+    }
+
+    fn render_entry(&self, entry_ix: usize, cx: &mut ViewContext<Self>) -> ListItem {
+        let entry = &self.entries[entry_ix];
+        let toggle = match &entry.children {
+            Children::Resolved(children) if children.is_empty() => None,
+            _ => Some(entry.expanded),
+        };
+        let status = match &entry.children {
+            Children::Loading { .. } => Some(Label::new("Loading…").color(Color::Muted)),
+            Children::Failed => Some(Label::new("Failed to load calls").color(Color::Error)),
+            Children::Resolved(children) if entry.expanded && children.is_empty() => {
+                Some(Label::new("No calls").color(Color::Muted))
+            }
+            _ => None,
+        };
+        let call_count = (entry.call_sites.len() > 1)
+            .then(|| Label::new(format!("{} calls", entry.call_sites.len())).color(Color::Muted));
+
+        ListItem::new(entry_ix)
+            .indent_level(entry.depth)
+            .indent_step_size(px(12.))
+            .toggle_state(self.selected_entry == Some(entry_ix))
+            .toggle(toggle)
+            .on_toggle(cx.listener(move |this, _, cx| this.toggle_expanded(entry_ix, cx)))
+            .start_slot(
+                Icon::new(IconName::Code)
+                    .size(IconSize::Small)
+                    .color(Color::Muted),
+            )
+            .child(
+                h_flex()
+                    .gap_2()
+                    .child(Label::new(entry.item.name.clone()))
+                    .when_some(entry.item.detail.clone(), |this, detail| {
+                        this.child(
+                            Label::new(detail)
+                                .size(LabelSize::Small)
+                                .color(Color::Muted),
+                        )
+                    })
+                    .child(
+                        Label::new(location_label(&entry.item, cx))
+                            .size(LabelSize::Small)
+                            .color(Color::Muted),
+                    )
+                    .children(call_count.map(|label| label.size(LabelSize::Small)))
+                    .children(status.map(|label| label.size(LabelSize::Small))),
+            )
+            .end_hover_slot(
+                IconButton::new(("go-to-definition", entry_ix), IconName::ArrowUpRight)
+                    .icon_size(IconSize::Small)
+                    .tooltip(|cx| Tooltip::for_action("Go to Definition", &SecondaryConfirm, cx))
+                    .on_click(cx.listener(move |this, _, cx| {
+                        this.select_entry(entry_ix, cx);
+                        this.open_definition(entry_ix, cx);
+                    })),
+            )
+            .on_click(cx.listener(move |this, _: &ClickEvent, cx| {
+                this.select_entry(entry_ix, cx);
+                this.open_call_sites(entry_ix, cx);
+            }))
+    }
+}
+
+fn location_label(item: &CallHierarchyItem, cx: &AppContext) -> String {
+    let buffer = item.buffer.read(cx);
+    let row = item.selection_range.start.to_point(buffer).row + 1;
+    match buffer.file() {
+        Some(file) => format!("{}:{row}", file.path().display()),
+        None => format!("{row}"),
+    }
+}
+
+impl Render for CallHierarchyView {
+    fn render(&mut self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let direction = self.direction;
+        v_flex()
+            .id("call-hierarchy")
+            .key_context("CallHierarchy")
+            .track_focus(&self.focus_handle)
+            .size_full()
+            .bg(cx.theme().colors().editor_background)
+            .on_action(cx.listener(Self::select_next))
+            .on_action(cx.listener(Self::select_prev))
+            .on_action(cx.listener(Self::expand_selected_entry))
+            .on_action(cx.listener(Self::collapse_selected_entry))
+            .on_action(cx.listener(Self::confirm))
+            .on_action(cx.listener(Self::secondary_confirm))
+            .child(
+                h_flex()
+                    .px_2()
+                    .py_1()
+                    .gap_2()
+                    .justify_between()
+                    .border_b_1()
+                    .border_color(cx.theme().colors().border_variant)
+                    .child(Label::new(self.title()))
+                    .child(
+                        h_flex()
+                            .gap_1()
+                            .child(
+                                Button::new("show-incoming-calls", "Callers")
+                                    .toggle_state(direction == CallHierarchyDirection::Incoming)
+                                    .on_click(cx.listener(|this, _, cx| {
+                                        this.set_direction(CallHierarchyDirection::Incoming, cx)
+                                    })),
+                            )
+                            .child(
+                                Button::new("show-outgoing-calls", "Callees")
+                                    .toggle_state(direction == CallHierarchyDirection::Outgoing)
+                                    .on_click(cx.listener(|this, _, cx| {
+                                        this.set_direction(CallHierarchyDirection::Outgoing, cx)
+                                    })),
+                            ),
+                    ),
+            )
+            .child(
+                uniform_list(
+                    cx.view().clone(),
+                    "call-hierarchy-entries",
+                    self.visible_entries.len(),
+                    |this, range, cx| {
+                        range
+                            .map(|visible_ix| {
+                                this.render_entry(this.visible_entries[visible_ix], cx)
+                            })
+                            .collect()
+                    },
+                )
+                .size_full()
+                .track_scroll(self.scroll_handle.clone()),
+            )
+    }
+}
+
+impl EventEmitter<ItemEvent> for CallHierarchyView {}
+
+impl FocusableView for CallHierarchyView {
+    fn focus_handle(&self, _: &AppContext) -> FocusHandle {
+        self.focus_handle.clone()
+    }
+}
+
+impl Item for CallHierarchyView {
+    type Event = ItemEvent;
+
+    fn to_item_events(event: &Self::Event, mut f: impl FnMut(ItemEvent)) {
+        f(*event)
+    }
+
+    fn tab_content_text(&self, _: &WindowContext) -> Option<SharedString> {
+        Some(self.title().into())
+    }
+
+    fn tab_icon(&self, _: &WindowContext) -> Option<Icon> {
+        Some(Icon::new(IconName::ListTree))
+    }
+
+    fn telemetry_event_text(&self) -> Option<&'static str> {
+        None
+    }
+
+    fn show_toolbar(&self) -> bool {
+        false
+    }
+}
@@ -128,6 +128,7 @@ pub struct CommandPaletteDelegate {
     commands: Vec<Command>,
     matches: Vec<StringMatch>,
     selected_ix: usize,
+    latest_query: String,
     previous_focus_handle: FocusHandle,
     updating_matches: Option<(
         Task<()>,
@@ -169,6 +170,7 @@ impl CommandPaletteDelegate {
             matches: vec![],
             commands,
             selected_ix: 0,
+            latest_query: String::new(),
             previous_focus_handle,
             updating_matches: None,
         }
@@ -222,6 +224,7 @@ impl CommandPaletteDelegate {
         }
         self.commands = commands;
         self.matches = matches;
+        self.latest_query = query;
         if self.matches.is_empty() {
             self.selected_ix = 0;
         } else {
@@ -357,6 +360,8 @@ impl PickerDelegate for CommandPaletteDelegate {
             return;
         }
         let action_ix = self.matches[self.selected_ix].candidate_id;
+        // The intercepted command is added after all of the regular commands.
+        let intercepted = action_ix >= self.all_commands.len();
         let command = self.commands.swap_remove(action_ix);
 
         telemetry::event!(
@@ -369,6 +374,9 @@ impl PickerDelegate for CommandPaletteDelegate {
         HitCounts::update_global(cx, |hit_counts, _cx| {
             *hit_counts.0.entry(command.name).or_default() += 1;
         });
+        if intercepted {
+            CommandPaletteInterceptor::command_run(&self.latest_query, cx);
+        }
         let action = command.action;
         cx.focus(&self.previous_focus_handle);
         self.dismissed(cx);
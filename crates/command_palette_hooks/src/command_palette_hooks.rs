@@ -106,9 +106,10 @@ pub struct CommandInterceptResult {
 
 /// An interceptor for the command palette.
 #[derive(Default)]
-pub struct CommandPaletteInterceptor(
-    Option<Box<dyn Fn(&str, &AppContext) -> Option<CommandInterceptResult>>>,
-);
+pub struct CommandPaletteInterceptor {
+    handler: Option<Box<dyn Fn(&str, &AppContext) -> Option<CommandInterceptResult>>>,
+    run_handler: Option<Box<dyn Fn(&str, &mut AppContext)>>,
+}
 
 #[derive(Default)]
 struct GlobalCommandPaletteInterceptor(CommandPaletteInterceptor);
@@ -132,14 +133,27 @@ impl CommandPaletteInterceptor {
 
     /// Intercepts the given query from the command palette.
     pub fn intercept(&self, query: &str, cx: &AppContext) -> Option<CommandInterceptResult> {
-        let handler = self.0.as_ref()?;
+        let handler = self.handler.as_ref()?;
 
         (handler)(query, cx)
     }
 
+    /// Notifies the global interceptor that the command it produced for the given query was run.
+    pub fn command_run(query: &str, cx: &mut AppContext) {
+        if !cx.has_global::<GlobalCommandPaletteInterceptor>() {
+            return;
+        }
+        cx.update_global(|this: &mut GlobalCommandPaletteInterceptor, cx| {
+            if let Some(run_handler) = this.0.run_handler.as_ref() {
+                (run_handler)(query, cx)
+            }
+        })
+    }
+
     /// Clears the global interceptor.
     pub fn clear(&mut self) {
-        self.0 = None;
+        self.handler = None;
+        self.run_handler = None;
     }
 
     /// Sets the global interceptor.
@@ -149,6 +163,12 @@ impl CommandPaletteInterceptor {
         &mut self,
         handler: Box<dyn Fn(&str, &AppContext) -> Option<CommandInterceptResult>>,
     ) {
-        self.0 = Some(handler);
+        self.handler = Some(handler);
+    }
+
+    /// Sets the handler that is called with the query whenever a command produced by the
+    /// interceptor is run.
+    pub fn set_run_handler(&mut self, run_handler: Box<dyn Fn(&str, &mut AppContext)>) {
+        self.run_handler = Some(run_handler);
     }
 }
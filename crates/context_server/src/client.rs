@@ -1,3 +1,5 @@
+mod sse;
+
 use anyhow::{anyhow, Context, Result};
 use collections::HashMap;
 use futures::{channel::oneshot, io::BufWriter, select, AsyncRead, AsyncWrite, FutureExt};
@@ -20,6 +22,7 @@ use std::{
     },
     time::{Duration, Instant},
 };
+use url::Url;
 use util::TryFutureExt;
 
 const JSON_RPC_VERSION: &str = "2.0";
@@ -34,6 +37,7 @@ pub const INTERNAL_ERROR: i32 = -32603;
 
 type ResponseHandler = Box<dyn Send + FnOnce(Result<String, Error>)>;
 type NotificationHandler = Box<dyn Send + FnMut(Value, AsyncAppContext)>;
+type RequestHandler = Box<dyn Send + FnMut(Value, AsyncAppContext) -> Task<Result<Value>>>;
 
 #[derive(Debug, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
 #[serde(untagged)]
@@ -48,6 +52,7 @@ pub struct Client {
     outbound_tx: channel::Sender<String>,
     name: Arc<str>,
     notification_handlers: Arc<Mutex<HashMap<&'static str, NotificationHandler>>>,
+    request_handlers: Arc<Mutex<HashMap<&'static str, RequestHandler>>>,
     response_handlers: Arc<Mutex<Option<HashMap<RequestId, ResponseHandler>>>>,
     #[allow(clippy::type_complexity)]
     #[allow(dead_code)]
@@ -79,6 +84,16 @@ struct Request<'a, T> {
     params: T,
 }
 
+/// A request sent by the server to the client.
+#[derive(Debug, Clone, Deserialize)]
+struct AnyRequest<'a> {
+    jsonrpc: &'a str,
+    id: RequestId,
+    method: String,
+    #[serde(default)]
+    params: Option<Value>,
+}
+
 #[derive(Serialize, Deserialize)]
 struct AnyResponse<'a> {
     jsonrpc: &'a str,
@@ -123,11 +138,32 @@ struct AnyNotification<'a> {
     params: Option<Value>,
 }
 
+/// The response to a request sent by the server.
+#[derive(Serialize)]
+struct OutgoingResponse {
+    jsonrpc: &'static str,
+    id: RequestId,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    result: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<Error>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 struct Error {
+    #[serde(default)]
+    code: i32,
     message: String,
 }
 
+/// What handles the messages the server sends, shared by the transports.
+struct IncomingHandlers {
+    notification_handlers: Arc<Mutex<HashMap<&'static str, NotificationHandler>>>,
+    request_handlers: Arc<Mutex<HashMap<&'static str, RequestHandler>>>,
+    response_handlers: Arc<Mutex<Option<HashMap<RequestId, ResponseHandler>>>>,
+    outbound_tx: channel::Sender<String>,
+}
+
 #[derive(Debug, Clone, Deserialize)]
 pub struct ModelContextServerBinary {
     pub executable: PathBuf,
@@ -135,6 +171,12 @@ pub struct ModelContextServerBinary {
     pub env: Option<HashMap<String, String>>,
 }
 
+#[derive(Debug, Clone)]
+pub struct ModelContextServerEndpoint {
+    pub url: Url,
+    pub headers: HashMap<String, String>,
+}
+
 impl Client {
     /// Creates a new Client instance for a context server.
     ///
@@ -171,23 +213,60 @@ impl Client {
         let stdin = server.stdin.take().unwrap();
         let stdout = server.stdout.take().unwrap();
         let stderr = server.stderr.take().unwrap();
+        let name: Arc<str> = binary
+            .executable
+            .file_name()
+            .map_or_else(|| "".into(), |name| name.to_string_lossy().into());
+
+        Ok(Self::new_internal(
+            server_id,
+            name,
+            stdin,
+            stdout,
+            Some(stderr),
+            Some(server),
+            cx,
+        ))
+    }
 
+    fn new_internal<Stdin, Stdout, Stderr>(
+        server_id: ContextServerId,
+        name: Arc<str>,
+        stdin: Stdin,
+        stdout: Stdout,
+        stderr: Option<Stderr>,
+        server: Option<Child>,
+        cx: AsyncAppContext,
+    ) -> Self
+    where
+        Stdin: AsyncWrite + Unpin + Send + 'static,
+        Stdout: AsyncRead + Unpin + Send + 'static,
+        Stderr: AsyncRead + Unpin + Send + 'static,
+    {
         let (outbound_tx, outbound_rx) = channel::unbounded::<String>();
         let (output_done_tx, output_done_rx) = barrier::channel();
 
         let notification_handlers =
             Arc::new(Mutex::new(HashMap::<_, NotificationHandler>::default()));
+        let request_handlers = Arc::new(Mutex::new(HashMap::<_, RequestHandler>::default()));
         let response_handlers =
             Arc::new(Mutex::new(Some(HashMap::<_, ResponseHandler>::default())));
 
         let stdout_input_task = cx.spawn({
-            let notification_handlers = notification_handlers.clone();
-            let response_handlers = response_handlers.clone();
-            move |cx| {
-                Self::handle_input(stdout, notification_handlers, response_handlers, cx).log_err()
+            let handlers = IncomingHandlers {
+                notification_handlers: notification_handlers.clone(),
+                request_handlers: request_handlers.clone(),
+                response_handlers: response_handlers.clone(),
+                outbound_tx: outbound_tx.clone(),
+            };
+            move |cx| Self::handle_input(stdout, handlers, cx).log_err()
+        });
+        let stderr_input_task = cx.spawn(|_| async move {
+            match stderr {
+                Some(stderr) => Self::handle_stderr(stderr).log_err().await,
+                None => Some(()),
             }
         });
-        let stderr_input_task = cx.spawn(|_| Self::handle_stderr(stderr).log_err());
         let input_task = cx.spawn(|_| async move {
             let (stdout, stderr) = futures::join!(stdout_input_task, stderr_input_task);
             stdout.or(stderr)
@@ -202,24 +281,110 @@ impl Client {
             .log_err()
         });
 
-        let mut context_server = Self {
+        Self {
             server_id,
             notification_handlers,
+            request_handlers,
             response_handlers,
-            name: "".into(),
+            name,
             next_id: Default::default(),
             outbound_tx,
             executor: cx.background_executor().clone(),
             io_tasks: Mutex::new(Some((input_task, output_task))),
             output_done_rx: Mutex::new(Some(output_done_rx)),
-            server: Arc::new(Mutex::new(Some(server))),
-        };
-
-        if let Some(name) = binary.executable.file_name() {
-            context_server.name = name.to_string_lossy().into();
+            server: Arc::new(Mutex::new(server)),
         }
+    }
 
-        Ok(context_server)
+    /// Creates a client connected to a fake context server, which is another client that plays the
+    /// server's part: it answers requests with the handlers registered with [`Self::on_request`],
+    /// and sends notifications with [`Self::notify`].
+    #[cfg(any(test, feature = "test-support"))]
+    pub fn fake(server_id: ContextServerId, cx: AsyncAppContext) -> (Self, Self) {
+        let (stdin_writer, stdin_reader) = async_pipe::pipe();
+        let (stdout_writer, stdout_reader) = async_pipe::pipe();
+        let client = Self::new_internal(
+            server_id.clone(),
+            server_id.0.clone(),
+            stdin_writer,
+            stdout_reader,
+            None::<async_pipe::PipeReader>,
+            None,
+            cx.clone(),
+        );
+        let server = Self::new_internal(
+            server_id.clone(),
+            server_id.0,
+            stdout_writer,
+            stdin_reader,
+            None::<async_pipe::PipeReader>,
+            None,
+            cx,
+        );
+        (client, server)
+    }
+
+    /// Creates a new Client connected to a context server over HTTP with Server-Sent Events.
+    ///
+    /// The server sends its responses and notifications on the event stream at the endpoint's URL,
+    /// and announces the URL the client posts its own messages to as the first event on that stream.
+    /// Messages sent before that are queued until the URL is known.
+    pub fn new_sse(
+        server_id: ContextServerId,
+        endpoint: ModelContextServerEndpoint,
+        cx: AsyncAppContext,
+    ) -> Result<Self> {
+        log::info!("connecting to context server (url={})", endpoint.url);
+
+        let http_client = cx.update(|cx| cx.http_client())?;
+        let (outbound_tx, outbound_rx) = channel::unbounded::<String>();
+        let (output_done_tx, output_done_rx) = barrier::channel();
+        let (message_url_tx, message_url_rx) = oneshot::channel();
+
+        let notification_handlers =
+            Arc::new(Mutex::new(HashMap::<_, NotificationHandler>::default()));
+        let request_handlers = Arc::new(Mutex::new(HashMap::<_, RequestHandler>::default()));
+        let response_handlers =
+            Arc::new(Mutex::new(Some(HashMap::<_, ResponseHandler>::default())));
+
+        let input_task = cx.spawn({
+            let http_client = http_client.clone();
+            let endpoint = endpoint.clone();
+            let handlers = IncomingHandlers {
+                notification_handlers: notification_handlers.clone(),
+                request_handlers: request_handlers.clone(),
+                response_handlers: response_handlers.clone(),
+                outbound_tx: outbound_tx.clone(),
+            };
+            move |cx| {
+                sse::handle_input(http_client, endpoint, message_url_tx, handlers, cx).log_err()
+            }
+        });
+        let output_task = cx.background_executor().spawn({
+            sse::handle_output(
+                http_client,
+                endpoint.headers.clone(),
+                message_url_rx,
+                outbound_rx,
+                output_done_tx,
+                response_handlers.clone(),
+            )
+            .log_err()
+        });
+
+        Ok(Self {
+            server_id,
+            notification_handlers,
+            request_handlers,
+            response_handlers,
+            name: endpoint.url.to_string().into(),
+            next_id: Default::default(),
+            outbound_tx,
+            executor: cx.background_executor().clone(),
+            io_tasks: Mutex::new(Some((input_task, output_task))),
+            output_done_rx: Mutex::new(Some(output_done_rx)),
+            server: Arc::new(Mutex::new(None)),
+        })
     }
 
     /// Handles input from the server's stdout.
@@ -230,8 +395,7 @@ impl Client {
     /// to pending requests) and notifications (which trigger registered handlers).
     async fn handle_input<Stdout>(
         stdout: Stdout,
-        notification_handlers: Arc<Mutex<HashMap<&'static str, NotificationHandler>>>,
-        response_handlers: Arc<Mutex<Option<HashMap<RequestId, ResponseHandler>>>>,
+        handlers: IncomingHandlers,
         cx: AsyncAppContext,
     ) -> anyhow::Result<()>
     where
@@ -246,29 +410,83 @@ impl Client {
                 return Ok(());
             }
 
-            let content = buffer.trim();
+            Self::handle_message(buffer.trim(), &handlers, &cx);
+            smol::future::yield_now().await;
+        }
+    }
+
+    /// Dispatches a single JSON-RPC message received from the server, either to the handler
+    /// of the request it responds to, to the handler registered for its request method, or to
+    /// the handler registered for its notification method.
+    fn handle_message(content: &str, handlers: &IncomingHandlers, cx: &AsyncAppContext) {
+        if content.is_empty() {
+            return;
+        }
 
-            if !content.is_empty() {
-                if let Ok(response) = serde_json::from_str::<AnyResponse>(content) {
-                    if let Some(handlers) = response_handlers.lock().as_mut() {
-                        if let Some(handler) = handlers.remove(&response.id) {
-                            handler(Ok(content.to_string()));
-                        }
-                    }
-                } else if let Ok(notification) = serde_json::from_str::<AnyNotification>(content) {
-                    let mut notification_handlers = notification_handlers.lock();
-                    if let Some(handler) =
-                        notification_handlers.get_mut(notification.method.as_str())
-                    {
-                        handler(notification.params.unwrap_or(Value::Null), cx.clone());
-                    }
+        if let Ok(request) = serde_json::from_str::<AnyRequest>(content) {
+            Self::handle_request(request, handlers, cx);
+        } else if let Ok(response) = serde_json::from_str::<AnyResponse>(content) {
+            if let Some(handlers) = handlers.response_handlers.lock().as_mut() {
+                if let Some(handler) = handlers.remove(&response.id) {
+                    handler(Ok(content.to_string()));
                 }
             }
-
-            smol::future::yield_now().await;
+        } else if let Ok(notification) = serde_json::from_str::<AnyNotification>(content) {
+            let mut notification_handlers = handlers.notification_handlers.lock();
+            if let Some(handler) = notification_handlers.get_mut(notification.method.as_str()) {
+                handler(notification.params.unwrap_or(Value::Null), cx.clone());
+            }
         }
     }
 
+    /// Runs the handler registered for a request sent by the server, and sends its result back.
+    fn handle_request(request: AnyRequest, handlers: &IncomingHandlers, cx: &AsyncAppContext) {
+        let task = handlers
+            .request_handlers
+            .lock()
+            .get_mut(request.method.as_str())
+            .map(|handler| handler(request.params.unwrap_or(Value::Null), cx.clone()));
+        let outbound_tx = handlers.outbound_tx.clone();
+        let id = request.id;
+        let method = request.method;
+        cx.spawn(|_| {
+            async move {
+                let (result, error) = match task {
+                    Some(task) => match task.await {
+                        Ok(result) => (Some(result), None),
+                        Err(error) => {
+                            log::error!(
+                                "failed to handle context server request {method:?}: {error:#}"
+                            );
+                            let error = Error {
+                                code: INTERNAL_ERROR,
+                                message: error.to_string(),
+                            };
+                            (None, Some(error))
+                        }
+                    },
+                    None => {
+                        let error = Error {
+                            code: METHOD_NOT_FOUND,
+                            message: format!("method not found: {method}"),
+                        };
+                        (None, Some(error))
+                    }
+                };
+                let response = serde_json::to_string(&OutgoingResponse {
+                    jsonrpc: JSON_RPC_VERSION,
+                    id,
+                    result,
+                    error,
+                })?;
+                outbound_tx.try_send(response)?;
+                anyhow::Ok(())
+            }
+            .log_err()
+        })
+        .detach();
+    }
+
     /// Handles the stderr output from the context server.
     /// Continuously reads and logs any error messages from the server.
     async fn handle_stderr<Stderr>(stderr: Stderr) -> anyhow::Result<()>
@@ -352,7 +570,7 @@ impl Client {
         let send = self
             .outbound_tx
             .try_send(request)
-            .context("failed to write to context server's stdin");
+            .context("failed to send message to context server");
 
         let executor = self.executor.clone();
         let started = Instant::now();
@@ -375,7 +593,7 @@ impl Client {
                             Err(anyhow!("Invalid response: no result or error"))
                         }
                     }
-                    Err(_) => anyhow::bail!("cancelled")
+                    Err(error) => Err(anyhow!(error.message))
                 }
             }
             _ = timeout => {
@@ -407,6 +625,15 @@ impl Client {
             .insert(method, Box::new(f));
     }
 
+    /// Registers a handler for the requests the server sends with the given method. The value
+    /// the handler's task resolves to is sent back as the result.
+    pub fn on_request<F>(&self, method: &'static str, f: F)
+    where
+        F: 'static + Send + FnMut(Value, AsyncAppContext) -> Task<Result<Value>>,
+    {
+        self.request_handlers.lock().insert(method, Box::new(f));
+    }
+
     pub fn name(&self) -> &str {
         &self.name
     }
@@ -438,3 +665,135 @@ impl fmt::Debug for Client {
             .finish_non_exhaustive()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gpui::TestAppContext;
+    use serde_json::json;
+
+    struct TestHandlers {
+        handlers: IncomingHandlers,
+        outbound_rx: channel::Receiver<String>,
+        notifications: Arc<Mutex<Vec<Value>>>,
+        responses: Arc<Mutex<Vec<String>>>,
+    }
+
+    /// Handlers with a `test/request` request handler that echoes its params, a `test/notify`
+    /// notification handler and a pending request with id 1, all recording what they receive.
+    fn test_handlers() -> TestHandlers {
+        let (outbound_tx, outbound_rx) = channel::unbounded();
+        let notifications = Arc::new(Mutex::new(Vec::new()));
+        let responses = Arc::new(Mutex::new(Vec::new()));
+
+        let mut request_handlers = HashMap::<_, RequestHandler>::default();
+        request_handlers.insert(
+            "test/request",
+            Box::new(|params: Value, _: AsyncAppContext| {
+                Task::ready(Ok(json!({ "echo": params })))
+            }),
+        );
+        request_handlers.insert(
+            "test/fail",
+            Box::new(|_: Value, _: AsyncAppContext| {
+                Task::ready(Err(anyhow!("the handler failed")))
+            }),
+        );
+        let mut notification_handlers = HashMap::<_, NotificationHandler>::default();
+        notification_handlers.insert(
+            "test/notify",
+            Box::new({
+                let notifications = notifications.clone();
+                move |params: Value, _: AsyncAppContext| notifications.lock().push(params)
+            }),
+        );
+        let mut response_handlers = HashMap::<_, ResponseHandler>::default();
+        response_handlers.insert(
+            RequestId::Int(1),
+            Box::new({
+                let responses = responses.clone();
+                move |response: Result<String, Error>| responses.lock().push(response.unwrap())
+            }),
+        );
+
+        TestHandlers {
+            handlers: IncomingHandlers {
+                notification_handlers: Arc::new(Mutex::new(notification_handlers)),
+                request_handlers: Arc::new(Mutex::new(request_handlers)),
+                response_handlers: Arc::new(Mutex::new(Some(response_handlers))),
+                outbound_tx,
+            },
+            outbound_rx,
+            notifications,
+            responses,
+        }
+    }
+
+    fn sent_messages(outbound_rx: &channel::Receiver<String>) -> Vec<Value> {
+        std::iter::from_fn(|| outbound_rx.try_recv().ok())
+            .map(|message| serde_json::from_str(&message).unwrap())
+            .collect()
+    }
+
+    #[gpui::test]
+    async fn test_handle_request(cx: &mut TestAppContext) {
+        let test = test_handlers();
+        let async_cx = cx.to_async();
+
+        Client::handle_message(
+            r#"{"jsonrpc":"2.0","id":1,"method":"test/request","params":{"value":2}}"#,
+            &test.handlers,
+            &async_cx,
+        );
+        cx.run_until_parked();
+        assert_eq!(
+            sent_messages(&test.outbound_rx),
+            [json!({ "jsonrpc": "2.0", "id": 1, "result": { "echo": { "value": 2 } } })]
+        );
+        // The request shares its id with a pending request, but is not its response.
+        assert!(test.responses.lock().is_empty());
+        assert!(test.notifications.lock().is_empty());
+
+        Client::handle_message(
+            r#"{"jsonrpc":"2.0","id":"a","method":"test/unknown"}"#,
+            &test.handlers,
+            &async_cx,
+        );
+        Client::handle_message(
+            r#"{"jsonrpc":"2.0","id":"b","method":"test/fail","params":{}}"#,
+            &test.handlers,
+            &async_cx,
+        );
+        cx.run_until_parked();
+        let messages = sent_messages(&test.outbound_rx);
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0]["id"], "a");
+        assert_eq!(messages[0]["error"]["code"], METHOD_NOT_FOUND);
+        assert!(messages[0].get("result").is_none());
+        assert_eq!(messages[1]["id"], "b");
+        assert_eq!(messages[1]["error"]["code"], INTERNAL_ERROR);
+        assert_eq!(messages[1]["error"]["message"], "the handler failed");
+    }
+
+    #[gpui::test]
+    async fn test_handle_responses_and_notifications(cx: &mut TestAppContext) {
+        let test = test_handlers();
+        let async_cx = cx.to_async();
+
+        Client::handle_message(
+            r#"{"jsonrpc":"2.0","method":"test/notify","params":{"progress":1}}"#,
+            &test.handlers,
+            &async_cx,
+        );
+        let response = r#"{"jsonrpc":"2.0","id":1,"result":{}}"#;
+        Client::handle_message(response, &test.handlers, &async_cx);
+        cx.run_until_parked();
+
+        assert_eq!(*test.notifications.lock(), [json!({ "progress": 1 })]);
+        assert_eq!(*test.responses.lock(), [response]);
+        assert!(
+            sent_messages(&test.outbound_rx).is_empty(),
+            "responses and notifications are not answered"
+        );
+    }
+}
@@ -0,0 +1,356 @@
+//! The HTTP with Server-Sent Events transport of the Model Context Protocol.
+//!
+//! The client opens a long-lived event stream to the server, which first sends an `endpoint` event
+//! with the URL to post messages to, followed by a `message` event for every response and notification.
+use std::sync::Arc;
+
+use anyhow::{anyhow, Context as _, Result};
+use collections::HashMap;
+use futures::{channel::oneshot, io::BufReader, AsyncBufReadExt, AsyncReadExt, StreamExt};
+use gpui::AsyncAppContext;
+use http_client::{AsyncBody, HttpClient, Method, Request};
+use parking_lot::Mutex;
+use postage::barrier;
+use serde::{de::IgnoredAny, Deserialize};
+use smol::channel;
+use url::Url;
+
+use super::{
+    Client, Error, IncomingHandlers, ModelContextServerEndpoint, RequestId, ResponseHandler,
+    INTERNAL_ERROR,
+};
+
+const ENDPOINT_EVENT: &str = "endpoint";
+const MESSAGE_EVENT: &str = "message";
+
+/// Reads the server's event stream, dispatching the messages it contains.
+///
+/// The URL announced by the server's `endpoint` event is sent through `message_url_tx`.
+pub(super) async fn handle_input(
+    http_client: Arc<dyn HttpClient>,
+    endpoint: ModelContextServerEndpoint,
+    message_url_tx: oneshot::Sender<Url>,
+    handlers: IncomingHandlers,
+    cx: AsyncAppContext,
+) -> Result<()> {
+    let mut request = Request::builder()
+        .method(Method::GET)
+        .uri(endpoint.url.as_str())
+        .header("Accept", "text/event-stream");
+    for (name, value) in &endpoint.headers {
+        request = request.header(name.as_str(), value.as_str());
+    }
+    let mut response = http_client.send(request.body(AsyncBody::empty())?).await?;
+    if !response.status().is_success() {
+        let mut body = String::new();
+        response.body_mut().read_to_string(&mut body).await.ok();
+        return Err(anyhow!(
+            "failed to connect to context server at {}: {} {}",
+            endpoint.url,
+            response.status(),
+            body.trim()
+        ));
+    }
+
+    let mut message_url_tx = Some(message_url_tx);
+    let mut parser = EventParser::default();
+    let mut lines = BufReader::new(response.into_body()).lines();
+    while let Some(line) = lines.next().await {
+        let Some(event) = parser.push_line(&line?) else {
+            continue;
+        };
+        match event.event.as_str() {
+            ENDPOINT_EVENT => {
+                let message_url = endpoint
+                    .url
+                    .join(event.data.trim())
+                    .context("context server sent an invalid endpoint")?;
+                if let Some(message_url_tx) = message_url_tx.take() {
+                    message_url_tx.send(message_url).ok();
+                }
+            }
+            MESSAGE_EVENT => {
+                log::trace!("incoming message: {}", event.data);
+                Client::handle_message(event.data.trim(), &handlers, &cx);
+            }
+            other => log::debug!("ignoring context server event {other:?}"),
+        }
+        smol::future::yield_now().await;
+    }
+    Ok(())
+}
+
+/// Posts the outgoing messages to the URL announced by the server, once it is known.
+pub(super) async fn handle_output(
+    http_client: Arc<dyn HttpClient>,
+    headers: HashMap<String, String>,
+    message_url_rx: oneshot::Receiver<Url>,
+    outbound_rx: channel::Receiver<String>,
+    output_done_tx: barrier::Sender,
+    response_handlers: Arc<Mutex<Option<HashMap<RequestId, ResponseHandler>>>>,
+) -> Result<()> {
+    let _clear_response_handlers = util::defer({
+        let response_handlers = response_handlers.clone();
+        move || {
+            response_handlers.lock().take();
+        }
+    });
+    let message_url = message_url_rx
+        .await
+        .context("context server closed its event stream before sending an endpoint")?;
+
+    while let Ok(message) = outbound_rx.recv().await {
+        log::trace!("outgoing message: {}", message);
+        let request_id = serde_json::from_str::<OutgoingRequest>(&message)
+            .ok()
+            .map(|request| request.id);
+        if let Err(error) =
+            post_message(http_client.as_ref(), &message_url, &headers, message).await
+        {
+            let message = format!("{error:#}");
+            log::error!("{message}");
+
+            // The server won't respond to a request it didn't accept, so fail it right away.
+            let handler = request_id.and_then(|id| response_handlers.lock().as_mut()?.remove(&id));
+            if let Some(handler) = handler {
+                handler(Err(Error {
+                    code: INTERNAL_ERROR,
+                    message,
+                }));
+            }
+        }
+    }
+    drop(output_done_tx);
+    Ok(())
+}
+
+async fn post_message(
+    http_client: &dyn HttpClient,
+    message_url: &Url,
+    headers: &HashMap<String, String>,
+    message: String,
+) -> Result<()> {
+    let mut request = Request::builder()
+        .method(Method::POST)
+        .uri(message_url.as_str())
+        .header("Content-Type", "application/json");
+    for (name, value) in headers {
+        request = request.header(name.as_str(), value.as_str());
+    }
+    let mut response = http_client
+        .send(request.body(AsyncBody::from(message))?)
+        .await
+        .context("failed to send message to context server")?;
+    if !response.status().is_success() {
+        let mut body = String::new();
+        response.body_mut().read_to_string(&mut body).await.ok();
+        return Err(anyhow!(
+            "context server rejected message: {} {}",
+            response.status(),
+            body.trim()
+        ));
+    }
+    Ok(())
+}
+
+/// A request sent to the server. Unlike the responses to the server's requests, it has a method.
+#[derive(Deserialize)]
+struct OutgoingRequest {
+    id: RequestId,
+    #[serde(rename = "method")]
+    _method: IgnoredAny,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct Event {
+    event: String,
+    data: String,
+}
+
+/// Assembles Server-Sent Events from the lines of an event stream.
+#[derive(Default)]
+struct EventParser {
+    event: Option<String>,
+    data: Option<String>,
+}
+
+impl EventParser {
+    /// Processes a line of the stream without its line ending,
+    /// returning the event it completes, if any.
+    fn push_line(&mut self, line: &str) -> Option<Event> {
+        if line.is_empty() {
+            let event = self.event.take();
+            let data = self.data.take()?;
+            return Some(Event {
+                event: event.unwrap_or_else(|| MESSAGE_EVENT.to_string()),
+                data,
+            });
+        }
+
+        // Lines starting with a colon are comments, often sent to keep the connection alive.
+        if line.starts_with(':') {
+            return None;
+        }
+
+        let (field, value) = match line.split_once(':') {
+            Some((field, value)) => (field, value.strip_prefix(' ').unwrap_or(value)),
+            None => (line, ""),
+        };
+        match field {
+            "event" => self.event = Some(value.to_string()),
+            "data" => match &mut self.data {
+                Some(data) => {
+                    data.push('\n');
+                    data.push_str(value);
+                }
+                None => self.data = Some(value.to_string()),
+            },
+            _ => {}
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gpui::{Task, TestAppContext};
+    use http_client::{FakeHttpClient, Response};
+
+    type ResponseReceiver = oneshot::Receiver<Result<String, Error>>;
+
+    /// Runs the output loop, returning the sender of the outgoing messages along with the receivers
+    /// of the responses to the requests with the given ids.
+    fn run_output(
+        http_client: Arc<dyn HttpClient>,
+        request_ids: &[i32],
+        cx: &TestAppContext,
+    ) -> (
+        channel::Sender<String>,
+        Vec<ResponseReceiver>,
+        Task<Result<()>>,
+    ) {
+        let (message_url_tx, message_url_rx) = oneshot::channel();
+        let (outbound_tx, outbound_rx) = channel::unbounded();
+        let (output_done_tx, _output_done_rx) = barrier::channel();
+        let mut response_handlers = HashMap::<_, ResponseHandler>::default();
+        let mut response_rxs = Vec::new();
+        for id in request_ids {
+            let (response_tx, response_rx) = oneshot::channel();
+            response_handlers.insert(
+                RequestId::Int(*id),
+                Box::new(move |response| {
+                    response_tx.send(response).ok();
+                }),
+            );
+            response_rxs.push(response_rx);
+        }
+        let output = cx.executor().spawn(handle_output(
+            http_client,
+            HashMap::default(),
+            message_url_rx,
+            outbound_rx,
+            output_done_tx,
+            Arc::new(Mutex::new(Some(response_handlers))),
+        ));
+        message_url_tx
+            .send(Url::parse("http://example.com/messages").unwrap())
+            .unwrap();
+        (outbound_tx, response_rxs, output)
+    }
+
+    #[gpui::test]
+    async fn test_rejected_request(cx: &mut TestAppContext) {
+        // Reject every message, echoing it back.
+        let http_client = FakeHttpClient::create(|request| async move {
+            let mut message = String::new();
+            request.into_body().read_to_string(&mut message).await?;
+            Ok(Response::builder()
+                .status(503)
+                .body(AsyncBody::from(message))?)
+        });
+        let (outbound_tx, mut response_rxs, _output) = run_output(http_client, &[1], cx);
+
+        // A response to the server's request with the same id doesn't fail the pending request.
+        let response = r#"{"jsonrpc":"2.0","id":1,"result":{}}"#;
+        let request = r#"{"jsonrpc":"2.0","id":1,"method":"tools/list"}"#;
+        outbound_tx.send(response.to_string()).await.unwrap();
+        outbound_tx.send(request.to_string()).await.unwrap();
+        let error = response_rxs.remove(0).await.unwrap().unwrap_err();
+        assert_eq!(error.code, INTERNAL_ERROR);
+        assert_eq!(
+            error.message,
+            format!("context server rejected message: 503 Service Unavailable {request}")
+        );
+    }
+
+    #[gpui::test]
+    async fn test_request_failing_to_send(cx: &mut TestAppContext) {
+        // Fail to send the first request, and reject the ones after it.
+        let http_client = FakeHttpClient::create(|request| async move {
+            let mut message = String::new();
+            request.into_body().read_to_string(&mut message).await?;
+            if message.contains(r#""id":1"#) {
+                return Err(anyhow!("connection reset"));
+            }
+            Ok(Response::builder().status(503).body(AsyncBody::empty())?)
+        });
+        let (outbound_tx, response_rxs, output) = run_output(http_client, &[1, 2], cx);
+
+        for id in [1, 2] {
+            let request = format!(r#"{{"jsonrpc":"2.0","id":{id},"method":"tools/list"}}"#);
+            outbound_tx.send(request).await.unwrap();
+        }
+        let [first_error, second_error] = futures::future::join_all(response_rxs)
+            .await
+            .try_into()
+            .unwrap();
+        assert_eq!(
+            first_error.unwrap().unwrap_err().message,
+            "failed to send message to context server: connection reset"
+        );
+        assert_eq!(
+            second_error.unwrap().unwrap_err().message,
+            "context server rejected message: 503 Service Unavailable "
+        );
+
+        // The output keeps running until there is nothing left to send.
+        drop(outbound_tx);
+        output.await.unwrap();
+    }
+
+    #[test]
+    fn test_parse_events() {
+        let stream = [
+            ": keep-alive",
+            "",
+            "event: endpoint",
+            "data: /messages?session_id=1",
+            "",
+            "data: {\"jsonrpc\":\"2.0\",",
+            "data:\"id\":0}",
+            "id: 1",
+            "",
+            "event: ignored",
+            "",
+        ];
+        let mut parser = EventParser::default();
+        let events = stream
+            .into_iter()
+            .filter_map(|line| parser.push_line(line))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            events,
+            vec![
+                Event {
+                    event: "endpoint".into(),
+                    data: "/messages?session_id=1".into(),
+                },
+                Event {
+                    event: "message".into(),
+                    data: "{\"jsonrpc\":\"2.0\",\n\"id\":0}".into(),
+                },
+            ]
+        );
+    }
+}
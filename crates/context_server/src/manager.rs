@@ -17,7 +17,7 @@
 use std::path::Path;
 use std::sync::Arc;
 
-use anyhow::{bail, Result};
+use anyhow::{bail, Context as _, Result};
 use collections::HashMap;
 use command_palette_hooks::CommandPaletteFilter;
 use gpui::{AsyncAppContext, EventEmitter, Model, ModelContext, Subscription, Task, WeakModel};
@@ -25,6 +25,7 @@ use log;
 use parking_lot::RwLock;
 use project::Project;
 use settings::{Settings, SettingsStore};
+use url::Url;
 use util::ResultExt as _;
 
 use crate::{ContextServerSettings, ServerConfig};
@@ -63,19 +64,36 @@ impl ContextServer {
 
     pub async fn start(self: Arc<Self>, cx: &AsyncAppContext) -> Result<()> {
         log::info!("starting context server {}", self.id);
-        let Some(command) = &self.config.command else {
-            bail!("no command specified for server {}", self.id);
+        let server_id = client::ContextServerId(self.id.clone());
+        let client = if let Some(url) = &self.config.url {
+            let url = Url::parse(url)
+                .with_context(|| format!("invalid url for context server {}", self.id))?;
+            Client::new_sse(
+                server_id,
+                client::ModelContextServerEndpoint {
+                    url,
+                    headers: self.config.headers.clone().unwrap_or_default(),
+                },
+                cx.clone(),
+            )?
+        } else if let Some(command) = &self.config.command {
+            Client::new(
+                server_id,
+                client::ModelContextServerBinary {
+                    executable: Path::new(&command.path).to_path_buf(),
+                    args: command.args.clone(),
+                    env: command.env.clone(),
+                },
+                cx.clone(),
+            )?
+        } else {
+            bail!("no command or url specified for server {}", self.id);
         };
-        let client = Client::new(
-            client::ContextServerId(self.id.clone()),
-            client::ModelContextServerBinary {
-                executable: Path::new(&command.path).to_path_buf(),
-                args: command.args.clone(),
-                env: command.env.clone(),
-            },
-            cx.clone(),
-        )?;
+        self.initialize(client).await
+    }
 
+    async fn initialize(&self, client: Client) -> Result<()> {
+        crate::sampling::register(&client, self.id.clone());
         let protocol = crate::protocol::ModelContextProtocol::new(client);
         let client_info = types::Implementation {
             name: "Zed".to_string(),
@@ -112,8 +130,19 @@ pub struct ContextServerManager {
 }
 
 pub enum Event {
-    ServerStarted { server_id: Arc<str> },
-    ServerStopped { server_id: Arc<str> },
+    ServerStarted {
+        server_id: Arc<str>,
+    },
+    ServerStopped {
+        server_id: Arc<str>,
+    },
+    /// A resource that was subscribed to with [`InitializedContextServerProtocol::subscribe_to_resource`] changed.
+    ///
+    /// [`InitializedContextServerProtocol::subscribe_to_resource`]: crate::protocol::InitializedContextServerProtocol::subscribe_to_resource
+    ResourceUpdated {
+        server_id: Arc<str>,
+        uri: Url,
+    },
 }
 
 impl EventEmitter<Event> for ContextServerManager {}
@@ -192,6 +221,7 @@ impl ContextServerManager {
                 let config = server.config();
                 let new_server = Arc::new(ContextServer::new(id.clone(), config));
                 new_server.clone().start(&cx).await?;
+                Self::watch_resource_updates(this.clone(), &new_server);
                 this.update(&mut cx, |this, cx| {
                     this.servers.insert(id.clone(), new_server);
                     cx.emit(Event::ServerStopped {
@@ -206,6 +236,25 @@ impl ContextServerManager {
         })
     }
 
+    /// Adds a running server that talks to the given client, made with [`Client::fake`].
+    #[cfg(any(test, feature = "test-support"))]
+    pub fn add_fake_server(
+        &mut self,
+        id: Arc<str>,
+        client: Client,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<()>> {
+        let server = Arc::new(ContextServer::new(id.clone(), Arc::default()));
+        cx.spawn(|this, mut cx| async move {
+            server.initialize(client).await?;
+            Self::watch_resource_updates(this.clone(), &server);
+            this.update(&mut cx, |this, cx| {
+                this.servers.insert(id.clone(), server);
+                cx.emit(Event::ServerStarted { server_id: id });
+            })
+        })
+    }
+
     pub fn servers(&self) -> Vec<Arc<ContextServer>> {
         self.servers
             .values()
@@ -234,7 +283,7 @@ impl ContextServerManager {
             registry.read_with(&cx, |registry, _| registry.context_server_factories())?
         {
             let config = desired_servers.entry(id).or_default();
-            if config.command.is_none() {
+            if config.command.is_none() && config.url.is_none() {
                 if let Some(extension_command) = factory(project.clone(), &cx).await.log_err() {
                     config.command = Some(extension_command);
                 }
@@ -276,7 +325,8 @@ impl ContextServerManager {
         }
 
         for (id, server) in servers_to_start {
-            if server.start(&cx).await.log_err().is_some() {
+            if server.clone().start(&cx).await.log_err().is_some() {
+                Self::watch_resource_updates(this.clone(), &server);
                 this.update(&mut cx, |_, cx| {
                     cx.emit(Event::ServerStarted { server_id: id })
                 })?;
@@ -285,4 +335,36 @@ impl ContextServerManager {
 
         Ok(())
     }
+
+    /// Emits [`Event::ResourceUpdated`] when the server notifies that a resource changed.
+    fn watch_resource_updates(this: WeakModel<Self>, server: &ContextServer) {
+        let Some(client) = server.client() else {
+            return;
+        };
+        let server_id = server.id();
+        client.on_notification(
+            types::NotificationType::ResourcesUpdated,
+            move |params, cx| {
+                let Some(params) =
+                    serde_json::from_value::<types::ResourcesUpdatedParams>(params).log_err()
+                else {
+                    return;
+                };
+                let this = this.clone();
+                let server_id = server_id.clone();
+                // Notification handlers are called while the client's handlers are locked, so
+                // the event is emitted separately in case a subscriber talks to the server.
+                cx.spawn(|mut cx| async move {
+                    this.update(&mut cx, |_, cx| {
+                        cx.emit(Event::ResourceUpdated {
+                            server_id,
+                            uri: params.uri,
+                        })
+                    })
+                    .ok();
+                })
+                .detach();
+            },
+        );
+    }
 }
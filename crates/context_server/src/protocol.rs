@@ -7,6 +7,8 @@
 
 use anyhow::Result;
 use collections::HashMap;
+use gpui::AsyncAppContext;
+use url::Url;
 
 use crate::client::Client;
 use crate::types;
@@ -34,7 +36,8 @@ impl ModelContextProtocol {
             protocol_version: types::ProtocolVersion(types::LATEST_PROTOCOL_VERSION.to_string()),
             capabilities: types::ClientCapabilities {
                 experimental: None,
-                sampling: None,
+                // Only text messages are supported, see the `sampling` module.
+                sampling: Some(serde_json::json!({})),
                 roots: None,
             },
             meta: None,
@@ -136,6 +139,68 @@ impl InitializedContextServerProtocol {
         Ok(response)
     }
 
+    /// List the MCP resource templates.
+    pub async fn list_resource_templates(&self) -> Result<types::ListResourceTemplatesResponse> {
+        self.check_capability(ServerCapability::Resources)?;
+
+        let response: types::ListResourceTemplatesResponse = self
+            .inner
+            .request(
+                types::RequestType::ListResourceTemplates.as_str(),
+                serde_json::json!({}),
+            )
+            .await?;
+
+        Ok(response)
+    }
+
+    /// Reads the contents of the MCP resource with the given URI.
+    pub async fn read_resource(&self, uri: Url) -> Result<types::ResourcesReadResponse> {
+        self.check_capability(ServerCapability::Resources)?;
+
+        let params = types::ResourcesReadParams { uri, meta: None };
+        let response: types::ResourcesReadResponse = self
+            .inner
+            .request(types::RequestType::ResourcesRead.as_str(), params)
+            .await?;
+
+        Ok(response)
+    }
+
+    /// Whether the server notifies about updates to the resources that are subscribed to.
+    pub fn can_subscribe_to_resources(&self) -> bool {
+        self.initialize
+            .capabilities
+            .resources
+            .as_ref()
+            .and_then(|resources| resources.subscribe)
+            .unwrap_or(false)
+    }
+
+    /// Asks the server to send a `notifications/resources/updated` notification when the
+    /// resource with the given URI changes.
+    pub async fn subscribe_to_resource(&self, uri: Url) -> Result<()> {
+        self.check_capability(ServerCapability::Resources)?;
+
+        let params = types::ResourcesSubscribeParams { uri, meta: None };
+        self.inner
+            .request::<serde_json::Value>(types::RequestType::ResourcesSubscribe.as_str(), params)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn unsubscribe_from_resource(&self, uri: Url) -> Result<()> {
+        self.check_capability(ServerCapability::Resources)?;
+
+        let params = types::ResourcesUnsubscribeParams { uri, meta: None };
+        self.inner
+            .request::<serde_json::Value>(types::RequestType::ResourcesUnsubscribe.as_str(), params)
+            .await?;
+
+        Ok(())
+    }
+
     /// Executes a prompt with the given arguments and returns the result.
     pub async fn run_prompt<P: AsRef<str>>(
         &self,
@@ -224,6 +289,13 @@ impl InitializedContextServerProtocol {
 }
 
 impl InitializedContextServerProtocol {
+    pub fn on_notification<F>(&self, notification: types::NotificationType, f: F)
+    where
+        F: 'static + Send + FnMut(serde_json::Value, AsyncAppContext),
+    {
+        self.inner.on_notification(notification.as_str(), f);
+    }
+
     pub async fn request<R: serde::de::DeserializeOwned>(
         &self,
         method: &str,
@@ -0,0 +1,216 @@
+//! Handles the `sampling/createMessage` requests with which context servers ask the client for
+//! a completion. Each request is shown to the user for approval and then sent to their active
+//! language model.
+//!
+//! Only text messages can be sampled: requests containing image or resource content are
+//! rejected with an error, since most language model providers are only sent text.
+
+use std::sync::Arc;
+
+use anyhow::{anyhow, bail, Context as _, Result};
+use futures::StreamExt as _;
+use gpui::{AsyncAppContext, PromptLevel};
+use language_model::{
+    LanguageModelCompletionEvent, LanguageModelRegistry, LanguageModelRequest,
+    LanguageModelRequestMessage, MessageContent, Role, StopReason,
+};
+use util::truncate_and_trailoff;
+
+use crate::client::Client;
+use crate::types;
+
+/// How much of the request is shown in the approval prompt.
+const MAX_PROMPT_DETAIL_CHARS: usize = 2000;
+
+pub(crate) fn register(client: &Client, server_id: Arc<str>) {
+    client.on_request(
+        types::RequestType::CreateMessage.as_str(),
+        move |params, cx| {
+            let server_id = server_id.clone();
+            cx.spawn(|cx| async move {
+                let params: types::CreateMessageRequest = serde_json::from_value(params)?;
+                let result = create_message(server_id, params, cx).await?;
+                Ok(serde_json::to_value(result)?)
+            })
+        },
+    );
+}
+
+async fn create_message(
+    server_id: Arc<str>,
+    params: types::CreateMessageRequest,
+    mut cx: AsyncAppContext,
+) -> Result<types::CreateMessageResult> {
+    let model = cx
+        .update(|cx| LanguageModelRegistry::read_global(cx).active_model())?
+        .context("no language model is configured")?;
+    let request = completion_request(&params)?;
+
+    let window = cx
+        .update(|cx| cx.active_window())?
+        .context("no window to ask for approval in")?;
+    let message = format!(
+        "The context server {server_id} wants to send a request to {}",
+        model.name().0
+    );
+    let detail = truncate_and_trailoff(&prompt_detail(&params), MAX_PROMPT_DETAIL_CHARS);
+    let answer = window.update(&mut cx, |_, cx| {
+        cx.prompt(
+            PromptLevel::Info,
+            &message,
+            Some(&detail),
+            &["Allow", "Deny"],
+        )
+    })?;
+    if answer.await? != 0 {
+        bail!("the user rejected the sampling request");
+    }
+
+    let mut events = model.stream_completion(request, &cx).await?;
+    let mut text = String::new();
+    let mut stop_reason = None;
+    while let Some(event) = events.next().await {
+        match event? {
+            LanguageModelCompletionEvent::Text(chunk) => text.push_str(&chunk),
+            LanguageModelCompletionEvent::Stop(reason) => stop_reason = Some(reason),
+            LanguageModelCompletionEvent::StartMessage { .. }
+            | LanguageModelCompletionEvent::ToolUse(_) => {}
+        }
+    }
+
+    Ok(types::CreateMessageResult {
+        role: types::Role::Assistant,
+        content: types::MessageContent::Text {
+            text,
+            annotations: None,
+        },
+        model: model.id().0.to_string(),
+        stop_reason: stop_reason.map(|reason| stop_reason_name(reason).to_string()),
+    })
+}
+
+/// The name of the stop reason in the protocol. Providers that don't report why they
+/// stopped leave it unset.
+fn stop_reason_name(reason: StopReason) -> &'static str {
+    match reason {
+        StopReason::EndTurn => "endTurn",
+        StopReason::MaxTokens => "maxTokens",
+        StopReason::StopSequence => "stopSequence",
+        StopReason::ToolUse => "toolUse",
+    }
+}
+
+fn completion_request(params: &types::CreateMessageRequest) -> Result<LanguageModelRequest> {
+    let mut messages = Vec::new();
+    if let Some(system_prompt) = &params.system_prompt {
+        messages.push(LanguageModelRequestMessage {
+            role: Role::System,
+            content: vec![MessageContent::Text(system_prompt.clone())],
+            cache: false,
+        });
+    }
+    for message in &params.messages {
+        let role = match message.role {
+            types::Role::User => Role::User,
+            types::Role::Assistant => Role::Assistant,
+        };
+        messages.push(LanguageModelRequestMessage {
+            role,
+            content: vec![MessageContent::Text(message_text(&message.content)?)],
+            cache: false,
+        });
+    }
+
+    Ok(LanguageModelRequest {
+        messages,
+        tools: Vec::new(),
+        stop: params.stop_sequences.clone().unwrap_or_default(),
+        temperature: params.temperature.map(|temperature| temperature as f32),
+        max_tokens: Some(params.max_tokens),
+    })
+}
+
+fn message_text(content: &types::MessageContent) -> Result<String> {
+    match content {
+        types::MessageContent::Text { text, .. } => Ok(text.clone()),
+        types::MessageContent::Image { .. } => {
+            Err(anyhow!("images are not supported in sampling requests"))
+        }
+        types::MessageContent::Resource { .. } => {
+            Err(anyhow!("resources are not supported in sampling requests"))
+        }
+    }
+}
+
+fn prompt_detail(params: &types::CreateMessageRequest) -> String {
+    let mut detail = String::new();
+    if let Some(system_prompt) = &params.system_prompt {
+        detail.push_str(&format!("System: {system_prompt}\n\n"));
+    }
+    for message in &params.messages {
+        let role = match message.role {
+            types::Role::User => "User",
+            types::Role::Assistant => "Assistant",
+        };
+        let text = match &message.content {
+            types::MessageContent::Text { text, .. } => text.as_str(),
+            types::MessageContent::Image { .. } => "[image]",
+            types::MessageContent::Resource { .. } => "[resource]",
+        };
+        detail.push_str(&format!("{role}: {text}\n\n"));
+    }
+    detail.truncate(detail.trim_end().len());
+    detail
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_completion_request() {
+        let params: types::CreateMessageRequest = serde_json::from_value(json!({
+            "messages": [
+                { "role": "user", "content": { "type": "text", "text": "Summarize the diff" } },
+                { "role": "assistant", "content": { "type": "text", "text": "Which diff?" } },
+                { "role": "user", "content": { "type": "text", "text": "The staged one" } },
+            ],
+            "systemPrompt": "You are terse.",
+            "temperature": 0.5,
+            "maxTokens": 100,
+            "stopSequences": ["\n\n"],
+        }))
+        .unwrap();
+
+        let message = |role, text: &str| LanguageModelRequestMessage {
+            role,
+            content: vec![MessageContent::Text(text.to_string())],
+            cache: false,
+        };
+        assert_eq!(
+            completion_request(&params).unwrap(),
+            LanguageModelRequest {
+                messages: vec![
+                    message(Role::System, "You are terse."),
+                    message(Role::User, "Summarize the diff"),
+                    message(Role::Assistant, "Which diff?"),
+                    message(Role::User, "The staged one"),
+                ],
+                tools: Vec::new(),
+                stop: vec!["\n\n".to_string()],
+                temperature: Some(0.5),
+                max_tokens: Some(100),
+            }
+        );
+
+        let params: types::CreateMessageRequest = serde_json::from_value(json!({
+            "messages": [
+                { "role": "user", "content": { "type": "image", "data": "", "mime_type": "image/png" } },
+            ],
+            "maxTokens": 100,
+        }))
+        .unwrap();
+        assert!(completion_request(&params).is_err());
+    }
+}
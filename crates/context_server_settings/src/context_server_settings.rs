@@ -18,6 +18,16 @@ pub struct ServerConfig {
     ///
     /// This will override the command set by an extension.
     pub command: Option<ServerCommand>,
+    /// The URL of a context server to connect to over HTTP with Server-Sent Events,
+    /// instead of running a command.
+    ///
+    /// This is the URL of the server's event stream, for example `http://localhost:8080/sse`.
+    #[serde(default)]
+    pub url: Option<String>,
+    /// The HTTP headers to send with every request to the context server at `url`,
+    /// for example to authenticate with it.
+    #[serde(default)]
+    pub headers: Option<HashMap<String, String>>,
     /// The settings for this context server.
     ///
     /// Consult the documentation for the context server to see what settings
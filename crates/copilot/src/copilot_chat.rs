@@ -104,6 +104,8 @@ pub struct Request {
     pub temperature: f32,
     pub model: Model,
     pub messages: Vec<ChatMessage>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u32>,
 }
 
 impl Request {
@@ -115,6 +117,7 @@ impl Request {
             temperature: 0.1,
             model,
             messages,
+            max_tokens: None,
         }
     }
 }
@@ -0,0 +1,356 @@
+//! A client for the [Debug Adapter Protocol](https://microsoft.github.io/debug-adapter-protocol/),
+//! which Zed uses to talk to debuggers.
+
+pub mod events;
+pub mod requests;
+mod session;
+mod transport;
+pub mod types;
+
+use std::{
+    path::PathBuf,
+    process::Stdio,
+    sync::{
+        atomic::{AtomicU64, Ordering::SeqCst},
+        Arc,
+    },
+};
+
+use anyhow::{anyhow, Context as _, Result};
+use collections::HashMap;
+use futures::{
+    channel::oneshot, io::BufReader, AsyncBufReadExt as _, AsyncRead, AsyncWrite, Future,
+};
+use gpui::{AsyncAppContext, Task};
+use parking_lot::Mutex;
+use serde_json::Value;
+use smol::{channel, process::Child};
+use util::ResultExt as _;
+
+pub use session::{DebugSession, DebugSessionEvent, SessionStatus};
+
+use crate::{
+    events::Event,
+    requests::Request,
+    types::{Message, RequestMessage, ResponseMessage},
+};
+
+type ResponseHandler = Box<dyn Send + FnOnce(Result<Value>)>;
+type EventHandler = Box<dyn Send + FnMut(Value, AsyncAppContext)>;
+type RequestHandler = Box<dyn Send + FnMut(Value, AsyncAppContext) -> Result<Value>>;
+
+/// The command that starts a debug adapter, which Zed talks to over its stdin and stdout.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DebugAdapterBinary {
+    pub command: String,
+    pub args: Vec<String>,
+    pub cwd: Option<PathBuf>,
+    pub env: HashMap<String, String>,
+}
+
+/// A connection to a running debug adapter.
+pub struct DebugAdapterClient {
+    next_seq: Arc<AtomicU64>,
+    outbound_tx: channel::Sender<String>,
+    response_handlers: Arc<Mutex<Option<HashMap<u64, ResponseHandler>>>>,
+    event_handlers: Arc<Mutex<HashMap<&'static str, EventHandler>>>,
+    request_handlers: Arc<Mutex<HashMap<&'static str, RequestHandler>>>,
+    _io_tasks: (Task<Option<()>>, Task<Option<()>>),
+    _process: Option<Child>,
+}
+
+impl DebugAdapterClient {
+    /// Starts a debug adapter process.
+    pub fn start(binary: &DebugAdapterBinary, cx: AsyncAppContext) -> Result<Self> {
+        log::info!(
+            "starting debug adapter {:?} with args {:?}",
+            binary.command,
+            binary.args
+        );
+        let mut command = util::command::new_smol_command(&binary.command);
+        command
+            .args(&binary.args)
+            .envs(binary.env.clone())
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+        if let Some(cwd) = &binary.cwd {
+            command.current_dir(cwd);
+        }
+        let mut process = command
+            .spawn()
+            .with_context(|| format!("failed to start debug adapter {:?}", binary.command))?;
+
+        let stdin = process.stdin.take().unwrap();
+        let stdout = process.stdout.take().unwrap();
+        let stderr = process.stderr.take().unwrap();
+        cx.background_executor()
+            .spawn(async move {
+                let mut lines = BufReader::new(stderr).lines();
+                while let Some(line) = futures::StreamExt::next(&mut lines).await {
+                    log::debug!("debug adapter stderr: {}", line?);
+                }
+                anyhow::Ok(())
+            })
+            .detach();
+
+        Ok(Self::new_internal(stdin, stdout, Some(process), cx))
+    }
+
+    fn new_internal<Stdin, Stdout>(
+        stdin: Stdin,
+        stdout: Stdout,
+        process: Option<Child>,
+        cx: AsyncAppContext,
+    ) -> Self
+    where
+        Stdin: AsyncWrite + Unpin + Send + 'static,
+        Stdout: AsyncRead + Unpin + Send + 'static,
+    {
+        let next_seq = Arc::new(AtomicU64::new(1));
+        let (outbound_tx, outbound_rx) = channel::unbounded::<String>();
+        let response_handlers = Arc::new(Mutex::new(Some(HashMap::default())));
+        let event_handlers = Arc::new(Mutex::new(HashMap::default()));
+        let request_handlers = Arc::new(Mutex::new(HashMap::default()));
+
+        let input_task = cx.spawn({
+            let next_seq = next_seq.clone();
+            let outbound_tx = outbound_tx.clone();
+            let response_handlers = response_handlers.clone();
+            let event_handlers = event_handlers.clone();
+            let request_handlers = request_handlers.clone();
+            move |cx| {
+                Self::handle_input(
+                    stdout,
+                    next_seq,
+                    outbound_tx,
+                    response_handlers,
+                    event_handlers,
+                    request_handlers,
+                    cx,
+                )
+                .log_err()
+            }
+        });
+        let output_task = cx.background_executor().spawn({
+            let response_handlers = response_handlers.clone();
+            async move {
+                let _clear_response_handlers = util::defer(move || {
+                    response_handlers.lock().take();
+                });
+                let mut stdin = stdin;
+                while let Ok(message) = outbound_rx.recv().await {
+                    transport::write_message(&mut stdin, &message).await?;
+                }
+                anyhow::Ok(())
+            }
+            .log_err()
+        });
+
+        Self {
+            next_seq,
+            outbound_tx,
+            response_handlers,
+            event_handlers,
+            request_handlers,
+            _io_tasks: (input_task, output_task),
+            _process: process,
+        }
+    }
+
+    async fn handle_input<Stdout>(
+        stdout: Stdout,
+        next_seq: Arc<AtomicU64>,
+        outbound_tx: channel::Sender<String>,
+        response_handlers: Arc<Mutex<Option<HashMap<u64, ResponseHandler>>>>,
+        event_handlers: Arc<Mutex<HashMap<&'static str, EventHandler>>>,
+        request_handlers: Arc<Mutex<HashMap<&'static str, RequestHandler>>>,
+        cx: AsyncAppContext,
+    ) -> Result<()>
+    where
+        Stdout: AsyncRead + Unpin + Send + 'static,
+    {
+        let _clear_response_handlers = util::defer({
+            let response_handlers = response_handlers.clone();
+            move || {
+                response_handlers.lock().take();
+            }
+        });
+        let mut stdout = BufReader::new(stdout);
+        let mut buffer = Vec::new();
+        while let Some(message) = transport::read_message(&mut stdout, &mut buffer).await? {
+            match message {
+                Message::Response(response) => {
+                    let handler = response_handlers
+                        .lock()
+                        .as_mut()
+                        .and_then(|handlers| handlers.remove(&response.request_seq));
+                    if let Some(handler) = handler {
+                        if response.success {
+                            handler(Ok(response.body));
+                        } else {
+                            let message = response.message.unwrap_or(response.command);
+                            handler(Err(anyhow!("{message}")));
+                        }
+                    }
+                }
+                Message::Event(event) => {
+                    let mut event_handlers = event_handlers.lock();
+                    if let Some(handler) = event_handlers.get_mut(event.event.as_str()) {
+                        handler(event.body, cx.clone());
+                    } else {
+                        log::debug!("unhandled debug adapter event {:?}", event.event);
+                    }
+                }
+                Message::Request(request) => {
+                    let result = match request_handlers.lock().get_mut(request.command.as_str()) {
+                        Some(handler) => handler(request.arguments, cx.clone()),
+                        None => Err(anyhow!("unsupported request {:?}", request.command)),
+                    };
+                    let (success, message, body) = match result {
+                        Ok(body) => (true, None, body),
+                        Err(error) => (false, Some(error.to_string()), Value::Null),
+                    };
+                    let response = Message::Response(ResponseMessage {
+                        seq: next_seq.fetch_add(1, SeqCst),
+                        request_seq: request.seq,
+                        success,
+                        command: request.command,
+                        message,
+                        body,
+                    });
+                    outbound_tx.try_send(serde_json::to_string(&response)?)?;
+                }
+            }
+
+            // Don't starve the main thread when receiving lots of messages at once.
+            smol::future::yield_now().await;
+        }
+        Ok(())
+    }
+
+    /// Sends a request to the debug adapter.
+    pub fn request<R: Request>(
+        &self,
+        arguments: R::Arguments,
+    ) -> impl 'static + Send + Future<Output = Result<R::Response>> {
+        let seq = self.next_seq.fetch_add(1, SeqCst);
+        let (tx, rx) = oneshot::channel();
+        let sent = serde_json::to_value(arguments)
+            .and_then(|arguments| {
+                serde_json::to_string(&Message::Request(RequestMessage {
+                    seq,
+                    command: R::COMMAND.into(),
+                    arguments,
+                }))
+            })
+            .map_err(anyhow::Error::from)
+            .and_then(|message| {
+                self.response_handlers
+                    .lock()
+                    .as_mut()
+                    .ok_or_else(|| anyhow!("debug adapter has shut down"))?
+                    .insert(
+                        seq,
+                        Box::new(move |result| {
+                            tx.send(result).ok();
+                        }),
+                    );
+                self.outbound_tx
+                    .try_send(message)
+                    .context("failed to write to the debug adapter's stdin")
+            });
+
+        async move {
+            sent?;
+            let body = rx
+                .await
+                .map_err(|_| anyhow!("debug adapter has shut down"))??;
+            // Adapters differ in whether responses without information have an empty body or none.
+            serde_json::from_value(body.clone())
+                .or_else(|error| match body {
+                    Value::Null => serde_json::from_value(Value::Object(Default::default())),
+                    Value::Object(object) if object.is_empty() => {
+                        serde_json::from_value(Value::Null)
+                    }
+                    _ => Err(error),
+                })
+                .with_context(|| format!("invalid response to the {} request", R::COMMAND))
+        }
+    }
+
+    /// Handles the events of a kind. A later handler replaces the previous one.
+    pub fn on_event<E, F>(&self, mut f: F)
+    where
+        E: Event,
+        F: 'static + Send + FnMut(E::Body, AsyncAppContext),
+    {
+        self.event_handlers.lock().insert(
+            E::EVENT,
+            Box::new(move |body, cx| match serde_json::from_value(body) {
+                Ok(body) => f(body, cx),
+                Err(error) => log::error!("invalid {} event from debug adapter: {error}", E::EVENT),
+            }),
+        );
+    }
+
+    /// Handles the requests of a kind that the debug adapter sends, like `runInTerminal`.
+    /// Requests without a handler get an error response.
+    pub fn on_request<R, F>(&self, mut f: F)
+    where
+        R: Request,
+        F: 'static + Send + FnMut(R::Arguments, AsyncAppContext) -> Result<R::Response>,
+    {
+        self.request_handlers.lock().insert(
+            R::COMMAND,
+            Box::new(move |arguments, cx| {
+                let response = f(serde_json::from_value(arguments)?, cx)?;
+                Ok(serde_json::to_value(response)?)
+            }),
+        );
+    }
+
+    #[cfg(any(test, feature = "test-support"))]
+    fn send_event<E: Event>(&self, body: E::Body) -> Result<()> {
+        let message = serde_json::to_string(&Message::Event(types::EventMessage {
+            seq: self.next_seq.fetch_add(1, SeqCst),
+            event: E::EVENT.into(),
+            body: serde_json::to_value(body)?,
+        }))?;
+        Ok(self.outbound_tx.try_send(message)?)
+    }
+}
+
+/// A fake debug adapter for use in tests, which talks to a [`DebugAdapterClient`] over pipes
+/// that stand in for its stdin and stdout.
+#[cfg(any(test, feature = "test-support"))]
+pub struct FakeDebugAdapter {
+    adapter: DebugAdapterClient,
+}
+
+#[cfg(any(test, feature = "test-support"))]
+impl FakeDebugAdapter {
+    pub fn new(cx: AsyncAppContext) -> (DebugAdapterClient, FakeDebugAdapter) {
+        let (stdin_writer, stdin_reader) = async_pipe::pipe();
+        let (stdout_writer, stdout_reader) = async_pipe::pipe();
+        let client =
+            DebugAdapterClient::new_internal(stdin_writer, stdout_reader, None, cx.clone());
+        let adapter = DebugAdapterClient::new_internal(stdout_writer, stdin_reader, None, cx);
+        (client, FakeDebugAdapter { adapter })
+    }
+
+    /// Responds to the requests of a kind with the given handler.
+    pub fn handle_request<R, F>(&self, mut f: F)
+    where
+        R: Request,
+        F: 'static + Send + FnMut(R::Arguments) -> Result<R::Response>,
+    {
+        self.adapter
+            .on_request::<R, _>(move |arguments, _| f(arguments));
+    }
+
+    pub fn send_event<E: Event>(&self, body: E::Body) {
+        self.adapter.send_event::<E>(body).unwrap();
+    }
+}
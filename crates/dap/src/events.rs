@@ -0,0 +1,105 @@
+//! The events that debug adapters send to Zed.
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::types::Breakpoint;
+
+pub trait Event {
+    const EVENT: &'static str;
+    type Body: Serialize + DeserializeOwned + Send + 'static;
+}
+
+/// Sent when the debug adapter is ready to receive the breakpoints.
+pub enum Initialized {}
+
+impl Event for Initialized {
+    const EVENT: &'static str = "initialized";
+    type Body = ();
+}
+
+pub enum Stopped {}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StoppedEventBody {
+    /// Why the program stopped, like `"breakpoint"`, `"step"` or `"exception"`.
+    pub reason: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub thread_id: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub all_threads_stopped: Option<bool>,
+}
+
+impl Event for Stopped {
+    const EVENT: &'static str = "stopped";
+    type Body = StoppedEventBody;
+}
+
+pub enum Continued {}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContinuedEventBody {
+    pub thread_id: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub all_threads_continued: Option<bool>,
+}
+
+impl Event for Continued {
+    const EVENT: &'static str = "continued";
+    type Body = ContinuedEventBody;
+}
+
+pub enum Exited {}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExitedEventBody {
+    pub exit_code: i64,
+}
+
+impl Event for Exited {
+    const EVENT: &'static str = "exited";
+    type Body = ExitedEventBody;
+}
+
+/// Sent when the debugging has ended.
+pub enum Terminated {}
+
+impl Event for Terminated {
+    const EVENT: &'static str = "terminated";
+    type Body = Option<serde_json::Value>;
+}
+
+pub enum Output {}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OutputEventBody {
+    /// Where the output comes from, like `"console"`, `"stdout"` or `"stderr"`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>,
+    pub output: String,
+}
+
+impl Event for Output {
+    const EVENT: &'static str = "output";
+    type Body = OutputEventBody;
+}
+
+/// Sent when a breakpoint is verified, moved or removed by the debug adapter.
+pub enum BreakpointChanged {}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BreakpointEventBody {
+    pub reason: String,
+    pub breakpoint: Breakpoint,
+}
+
+impl Event for BreakpointChanged {
+    const EVENT: &'static str = "breakpoint";
+    type Body = BreakpointEventBody;
+}
@@ -0,0 +1,272 @@
+//! The requests that Zed sends to debug adapters.
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::types::{
+    Breakpoint, Capabilities, Scope, Source, SourceBreakpoint, StackFrame, Thread, Variable,
+};
+
+pub trait Request {
+    const COMMAND: &'static str;
+    type Arguments: Serialize + DeserializeOwned + Send + 'static;
+    type Response: Serialize + DeserializeOwned + Send + 'static;
+}
+
+pub enum Initialize {}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InitializeArguments {
+    #[serde(rename = "clientID")]
+    pub client_id: String,
+    pub client_name: String,
+    #[serde(rename = "adapterID")]
+    pub adapter_id: String,
+    pub lines_start_at1: bool,
+    pub columns_start_at1: bool,
+    pub path_format: String,
+    pub supports_variable_type: bool,
+}
+
+impl Default for InitializeArguments {
+    fn default() -> Self {
+        Self {
+            client_id: "zed".into(),
+            client_name: "Zed".into(),
+            adapter_id: String::new(),
+            lines_start_at1: true,
+            columns_start_at1: true,
+            path_format: "path".into(),
+            supports_variable_type: true,
+        }
+    }
+}
+
+impl Request for Initialize {
+    const COMMAND: &'static str = "initialize";
+    type Arguments = InitializeArguments;
+    type Response = Capabilities;
+}
+
+/// Starts the program to debug, with arguments that are specific to each debug adapter.
+pub enum Launch {}
+
+impl Request for Launch {
+    const COMMAND: &'static str = "launch";
+    type Arguments = Value;
+    type Response = ();
+}
+
+/// Attaches to a running program, with arguments that are specific to each debug adapter.
+pub enum Attach {}
+
+impl Request for Attach {
+    const COMMAND: &'static str = "attach";
+    type Arguments = Value;
+    type Response = ();
+}
+
+/// Replaces all the breakpoints of a source.
+pub enum SetBreakpoints {}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SetBreakpointsArguments {
+    pub source: Source,
+    pub breakpoints: Vec<SourceBreakpoint>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SetBreakpointsResponse {
+    pub breakpoints: Vec<Breakpoint>,
+}
+
+impl Request for SetBreakpoints {
+    const COMMAND: &'static str = "setBreakpoints";
+    type Arguments = SetBreakpointsArguments;
+    type Response = SetBreakpointsResponse;
+}
+
+/// Tells the debug adapter that all the breakpoints have been set.
+pub enum ConfigurationDone {}
+
+impl Request for ConfigurationDone {
+    const COMMAND: &'static str = "configurationDone";
+    type Arguments = ();
+    type Response = ();
+}
+
+pub enum Threads {}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ThreadsResponse {
+    pub threads: Vec<Thread>,
+}
+
+impl Request for Threads {
+    const COMMAND: &'static str = "threads";
+    type Arguments = ();
+    type Response = ThreadsResponse;
+}
+
+pub enum StackTrace {}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StackTraceArguments {
+    pub thread_id: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start_frame: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub levels: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StackTraceResponse {
+    pub stack_frames: Vec<StackFrame>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub total_frames: Option<u64>,
+}
+
+impl Request for StackTrace {
+    const COMMAND: &'static str = "stackTrace";
+    type Arguments = StackTraceArguments;
+    type Response = StackTraceResponse;
+}
+
+pub enum Scopes {}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScopesArguments {
+    pub frame_id: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScopesResponse {
+    pub scopes: Vec<Scope>,
+}
+
+impl Request for Scopes {
+    const COMMAND: &'static str = "scopes";
+    type Arguments = ScopesArguments;
+    type Response = ScopesResponse;
+}
+
+pub enum Variables {}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VariablesArguments {
+    pub variables_reference: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VariablesResponse {
+    pub variables: Vec<Variable>,
+}
+
+impl Request for Variables {
+    const COMMAND: &'static str = "variables";
+    type Arguments = VariablesArguments;
+    type Response = VariablesResponse;
+}
+
+pub enum Evaluate {}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EvaluateArguments {
+    pub expression: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub frame_id: Option<u64>,
+    /// Where the expression comes from, like `"repl"` or `"hover"`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EvaluateResponse {
+    pub result: String,
+    #[serde(default, rename = "type", skip_serializing_if = "Option::is_none")]
+    pub type_: Option<String>,
+    #[serde(default)]
+    pub variables_reference: u64,
+}
+
+impl Request for Evaluate {
+    const COMMAND: &'static str = "evaluate";
+    type Arguments = EvaluateArguments;
+    type Response = EvaluateResponse;
+}
+
+/// The arguments of the requests that act on a single thread.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ThreadArguments {
+    pub thread_id: u64,
+}
+
+pub enum Continue {}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct ContinueResponse {
+    pub all_threads_continued: Option<bool>,
+}
+
+impl Request for Continue {
+    const COMMAND: &'static str = "continue";
+    type Arguments = ThreadArguments;
+    type Response = ContinueResponse;
+}
+
+/// Steps over the current line.
+pub enum Next {}
+
+impl Request for Next {
+    const COMMAND: &'static str = "next";
+    type Arguments = ThreadArguments;
+    type Response = ();
+}
+
+pub enum StepIn {}
+
+impl Request for StepIn {
+    const COMMAND: &'static str = "stepIn";
+    type Arguments = ThreadArguments;
+    type Response = ();
+}
+
+pub enum StepOut {}
+
+impl Request for StepOut {
+    const COMMAND: &'static str = "stepOut";
+    type Arguments = ThreadArguments;
+    type Response = ();
+}
+
+pub enum Pause {}
+
+impl Request for Pause {
+    const COMMAND: &'static str = "pause";
+    type Arguments = ThreadArguments;
+    type Response = ();
+}
+
+/// Ends the debug session, terminating the program when it was launched.
+pub enum Disconnect {}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct DisconnectArguments {
+    pub terminate_debuggee: Option<bool>,
+}
+
+impl Request for Disconnect {
+    const COMMAND: &'static str = "disconnect";
+    type Arguments = DisconnectArguments;
+    type Response = ();
+}
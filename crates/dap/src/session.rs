@@ -0,0 +1,742 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use anyhow::{anyhow, Context as _, Result};
+use collections::HashMap;
+use futures::{channel::oneshot, FutureExt as _};
+use gpui::{AppContext, EventEmitter, Model, ModelContext, Task};
+use task::{DebugRequestKind, ResolvedDebugTemplate};
+
+use crate::{
+    events::{Continued, Exited, Initialized, Output, Stopped, StoppedEventBody, Terminated},
+    requests::{
+        Attach, ConfigurationDone, Continue, Disconnect, DisconnectArguments, Evaluate,
+        EvaluateArguments, Initialize, InitializeArguments, Launch, Next, Pause, Request, Scopes,
+        ScopesArguments, SetBreakpoints, SetBreakpointsArguments, StackTrace, StackTraceArguments,
+        StepIn, StepOut, ThreadArguments, Threads, Variables, VariablesArguments,
+    },
+    types::{
+        Breakpoint, Capabilities, Scope, Source, SourceBreakpoint, StackFrame, Thread, Variable,
+    },
+    DebugAdapterClient,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SessionStatus {
+    Starting,
+    Running,
+    Stopped {
+        thread_id: Option<u64>,
+        reason: String,
+    },
+    Exited,
+}
+
+pub enum DebugSessionEvent {
+    Stopped,
+    Exited,
+}
+
+/// The state of a program being debugged through a debug adapter.
+pub struct DebugSession {
+    label: String,
+    client: Arc<DebugAdapterClient>,
+    capabilities: Capabilities,
+    status: SessionStatus,
+    threads: Vec<Thread>,
+    stack_frames: Vec<StackFrame>,
+    selected_frame_id: Option<u64>,
+    scopes: Vec<Scope>,
+    variables: HashMap<u64, Vec<Variable>>,
+    output: String,
+}
+
+impl EventEmitter<DebugSessionEvent> for DebugSession {}
+
+impl DebugSession {
+    /// Initializes the debug adapter, launches or attaches to the program and sets the breakpoints,
+    /// given as 0-based rows of each file.
+    pub fn start(
+        client: DebugAdapterClient,
+        template: &ResolvedDebugTemplate,
+        breakpoints: Vec<(PathBuf, Vec<u32>)>,
+        cx: &mut AppContext,
+    ) -> Task<Result<Model<Self>>> {
+        let client = Arc::new(client);
+        let (initialized_tx, initialized_rx) = oneshot::channel();
+        let mut initialized_tx = Some(initialized_tx);
+        client.on_event::<Initialized, _>(move |(), _| {
+            if let Some(initialized_tx) = initialized_tx.take() {
+                initialized_tx.send(()).ok();
+            }
+        });
+
+        let session = cx.new_model(|cx| Self::new(template.label.clone(), client.clone(), cx));
+        let adapter_id = Path::new(&template.adapter)
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let initialize = client.request::<Initialize>(InitializeArguments {
+            adapter_id,
+            ..InitializeArguments::default()
+        });
+        let request = template.request;
+        let request_args = template.request_args.clone();
+        cx.spawn(|mut cx| async move {
+            let capabilities = initialize
+                .await
+                .context("failed to initialize the debug adapter")?;
+            session.update(&mut cx, |session, cx| {
+                session.capabilities = capabilities.clone();
+                cx.notify();
+            })?;
+
+            // Adapters may only respond to the launch request once the configuration is done,
+            // which happens after they are initialized.
+            let mut launch = match request {
+                DebugRequestKind::Launch => client.request::<Launch>(request_args).boxed(),
+                DebugRequestKind::Attach => client.request::<Attach>(request_args).boxed(),
+            }
+            .fuse();
+            let mut initialized_rx = initialized_rx.fuse();
+            let launched = futures::select_biased! {
+                _ = initialized_rx => false,
+                result = launch => {
+                    result.context("failed to start debugging")?;
+                    true
+                }
+            };
+            if launched {
+                initialized_rx
+                    .await
+                    .map_err(|_| anyhow!("debug adapter was never initialized"))?;
+            }
+
+            for (path, rows) in breakpoints {
+                client
+                    .request::<SetBreakpoints>(set_breakpoints_arguments(path, &rows))
+                    .await
+                    .context("failed to set breakpoints")?;
+            }
+            if capabilities.supports_configuration_done_request {
+                client.request::<ConfigurationDone>(()).await?;
+            }
+            if !launched {
+                launch.await.context("failed to start debugging")?;
+            }
+
+            session.update(&mut cx, |session, cx| {
+                if session.status == SessionStatus::Starting {
+                    session.status = SessionStatus::Running;
+                    cx.notify();
+                }
+            })?;
+            Ok(session)
+        })
+    }
+
+    fn new(label: String, client: Arc<DebugAdapterClient>, cx: &mut ModelContext<Self>) -> Self {
+        let this = cx.weak_model();
+        client.on_event::<Stopped, _>({
+            let this = this.clone();
+            move |body, mut cx| {
+                this.update(&mut cx, |this, cx| this.handle_stopped(body, cx))
+                    .ok();
+            }
+        });
+        client.on_event::<Continued, _>({
+            let this = this.clone();
+            move |_, mut cx| {
+                this.update(&mut cx, |this, cx| {
+                    if let SessionStatus::Stopped { .. } = this.status {
+                        this.set_running(cx);
+                    }
+                })
+                .ok();
+            }
+        });
+        client.on_event::<Output, _>({
+            let this = this.clone();
+            move |body, mut cx| {
+                if body.category.as_deref() == Some("telemetry") {
+                    return;
+                }
+                this.update(&mut cx, |this, cx| {
+                    this.output.push_str(&body.output);
+                    cx.notify();
+                })
+                .ok();
+            }
+        });
+        client.on_event::<Exited, _>({
+            let this = this.clone();
+            move |body, mut cx| {
+                this.update(&mut cx, |this, cx| {
+                    this.output
+                        .push_str(&format!("Program exited with code {}\n", body.exit_code));
+                    cx.notify();
+                })
+                .ok();
+            }
+        });
+        client.on_event::<Terminated, _>(move |_, mut cx| {
+            this.update(&mut cx, |this, cx| this.set_exited(cx)).ok();
+        });
+
+        Self {
+            label,
+            client,
+            capabilities: Capabilities::default(),
+            status: SessionStatus::Starting,
+            threads: Vec::new(),
+            stack_frames: Vec::new(),
+            selected_frame_id: None,
+            scopes: Vec::new(),
+            variables: HashMap::default(),
+            output: String::new(),
+        }
+    }
+
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
+    pub fn capabilities(&self) -> &Capabilities {
+        &self.capabilities
+    }
+
+    pub fn status(&self) -> &SessionStatus {
+        &self.status
+    }
+
+    pub fn threads(&self) -> &[Thread] {
+        &self.threads
+    }
+
+    /// The frames of the stopped thread's stack, innermost first.
+    pub fn stack_frames(&self) -> &[StackFrame] {
+        &self.stack_frames
+    }
+
+    pub fn selected_frame_id(&self) -> Option<u64> {
+        self.selected_frame_id
+    }
+
+    /// The scopes of the selected stack frame.
+    pub fn scopes(&self) -> &[Scope] {
+        &self.scopes
+    }
+
+    /// The variables with the given reference, if they have been loaded.
+    pub fn variables(&self, variables_reference: u64) -> Option<&[Variable]> {
+        self.variables
+            .get(&variables_reference)
+            .map(|variables| variables.as_slice())
+    }
+
+    /// Everything the program and the debug adapter printed, along with the evaluated expressions.
+    pub fn output(&self) -> &str {
+        &self.output
+    }
+
+    fn handle_stopped(&mut self, body: StoppedEventBody, cx: &mut ModelContext<Self>) {
+        self.status = SessionStatus::Stopped {
+            thread_id: body.thread_id,
+            reason: body.reason,
+        };
+        self.clear_frames();
+        cx.notify();
+
+        let threads = self.client.request::<Threads>(());
+        let client = self.client.clone();
+        cx.spawn(|this, mut cx| async move {
+            let threads = threads.await?.threads;
+            let thread_id = body
+                .thread_id
+                .or_else(|| threads.first().map(|thread| thread.id));
+            let stack_frames = match thread_id {
+                Some(thread_id) => {
+                    client
+                        .request::<StackTrace>(StackTraceArguments {
+                            thread_id,
+                            start_frame: None,
+                            levels: None,
+                        })
+                        .await?
+                        .stack_frames
+                }
+                None => Vec::new(),
+            };
+            let select_frame = this.update(&mut cx, |this, cx| {
+                let SessionStatus::Stopped {
+                    thread_id: stopped_thread_id,
+                    ..
+                } = &mut this.status
+                else {
+                    return None;
+                };
+                *stopped_thread_id = thread_id;
+                this.threads = threads;
+                this.stack_frames = stack_frames;
+                cx.emit(DebugSessionEvent::Stopped);
+                cx.notify();
+                let top_frame_id = this.stack_frames.first()?.id;
+                Some(this.select_frame(top_frame_id, cx))
+            })?;
+            if let Some(select_frame) = select_frame {
+                select_frame.await?;
+            }
+            anyhow::Ok(())
+        })
+        .detach_and_log_err(cx);
+    }
+
+    /// Selects a frame of the stopped thread's stack, loading its scopes and their variables.
+    /// Variables of the scopes that the debug adapter marks as expensive are only loaded with [`Self::load_variables`].
+    pub fn select_frame(&mut self, frame_id: u64, cx: &mut ModelContext<Self>) -> Task<Result<()>> {
+        self.selected_frame_id = Some(frame_id);
+        cx.notify();
+
+        let scopes = self.client.request::<Scopes>(ScopesArguments { frame_id });
+        let client = self.client.clone();
+        cx.spawn(|this, mut cx| async move {
+            let scopes = scopes.await?.scopes;
+            let mut variables = HashMap::default();
+            for scope in scopes.iter().filter(|scope| !scope.expensive) {
+                let response = client
+                    .request::<Variables>(VariablesArguments {
+                        variables_reference: scope.variables_reference,
+                    })
+                    .await?;
+                variables.insert(scope.variables_reference, response.variables);
+            }
+            this.update(&mut cx, |this, cx| {
+                if this.selected_frame_id == Some(frame_id) {
+                    this.scopes = scopes;
+                    this.variables = variables;
+                    cx.notify();
+                }
+            })
+        })
+    }
+
+    /// Loads the children of a scope or a variable.
+    pub fn load_variables(
+        &mut self,
+        variables_reference: u64,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<()>> {
+        if variables_reference == 0 || self.variables.contains_key(&variables_reference) {
+            return Task::ready(Ok(()));
+        }
+
+        let request = self.client.request::<Variables>(VariablesArguments {
+            variables_reference,
+        });
+        cx.spawn(|this, mut cx| async move {
+            let variables = request.await?.variables;
+            this.update(&mut cx, |this, cx| {
+                this.variables.insert(variables_reference, variables);
+                cx.notify();
+            })
+        })
+    }
+
+    pub fn resume(&mut self, cx: &mut ModelContext<Self>) -> Task<Result<()>> {
+        self.thread_request::<Continue>(cx)
+    }
+
+    pub fn step_over(&mut self, cx: &mut ModelContext<Self>) -> Task<Result<()>> {
+        self.thread_request::<Next>(cx)
+    }
+
+    pub fn step_in(&mut self, cx: &mut ModelContext<Self>) -> Task<Result<()>> {
+        self.thread_request::<StepIn>(cx)
+    }
+
+    pub fn step_out(&mut self, cx: &mut ModelContext<Self>) -> Task<Result<()>> {
+        self.thread_request::<StepOut>(cx)
+    }
+
+    fn thread_request<R>(&mut self, cx: &mut ModelContext<Self>) -> Task<Result<()>>
+    where
+        R: Request<Arguments = ThreadArguments>,
+    {
+        let SessionStatus::Stopped { thread_id, .. } = &self.status else {
+            return Task::ready(Err(anyhow!("the program is not stopped")));
+        };
+        let Some(thread_id) = (*thread_id).or_else(|| Some(self.threads.first()?.id)) else {
+            return Task::ready(Err(anyhow!("the program has no threads")));
+        };
+
+        let request = self.client.request::<R>(ThreadArguments { thread_id });
+        self.set_running(cx);
+        cx.background_executor().spawn(async move {
+            request.await?;
+            Ok(())
+        })
+    }
+
+    pub fn pause(&mut self, cx: &mut ModelContext<Self>) -> Task<Result<()>> {
+        if self.status != SessionStatus::Running {
+            return Task::ready(Ok(()));
+        }
+
+        let threads = self.client.request::<Threads>(());
+        let client = self.client.clone();
+        cx.background_executor().spawn(async move {
+            let thread = threads
+                .await?
+                .threads
+                .into_iter()
+                .next()
+                .context("the program has no threads")?;
+            client
+                .request::<Pause>(ThreadArguments {
+                    thread_id: thread.id,
+                })
+                .await
+        })
+    }
+
+    /// Ends the session, terminating the program when it was launched.
+    pub fn stop(&mut self, cx: &mut ModelContext<Self>) -> Task<Result<()>> {
+        if self.status == SessionStatus::Exited {
+            return Task::ready(Ok(()));
+        }
+
+        let request = self.client.request::<Disconnect>(DisconnectArguments {
+            terminate_debuggee: Some(true),
+        });
+        cx.spawn(|this, mut cx| async move {
+            let result = request.await;
+            this.update(&mut cx, |this, cx| this.set_exited(cx))?;
+            result
+        })
+    }
+
+    /// Evaluates an expression in the selected stack frame, printing it and its result to the output.
+    pub fn evaluate(
+        &mut self,
+        expression: String,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<()>> {
+        self.output.push_str(&format!("> {expression}\n"));
+        cx.notify();
+
+        let request = self.client.request::<Evaluate>(EvaluateArguments {
+            expression,
+            frame_id: self.selected_frame_id,
+            context: Some("repl".into()),
+        });
+        cx.spawn(|this, mut cx| async move {
+            let result = request.await;
+            let line = match &result {
+                Ok(response) => response.result.clone(),
+                Err(error) => error.to_string(),
+            };
+            this.update(&mut cx, |this, cx| {
+                this.output.push_str(&line);
+                this.output.push('\n');
+                cx.notify();
+            })?;
+            result?;
+            Ok(())
+        })
+    }
+
+    /// Replaces the breakpoints of a file with the given 0-based rows.
+    pub fn set_breakpoints(
+        &mut self,
+        path: PathBuf,
+        rows: &[u32],
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<Vec<Breakpoint>>> {
+        if self.status == SessionStatus::Exited {
+            return Task::ready(Ok(Vec::new()));
+        }
+
+        let request = self
+            .client
+            .request::<SetBreakpoints>(set_breakpoints_arguments(path, rows));
+        cx.background_executor()
+            .spawn(async move { Ok(request.await?.breakpoints) })
+    }
+
+    fn set_running(&mut self, cx: &mut ModelContext<Self>) {
+        self.status = SessionStatus::Running;
+        self.clear_frames();
+        cx.notify();
+    }
+
+    fn set_exited(&mut self, cx: &mut ModelContext<Self>) {
+        if self.status != SessionStatus::Exited {
+            self.status = SessionStatus::Exited;
+            self.threads.clear();
+            self.clear_frames();
+            cx.emit(DebugSessionEvent::Exited);
+            cx.notify();
+        }
+    }
+
+    fn clear_frames(&mut self) {
+        self.stack_frames.clear();
+        self.selected_frame_id = None;
+        self.scopes.clear();
+        self.variables.clear();
+    }
+}
+
+fn set_breakpoints_arguments(path: PathBuf, rows: &[u32]) -> SetBreakpointsArguments {
+    SetBreakpointsArguments {
+        source: Source {
+            name: path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned()),
+            path: Some(path),
+            source_reference: None,
+        },
+        breakpoints: rows
+            .iter()
+            .map(|row| SourceBreakpoint {
+                line: row + 1,
+                condition: None,
+            })
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use gpui::TestAppContext;
+    use parking_lot::Mutex;
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        requests::{
+            EvaluateResponse, ScopesResponse, SetBreakpointsResponse, StackTraceResponse,
+            ThreadsResponse, VariablesResponse,
+        },
+        FakeDebugAdapter,
+    };
+
+    #[gpui::test]
+    async fn test_debug_session(cx: &mut TestAppContext) {
+        let (client, adapter) = FakeDebugAdapter::new(cx.to_async());
+        let requests = Arc::new(Mutex::new(Vec::<String>::new()));
+
+        adapter.handle_request::<Initialize, _>(|arguments| {
+            assert_eq!(arguments.adapter_id, "fake-adapter");
+            Ok(Capabilities {
+                supports_configuration_done_request: true,
+                ..Capabilities::default()
+            })
+        });
+        adapter.handle_request::<Launch, _>({
+            let requests = requests.clone();
+            move |arguments| {
+                requests.lock().push(format!("launch {arguments}"));
+                Ok(())
+            }
+        });
+        adapter.handle_request::<SetBreakpoints, _>({
+            let requests = requests.clone();
+            move |arguments| {
+                let lines = arguments
+                    .breakpoints
+                    .iter()
+                    .map(|breakpoint| breakpoint.line)
+                    .collect::<Vec<_>>();
+                requests.lock().push(format!(
+                    "setBreakpoints {} {lines:?}",
+                    arguments.source.path.unwrap().display()
+                ));
+                Ok(SetBreakpointsResponse {
+                    breakpoints: Vec::new(),
+                })
+            }
+        });
+        adapter.handle_request::<ConfigurationDone, _>({
+            let requests = requests.clone();
+            move |()| {
+                requests.lock().push("configurationDone".into());
+                Ok(())
+            }
+        });
+        adapter.handle_request::<Threads, _>(|()| {
+            Ok(ThreadsResponse {
+                threads: vec![Thread {
+                    id: 1,
+                    name: "main".into(),
+                }],
+            })
+        });
+        adapter.handle_request::<StackTrace, _>(|arguments| {
+            assert_eq!(arguments.thread_id, 1);
+            Ok(StackTraceResponse {
+                stack_frames: vec![
+                    StackFrame {
+                        id: 100,
+                        name: "inner".into(),
+                        source: Some(Source {
+                            path: Some("/project/main.py".into()),
+                            ..Source::default()
+                        }),
+                        line: 5,
+                        column: 1,
+                    },
+                    StackFrame {
+                        id: 101,
+                        name: "outer".into(),
+                        source: None,
+                        line: 20,
+                        column: 1,
+                    },
+                ],
+                total_frames: Some(2),
+            })
+        });
+        adapter.handle_request::<Scopes, _>(|arguments| {
+            assert_eq!(arguments.frame_id, 100);
+            Ok(ScopesResponse {
+                scopes: vec![
+                    Scope {
+                        name: "Locals".into(),
+                        variables_reference: 10,
+                        expensive: false,
+                    },
+                    Scope {
+                        name: "Globals".into(),
+                        variables_reference: 11,
+                        expensive: true,
+                    },
+                ],
+            })
+        });
+        adapter.handle_request::<Variables, _>(|arguments| {
+            assert_eq!(arguments.variables_reference, 10);
+            Ok(VariablesResponse {
+                variables: vec![Variable {
+                    name: "x".into(),
+                    value: "1".into(),
+                    type_: Some("int".into()),
+                    variables_reference: 0,
+                }],
+            })
+        });
+        adapter.handle_request::<Evaluate, _>(|arguments| {
+            assert_eq!(arguments.frame_id, Some(100));
+            Ok(EvaluateResponse {
+                result: format!("{} = 2", arguments.expression),
+                type_: None,
+                variables_reference: 0,
+            })
+        });
+        adapter.handle_request::<Next, _>({
+            let requests = requests.clone();
+            move |arguments| {
+                requests
+                    .lock()
+                    .push(format!("next {}", arguments.thread_id));
+                Ok(())
+            }
+        });
+
+        let template = ResolvedDebugTemplate {
+            label: "Debug main.py".into(),
+            adapter: "/usr/bin/fake-adapter".into(),
+            adapter_args: Vec::new(),
+            request: DebugRequestKind::Launch,
+            request_args: json!({ "program": "/project/main.py" }),
+            cwd: None,
+        };
+        let start = cx.update(|cx| {
+            DebugSession::start(
+                client,
+                &template,
+                vec![(PathBuf::from("/project/main.py"), vec![4, 9])],
+                cx,
+            )
+        });
+        cx.run_until_parked();
+        adapter.send_event::<Initialized>(());
+        let session = start.await.unwrap();
+        assert_eq!(
+            requests.lock().drain(..).collect::<Vec<_>>(),
+            [
+                r#"launch {"program":"/project/main.py"}"#,
+                "setBreakpoints /project/main.py [5, 10]",
+                "configurationDone",
+            ]
+        );
+        session.read_with(cx, |session, _| {
+            assert_eq!(session.label(), "Debug main.py");
+            assert_eq!(session.status(), &SessionStatus::Running);
+        });
+
+        adapter.send_event::<Stopped>(StoppedEventBody {
+            reason: "breakpoint".into(),
+            description: None,
+            thread_id: Some(1),
+            text: None,
+            all_threads_stopped: None,
+        });
+        cx.run_until_parked();
+        session.read_with(cx, |session, _| {
+            assert_eq!(
+                session.status(),
+                &SessionStatus::Stopped {
+                    thread_id: Some(1),
+                    reason: "breakpoint".into()
+                }
+            );
+            assert_eq!(
+                session
+                    .stack_frames()
+                    .iter()
+                    .map(|frame| frame.name.as_str())
+                    .collect::<Vec<_>>(),
+                ["inner", "outer"]
+            );
+            assert_eq!(session.selected_frame_id(), Some(100));
+            assert_eq!(session.scopes().len(), 2);
+            assert_eq!(
+                session
+                    .variables(10)
+                    .unwrap()
+                    .iter()
+                    .map(|variable| (variable.name.as_str(), variable.value.as_str()))
+                    .collect::<Vec<_>>(),
+                [("x", "1")]
+            );
+            assert_eq!(session.variables(11), None);
+        });
+
+        session
+            .update(cx, |session, cx| session.evaluate("x + 1".into(), cx))
+            .await
+            .unwrap();
+        session.read_with(cx, |session, _| {
+            assert_eq!(session.output(), "> x + 1\nx + 1 = 2\n");
+        });
+
+        session
+            .update(cx, |session, cx| session.step_over(cx))
+            .await
+            .unwrap();
+        assert_eq!(requests.lock().drain(..).collect::<Vec<_>>(), ["next 1"]);
+        session.read_with(cx, |session, _| {
+            assert_eq!(session.status(), &SessionStatus::Running);
+            assert!(session.stack_frames().is_empty());
+        });
+
+        adapter.send_event::<Terminated>(None);
+        cx.run_until_parked();
+        session.read_with(cx, |session, _| {
+            assert_eq!(session.status(), &SessionStatus::Exited);
+        });
+    }
+}
@@ -0,0 +1,98 @@
+use anyhow::{anyhow, Context as _, Result};
+use futures::{
+    AsyncBufRead, AsyncBufReadExt as _, AsyncReadExt as _, AsyncWrite, AsyncWriteExt as _,
+};
+
+use crate::types::Message;
+
+const CONTENT_LEN_HEADER: &str = "Content-Length: ";
+const HEADER_DELIMITER: &[u8] = b"\r\n\r\n";
+
+/// Reads the next message, or returns `None` when the stream has ended.
+pub(crate) async fn read_message<R>(reader: &mut R, buffer: &mut Vec<u8>) -> Result<Option<Message>>
+where
+    R: AsyncBufRead + Unpin,
+{
+    buffer.clear();
+    while !buffer.ends_with(HEADER_DELIMITER) {
+        if reader.read_until(b'\n', buffer).await? == 0 {
+            if buffer.iter().all(u8::is_ascii_whitespace) {
+                return Ok(None);
+            }
+            return Err(anyhow!("debug adapter output ended in message headers"));
+        }
+    }
+
+    let headers = std::str::from_utf8(buffer)?;
+    let message_len = headers
+        .lines()
+        .find_map(|line| line.strip_prefix(CONTENT_LEN_HEADER))
+        .ok_or_else(|| anyhow!("invalid debug adapter message headers {headers:?}"))?
+        .trim()
+        .parse()?;
+
+    buffer.resize(message_len, 0);
+    reader.read_exact(buffer).await?;
+    log::trace!("incoming message: {}", String::from_utf8_lossy(buffer));
+    let message = serde_json::from_slice(buffer).with_context(|| {
+        format!(
+            "invalid debug adapter message {:?}",
+            String::from_utf8_lossy(buffer)
+        )
+    })?;
+    Ok(Some(message))
+}
+
+pub(crate) async fn write_message<W>(writer: &mut W, message: &str) -> Result<()>
+where
+    W: AsyncWrite + Unpin,
+{
+    log::trace!("outgoing message: {message}");
+    writer
+        .write_all(format!("{CONTENT_LEN_HEADER}{}\r\n\r\n", message.len()).as_bytes())
+        .await?;
+    writer.write_all(message.as_bytes()).await?;
+    writer.flush().await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::io::BufReader;
+
+    use super::*;
+    use crate::types::{EventMessage, Message};
+
+    #[gpui::test]
+    async fn test_read_and_write_messages() {
+        let event = Message::Event(EventMessage {
+            seq: 1,
+            event: "initialized".into(),
+            body: serde_json::Value::Null,
+        });
+        let mut output = Vec::new();
+        write_message(&mut output, &serde_json::to_string(&event).unwrap())
+            .await
+            .unwrap();
+        write_message(
+            &mut output,
+            r#"{"seq":2,"type":"request","command":"threads"}"#,
+        )
+        .await
+        .unwrap();
+        assert!(output.starts_with(b"Content-Length: 46\r\n\r\n{\"type\":\"event\""));
+
+        let mut reader = BufReader::new(output.as_slice());
+        let mut buffer = Vec::new();
+        assert_eq!(
+            read_message(&mut reader, &mut buffer).await.unwrap(),
+            Some(event)
+        );
+        let Some(Message::Request(request)) = read_message(&mut reader, &mut buffer).await.unwrap()
+        else {
+            panic!("expected a request");
+        };
+        assert_eq!((request.seq, request.command.as_str()), (2, "threads"));
+        assert_eq!(read_message(&mut reader, &mut buffer).await.unwrap(), None);
+    }
+}
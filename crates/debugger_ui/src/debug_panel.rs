@@ -0,0 +1,734 @@
+use std::sync::Arc;
+
+use anyhow::{Context as _, Result};
+use collections::HashSet;
+use dap::{
+    types::StackFrame, DebugAdapterBinary, DebugAdapterClient, DebugSession, DebugSessionEvent,
+    SessionStatus,
+};
+use editor::{scroll::Autoscroll, BreakpointStore, BreakpointStoreEvent, Editor};
+use fs::Fs;
+use gpui::{
+    px, Action, AppContext, AsyncWindowContext, EventEmitter, FocusHandle, FocusableView, Model,
+    ModelContext, Pixels, Render, Subscription, Task, View, ViewContext, WeakView,
+};
+use language::Point;
+use project::Project;
+use task::{DebugTemplate, DebugTemplates};
+use ui::{prelude::*, ListHeader, ListItem, ListItemSpacing, Tooltip};
+use workspace::{
+    dock::{DockPosition, Panel, PanelEvent},
+    Workspace,
+};
+
+use crate::{Continue, Pause, Start, StepInto, StepOut, StepOver, Stop, ToggleFocus};
+
+/// The row highlight of the line that the selected stack frame is at.
+enum ActiveStackFrame {}
+
+pub struct DebugPanel {
+    workspace: WeakView<Workspace>,
+    project: Model<Project>,
+    fs: Arc<dyn Fs>,
+    focus_handle: FocusHandle,
+    position: DockPosition,
+    width: Option<Pixels>,
+    height: Option<Pixels>,
+    configurations: Vec<DebugTemplate>,
+    session: Option<Model<DebugSession>>,
+    error: Option<SharedString>,
+    expression_editor: View<Editor>,
+    /// The scopes and variables whose expansion was toggled since the program last stopped.
+    toggled_variables: HashSet<u64>,
+    active_frame_editor: Option<WeakView<Editor>>,
+    _session_subscriptions: Vec<Subscription>,
+    _subscriptions: Vec<Subscription>,
+}
+
+impl DebugPanel {
+    pub fn load(
+        workspace: WeakView<Workspace>,
+        cx: AsyncWindowContext,
+    ) -> Task<Result<View<Self>>> {
+        cx.spawn(|mut cx| async move { workspace.update(&mut cx, Self::new) })
+    }
+
+    pub fn new(workspace: &mut Workspace, cx: &mut ViewContext<Workspace>) -> View<Self> {
+        let fs = workspace.app_state().fs.clone();
+        let project = workspace.project().clone();
+        let weak_workspace = workspace.weak_handle();
+
+        cx.new_view(|cx| {
+            let expression_editor = cx.new_view(|cx| {
+                let mut editor = Editor::single_line(cx);
+                editor.set_placeholder_text("Evaluate an expression", cx);
+                editor
+            });
+
+            let mut subscriptions = vec![cx.subscribe(&project, |this, _, event, cx| {
+                if let project::Event::WorktreeAdded(_) | project::Event::WorktreeRemoved(_) = event
+                {
+                    this.reload_configurations(cx).detach();
+                }
+            })];
+            if let Some(store) = BreakpointStore::global(cx) {
+                subscriptions.push(cx.subscribe(&store, Self::handle_breakpoints_changed));
+            }
+
+            let mut this = Self {
+                workspace: weak_workspace,
+                project,
+                fs,
+                focus_handle: cx.focus_handle(),
+                position: DockPosition::Bottom,
+                width: None,
+                height: None,
+                configurations: Vec::new(),
+                session: None,
+                error: None,
+                expression_editor,
+                toggled_variables: HashSet::default(),
+                active_frame_editor: None,
+                _session_subscriptions: Vec::new(),
+                _subscriptions: subscriptions,
+            };
+            this.reload_configurations(cx).detach();
+            this
+        })
+    }
+
+    /// Reads the debug configurations of the global `debug.json` file and of the `.zed/debug.json` file of each worktree.
+    fn reload_configurations(&mut self, cx: &mut ViewContext<Self>) -> Task<()> {
+        let mut paths = vec![paths::debug_file().clone()];
+        paths.extend(self.project.read(cx).visible_worktrees(cx).map(|worktree| {
+            worktree
+                .read(cx)
+                .abs_path()
+                .join(paths::local_debug_file_relative_path())
+        }));
+        let fs = self.fs.clone();
+        cx.spawn(|this, mut cx| async move {
+            let mut configurations = Vec::new();
+            for path in paths {
+                let Ok(contents) = fs.load(&path).await else {
+                    continue;
+                };
+                match serde_json_lenient::from_str::<DebugTemplates>(&contents) {
+                    Ok(templates) => configurations.extend(templates.0),
+                    Err(error) => {
+                        log::error!("failed to parse debug configurations in {path:?}: {error}")
+                    }
+                }
+            }
+            this.update(&mut cx, |this, cx| {
+                this.configurations = configurations;
+                cx.notify();
+            })
+            .ok();
+        })
+    }
+
+    /// Starts the only debug configuration there is, and otherwise leaves the user to pick one.
+    pub fn start_default(&mut self, cx: &mut ViewContext<Self>) {
+        let reload = self.reload_configurations(cx);
+        cx.spawn(|this, mut cx| async move {
+            reload.await;
+            this.update(&mut cx, |this, cx| {
+                if let [template] = this.configurations.as_slice() {
+                    this.start(template.clone(), cx);
+                }
+            })
+        })
+        .detach_and_log_err(cx);
+    }
+
+    pub fn start(&mut self, template: DebugTemplate, cx: &mut ViewContext<Self>) {
+        let Some(workspace) = self.workspace.upgrade() else {
+            return;
+        };
+        let task_context = workspace.update(cx, |workspace, cx| {
+            editor::tasks::task_context(workspace, cx)
+        });
+        let worktree_root = self
+            .project
+            .read(cx)
+            .visible_worktrees(cx)
+            .next()
+            .map(|worktree| worktree.read(cx).abs_path().to_path_buf());
+        let breakpoints = BreakpointStore::global(cx)
+            .map(|store| {
+                store
+                    .read(cx)
+                    .breakpoints()
+                    .map(|(path, rows)| (path.to_path_buf(), rows.iter().copied().collect()))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        self.stop(cx);
+        self.error = None;
+        cx.notify();
+
+        cx.spawn(|this, mut cx| async move {
+            let session = async {
+                let mut task_context = task_context.await;
+                if task_context.cwd.is_none() {
+                    task_context.cwd = worktree_root;
+                }
+                let resolved = template.resolve(&task_context).with_context(|| {
+                    format!("failed to resolve debug configuration {:?}", template.label)
+                })?;
+                let binary = DebugAdapterBinary {
+                    command: resolved.adapter.clone(),
+                    args: resolved.adapter_args.clone(),
+                    cwd: resolved.cwd.clone(),
+                    env: Default::default(),
+                };
+                let client = DebugAdapterClient::start(&binary, (*cx).clone())?;
+                let session =
+                    cx.update(|cx| DebugSession::start(client, &resolved, breakpoints, cx))?;
+                anyhow::Ok(session.await?)
+            }
+            .await;
+            this.update(&mut cx, |this, cx| match session {
+                Ok(session) => this.set_session(session, cx),
+                Err(error) => {
+                    this.error = Some(format!("{error:#}").into());
+                    cx.notify();
+                }
+            })
+        })
+        .detach_and_log_err(cx);
+    }
+
+    fn set_session(&mut self, session: Model<DebugSession>, cx: &mut ViewContext<Self>) {
+        self._session_subscriptions = vec![
+            cx.observe(&session, |_, _, cx| cx.notify()),
+            cx.subscribe(&session, |this, session, event, cx| match event {
+                DebugSessionEvent::Stopped => {
+                    this.toggled_variables.clear();
+                    if let Some(frame) = session.read(cx).stack_frames().first().cloned() {
+                        this.open_frame(&frame, cx);
+                    }
+                }
+                DebugSessionEvent::Exited => this.clear_active_frame(cx),
+            }),
+        ];
+        self.session = Some(session);
+        cx.notify();
+    }
+
+    fn handle_breakpoints_changed(
+        &mut self,
+        store: Model<BreakpointStore>,
+        event: &BreakpointStoreEvent,
+        cx: &mut ViewContext<Self>,
+    ) {
+        let BreakpointStoreEvent::BreakpointsChanged(path) = event;
+        let Some(session) = &self.session else {
+            return;
+        };
+        let rows = store
+            .read(cx)
+            .rows(path)
+            .map(|rows| rows.iter().copied().collect::<Vec<_>>())
+            .unwrap_or_default();
+        session
+            .update(cx, |session, cx| {
+                session.set_breakpoints(path.to_path_buf(), &rows, cx)
+            })
+            .detach_and_log_err(cx);
+    }
+
+    fn session_status(&self, cx: &AppContext) -> Option<SessionStatus> {
+        Some(self.session.as_ref()?.read(cx).status().clone())
+    }
+
+    pub fn is_stopped(&self, cx: &AppContext) -> bool {
+        matches!(self.session_status(cx), Some(SessionStatus::Stopped { .. }))
+    }
+
+    /// Whether a debug session is starting or running, stopped or not.
+    pub fn is_running(&self, cx: &AppContext) -> bool {
+        self.session_status(cx)
+            .is_some_and(|status| status != SessionStatus::Exited)
+    }
+
+    fn update_session(
+        &mut self,
+        cx: &mut ViewContext<Self>,
+        update: impl FnOnce(&mut DebugSession, &mut ModelContext<DebugSession>) -> Task<Result<()>>,
+    ) {
+        if let Some(session) = &self.session {
+            session.update(cx, update).detach_and_log_err(cx);
+        }
+    }
+
+    pub fn resume(&mut self, cx: &mut ViewContext<Self>) {
+        self.clear_active_frame(cx);
+        self.update_session(cx, |session, cx| session.resume(cx));
+    }
+
+    pub fn step_over(&mut self, cx: &mut ViewContext<Self>) {
+        self.update_session(cx, |session, cx| session.step_over(cx));
+    }
+
+    pub fn step_in(&mut self, cx: &mut ViewContext<Self>) {
+        self.update_session(cx, |session, cx| session.step_in(cx));
+    }
+
+    pub fn step_out(&mut self, cx: &mut ViewContext<Self>) {
+        self.update_session(cx, |session, cx| session.step_out(cx));
+    }
+
+    pub fn pause(&mut self, cx: &mut ViewContext<Self>) {
+        self.update_session(cx, |session, cx| session.pause(cx));
+    }
+
+    pub fn stop(&mut self, cx: &mut ViewContext<Self>) {
+        self.clear_active_frame(cx);
+        self.update_session(cx, |session, cx| session.stop(cx));
+    }
+
+    fn select_frame(&mut self, frame: &StackFrame, cx: &mut ViewContext<Self>) {
+        self.toggled_variables.clear();
+        let frame_id = frame.id;
+        self.update_session(cx, |session, cx| session.select_frame(frame_id, cx));
+        self.open_frame(frame, cx);
+    }
+
+    /// Opens the file of a stack frame and highlights its line.
+    fn open_frame(&mut self, frame: &StackFrame, cx: &mut ViewContext<Self>) {
+        let Some(path) = frame.source.as_ref().and_then(|source| source.path.clone()) else {
+            return;
+        };
+        let Some(workspace) = self.workspace.upgrade() else {
+            return;
+        };
+        let point = Point::new(frame.line.saturating_sub(1), frame.column.saturating_sub(1));
+        let open = workspace.update(cx, |workspace, cx| workspace.open_abs_path(path, true, cx));
+        cx.spawn(|this, mut cx| async move {
+            let Some(editor) = open.await?.downcast::<Editor>() else {
+                return Ok(());
+            };
+            this.update(&mut cx, |this, cx| {
+                this.clear_active_frame(cx);
+                let color = cx.theme().status().warning_background;
+                editor.update(cx, |editor, cx| {
+                    let snapshot = editor.buffer().read(cx).snapshot(cx);
+                    let point = snapshot.clip_point(point, language::Bias::Left);
+                    let anchor = snapshot.anchor_before(point);
+                    editor.change_selections(Some(Autoscroll::center()), cx, |selections| {
+                        selections.select_ranges([point..point])
+                    });
+                    editor.highlight_rows::<ActiveStackFrame>(anchor..anchor, color, false, cx);
+                });
+                this.active_frame_editor = Some(editor.downgrade());
+            })
+        })
+        .detach_and_log_err(cx);
+    }
+
+    fn clear_active_frame(&mut self, cx: &mut ViewContext<Self>) {
+        if let Some(editor) = self
+            .active_frame_editor
+            .take()
+            .and_then(|editor| editor.upgrade())
+        {
+            editor.update(cx, |editor, cx| {
+                editor.clear_row_highlights::<ActiveStackFrame>();
+                cx.notify();
+            });
+        }
+    }
+
+    fn toggle_variables(&mut self, variables_reference: u64, cx: &mut ViewContext<Self>) {
+        if !self.toggled_variables.remove(&variables_reference) {
+            self.toggled_variables.insert(variables_reference);
+        }
+        self.update_session(cx, |session, cx| {
+            session.load_variables(variables_reference, cx)
+        });
+        cx.notify();
+    }
+
+    fn evaluate(&mut self, _: &menu::Confirm, cx: &mut ViewContext<Self>) {
+        let expression = self.expression_editor.read(cx).text(cx);
+        if expression.trim().is_empty() {
+            return;
+        }
+        self.expression_editor
+            .update(cx, |editor, cx| editor.clear(cx));
+        self.update_session(cx, |session, cx| session.evaluate(expression, cx));
+    }
+
+    fn render_toolbar(&self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let status = self.session_status(cx);
+        let stopped = matches!(status, Some(SessionStatus::Stopped { .. }));
+        let running = status
+            .as_ref()
+            .is_some_and(|status| *status != SessionStatus::Exited);
+        let title = match (&self.session, &status) {
+            (Some(session), Some(status)) => {
+                let status = match status {
+                    SessionStatus::Starting => "Starting".to_string(),
+                    SessionStatus::Running => "Running".to_string(),
+                    SessionStatus::Stopped { reason, .. } => format!("Paused on {reason}"),
+                    SessionStatus::Exited => "Exited".to_string(),
+                };
+                format!("{} — {status}", session.read(cx).label())
+            }
+            _ => "Debugger".to_string(),
+        };
+
+        let focus_handle = self.focus_handle.clone();
+        let button = move |id: &'static str,
+                           icon: IconName,
+                           label: &'static str,
+                           action: Box<dyn Action>,
+                           enabled: bool| {
+            let focus_handle = focus_handle.clone();
+            let tooltip_action = action.boxed_clone();
+            IconButton::new(id, icon)
+                .icon_size(IconSize::Small)
+                .style(ButtonStyle::Subtle)
+                .disabled(!enabled)
+                .tooltip(move |cx| {
+                    Tooltip::for_action_in(label, &*tooltip_action, &focus_handle, cx)
+                })
+                .on_click(move |_, cx| cx.dispatch_action(action.boxed_clone()))
+        };
+
+        h_flex()
+            .id("debug-toolbar")
+            .justify_between()
+            .gap(DynamicSpacing::Base08.rems(cx))
+            .h(Tab::container_height(cx))
+            .px(DynamicSpacing::Base08.rems(cx))
+            .bg(cx.theme().colors().tab_bar_background)
+            .border_b_1()
+            .border_color(cx.theme().colors().border)
+            .child(Label::new(title).single_line())
+            .child(
+                h_flex()
+                    .gap(DynamicSpacing::Base02.rems(cx))
+                    .map(|toolbar| {
+                        if running {
+                            toolbar
+                                .child(button(
+                                    "continue",
+                                    IconName::Play,
+                                    "Continue",
+                                    Box::new(Continue),
+                                    stopped,
+                                ))
+                                .child(button(
+                                    "pause",
+                                    IconName::Pause,
+                                    "Pause",
+                                    Box::new(Pause),
+                                    !stopped,
+                                ))
+                        } else {
+                            toolbar.child(button(
+                                "start",
+                                IconName::Play,
+                                "Start Debugging",
+                                Box::new(Start),
+                                true,
+                            ))
+                        }
+                    })
+                    .child(button(
+                        "step-over",
+                        IconName::ArrowRight,
+                        "Step Over",
+                        Box::new(StepOver),
+                        stopped,
+                    ))
+                    .child(button(
+                        "step-into",
+                        IconName::ArrowDown,
+                        "Step Into",
+                        Box::new(StepInto),
+                        stopped,
+                    ))
+                    .child(button(
+                        "step-out",
+                        IconName::ArrowUp,
+                        "Step Out",
+                        Box::new(StepOut),
+                        stopped,
+                    ))
+                    .child(button(
+                        "stop",
+                        IconName::Stop,
+                        "Stop",
+                        Box::new(Stop),
+                        running,
+                    )),
+            )
+    }
+
+    fn render_configurations(&self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        v_flex()
+            .id("debug-configurations")
+            .flex_1()
+            .overflow_y_scroll()
+            .child(ListHeader::new("Configurations"))
+            .when(self.configurations.is_empty(), |list| {
+                list.child(
+                    div().px_2().child(
+                        Label::new(
+                            "Add debug configurations to .zed/debug.json to start debugging.",
+                        )
+                        .color(Color::Muted),
+                    ),
+                )
+            })
+            .children(
+                self.configurations
+                    .iter()
+                    .enumerate()
+                    .map(|(ix, template)| {
+                        let template = template.clone();
+                        ListItem::new(("debug-configuration", ix))
+                            .inset(true)
+                            .spacing(ListItemSpacing::Sparse)
+                            .start_slot(Icon::new(IconName::Debug).color(Color::Muted))
+                            .child(Label::new(template.label.clone()))
+                            .on_click(cx.listener(move |this, _, cx| {
+                                this.start(template.clone(), cx);
+                            }))
+                    }),
+            )
+    }
+
+    fn render_session(
+        &self,
+        session: &Model<DebugSession>,
+        cx: &mut ViewContext<Self>,
+    ) -> impl IntoElement {
+        let session = session.read(cx);
+        let selected_frame_id = session.selected_frame_id();
+        let frames = session
+            .stack_frames()
+            .iter()
+            .map(|frame| {
+                let location = frame
+                    .source
+                    .as_ref()
+                    .and_then(|source| {
+                        source.name.clone().or_else(|| {
+                            Some(
+                                source
+                                    .path
+                                    .as_ref()?
+                                    .file_name()?
+                                    .to_string_lossy()
+                                    .into_owned(),
+                            )
+                        })
+                    })
+                    .map(|name| format!("{name}:{}", frame.line))
+                    .unwrap_or_default();
+                let frame = frame.clone();
+                ListItem::new(("stack-frame", frame.id as usize))
+                    .inset(true)
+                    .spacing(ListItemSpacing::Sparse)
+                    .toggle_state(selected_frame_id == Some(frame.id))
+                    .child(
+                        h_flex()
+                            .gap_2()
+                            .child(Label::new(frame.name.clone()))
+                            .child(Label::new(location).color(Color::Muted)),
+                    )
+                    .on_click(cx.listener(move |this, _, cx| this.select_frame(&frame, cx)))
+                    .into_any_element()
+            })
+            .collect::<Vec<_>>();
+
+        let mut variables = Vec::new();
+        for scope in session.scopes() {
+            let reference = scope.variables_reference;
+            let expanded = !scope.expensive != self.toggled_variables.contains(&reference);
+            variables.push(
+                ListItem::new(("scope", reference as usize))
+                    .inset(true)
+                    .spacing(ListItemSpacing::Sparse)
+                    .toggle(expanded)
+                    .on_toggle(cx.listener(move |this, _, cx| this.toggle_variables(reference, cx)))
+                    .child(Label::new(scope.name.clone()))
+                    .into_any_element(),
+            );
+            if expanded {
+                self.render_variables(session, reference, 1, &mut variables, cx);
+            }
+        }
+
+        v_flex()
+            .flex_1()
+            .min_h_0()
+            .child(
+                h_flex()
+                    .flex_1()
+                    .min_h_0()
+                    .items_start()
+                    .child(
+                        v_flex()
+                            .id("stack-frames")
+                            .w_1_3()
+                            .h_full()
+                            .overflow_y_scroll()
+                            .border_r_1()
+                            .border_color(cx.theme().colors().border)
+                            .child(ListHeader::new("Call Stack"))
+                            .children(frames),
+                    )
+                    .child(
+                        v_flex()
+                            .id("variables")
+                            .flex_1()
+                            .h_full()
+                            .overflow_y_scroll()
+                            .child(ListHeader::new("Variables"))
+                            .children(variables),
+                    ),
+            )
+            .child(
+                v_flex()
+                    .h(px(160.))
+                    .border_t_1()
+                    .border_color(cx.theme().colors().border)
+                    .child(
+                        div()
+                            .id("debug-output")
+                            .flex_1()
+                            .overflow_y_scroll()
+                            .px_2()
+                            .child(Label::new(session.output().to_string()).size(LabelSize::Small)),
+                    )
+                    .child(
+                        h_flex()
+                            .px_2()
+                            .py_1()
+                            .border_t_1()
+                            .border_color(cx.theme().colors().border)
+                            .child(self.expression_editor.clone()),
+                    ),
+            )
+    }
+
+    fn render_variables(
+        &self,
+        session: &DebugSession,
+        variables_reference: u64,
+        depth: usize,
+        items: &mut Vec<AnyElement>,
+        cx: &ViewContext<Self>,
+    ) {
+        let Some(variables) = session.variables(variables_reference) else {
+            return;
+        };
+        for (ix, variable) in variables.iter().enumerate() {
+            let reference = variable.variables_reference;
+            let expanded = reference != 0 && self.toggled_variables.contains(&reference);
+            items.push(
+                ListItem::new(SharedString::from(format!(
+                    "variable-{variables_reference}-{ix}"
+                )))
+                .inset(true)
+                .spacing(ListItemSpacing::Sparse)
+                .indent_level(depth)
+                .indent_step_size(px(12.))
+                .toggle((reference != 0).then_some(expanded))
+                .on_toggle(cx.listener(move |this, _, cx| this.toggle_variables(reference, cx)))
+                .child(
+                    h_flex()
+                        .gap_2()
+                        .child(Label::new(variable.name.clone()))
+                        .child(Label::new(variable.value.clone()).color(Color::Muted)),
+                )
+                .into_any_element(),
+            );
+            if expanded {
+                self.render_variables(session, reference, depth + 1, items, cx);
+            }
+        }
+    }
+}
+
+impl EventEmitter<PanelEvent> for DebugPanel {}
+
+impl FocusableView for DebugPanel {
+    fn focus_handle(&self, _: &AppContext) -> FocusHandle {
+        self.focus_handle.clone()
+    }
+}
+
+impl Panel for DebugPanel {
+    fn persistent_name() -> &'static str {
+        "DebugPanel"
+    }
+
+    fn position(&self, _: &WindowContext) -> DockPosition {
+        self.position
+    }
+
+    fn position_is_valid(&self, _: DockPosition) -> bool {
+        true
+    }
+
+    fn set_position(&mut self, position: DockPosition, cx: &mut ViewContext<Self>) {
+        self.position = position;
+        cx.notify();
+    }
+
+    fn size(&self, _: &WindowContext) -> Pixels {
+        match self.position {
+            DockPosition::Left | DockPosition::Right => self.width.unwrap_or(px(400.)),
+            DockPosition::Bottom => self.height.unwrap_or(px(320.)),
+        }
+    }
+
+    fn set_size(&mut self, size: Option<Pixels>, cx: &mut ViewContext<Self>) {
+        match self.position {
+            DockPosition::Left | DockPosition::Right => self.width = size,
+            DockPosition::Bottom => self.height = size,
+        }
+        cx.notify();
+    }
+
+    fn icon(&self, _: &WindowContext) -> Option<IconName> {
+        Some(IconName::Debug)
+    }
+
+    fn icon_tooltip(&self, _: &WindowContext) -> Option<&'static str> {
+        Some("Debug Panel")
+    }
+
+    fn toggle_action(&self) -> Box<dyn Action> {
+        Box::new(ToggleFocus)
+    }
+}
+
+impl Render for DebugPanel {
+    fn render(&mut self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        v_flex()
+            .key_context("DebugPanel")
+            .track_focus(&self.focus_handle)
+            .size_full()
+            .on_action(cx.listener(Self::evaluate))
+            .child(self.render_toolbar(cx))
+            .children(self.error.clone().map(|error| {
+                div()
+                    .px_2()
+                    .py_1()
+                    .child(Label::new(error).color(Color::Error))
+            }))
+            .map(|panel| match self.session.clone() {
+                Some(session) => panel.child(self.render_session(&session, cx)),
+                None => panel.child(self.render_configurations(cx)),
+            })
+    }
+}
@@ -0,0 +1,70 @@
+mod debug_panel;
+
+use gpui::{actions, AppContext};
+use workspace::Workspace;
+
+pub use debug_panel::DebugPanel;
+
+actions!(
+    debugger,
+    [
+        ToggleFocus,
+        Start,
+        Continue,
+        StepOver,
+        StepInto,
+        StepOut,
+        Pause,
+        Stop
+    ]
+);
+
+pub fn init(cx: &mut AppContext) {
+    cx.observe_new_views(|workspace: &mut Workspace, _| {
+        workspace
+            .register_action(|workspace, _: &ToggleFocus, cx| {
+                workspace.toggle_panel_focus::<DebugPanel>(cx);
+            })
+            .register_action(|workspace, _: &Start, cx| {
+                if let Some(panel) = workspace.focus_panel::<DebugPanel>(cx) {
+                    panel.update(cx, |panel, cx| panel.start_default(cx));
+                }
+            })
+            .register_action(|workspace, _: &Continue, cx| {
+                if let Some(panel) = workspace.panel::<DebugPanel>(cx) {
+                    if panel.read(cx).is_stopped(cx) {
+                        panel.update(cx, |panel, cx| panel.resume(cx));
+                    } else if !panel.read(cx).is_running(cx) {
+                        workspace.focus_panel::<DebugPanel>(cx);
+                        panel.update(cx, |panel, cx| panel.start_default(cx));
+                    }
+                }
+            })
+            .register_action(|workspace, _: &StepOver, cx| {
+                if let Some(panel) = workspace.panel::<DebugPanel>(cx) {
+                    panel.update(cx, |panel, cx| panel.step_over(cx));
+                }
+            })
+            .register_action(|workspace, _: &StepInto, cx| {
+                if let Some(panel) = workspace.panel::<DebugPanel>(cx) {
+                    panel.update(cx, |panel, cx| panel.step_in(cx));
+                }
+            })
+            .register_action(|workspace, _: &StepOut, cx| {
+                if let Some(panel) = workspace.panel::<DebugPanel>(cx) {
+                    panel.update(cx, |panel, cx| panel.step_out(cx));
+                }
+            })
+            .register_action(|workspace, _: &Pause, cx| {
+                if let Some(panel) = workspace.panel::<DebugPanel>(cx) {
+                    panel.update(cx, |panel, cx| panel.pause(cx));
+                }
+            })
+            .register_action(|workspace, _: &Stop, cx| {
+                if let Some(panel) = workspace.panel::<DebugPanel>(cx) {
+                    panel.update(cx, |panel, cx| panel.stop(cx));
+                }
+            });
+    })
+    .detach();
+}
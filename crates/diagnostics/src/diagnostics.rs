@@ -134,6 +134,9 @@ impl ProjectDiagnosticsEditor {
                 project::Event::DiskBasedDiagnosticsStarted { .. } => {
                     cx.notify();
                 }
+                project::Event::LanguageServerAdded(..) => {
+                    this.pull_workspace_diagnostics(cx);
+                }
                 project::Event::DiskBasedDiagnosticsFinished { language_server_id } => {
                     log::debug!("disk based diagnostics finished for server {language_server_id}");
                     this.update_stale_excerpts(cx);
@@ -205,9 +208,18 @@ impl ProjectDiagnosticsEditor {
             _subscription: project_event_subscription,
         };
         this.update_all_excerpts(cx);
+        this.pull_workspace_diagnostics(cx);
         this
     }
 
+    /// Asks the language servers supporting pull diagnostics for the diagnostics of the files that are not open,
+    /// the pushed ones are reported without asking.
+    fn pull_workspace_diagnostics(&mut self, cx: &mut ViewContext<Self>) {
+        self.project
+            .update(cx, |project, cx| project.pull_workspace_diagnostics(cx))
+            .detach_and_log_err(cx);
+    }
+
     fn update_stale_excerpts(&mut self, cx: &mut ViewContext<Self>) {
         if self.update_excerpts_task.is_some() {
             return;
@@ -211,6 +211,9 @@ impl_actions!(
 gpui::actions!(
     editor,
     [
+        AcceptConflictBoth,
+        AcceptConflictOurs,
+        AcceptConflictTheirs,
         AcceptInlineCompletion,
         AcceptPartialCopilotSuggestion,
         AcceptPartialInlineCompletion,
@@ -312,6 +315,7 @@ gpui::actions!(
         OpenExcerptsSplit,
         OpenProposedChangesEditor,
         OpenFile,
+        OpenMergeView,
         OpenDocs,
         OpenPermalinkToLine,
         OpenUrl,
@@ -356,16 +360,21 @@ gpui::actions!(
         SelectToStartOfParagraph,
         SelectUp,
         ShowCharacterPalette,
+        ShowFileHistory,
+        ShowIncomingCalls,
         ShowInlineCompletion,
+        ShowOutgoingCalls,
         ShowSignatureHelp,
         ShuffleLines,
         SortLinesCaseInsensitive,
         SortLinesCaseSensitive,
         SplitSelectionIntoLines,
+        StageSelectedHunks,
         SwitchSourceHeader,
         Tab,
         TabPrev,
         ToggleAutoSignatureHelp,
+        ToggleBreakpoint,
         ToggleGitBlame,
         ToggleGitBlameInline,
         ToggleHunkDiff,
@@ -385,6 +394,7 @@ gpui::actions!(
         UnfoldRecursive,
         UniqueLinesCaseInsensitive,
         UniqueLinesCaseSensitive,
+        UnstageSelectedHunks,
     ]
 );
 
@@ -0,0 +1,88 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::Path,
+    sync::Arc,
+};
+
+use gpui::{AppContext, Context as _, EventEmitter, Global, Model, ModelContext};
+use util::ResultExt as _;
+
+use crate::persistence::DB;
+
+/// The breakpoints of all files, kept by absolute path and 0-based row so that the debugger
+/// can use them for any file, open or not.
+#[derive(Default)]
+pub struct BreakpointStore {
+    breakpoints: BTreeMap<Arc<Path>, BTreeSet<u32>>,
+}
+
+pub enum BreakpointStoreEvent {
+    BreakpointsChanged(Arc<Path>),
+}
+
+impl EventEmitter<BreakpointStoreEvent> for BreakpointStore {}
+
+struct GlobalBreakpointStore(Model<BreakpointStore>);
+
+impl Global for GlobalBreakpointStore {}
+
+impl BreakpointStore {
+    /// Creates the global store and restores the breakpoints saved by the previous sessions.
+    pub fn init(cx: &mut AppContext) {
+        let store = cx.new_model(|_| BreakpointStore::default());
+        cx.set_global(GlobalBreakpointStore(store.clone()));
+
+        cx.spawn(|mut cx| async move {
+            let breakpoints = cx
+                .background_executor()
+                .spawn(async move { DB.get_breakpoints() })
+                .await?;
+            store.update(&mut cx, |store, cx| {
+                for (path, row) in breakpoints {
+                    store
+                        .breakpoints
+                        .entry(Arc::from(path.as_path()))
+                        .or_default()
+                        .insert(row);
+                }
+                cx.notify();
+            })
+        })
+        .detach_and_log_err(cx);
+    }
+
+    pub fn global(cx: &AppContext) -> Option<Model<Self>> {
+        cx.try_global::<GlobalBreakpointStore>()
+            .map(|store| store.0.clone())
+    }
+
+    /// The rows of the breakpoints in a file.
+    pub fn rows(&self, path: &Path) -> Option<&BTreeSet<u32>> {
+        self.breakpoints.get(path)
+    }
+
+    pub fn breakpoints(&self) -> impl Iterator<Item = (&Arc<Path>, &BTreeSet<u32>)> {
+        self.breakpoints.iter()
+    }
+
+    /// Adds a breakpoint to a row of a file, or removes the breakpoint it already has.
+    pub fn toggle(&mut self, path: Arc<Path>, row: u32, cx: &mut ModelContext<Self>) {
+        let rows = self.breakpoints.entry(path.clone()).or_default();
+        if !rows.remove(&row) {
+            rows.insert(row);
+        }
+        let saved_rows = rows.iter().copied().collect::<Vec<_>>();
+        if saved_rows.is_empty() {
+            self.breakpoints.remove(&path);
+        }
+
+        let saved_path = path.to_path_buf();
+        cx.background_executor()
+            .spawn(async move {
+                DB.save_breakpoints(saved_path, saved_rows).await.log_err();
+            })
+            .detach();
+        cx.emit(BreakpointStoreEvent::BreakpointsChanged(path));
+        cx.notify();
+    }
+}
@@ -0,0 +1,338 @@
+//! Stores the data received from LSP <a href="https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#textDocument_codeLens">textDocument/codeLens</a> requests
+//! and shows it as clickable blocks above the lines the lenses refer to.
+//!
+//! Like semantic tokens, lenses are cached per buffer together with the buffer version they were requested for.
+use std::{sync::Arc, time::Duration};
+
+use crate::{
+    display_map::{BlockPlacement, BlockProperties, BlockStyle, CustomBlockId},
+    rust_analyzer_ext::{self, RUN_SINGLE_COMMAND, SHOW_REFERENCES_COMMAND},
+    scroll::Autoscroll,
+    Editor, EditorMode, EditorSettings, FindAllReferences,
+};
+use clock::Global;
+use collections::{HashMap, HashSet};
+use gpui::{Model, Task, ViewContext};
+use language::{Buffer, OffsetRangeExt as _, Point, ToPoint as _};
+use multi_buffer::ExcerptId;
+use project::{CodeAction, CodeLens, TaskSourceKind};
+use settings::Settings as _;
+use text::BufferId;
+use ui::{prelude::*, Label, LabelSize};
+use util::ResultExt as _;
+
+const CODE_LENS_DEBOUNCE: Duration = Duration::from_millis(250);
+
+#[derive(Default)]
+pub struct CodeLensCache {
+    buffers: HashMap<BufferId, CachedBufferLenses>,
+    block_ids: HashSet<CustomBlockId>,
+    refresh_task: Option<Task<()>>,
+}
+
+struct CachedBufferLenses {
+    version: Global,
+    lenses: Vec<CodeLens>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum CodeLensRefreshReason {
+    /// The language server asked for all lenses to be re-requested.
+    RefreshRequested,
+    /// Some buffers were edited or shown, only query those whose lenses are outdated.
+    BuffersChanged,
+    /// Settings changed: drop the lenses if they got disabled, query the missing ones otherwise.
+    SettingsChange,
+    ExcerptsRemoved,
+}
+
+impl CodeLensCache {
+    fn is_empty(&self) -> bool {
+        self.buffers.is_empty() && self.block_ids.is_empty()
+    }
+}
+
+impl Editor {
+    pub(crate) fn refresh_code_lens(
+        &mut self,
+        reason: CodeLensRefreshReason,
+        cx: &mut ViewContext<Self>,
+    ) {
+        if self.mode != EditorMode::Full || self.semantics_provider.is_none() {
+            return;
+        }
+        if !EditorSettings::get_global(cx).code_lens {
+            if !self.code_lens_cache.is_empty() {
+                self.code_lens_cache.buffers.clear();
+                self.code_lens_cache.refresh_task = None;
+                self.update_code_lens_blocks(cx);
+            }
+            return;
+        }
+
+        match reason {
+            CodeLensRefreshReason::ExcerptsRemoved => {
+                let multi_buffer = self.buffer.read(cx);
+                self.code_lens_cache
+                    .buffers
+                    .retain(|buffer_id, _| multi_buffer.buffer(*buffer_id).is_some());
+                self.update_code_lens_blocks(cx);
+                return;
+            }
+            CodeLensRefreshReason::RefreshRequested
+            | CodeLensRefreshReason::BuffersChanged
+            | CodeLensRefreshReason::SettingsChange => {}
+        }
+
+        let invalidate = reason == CodeLensRefreshReason::RefreshRequested;
+        let mut queried_buffers = HashSet::default();
+        let buffers_to_query = self
+            .excerpts_for_inlay_hints_query(None, cx)
+            .into_values()
+            .filter(|(buffer, version, _)| {
+                let buffer_id = buffer.read(cx).remote_id();
+                queried_buffers.insert(buffer_id)
+                    && (invalidate
+                        || self
+                            .code_lens_cache
+                            .buffers
+                            .get(&buffer_id)
+                            .map_or(true, |cached| cached.version != *version))
+            })
+            .map(|(buffer, version, _)| (buffer, version))
+            .collect::<Vec<_>>();
+        if buffers_to_query.is_empty() {
+            return;
+        }
+
+        self.code_lens_cache.refresh_task = Some(cx.spawn(|editor, mut cx| async move {
+            cx.background_executor().timer(CODE_LENS_DEBOUNCE).await;
+
+            let Some(tasks) = editor
+                .update(&mut cx, |editor, cx| {
+                    let provider = editor.semantics_provider.clone()?;
+                    Some(
+                        buffers_to_query
+                            .into_iter()
+                            .filter_map(|(buffer, version)| {
+                                let buffer_id = buffer.read(cx).remote_id();
+                                let task = provider.code_lens(buffer, cx)?;
+                                Some((buffer_id, version, task))
+                            })
+                            .collect::<Vec<_>>(),
+                    )
+                })
+                .ok()
+                .flatten()
+            else {
+                return;
+            };
+
+            let mut fetched = Vec::with_capacity(tasks.len());
+            for (buffer_id, version, task) in tasks {
+                if let Some(lenses) = task.await.log_err() {
+                    fetched.push((buffer_id, CachedBufferLenses { version, lenses }));
+                }
+            }
+
+            editor
+                .update(&mut cx, |editor, cx| {
+                    editor.code_lens_cache.buffers.extend(fetched);
+                    editor.update_code_lens_blocks(cx);
+                })
+                .ok();
+        }));
+    }
+
+    /// Replaces all code lens blocks with new ones, one per line that has lenses with a command in a visible excerpt.
+    fn update_code_lens_blocks(&mut self, cx: &mut ViewContext<Self>) {
+        let old_block_ids = std::mem::take(&mut self.code_lens_cache.block_ids);
+        if !old_block_ids.is_empty() {
+            self.remove_blocks(old_block_ids, None, cx);
+        }
+
+        let multi_buffer = self.buffer.read(cx);
+        let multi_buffer_snapshot = multi_buffer.snapshot(cx);
+        let editor = cx.view().clone();
+        let mut blocks = Vec::new();
+        for (excerpt_id, buffer_snapshot, excerpt_range) in multi_buffer_snapshot.excerpts() {
+            let buffer_id = buffer_snapshot.remote_id();
+            let (Some(cached), Some(buffer)) = (
+                self.code_lens_cache.buffers.get(&buffer_id),
+                multi_buffer.buffer(buffer_id),
+            ) else {
+                continue;
+            };
+            let context = excerpt_range.context.to_point(buffer_snapshot);
+
+            let mut lenses_by_row = Vec::<(u32, Vec<CodeLens>)>::new();
+            for lens in &cached.lenses {
+                if lens.lsp_lens.command.is_none() {
+                    continue;
+                }
+                let start = lens.range.start.to_point(buffer_snapshot);
+                if start < context.start || start > context.end {
+                    continue;
+                }
+                match lenses_by_row.iter_mut().find(|(row, _)| *row == start.row) {
+                    Some((_, lenses)) => lenses.push(lens.clone()),
+                    None => lenses_by_row.push((start.row, vec![lens.clone()])),
+                }
+            }
+
+            for (row, lenses) in lenses_by_row {
+                // Anchor the block at the first non-whitespace character so that it is aligned with the item.
+                let indent = buffer_snapshot.indent_size_for_line(row).len;
+                let Some(position) = multi_buffer_snapshot.anchor_in_excerpt(
+                    excerpt_id,
+                    buffer_snapshot.anchor_before(Point::new(row, indent)),
+                ) else {
+                    continue;
+                };
+                let editor = editor.clone();
+                let buffer = buffer.clone();
+                blocks.push(BlockProperties {
+                    placement: BlockPlacement::Above(position),
+                    height: 1,
+                    style: BlockStyle::Flex,
+                    priority: 0,
+                    render: Arc::new(move |cx| {
+                        h_flex()
+                            .id(cx.block_id)
+                            .h(cx.line_height)
+                            .pl(cx.anchor_x)
+                            .gap_1()
+                            .children(lenses.iter().enumerate().map(|(ix, lens)| {
+                                let title = lens.title().unwrap_or_default().to_string();
+                                h_flex()
+                                    .gap_1()
+                                    .when(ix > 0, |this| {
+                                        this.child(
+                                            Label::new("|")
+                                                .size(LabelSize::Small)
+                                                .color(Color::Disabled),
+                                        )
+                                    })
+                                    .child(
+                                        div()
+                                            .id(ix)
+                                            .cursor_pointer()
+                                            .child(
+                                                Label::new(title)
+                                                    .size(LabelSize::Small)
+                                                    .color(Color::Muted),
+                                            )
+                                            .on_click({
+                                                let editor = editor.clone();
+                                                let buffer = buffer.clone();
+                                                let lens = lens.clone();
+                                                move |_, cx| {
+                                                    editor.update(cx, |editor, cx| {
+                                                        editor.execute_code_lens(
+                                                            excerpt_id,
+                                                            buffer.clone(),
+                                                            lens.clone(),
+                                                            cx,
+                                                        );
+                                                    });
+                                                }
+                                            }),
+                                    )
+                            }))
+                            .into_any_element()
+                    }),
+                });
+            }
+        }
+
+        if !blocks.is_empty() {
+            self.code_lens_cache.block_ids =
+                self.insert_blocks(blocks, None, cx).into_iter().collect();
+        }
+    }
+
+    fn execute_code_lens(
+        &mut self,
+        excerpt_id: ExcerptId,
+        buffer: Model<Buffer>,
+        lens: CodeLens,
+        cx: &mut ViewContext<Self>,
+    ) {
+        let Some(command) = lens.lsp_lens.command.clone() else {
+            return;
+        };
+        let Some(project) = self.project.clone() else {
+            return;
+        };
+        let Some(workspace) = self.workspace() else {
+            return;
+        };
+
+        match command.command.as_str() {
+            SHOW_REFERENCES_COMMAND | "editor.action.showReferences" => {
+                let Some(position) = self
+                    .buffer
+                    .read(cx)
+                    .snapshot(cx)
+                    .anchor_in_excerpt(excerpt_id, lens.range.start)
+                else {
+                    return;
+                };
+                self.change_selections(Some(Autoscroll::fit()), cx, |selections| {
+                    selections.select_anchor_ranges([position..position])
+                });
+                if let Some(task) = self.find_all_references(&FindAllReferences, cx) {
+                    task.detach_and_log_err(cx);
+                }
+            }
+            RUN_SINGLE_COMMAND => {
+                let Some(resolved_task) = rust_analyzer_ext::runnable_task(&command) else {
+                    log::error!(
+                        "failed to parse the runnable of code lens {:?}",
+                        lens.title()
+                    );
+                    return;
+                };
+                workspace.update(cx, |workspace, cx| {
+                    workspace::tasks::schedule_resolved_task(
+                        workspace,
+                        TaskSourceKind::Language {
+                            name: "Rust".into(),
+                        },
+                        resolved_task,
+                        false,
+                        cx,
+                    );
+                });
+            }
+            _ => {
+                let title = command.title.clone();
+                let action = CodeAction {
+                    server_id: lens.server_id,
+                    range: lens.range.clone(),
+                    lsp_action: lsp::CodeAction {
+                        title: title.clone(),
+                        command: Some(command),
+                        ..lsp::CodeAction::default()
+                    },
+                };
+                let apply_code_action = project.update(cx, |project, cx| {
+                    project.apply_code_action(buffer, action, true, cx)
+                });
+                let workspace = workspace.downgrade();
+                cx.spawn(|editor, cx| async move {
+                    let project_transaction = apply_code_action.await?;
+                    Self::open_project_transaction(
+                        &editor,
+                        workspace,
+                        project_transaction,
+                        title,
+                        cx,
+                    )
+                    .await
+                })
+                .detach_and_log_err(cx);
+            }
+        }
+    }
+}
@@ -8,6 +8,8 @@
 //! * [`display_map`] - chunks up text in the editor into the logical blocks, establishes coordinates and mapping between each of them.
 //!   Contains all metadata related to text transformations (folds, fake inlay text insertions, soft wraps, tab markup, etc.).
 //! * [`inlay_hint_cache`] - is a storage of inlay hints out of LSP requests, responsible for querying LSP and updating `display_map`'s state accordingly.
+//! * [`semantic_tokens`] - is a storage of LSP semantic tokens, layered over the tree-sitter highlights through `display_map`.
+//! * [`code_lens`] - is a storage of LSP code lenses, shown as clickable blocks above the lines they refer to.
 //!
 //! All other submodules and structs are mostly concerned with holding editor data about the way it displays current buffer region(s).
 //!
@@ -15,8 +17,10 @@
 pub mod actions;
 mod blame_entry_tooltip;
 mod blink_manager;
+mod breakpoints;
 mod clangd_ext;
 mod code_context_menus;
+mod code_lens;
 pub mod display_map;
 mod editor_settings;
 mod editor_settings_controls;
@@ -38,6 +42,7 @@ mod proposed_changes_editor;
 mod rust_analyzer_ext;
 pub mod scroll;
 mod selections_collection;
+mod semantic_tokens;
 pub mod tasks;
 
 #[cfg(test)]
@@ -48,12 +53,13 @@ mod signature_help;
 #[cfg(any(test, feature = "test-support"))]
 pub mod test;
 
-use ::git::diff::DiffHunkStatus;
+use ::git::{diff::DiffHunkStatus, repository::DiffBase};
 pub(crate) use actions::*;
 pub use actions::{OpenExcerpts, OpenExcerptsSplit};
 use aho_corasick::AhoCorasick;
 use anyhow::{anyhow, Context as _, Result};
 use blink_manager::BlinkManager;
+pub use breakpoints::{BreakpointStore, BreakpointStoreEvent};
 use client::{Collaborator, ParticipantIndex};
 use clock::ReplicaId;
 use collections::{BTreeMap, Bound, HashMap, HashSet, VecDeque};
@@ -75,7 +81,11 @@ use code_context_menus::{
     AvailableCodeAction, CodeActionContents, CodeActionsItem, CodeActionsMenu, CodeContextMenu,
     CompletionEntry, CompletionsMenu, ContextMenuOrigin,
 };
+use code_lens::{CodeLensCache, CodeLensRefreshReason};
 use git::blame::GitBlame;
+pub use git::commit_diff::CommitDiffEditor;
+use git::merge_conflicts::MergeConflicts;
+pub use git::merge_view::MergeView;
 use gpui::{
     div, impl_actions, point, prelude::*, px, relative, size, Action, AnyElement, AppContext,
     AsyncWindowContext, AvailableSpace, Bounds, ClipboardEntry, ClipboardItem, Context,
@@ -108,6 +118,7 @@ use mouse_context_menu::MouseContextMenu;
 pub use proposed_changes_editor::{
     ProposedChangeLocation, ProposedChangesEditor, ProposedChangesEditorToolbar,
 };
+use semantic_tokens::{SemanticTokensCache, SemanticTokensRefreshReason};
 use similar::{ChangeTag, TextDiff};
 use std::iter::Peekable;
 use task::{ResolvedTask, TaskTemplate, TaskVariables};
@@ -131,8 +142,9 @@ use project::{
     buffer_store::BufferChangeSet,
     lsp_store::{FormatTarget, FormatTrigger, OpenLspBufferHandle},
     project_settings::{GitGutterSetting, ProjectSettings},
-    CodeAction, Completion, CompletionIntent, DocumentHighlight, InlayHint, Location, LocationLink,
-    LspStore, Project, ProjectItem, ProjectTransaction, TaskSourceKind,
+    CodeAction, CodeLens, Completion, CompletionIntent, DocumentHighlight, InlayHint, Location,
+    LocationLink, LspStore, Project, ProjectItem, ProjectTransaction, SemanticTokens,
+    TaskSourceKind,
 };
 use rand::prelude::*;
 use rpc::{proto::*, ErrorExt};
@@ -299,6 +311,7 @@ pub fn init_settings(cx: &mut AppContext) {
 
 pub fn init(cx: &mut AppContext) {
     init_settings(cx);
+    BreakpointStore::init(cx);
 
     workspace::register_project_item::<Editor>(cx);
     workspace::FollowableViewRegistry::register::<Editor>(cx);
@@ -658,6 +671,9 @@ pub struct Editor {
     enable_inline_completions: bool,
     show_inline_completions_override: Option<bool>,
     inlay_hint_cache: InlayHintCache,
+    semantic_tokens_cache: SemanticTokensCache,
+    code_lens_cache: CodeLensCache,
+    merge_conflicts: MergeConflicts,
     diff_map: DiffMap,
     next_inlay_id: usize,
     _subscriptions: Vec<Subscription>,
@@ -948,13 +964,26 @@ pub struct ClipboardSelection {
 }
 
 #[derive(Debug)]
-pub(crate) struct NavigationData {
+pub struct NavigationData {
     cursor_anchor: Anchor,
     cursor_position: Point,
     scroll_anchor: ScrollAnchor,
     scroll_top_row: u32,
 }
 
+impl NavigationData {
+    /// Where the cursor was when this entry was added to the navigation history, in the
+    /// editor's buffer.
+    pub fn cursor_anchor(&self) -> Anchor {
+        self.cursor_anchor
+    }
+
+    /// Where the cursor was when this entry was added, for when the editor no longer exists.
+    pub fn cursor_position(&self) -> Point {
+        self.cursor_position
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum GotoDefinitionKind {
     Symbol,
@@ -1157,6 +1186,20 @@ impl Editor {
                 project_subscriptions.push(cx.subscribe(project, |editor, _, event, cx| {
                     if let project::Event::RefreshInlayHints = event {
                         editor.refresh_inlay_hints(InlayHintRefreshReason::RefreshRequested, cx);
+                    } else if let project::Event::RefreshSemanticTokens
+                    | project::Event::LanguageServerAdded(..) = event
+                    {
+                        editor.refresh_semantic_tokens(
+                            SemanticTokensRefreshReason::RefreshRequested,
+                            cx,
+                        );
+                        if let project::Event::LanguageServerAdded(..) = event {
+                            editor.refresh_code_lens(CodeLensRefreshReason::RefreshRequested, cx);
+                        }
+                    } else if let project::Event::RefreshCodeLens = event {
+                        editor.refresh_code_lens(CodeLensRefreshReason::RefreshRequested, cx);
+                    } else if let project::Event::WorktreeUpdatedGitRepositories(_) = event {
+                        editor.refresh_merge_conflicts(cx);
                     } else if let project::Event::SnippetEdit(id, snippet_edits) = event {
                         if let Some(buffer) = editor.buffer.read(cx).buffer(*id) {
                             let focus_handle = editor.focus_handle(cx);
@@ -1185,6 +1228,9 @@ impl Editor {
                     }));
                 }
             }
+            if let Some(breakpoint_store) = BreakpointStore::global(cx) {
+                project_subscriptions.push(cx.observe(&breakpoint_store, |_, _, cx| cx.notify()));
+            }
         }
 
         let buffer_snapshot = buffer.read(cx).snapshot(cx);
@@ -1291,6 +1337,9 @@ impl Editor {
             inline_completion_provider: None,
             active_inline_completion: None,
             inlay_hint_cache: InlayHintCache::new(inlay_hint_settings),
+            semantic_tokens_cache: SemanticTokensCache::default(),
+            code_lens_cache: CodeLensCache::default(),
+            merge_conflicts: MergeConflicts::default(),
             diff_map: DiffMap::default(),
             gutter_hovered: false,
             pixel_position_of_newest_cursor: None,
@@ -1360,6 +1409,7 @@ impl Editor {
                 this.git_blame_inline_enabled = true;
                 this.start_git_blame_inline(false, cx);
             }
+            this.refresh_merge_conflicts(cx);
 
             if let Some(buffer) = buffer.read(cx).as_singleton() {
                 if let Some(project) = this.project.as_ref() {
@@ -5092,6 +5142,103 @@ impl Editor {
             }))
     }
 
+    fn render_breakpoint_indicator(
+        &self,
+        row: DisplayRow,
+        path: Arc<Path>,
+        buffer_row: u32,
+        cx: &mut ViewContext<Self>,
+    ) -> IconButton {
+        IconButton::new(
+            ("breakpoint_indicator", row.0 as usize),
+            ui::IconName::DebugBreakpoint,
+        )
+        .shape(ui::IconButtonShape::Square)
+        .icon_size(IconSize::XSmall)
+        .icon_color(Color::Error)
+        .on_click(cx.listener(move |_, _e, cx| {
+            if let Some(store) = BreakpointStore::global(cx) {
+                store.update(cx, |store, cx| store.toggle(path.clone(), buffer_row, cx));
+            }
+        }))
+    }
+
+    /// The absolute path and row of each row in the given display rows that has a breakpoint,
+    /// by display row.
+    fn breakpoint_rows(
+        &self,
+        range: Range<DisplayRow>,
+        snapshot: &EditorSnapshot,
+        cx: &AppContext,
+    ) -> Vec<(DisplayRow, Arc<Path>, u32)> {
+        let Some(store) = BreakpointStore::global(cx) else {
+            return Vec::new();
+        };
+        let store = store.read(cx);
+        let mut paths = HashMap::<BufferId, Option<Arc<Path>>>::default();
+        let mut breakpoint_rows = Vec::new();
+        let mut previous_row = None;
+        for display_row in range.start.0..range.end.0 {
+            let display_row = DisplayRow(display_row);
+            let point = DisplayPoint::new(display_row, 0).to_point(&snapshot.display_snapshot);
+            // Soft wrapped lines only get a breakpoint on their first display row.
+            if previous_row.replace(point.row) == Some(point.row) {
+                continue;
+            }
+            let Some((buffer, range)) = snapshot
+                .buffer_snapshot
+                .buffer_line_for_row(MultiBufferRow(point.row))
+            else {
+                continue;
+            };
+            let path = paths.entry(buffer.remote_id()).or_insert_with(|| {
+                let abs_path = buffer.file()?.as_local()?.abs_path(cx);
+                Some(Arc::from(abs_path.as_path()))
+            });
+            if let Some(path) = path {
+                if store
+                    .rows(path)
+                    .map_or(false, |rows| rows.contains(&range.start.row))
+                {
+                    breakpoint_rows.push((display_row, path.clone(), range.start.row));
+                }
+            }
+        }
+        breakpoint_rows
+    }
+
+    pub fn toggle_breakpoint(&mut self, _: &ToggleBreakpoint, cx: &mut ViewContext<Self>) {
+        let Some(store) = BreakpointStore::global(cx) else {
+            return;
+        };
+        let mut breakpoints = Vec::new();
+        for selection in self.selections.all::<Point>(cx) {
+            let Some((buffer, point, _)) = self
+                .buffer
+                .read(cx)
+                .point_to_buffer_point(selection.head(), cx)
+            else {
+                continue;
+            };
+            let Some(abs_path) = buffer
+                .read(cx)
+                .file()
+                .and_then(|file| Some(file.as_local()?.abs_path(cx)))
+            else {
+                continue;
+            };
+            let breakpoint = (Arc::<Path>::from(abs_path.as_path()), point.row);
+            if !breakpoints.contains(&breakpoint) {
+                breakpoints.push(breakpoint);
+            }
+        }
+        store.update(cx, |store, cx| {
+            for (path, row) in breakpoints {
+                store.toggle(path, row, cx);
+            }
+        });
+    }
+
     #[cfg(any(feature = "test-support", test))]
     pub fn context_menu_visible(&self) -> bool {
         self.context_menu
@@ -5989,6 +6136,120 @@ impl Editor {
         }
     }
 
+    pub fn stage_selected_hunks(&mut self, _: &StageSelectedHunks, cx: &mut ViewContext<Self>) {
+        let snapshot = self.snapshot(cx);
+        let hunks = hunks_for_selections(&snapshot, &self.selections.all(cx));
+        self.stage_hunks(hunks, cx);
+    }
+
+    fn stage_hunk(&mut self, hunk: HoveredHunk, cx: &mut ViewContext<Editor>) {
+        let snapshot = self.buffer.read(cx).read(cx);
+        if let Some(hunk) = crate::hunk_diff::to_diff_hunk(&hunk, &snapshot) {
+            drop(snapshot);
+            self.stage_hunks(Some(hunk), cx);
+        }
+    }
+
+    /// Writes the given hunks' buffer contents into the git index, leaving the rest of the
+    /// index text untouched. Files that are not in the index yet are staged as a whole.
+    fn stage_hunks(
+        &mut self,
+        hunks: impl IntoIterator<Item = MultiBufferDiffHunk>,
+        cx: &mut ViewContext<Editor>,
+    ) {
+        let Some(project) = self.project.clone() else {
+            return;
+        };
+        // The hunks are only relative to the index while diffing against it.
+        if *project.read(cx).diff_base(cx) != DiffBase::Index {
+            Task::<Result<()>>::ready(Err(anyhow!(
+                "Hunks can only be staged while diffing against the index"
+            )))
+            .detach_and_notify_err(cx);
+            return;
+        }
+        let mut hunks_by_buffer = HashMap::<BufferId, Vec<MultiBufferDiffHunk>>::default();
+        for hunk in hunks {
+            hunks_by_buffer
+                .entry(hunk.buffer_id)
+                .or_default()
+                .push(hunk);
+        }
+
+        for (buffer_id, mut hunks) in hunks_by_buffer {
+            let Some(buffer) = self.buffer.read(cx).buffer(buffer_id) else {
+                continue;
+            };
+            let Some(project_path) = buffer.read(cx).project_path(cx) else {
+                continue;
+            };
+            let base_text = self
+                .diff_map
+                .diff_bases
+                .get(&buffer_id)
+                .and_then(|diff_base| diff_base.change_set.read(cx).base_text.clone());
+            let task = match base_text {
+                Some(base_text) => {
+                    let buffer_snapshot = buffer.read(cx).snapshot();
+                    let mut index_text = base_text.read(cx).as_rope().clone();
+                    hunks.sort_by_key(|hunk| hunk.diff_base_byte_range.start);
+                    hunks.dedup_by_key(|hunk| hunk.diff_base_byte_range.start);
+                    for hunk in hunks.iter().rev() {
+                        let new_text = buffer_snapshot
+                            .text_for_range(hunk.buffer_range.clone())
+                            .collect::<String>();
+                        index_text.replace(hunk.diff_base_byte_range.clone(), &new_text);
+                    }
+                    project
+                        .read(cx)
+                        .set_index_text(project_path, Some(index_text.to_string()), cx)
+                }
+                None => project.read(cx).stage_paths(
+                    project_path.worktree_id,
+                    vec![project_path.path],
+                    cx,
+                ),
+            };
+            task.detach_and_notify_err(cx);
+        }
+    }
+
+    /// Resets the staged changes overlapping the selected hunks back to HEAD, which the hunks
+    /// must be relative to.
+    pub fn unstage_selected_hunks(&mut self, _: &UnstageSelectedHunks, cx: &mut ViewContext<Self>) {
+        let Some(project) = self.project.clone() else {
+            return;
+        };
+        if *project.read(cx).diff_base(cx) != DiffBase::Head {
+            Task::<Result<()>>::ready(Err(anyhow!(
+                "Hunks can only be unstaged while diffing against HEAD"
+            )))
+            .detach_and_notify_err(cx);
+            return;
+        }
+        let snapshot = self.snapshot(cx);
+        let mut head_ranges_by_buffer = HashMap::<BufferId, Vec<Range<usize>>>::default();
+        for hunk in hunks_for_selections(&snapshot, &self.selections.all(cx)) {
+            head_ranges_by_buffer
+                .entry(hunk.buffer_id)
+                .or_default()
+                .push(hunk.diff_base_byte_range);
+        }
+
+        for (buffer_id, head_ranges) in head_ranges_by_buffer {
+            let Some(buffer) = self.buffer.read(cx).buffer(buffer_id) else {
+                continue;
+            };
+            let Some(project_path) = buffer.read(cx).project_path(cx) else {
+                continue;
+            };
+            project
+                .read(cx)
+                .unstage_hunks(project_path, head_ranges, cx)
+                .detach_and_notify_err(cx);
+        }
+    }
+
     pub fn open_active_item_in_terminal(&mut self, _: &OpenInTerminal, cx: &mut ViewContext<Self>) {
         if let Some(working_directory) = self.active_excerpt(cx).and_then(|(_, buffer, _)| {
             let project_path = buffer.read(cx).project_path(cx)?;
@@ -12260,6 +12521,9 @@ impl Editor {
                         }
                     }
                 }
+                self.refresh_semantic_tokens(SemanticTokensRefreshReason::BuffersChanged, cx);
+                self.refresh_code_lens(CodeLensRefreshReason::BuffersChanged, cx);
+                self.refresh_merge_conflicts(cx);
 
                 let Some(project) = &self.project else { return };
                 let (telemetry, is_via_ssh) = {
@@ -12289,9 +12553,15 @@ impl Editor {
                     excerpts: excerpts.clone(),
                 });
                 self.refresh_inlay_hints(InlayHintRefreshReason::NewLinesShown, cx);
+                self.refresh_semantic_tokens(SemanticTokensRefreshReason::BuffersChanged, cx);
+                self.refresh_code_lens(CodeLensRefreshReason::BuffersChanged, cx);
+                self.refresh_merge_conflicts(cx);
             }
             multi_buffer::Event::ExcerptsRemoved { ids } => {
                 self.refresh_inlay_hints(InlayHintRefreshReason::ExcerptsRemoved(ids.clone()), cx);
+                self.refresh_semantic_tokens(SemanticTokensRefreshReason::ExcerptsRemoved, cx);
+                self.refresh_code_lens(CodeLensRefreshReason::ExcerptsRemoved, cx);
+                self.refresh_merge_conflicts(cx);
                 let buffer = self.buffer.read(cx);
                 self.registered_buffers
                     .retain(|buffer_id, _| buffer.buffer(*buffer_id).is_some());
@@ -12348,6 +12618,8 @@ impl Editor {
             )),
             cx,
         );
+        self.refresh_semantic_tokens(SemanticTokensRefreshReason::SettingsChange, cx);
+        self.refresh_code_lens(CodeLensRefreshReason::SettingsChange, cx);
 
         let old_cursor_shape = self.cursor_shape;
 
@@ -13402,6 +13674,18 @@ pub trait SemanticsProvider {
 
     fn supports_inlay_hints(&self, buffer: &Model<Buffer>, cx: &AppContext) -> bool;
 
+    fn semantic_tokens(
+        &self,
+        buffer_handle: Model<Buffer>,
+        cx: &mut AppContext,
+    ) -> Option<Task<anyhow::Result<SemanticTokens>>>;
+
+    fn code_lens(
+        &self,
+        buffer_handle: Model<Buffer>,
+        cx: &mut AppContext,
+    ) -> Option<Task<anyhow::Result<Vec<CodeLens>>>>;
+
     fn document_highlights(
         &self,
         buffer: &Model<Buffer>,
@@ -13815,6 +14099,22 @@ impl SemanticsProvider for Model<Project> {
         }))
     }
 
+    fn semantic_tokens(
+        &self,
+        buffer_handle: Model<Buffer>,
+        cx: &mut AppContext,
+    ) -> Option<Task<anyhow::Result<SemanticTokens>>> {
+        Some(self.update(cx, |project, cx| project.semantic_tokens(buffer_handle, cx)))
+    }
+
+    fn code_lens(
+        &self,
+        buffer_handle: Model<Buffer>,
+        cx: &mut AppContext,
+    ) -> Option<Task<anyhow::Result<Vec<CodeLens>>>> {
+        Some(self.update(cx, |project, cx| project.code_lens(&buffer_handle, cx)))
+    }
+
     fn range_for_rename(
         &self,
         buffer: &Model<Buffer>,
@@ -10,6 +10,8 @@ pub struct EditorSettings {
     pub cursor_shape: Option<CursorShape>,
     pub current_line_highlight: CurrentLineHighlight,
     pub lsp_highlight_debounce: u64,
+    pub semantic_tokens: bool,
+    pub code_lens: bool,
     pub hover_popover_enabled: bool,
     pub hover_popover_delay: u64,
     pub toolbar: Toolbar,
@@ -210,6 +212,16 @@ pub struct EditorSettingsContent {
     ///
     /// Default: 75
     pub lsp_highlight_debounce: Option<u64>,
+    /// Whether to highlight code with the semantic tokens reported by
+    /// language servers, on top of the tree-sitter highlights.
+    ///
+    /// Default: true
+    pub semantic_tokens: Option<bool>,
+    /// Whether to show the code lenses reported by language servers,
+    /// e.g. reference counts or "Run" buttons, above the items they refer to.
+    ///
+    /// Default: true
+    pub code_lens: Option<bool>,
     /// Whether to show the informational hover box when moving the mouse
     /// over symbols in the editor.
     ///
@@ -65,6 +65,7 @@ use std::{
     fmt::{self, Write},
     iter, mem,
     ops::{Deref, Range},
+    path::Path,
     rc::Rc,
     sync::Arc,
 };
@@ -369,6 +370,7 @@ impl EditorElement {
         register_action(view, cx, Editor::toggle_relative_line_numbers);
         register_action(view, cx, Editor::toggle_indent_guides);
         register_action(view, cx, Editor::toggle_inlay_hints);
+        register_action(view, cx, Editor::toggle_breakpoint);
         register_action(view, cx, Editor::toggle_inline_completions);
         register_action(view, cx, hover_popover::hover);
         register_action(view, cx, Editor::reveal_in_finder);
@@ -381,6 +383,10 @@ impl EditorElement {
         register_action(view, cx, Editor::toggle_git_blame);
         register_action(view, cx, Editor::toggle_git_blame_inline);
         register_action(view, cx, Editor::toggle_hunk_diff);
+        register_action(view, cx, Editor::accept_conflict_ours);
+        register_action(view, cx, Editor::accept_conflict_theirs);
+        register_action(view, cx, Editor::accept_conflict_both);
+        register_action(view, cx, Editor::open_merge_view);
         register_action(view, cx, Editor::expand_all_hunk_diffs);
         register_action(view, cx, |editor, action, cx| {
             if let Some(task) = editor.format(action, cx) {
@@ -456,6 +462,8 @@ impl EditorElement {
         register_action(view, cx, Editor::accept_inline_completion);
         register_action(view, cx, Editor::revert_file);
         register_action(view, cx, Editor::revert_selected_hunks);
+        register_action(view, cx, Editor::stage_selected_hunks);
+        register_action(view, cx, Editor::unstage_selected_hunks);
         register_action(view, cx, Editor::apply_all_diff_hunks);
         register_action(view, cx, Editor::apply_selected_diff_hunks);
         register_action(view, cx, Editor::open_active_item_in_terminal);
@@ -1793,6 +1801,7 @@ impl EditorElement {
         gutter_dimensions: &GutterDimensions,
         gutter_hitbox: &Hitbox,
         rows_with_hunk_bounds: &HashMap<DisplayRow, Bounds<Pixels>>,
+        breakpoint_rows: &[(DisplayRow, Arc<Path>, u32)],
         snapshot: &EditorSnapshot,
         cx: &mut WindowContext,
     ) -> Vec<AnyElement> {
@@ -1852,6 +1861,10 @@ impl EditorElement {
                         }
                     }
                     let display_row = multibuffer_point.to_display_point(snapshot).row();
+                    // Breakpoints are shown in place of the run indicators.
+                    if breakpoint_rows.iter().any(|(row, ..)| *row == display_row) {
+                        return None;
+                    }
                     let button = editor.render_run_indicator(
                         &self.style,
                         Some(display_row) == active_task_indicator_row,
@@ -1875,6 +1888,38 @@ impl EditorElement {
         })
     }
 
+    #[allow(clippy::too_many_arguments)]
+    fn layout_breakpoint_indicators(
+        &self,
+        breakpoint_rows: Vec<(DisplayRow, Arc<Path>, u32)>,
+        line_height: Pixels,
+        scroll_pixel_position: gpui::Point<Pixels>,
+        gutter_dimensions: &GutterDimensions,
+        gutter_hitbox: &Hitbox,
+        rows_with_hunk_bounds: &HashMap<DisplayRow, Bounds<Pixels>>,
+        cx: &mut WindowContext,
+    ) -> Vec<AnyElement> {
+        self.editor.update(cx, |editor, cx| {
+            breakpoint_rows
+                .into_iter()
+                .map(|(display_row, path, buffer_row)| {
+                    let button =
+                        editor.render_breakpoint_indicator(display_row, path, buffer_row, cx);
+                    prepaint_gutter_button(
+                        button,
+                        display_row,
+                        line_height,
+                        gutter_dimensions,
+                        scroll_pixel_position,
+                        gutter_hitbox,
+                        rows_with_hunk_bounds,
+                        cx,
+                    )
+                })
+                .collect_vec()
+        })
+    }
+
     #[allow(clippy::too_many_arguments)]
     fn layout_code_actions_indicator(
         &self,
@@ -4029,6 +4074,10 @@ impl EditorElement {
                 test_indicator.paint(cx);
             }
 
+            for breakpoint_indicator in layout.breakpoint_indicators.iter_mut() {
+                breakpoint_indicator.paint(cx);
+            }
+
             if let Some(indicator) = layout.code_actions_indicator.as_mut() {
                 indicator.paint(cx);
             }
@@ -6485,6 +6534,14 @@ impl Element for EditorElement {
                         }
                     }
 
+                    let breakpoint_rows = if snapshot.mode == EditorMode::Full {
+                        self.editor
+                            .read(cx)
+                            .breakpoint_rows(start_row..end_row, &snapshot, cx)
+                    } else {
+                        Vec::new()
+                    };
+
                     let test_indicators = if gutter_settings.runnables {
                         self.layout_run_indicators(
                             line_height,
@@ -6493,6 +6550,7 @@ impl Element for EditorElement {
                             &gutter_dimensions,
                             &gutter_hitbox,
                             &rows_with_hunk_bounds,
+                            &breakpoint_rows,
                             &snapshot,
                             cx,
                         )
@@ -6500,6 +6558,16 @@ impl Element for EditorElement {
                         Vec::new()
                     };
 
+                    let breakpoint_indicators = self.layout_breakpoint_indicators(
+                        breakpoint_rows,
+                        line_height,
+                        scroll_pixel_position,
+                        &gutter_dimensions,
+                        &gutter_hitbox,
+                        &rows_with_hunk_bounds,
+                        cx,
+                    );
+
                     self.layout_signature_help(
                         &hitbox,
                         content_origin,
@@ -6630,6 +6698,7 @@ impl Element for EditorElement {
                         inline_completion_popover,
                         mouse_context_menu,
                         test_indicators,
+                        breakpoint_indicators,
                         code_actions_indicator,
                         crease_toggles,
                         crease_trailers,
@@ -6824,6 +6893,7 @@ pub struct EditorLayout {
     selections: Vec<(PlayerColor, Vec<SelectionLayout>)>,
     code_actions_indicator: Option<AnyElement>,
     test_indicators: Vec<AnyElement>,
+    breakpoint_indicators: Vec<AnyElement>,
     crease_toggles: Vec<Option<AnyElement>>,
     crease_trailers: Vec<Option<CreaseTrailerLayout>>,
     inline_completion_popover: Option<AnyElement>,
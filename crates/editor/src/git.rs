@@ -1,2 +1,5 @@
 pub mod blame;
+pub mod commit_diff;
+pub mod merge_conflicts;
+pub mod merge_view;
 pub mod project_diff;
@@ -0,0 +1,383 @@
+//! Shows the changes made by a single git commit, using the same expanded diff hunks
+//! as the [`super::project_diff`] view shows for the uncommitted changes.
+//!
+//! The contents of the changed files are loaded from the repository into read-only buffers that
+//! are not backed by the worktree, so the view stays the same when the working copy changes.
+use std::{
+    any::{Any, TypeId},
+    ffi::OsStr,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use anyhow::Context as _;
+use git::commit::{CommitFileDiff, CommitSummary};
+use gpui::{
+    AnyView, AppContext, EventEmitter, FocusHandle, FocusableView, Model, Render, Task, View,
+};
+use language::{Buffer, BufferRow, Capability, DiskState, LocalFile};
+use multi_buffer::MultiBuffer;
+use project::{buffer_store::BufferChangeSet, Project, ProjectPath, WorktreeId};
+use text::BufferId;
+use theme::ActiveTheme;
+use ui::prelude::*;
+use util::paths::compare_paths;
+use workspace::{
+    item::{BreadcrumbText, Item, ItemEvent, ItemHandle},
+    ItemNavHistory, ToolbarItemLocation, Workspace,
+};
+
+use crate::{Editor, EditorEvent, DEFAULT_MULTIBUFFER_CONTEXT};
+
+pub struct CommitDiffEditor {
+    commit: CommitSummary,
+    excerpts: Model<MultiBuffer>,
+    editor: View<Editor>,
+    state: LoadState,
+    focus_handle: FocusHandle,
+}
+
+enum LoadState {
+    Loading { _task: Task<()> },
+    Loaded { file_count: usize },
+    Failed(SharedString),
+}
+
+impl CommitDiffEditor {
+    /// Opens the changes of a commit of the repository containing `project_path`,
+    /// activating the view already showing that commit if there is one.
+    pub fn deploy(
+        workspace: &mut Workspace,
+        project_path: ProjectPath,
+        commit: CommitSummary,
+        cx: &mut ViewContext<Workspace>,
+    ) {
+        let existing = workspace
+            .items_of_type::<Self>(cx)
+            .find(|item| item.read(cx).commit.sha == commit.sha);
+        if let Some(existing) = existing {
+            workspace.activate_item(&existing, true, true, cx);
+        } else {
+            let project = workspace.project().clone();
+            let commit_diff = cx.new_view(|cx| Self::new(project, project_path, commit, cx));
+            workspace.add_item_to_active_pane(Box::new(commit_diff), None, true, cx);
+        }
+    }
+
+    fn new(
+        project: Model<Project>,
+        project_path: ProjectPath,
+        commit: CommitSummary,
+        cx: &mut ViewContext<Self>,
+    ) -> Self {
+        let excerpts = cx.new_model(|_| MultiBuffer::new(Capability::ReadOnly));
+        let editor = cx.new_view(|cx| {
+            let mut diff_display_editor =
+                Editor::for_multibuffer(excerpts.clone(), Some(project.clone()), true, cx);
+            diff_display_editor.set_expand_all_diff_hunks();
+            diff_display_editor.set_read_only(true);
+            diff_display_editor
+        });
+
+        let load_task = Self::load(project, project_path, commit.sha.to_string(), cx);
+        Self {
+            commit,
+            excerpts,
+            editor,
+            state: LoadState::Loading { _task: load_task },
+            focus_handle: cx.focus_handle(),
+        }
+    }
+
+    fn load(
+        project: Model<Project>,
+        project_path: ProjectPath,
+        sha: String,
+        cx: &mut ViewContext<Self>,
+    ) -> Task<()> {
+        let worktree_id = project_path.worktree_id;
+        let languages = project.read(cx).languages().clone();
+        let files = project.update(cx, |project, cx| {
+            project.git_commit_diff(project_path, sha, cx)
+        });
+        cx.spawn(|this, mut cx| async move {
+            let diffs = async {
+                let mut files = files.await?;
+                files.sort_by(|a, b| {
+                    compare_paths((a.path.as_ref(), true), (b.path.as_ref(), true))
+                });
+
+                let mut diffs = Vec::with_capacity(files.len());
+                for file in files {
+                    let language = languages.language_for_file_path(&file.path).await.ok();
+                    let (buffer, change_set, diff_updated) = this.update(&mut cx, |_, cx| {
+                        let buffer =
+                            Self::build_buffer(&file, worktree_id, languages.clone(), language, cx);
+                        let snapshot = buffer.read(cx).text_snapshot();
+                        let change_set = cx.new_model(|_| BufferChangeSet::new(&snapshot));
+                        let diff_updated = change_set.update(cx, |change_set, cx| {
+                            change_set.set_base_text(
+                                file.old_text.clone().unwrap_or_default(),
+                                snapshot,
+                                cx,
+                            )
+                        });
+                        (buffer, change_set, diff_updated)
+                    })?;
+                    diff_updated.await.ok();
+                    diffs.push((buffer, change_set));
+                }
+                anyhow::Ok(diffs)
+            }
+            .await;
+
+            this.update(&mut cx, |this, cx| {
+                match diffs.context("loading the commit changes") {
+                    Ok(diffs) => this.show_diffs(diffs, cx),
+                    Err(error) => {
+                        log::error!("{error:#}");
+                        this.state = LoadState::Failed(format!("{error:#}").into());
+                    }
+                }
+                cx.emit(EditorEvent::TitleChanged);
+                cx.notify();
+            })
+            .ok();
+        })
+    }
+
+    fn build_buffer(
+        file: &CommitFileDiff,
+        worktree_id: WorktreeId,
+        languages: Arc<language::LanguageRegistry>,
+        language: Option<Arc<language::Language>>,
+        cx: &mut ViewContext<Self>,
+    ) -> Model<Buffer> {
+        let text = file.new_text.clone().unwrap_or_default();
+        let commit_file = Arc::new(CommitFile {
+            path: Arc::from(file.path.as_path()),
+            worktree_id,
+        });
+        cx.new_model(|cx| {
+            let mut buffer = Buffer::build(
+                text::Buffer::new(0, BufferId::from(cx.entity_id().as_non_zero_u64()), text),
+                Some(commit_file),
+                Capability::ReadOnly,
+            );
+            buffer.set_language_registry(languages);
+            buffer.set_language(language, cx);
+            buffer
+        })
+    }
+
+    fn show_diffs(
+        &mut self,
+        diffs: Vec<(Model<Buffer>, Model<BufferChangeSet>)>,
+        cx: &mut ViewContext<Self>,
+    ) {
+        self.state = LoadState::Loaded {
+            file_count: diffs.len(),
+        };
+        self.excerpts.update(cx, |multi_buffer, cx| {
+            for (buffer, change_set) in &diffs {
+                let snapshot = buffer.read(cx).text_snapshot();
+                let hunk_ranges = change_set
+                    .read(cx)
+                    .diff_to_buffer
+                    .hunks_in_row_range(0..BufferRow::MAX, &snapshot)
+                    .map(|hunk| hunk.buffer_range)
+                    .collect();
+                multi_buffer.push_excerpts_with_context_lines(
+                    buffer.clone(),
+                    hunk_ranges,
+                    DEFAULT_MULTIBUFFER_CONTEXT,
+                    cx,
+                );
+            }
+        });
+        self.editor.update(cx, |editor, cx| {
+            for (_, change_set) in diffs {
+                editor.diff_map.add_change_set(change_set, cx);
+            }
+        });
+    }
+
+    fn title(&self) -> String {
+        format!("{} {}", short_sha(&self.commit.sha), self.commit.subject)
+    }
+}
+
+fn short_sha(sha: &str) -> &str {
+    &sha[..sha.len().min(7)]
+}
+
+/// A file as it was after a commit. Deleted files are represented by an empty buffer.
+struct CommitFile {
+    path: Arc<Path>,
+    worktree_id: WorktreeId,
+}
+
+impl language::File for CommitFile {
+    fn as_local(&self) -> Option<&dyn LocalFile> {
+        None
+    }
+
+    fn disk_state(&self) -> DiskState {
+        DiskState::New
+    }
+
+    fn path(&self) -> &Arc<Path> {
+        &self.path
+    }
+
+    fn full_path(&self, _: &AppContext) -> PathBuf {
+        self.path.to_path_buf()
+    }
+
+    fn file_name<'a>(&'a self, _: &'a AppContext) -> &'a OsStr {
+        self.path.file_name().unwrap_or(self.path.as_os_str())
+    }
+
+    fn worktree_id(&self, _: &AppContext) -> WorktreeId {
+        self.worktree_id
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn to_proto(&self, _: &AppContext) -> rpc::proto::File {
+        rpc::proto::File {
+            worktree_id: self.worktree_id.to_proto(),
+            entry_id: None,
+            path: self.path.to_string_lossy().into(),
+            mtime: None,
+            is_deleted: false,
+        }
+    }
+
+    fn is_private(&self) -> bool {
+        false
+    }
+}
+
+impl EventEmitter<EditorEvent> for CommitDiffEditor {}
+
+impl FocusableView for CommitDiffEditor {
+    fn focus_handle(&self, _: &AppContext) -> FocusHandle {
+        self.focus_handle.clone()
+    }
+}
+
+impl Item for CommitDiffEditor {
+    type Event = EditorEvent;
+
+    fn to_item_events(event: &EditorEvent, f: impl FnMut(ItemEvent)) {
+        Editor::to_item_events(event, f)
+    }
+
+    fn deactivated(&mut self, cx: &mut ViewContext<Self>) {
+        self.editor.update(cx, |editor, cx| editor.deactivated(cx));
+    }
+
+    fn navigate(&mut self, data: Box<dyn Any>, cx: &mut ViewContext<Self>) -> bool {
+        self.editor
+            .update(cx, |editor, cx| editor.navigate(data, cx))
+    }
+
+    fn tab_tooltip_text(&self, _: &AppContext) -> Option<SharedString> {
+        Some(
+            format!(
+                "{}\n{} <{}>",
+                self.commit.sha, self.commit.author_name, self.commit.author_email
+            )
+            .into(),
+        )
+    }
+
+    fn tab_content_text(&self, _: &WindowContext) -> Option<SharedString> {
+        Some(self.title().into())
+    }
+
+    fn tab_icon(&self, _: &WindowContext) -> Option<Icon> {
+        Some(Icon::new(IconName::FileGit))
+    }
+
+    fn telemetry_event_text(&self) -> Option<&'static str> {
+        Some("commit diff")
+    }
+
+    fn for_each_project_item(
+        &self,
+        cx: &AppContext,
+        f: &mut dyn FnMut(gpui::EntityId, &dyn project::ProjectItem),
+    ) {
+        self.editor.for_each_project_item(cx, f)
+    }
+
+    fn is_singleton(&self, _: &AppContext) -> bool {
+        false
+    }
+
+    fn set_nav_history(&mut self, nav_history: ItemNavHistory, cx: &mut ViewContext<Self>) {
+        self.editor.update(cx, |editor, _| {
+            editor.set_nav_history(Some(nav_history));
+        });
+    }
+
+    fn act_as_type<'a>(
+        &'a self,
+        type_id: TypeId,
+        self_handle: &'a View<Self>,
+        _: &'a AppContext,
+    ) -> Option<AnyView> {
+        if type_id == TypeId::of::<Self>() {
+            Some(self_handle.to_any())
+        } else if type_id == TypeId::of::<Editor>() {
+            Some(self.editor.to_any())
+        } else {
+            None
+        }
+    }
+
+    fn breadcrumb_location(&self, _: &AppContext) -> ToolbarItemLocation {
+        ToolbarItemLocation::PrimaryLeft
+    }
+
+    fn breadcrumbs(&self, theme: &theme::Theme, cx: &AppContext) -> Option<Vec<BreadcrumbText>> {
+        self.editor.breadcrumbs(theme, cx)
+    }
+
+    fn added_to_workspace(&mut self, workspace: &mut Workspace, cx: &mut ViewContext<Self>) {
+        self.editor
+            .update(cx, |editor, cx| editor.added_to_workspace(workspace, cx));
+    }
+}
+
+impl Render for CommitDiffEditor {
+    fn render(&mut self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let message = match &self.state {
+            LoadState::Loading { .. } => Some(Label::new("Loading commit…").color(Color::Muted)),
+            LoadState::Failed(error) => Some(Label::new(error.clone()).color(Color::Error)),
+            LoadState::Loaded { file_count: 0 } => {
+                Some(Label::new("No text changes in this commit").color(Color::Muted))
+            }
+            LoadState::Loaded { .. } => None,
+        };
+        let child = match message {
+            Some(message) => div()
+                .bg(cx.theme().colors().editor_background)
+                .flex()
+                .items_center()
+                .justify_center()
+                .size_full()
+                .child(message),
+            None => div().size_full().child(self.editor.clone()),
+        };
+
+        div()
+            .track_focus(&self.focus_handle)
+            .size_full()
+            .child(child)
+    }
+}
@@ -0,0 +1,561 @@
+//! Highlights the conflicts left by a merge in the files git reports as conflicted,
+//! and shows inline actions above each of them to resolve it by accepting either or both sides.
+//!
+//! Conflicts are parsed from the buffer text and cached per buffer together with the buffer version they were parsed for.
+use std::{ops::Range, sync::Arc, time::Duration};
+
+use clock::Global;
+use collections::{HashMap, HashSet};
+use git::{conflict::parse_conflicts, repository::GitFileStatus};
+use gpui::{AppContext, Task, ViewContext};
+use language::{Buffer, OffsetRangeExt as _};
+use multi_buffer::ExcerptId;
+use project::ProjectPath;
+use text::BufferId;
+use theme::ActiveTheme as _;
+use ui::{prelude::*, Label, LabelSize};
+
+use crate::{
+    display_map::{BlockPlacement, BlockProperties, BlockStyle, CustomBlockId},
+    AcceptConflictBoth, AcceptConflictOurs, AcceptConflictTheirs, Editor, EditorMode,
+    OpenMergeView,
+};
+
+use super::merge_view::MergeView;
+
+const MERGE_CONFLICTS_DEBOUNCE: Duration = Duration::from_millis(100);
+
+#[derive(Default)]
+pub struct MergeConflicts {
+    buffers: HashMap<BufferId, CachedBufferConflicts>,
+    block_ids: HashSet<CustomBlockId>,
+    refresh_task: Option<Task<()>>,
+}
+
+struct CachedBufferConflicts {
+    version: Global,
+    conflicts: Vec<BufferConflict>,
+}
+
+#[derive(Clone, Debug)]
+struct BufferConflict {
+    range: Range<text::Anchor>,
+    ours: Range<text::Anchor>,
+    base: Option<Range<text::Anchor>>,
+    theirs: Range<text::Anchor>,
+    ours_label: SharedString,
+    theirs_label: SharedString,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConflictResolution {
+    Ours,
+    Theirs,
+    Both,
+}
+
+#[derive(Clone, Copy)]
+enum ConflictSide {
+    Ours,
+    Base,
+    Theirs,
+}
+
+enum ConflictOursHighlight {}
+enum ConflictBaseHighlight {}
+enum ConflictTheirsHighlight {}
+
+impl MergeConflicts {
+    fn is_empty(&self) -> bool {
+        self.buffers.is_empty() && self.block_ids.is_empty()
+    }
+}
+
+/// Whether git reports the file of the buffer as having unresolved merge conflicts.
+pub(crate) fn is_conflicted(buffer: &Buffer, cx: &AppContext) -> bool {
+    let Some(file) = project::File::from_dyn(buffer.file()) else {
+        return false;
+    };
+    file.worktree
+        .read(cx)
+        .entry_for_path(&file.path)
+        .map_or(false, |entry| {
+            entry.git_status == Some(GitFileStatus::Conflict)
+        })
+}
+
+impl Editor {
+    pub(crate) fn refresh_merge_conflicts(&mut self, cx: &mut ViewContext<Self>) {
+        if self.mode != EditorMode::Full || self.project.is_none() {
+            return;
+        }
+
+        let mut conflicted_buffers = HashSet::default();
+        let mut buffers_to_parse = Vec::new();
+        for buffer in self.buffer.read(cx).all_buffers() {
+            let buffer = buffer.read(cx);
+            if !is_conflicted(buffer, cx) {
+                continue;
+            }
+            let buffer_id = buffer.remote_id();
+            conflicted_buffers.insert(buffer_id);
+            let is_outdated = self
+                .merge_conflicts
+                .buffers
+                .get(&buffer_id)
+                .map_or(true, |cached| cached.version != buffer.version());
+            if is_outdated {
+                buffers_to_parse.push(buffer.text_snapshot());
+            }
+        }
+
+        let cached_buffers = self.merge_conflicts.buffers.len();
+        self.merge_conflicts
+            .buffers
+            .retain(|buffer_id, _| conflicted_buffers.contains(buffer_id));
+        if buffers_to_parse.is_empty() {
+            self.merge_conflicts.refresh_task = None;
+            if cached_buffers != self.merge_conflicts.buffers.len()
+                || (self.merge_conflicts.buffers.is_empty() && !self.merge_conflicts.is_empty())
+            {
+                self.update_merge_conflicts(cx);
+            }
+            return;
+        }
+
+        self.merge_conflicts.refresh_task = Some(cx.spawn(|editor, mut cx| async move {
+            cx.background_executor()
+                .timer(MERGE_CONFLICTS_DEBOUNCE)
+                .await;
+
+            let parsed = cx
+                .background_executor()
+                .spawn(async move {
+                    buffers_to_parse
+                        .into_iter()
+                        .map(|snapshot| {
+                            let conflicts = parse_conflicts(&snapshot.text())
+                                .into_iter()
+                                .map(|conflict| {
+                                    let anchor_range = |range: Range<usize>| {
+                                        snapshot.anchor_after(range.start)
+                                            ..snapshot.anchor_before(range.end)
+                                    };
+                                    BufferConflict {
+                                        range: anchor_range(conflict.range),
+                                        ours: anchor_range(conflict.ours),
+                                        base: conflict.base.map(anchor_range),
+                                        theirs: anchor_range(conflict.theirs),
+                                        ours_label: conflict.ours_label.into(),
+                                        theirs_label: conflict.theirs_label.into(),
+                                    }
+                                })
+                                .collect();
+                            (
+                                snapshot.remote_id(),
+                                CachedBufferConflicts {
+                                    version: snapshot.version().clone(),
+                                    conflicts,
+                                },
+                            )
+                        })
+                        .collect::<Vec<_>>()
+                })
+                .await;
+
+            editor
+                .update(&mut cx, |editor, cx| {
+                    editor.merge_conflicts.buffers.extend(parsed);
+                    editor.update_merge_conflicts(cx);
+                })
+                .ok();
+        }));
+    }
+
+    /// Replaces all conflict highlights and action blocks with the ones for the conflicts in the visible excerpts.
+    fn update_merge_conflicts(&mut self, cx: &mut ViewContext<Self>) {
+        self.clear_row_highlights::<ConflictOursHighlight>();
+        self.clear_row_highlights::<ConflictBaseHighlight>();
+        self.clear_row_highlights::<ConflictTheirsHighlight>();
+        let old_block_ids = std::mem::take(&mut self.merge_conflicts.block_ids);
+        if !old_block_ids.is_empty() {
+            self.remove_blocks(old_block_ids, None, cx);
+        }
+
+        let multi_buffer_snapshot = self.buffer.read(cx).snapshot(cx);
+        let status_colors = cx.theme().status();
+        let (ours_color, base_color, theirs_color) = (
+            status_colors.created_background,
+            status_colors.modified_background,
+            status_colors.info_background,
+        );
+        let editor = cx.view().clone();
+        let mut highlights = Vec::new();
+        let mut blocks = Vec::new();
+        for (excerpt_id, buffer_snapshot, excerpt_range) in multi_buffer_snapshot.excerpts() {
+            let buffer_id = buffer_snapshot.remote_id();
+            let Some(cached) = self.merge_conflicts.buffers.get(&buffer_id) else {
+                continue;
+            };
+            let context = excerpt_range.context.to_offset(buffer_snapshot);
+            let anchor_range = |range: &Range<text::Anchor>| {
+                Some(
+                    multi_buffer_snapshot.anchor_in_excerpt(excerpt_id, range.start)?
+                        ..multi_buffer_snapshot.anchor_in_excerpt(excerpt_id, range.end)?,
+                )
+            };
+
+            for conflict in &cached.conflicts {
+                let range = conflict.range.to_offset(buffer_snapshot);
+                if range.is_empty() || range.start < context.start || range.start > context.end {
+                    continue;
+                }
+                let mut sides = vec![
+                    (ConflictSide::Ours, &conflict.ours, ours_color),
+                    (ConflictSide::Theirs, &conflict.theirs, theirs_color),
+                ];
+                if let Some(base) = &conflict.base {
+                    sides.push((ConflictSide::Base, base, base_color));
+                }
+                for (side, range, color) in sides {
+                    if range.to_offset(buffer_snapshot).is_empty() {
+                        continue;
+                    }
+                    if let Some(range) = anchor_range(range) {
+                        highlights.push((side, range, color));
+                    }
+                }
+
+                let Some(position) = anchor_range(&conflict.range).map(|range| range.start) else {
+                    continue;
+                };
+                let editor = editor.clone();
+                let conflict = conflict.clone();
+                blocks.push(BlockProperties {
+                    placement: BlockPlacement::Above(position),
+                    height: 1,
+                    style: BlockStyle::Flex,
+                    priority: 0,
+                    render: Arc::new(move |cx| {
+                        let ours_title = if conflict.ours_label.is_empty() {
+                            "Accept Ours".to_string()
+                        } else {
+                            format!("Accept Ours ({})", conflict.ours_label)
+                        };
+                        let theirs_title = if conflict.theirs_label.is_empty() {
+                            "Accept Theirs".to_string()
+                        } else {
+                            format!("Accept Theirs ({})", conflict.theirs_label)
+                        };
+                        let actions = [
+                            (ours_title, Some(ConflictResolution::Ours)),
+                            (theirs_title, Some(ConflictResolution::Theirs)),
+                            ("Accept Both".to_string(), Some(ConflictResolution::Both)),
+                            ("Open Merge View".to_string(), None),
+                        ];
+                        h_flex()
+                            .id(cx.block_id)
+                            .h(cx.line_height)
+                            .pl(cx.anchor_x)
+                            .gap_1()
+                            .children(actions.into_iter().enumerate().map(
+                                |(ix, (title, resolution))| {
+                                    h_flex()
+                                        .gap_1()
+                                        .when(ix > 0, |this| {
+                                            this.child(
+                                                Label::new("|")
+                                                    .size(LabelSize::Small)
+                                                    .color(Color::Disabled),
+                                            )
+                                        })
+                                        .child(
+                                            div()
+                                                .id(ix)
+                                                .cursor_pointer()
+                                                .child(
+                                                    Label::new(title)
+                                                        .size(LabelSize::Small)
+                                                        .color(Color::Muted),
+                                                )
+                                                .on_click({
+                                                    let editor = editor.clone();
+                                                    let conflict = conflict.clone();
+                                                    move |_, cx| {
+                                                        editor.update(cx, |editor, cx| {
+                                                            match resolution {
+                                                                Some(resolution) => editor
+                                                                    .resolve_merge_conflict(
+                                                                        excerpt_id, buffer_id,
+                                                                        &conflict, resolution, cx,
+                                                                    ),
+                                                                None => editor
+                                                                    .open_merge_view_for_buffer(
+                                                                        buffer_id, cx,
+                                                                    ),
+                                                            }
+                                                        });
+                                                    }
+                                                }),
+                                        )
+                                },
+                            ))
+                            .into_any_element()
+                    }),
+                });
+            }
+        }
+
+        for (side, range, color) in highlights {
+            match side {
+                ConflictSide::Ours => {
+                    self.highlight_rows::<ConflictOursHighlight>(range, color, false, cx)
+                }
+                ConflictSide::Base => {
+                    self.highlight_rows::<ConflictBaseHighlight>(range, color, false, cx)
+                }
+                ConflictSide::Theirs => {
+                    self.highlight_rows::<ConflictTheirsHighlight>(range, color, false, cx)
+                }
+            }
+        }
+        if !blocks.is_empty() {
+            self.merge_conflicts.block_ids =
+                self.insert_blocks(blocks, None, cx).into_iter().collect();
+        }
+        cx.notify();
+    }
+
+    fn resolve_merge_conflict(
+        &mut self,
+        excerpt_id: ExcerptId,
+        buffer_id: BufferId,
+        conflict: &BufferConflict,
+        resolution: ConflictResolution,
+        cx: &mut ViewContext<Self>,
+    ) {
+        let Some(buffer) = self.buffer.read(cx).buffer(buffer_id) else {
+            return;
+        };
+        let buffer_snapshot = buffer.read(cx).text_snapshot();
+        let side_text = |range: &Range<text::Anchor>| {
+            buffer_snapshot
+                .text_for_range(range.clone())
+                .collect::<String>()
+        };
+        let new_text = match resolution {
+            ConflictResolution::Ours => side_text(&conflict.ours),
+            ConflictResolution::Theirs => side_text(&conflict.theirs),
+            ConflictResolution::Both => side_text(&conflict.ours) + &side_text(&conflict.theirs),
+        };
+
+        let multi_buffer_snapshot = self.buffer.read(cx).snapshot(cx);
+        let Some((start, end)) = multi_buffer_snapshot
+            .anchor_in_excerpt(excerpt_id, conflict.range.start)
+            .zip(multi_buffer_snapshot.anchor_in_excerpt(excerpt_id, conflict.range.end))
+        else {
+            return;
+        };
+        self.transact(cx, |editor, cx| editor.edit([(start..end, new_text)], cx));
+
+        // Drop the resolved conflict right away instead of waiting for the buffer to be parsed again.
+        if let Some(cached) = self.merge_conflicts.buffers.get_mut(&buffer_id) {
+            cached
+                .conflicts
+                .retain(|cached_conflict| cached_conflict.range != conflict.range);
+        }
+        self.update_merge_conflicts(cx);
+    }
+
+    /// Returns the conflict containing the newest cursor, if there is one.
+    fn merge_conflict_at_cursor(
+        &self,
+        cx: &AppContext,
+    ) -> Option<(ExcerptId, BufferId, BufferConflict)> {
+        let cursor = self.selections.newest_anchor().head();
+        let (buffer, offset, excerpt_id) =
+            self.buffer.read(cx).point_to_buffer_offset(cursor, cx)?;
+        let buffer = buffer.read(cx);
+        let buffer_id = buffer.remote_id();
+        let conflict = self
+            .merge_conflicts
+            .buffers
+            .get(&buffer_id)?
+            .conflicts
+            .iter()
+            .find(|conflict| {
+                let range = conflict.range.to_offset(buffer);
+                range.start <= offset && offset <= range.end
+            })?
+            .clone();
+        Some((excerpt_id, buffer_id, conflict))
+    }
+
+    fn resolve_merge_conflict_at_cursor(
+        &mut self,
+        resolution: ConflictResolution,
+        cx: &mut ViewContext<Self>,
+    ) {
+        if let Some((excerpt_id, buffer_id, conflict)) = self.merge_conflict_at_cursor(cx) {
+            self.resolve_merge_conflict(excerpt_id, buffer_id, &conflict, resolution, cx);
+        }
+    }
+
+    pub fn accept_conflict_ours(&mut self, _: &AcceptConflictOurs, cx: &mut ViewContext<Self>) {
+        self.resolve_merge_conflict_at_cursor(ConflictResolution::Ours, cx);
+    }
+
+    pub fn accept_conflict_theirs(&mut self, _: &AcceptConflictTheirs, cx: &mut ViewContext<Self>) {
+        self.resolve_merge_conflict_at_cursor(ConflictResolution::Theirs, cx);
+    }
+
+    pub fn accept_conflict_both(&mut self, _: &AcceptConflictBoth, cx: &mut ViewContext<Self>) {
+        self.resolve_merge_conflict_at_cursor(ConflictResolution::Both, cx);
+    }
+
+    pub fn open_merge_view(&mut self, _: &OpenMergeView, cx: &mut ViewContext<Self>) {
+        let cursor = self.selections.newest_anchor().head();
+        let buffer = self
+            .buffer
+            .read(cx)
+            .point_to_buffer_offset(cursor, cx)
+            .map(|(buffer, _, _)| buffer);
+        if let Some(buffer) = buffer {
+            let buffer_id = buffer.read(cx).remote_id();
+            self.open_merge_view_for_buffer(buffer_id, cx);
+        }
+    }
+
+    fn open_merge_view_for_buffer(&mut self, buffer_id: BufferId, cx: &mut ViewContext<Self>) {
+        let Some(workspace) = self.workspace() else {
+            return;
+        };
+        let Some(buffer) = self.buffer.read(cx).buffer(buffer_id) else {
+            return;
+        };
+        let buffer = buffer.read(cx);
+        if !is_conflicted(buffer, cx) {
+            return;
+        }
+        let Some(file) = project::File::from_dyn(buffer.file()) else {
+            return;
+        };
+        let project_path = ProjectPath {
+            worktree_id: file.worktree_id(cx),
+            path: file.path.clone(),
+        };
+        workspace.update(cx, |workspace, cx| {
+            MergeView::deploy(workspace, project_path, cx);
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use fs::FakeFs;
+    use gpui::TestAppContext;
+    use language::Point;
+    use project::Project;
+    use serde_json::json;
+    use unindent::Unindent as _;
+
+    use super::*;
+    use crate::{editor_tests::init_test, Undo};
+
+    #[gpui::test]
+    async fn test_accept_merge_conflicts(cx: &mut TestAppContext) {
+        init_test(cx, |_| {});
+
+        let text = "
+            fn main() {
+            <<<<<<< HEAD
+                ours();
+            =======
+                theirs();
+            >>>>>>> feature
+            }
+            <<<<<<< HEAD
+            a
+            ||||||| base
+            b
+            =======
+            c
+            >>>>>>> feature
+        "
+        .unindent();
+        let fs = FakeFs::new(cx.executor());
+        fs.insert_tree("/root", json!({ ".git": {}, "main.rs": text.clone() }))
+            .await;
+        fs.set_status_for_repo_via_git_operation(
+            Path::new("/root/.git"),
+            &[(Path::new("main.rs"), GitFileStatus::Conflict)],
+        );
+        let project = Project::test(fs, ["/root".as_ref()], cx).await;
+        let buffer = project
+            .update(cx, |project, cx| {
+                project.open_local_buffer("/root/main.rs", cx)
+            })
+            .await
+            .unwrap();
+        let (editor, cx) =
+            cx.add_window_view(|cx| Editor::for_buffer(buffer, Some(project.clone()), cx));
+        cx.executor().advance_clock(MERGE_CONFLICTS_DEBOUNCE);
+        cx.run_until_parked();
+
+        editor.update(cx, |editor, cx| {
+            assert_eq!(editor.merge_conflicts.block_ids.len(), 2);
+
+            editor.change_selections(None, cx, |selections| {
+                selections.select_ranges([Point::new(2, 0)..Point::new(2, 0)])
+            });
+            editor.accept_conflict_theirs(&AcceptConflictTheirs, cx);
+            assert_eq!(
+                editor.text(cx),
+                "
+                    fn main() {
+                        theirs();
+                    }
+                    <<<<<<< HEAD
+                    a
+                    ||||||| base
+                    b
+                    =======
+                    c
+                    >>>>>>> feature
+                "
+                .unindent()
+            );
+            assert_eq!(editor.merge_conflicts.block_ids.len(), 1);
+
+            editor.change_selections(None, cx, |selections| {
+                selections.select_ranges([Point::new(4, 0)..Point::new(4, 0)])
+            });
+            editor.accept_conflict_both(&AcceptConflictBoth, cx);
+            assert_eq!(
+                editor.text(cx),
+                "
+                    fn main() {
+                        theirs();
+                    }
+                    a
+                    c
+                "
+                .unindent()
+            );
+            assert!(editor.merge_conflicts.block_ids.is_empty());
+
+            editor.undo(&Undo, cx);
+        });
+        cx.executor().advance_clock(MERGE_CONFLICTS_DEBOUNCE);
+        cx.run_until_parked();
+        editor.update(cx, |editor, _| {
+            assert_eq!(
+                editor.merge_conflicts.block_ids.len(),
+                1,
+                "Undoing a resolution should bring its conflict back"
+            );
+        });
+    }
+}
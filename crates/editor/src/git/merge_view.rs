@@ -0,0 +1,303 @@
+//! A three-pane view of a conflicted file: our and their versions of it on the sides, each showing its
+//! changes relative to the common ancestor, and the file being merged in the middle.
+//!
+//! The sides are loaded from the index stages of the conflict into read-only buffers, the middle pane is a regular
+//! editor for the project buffer, with the same inline conflict actions as any other editor.
+use std::any::{Any, TypeId};
+
+use anyhow::Context as _;
+use git::conflict::ConflictStages;
+use gpui::{
+    AnyView, AppContext, EventEmitter, FocusHandle, FocusableView, Model, Render, Task, View,
+};
+use language::{Buffer, Capability};
+use project::{buffer_store::BufferChangeSet, Project, ProjectPath};
+use text::BufferId;
+use theme::ActiveTheme;
+use ui::prelude::*;
+use workspace::{
+    item::{BreadcrumbText, Item, ItemEvent},
+    notifications::DetachAndPromptErr,
+    ItemNavHistory, ToolbarItemLocation, Workspace,
+};
+
+use crate::{Editor, EditorEvent};
+
+pub struct MergeView {
+    project_path: ProjectPath,
+    ours: View<Editor>,
+    result: View<Editor>,
+    theirs: View<Editor>,
+}
+
+impl MergeView {
+    /// Opens the merge view for the conflicted file at `project_path`,
+    /// activating the one already open for it if there is one.
+    pub fn deploy(
+        workspace: &mut Workspace,
+        project_path: ProjectPath,
+        cx: &mut ViewContext<Workspace>,
+    ) {
+        let existing = workspace
+            .items_of_type::<Self>(cx)
+            .find(|item| item.read(cx).project_path == project_path);
+        if let Some(existing) = existing {
+            workspace.activate_item(&existing, true, true, cx);
+            return;
+        }
+
+        let project = workspace.project().clone();
+        let (open_buffer, stages) = project.update(cx, |project, cx| {
+            (
+                project.open_buffer(project_path.clone(), cx),
+                project.git_conflict_stages(project_path.clone(), cx),
+            )
+        });
+        cx.spawn(|workspace, mut cx| async move {
+            let buffer = open_buffer.await.context("opening the conflicted file")?;
+            let stages = stages.await.context("loading the conflict stages")?;
+            workspace.update(&mut cx, |workspace, cx| {
+                let merge_view =
+                    cx.new_view(|cx| Self::new(project, project_path, buffer, stages, cx));
+                workspace.add_item_to_active_pane(Box::new(merge_view), None, true, cx);
+            })
+        })
+        .detach_and_prompt_err("Failed to open the merge view", cx, |_, _| None);
+    }
+
+    fn new(
+        project: Model<Project>,
+        project_path: ProjectPath,
+        buffer: Model<Buffer>,
+        stages: ConflictStages,
+        cx: &mut ViewContext<Self>,
+    ) -> Self {
+        let base_text = stages.base.unwrap_or_default();
+        let ours = Self::build_side(
+            &buffer,
+            stages.ours.unwrap_or_default(),
+            base_text.clone(),
+            cx,
+        );
+        let theirs = Self::build_side(&buffer, stages.theirs.unwrap_or_default(), base_text, cx);
+        let result = cx.new_view(|cx| Editor::for_buffer(buffer, Some(project), cx));
+        cx.subscribe(&result, |_, _, event: &EditorEvent, cx| {
+            cx.emit(event.clone())
+        })
+        .detach();
+
+        Self {
+            project_path,
+            ours,
+            result,
+            theirs,
+        }
+    }
+
+    /// Builds a read-only editor for one side of the conflict, with its changes to the common ancestor expanded.
+    fn build_side(
+        buffer: &Model<Buffer>,
+        text: String,
+        base_text: String,
+        cx: &mut ViewContext<Self>,
+    ) -> View<Editor> {
+        let (language, languages) = {
+            let buffer = buffer.read(cx);
+            (buffer.language().cloned(), buffer.language_registry())
+        };
+        let side_buffer = cx.new_model(|cx| {
+            let mut side_buffer = Buffer::build(
+                text::Buffer::new(0, BufferId::from(cx.entity_id().as_non_zero_u64()), text),
+                None,
+                Capability::ReadOnly,
+            );
+            if let Some(languages) = languages {
+                side_buffer.set_language_registry(languages);
+            }
+            side_buffer.set_language(language, cx);
+            side_buffer
+        });
+
+        let snapshot = side_buffer.read(cx).text_snapshot();
+        let change_set = cx.new_model(|_| BufferChangeSet::new(&snapshot));
+        // The editor observes the change set, so the hunks show up once the diff is computed.
+        change_set.update(cx, |change_set, cx| {
+            let _ = change_set.set_base_text(base_text, snapshot, cx);
+        });
+
+        cx.new_view(|cx| {
+            let mut editor = Editor::for_buffer(side_buffer, None, cx);
+            editor.set_expand_all_diff_hunks();
+            editor.set_read_only(true);
+            editor.diff_map.add_change_set(change_set, cx);
+            editor
+        })
+    }
+
+    fn render_pane(
+        &self,
+        title: &'static str,
+        editor: &View<Editor>,
+        cx: &ViewContext<Self>,
+    ) -> impl IntoElement {
+        v_flex()
+            .flex_1()
+            .h_full()
+            .min_w_0()
+            .child(
+                h_flex()
+                    .px_2()
+                    .py_1()
+                    .border_b_1()
+                    .border_color(cx.theme().colors().border)
+                    .child(Label::new(title).size(LabelSize::Small).color(Color::Muted)),
+            )
+            .child(div().flex_1().min_h_0().child(editor.clone()))
+    }
+}
+
+impl EventEmitter<EditorEvent> for MergeView {}
+
+impl FocusableView for MergeView {
+    fn focus_handle(&self, cx: &AppContext) -> FocusHandle {
+        self.result.focus_handle(cx)
+    }
+}
+
+impl Item for MergeView {
+    type Event = EditorEvent;
+
+    fn to_item_events(event: &EditorEvent, f: impl FnMut(ItemEvent)) {
+        Editor::to_item_events(event, f)
+    }
+
+    fn deactivated(&mut self, cx: &mut ViewContext<Self>) {
+        self.result.update(cx, |editor, cx| editor.deactivated(cx));
+    }
+
+    fn navigate(&mut self, data: Box<dyn Any>, cx: &mut ViewContext<Self>) -> bool {
+        self.result
+            .update(cx, |editor, cx| editor.navigate(data, cx))
+    }
+
+    fn tab_tooltip_text(&self, _: &AppContext) -> Option<SharedString> {
+        Some(self.project_path.path.to_string_lossy().to_string().into())
+    }
+
+    fn tab_content_text(&self, _: &WindowContext) -> Option<SharedString> {
+        let file_name = self
+            .project_path
+            .path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        Some(format!("Merge {file_name}").into())
+    }
+
+    fn tab_icon(&self, _: &WindowContext) -> Option<Icon> {
+        Some(Icon::new(IconName::FileGit))
+    }
+
+    fn telemetry_event_text(&self) -> Option<&'static str> {
+        Some("merge view")
+    }
+
+    fn for_each_project_item(
+        &self,
+        cx: &AppContext,
+        f: &mut dyn FnMut(gpui::EntityId, &dyn project::ProjectItem),
+    ) {
+        self.result.for_each_project_item(cx, f)
+    }
+
+    fn is_singleton(&self, _: &AppContext) -> bool {
+        false
+    }
+
+    fn set_nav_history(&mut self, nav_history: ItemNavHistory, cx: &mut ViewContext<Self>) {
+        self.result.update(cx, |editor, _| {
+            editor.set_nav_history(Some(nav_history));
+        });
+    }
+
+    fn is_dirty(&self, cx: &AppContext) -> bool {
+        self.result.read(cx).buffer().read(cx).is_dirty(cx)
+    }
+
+    fn has_conflict(&self, cx: &AppContext) -> bool {
+        self.result.read(cx).buffer().read(cx).has_conflict(cx)
+    }
+
+    fn can_save(&self, _: &AppContext) -> bool {
+        true
+    }
+
+    fn save(
+        &mut self,
+        format: bool,
+        project: Model<Project>,
+        cx: &mut ViewContext<Self>,
+    ) -> Task<anyhow::Result<()>> {
+        self.result.save(format, project, cx)
+    }
+
+    fn save_as(
+        &mut self,
+        _: Model<Project>,
+        _: ProjectPath,
+        _: &mut ViewContext<Self>,
+    ) -> Task<anyhow::Result<()>> {
+        unreachable!()
+    }
+
+    fn reload(
+        &mut self,
+        project: Model<Project>,
+        cx: &mut ViewContext<Self>,
+    ) -> Task<anyhow::Result<()>> {
+        self.result.reload(project, cx)
+    }
+
+    fn act_as_type<'a>(
+        &'a self,
+        type_id: TypeId,
+        self_handle: &'a View<Self>,
+        _: &'a AppContext,
+    ) -> Option<AnyView> {
+        if type_id == TypeId::of::<Self>() {
+            Some(self_handle.to_any())
+        } else if type_id == TypeId::of::<Editor>() {
+            Some(self.result.to_any())
+        } else {
+            None
+        }
+    }
+
+    fn breadcrumb_location(&self, _: &AppContext) -> ToolbarItemLocation {
+        ToolbarItemLocation::PrimaryLeft
+    }
+
+    fn breadcrumbs(&self, theme: &theme::Theme, cx: &AppContext) -> Option<Vec<BreadcrumbText>> {
+        self.result.breadcrumbs(theme, cx)
+    }
+
+    fn added_to_workspace(&mut self, workspace: &mut Workspace, cx: &mut ViewContext<Self>) {
+        for editor in [&self.ours, &self.result, &self.theirs] {
+            editor.update(cx, |editor, cx| editor.added_to_workspace(workspace, cx));
+        }
+    }
+}
+
+impl Render for MergeView {
+    fn render(&mut self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let border_color = cx.theme().colors().border;
+        h_flex()
+            .size_full()
+            .bg(cx.theme().colors().editor_background)
+            .child(self.render_pane("Ours", &self.ours, cx))
+            .child(div().w_px().h_full().bg(border_color))
+            .child(self.render_pane("Result", &self.result, cx))
+            .child(div().w_px().h_full().bg(border_color))
+            .child(self.render_pane("Theirs", &self.theirs, cx))
+    }
+}
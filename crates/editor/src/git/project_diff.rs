@@ -137,6 +137,9 @@ impl ProjectDiffEditor {
                     project::Event::Closed => {
                         project_diff_editor.buffer_changes.clear();
                     }
+                    project::Event::DiffBaseChanged => {
+                        project_diff_editor.schedule_rescan_all(cx);
+                    }
                     _ => {}
                 }
 
@@ -191,6 +194,17 @@ impl ProjectDiffEditor {
             id,
             cx.spawn(|project_diff_editor, mut cx| async move {
                 cx.background_executor().timer(UPDATE_DEBOUNCE).await;
+                // Remote projects only diff against the index, whose changes the git statuses already cover.
+                let changed_paths = match project
+                    .update(&mut cx, |project, cx| project.git_changed_paths(id, cx))
+                {
+                    Ok(changed_paths) => changed_paths
+                        .await
+                        .unwrap_or_default()
+                        .into_iter()
+                        .collect::<HashSet<_>>(),
+                    Err(_) => return,
+                };
                 let open_tasks = project
                     .update(&mut cx, |project, cx| {
                         let worktree = project.worktree_for_id(id, cx)?;
@@ -199,7 +213,14 @@ impl ProjectDiffEditor {
                             .entries(false, 0)
                             .filter(|entry| !entry.is_external)
                             .filter(|entry| entry.is_file())
-                            .filter_map(|entry| Some((entry.git_status?, entry)))
+                            .filter_map(|entry| {
+                                let git_status = entry.git_status.or_else(|| {
+                                    changed_paths
+                                        .contains(&entry.path)
+                                        .then_some(GitFileStatus::Modified)
+                                })?;
+                                Some((git_status, entry))
+                            })
                             .filter_map(|(git_status, entry)| {
                                 Some((git_status, entry.id, project.path_for_entry(entry.id, cx)?))
                             })
@@ -3,7 +3,8 @@ use crate::{
     actions::Format, selections_collection::SelectionsCollection, Copy, CopyPermalinkToLine, Cut,
     DisplayPoint, DisplaySnapshot, Editor, EditorMode, FindAllReferences, GoToDeclaration,
     GoToDefinition, GoToImplementation, GoToTypeDefinition, Paste, Rename, RevealInFileManager,
-    SelectMode, ToDisplayPoint, ToggleCodeActions,
+    SelectMode, ShowFileHistory, ShowIncomingCalls, ShowOutgoingCalls, ToDisplayPoint,
+    ToggleCodeActions,
 };
 use gpui::prelude::FluentBuilder;
 use gpui::{DismissEvent, Pixels, Point, Subscription, View, ViewContext};
@@ -164,6 +165,8 @@ pub fn deploy_context_menu(
                 .action("Go to Type Definition", Box::new(GoToTypeDefinition))
                 .action("Go to Implementation", Box::new(GoToImplementation))
                 .action("Find All References", Box::new(FindAllReferences))
+                .action("Show Incoming Calls", Box::new(ShowIncomingCalls))
+                .action("Show Outgoing Calls", Box::new(ShowOutgoingCalls))
                 .separator()
                 .action("Rename Symbol", Box::new(Rename))
                 .action("Format Buffer", Box::new(Format))
@@ -190,7 +193,14 @@ pub fn deploy_context_menu(
                     }
                 })
                 .action("Open in Terminal", Box::new(OpenInTerminal))
-                .action("Copy Permalink", Box::new(CopyPermalinkToLine));
+                .action("Copy Permalink", Box::new(CopyPermalinkToLine))
+                .map(|builder| {
+                    if has_reveal_target {
+                        builder.action("Show File History", Box::new(ShowFileHistory))
+                    } else {
+                        builder.disabled_action("Show File History", Box::new(ShowFileHistory))
+                    }
+                });
             match focus {
                 Some(focus) => builder.context(focus),
                 None => builder,
@@ -87,6 +87,11 @@ define_connection!(
     //   mtime_seconds: Option<i64>,
     //   mtime_nanos: Option<i32>,
     // )
+    //
+    // breakpoints(
+    //   path: PathBuf,
+    //   row: u32,
+    // )
     pub static ref DB: EditorDb<WorkspaceDb> =
         &[sql! (
             CREATE TABLE editors(
@@ -134,6 +139,13 @@ define_connection!(
             ALTER TABLE editors ADD COLUMN mtime_seconds INTEGER DEFAULT NULL;
             ALTER TABLE editors ADD COLUMN mtime_nanos INTEGER DEFAULT NULL;
         ),
+        sql! (
+            CREATE TABLE breakpoints(
+                path BLOB NOT NULL,
+                row INTEGER NOT NULL,
+                PRIMARY KEY(path, row)
+            ) STRICT;
+        ),
         ];
 );
 
@@ -188,6 +200,30 @@ impl EditorDb {
         }
     }
 
+    // Returns the path and 0-based row of every breakpoint
+    query! {
+        pub fn get_breakpoints() -> Result<Vec<(PathBuf, u32)>> {
+            SELECT path, row FROM breakpoints
+            ORDER BY path, row
+        }
+    }
+
+    /// Replaces the breakpoints of a file.
+    pub async fn save_breakpoints(&self, path: PathBuf, rows: Vec<u32>) -> Result<()> {
+        self.write(move |conn| {
+            conn.with_savepoint("save_breakpoints", || {
+                conn.exec_bound(sql!(DELETE FROM breakpoints WHERE path = ?))?(path.as_path())?;
+                let mut insert_breakpoint =
+                    conn.exec_bound(sql!(INSERT INTO breakpoints(path, row) VALUES (?, ?)))?;
+                for row in rows {
+                    insert_breakpoint((path.as_path(), row))?;
+                }
+                Ok(())
+            })
+        })
+        .await
+    }
+
     pub async fn delete_unloaded_items(
         &self,
         workspace: WorkspaceId,
@@ -294,4 +330,33 @@ mod tests {
             .unwrap();
         assert_eq!(have, serialized_editor);
     }
+
+    #[gpui::test]
+    async fn test_save_and_get_breakpoints() {
+        let db = EditorDb(db::open_test_db("test_save_and_get_breakpoints").await);
+        assert!(db.get_breakpoints().unwrap().is_empty());
+
+        db.save_breakpoints(PathBuf::from("/root/b.rs"), vec![7, 2])
+            .await
+            .unwrap();
+        db.save_breakpoints(PathBuf::from("/root/a.rs"), vec![0])
+            .await
+            .unwrap();
+        assert_eq!(
+            db.get_breakpoints().unwrap(),
+            [
+                (PathBuf::from("/root/a.rs"), 0),
+                (PathBuf::from("/root/b.rs"), 2),
+                (PathBuf::from("/root/b.rs"), 7),
+            ]
+        );
+
+        db.save_breakpoints(PathBuf::from("/root/b.rs"), Vec::new())
+            .await
+            .unwrap();
+        assert_eq!(
+            db.get_breakpoints().unwrap(),
+            [(PathBuf::from("/root/a.rs"), 0)]
+        );
+    }
 }
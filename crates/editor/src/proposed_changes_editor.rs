@@ -463,6 +463,22 @@ impl SemanticsProvider for BranchBufferSemanticsProvider {
         }
     }
 
+    fn semantic_tokens(
+        &self,
+        _: Model<Buffer>,
+        _: &mut AppContext,
+    ) -> Option<Task<anyhow::Result<project::SemanticTokens>>> {
+        None
+    }
+
+    fn code_lens(
+        &self,
+        _: Model<Buffer>,
+        _: &mut AppContext,
+    ) -> Option<Task<anyhow::Result<Vec<project::CodeLens>>>> {
+        None
+    }
+
     fn document_highlights(
         &self,
         buffer: &Model<Buffer>,
@@ -1,10 +1,13 @@
 use std::{fs, path::Path};
 
 use anyhow::Context as _;
+use collections::HashMap;
 use gpui::{Context, View, ViewContext, VisualContext, WindowContext};
 use language::Language;
 use multi_buffer::MultiBuffer;
 use project::lsp_ext_command::ExpandMacro;
+use serde::Deserialize;
+use task::{ResolvedTask, TaskContext, TaskTemplate};
 use text::ToPointUtf16;
 
 use crate::{
@@ -13,6 +16,8 @@ use crate::{
 };
 
 const RUST_ANALYZER_NAME: &str = "rust-analyzer";
+pub(crate) const RUN_SINGLE_COMMAND: &str = "rust-analyzer.runSingle";
+pub(crate) const SHOW_REFERENCES_COMMAND: &str = "rust-analyzer.showReferences";
 
 fn is_rust_language(language: &Language) -> bool {
     language.name() == "Rust".into()
@@ -158,3 +163,48 @@ pub fn open_docs(editor: &mut Editor, _: &OpenDocs, cx: &mut ViewContext<Editor>
     })
     .detach_and_log_err(cx);
 }
+
+/// A `rust-analyzer.runSingle` argument, as sent in the "Run" and "Debug" code lenses.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Runnable {
+    label: String,
+    args: CargoRunnableArgs,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CargoRunnableArgs {
+    #[serde(default)]
+    workspace_root: Option<String>,
+    #[serde(default)]
+    cwd: Option<String>,
+    cargo_args: Vec<String>,
+    #[serde(default)]
+    executable_args: Vec<String>,
+    #[serde(default)]
+    override_cargo: Option<String>,
+    #[serde(default)]
+    environment: HashMap<String, String>,
+}
+
+/// Converts a `rust-analyzer.runSingle` command into a task that runs the corresponding cargo invocation.
+pub(crate) fn runnable_task(command: &lsp::Command) -> Option<ResolvedTask> {
+    let runnable = command.arguments.as_ref()?.first()?.clone();
+    let runnable = serde_json::from_value::<Runnable>(runnable).ok()?;
+    let args = runnable.args;
+    let mut task_args = args.cargo_args;
+    if !args.executable_args.is_empty() {
+        task_args.push("--".to_string());
+        task_args.extend(args.executable_args);
+    }
+    let template = TaskTemplate {
+        label: runnable.label,
+        command: args.override_cargo.unwrap_or_else(|| "cargo".to_string()),
+        args: task_args,
+        env: args.environment,
+        cwd: args.cwd.or(args.workspace_root),
+        ..TaskTemplate::default()
+    };
+    template.resolve_task(RUST_ANALYZER_NAME, &TaskContext::default())
+}
@@ -7,8 +7,9 @@ use crate::{
     display_map::{DisplaySnapshot, ToDisplayPoint},
     hover_popover::hide_hover,
     persistence::DB,
-    Anchor, DisplayPoint, DisplayRow, Editor, EditorEvent, EditorMode, EditorSettings,
-    InlayHintRefreshReason, MultiBufferSnapshot, RowExt, ToPoint,
+    Anchor, CodeLensRefreshReason, DisplayPoint, DisplayRow, Editor, EditorEvent, EditorMode,
+    EditorSettings, InlayHintRefreshReason, MultiBufferSnapshot, RowExt,
+    SemanticTokensRefreshReason, ToPoint,
 };
 pub use autoscroll::{Autoscroll, AutoscrollStrategy};
 use core::fmt::Debug;
@@ -415,7 +416,12 @@ impl Editor {
             cx.spawn(|editor, mut cx| async move {
                 editor
                     .update(&mut cx, |editor, cx| {
-                        editor.refresh_inlay_hints(InlayHintRefreshReason::NewLinesShown, cx)
+                        editor.refresh_inlay_hints(InlayHintRefreshReason::NewLinesShown, cx);
+                        editor.refresh_semantic_tokens(
+                            SemanticTokensRefreshReason::BuffersChanged,
+                            cx,
+                        );
+                        editor.refresh_code_lens(CodeLensRefreshReason::BuffersChanged, cx);
                     })
                     .ok()
             })
@@ -479,6 +485,8 @@ impl Editor {
         );
 
         self.refresh_inlay_hints(InlayHintRefreshReason::NewLinesShown, cx);
+        self.refresh_semantic_tokens(SemanticTokensRefreshReason::BuffersChanged, cx);
+        self.refresh_code_lens(CodeLensRefreshReason::BuffersChanged, cx);
     }
 
     pub fn scroll_position(&self, cx: &mut ViewContext<Self>) -> gpui::Point<f32> {
@@ -0,0 +1,280 @@
+//! Stores the data received from LSP <a href="https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#textDocument_semanticTokens">textDocument/semanticTokens</a> requests
+//! and layers it over the tree-sitter highlights through [`crate::display_map::DisplayMap`].
+//!
+//! Tokens are cached per buffer, together with the buffer version they were requested for, so that
+//! scrolling through excerpts of an unchanged buffer does not query the language server again.
+use std::time::Duration;
+
+use crate::{Editor, EditorMode, EditorSettings};
+use clock::Global;
+use collections::{HashMap, HashSet};
+use gpui::{HighlightStyle, Task, ViewContext};
+use project::{SemanticToken, SemanticTokens};
+use settings::Settings as _;
+use text::BufferId;
+use theme::{ActiveTheme as _, SyntaxTheme};
+use util::ResultExt as _;
+
+const SEMANTIC_TOKENS_DEBOUNCE: Duration = Duration::from_millis(150);
+
+#[derive(Default)]
+pub struct SemanticTokensCache {
+    buffers: HashMap<BufferId, CachedBufferTokens>,
+    refresh_task: Option<Task<()>>,
+}
+
+struct CachedBufferTokens {
+    version: Global,
+    tokens: SemanticTokens,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum SemanticTokensRefreshReason {
+    /// The language server asked for all tokens to be re-requested.
+    RefreshRequested,
+    /// Some buffers were edited or shown, only query those whose tokens are outdated.
+    BuffersChanged,
+    /// Settings or the theme changed: restyle the cached tokens and query the missing ones.
+    SettingsChange,
+    ExcerptsRemoved,
+}
+
+impl SemanticTokensCache {
+    pub fn is_empty(&self) -> bool {
+        self.buffers.is_empty()
+    }
+
+    fn clear(&mut self) {
+        self.buffers.clear();
+        self.refresh_task = None;
+    }
+}
+
+impl Editor {
+    pub(crate) fn refresh_semantic_tokens(
+        &mut self,
+        reason: SemanticTokensRefreshReason,
+        cx: &mut ViewContext<Self>,
+    ) {
+        if self.mode != EditorMode::Full || self.semantics_provider.is_none() {
+            return;
+        }
+        if !EditorSettings::get_global(cx).semantic_tokens {
+            if !self.semantic_tokens_cache.is_empty() {
+                self.semantic_tokens_cache.clear();
+                self.update_semantic_highlights(cx);
+            }
+            return;
+        }
+
+        match reason {
+            SemanticTokensRefreshReason::SettingsChange => {
+                self.update_semantic_highlights(cx);
+            }
+            SemanticTokensRefreshReason::ExcerptsRemoved => {
+                let multi_buffer = self.buffer.read(cx);
+                self.semantic_tokens_cache
+                    .buffers
+                    .retain(|buffer_id, _| multi_buffer.buffer(*buffer_id).is_some());
+                self.update_semantic_highlights(cx);
+                return;
+            }
+            SemanticTokensRefreshReason::RefreshRequested
+            | SemanticTokensRefreshReason::BuffersChanged => {}
+        }
+
+        let invalidate = reason == SemanticTokensRefreshReason::RefreshRequested;
+        let mut queried_buffers = HashSet::default();
+        let buffers_to_query = self
+            .excerpts_for_inlay_hints_query(None, cx)
+            .into_values()
+            .filter(|(buffer, version, _)| {
+                let buffer_id = buffer.read(cx).remote_id();
+                queried_buffers.insert(buffer_id)
+                    && (invalidate
+                        || self
+                            .semantic_tokens_cache
+                            .buffers
+                            .get(&buffer_id)
+                            .map_or(true, |cached| cached.version != *version))
+            })
+            .map(|(buffer, version, _)| (buffer, version))
+            .collect::<Vec<_>>();
+        if buffers_to_query.is_empty() {
+            return;
+        }
+
+        self.semantic_tokens_cache.refresh_task = Some(cx.spawn(|editor, mut cx| async move {
+            cx.background_executor()
+                .timer(SEMANTIC_TOKENS_DEBOUNCE)
+                .await;
+
+            let Some(tasks) = editor
+                .update(&mut cx, |editor, cx| {
+                    let provider = editor.semantics_provider.clone()?;
+                    Some(
+                        buffers_to_query
+                            .into_iter()
+                            .filter_map(|(buffer, version)| {
+                                let buffer_id = buffer.read(cx).remote_id();
+                                let task = provider.semantic_tokens(buffer, cx)?;
+                                Some((buffer_id, version, task))
+                            })
+                            .collect::<Vec<_>>(),
+                    )
+                })
+                .ok()
+                .flatten()
+            else {
+                return;
+            };
+
+            let mut fetched = Vec::with_capacity(tasks.len());
+            for (buffer_id, version, task) in tasks {
+                if let Some(tokens) = task.await.log_err() {
+                    fetched.push((buffer_id, CachedBufferTokens { version, tokens }));
+                }
+            }
+
+            editor
+                .update(&mut cx, |editor, cx| {
+                    editor.semantic_tokens_cache.buffers.extend(fetched);
+                    editor.update_semantic_highlights(cx);
+                })
+                .ok();
+        }));
+    }
+
+    fn update_semantic_highlights(&mut self, cx: &mut ViewContext<Self>) {
+        let multi_buffer_snapshot = self.buffer.read(cx).snapshot(cx);
+        let syntax_theme = cx.theme().syntax().clone();
+        let mut styles = HashMap::<(BufferId, u32, u32), Option<HighlightStyle>>::default();
+        let mut highlights = Vec::new();
+        for (excerpt_id, buffer_snapshot, excerpt_range) in multi_buffer_snapshot.excerpts() {
+            let buffer_id = buffer_snapshot.remote_id();
+            let Some(cached) = self.semantic_tokens_cache.buffers.get(&buffer_id) else {
+                continue;
+            };
+            let context = excerpt_range.context;
+            for token in &cached.tokens.tokens {
+                if token.range.end.cmp(&context.start, buffer_snapshot).is_lt() {
+                    continue;
+                }
+                if token.range.start.cmp(&context.end, buffer_snapshot).is_gt() {
+                    break;
+                }
+                let Some(style) = *styles
+                    .entry((buffer_id, token.token_type, token.token_modifiers))
+                    .or_insert_with(|| token_style(&cached.tokens, token, &syntax_theme))
+                else {
+                    continue;
+                };
+                let (Some(start), Some(end)) = (
+                    multi_buffer_snapshot.anchor_in_excerpt(excerpt_id, token.range.start),
+                    multi_buffer_snapshot.anchor_in_excerpt(excerpt_id, token.range.end),
+                ) else {
+                    continue;
+                };
+                highlights.push((start..end, style));
+            }
+        }
+
+        self.display_map.update(cx, |display_map, _| {
+            display_map.set_semantic_highlights(highlights);
+        });
+        cx.notify();
+    }
+}
+
+/// Looks up the theme style for a token, trying the most specific syntax key first:
+/// `variable.mutable` before `variable`, `function.unsafe` before `function`.
+/// Tokens without a matching theme style keep their tree-sitter highlighting.
+fn token_style(
+    tokens: &SemanticTokens,
+    token: &SemanticToken,
+    syntax_theme: &SyntaxTheme,
+) -> Option<HighlightStyle> {
+    let token_type = tokens.token_type(token)?;
+    let base_key = syntax_key_for_token_type(token_type);
+    tokens
+        .token_modifiers(token)
+        .map(|modifier| format!("{base_key}.{}", snake_case(modifier)))
+        .chain(Some(base_key.to_string()))
+        .find_map(|key| {
+            let ix = syntax_theme.highlight_id(&key)?;
+            Some(syntax_theme.highlights[ix as usize].1)
+        })
+}
+
+fn syntax_key_for_token_type(token_type: &str) -> &str {
+    match token_type {
+        "type" | "class" | "interface" | "struct" | "typeParameter" | "typeAlias"
+        | "builtinType" => "type",
+        "enumMember" => "variant",
+        "parameter" | "variable" => "variable",
+        "method" | "function" => "function",
+        "macro" => "function.special",
+        "regexp" => "string.regex",
+        "decorator" => "attribute",
+        "selfKeyword" | "selfTypeKeyword" => "variable.special",
+        other => other,
+    }
+}
+
+fn snake_case(name: &str) -> String {
+    let mut result = String::with_capacity(name.len());
+    for c in name.chars() {
+        if c.is_ascii_uppercase() {
+            result.push('_');
+            result.push(c.to_ascii_lowercase());
+        } else {
+            result.push(c);
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gpui::{hsla, Hsla};
+
+    #[test]
+    fn test_token_style() {
+        let red: Hsla = hsla(0., 1., 0.5, 1.);
+        let green: Hsla = hsla(0.33, 1., 0.5, 1.);
+        let blue: Hsla = hsla(0.66, 1., 0.5, 1.);
+        let syntax_theme = SyntaxTheme::new_test([
+            ("variable", red),
+            ("variable.mutable", green),
+            ("function.special", blue),
+        ]);
+        let tokens = SemanticTokens {
+            token_types: ["variable", "macro", "keyword"]
+                .into_iter()
+                .map(String::from)
+                .collect(),
+            token_modifiers: ["declaration", "mutable", "defaultLibrary"]
+                .into_iter()
+                .map(String::from)
+                .collect(),
+            tokens: Vec::new(),
+        };
+        let new_token = |token_type, token_modifiers| SemanticToken {
+            range: text::Anchor::MIN..text::Anchor::MAX,
+            token_type,
+            token_modifiers,
+        };
+
+        let color = |token: SemanticToken| {
+            token_style(&tokens, &token, &syntax_theme).and_then(|style| style.color)
+        };
+        assert_eq!(color(new_token(0, 0)), Some(red));
+        assert_eq!(color(new_token(0, 0b001)), Some(red));
+        assert_eq!(color(new_token(0, 0b011)), Some(green));
+        assert_eq!(color(new_token(1, 0b100)), Some(blue));
+        assert_eq!(color(new_token(2, 0)), None);
+        assert_eq!(color(new_token(7, 0)), None);
+        assert_eq!(snake_case("defaultLibrary"), "default_library");
+    }
+}
@@ -3,7 +3,7 @@ use anyhow::Result;
 use clap::Parser;
 use client::{Client, UserStore};
 use clock::RealSystemClock;
-use collections::BTreeMap;
+use collections::{BTreeMap, HashMap};
 use feature_flags::FeatureFlagAppExt as _;
 use git::GitHostingProviderRegistry;
 use gpui::{AsyncAppContext, BackgroundExecutor, Context, Model};
@@ -14,7 +14,7 @@ use open_ai::OpenAiEmbeddingModel;
 use project::Project;
 use reqwest_client::ReqwestClient;
 use semantic_index::{
-    EmbeddingProvider, OpenAiEmbeddingProvider, ProjectIndex, SemanticDb, Status,
+    EmbeddingProvider, OpenAiEmbeddingProvider, ProjectIndex, SearchMode, SemanticDb, Status,
 };
 use serde::{Deserialize, Serialize};
 use settings::SettingsStore;
@@ -39,6 +39,12 @@ const EVAL_REPOS_DIR: &'static str = "target/datasets/eval-repos";
 const EVAL_DB_PATH: &'static str = "target/eval_db";
 const SEARCH_RESULT_LIMIT: usize = 8;
 const SKIP_EVAL_PATH: &'static str = ".skip_eval";
+/// Every query is run in each of these modes, so that their results can be compared.
+const SEARCH_MODES: [SearchMode; 3] = [
+    SearchMode::Semantic,
+    SearchMode::Lexical,
+    SearchMode::Hybrid,
+];
 
 #[derive(clap::Parser)]
 #[command(author, version, about, long_about = None)]
@@ -86,6 +92,7 @@ struct EvaluationProjectOutcome {
 struct EvaluationQueryOutcome {
     repo: String,
     query: String,
+    mode: SearchMode,
     expected_results: Vec<EvaluationSearchResult>,
     actual_results: Vec<EvaluationSearchResult>,
     covered_file_count: usize,
@@ -309,7 +316,10 @@ async fn run_evaluation(
     cx.update(|cx| languages::init(language_registry.clone(), node_runtime.clone(), cx))
         .unwrap();
 
-    let mut counts = Counts::default();
+    let mut counts = SEARCH_MODES
+        .into_iter()
+        .map(|mode| (mode, Counts::default()))
+        .collect::<HashMap<_, _>>();
     eprint!("Running evals.");
 
     let mut failures = Vec::new();
@@ -324,13 +334,8 @@ async fn run_evaluation(
 
         eprint!("\r\x1B[2K");
         eprint!(
-            "Running evals. {}/{} covered. {}/{} overlapped. {}/{} files captured. Project: {}...",
-            counts.covered_results,
-            counts.total_results,
-            counts.overlapped_results,
-            counts.total_results,
-            counts.covered_files,
-            counts.total_results,
+            "Running evals. {} Project: {}...",
+            format_counts(&counts),
             evaluation_project.repo
         );
 
@@ -378,13 +383,8 @@ async fn run_evaluation(
     }
 
     eprintln!(
-        "Running evals. {}/{} covered. {}/{} overlapped. {}/{} files captured. {} failed.",
-        counts.covered_results,
-        counts.total_results,
-        counts.overlapped_results,
-        counts.total_results,
-        counts.covered_files,
-        counts.total_results,
+        "Running evals. {} {} failed.",
+        format_counts(&counts),
         failures.len(),
     );
 
@@ -407,7 +407,7 @@ async fn run_eval_project(
     user_store: &Model<UserStore>,
     repo_db_path: PathBuf,
     repo_dir: &Path,
-    counts: &mut Counts,
+    counts: &mut HashMap<SearchMode, Counts>,
     project: Model<Project>,
     embedding_provider: Arc<dyn EmbeddingProvider>,
     fs: Arc<dyn Fs>,
@@ -430,7 +430,12 @@ async fn run_eval_project(
     let project_index = cx.update(|cx| semantic_index.create_project_index(project.clone(), cx))?;
     wait_for_indexing_complete(&project_index, cx, Some(Duration::from_secs(120))).await;
 
-    for query in evaluation_project.queries {
+    // Each query is run in every mode before moving on to the next one.
+    let queries = evaluation_project
+        .queries
+        .iter()
+        .flat_map(|query| SEARCH_MODES.map(|mode| (query, mode)));
+    for (query, mode) in queries {
         let results = {
             // Retry search up to 3 times in case of timeout, network failure, etc.
             let mut retries_remaining = 3;
@@ -439,7 +444,7 @@ async fn run_eval_project(
             loop {
                 match cx.update(|cx| {
                     let project_index = project_index.read(cx);
-                    project_index.search(vec![query.query.clone()], SEARCH_RESULT_LIMIT, cx)
+                    project_index.search(vec![query.query.clone()], SEARCH_RESULT_LIMIT, mode, cx)
                 }) {
                     Ok(task) => match task.await {
                         Ok(answer) => {
@@ -514,12 +519,13 @@ async fn run_eval_project(
 
         let query_results = EvaluationQueryOutcome {
             repo: outcome_repo,
-            query: query.query,
+            query: query.query.clone(),
+            mode,
             total_result_count: query.expected_results.len(),
             covered_result_count: project_covered_result_count,
             overlapped_result_count: project_overlapped_result_count,
             covered_file_count: project_covered_file_count,
-            expected_results: query.expected_results,
+            expected_results: query.expected_results.clone(),
             actual_results: results
                 .iter()
                 .map(|result| EvaluationSearchResult {
@@ -530,6 +536,7 @@ async fn run_eval_project(
             covered_result_indices,
         };
 
+        let counts = counts.entry(mode).or_default();
         counts.overlapped_results += query_results.overlapped_result_count;
         counts.covered_results += query_results.covered_result_count;
         counts.covered_files += query_results.covered_file_count;
@@ -546,6 +553,25 @@ async fn run_eval_project(
     })
 }
 
+fn format_counts(counts: &HashMap<SearchMode, Counts>) -> String {
+    SEARCH_MODES
+        .iter()
+        .map(|mode| {
+            let counts = &counts[mode];
+            format!(
+                "{mode:?}: {}/{} covered, {}/{} overlapped, {}/{} files captured.",
+                counts.covered_results,
+                counts.total_results,
+                counts.overlapped_results,
+                counts.total_results,
+                counts.covered_files,
+                counts.total_results,
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
 async fn wait_for_indexing_complete(
     project_index: &Model<ProjectIndex>,
     cx: &mut AsyncAppContext,
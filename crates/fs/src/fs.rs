@@ -1322,6 +1322,23 @@ impl FakeFs {
         });
     }
 
+    pub fn set_diff_base_for_repo(
+        &self,
+        dot_git: &Path,
+        base: git::repository::DiffBase,
+        contents: &[(&Path, String)],
+    ) {
+        self.with_git_state(dot_git, true, |state| {
+            state.diff_base_contents.insert(
+                base,
+                contents
+                    .iter()
+                    .map(|(path, content)| (path.to_path_buf(), content.clone()))
+                    .collect(),
+            );
+        });
+    }
+
     pub fn set_blame_for_repo(&self, dot_git: &Path, blames: Vec<(&Path, git::blame::Blame)>) {
         self.with_git_state(dot_git, true, |state| {
             state.blames.clear();
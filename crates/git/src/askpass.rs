@@ -0,0 +1,208 @@
+//! Lets git ask the user for credentials while it talks to a remote.
+//!
+//! `GIT_ASKPASS` and `SSH_ASKPASS` point git and ssh at a script that writes each prompt into a
+//! named pipe and prints the answer it reads back from another one. On Zed's side, a thread
+//! forwards the prompts to a [`RemoteDelegate`] and writes the answers back.
+
+use crate::repository::RemoteDelegate;
+use anyhow::Result;
+use std::process::Command;
+
+#[cfg(unix)]
+use std::{
+    io::Write as _,
+    path::Path,
+    sync::atomic::{AtomicBool, Ordering::SeqCst},
+    time::Duration,
+};
+
+#[cfg(unix)]
+const SCRIPT: &str = r#"#!/bin/sh
+printf '%s' "$1" > "$ZED_ASKPASS_DIR/prompt" || exit 1
+response=$(cat "$ZED_ASKPASS_DIR/response") || exit 1
+case "$response" in
+    ok:*) printf '%s\n' "${response#ok:}" ;;
+    *) exit 1 ;;
+esac
+"#;
+
+#[cfg(unix)]
+const SCRIPT_NAME: &str = "askpass.sh";
+#[cfg(unix)]
+const PROMPT_PIPE: &str = "prompt";
+#[cfg(unix)]
+const RESPONSE_PIPE: &str = "response";
+
+/// The askpass script of one git command, along with its pipes.
+/// On Windows, git's own credential manager prompts for credentials instead.
+pub(crate) struct AskPass {
+    #[cfg(unix)]
+    dir: tempfile::TempDir,
+}
+
+impl AskPass {
+    #[cfg(unix)]
+    pub fn new() -> Result<Self> {
+        use std::os::unix::fs::PermissionsExt as _;
+
+        let dir = tempfile::Builder::new().prefix("zed-askpass").tempdir()?;
+        let script_path = dir.path().join(SCRIPT_NAME);
+        std::fs::write(&script_path, SCRIPT)?;
+        std::fs::set_permissions(&script_path, std::fs::Permissions::from_mode(0o700))?;
+        for pipe in [PROMPT_PIPE, RESPONSE_PIPE] {
+            make_pipe(&dir.path().join(pipe))?;
+        }
+        Ok(Self { dir })
+    }
+
+    #[cfg(not(unix))]
+    pub fn new() -> Result<Self> {
+        Ok(Self {})
+    }
+
+    /// Makes a git command prompt through the script, instead of through a terminal it doesn't have.
+    pub fn configure(&self, command: &mut Command) {
+        command.env("GIT_TERMINAL_PROMPT", "0");
+        #[cfg(unix)]
+        {
+            let script_path = self.dir.path().join(SCRIPT_NAME);
+            command
+                .env("GIT_ASKPASS", &script_path)
+                .env("SSH_ASKPASS", &script_path)
+                .env("SSH_ASKPASS_REQUIRE", "force")
+                .env("ZED_ASKPASS_DIR", self.dir.path());
+        }
+    }
+
+    /// Runs `f`, answering the prompts of the commands it runs with the delegate meanwhile.
+    pub fn serve_while<R>(&self, delegate: &RemoteDelegate, f: impl FnOnce() -> R) -> R {
+        #[cfg(unix)]
+        {
+            let stopped = AtomicBool::new(false);
+            std::thread::scope(|scope| {
+                let server = scope.spawn(|| self.serve(delegate, &stopped));
+                let result = f();
+                stopped.store(true, SeqCst);
+                while !server.is_finished() {
+                    // Unblock the server if it's waiting for a prompt that will never come.
+                    open_pipe_for_writing(&self.dir.path().join(PROMPT_PIPE)).ok();
+                    std::thread::sleep(Duration::from_millis(10));
+                }
+                result
+            })
+        }
+
+        #[cfg(not(unix))]
+        {
+            let _ = delegate;
+            f()
+        }
+    }
+
+    #[cfg(unix)]
+    fn serve(&self, delegate: &RemoteDelegate, stopped: &AtomicBool) {
+        let prompt_path = self.dir.path().join(PROMPT_PIPE);
+        let response_path = self.dir.path().join(RESPONSE_PIPE);
+        loop {
+            // Opening the pipe waits until the script opens it to write a prompt.
+            let prompt = match std::fs::read_to_string(&prompt_path) {
+                Ok(prompt) => prompt,
+                Err(error) => {
+                    log::error!("failed to read askpass prompt: {error}");
+                    return;
+                }
+            };
+            if stopped.load(SeqCst) {
+                return;
+            }
+
+            let response = match delegate.ask_pass_blocking(prompt.trim().to_string()) {
+                Some(response) => format!("ok:{response}"),
+                None => "cancel".to_string(),
+            };
+            // The script reads the response right after writing the prompt, unless it got killed.
+            loop {
+                match open_pipe_for_writing(&response_path) {
+                    Ok(mut pipe) => {
+                        if let Err(error) = pipe.write_all(response.as_bytes()) {
+                            log::error!("failed to write askpass response: {error}");
+                        }
+                        break;
+                    }
+                    Err(_) if !stopped.load(SeqCst) => {
+                        std::thread::sleep(Duration::from_millis(10));
+                    }
+                    Err(_) => return,
+                }
+            }
+        }
+    }
+}
+
+#[cfg(unix)]
+fn make_pipe(path: &Path) -> Result<()> {
+    use std::os::unix::ffi::OsStrExt as _;
+
+    let path = std::ffi::CString::new(path.as_os_str().as_bytes())?;
+    // SAFETY: `path` is a valid, nul-terminated C string.
+    if unsafe { libc::mkfifo(path.as_ptr(), 0o600) } != 0 {
+        return Err(std::io::Error::last_os_error().into());
+    }
+    Ok(())
+}
+
+/// Opens a named pipe for writing, failing instead of blocking if nothing reads from it.
+#[cfg(unix)]
+fn open_pipe_for_writing(path: &Path) -> std::io::Result<std::fs::File> {
+    use std::os::unix::fs::OpenOptionsExt as _;
+
+    std::fs::OpenOptions::new()
+        .write(true)
+        .custom_flags(libc::O_NONBLOCK)
+        .open(path)
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_askpass_script() {
+        let askpass = AskPass::new().unwrap();
+        let (delegate, ask_pass_rx, _progress_rx) = RemoteDelegate::new();
+        let answers = std::thread::spawn(move || {
+            let mut prompts = Vec::new();
+            while let Ok(request) = ask_pass_rx.recv_blocking() {
+                let response = prompts.is_empty().then(|| "hunter2".to_string());
+                prompts.push(request.prompt.clone());
+                request.respond(response);
+            }
+            prompts
+        });
+
+        let run_script = |prompt: &str| {
+            let mut command = Command::new(askpass.dir.path().join(SCRIPT_NAME));
+            command.arg(prompt);
+            askpass.configure(&mut command);
+            command.output().unwrap()
+        };
+        let (answered, cancelled) = askpass.serve_while(&delegate, || {
+            (
+                run_script("Password for 'https://example.com': "),
+                run_script("Password for 'https://example.com': "),
+            )
+        });
+        drop(delegate);
+
+        assert!(answered.status.success());
+        assert_eq!(String::from_utf8_lossy(&answered.stdout), "hunter2\n");
+        assert!(!cancelled.status.success());
+        assert_eq!(
+            answers.join().unwrap(),
+            [
+                "Password for 'https://example.com':",
+                "Password for 'https://example.com':"
+            ]
+        );
+    }
+}
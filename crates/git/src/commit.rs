@@ -1,8 +1,33 @@
-use crate::Oid;
-use anyhow::{anyhow, Result};
+use crate::{repository::RepoPath, Oid};
+use anyhow::{anyhow, Context as _, Result};
 use collections::HashMap;
+use gpui::SharedString;
 use std::path::Path;
 
+/// The `git log` format parsed by [`parse_log`]: one commit per line, fields separated by `\x1f`.
+pub const LOG_FORMAT: &str = "--format=%H%x1f%ct%x1f%an%x1f%ae%x1f%s";
+
+/// A commit listed by [`crate::repository::GitRepository::log`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CommitSummary {
+    pub sha: SharedString,
+    pub subject: SharedString,
+    pub author_name: SharedString,
+    pub author_email: SharedString,
+    /// Commit timestamp, normalized to Unix Epoch format.
+    pub commit_timestamp: i64,
+}
+
+/// A file changed by a commit, with its contents before and after the commit.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CommitFileDiff {
+    pub path: RepoPath,
+    /// `None` if the commit added the file.
+    pub old_text: Option<String>,
+    /// `None` if the commit deleted the file.
+    pub new_text: Option<String>,
+}
+
 pub fn get_messages(working_directory: &Path, shas: &[Oid]) -> Result<HashMap<Oid, String>> {
     if shas.is_empty() {
         return Ok(HashMap::default());
@@ -36,3 +61,68 @@ pub fn get_messages(working_directory: &Path, shas: &[Oid]) -> Result<HashMap<Oi
         )
         .collect::<HashMap<Oid, String>>())
 }
+
+pub fn parse_log(output: &str) -> Result<Vec<CommitSummary>> {
+    output
+        .lines()
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            let mut fields = line.splitn(5, '\x1f');
+            let mut next_field = |name: &str| {
+                fields
+                    .next()
+                    .with_context(|| format!("missing {name} in git log line {line:?}"))
+            };
+            let sha = next_field("sha")?;
+            let commit_timestamp = next_field("timestamp")?
+                .parse()
+                .with_context(|| format!("invalid timestamp in git log line {line:?}"))?;
+            let author_name = next_field("author name")?;
+            let author_email = next_field("author email")?;
+            let subject = next_field("subject")?;
+            Ok(CommitSummary {
+                sha: sha.to_string().into(),
+                subject: subject.to_string().into(),
+                author_name: author_name.to_string().into(),
+                author_email: author_email.to_string().into(),
+                commit_timestamp,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_log() {
+        let output = "\
+            4d5c1b1f2f0b9c1f55c9e2d0a1c9ee0f3ed06a5f\x1f1700000000\x1fJane Doe\x1fjane@example.com\x1fFix the parser\n\
+            0a1b2c3d4e5f60718293a4b5c6d7e8f901234567\x1f1690000000\x1fJohn Roe\x1fjohn@example.com\x1fInitial commit: a|b\n";
+        let commits = parse_log(output).unwrap();
+        assert_eq!(
+            commits,
+            vec![
+                CommitSummary {
+                    sha: "4d5c1b1f2f0b9c1f55c9e2d0a1c9ee0f3ed06a5f".into(),
+                    subject: "Fix the parser".into(),
+                    author_name: "Jane Doe".into(),
+                    author_email: "jane@example.com".into(),
+                    commit_timestamp: 1700000000,
+                },
+                CommitSummary {
+                    sha: "0a1b2c3d4e5f60718293a4b5c6d7e8f901234567".into(),
+                    subject: "Initial commit: a|b".into(),
+                    author_name: "John Roe".into(),
+                    author_email: "john@example.com".into(),
+                    commit_timestamp: 1690000000,
+                },
+            ]
+        );
+
+        assert!(parse_log("").unwrap().is_empty());
+        assert!(parse_log("abc\x1fnot-a-number\x1fa\x1fb\x1fc").is_err());
+        assert!(parse_log("abc\x1f1700000000").is_err());
+    }
+}
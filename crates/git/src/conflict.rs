@@ -0,0 +1,256 @@
+use std::ops::Range;
+
+const OURS_MARKER: &str = "<<<<<<<";
+const BASE_MARKER: &str = "|||||||";
+const SEPARATOR_MARKER: &str = "=======";
+const THEIRS_MARKER: &str = ">>>>>>>";
+
+/// A conflict left in a file by a merge, delimited by conflict markers:
+///
+/// ```text
+/// <<<<<<< ours label
+/// our side of the conflict
+/// ||||||| base label
+/// the common ancestor, only present with the `diff3` and `zdiff3` conflict styles
+/// =======
+/// their side of the conflict
+/// >>>>>>> theirs label
+/// ```
+///
+/// All ranges are byte offsets into the parsed text. The side ranges cover the lines between
+/// the markers, including their trailing newline.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConflictRegion {
+    /// The whole conflict, from the start of the `<<<<<<<` line to the end of the `>>>>>>>` line.
+    pub range: Range<usize>,
+    pub ours: Range<usize>,
+    pub base: Option<Range<usize>>,
+    pub theirs: Range<usize>,
+    pub ours_label: String,
+    pub theirs_label: String,
+}
+
+/// The contents of a conflicted file in each stage of the index.
+/// A side is `None` when the file does not exist in it, for example when it was deleted on one side.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ConflictStages {
+    pub base: Option<String>,
+    pub ours: Option<String>,
+    pub theirs: Option<String>,
+}
+
+enum State {
+    Outside,
+    Ours {
+        start: usize,
+        ours_label: String,
+        ours_start: usize,
+    },
+    Base {
+        start: usize,
+        ours_label: String,
+        ours: Range<usize>,
+        base_start: usize,
+    },
+    Theirs {
+        start: usize,
+        ours_label: String,
+        ours: Range<usize>,
+        base: Option<Range<usize>>,
+        theirs_start: usize,
+    },
+}
+
+/// Finds all complete conflicts in the given text, in order.
+/// Conflicts missing any of their markers are ignored.
+pub fn parse_conflicts(text: &str) -> Vec<ConflictRegion> {
+    let mut conflicts = Vec::new();
+    let mut state = State::Outside;
+    let mut line_start = 0;
+    for line in text.split_inclusive('\n') {
+        let line_end = line_start + line.len();
+        let content = line.trim_end_matches(['\n', '\r']);
+
+        // A new conflict start marker always starts a new conflict, dropping an unterminated one.
+        if let Some(label) = marker_label(content, OURS_MARKER) {
+            state = State::Ours {
+                start: line_start,
+                ours_label: label.to_string(),
+                ours_start: line_end,
+            };
+            line_start = line_end;
+            continue;
+        }
+
+        state = match state {
+            State::Ours {
+                start,
+                ours_label,
+                ours_start,
+            } => {
+                if marker_label(content, BASE_MARKER).is_some() {
+                    State::Base {
+                        start,
+                        ours_label,
+                        ours: ours_start..line_start,
+                        base_start: line_end,
+                    }
+                } else if content == SEPARATOR_MARKER {
+                    State::Theirs {
+                        start,
+                        ours_label,
+                        ours: ours_start..line_start,
+                        base: None,
+                        theirs_start: line_end,
+                    }
+                } else {
+                    State::Ours {
+                        start,
+                        ours_label,
+                        ours_start,
+                    }
+                }
+            }
+            State::Base {
+                start,
+                ours_label,
+                ours,
+                base_start,
+            } => {
+                if content == SEPARATOR_MARKER {
+                    State::Theirs {
+                        start,
+                        ours_label,
+                        ours,
+                        base: Some(base_start..line_start),
+                        theirs_start: line_end,
+                    }
+                } else {
+                    State::Base {
+                        start,
+                        ours_label,
+                        ours,
+                        base_start,
+                    }
+                }
+            }
+            State::Theirs {
+                start,
+                ours_label,
+                ours,
+                base,
+                theirs_start,
+            } => {
+                if let Some(theirs_label) = marker_label(content, THEIRS_MARKER) {
+                    conflicts.push(ConflictRegion {
+                        range: start..line_end,
+                        ours,
+                        base,
+                        theirs: theirs_start..line_start,
+                        ours_label,
+                        theirs_label: theirs_label.to_string(),
+                    });
+                    State::Outside
+                } else {
+                    State::Theirs {
+                        start,
+                        ours_label,
+                        ours,
+                        base,
+                        theirs_start,
+                    }
+                }
+            }
+            State::Outside => State::Outside,
+        };
+        line_start = line_end;
+    }
+    conflicts
+}
+
+/// Returns the label following a marker, if the line is that marker.
+fn marker_label<'a>(line: &'a str, marker: &str) -> Option<&'a str> {
+    let rest = line.strip_prefix(marker)?;
+    if rest.is_empty() {
+        Some("")
+    } else {
+        rest.strip_prefix(' ')
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use unindent::Unindent as _;
+
+    #[test]
+    fn test_parse_conflicts() {
+        let text = "
+            fn main() {
+            <<<<<<< HEAD
+                println!(\"ours\");
+            =======
+                println!(\"theirs\");
+                println!(\"theirs again\");
+            >>>>>>> feature
+            }
+            <<<<<<< HEAD
+            a
+            ||||||| base
+            b
+            =======
+            >>>>>>> feature
+        "
+        .unindent();
+        let conflicts = parse_conflicts(&text);
+        assert_eq!(conflicts.len(), 2);
+
+        let first = &conflicts[0];
+        assert_eq!(
+            &text[first.range.clone()],
+            "<<<<<<< HEAD\n    println!(\"ours\");\n=======\n    println!(\"theirs\");\n    println!(\"theirs again\");\n>>>>>>> feature\n"
+        );
+        assert_eq!(&text[first.ours.clone()], "    println!(\"ours\");\n");
+        assert_eq!(first.base, None);
+        assert_eq!(
+            &text[first.theirs.clone()],
+            "    println!(\"theirs\");\n    println!(\"theirs again\");\n"
+        );
+        assert_eq!(first.ours_label, "HEAD");
+        assert_eq!(first.theirs_label, "feature");
+
+        let second = &conflicts[1];
+        assert_eq!(&text[second.ours.clone()], "a\n");
+        assert_eq!(&text[second.base.clone().unwrap()], "b\n");
+        assert_eq!(&text[second.theirs.clone()], "");
+        assert_eq!(second.range.end, text.len());
+    }
+
+    #[test]
+    fn test_parse_incomplete_conflicts() {
+        let text = "
+            <<<<<<< HEAD
+            unterminated
+            =======
+            <<<<<<< HEAD
+            ours
+            =======
+            theirs
+            >>>>>>> other
+            ========
+            >>>>>>>not a marker
+        "
+        .unindent();
+        let conflicts = parse_conflicts(&text);
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(&text[conflicts[0].ours.clone()], "ours\n");
+        assert_eq!(&text[conflicts[0].theirs.clone()], "theirs\n");
+        assert_eq!(conflicts[0].theirs_label, "other");
+
+        assert_eq!(
+            parse_conflicts("<<<<<<< a\r\nx\r\n=======\r\ny\r\n>>>>>>> b").len(),
+            1
+        );
+    }
+}
@@ -286,6 +286,57 @@ impl BufferDiff {
     }
 }
 
+/// Returns the index text with the staged hunks that overlap any of the given byte ranges of the
+/// HEAD text reverted to their contents in HEAD, keeping the other staged changes.
+pub fn unstage_hunks(head_text: &str, index_text: &str, head_ranges: &[Range<usize>]) -> String {
+    let Some(patch) = BufferDiff::diff(head_text, index_text) else {
+        return index_text.to_string();
+    };
+    let head_line_offsets = line_offsets(head_text);
+    let index_line_offsets = line_offsets(index_text);
+    // Hunk line numbers are 1-based, and point at the line before the hunk when it has no lines.
+    let byte_range = |line_offsets: &[usize], start: u32, len: u32| {
+        let start = if len == 0 { start } else { start - 1 };
+        let (start, end) = (start as usize, (start + len) as usize);
+        line_offsets[start.min(line_offsets.len() - 1)]
+            ..line_offsets[end.min(line_offsets.len() - 1)]
+    };
+
+    let mut new_index_text = index_text.to_string();
+    for hunk_index in (0..patch.num_hunks()).rev() {
+        let Ok((hunk, _)) = patch.hunk(hunk_index) else {
+            continue;
+        };
+        let head_range = byte_range(&head_line_offsets, hunk.old_start(), hunk.old_lines());
+        if head_ranges
+            .iter()
+            .any(|range| ranges_overlap(range, &head_range))
+        {
+            let index_range = byte_range(&index_line_offsets, hunk.new_start(), hunk.new_lines());
+            new_index_text.replace_range(index_range, &head_text[head_range]);
+        }
+    }
+    new_index_text
+}
+
+/// Empty ranges, which stand for insertions and deletions, overlap the ranges they touch.
+fn ranges_overlap(a: &Range<usize>, b: &Range<usize>) -> bool {
+    if a.is_empty() || b.is_empty() {
+        a.start <= b.end && b.start <= a.end
+    } else {
+        a.start < b.end && b.start < a.end
+    }
+}
+
+/// The offset at which each line starts, followed by the length of the text.
+fn line_offsets(text: &str) -> Vec<usize> {
+    iter::once(0)
+        .chain(text.match_indices('\n').map(|(ix, _)| ix + 1))
+        .filter(|offset| *offset < text.len())
+        .chain(iter::once(text.len()))
+        .collect()
+}
+
 /// Range (crossing new lines), old, new
 #[cfg(any(test, feature = "test-support"))]
 #[track_caller]
@@ -327,6 +378,28 @@ mod tests {
     use text::{Buffer, BufferId};
     use unindent::Unindent as _;
 
+    #[test]
+    fn test_unstage_hunks() {
+        let head_text = "one\ntwo\nthree\nfour\nfive\n";
+        let index_text = "one\nTWO\nthree\nfive\nsix\n";
+
+        // Only the hunk overlapping "two\n" is unstaged.
+        assert_eq!(
+            unstage_hunks(head_text, index_text, &[4..8]),
+            "one\ntwo\nthree\nfive\nsix\n"
+        );
+        // The deletion of "four\n" and the addition of "six\n" are unstaged.
+        assert_eq!(
+            unstage_hunks(head_text, index_text, &[14..19, 24..24]),
+            "one\nTWO\nthree\nfour\nfive\n"
+        );
+        assert_eq!(
+            unstage_hunks(head_text, index_text, &[0..head_text.len()]),
+            head_text
+        );
+        assert_eq!(unstage_hunks(head_text, index_text, &[]), index_text);
+    }
+
     #[test]
     fn test_buffer_diff_simple() {
         let diff_base = "
@@ -1,5 +1,7 @@
+mod askpass;
 pub mod blame;
 pub mod commit;
+pub mod conflict;
 pub mod diff;
 mod hosting_provider;
 mod remote;
@@ -1,7 +1,14 @@
 use crate::GitHostingProviderRegistry;
-use crate::{blame::Blame, status::GitStatus};
+use crate::{
+    askpass::AskPass,
+    blame::Blame,
+    commit::{self, CommitFileDiff, CommitSummary},
+    conflict::ConflictStages,
+    status::{GitStatus, StageStatus},
+};
 use anyhow::{Context, Result};
 use collections::{HashMap, HashSet};
+use futures::{future::BoxFuture, FutureExt as _};
 use git2::BranchType;
 use gpui::SharedString;
 use parking_lot::Mutex;
@@ -9,7 +16,11 @@ use rope::Rope;
 use serde::{Deserialize, Serialize};
 use std::{
     cmp::Ordering,
+    ffi::OsStr,
+    io::{BufReader, Read, Write},
+    ops::Range,
     path::{Component, Path, PathBuf},
+    process::Stdio,
     sync::Arc,
 };
 use sum_tree::MapSeekTarget;
@@ -21,6 +32,147 @@ pub struct Branch {
     pub name: SharedString,
     /// Timestamp of most recent commit, normalized to Unix Epoch format.
     pub unix_timestamp: Option<i64>,
+    pub upstream: Option<UpstreamTracking>,
+}
+
+/// How far a local branch and the remote branch it tracks have diverged.
+#[derive(Clone, Copy, Debug, Default, Hash, PartialEq, Eq)]
+pub struct UpstreamTracking {
+    /// The number of commits on the local branch that aren't on the upstream branch.
+    pub ahead: u32,
+    /// The number of commits on the upstream branch that aren't on the local branch.
+    pub behind: u32,
+}
+
+impl UpstreamTracking {
+    /// A short summary like `↑2 ↓1`, or `None` if both branches point at the same commit.
+    pub fn summary(&self) -> Option<String> {
+        match (self.ahead, self.behind) {
+            (0, 0) => None,
+            (ahead, 0) => Some(format!("↑{ahead}")),
+            (0, behind) => Some(format!("↓{behind}")),
+            (ahead, behind) => Some(format!("↑{ahead} ↓{behind}")),
+        }
+    }
+}
+
+/// A git operation that talks to a remote.
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub enum RemoteOperation {
+    Fetch,
+    Pull,
+    Push,
+}
+
+impl RemoteOperation {
+    /// What to show while the operation is running, like `Fetching…`.
+    pub fn progress_label(&self) -> &'static str {
+        match self {
+            RemoteOperation::Fetch => "Fetching…",
+            RemoteOperation::Pull => "Pulling…",
+            RemoteOperation::Push => "Pushing…",
+        }
+    }
+}
+
+impl std::fmt::Display for RemoteOperation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RemoteOperation::Fetch => write!(f, "Fetch"),
+            RemoteOperation::Pull => write!(f, "Pull"),
+            RemoteOperation::Push => write!(f, "Push"),
+        }
+    }
+}
+
+/// A prompt for a username, password or passphrase that a remote operation needs.
+#[derive(Clone, Debug)]
+pub struct AskPassRequest {
+    pub prompt: String,
+    response_tx: smol::channel::Sender<Option<String>>,
+}
+
+impl AskPassRequest {
+    pub fn new(prompt: String) -> (Self, smol::channel::Receiver<Option<String>>) {
+        let (response_tx, response_rx) = smol::channel::bounded(1);
+        (
+            Self {
+                prompt,
+                response_tx,
+            },
+            response_rx,
+        )
+    }
+
+    /// Answers the prompt. Answering `None`, or dropping the request, cancels the operation.
+    pub fn respond(&self, response: Option<String>) {
+        self.response_tx.try_send(response).ok();
+    }
+}
+
+impl PartialEq for AskPassRequest {
+    fn eq(&self, other: &Self) -> bool {
+        self.prompt == other.prompt && self.response_tx.same_channel(&other.response_tx)
+    }
+}
+
+/// Where a remote operation sends its credential prompts and the progress reported by git.
+#[derive(Clone, Debug)]
+pub struct RemoteDelegate {
+    pub ask_pass_tx: smol::channel::Sender<AskPassRequest>,
+    pub progress_tx: smol::channel::Sender<String>,
+}
+
+impl RemoteDelegate {
+    pub fn new() -> (
+        Self,
+        smol::channel::Receiver<AskPassRequest>,
+        smol::channel::Receiver<String>,
+    ) {
+        let (ask_pass_tx, ask_pass_rx) = smol::channel::unbounded();
+        let (progress_tx, progress_rx) = smol::channel::unbounded();
+        (
+            Self {
+                ask_pass_tx,
+                progress_tx,
+            },
+            ask_pass_rx,
+            progress_rx,
+        )
+    }
+
+    /// Asks for a credential and waits for the answer, blocking the current thread.
+    pub(crate) fn ask_pass_blocking(&self, prompt: String) -> Option<String> {
+        let (request, response_rx) = AskPassRequest::new(prompt);
+        self.ask_pass_tx.send_blocking(request).ok()?;
+        response_rx.recv_blocking().ok().flatten()
+    }
+}
+
+/// What the working copy gets compared against when computing buffer diffs.
+#[derive(Clone, Debug, Default, Hash, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DiffBase {
+    /// The index, so that diffs show the unstaged changes.
+    #[default]
+    Index,
+    /// The current commit, so that diffs show all uncommitted changes.
+    Head,
+    /// The tip of a branch, or any other revision.
+    Branch(String),
+    /// The common ancestor of the current commit and a branch,
+    /// so that diffs show everything changed since the current branch forked off it.
+    MergeBase(String),
+}
+
+impl std::fmt::Display for DiffBase {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DiffBase::Index => write!(f, "Index"),
+            DiffBase::Head => write!(f, "HEAD"),
+            DiffBase::Branch(branch) => write!(f, "{branch}"),
+            DiffBase::MergeBase(branch) => write!(f, "Merge base with {branch}"),
+        }
+    }
 }
 
 pub trait GitRepository: Send + Sync {
@@ -30,6 +182,25 @@ pub trait GitRepository: Send + Sync {
     /// Note that for symlink entries, this will return the contents of the symlink, not the target.
     fn load_index_text(&self, relative_file_path: &Path) -> Option<String>;
 
+    /// Loads a git repository entry's contents in the given diff base.
+    /// Like [`Self::load_index_text`], this returns `None` for symlinks and for paths missing from the base.
+    fn load_diff_base_text(&self, base: &DiffBase, relative_file_path: &Path) -> Option<String> {
+        self.load_diff_base_texts(base, &[relative_file_path])
+            .pop()
+            .flatten()
+    }
+
+    /// Loads the contents of several entries in the given diff base, which is only resolved once.
+    fn load_diff_base_texts(
+        &self,
+        base: &DiffBase,
+        relative_file_paths: &[&Path],
+    ) -> Vec<Option<String>>;
+
+    /// Returns the paths whose working copy contents differ from the given diff base.
+    /// Untracked files are not included.
+    fn changed_paths(&self, base: &DiffBase) -> Result<Vec<RepoPath>>;
+
     /// Returns the URL of the remote with the given name.
     fn remote_url(&self, name: &str) -> Option<String>;
     fn branch_name(&self) -> Option<String>;
@@ -48,6 +219,47 @@ pub trait GitRepository: Send + Sync {
 
     /// Returns the path to the repository, typically the `.git` folder.
     fn dot_git_dir(&self) -> PathBuf;
+
+    /// Updates the index to match the working copy for the given paths,
+    /// removing index entries for paths that no longer exist on disk.
+    fn stage_paths(&self, paths: &[RepoPath]) -> Result<()>;
+
+    /// Resets the index entries for the given paths to their state in HEAD.
+    fn unstage_paths(&self, paths: &[RepoPath]) -> Result<()>;
+
+    /// Replaces the index contents of a single path, without touching the working copy.
+    /// Passing `None` removes the path from the index.
+    fn set_index_text(&self, path: &RepoPath, content: Option<String>) -> Result<()>;
+
+    /// Resets the staged changes of a single path that overlap the given byte ranges of its HEAD
+    /// text back to HEAD, keeping its other staged changes and leaving the working copy untouched.
+    fn unstage_hunks(&self, path: &RepoPath, head_ranges: &[Range<usize>]) -> Result<()>;
+
+    /// Throws away working copy changes for the given paths: tracked paths are restored
+    /// from the index, untracked paths are deleted.
+    fn discard_paths(&self, paths: &[RepoPath]) -> Result<()>;
+
+    /// Creates a new commit on the current branch from the contents of the index.
+    fn commit(&self, message: &str) -> Result<()>;
+
+    /// Lists up to `limit` commits reachable from HEAD, newest first, after skipping the `skip` newest ones.
+    /// When a path is given, only the commits that changed it are listed, following renames.
+    fn log(&self, path: Option<&RepoPath>, skip: usize, limit: usize)
+        -> Result<Vec<CommitSummary>>;
+
+    /// Returns the text files changed by the given commit, compared to its first parent.
+    fn commit_diff(&self, sha: &str) -> Result<Vec<CommitFileDiff>>;
+
+    /// Loads the common ancestor, our and their versions of a conflicted path from the index stages.
+    fn conflict_stages(&self, path: &RepoPath) -> Result<ConflictStages>;
+
+    /// Fetches, pulls or pushes the current branch, from or to the remote of its upstream branch.
+    /// Pushing a branch without an upstream branch publishes it on `origin` and tracks it.
+    fn remote_operation(
+        &self,
+        operation: RemoteOperation,
+        delegate: RemoteDelegate,
+    ) -> BoxFuture<'static, Result<()>>;
 }
 
 impl std::fmt::Debug for dyn GitRepository {
@@ -74,6 +286,89 @@ impl RealGitRepository {
             hosting_provider_registry,
         }
     }
+
+    fn working_directory(&self) -> Result<PathBuf> {
+        self.repository
+            .lock()
+            .workdir()
+            .context("failed to read git work directory")
+            .map(Path::to_path_buf)
+    }
+
+    fn run_git_command<I, S>(&self, args: I, stdin: Option<&str>) -> Result<String>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        let working_directory = self.working_directory()?;
+        let mut child = util::command::new_std_command(&self.git_binary_path)
+            .current_dir(&working_directory)
+            .args(args)
+            .stdin(if stdin.is_some() {
+                Stdio::piped()
+            } else {
+                Stdio::null()
+            })
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .context("failed to start git process")?;
+
+        if let Some(stdin) = stdin {
+            let mut pipe = child
+                .stdin
+                .take()
+                .context("failed to get pipe to stdin of git process")?;
+            pipe.write_all(stdin.as_bytes())?;
+            pipe.flush()?;
+        }
+
+        let output = child
+            .wait_with_output()
+            .context("failed to read git process output")?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            anyhow::bail!("git process failed: {}", stderr.trim());
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+
+    fn remote_operation_args(&self, operation: RemoteOperation) -> Result<Vec<String>> {
+        let args = match operation {
+            RemoteOperation::Fetch => vec!["fetch", "--progress"],
+            RemoteOperation::Pull => vec!["pull", "--progress", "--no-edit"],
+            RemoteOperation::Push => {
+                let repo = self.repository.lock();
+                let head = repo.head().context("failed to read HEAD")?;
+                anyhow::ensure!(
+                    head.is_branch(),
+                    "HEAD is detached, there is no branch to push"
+                );
+                if git2::Branch::wrap(head).upstream().is_ok() {
+                    vec!["push", "--progress"]
+                } else {
+                    let remotes = repo.remotes()?;
+                    let remotes = remotes.iter().flatten().collect::<Vec<_>>();
+                    let remote = match remotes.as_slice() {
+                        [] => anyhow::bail!("the repository has no remote to push to"),
+                        [remote] => remote.to_string(),
+                        _ if remotes.contains(&"origin") => "origin".to_string(),
+                        _ => anyhow::bail!(
+                            "the current branch has no upstream branch and there is no origin remote"
+                        ),
+                    };
+                    return Ok(vec![
+                        "push".to_string(),
+                        "--progress".to_string(),
+                        "--set-upstream".to_string(),
+                        remote,
+                        "HEAD".to_string(),
+                    ]);
+                }
+            }
+        };
+        Ok(args.into_iter().map(String::from).collect())
+    }
 }
 
 // https://git-scm.com/book/en/v2/Git-Internals-Git-Objects
@@ -115,6 +410,71 @@ impl GitRepository for RealGitRepository {
         None
     }
 
+    fn load_diff_base_texts(
+        &self,
+        base: &DiffBase,
+        relative_file_paths: &[&Path],
+    ) -> Vec<Option<String>> {
+        fn logic(
+            repo: &git2::Repository,
+            tree: &git2::Tree,
+            relative_file_path: &Path,
+        ) -> Result<Option<String>> {
+            check_path_to_repo_path_errors(relative_file_path)?;
+            let entry = match tree.get_path(relative_file_path) {
+                Ok(entry) => entry,
+                Err(error) if error.code() == git2::ErrorCode::NotFound => return Ok(None),
+                Err(error) => return Err(error.into()),
+            };
+            if entry.kind() != Some(git2::ObjectType::Blob)
+                || entry.filemode() == GIT_MODE_SYMLINK as i32
+            {
+                return Ok(None);
+            }
+
+            let content = repo.find_blob(entry.id())?.content().to_owned();
+            Ok(Some(String::from_utf8(content)?))
+        }
+
+        if *base == DiffBase::Index {
+            return relative_file_paths
+                .iter()
+                .map(|path| self.load_index_text(path))
+                .collect();
+        }
+        let repo = self.repository.lock();
+        let tree = match diff_base_tree(&repo, base) {
+            Ok(tree) => tree,
+            Err(err) => {
+                log::error!("Error loading {base} tree: {:?}", err);
+                None
+            }
+        };
+        relative_file_paths
+            .iter()
+            .map(|path| match logic(&repo, tree.as_ref()?, path) {
+                Ok(value) => value,
+                Err(err) => {
+                    log::error!("Error loading {base} text: {:?}", err);
+                    None
+                }
+            })
+            .collect()
+    }
+
+    fn changed_paths(&self, base: &DiffBase) -> Result<Vec<RepoPath>> {
+        let repo = self.repository.lock();
+        let diff = match diff_base_tree(&repo, base)? {
+            Some(tree) => repo.diff_tree_to_workdir_with_index(Some(&tree), None)?,
+            None => repo.diff_index_to_workdir(None, None)?,
+        };
+        Ok(diff
+            .deltas()
+            .filter_map(|delta| delta.new_file().path().or(delta.old_file().path()))
+            .map(RepoPath::from)
+            .collect())
+    }
+
     fn remote_url(&self, name: &str) -> Option<String> {
         let repo = self.repository.lock();
         let remote = repo.find_remote(name).ok()?;
@@ -166,6 +526,15 @@ impl GitRepository for RealGitRepository {
                         .ok()
                         .flatten()
                         .map(|name| name.to_string().into())?;
+                    let upstream = branch.upstream().ok().and_then(|upstream| {
+                        let (ahead, behind) = repo
+                            .graph_ahead_behind(branch.get().target()?, upstream.get().target()?)
+                            .ok()?;
+                        Some(UpstreamTracking {
+                            ahead: ahead as u32,
+                            behind: behind as u32,
+                        })
+                    });
                     let timestamp = branch.get().peel_to_commit().ok()?.time();
                     let unix_timestamp = timestamp.seconds();
                     let timezone_offset = timestamp.offset_minutes();
@@ -177,6 +546,7 @@ impl GitRepository for RealGitRepository {
                         is_head,
                         name,
                         unix_timestamp: Some(unix_timestamp.to_offset(utc_offset).unix_timestamp()),
+                        upstream,
                     })
                 })
             })
@@ -225,6 +595,251 @@ impl GitRepository for RealGitRepository {
             self.hosting_provider_registry.clone(),
         )
     }
+
+    fn stage_paths(&self, paths: &[RepoPath]) -> Result<()> {
+        if paths.is_empty() {
+            return Ok(());
+        }
+        let mut args = vec![
+            OsStr::new("update-index"),
+            OsStr::new("--add"),
+            OsStr::new("--remove"),
+            OsStr::new("--"),
+        ];
+        args.extend(paths.iter().map(|path| path.as_os_str()));
+        self.run_git_command(args, None)?;
+        Ok(())
+    }
+
+    fn unstage_paths(&self, paths: &[RepoPath]) -> Result<()> {
+        if paths.is_empty() {
+            return Ok(());
+        }
+        let has_head = self.repository.lock().head().is_ok();
+        // Without a HEAD commit there is nothing to reset to, so unstaging
+        // means dropping the paths from the index altogether.
+        let mut args = if has_head {
+            vec![OsStr::new("reset"), OsStr::new("--quiet")]
+        } else {
+            vec![
+                OsStr::new("rm"),
+                OsStr::new("--cached"),
+                OsStr::new("--quiet"),
+            ]
+        };
+        args.push(OsStr::new("--"));
+        args.extend(paths.iter().map(|path| path.as_os_str()));
+        self.run_git_command(args, None)?;
+        Ok(())
+    }
+
+    fn set_index_text(&self, path: &RepoPath, content: Option<String>) -> Result<()> {
+        check_path_to_repo_path_errors(path)?;
+        let Some(content) = content else {
+            self.run_git_command(
+                [
+                    OsStr::new("update-index"),
+                    OsStr::new("--force-remove"),
+                    OsStr::new("--"),
+                    path.as_os_str(),
+                ],
+                None,
+            )?;
+            return Ok(());
+        };
+
+        const STAGE_NORMAL: i32 = 0;
+        const GIT_MODE_FILE: u32 = 0o100644;
+        let mode = self
+            .repository
+            .lock()
+            .index()?
+            .get_path(path, STAGE_NORMAL)
+            .map_or(GIT_MODE_FILE, |entry| entry.mode);
+        let sha = self.run_git_command(["hash-object", "-w", "--stdin"], Some(&content))?;
+        let cache_info = format!("{:o},{},{}", mode, sha.trim(), path.to_string_lossy());
+        self.run_git_command(
+            ["update-index", "--add", "--cacheinfo", cache_info.as_str()],
+            None,
+        )?;
+        Ok(())
+    }
+
+    fn unstage_hunks(&self, path: &RepoPath, head_ranges: &[Range<usize>]) -> Result<()> {
+        check_path_to_repo_path_errors(path)?;
+        let Some(index_text) = self.load_index_text(path) else {
+            return Ok(());
+        };
+        match self.load_diff_base_text(&DiffBase::Head, path) {
+            Some(head_text) => self.set_index_text(
+                path,
+                Some(crate::diff::unstage_hunks(
+                    &head_text,
+                    &index_text,
+                    head_ranges,
+                )),
+            ),
+            // Files missing from HEAD are staged as a whole.
+            None => self.unstage_paths(&[path.clone()]),
+        }
+    }
+
+    fn discard_paths(&self, paths: &[RepoPath]) -> Result<()> {
+        const STAGE_NORMAL: i32 = 0;
+        let (tracked, untracked): (Vec<_>, Vec<_>) = {
+            let repo = self.repository.lock();
+            let index = repo.index()?;
+            for path in paths {
+                check_path_to_repo_path_errors(path)?;
+            }
+            paths
+                .iter()
+                .partition(|path| index.get_path(path, STAGE_NORMAL).is_some())
+        };
+
+        if !tracked.is_empty() {
+            let mut args = vec![
+                OsStr::new("checkout"),
+                OsStr::new("--quiet"),
+                OsStr::new("--"),
+            ];
+            args.extend(tracked.iter().map(|path| path.as_os_str()));
+            self.run_git_command(args, None)?;
+        }
+        if !untracked.is_empty() {
+            let mut args = vec![
+                OsStr::new("clean"),
+                OsStr::new("--force"),
+                OsStr::new("--quiet"),
+                OsStr::new("--"),
+            ];
+            args.extend(untracked.iter().map(|path| path.as_os_str()));
+            self.run_git_command(args, None)?;
+        }
+        Ok(())
+    }
+
+    fn commit(&self, message: &str) -> Result<()> {
+        anyhow::ensure!(!message.trim().is_empty(), "commit message is empty");
+        self.run_git_command(
+            ["commit", "--quiet", "--cleanup=strip", "--file=-"],
+            Some(message),
+        )?;
+        Ok(())
+    }
+
+    fn log(
+        &self,
+        path: Option<&RepoPath>,
+        skip: usize,
+        limit: usize,
+    ) -> Result<Vec<CommitSummary>> {
+        if self.repository.lock().head().is_err() {
+            return Ok(Vec::new());
+        }
+        let mut args = vec![
+            "log".to_string(),
+            commit::LOG_FORMAT.to_string(),
+            format!("--skip={skip}"),
+            format!("--max-count={limit}"),
+        ];
+        if let Some(path) = path {
+            args.push("--follow".to_string());
+            args.push("--".to_string());
+            args.push(path.to_string_lossy().into_owned());
+        }
+        let output = self.run_git_command(args, None)?;
+        commit::parse_log(&output)
+    }
+
+    fn commit_diff(&self, sha: &str) -> Result<Vec<CommitFileDiff>> {
+        let repo = self.repository.lock();
+        let commit = repo
+            .revparse_single(sha)
+            .and_then(|object| object.peel_to_commit())
+            .with_context(|| format!("failed to find commit {sha}"))?;
+        let new_tree = commit.tree()?;
+        let old_tree = match commit.parent(0) {
+            Ok(parent) => Some(parent.tree()?),
+            Err(_) => None,
+        };
+        let mut diff = repo.diff_tree_to_tree(old_tree.as_ref(), Some(&new_tree), None)?;
+        diff.find_similar(None)?;
+
+        // Submodules and binary files have no text to show, skip them.
+        let blob_text = |id: git2::Oid| -> Option<Option<String>> {
+            if id.is_zero() {
+                return Some(None);
+            }
+            let blob = repo.find_blob(id).ok()?;
+            if blob.is_binary() {
+                return None;
+            }
+            Some(Some(String::from_utf8_lossy(blob.content()).into_owned()))
+        };
+        let mut files = Vec::new();
+        for delta in diff.deltas() {
+            let Some(path) = delta.new_file().path().or(delta.old_file().path()) else {
+                continue;
+            };
+            let (Some(old_text), Some(new_text)) = (
+                blob_text(delta.old_file().id()),
+                blob_text(delta.new_file().id()),
+            ) else {
+                continue;
+            };
+            files.push(CommitFileDiff {
+                path: RepoPath::from(path),
+                old_text,
+                new_text,
+            });
+        }
+        Ok(files)
+    }
+
+    fn conflict_stages(&self, path: &RepoPath) -> Result<ConflictStages> {
+        const STAGE_BASE: i32 = 1;
+        const STAGE_OURS: i32 = 2;
+        const STAGE_THEIRS: i32 = 3;
+
+        // This check is required because index.get_path() unwraps internally :(
+        check_path_to_repo_path_errors(path)?;
+
+        let repo = self.repository.lock();
+        let mut index = repo.index()?;
+        index.read(false)?;
+        let stage_text = |stage: i32| -> Result<Option<String>> {
+            let Some(entry) = index.get_path(path, stage) else {
+                return Ok(None);
+            };
+            let blob = repo.find_blob(entry.id)?;
+            Ok(Some(String::from_utf8_lossy(blob.content()).into_owned()))
+        };
+        let stages = ConflictStages {
+            base: stage_text(STAGE_BASE)?,
+            ours: stage_text(STAGE_OURS)?,
+            theirs: stage_text(STAGE_THEIRS)?,
+        };
+        anyhow::ensure!(
+            stages.ours.is_some() || stages.theirs.is_some(),
+            "{path:?} has no conflict"
+        );
+        Ok(stages)
+    }
+
+    fn remote_operation(
+        &self,
+        operation: RemoteOperation,
+        delegate: RemoteDelegate,
+    ) -> BoxFuture<'static, Result<()>> {
+        let args = self.remote_operation_args(operation);
+        let working_directory = self.working_directory();
+        let git_binary_path = self.git_binary_path.clone();
+        smol::unblock(move || {
+            run_remote_git_command(&git_binary_path, &working_directory?, &args?, &delegate)
+        })
+        .boxed()
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -241,6 +856,19 @@ pub struct FakeGitRepositoryState {
     pub worktree_statuses: HashMap<RepoPath, GitFileStatus>,
     pub current_branch_name: Option<String>,
     pub branches: HashSet<String>,
+    pub staged_paths: HashSet<RepoPath>,
+    pub commit_messages: Vec<String>,
+    /// Commits returned by `log`, newest first.
+    pub commits: Vec<(CommitSummary, Vec<CommitFileDiff>)>,
+    pub conflict_stages: HashMap<RepoPath, ConflictStages>,
+    /// File contents in the diff bases other than the index, which uses `index_contents`.
+    pub diff_base_contents: HashMap<DiffBase, HashMap<PathBuf, String>>,
+    /// How each branch, by name, has diverged from its upstream branch.
+    pub upstreams: HashMap<String, UpstreamTracking>,
+    /// The remote operations that succeeded, oldest first.
+    pub remote_operations: Vec<RemoteOperation>,
+    /// The password that remote operations ask for, if any.
+    pub remote_password: Option<String>,
 }
 
 impl FakeGitRepository {
@@ -259,6 +887,14 @@ impl FakeGitRepositoryState {
             worktree_statuses: Default::default(),
             current_branch_name: Default::default(),
             branches: Default::default(),
+            staged_paths: Default::default(),
+            commit_messages: Default::default(),
+            commits: Default::default(),
+            conflict_stages: Default::default(),
+            diff_base_contents: Default::default(),
+            upstreams: Default::default(),
+            remote_operations: Default::default(),
+            remote_password: Default::default(),
         }
     }
 }
@@ -271,6 +907,37 @@ impl GitRepository for FakeGitRepository {
         state.index_contents.get(path).cloned()
     }
 
+    fn load_diff_base_texts(&self, base: &DiffBase, paths: &[&Path]) -> Vec<Option<String>> {
+        let state = self.state.lock();
+        paths
+            .iter()
+            .map(|path| match base {
+                DiffBase::Index => state.index_contents.get(*path).cloned(),
+                _ => state.diff_base_contents.get(base)?.get(*path).cloned(),
+            })
+            .collect()
+    }
+
+    fn changed_paths(&self, base: &DiffBase) -> Result<Vec<RepoPath>> {
+        let state = self.state.lock();
+        let mut paths = state
+            .worktree_statuses
+            .keys()
+            .cloned()
+            .chain(
+                state
+                    .diff_base_contents
+                    .get(base)
+                    .into_iter()
+                    .flat_map(|contents| contents.keys())
+                    .map(|path| RepoPath::from(path.as_path())),
+            )
+            .collect::<Vec<_>>();
+        paths.sort();
+        paths.dedup();
+        Ok(paths)
+    }
+
     fn remote_url(&self, _name: &str) -> Option<String> {
         None
     }
@@ -306,8 +973,20 @@ impl GitRepository for FakeGitRepository {
             })
             .collect::<Vec<_>>();
         entries.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+        let stage_statuses = entries
+            .iter()
+            .map(|(repo_path, _)| {
+                let stage_status = if state.staged_paths.contains(repo_path) {
+                    StageStatus::Staged
+                } else {
+                    StageStatus::Unstaged
+                };
+                (repo_path.clone(), stage_status)
+            })
+            .collect::<Vec<_>>();
         Ok(GitStatus {
             entries: entries.into(),
+            stage_statuses: stage_statuses.into(),
         })
     }
 
@@ -321,6 +1000,7 @@ impl GitRepository for FakeGitRepository {
                 is_head: Some(branch_name) == current_branch.as_ref(),
                 name: branch_name.into(),
                 unix_timestamp: None,
+                upstream: state.upstreams.get(branch_name).copied(),
             })
             .collect())
     }
@@ -358,6 +1038,250 @@ impl GitRepository for FakeGitRepository {
             .with_context(|| format!("failed to get blame for {:?}", path))
             .cloned()
     }
+
+    fn stage_paths(&self, paths: &[RepoPath]) -> Result<()> {
+        let mut state = self.state.lock();
+        state.staged_paths.extend(paths.iter().cloned());
+        state
+            .event_emitter
+            .try_send(state.dot_git_dir.clone())
+            .expect("Dropped repo change event");
+        Ok(())
+    }
+
+    fn unstage_paths(&self, paths: &[RepoPath]) -> Result<()> {
+        let mut state = self.state.lock();
+        for path in paths {
+            state.staged_paths.remove(path);
+        }
+        state
+            .event_emitter
+            .try_send(state.dot_git_dir.clone())
+            .expect("Dropped repo change event");
+        Ok(())
+    }
+
+    fn set_index_text(&self, path: &RepoPath, content: Option<String>) -> Result<()> {
+        let mut state = self.state.lock();
+        match content {
+            Some(content) => state.index_contents.insert(path.to_path_buf(), content),
+            None => state.index_contents.remove(path.as_path()),
+        };
+        state
+            .event_emitter
+            .try_send(state.dot_git_dir.clone())
+            .expect("Dropped repo change event");
+        Ok(())
+    }
+
+    fn unstage_hunks(&self, path: &RepoPath, head_ranges: &[Range<usize>]) -> Result<()> {
+        let mut state = self.state.lock();
+        let Some(index_text) = state.index_contents.get(path.as_path()) else {
+            return Ok(());
+        };
+        let head_text = state
+            .diff_base_contents
+            .get(&DiffBase::Head)
+            .and_then(|contents| contents.get(path.as_path()));
+        match head_text {
+            Some(head_text) => {
+                let index_text = crate::diff::unstage_hunks(head_text, index_text, head_ranges);
+                state.index_contents.insert(path.to_path_buf(), index_text);
+            }
+            None => {
+                state.index_contents.remove(path.as_path());
+                state.staged_paths.remove(path);
+            }
+        }
+        state
+            .event_emitter
+            .try_send(state.dot_git_dir.clone())
+            .expect("Dropped repo change event");
+        Ok(())
+    }
+
+    fn discard_paths(&self, paths: &[RepoPath]) -> Result<()> {
+        let mut state = self.state.lock();
+        for path in paths {
+            if !state.staged_paths.contains(path) {
+                state.worktree_statuses.remove(path);
+            }
+        }
+        state
+            .event_emitter
+            .try_send(state.dot_git_dir.clone())
+            .expect("Dropped repo change event");
+        Ok(())
+    }
+
+    fn commit(&self, message: &str) -> Result<()> {
+        let mut state = self.state.lock();
+        anyhow::ensure!(!message.trim().is_empty(), "commit message is empty");
+        anyhow::ensure!(!state.staged_paths.is_empty(), "nothing to commit");
+        let staged_paths = std::mem::take(&mut state.staged_paths);
+        for path in &staged_paths {
+            state.worktree_statuses.remove(path);
+        }
+        state.commit_messages.push(message.to_string());
+        state
+            .event_emitter
+            .try_send(state.dot_git_dir.clone())
+            .expect("Dropped repo change event");
+        Ok(())
+    }
+
+    fn log(
+        &self,
+        path: Option<&RepoPath>,
+        skip: usize,
+        limit: usize,
+    ) -> Result<Vec<CommitSummary>> {
+        let state = self.state.lock();
+        Ok(state
+            .commits
+            .iter()
+            .filter(|(_, files)| {
+                path.map_or(true, |path| files.iter().any(|file| &file.path == path))
+            })
+            .skip(skip)
+            .take(limit)
+            .map(|(commit, _)| commit.clone())
+            .collect())
+    }
+
+    fn commit_diff(&self, sha: &str) -> Result<Vec<CommitFileDiff>> {
+        let state = self.state.lock();
+        state
+            .commits
+            .iter()
+            .find(|(commit, _)| commit.sha.as_ref() == sha)
+            .map(|(_, files)| files.clone())
+            .with_context(|| format!("failed to find commit {sha}"))
+    }
+
+    fn conflict_stages(&self, path: &RepoPath) -> Result<ConflictStages> {
+        let state = self.state.lock();
+        state
+            .conflict_stages
+            .get(path)
+            .cloned()
+            .with_context(|| format!("{path:?} has no conflict"))
+    }
+
+    fn remote_operation(
+        &self,
+        operation: RemoteOperation,
+        delegate: RemoteDelegate,
+    ) -> BoxFuture<'static, Result<()>> {
+        let state = self.state.clone();
+        async move {
+            let password = state.lock().remote_password.clone();
+            if let Some(password) = password {
+                let (request, response_rx) = AskPassRequest::new("Password: ".to_string());
+                delegate.ask_pass_tx.send(request).await?;
+                let response = response_rx
+                    .recv()
+                    .await
+                    .ok()
+                    .flatten()
+                    .context("authentication was cancelled")?;
+                anyhow::ensure!(response == password, "authentication failed");
+            }
+            delegate
+                .progress_tx
+                .send(format!("{operation} complete"))
+                .await
+                .ok();
+            state.lock().remote_operations.push(operation);
+            Ok(())
+        }
+        .boxed()
+    }
+}
+
+/// Runs a git command that talks to a remote, reporting its progress and answering its
+/// credential prompts through the delegate.
+fn run_remote_git_command(
+    git_binary_path: &Path,
+    working_directory: &Path,
+    args: &[String],
+    delegate: &RemoteDelegate,
+) -> Result<()> {
+    let askpass = AskPass::new().context("failed to set up credential prompts")?;
+    let mut command = util::command::new_std_command(git_binary_path);
+    command
+        .current_dir(working_directory)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped());
+    askpass.configure(&mut command);
+    let mut child = command.spawn().context("failed to start git process")?;
+    let stderr = child
+        .stderr
+        .take()
+        .context("failed to get pipe to stderr of git process")?;
+
+    let (messages, status) = askpass.serve_while(delegate, || {
+        let messages = read_git_progress(stderr, delegate);
+        (messages, child.wait())
+    });
+    let status = status.context("failed to wait for git process")?;
+    if !status.success() {
+        anyhow::bail!("git {} failed: {}", args[0], messages?.join("\n"));
+    }
+    Ok(())
+}
+
+/// Reads the stderr of git, where progress updates end with a carriage return and other messages
+/// with a newline. Reports both as progress, and returns the messages.
+fn read_git_progress(stderr: impl Read, delegate: &RemoteDelegate) -> Result<Vec<String>> {
+    let mut messages = Vec::new();
+    let mut line = Vec::new();
+    for byte in BufReader::new(stderr).bytes() {
+        let byte = byte?;
+        if byte != b'\r' && byte != b'\n' {
+            line.push(byte);
+            continue;
+        }
+        let text = String::from_utf8_lossy(&line).trim().to_string();
+        line.clear();
+        if !text.is_empty() {
+            delegate.progress_tx.try_send(text.clone()).ok();
+            if byte == b'\n' {
+                messages.push(text);
+            }
+        }
+    }
+    Ok(messages)
+}
+
+/// Returns the tree of the commit that a diff base refers to, or `None` for the index.
+fn diff_base_tree<'a>(
+    repo: &'a git2::Repository,
+    base: &DiffBase,
+) -> Result<Option<git2::Tree<'a>>> {
+    let commit = match base {
+        DiffBase::Index => return Ok(None),
+        DiffBase::Head => repo.head()?.peel_to_commit()?,
+        DiffBase::Branch(revision) => repo
+            .revparse_single(revision)
+            .and_then(|object| object.peel_to_commit())
+            .with_context(|| format!("failed to find revision {revision}"))?,
+        DiffBase::MergeBase(revision) => {
+            let head = repo.head()?.peel_to_commit()?.id();
+            let other = repo
+                .revparse_single(revision)
+                .and_then(|object| object.peel_to_commit())
+                .with_context(|| format!("failed to find revision {revision}"))?
+                .id();
+            let merge_base = repo
+                .merge_base(head, other)
+                .with_context(|| format!("failed to find the merge base with {revision}"))?;
+            repo.find_commit(merge_base)?
+        }
+    };
+    Ok(Some(commit.tree()?))
 }
 
 fn check_path_to_repo_path_errors(relative_file_path: &Path) -> Result<()> {
@@ -476,3 +1400,99 @@ impl<'a> MapSeekTarget<RepoPath> for RepoPathDescendants<'a> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_remote_operations_with_bare_remote() {
+        let dir = tempfile::tempdir().unwrap();
+        let remote_path = dir.path().join("remote.git");
+        git2::Repository::init_bare(&remote_path).unwrap();
+
+        // Publishing a branch without an upstream branch makes it track the pushed one.
+        let first = git2::Repository::init(dir.path().join("first")).unwrap();
+        first
+            .remote("origin", remote_path.to_str().unwrap())
+            .unwrap();
+        commit_file(&first, "one\n", "one");
+        let branch_name = first.head().unwrap().shorthand().unwrap().to_string();
+        let first = real_repository(first);
+        run_remote_operation(&first, RemoteOperation::Push);
+        let remote = git2::Repository::open_bare(&remote_path).unwrap();
+        assert!(remote.find_branch(&branch_name, BranchType::Local).is_ok());
+        assert_eq!(
+            head_upstream(&first),
+            Some(UpstreamTracking::default()),
+            "the pushed branch should track the remote branch"
+        );
+
+        let second_path = dir.path().join("second");
+        let second = git2::Repository::clone(remote_path.to_str().unwrap(), &second_path).unwrap();
+        commit_file(&second, "two\n", "two");
+        let second = real_repository(second);
+        assert_eq!(
+            head_upstream(&second),
+            Some(UpstreamTracking {
+                ahead: 1,
+                behind: 0
+            })
+        );
+        run_remote_operation(&second, RemoteOperation::Push);
+        assert_eq!(head_upstream(&second), Some(UpstreamTracking::default()));
+
+        run_remote_operation(&first, RemoteOperation::Fetch);
+        assert_eq!(
+            head_upstream(&first),
+            Some(UpstreamTracking {
+                ahead: 0,
+                behind: 1
+            })
+        );
+        run_remote_operation(&first, RemoteOperation::Pull);
+        assert_eq!(head_upstream(&first), Some(UpstreamTracking::default()));
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("first/a.txt")).unwrap(),
+            "two\n"
+        );
+    }
+
+    fn real_repository(repo: git2::Repository) -> RealGitRepository {
+        RealGitRepository::new(repo, None, Arc::new(GitHostingProviderRegistry::default()))
+    }
+
+    fn run_remote_operation(repo: &RealGitRepository, operation: RemoteOperation) {
+        let (delegate, _, _) = RemoteDelegate::new();
+        smol::block_on(repo.remote_operation(operation, delegate)).unwrap();
+    }
+
+    fn head_upstream(repo: &RealGitRepository) -> Option<UpstreamTracking> {
+        repo.branches()
+            .unwrap()
+            .into_iter()
+            .find(|branch| branch.is_head)
+            .unwrap()
+            .upstream
+    }
+
+    fn commit_file(repo: &git2::Repository, content: &str, message: &str) {
+        let work_dir = repo.workdir().unwrap();
+        std::fs::write(work_dir.join("a.txt"), content).unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new("a.txt")).unwrap();
+        index.write().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let signature = git2::Signature::now("test", "test@zed.dev").unwrap();
+        let parent = repo.head().ok().map(|head| head.peel_to_commit().unwrap());
+        repo.commit(
+            Some("HEAD"),
+            &signature,
+            &signature,
+            message,
+            &tree,
+            &parent.iter().collect::<Vec<_>>(),
+        )
+        .unwrap();
+    }
+}
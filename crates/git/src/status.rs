@@ -6,9 +6,31 @@ use std::{
     sync::Arc,
 };
 
+/// Describes how much of a changed path has been added to the index.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum StageStatus {
+    /// None of the path's changes are in the index.
+    Unstaged,
+    /// Some of the path's changes are in the index, and some are only in the working copy.
+    PartiallyStaged,
+    /// All of the path's changes are in the index.
+    Staged,
+}
+
+impl StageStatus {
+    pub fn has_staged(&self) -> bool {
+        matches!(self, StageStatus::Staged | StageStatus::PartiallyStaged)
+    }
+
+    pub fn has_unstaged(&self) -> bool {
+        matches!(self, StageStatus::Unstaged | StageStatus::PartiallyStaged)
+    }
+}
+
 #[derive(Clone)]
 pub struct GitStatus {
     pub entries: Arc<[(RepoPath, GitFileStatus)]>,
+    pub stage_statuses: Arc<[(RepoPath, StageStatus)]>,
 }
 
 impl GitStatus {
@@ -48,28 +70,39 @@ impl GitStatus {
             return Err(anyhow!("git status process failed: {}", stderr));
         }
         let stdout = String::from_utf8_lossy(&output.stdout);
-        let mut entries = stdout
-            .split('\0')
-            .filter_map(|entry| {
-                if entry.is_char_boundary(3) {
-                    let (status, path) = entry.split_at(3);
-                    let status = status.trim();
-                    Some((
-                        RepoPath(PathBuf::from(path)),
-                        match status {
-                            "A" | "??" => GitFileStatus::Added,
-                            "M" => GitFileStatus::Modified,
-                            _ => return None,
-                        },
-                    ))
-                } else {
-                    None
-                }
-            })
-            .collect::<Vec<_>>();
+        let mut entries = Vec::new();
+        let mut stage_statuses = Vec::new();
+        for entry in stdout.split('\0') {
+            if !entry.is_char_boundary(3) {
+                continue;
+            }
+            let (status, path) = entry.split_at(3);
+            let mut status = status.chars();
+            let (Some(index_status), Some(worktree_status)) = (status.next(), status.next()) else {
+                continue;
+            };
+            let file_status = match (index_status, worktree_status) {
+                // Unmerged paths, which have several stages in the index.
+                ('U', _) | (_, 'U') | ('A', 'A') | ('D', 'D') => GitFileStatus::Conflict,
+                ('?', '?') | ('A', _) | (_, 'A') => GitFileStatus::Added,
+                ('M', _) | (_, 'M') => GitFileStatus::Modified,
+                _ => continue,
+            };
+            let stage_status = match (index_status, worktree_status) {
+                _ if file_status == GitFileStatus::Conflict => StageStatus::Unstaged,
+                ('?', '?') | (' ', _) => StageStatus::Unstaged,
+                (_, ' ') => StageStatus::Staged,
+                _ => StageStatus::PartiallyStaged,
+            };
+            let repo_path = RepoPath(PathBuf::from(path));
+            entries.push((repo_path.clone(), file_status));
+            stage_statuses.push((repo_path, stage_status));
+        }
         entries.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+        stage_statuses.sort_unstable_by(|a, b| a.0.cmp(&b.0));
         Ok(Self {
             entries: entries.into(),
+            stage_statuses: stage_statuses.into(),
         })
     }
 
@@ -79,12 +112,20 @@ impl GitStatus {
             .ok()
             .map(|index| self.entries[index].1)
     }
+
+    pub fn stage_status(&self, path: &Path) -> Option<StageStatus> {
+        self.stage_statuses
+            .binary_search_by(|(repo_path, _)| repo_path.0.as_path().cmp(path))
+            .ok()
+            .map(|index| self.stage_statuses[index].1)
+    }
 }
 
 impl Default for GitStatus {
     fn default() -> Self {
         Self {
             entries: Arc::new([]),
+            stage_statuses: Arc::new([]),
         }
     }
 }
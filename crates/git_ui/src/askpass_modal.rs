@@ -0,0 +1,116 @@
+use std::collections::VecDeque;
+
+use editor::Editor;
+use git::repository::AskPassRequest;
+use gpui::{
+    AppContext, DismissEvent, EventEmitter, FocusHandle, FocusableView, Render, View, ViewContext,
+};
+use ui::prelude::*;
+use workspace::ModalView;
+
+/// Asks for the credentials that git or ssh need to talk to a remote.
+pub struct AskPassModal {
+    requests: VecDeque<AskPassRequest>,
+    editor: View<Editor>,
+}
+
+impl ModalView for AskPassModal {}
+
+impl FocusableView for AskPassModal {
+    fn focus_handle(&self, cx: &AppContext) -> FocusHandle {
+        self.editor.focus_handle(cx)
+    }
+}
+
+impl EventEmitter<DismissEvent> for AskPassModal {}
+
+impl AskPassModal {
+    pub fn new(request: AskPassRequest, cx: &mut ViewContext<Self>) -> Self {
+        let editor = cx.new_view(Editor::single_line);
+        let mut this = Self {
+            requests: VecDeque::new(),
+            editor,
+        };
+        this.push_request(request, cx);
+        this
+    }
+
+    /// Queues a prompt behind the ones already shown, so that concurrent operations can all prompt.
+    pub fn push_request(&mut self, request: AskPassRequest, cx: &mut ViewContext<Self>) {
+        self.requests.push_back(request);
+        if self.requests.len() == 1 {
+            self.show_next_request(cx);
+        }
+    }
+
+    fn show_next_request(&mut self, cx: &mut ViewContext<Self>) {
+        let Some(request) = self.requests.front() else {
+            return;
+        };
+        let masked = is_secret_prompt(&request.prompt);
+        self.editor.update(cx, |editor, cx| {
+            editor.clear(cx);
+            editor.set_masked(masked, cx);
+        });
+        cx.notify();
+    }
+
+    fn cancel(&mut self, _: &menu::Cancel, cx: &mut ViewContext<Self>) {
+        for request in self.requests.drain(..) {
+            request.respond(None);
+        }
+        cx.emit(DismissEvent);
+    }
+
+    fn confirm(&mut self, _: &menu::Confirm, cx: &mut ViewContext<Self>) {
+        if let Some(request) = self.requests.pop_front() {
+            request.respond(Some(self.editor.read(cx).text(cx)));
+        }
+        if self.requests.is_empty() {
+            cx.emit(DismissEvent);
+        } else {
+            self.show_next_request(cx);
+        }
+    }
+}
+
+/// Whether the answer to a prompt should be hidden while it's typed.
+fn is_secret_prompt(prompt: &str) -> bool {
+    let prompt = prompt.to_lowercase();
+    ["password", "passphrase", "token"]
+        .iter()
+        .any(|secret| prompt.contains(secret))
+}
+
+impl Render for AskPassModal {
+    fn render(&mut self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let prompt = self
+            .requests
+            .front()
+            .map(|request| request.prompt.clone())
+            .unwrap_or_default();
+
+        v_flex()
+            .w(rems(34.))
+            .elevation_2(cx)
+            .key_context("AskPassModal")
+            .on_action(cx.listener(Self::cancel))
+            .on_action(cx.listener(Self::confirm))
+            .child(
+                h_flex()
+                    .px_2()
+                    .py_1()
+                    .gap_1()
+                    .child(Icon::new(IconName::FileLock).color(Color::Muted))
+                    .child(Label::new(prompt)),
+            )
+            .child(
+                div()
+                    .border_t_1()
+                    .border_color(cx.theme().colors().border_variant)
+                    .px_2()
+                    .py_1()
+                    .child(self.editor.clone()),
+            )
+    }
+}
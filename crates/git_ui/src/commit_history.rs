@@ -0,0 +1,417 @@
+use std::path::Path;
+
+use editor::{actions::ShowFileHistory, CommitDiffEditor, Editor, EditorMode};
+use git::commit::CommitSummary;
+use gpui::{
+    uniform_list, AnyElement, AppContext, ClickEvent, EventEmitter, FocusHandle, FocusableView,
+    KeyContext, Model, ScrollStrategy, Task, UniformListScrollHandle, View, WeakView,
+};
+use menu::{Confirm, SelectNext, SelectPrev};
+use project::{Project, ProjectPath};
+use time::{OffsetDateTime, UtcOffset};
+use ui::{prelude::*, ListItem, Tooltip};
+use workspace::{
+    item::{Item, ItemEvent},
+    Workspace,
+};
+
+use crate::ShowCommitHistory;
+
+/// How many commits are requested from the repository at once.
+const PAGE_SIZE: usize = 100;
+
+pub fn init(cx: &mut AppContext) {
+    cx.observe_new_views(register_workspace).detach();
+    cx.observe_new_views(register_editor).detach();
+}
+
+fn register_workspace(workspace: &mut Workspace, _: &mut ViewContext<Workspace>) {
+    workspace.register_action(|workspace, _: &ShowCommitHistory, cx| {
+        let project_path = workspace
+            .active_item(cx)
+            .and_then(|item| item.project_path(cx))
+            .or_else(|| {
+                let worktree = workspace.project().read(cx).visible_worktrees(cx).next()?;
+                Some(ProjectPath {
+                    worktree_id: worktree.read(cx).id(),
+                    path: Path::new("").into(),
+                })
+            });
+        if let Some(project_path) = project_path {
+            CommitHistory::deploy(workspace, project_path, false, cx);
+        }
+    });
+}
+
+fn register_editor(editor: &mut Editor, cx: &mut ViewContext<Editor>) {
+    if editor.mode() != EditorMode::Full {
+        return;
+    }
+
+    let handle = cx.view().downgrade();
+    editor
+        .register_action(move |_: &ShowFileHistory, cx| {
+            if let Some(editor) = handle.upgrade() {
+                show_file_history(editor, cx);
+            }
+        })
+        .detach();
+}
+
+/// Shows the commits that changed the file under the editor's newest cursor.
+fn show_file_history(editor: View<Editor>, cx: &mut WindowContext) {
+    let Some(workspace) = editor.read(cx).workspace() else {
+        return;
+    };
+    let Some(project_path) = editor.update(cx, |editor, cx| {
+        let head = editor.selections.newest_anchor().head();
+        let (buffer, _) = editor
+            .buffer()
+            .read(cx)
+            .text_anchor_for_position(head, cx)?;
+        let file = project::File::from_dyn(buffer.read(cx).file())?;
+        Some(ProjectPath {
+            worktree_id: file.worktree_id(cx),
+            path: file.path.clone(),
+        })
+    }) else {
+        return;
+    };
+    workspace.update(cx, |workspace, cx| {
+        CommitHistory::deploy(workspace, project_path, true, cx);
+    });
+}
+
+/// Lists the commits of a repository, newest first, and opens their changes when selected.
+pub struct CommitHistory {
+    project: Model<Project>,
+    workspace: WeakView<Workspace>,
+    project_path: ProjectPath,
+    /// Whether only the commits changing `project_path` are listed,
+    /// instead of all the commits of its repository.
+    file_history: bool,
+    commits: Vec<CommitSummary>,
+    /// Whether the last requested page was full, so there may be older commits to load.
+    has_more: bool,
+    load_task: Option<Task<()>>,
+    error: Option<SharedString>,
+    selected_ix: Option<usize>,
+    scroll_handle: UniformListScrollHandle,
+    focus_handle: FocusHandle,
+}
+
+impl CommitHistory {
+    /// Opens the history of `project_path`, activating the view already showing it if there is one.
+    pub fn deploy(
+        workspace: &mut Workspace,
+        project_path: ProjectPath,
+        file_history: bool,
+        cx: &mut ViewContext<Workspace>,
+    ) {
+        let existing = workspace.items_of_type::<Self>(cx).find(|item| {
+            let item = item.read(cx);
+            item.file_history == file_history
+                && if file_history {
+                    item.project_path == project_path
+                } else {
+                    item.project_path.worktree_id == project_path.worktree_id
+                }
+        });
+        if let Some(existing) = existing {
+            workspace.activate_item(&existing, true, true, cx);
+        } else {
+            let project = workspace.project().clone();
+            let workspace_handle = workspace.weak_handle();
+            let history = cx.new_view(|cx| {
+                Self::new(project, workspace_handle, project_path, file_history, cx)
+            });
+            workspace.add_item_to_active_pane(Box::new(history), None, true, cx);
+        }
+    }
+
+    fn new(
+        project: Model<Project>,
+        workspace: WeakView<Workspace>,
+        project_path: ProjectPath,
+        file_history: bool,
+        cx: &mut ViewContext<Self>,
+    ) -> Self {
+        let mut this = Self {
+            project,
+            workspace,
+            project_path,
+            file_history,
+            commits: Vec::new(),
+            has_more: true,
+            load_task: None,
+            error: None,
+            selected_ix: None,
+            scroll_handle: UniformListScrollHandle::new(),
+            focus_handle: cx.focus_handle(),
+        };
+        this.load_more(cx);
+        this
+    }
+
+    fn load_more(&mut self, cx: &mut ViewContext<Self>) {
+        if !self.has_more || self.load_task.is_some() {
+            return;
+        }
+        let git_log = self.project.update(cx, |project, cx| {
+            project.git_log(
+                self.project_path.clone(),
+                self.file_history,
+                self.commits.len(),
+                PAGE_SIZE,
+                cx,
+            )
+        });
+        self.load_task = Some(cx.spawn(|this, mut cx| async move {
+            let result = git_log.await;
+            this.update(&mut cx, |this, cx| {
+                this.load_task = None;
+                match result {
+                    Ok(commits) => {
+                        this.has_more = commits.len() == PAGE_SIZE;
+                        this.commits.extend(commits);
+                        this.error = None;
+                    }
+                    Err(error) => {
+                        log::error!("failed to load the commit history: {error:#}");
+                        this.has_more = false;
+                        this.error = Some(format!("{error:#}").into());
+                    }
+                }
+                if this.selected_ix.is_none() && !this.commits.is_empty() {
+                    this.selected_ix = Some(0);
+                }
+                cx.notify();
+            })
+            .ok();
+        }));
+        cx.notify();
+    }
+
+    fn title(&self) -> String {
+        if self.file_history {
+            let file_name = self
+                .project_path
+                .path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default();
+            format!("History of {file_name}")
+        } else {
+            "Commit History".to_string()
+        }
+    }
+
+    fn select(&mut self, ix: usize, cx: &mut ViewContext<Self>) {
+        self.selected_ix = Some(ix);
+        self.scroll_handle
+            .scroll_to_item(ix, ScrollStrategy::Center);
+        if ix + 1 == self.commits.len() {
+            self.load_more(cx);
+        }
+        cx.notify();
+    }
+
+    fn select_next(&mut self, _: &SelectNext, cx: &mut ViewContext<Self>) {
+        let next_ix = self.selected_ix.map_or(0, |ix| ix + 1);
+        if next_ix < self.commits.len() {
+            self.select(next_ix, cx);
+        }
+    }
+
+    fn select_prev(&mut self, _: &SelectPrev, cx: &mut ViewContext<Self>) {
+        let prev_ix = self.selected_ix.map_or(0, |ix| ix.saturating_sub(1));
+        if prev_ix < self.commits.len() {
+            self.select(prev_ix, cx);
+        }
+    }
+
+    fn confirm(&mut self, _: &Confirm, cx: &mut ViewContext<Self>) {
+        if let Some(ix) = self.selected_ix {
+            self.open_commit(ix, cx);
+        }
+    }
+
+    fn open_commit(&mut self, ix: usize, cx: &mut ViewContext<Self>) {
+        let Some(commit) = self.commits.get(ix).cloned() else {
+            return;
+        };
+        let project_path = self.project_path.clone();
+        self.workspace
+            .update(cx, |workspace, cx| {
+                CommitDiffEditor::deploy(workspace, project_path, commit, cx);
+            })
+            .ok();
+    }
+
+    fn dispatch_context(&self) -> KeyContext {
+        let mut dispatch_context = KeyContext::new_with_defaults();
+        dispatch_context.add("CommitHistory");
+        dispatch_context.add("menu");
+        dispatch_context
+    }
+
+    fn render_commit(
+        &self,
+        ix: usize,
+        now: OffsetDateTime,
+        cx: &mut ViewContext<Self>,
+    ) -> ListItem {
+        let commit = &self.commits[ix];
+        let timestamp = OffsetDateTime::from_unix_timestamp(commit.commit_timestamp)
+            .map(|timestamp| {
+                let local_offset = chrono::Local::now().offset().local_minus_utc();
+                time_format::format_localized_timestamp(
+                    timestamp,
+                    now,
+                    UtcOffset::from_whole_seconds(local_offset).unwrap_or(UtcOffset::UTC),
+                    time_format::TimestampFormat::Relative,
+                )
+            })
+            .unwrap_or_default();
+        let sha = commit.sha.clone();
+        let author = format!("{} <{}>", commit.author_name, commit.author_email);
+
+        ListItem::new(ix)
+            .toggle_state(self.selected_ix == Some(ix))
+            .start_slot(
+                Label::new(commit.sha[..commit.sha.len().min(7)].to_string())
+                    .size(LabelSize::Small)
+                    .color(Color::Muted),
+            )
+            .child(
+                h_flex()
+                    .gap_2()
+                    .justify_between()
+                    .child(Label::new(commit.subject.clone()).single_line())
+                    .child(
+                        h_flex()
+                            .gap_2()
+                            .flex_none()
+                            .child(
+                                Label::new(commit.author_name.clone())
+                                    .size(LabelSize::Small)
+                                    .color(Color::Muted),
+                            )
+                            .child(
+                                Label::new(timestamp)
+                                    .size(LabelSize::Small)
+                                    .color(Color::Muted),
+                            ),
+                    ),
+            )
+            .tooltip(move |cx| Tooltip::with_meta(sha.clone(), None, author.clone(), cx))
+            .on_click(cx.listener(move |this, _: &ClickEvent, cx| {
+                this.select(ix, cx);
+                this.open_commit(ix, cx);
+            }))
+    }
+
+    fn render_footer(&self, cx: &mut ViewContext<Self>) -> Option<AnyElement> {
+        if let Some(error) = &self.error {
+            return Some(
+                div()
+                    .p_2()
+                    .child(Label::new(error.clone()).color(Color::Error))
+                    .into_any_element(),
+            );
+        }
+        if self.load_task.is_some() {
+            return Some(
+                div()
+                    .p_2()
+                    .child(Label::new("Loading commits…").color(Color::Muted))
+                    .into_any_element(),
+            );
+        }
+        if self.commits.is_empty() {
+            return Some(
+                div()
+                    .p_2()
+                    .child(Label::new("No commits").color(Color::Muted))
+                    .into_any_element(),
+            );
+        }
+        self.has_more.then(|| {
+            div()
+                .p_2()
+                .child(
+                    Button::new("load-more-commits", "Load More Commits")
+                        .on_click(cx.listener(|this, _, cx| this.load_more(cx))),
+                )
+                .into_any_element()
+        })
+    }
+}
+
+impl Render for CommitHistory {
+    fn render(&mut self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let now = OffsetDateTime::now_utc();
+        v_flex()
+            .id("commit-history")
+            .key_context(self.dispatch_context())
+            .track_focus(&self.focus_handle)
+            .size_full()
+            .bg(cx.theme().colors().editor_background)
+            .on_action(cx.listener(Self::select_next))
+            .on_action(cx.listener(Self::select_prev))
+            .on_action(cx.listener(Self::confirm))
+            .child(
+                h_flex()
+                    .px_2()
+                    .py_1()
+                    .border_b_1()
+                    .border_color(cx.theme().colors().border_variant)
+                    .child(Label::new(self.title())),
+            )
+            .child(
+                uniform_list(
+                    cx.view().clone(),
+                    "commit-history-entries",
+                    self.commits.len(),
+                    move |this, range, cx| {
+                        range.map(|ix| this.render_commit(ix, now, cx)).collect()
+                    },
+                )
+                .flex_grow()
+                .track_scroll(self.scroll_handle.clone()),
+            )
+            .children(self.render_footer(cx))
+    }
+}
+
+impl EventEmitter<ItemEvent> for CommitHistory {}
+
+impl FocusableView for CommitHistory {
+    fn focus_handle(&self, _: &AppContext) -> FocusHandle {
+        self.focus_handle.clone()
+    }
+}
+
+impl Item for CommitHistory {
+    type Event = ItemEvent;
+
+    fn to_item_events(event: &Self::Event, mut f: impl FnMut(ItemEvent)) {
+        f(*event)
+    }
+
+    fn tab_content_text(&self, _: &WindowContext) -> Option<SharedString> {
+        Some(self.title().into())
+    }
+
+    fn tab_icon(&self, _: &WindowContext) -> Option<Icon> {
+        Some(Icon::new(IconName::GitBranch))
+    }
+
+    fn telemetry_event_text(&self) -> Option<&'static str> {
+        None
+    }
+
+    fn show_toolbar(&self) -> bool {
+        false
+    }
+}
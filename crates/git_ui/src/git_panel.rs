@@ -5,16 +5,21 @@ use editor::{
     scroll::{Autoscroll, AutoscrollStrategy},
     Editor, MultiBuffer, DEFAULT_MULTIBUFFER_CONTEXT,
 };
-use git::{diff::DiffHunk, repository::GitFileStatus};
+use git::{
+    conflict::parse_conflicts,
+    diff::DiffHunk,
+    repository::{DiffBase, GitFileStatus},
+    status::StageStatus,
+};
 use gpui::{
     actions, prelude::*, uniform_list, Action, AppContext, AsyncWindowContext, ClickEvent,
-    CursorStyle, EventEmitter, FocusHandle, FocusableView, KeyContext,
-    ListHorizontalSizingBehavior, ListSizingBehavior, Model, Modifiers, ModifiersChangedEvent,
-    MouseButton, ScrollStrategy, Stateful, Task, UniformListScrollHandle, View, WeakView,
+    EventEmitter, FocusHandle, FocusableView, KeyContext, ListHorizontalSizingBehavior,
+    ListSizingBehavior, Model, Modifiers, ModifiersChangedEvent, MouseButton, PromptLevel,
+    ScrollStrategy, Stateful, Task, UniformListScrollHandle, View, WeakView,
 };
 use language::{Buffer, BufferRow, OffsetRangeExt};
 use menu::{SelectNext, SelectPrev};
-use project::{Entry, EntryKind, Fs, Project, ProjectEntryId, WorktreeId};
+use project::{Entry, EntryKind, Fs, Project, ProjectEntryId, ProjectPath, WorktreeId};
 use serde::{Deserialize, Serialize};
 use settings::Settings as _;
 use std::{
@@ -29,12 +34,13 @@ use std::{
     usize,
 };
 use ui::{
-    prelude::*, Checkbox, Divider, DividerColor, ElevationIndex, ListItem, Scrollbar,
-    ScrollbarState, Tooltip,
+    prelude::*, Checkbox, ContextMenu, Divider, DividerColor, ElevationIndex, ListItem,
+    PopoverMenu, Scrollbar, ScrollbarState, Tooltip,
 };
 use util::{ResultExt, TryFutureExt};
 use workspace::{
     dock::{DockPosition, Panel, PanelEvent},
+    notifications::NotifyTaskExt,
     ItemHandle, Workspace,
 };
 
@@ -70,10 +76,12 @@ struct EntryDetails {
     filename: String,
     display_name: String,
     path: Arc<Path>,
+    worktree_id: WorktreeId,
     kind: EntryKind,
     depth: usize,
     is_expanded: bool,
     status: Option<GitFileStatus>,
+    stage_status: Option<StageStatus>,
     hunks: Rc<OnceCell<Vec<DiffHunk>>>,
     index: usize,
 }
@@ -110,6 +118,13 @@ pub struct GitPanel {
     git_diff_editor: Option<View<Editor>>,
     git_diff_editor_updates: Task<()>,
     reveal_in_editor: Task<()>,
+    commit_editor: View<Editor>,
+    stage_statuses: HashMap<WorktreeId, HashMap<Arc<Path>, StageStatus>>,
+    stage_status_updates: HashMap<WorktreeId, Task<()>>,
+    /// The files that differ from a diff base other than the index, without having a git status.
+    changed_paths: HashMap<WorktreeId, HashSet<Arc<Path>>>,
+    changed_paths_update: Task<()>,
+    branches: Vec<SharedString>,
 }
 
 #[derive(Debug, Clone)]
@@ -167,6 +182,8 @@ impl GitPanel {
             cx.subscribe(&project, |this, _, event, cx| match event {
                 project::Event::WorktreeRemoved(id) => {
                     this.expanded_dir_ids.remove(id);
+                    this.stage_statuses.remove(id);
+                    this.stage_status_updates.remove(id);
                     this.update_visible_entries(None, None, cx);
                     cx.notify();
                 }
@@ -178,6 +195,14 @@ impl GitPanel {
                 | project::Event::WorktreeAdded(id)
                 | project::Event::WorktreeUpdatedGitRepositories(id) => {
                     this.update_visible_entries(Some(*id), None, cx);
+                    if matches!(event, project::Event::WorktreeUpdatedGitRepositories(_)) {
+                        this.update_branches(cx);
+                    }
+                    this.update_changed_paths(cx);
+                    cx.notify();
+                }
+                project::Event::DiffBaseChanged => {
+                    this.update_changed_paths(cx);
                     cx.notify();
                 }
                 project::Event::Closed => {
@@ -185,6 +210,10 @@ impl GitPanel {
                     this.reveal_in_editor = Task::ready(());
                     this.expanded_dir_ids.clear();
                     this.visible_entries.clear();
+                    this.stage_statuses.clear();
+                    this.stage_status_updates.clear();
+                    this.changed_paths.clear();
+                    this.changed_paths_update = Task::ready(());
                     this.git_diff_editor = None;
                 }
                 _ => {}
@@ -192,6 +221,12 @@ impl GitPanel {
             .detach();
 
             let scroll_handle = UniformListScrollHandle::new();
+            let commit_editor = cx.new_view(|cx| {
+                let mut commit_editor = Editor::auto_height(10, cx);
+                commit_editor.set_placeholder_text("Enter commit message", cx);
+                commit_editor
+            });
+            cx.observe(&commit_editor, |_, _, cx| cx.notify()).detach();
 
             let mut git_panel = Self {
                 workspace: weak_workspace,
@@ -211,9 +246,16 @@ impl GitPanel {
                 git_diff_editor: Some(diff_display_editor(cx)),
                 git_diff_editor_updates: Task::ready(()),
                 reveal_in_editor: Task::ready(()),
+                commit_editor,
+                stage_statuses: HashMap::default(),
+                stage_status_updates: HashMap::default(),
+                changed_paths: HashMap::default(),
+                changed_paths_update: Task::ready(()),
+                branches: Vec::new(),
                 project,
             };
             git_panel.update_visible_entries(None, None, cx);
+            git_panel.update_branches(cx);
             git_panel
         });
 
@@ -393,36 +435,342 @@ impl GitPanel {
 }
 
 impl GitPanel {
-    fn stage_all(&mut self, _: &StageAll, _cx: &mut ViewContext<Self>) {
-        // TODO: Implement stage all
-        println!("Stage all triggered");
+    fn stage_all(&mut self, _: &StageAll, cx: &mut ViewContext<Self>) {
+        for (worktree_id, paths) in self.changed_paths(|stage_status| stage_status.has_unstaged()) {
+            let task = self.project.read(cx).stage_paths(worktree_id, paths, cx);
+            self.perform_git_operation(worktree_id, task, cx);
+        }
+    }
+
+    fn unstage_all(&mut self, _: &UnstageAll, cx: &mut ViewContext<Self>) {
+        for (worktree_id, paths) in self.changed_paths(|stage_status| stage_status.has_staged()) {
+            let task = self.project.read(cx).unstage_paths(worktree_id, paths, cx);
+            self.perform_git_operation(worktree_id, task, cx);
+        }
+    }
+
+    fn discard_all(&mut self, _: &DiscardAll, cx: &mut ViewContext<Self>) {
+        let changes = self.changed_paths(|stage_status| stage_status.has_unstaged());
+        if changes.is_empty() {
+            return;
+        }
+        let file_count = changes.iter().map(|(_, paths)| paths.len()).sum::<usize>();
+        let answer = cx.prompt(
+            PromptLevel::Warning,
+            &format!("Discard unstaged changes in {file_count} files?"),
+            Some("Untracked files will be deleted. This cannot be undone."),
+            &["Discard", "Cancel"],
+        );
+        cx.spawn(|git_panel, mut cx| async move {
+            if answer.await != Ok(0) {
+                return Ok(());
+            }
+            git_panel.update(&mut cx, |git_panel, cx| {
+                for (worktree_id, paths) in changes {
+                    let task = git_panel
+                        .project
+                        .read(cx)
+                        .discard_paths(worktree_id, paths, cx);
+                    git_panel.perform_git_operation(worktree_id, task, cx);
+                }
+            })
+        })
+        .detach_and_notify_err(cx);
     }
 
-    fn unstage_all(&mut self, _: &UnstageAll, _cx: &mut ViewContext<Self>) {
-        // TODO: Implement unstage all
-        println!("Unstage all triggered");
+    fn toggle_staged_for_entry(
+        &mut self,
+        worktree_id: WorktreeId,
+        path: Arc<Path>,
+        stage: bool,
+        cx: &mut ViewContext<Self>,
+    ) {
+        let project = self.project.read(cx);
+        let task = if stage {
+            project.stage_paths(worktree_id, vec![path], cx)
+        } else {
+            project.unstage_paths(worktree_id, vec![path], cx)
+        };
+        self.perform_git_operation(worktree_id, task, cx);
     }
 
-    fn discard_all(&mut self, _: &DiscardAll, _cx: &mut ViewContext<Self>) {
-        // TODO: Implement discard all
-        println!("Discard all triggered");
+    fn discard_entry(
+        &mut self,
+        worktree_id: WorktreeId,
+        path: Arc<Path>,
+        cx: &mut ViewContext<Self>,
+    ) {
+        let answer = cx.prompt(
+            PromptLevel::Warning,
+            &format!("Discard unstaged changes in {}?", path.display()),
+            Some("If the file is untracked, it will be deleted. This cannot be undone."),
+            &["Discard", "Cancel"],
+        );
+        cx.spawn(|git_panel, mut cx| async move {
+            if answer.await != Ok(0) {
+                return Ok(());
+            }
+            git_panel.update(&mut cx, |git_panel, cx| {
+                let task = git_panel
+                    .project
+                    .read(cx)
+                    .discard_paths(worktree_id, vec![path], cx);
+                git_panel.perform_git_operation(worktree_id, task, cx);
+            })
+        })
+        .detach_and_notify_err(cx);
     }
 
     /// Commit all staged changes
-    fn commit_staged_changes(&mut self, _: &CommitStagedChanges, _cx: &mut ViewContext<Self>) {
-        // TODO: Implement commit all staged
-        println!("Commit staged changes triggered");
+    fn commit_staged_changes(&mut self, _: &CommitStagedChanges, cx: &mut ViewContext<Self>) {
+        let Some(message) = self.commit_message(cx) else {
+            return;
+        };
+        let staged_changes = self.changed_paths(|stage_status| stage_status.has_staged());
+        if staged_changes.is_empty() {
+            return;
+        }
+        let project = self.project.clone();
+        cx.spawn(|git_panel, mut cx| async move {
+            let result = async {
+                project
+                    .update(&mut cx, |project, cx| {
+                        project.commit(staged_changes, message, cx)
+                    })?
+                    .await
+            }
+            .await;
+            git_panel.update(&mut cx, |git_panel, cx| {
+                git_panel.after_commit(result.is_ok(), cx)
+            })?;
+            result
+        })
+        .detach_and_notify_err(cx);
     }
 
     /// Commit all changes, regardless of whether they are staged or not
-    fn commit_all_changes(&mut self, _: &CommitAllChanges, _cx: &mut ViewContext<Self>) {
-        // TODO: Implement commit all changes
-        println!("Commit all changes triggered");
+    fn commit_all_changes(&mut self, _: &CommitAllChanges, cx: &mut ViewContext<Self>) {
+        let Some(message) = self.commit_message(cx) else {
+            return;
+        };
+        let changes = self.changed_paths(|_| true);
+        if changes.is_empty() {
+            return;
+        }
+        let project = self.project.clone();
+        cx.spawn(|git_panel, mut cx| async move {
+            let result = async {
+                for (worktree_id, paths) in &changes {
+                    project
+                        .update(&mut cx, |project, cx| {
+                            project.stage_paths(*worktree_id, paths.clone(), cx)
+                        })?
+                        .await?;
+                }
+                project
+                    .update(&mut cx, |project, cx| project.commit(changes, message, cx))?
+                    .await
+            }
+            .await;
+            git_panel.update(&mut cx, |git_panel, cx| {
+                git_panel.after_commit(result.is_ok(), cx)
+            })?;
+            result
+        })
+        .detach_and_notify_err(cx);
+    }
+
+    fn commit_message(&self, cx: &AppContext) -> Option<String> {
+        let message = self.commit_editor.read(cx).text(cx);
+        if message.trim().is_empty() {
+            None
+        } else {
+            Some(message)
+        }
+    }
+
+    fn after_commit(&mut self, succeeded: bool, cx: &mut ViewContext<Self>) {
+        if succeeded {
+            self.commit_editor
+                .update(cx, |commit_editor, cx| commit_editor.clear(cx));
+        }
+        let worktree_ids = self
+            .visible_entries
+            .iter()
+            .map(|worktree_entries| worktree_entries.worktree_id)
+            .collect::<Vec<_>>();
+        for worktree_id in worktree_ids {
+            self.update_stage_statuses(worktree_id, cx);
+        }
+    }
+
+    /// Awaits a git operation, reporting its error and refreshing the stage statuses it could have changed.
+    fn perform_git_operation(
+        &mut self,
+        worktree_id: WorktreeId,
+        operation: Task<Result<()>>,
+        cx: &mut ViewContext<Self>,
+    ) {
+        cx.spawn(|git_panel, mut cx| async move {
+            let result = operation.await;
+            git_panel.update(&mut cx, |git_panel, cx| {
+                git_panel.update_stage_statuses(worktree_id, cx)
+            })?;
+            result
+        })
+        .detach_and_notify_err(cx);
+    }
+
+    fn update_stage_statuses(&mut self, worktree_id: WorktreeId, cx: &mut ViewContext<Self>) {
+        let Some(paths) = self
+            .visible_entries
+            .iter()
+            .find(|worktree_entries| worktree_entries.worktree_id == worktree_id)
+            .map(|worktree_entries| {
+                worktree_entries
+                    .visible_entries
+                    .iter()
+                    .filter(|entry| !entry.is_dir())
+                    .map(|entry| entry.path.clone())
+                    .collect::<Vec<_>>()
+            })
+        else {
+            self.stage_statuses.remove(&worktree_id);
+            self.stage_status_updates.remove(&worktree_id);
+            return;
+        };
+
+        let stage_statuses = self
+            .project
+            .read(cx)
+            .git_stage_statuses(worktree_id, paths, cx);
+        self.stage_status_updates.insert(
+            worktree_id,
+            cx.spawn(|git_panel, mut cx| async move {
+                let Some(stage_statuses) = stage_statuses.await.log_err() else {
+                    return;
+                };
+                git_panel
+                    .update(&mut cx, |git_panel, cx| {
+                        git_panel
+                            .stage_statuses
+                            .insert(worktree_id, stage_statuses.into_iter().collect());
+                        cx.notify();
+                    })
+                    .ok();
+            }),
+        );
+    }
+
+    /// Reloads the files that differ from the project's diff base, unless that is the index,
+    /// whose changes the git statuses already cover.
+    fn update_changed_paths(&mut self, cx: &mut ViewContext<Self>) {
+        let project = self.project.read(cx);
+        if *project.diff_base(cx) == DiffBase::Index {
+            if !self.changed_paths.is_empty() {
+                self.changed_paths.clear();
+                self.changed_paths_update = Task::ready(());
+                self.update_visible_entries(None, None, cx);
+            }
+            return;
+        }
+
+        let changed_paths = project
+            .visible_worktrees(cx)
+            .map(|worktree| {
+                let worktree_id = worktree.read(cx).id();
+                (worktree_id, project.git_changed_paths(worktree_id, cx))
+            })
+            .collect::<Vec<_>>();
+        self.changed_paths_update = cx.spawn(|git_panel, mut cx| async move {
+            cx.background_executor().timer(UPDATE_DEBOUNCE).await;
+            let mut new_changed_paths = HashMap::default();
+            for (worktree_id, paths) in changed_paths {
+                if let Some(paths) = paths.await.log_err() {
+                    new_changed_paths.insert(worktree_id, paths.into_iter().collect());
+                }
+            }
+            git_panel
+                .update(&mut cx, |git_panel, cx| {
+                    if git_panel.changed_paths != new_changed_paths {
+                        git_panel.changed_paths = new_changed_paths;
+                        git_panel.update_visible_entries(None, None, cx);
+                    }
+                })
+                .ok();
+        });
+    }
+
+    fn update_branches(&mut self, cx: &mut ViewContext<Self>) {
+        let project = self.project.read(cx);
+        let Some(worktree) = project.visible_worktrees(cx).next() else {
+            return;
+        };
+        let branches = project.branches(ProjectPath::root_path(worktree.read(cx).id()), cx);
+        cx.spawn(|git_panel, mut cx| async move {
+            let mut branches = branches.await?;
+            branches.sort_by(|a, b| a.name.cmp(&b.name));
+            git_panel.update(&mut cx, |git_panel, cx| {
+                git_panel.branches = branches
+                    .into_iter()
+                    .filter(|branch| !branch.is_head)
+                    .map(|branch| branch.name)
+                    .collect();
+                cx.notify();
+            })
+        })
+        .detach_and_log_err(cx);
+    }
+
+    fn stage_status(&self, worktree_id: WorktreeId, path: &Path) -> Option<StageStatus> {
+        self.stage_statuses.get(&worktree_id)?.get(path).copied()
+    }
+
+    /// Returns the changed files of every worktree whose stage status matches the predicate.
+    /// Files with a not yet known stage status are treated as unstaged.
+    fn changed_paths(
+        &self,
+        predicate: impl Fn(StageStatus) -> bool,
+    ) -> Vec<(WorktreeId, Vec<Arc<Path>>)> {
+        self.visible_entries
+            .iter()
+            .filter_map(|worktree_entries| {
+                let worktree_id = worktree_entries.worktree_id;
+                let paths = worktree_entries
+                    .visible_entries
+                    .iter()
+                    .filter(|entry| !entry.is_dir() && entry.git_status.is_some())
+                    .filter(|entry| {
+                        predicate(
+                            self.stage_status(worktree_id, &entry.path)
+                                .unwrap_or(StageStatus::Unstaged),
+                        )
+                    })
+                    .map(|entry| entry.path.clone())
+                    .collect::<Vec<_>>();
+                (!paths.is_empty()).then_some((worktree_id, paths))
+            })
+            .collect()
     }
 
     fn all_staged(&self) -> bool {
-        // TODO: Implement all_staged
-        true
+        !self.no_entries()
+            && self
+                .changed_paths(|stage_status| stage_status.has_unstaged())
+                .is_empty()
+    }
+
+    fn all_changes_toggle_state(&self) -> ToggleState {
+        if self.all_staged() {
+            ToggleState::Selected
+        } else if self
+            .changed_paths(|stage_status| stage_status.has_staged())
+            .is_empty()
+        {
+            ToggleState::Unselected
+        } else {
+            ToggleState::Indeterminate
+        }
     }
 
     fn no_entries(&self) -> bool {
@@ -510,7 +858,9 @@ impl GitPanel {
                         kind: entry.kind,
                         is_expanded,
                         path: entry.path.clone(),
+                        worktree_id: worktree_entries.worktree_id,
                         status,
+                        stage_status: self.stage_status(worktree_entries.worktree_id, &entry.path),
                         hunks: entry.hunks.clone(),
                         depth,
                         index,
@@ -555,11 +905,19 @@ impl GitPanel {
             }
             let snapshot = worktree.read(cx).snapshot();
 
+            let changed_paths = self.changed_paths.get(&worktree_id);
             let mut visible_worktree_entries = snapshot
                 .entries(false, 0)
                 .filter(|entry| !entry.is_external)
-                .filter(|entry| entry.git_status.is_some())
-                .cloned()
+                .filter_map(|entry| {
+                    let mut entry = entry.clone();
+                    if entry.git_status.is_none()
+                        && changed_paths.map_or(false, |paths| paths.contains(&entry.path))
+                    {
+                        entry.git_status = Some(GitFileStatus::Modified);
+                    }
+                    entry.git_status.is_some().then_some(entry)
+                })
                 .collect::<Vec<_>>();
             snapshot.propagate_git_statuses(&mut visible_worktree_entries);
             project::sort_worktree_entries(&mut visible_worktree_entries);
@@ -580,6 +938,18 @@ impl GitPanel {
         }
         self.visible_entries.extend(after_update);
 
+        let updated_worktree_ids = match for_worktree {
+            Some(worktree_id) => vec![worktree_id],
+            None => self
+                .visible_entries
+                .iter()
+                .map(|worktree_entries| worktree_entries.worktree_id)
+                .collect(),
+        };
+        for worktree_id in updated_worktree_ids {
+            self.update_stage_statuses(worktree_id, cx);
+        }
+
         if let Some((worktree_id, entry_id)) = new_selected_entry {
             self.selected_item = self.visible_entries.iter().enumerate().find_map(
                 |(worktree_index, worktree_entries)| {
@@ -682,8 +1052,28 @@ impl GitPanel {
                                                                                 )
                                                                                 .collect()
                                                                     }
-                                                                    // TODO support conflicts display
-                                                                    GitFileStatus::Conflict => Vec::new(),
+                                                                    GitFileStatus::Conflict => {
+                                                                        let buffer_snapshot =
+                                                                            buffer.read(cx).snapshot();
+                                                                        parse_conflicts(&buffer_snapshot.text())
+                                                                            .into_iter()
+                                                                            .map(|conflict| {
+                                                                                let buffer_range = buffer_snapshot
+                                                                                    .anchor_after(conflict.range.start)
+                                                                                    ..buffer_snapshot
+                                                                                        .anchor_before(conflict.range.end);
+                                                                                let point_range = buffer_range
+                                                                                    .clone()
+                                                                                    .to_point(&buffer_snapshot);
+                                                                                DiffHunk {
+                                                                                    row_range: point_range.start.row
+                                                                                        ..point_range.end.row,
+                                                                                    buffer_range,
+                                                                                    diff_base_byte_range: 0..0,
+                                                                                }
+                                                                            })
+                                                                            .collect()
+                                                                    }
                                                                 }
                                                             }).clone()
                                                     })?;
@@ -789,6 +1179,7 @@ impl GitPanel {
         let focus_handle = self.focus_handle(cx).clone();
 
         let changes_string = format!("{} changes", self.entry_count());
+        let no_entries = self.no_entries();
 
         h_flex()
             .h(px(32.))
@@ -798,8 +1189,19 @@ impl GitPanel {
             .child(
                 h_flex()
                     .gap_2()
-                    .child(Checkbox::new("all-changes", true.into()).disabled(true))
-                    .child(div().text_buffer(cx).text_ui_sm(cx).child(changes_string)),
+                    .child(
+                        Checkbox::new("all-changes", self.all_changes_toggle_state())
+                            .disabled(no_entries)
+                            .on_click(cx.listener(|this, state: &ToggleState, cx| {
+                                if *state == ToggleState::Selected {
+                                    this.stage_all(&StageAll, cx)
+                                } else {
+                                    this.unstage_all(&UnstageAll, cx)
+                                }
+                            })),
+                    )
+                    .child(div().text_buffer(cx).text_ui_sm(cx).child(changes_string))
+                    .child(self.render_diff_base_menu(cx)),
             )
             .child(div().flex_grow())
             .child(
@@ -818,11 +1220,14 @@ impl GitPanel {
                                 )
                             })
                             .icon_size(IconSize::Small)
-                            .disabled(true),
+                            .disabled(no_entries)
+                            .on_click(cx.listener(|this, _: &ClickEvent, cx| {
+                                this.discard_all(&DiscardAll, cx)
+                            })),
                     )
                     .child(if self.all_staged() {
                         self.panel_button("unstage-all", "Unstage All").on_click(
-                            cx.listener(move |_, _, cx| cx.dispatch_action(Box::new(DiscardAll))),
+                            cx.listener(move |_, _, cx| cx.dispatch_action(Box::new(UnstageAll))),
                         )
                     } else {
                         self.panel_button("stage-all", "Stage All").on_click(
@@ -832,6 +1237,68 @@ impl GitPanel {
             )
     }
 
+    fn render_diff_base_menu(&self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let project = self.project.clone();
+        let diff_base = project.read(cx).diff_base(cx).clone();
+        let branches = self.branches.clone();
+
+        PopoverMenu::new("diff-base-menu")
+            .trigger(
+                Button::new("diff-base", format!("vs. {diff_base}"))
+                    .label_size(LabelSize::Small)
+                    .color(Color::Muted)
+                    .icon(IconName::ChevronDown)
+                    .icon_position(IconPosition::End)
+                    .icon_size(IconSize::XSmall)
+                    .icon_color(Color::Muted)
+                    .disabled(!project.read(cx).is_local())
+                    .tooltip(|cx| Tooltip::text("Choose What to Compare Changes Against", cx)),
+            )
+            .menu(move |cx| {
+                let project = project.clone();
+                let diff_base = diff_base.clone();
+                let branches = branches.clone();
+                Some(ContextMenu::build(cx, move |mut menu, _| {
+                    let add_entry = |menu: ContextMenu, label: SharedString, base: DiffBase| {
+                        let project = project.clone();
+                        menu.toggleable_entry(
+                            label,
+                            diff_base == base,
+                            IconPosition::Start,
+                            None,
+                            move |cx| {
+                                project.update(cx, |project, cx| {
+                                    project.set_diff_base(base.clone(), cx)
+                                })
+                            },
+                        )
+                    };
+                    menu = add_entry(menu, "Index (unstaged changes)".into(), DiffBase::Index);
+                    menu = add_entry(menu, "HEAD (uncommitted changes)".into(), DiffBase::Head);
+                    if !branches.is_empty() {
+                        menu = menu.header("Branch");
+                        for branch in &branches {
+                            menu = add_entry(
+                                menu,
+                                branch.clone(),
+                                DiffBase::Branch(branch.to_string()),
+                            );
+                        }
+                        menu = menu.header("Merge Base With");
+                        for branch in &branches {
+                            menu = add_entry(
+                                menu,
+                                branch.clone(),
+                                DiffBase::MergeBase(branch.to_string()),
+                            );
+                        }
+                    }
+                    menu
+                }))
+            })
+            .anchor(gpui::Corner::TopLeft)
+    }
+
     pub fn render_commit_editor(&self, cx: &ViewContext<Self>) -> impl IntoElement {
         let focus_handle_1 = self.focus_handle(cx).clone();
         let focus_handle_2 = self.focus_handle(cx).clone();
@@ -866,27 +1333,30 @@ impl GitPanel {
                 this.commit_all_changes(&CommitAllChanges, cx)
             }));
 
-        div().w_full().h(px(140.)).px_2().pt_1().pb_2().child(
+        let can_commit = !self.no_entries() && self.commit_message(cx).is_some();
+        let commit_editor = self.commit_editor.clone();
+
+        div().w_full().min_h(px(140.)).px_2().pt_1().pb_2().child(
             v_flex()
+                .id("commit-editor-container")
                 .h_full()
                 .py_2p5()
                 .px_3()
                 .bg(cx.theme().colors().editor_background)
                 .font_buffer(cx)
                 .text_ui_sm(cx)
-                .text_color(cx.theme().colors().text_muted)
-                .child("Add a message")
+                .on_click(cx.listener(move |_, _: &ClickEvent, cx| {
+                    cx.focus_view(&commit_editor);
+                }))
+                .child(div().flex_grow().child(self.commit_editor.clone()))
                 .gap_1()
-                .child(div().flex_grow())
                 .child(h_flex().child(div().gap_1().flex_grow()).child(
                     if self.current_modifiers.alt {
-                        commit_all_button
+                        commit_all_button.disabled(!can_commit)
                     } else {
-                        commit_staged_button
+                        commit_staged_button.disabled(!can_commit)
                     },
-                ))
-                .cursor(CursorStyle::OperationNotAllowed)
-                .opacity(0.5),
+                )),
         )
     }
 
@@ -999,8 +1469,14 @@ impl GitPanel {
     ) -> impl IntoElement {
         let id = id.to_proto() as usize;
         let checkbox_id = ElementId::Name(format!("checkbox_{}", id).into());
-        let is_staged = ToggleState::Selected;
+        let is_staged = match details.stage_status {
+            Some(StageStatus::Staged) => ToggleState::Selected,
+            Some(StageStatus::PartiallyStaged) => ToggleState::Indeterminate,
+            Some(StageStatus::Unstaged) | None => ToggleState::Unselected,
+        };
         let handle = cx.view().downgrade();
+        let worktree_id = details.worktree_id;
+        let path = details.path.clone();
 
         h_flex()
             .id(id)
@@ -1013,7 +1489,23 @@ impl GitPanel {
             .font_buffer(cx)
             .text_ui_sm(cx)
             .when(!details.is_dir(), |this| {
-                this.child(Checkbox::new(checkbox_id, is_staged))
+                this.child(Checkbox::new(checkbox_id, is_staged).on_click({
+                    let handle = handle.clone();
+                    let path = path.clone();
+                    move |state, cx| {
+                        let stage = *state == ToggleState::Selected;
+                        handle
+                            .update(cx, |git_panel, cx| {
+                                git_panel.toggle_staged_for_entry(
+                                    worktree_id,
+                                    path.clone(),
+                                    stage,
+                                    cx,
+                                );
+                            })
+                            .ok();
+                    }
+                }))
             })
             .when_some(details.status, |this, status| {
                 this.child(git_status_icon(status))
@@ -1022,6 +1514,27 @@ impl GitPanel {
                 ListItem::new(("label", id))
                     .toggle_state(selected)
                     .child(h_flex().gap_1p5().child(details.display_name.clone()))
+                    .when(!details.is_dir(), |this| {
+                        this.end_hover_slot(
+                            IconButton::new(("discard", id), IconName::Undo)
+                                .icon_size(IconSize::Small)
+                                .tooltip(|cx| Tooltip::text("Discard Changes", cx))
+                                .on_click({
+                                    let handle = handle.clone();
+                                    move |_, cx| {
+                                        handle
+                                            .update(cx, |git_panel, cx| {
+                                                git_panel.discard_entry(
+                                                    worktree_id,
+                                                    path.clone(),
+                                                    cx,
+                                                );
+                                            })
+                                            .ok();
+                                    }
+                                }),
+                        )
+                    })
                     .on_click(move |e, cx| {
                         handle
                             .update(cx, |git_panel, cx| {
@@ -4,7 +4,10 @@ use gpui::{actions, AppContext, Hsla};
 use settings::GitPanelSettings;
 use ui::{Color, Icon, IconName, IntoElement};
 
+mod askpass_modal;
+pub mod commit_history;
 pub mod git_panel;
+mod remote_operations;
 mod settings;
 
 actions!(
@@ -14,12 +17,15 @@ actions!(
         UnstageAll,
         DiscardAll,
         CommitStagedChanges,
-        CommitAllChanges
+        CommitAllChanges,
+        ShowCommitHistory
     ]
 );
 
 pub fn init(cx: &mut AppContext) {
     GitPanelSettings::register(cx);
+    commit_history::init(cx);
+    remote_operations::init(cx);
 }
 
 const ADDED_COLOR: Hsla = Hsla {
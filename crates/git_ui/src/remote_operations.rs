@@ -0,0 +1,66 @@
+use git::repository::{AskPassRequest, RemoteOperation};
+use gpui::{AppContext, Model, ViewContext};
+use project::Project;
+use workspace::{notifications::NotifyTaskExt as _, Workspace};
+use zed_actions::git::{Fetch, Pull, Push};
+
+use crate::askpass_modal::AskPassModal;
+
+pub fn init(cx: &mut AppContext) {
+    cx.observe_new_views(register_workspace).detach();
+}
+
+fn register_workspace(workspace: &mut Workspace, cx: &mut ViewContext<Workspace>) {
+    workspace
+        .register_action(|workspace, _: &Fetch, cx| {
+            run_remote_operation(workspace, RemoteOperation::Fetch, cx)
+        })
+        .register_action(|workspace, _: &Pull, cx| {
+            run_remote_operation(workspace, RemoteOperation::Pull, cx)
+        })
+        .register_action(|workspace, _: &Push, cx| {
+            run_remote_operation(workspace, RemoteOperation::Push, cx)
+        });
+
+    let project = workspace.project().clone();
+    cx.subscribe(&project, |workspace, _: Model<Project>, event, cx| {
+        if let project::Event::GitAskPass(request) = event {
+            show_ask_pass(workspace, request.clone(), cx);
+        }
+    })
+    .detach();
+}
+
+/// Runs the operation in the repository of the first worktree, like the branch picker does.
+fn run_remote_operation(
+    workspace: &mut Workspace,
+    operation: RemoteOperation,
+    cx: &mut ViewContext<Workspace>,
+) {
+    let project = workspace.project().clone();
+    let Some(worktree_id) = project
+        .read(cx)
+        .visible_worktrees(cx)
+        .next()
+        .map(|worktree| worktree.read(cx).id())
+    else {
+        return;
+    };
+    project
+        .update(cx, |project, cx| {
+            project.git_remote_operation(worktree_id, operation, cx)
+        })
+        .detach_and_notify_err(cx);
+}
+
+fn show_ask_pass(
+    workspace: &mut Workspace,
+    request: AskPassRequest,
+    cx: &mut ViewContext<Workspace>,
+) {
+    if let Some(modal) = workspace.active_modal::<AskPassModal>(cx) {
+        modal.update(cx, |modal, cx| modal.push_request(request, cx));
+    } else {
+        workspace.toggle_modal(cx, |cx| AskPassModal::new(request, cx));
+    }
+}
@@ -60,6 +60,7 @@ pub enum LanguageModelCompletionEvent {
 pub enum StopReason {
     EndTurn,
     MaxTokens,
+    StopSequence,
     ToolUse,
 }
 
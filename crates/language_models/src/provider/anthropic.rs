@@ -528,6 +528,7 @@ pub fn map_to_language_model_completion_events(
                                 let stop_reason = match stop_reason {
                                     "end_turn" => StopReason::EndTurn,
                                     "max_tokens" => StopReason::MaxTokens,
+                                    "stop_sequence" => StopReason::StopSequence,
                                     "tool_use" => StopReason::ToolUse,
                                     _ => StopReason::EndTurn,
                                 };
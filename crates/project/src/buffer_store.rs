@@ -10,7 +10,11 @@ use client::Client;
 use collections::{hash_map, HashMap, HashSet};
 use fs::Fs;
 use futures::{channel::oneshot, future::Shared, Future, FutureExt as _, StreamExt};
-use git::{blame::Blame, diff::BufferDiff, repository::RepoPath};
+use git::{
+    blame::Blame,
+    diff::BufferDiff,
+    repository::{DiffBase, GitRepository, RepoPath},
+};
 use gpui::{
     AppContext, AsyncAppContext, Context as _, EventEmitter, Model, ModelContext, Subscription,
     Task, WeakModel,
@@ -50,6 +54,8 @@ pub struct BufferStore {
     opened_buffers: HashMap<BufferId, OpenBuffer>,
     downstream_client: Option<(AnyProtoClient, u64)>,
     shared_buffers: HashMap<proto::PeerId, HashMap<BufferId, SharedBuffer>>,
+    /// What the change sets of local buffers compare the buffer contents against.
+    diff_base: DiffBase,
 }
 
 #[derive(Hash, Eq, PartialEq, Clone)]
@@ -398,9 +404,10 @@ impl RemoteBufferStore {
 }
 
 impl LocalBufferStore {
-    fn load_staged_text(
+    fn load_diff_base_text(
         &self,
         buffer: &Model<Buffer>,
+        base: &DiffBase,
         cx: &AppContext,
     ) -> Task<Result<Option<String>>> {
         let Some(file) = buffer.read(cx).file() else {
@@ -416,7 +423,9 @@ impl LocalBufferStore {
             return Task::ready(Err(anyhow!("no such worktree")));
         };
 
-        worktree.read(cx).load_staged_file(path.as_ref(), cx)
+        worktree
+            .read(cx)
+            .load_diff_base_file(path.as_ref(), base.clone(), cx)
     }
 
     fn save_local_buffer(
@@ -556,34 +565,75 @@ impl LocalBufferStore {
             })
             .collect::<Vec<_>>();
 
+        this.reload_diff_bases(worktree_handle, buffer_change_sets, cx)
+            .detach_and_log_err(cx);
+    }
+
+    /// Reloads the base texts of the given change sets of a local worktree's buffers.
+    fn reload_diff_bases(
+        &mut self,
+        worktree_handle: Model<Worktree>,
+        buffer_change_sets: Vec<(Model<BufferChangeSet>, text::BufferSnapshot, Arc<Path>)>,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<()>> {
         if buffer_change_sets.is_empty() {
-            return;
+            return Task::ready(Ok(()));
         }
 
+        let diff_base = self.diff_base.clone();
         cx.spawn(move |this, mut cx| async move {
             let snapshot =
                 worktree_handle.update(&mut cx, |tree, _| tree.as_local().unwrap().snapshot())?;
             let diff_bases_by_buffer = cx
                 .background_executor()
                 .spawn(async move {
-                    buffer_change_sets
-                        .into_iter()
-                        .filter_map(|(change_set, buffer_snapshot, path)| {
-                            let (repo_entry, local_repo_entry) = snapshot.repo_for_path(&path)?;
-                            let relative_path = repo_entry.relativize(&snapshot, &path).ok()?;
-                            let base_text = local_repo_entry.repo().load_index_text(&relative_path);
-                            Some((change_set, buffer_snapshot, base_text))
-                        })
-                        .collect::<Vec<_>>()
+                    // Group the buffers by repository, so that each repository resolves the
+                    // diff base once.
+                    let mut change_sets_by_repo =
+                        HashMap::<ProjectEntryId, (Arc<dyn GitRepository>, Vec<_>)>::default();
+                    for (change_set, buffer_snapshot, path) in buffer_change_sets {
+                        let Some((repo_entry, local_repo_entry)) = snapshot.repo_for_path(&path)
+                        else {
+                            continue;
+                        };
+                        let Some(relative_path) = repo_entry.relativize(&snapshot, &path).ok()
+                        else {
+                            continue;
+                        };
+                        change_sets_by_repo
+                            .entry(repo_entry.work_directory_id())
+                            .or_insert_with(|| (local_repo_entry.repo().clone(), Vec::new()))
+                            .1
+                            .push((change_set, buffer_snapshot, relative_path));
+                    }
+
+                    let mut diff_bases_by_buffer = Vec::new();
+                    for (repo, change_sets) in change_sets_by_repo.into_values() {
+                        let relative_paths = change_sets
+                            .iter()
+                            .map(|(_, _, relative_path)| relative_path.as_path())
+                            .collect::<Vec<_>>();
+                        let base_texts = repo.load_diff_base_texts(&diff_base, &relative_paths);
+                        diff_bases_by_buffer.extend(change_sets.into_iter().zip(base_texts).map(
+                            |((change_set, buffer_snapshot, _), base_text)| {
+                                (change_set, buffer_snapshot, base_text)
+                            },
+                        ));
+                    }
+                    diff_bases_by_buffer
                 })
                 .await;
 
-            this.update(&mut cx, |this, cx| {
-                for (change_set, buffer_snapshot, staged_text) in diff_bases_by_buffer {
+            let diffs_updated = this.update(&mut cx, |this, cx| {
+                let mut diffs_updated = Vec::new();
+                for (change_set, buffer_snapshot, base_text) in diff_bases_by_buffer {
                     change_set.update(cx, |change_set, cx| {
-                        if let Some(staged_text) = staged_text.clone() {
-                            let _ =
-                                change_set.set_base_text(staged_text, buffer_snapshot.clone(), cx);
+                        if let Some(base_text) = base_text.clone() {
+                            diffs_updated.push(change_set.set_base_text(
+                                base_text,
+                                buffer_snapshot.clone(),
+                                cx,
+                            ));
                         } else {
                             change_set.unset_base_text(buffer_snapshot.clone(), cx);
                         }
@@ -594,14 +644,73 @@ impl LocalBufferStore {
                             .send(proto::UpdateDiffBase {
                                 project_id: *project_id,
                                 buffer_id: buffer_snapshot.remote_id().to_proto(),
-                                staged_text,
+                                base_text,
                             })
                             .log_err();
                     }
                 }
-            })
+                diffs_updated
+            })?;
+            for diff_updated in diffs_updated {
+                diff_updated.await.ok();
+            }
+            Ok(())
+        })
+    }
+
+    pub fn diff_base(&self) -> &DiffBase {
+        &self.diff_base
+    }
+
+    /// Changes what the change sets of local buffers compare against, and reloads their base texts.
+    /// The returned task completes once all of their diffs are recalculated.
+    pub fn set_diff_base(&mut self, diff_base: DiffBase, cx: &mut ModelContext<Self>) -> Task<()> {
+        if self.diff_base == diff_base {
+            return Task::ready(());
+        }
+        self.diff_base = diff_base;
+
+        let mut change_sets_by_worktree =
+            HashMap::<WorktreeId, (Model<Worktree>, Vec<_>)>::default();
+        for buffer in self.opened_buffers.values() {
+            let OpenBuffer::Complete {
+                buffer,
+                unstaged_changes,
+            } = buffer
+            else {
+                continue;
+            };
+            let (Some(buffer), Some(change_set)) = (
+                buffer.upgrade(),
+                unstaged_changes
+                    .as_ref()
+                    .and_then(|change_set| change_set.upgrade()),
+            ) else {
+                continue;
+            };
+            let buffer = buffer.read(cx);
+            let Some(file) = File::from_dyn(buffer.file()) else {
+                continue;
+            };
+            if !file.worktree.read(cx).is_local() {
+                continue;
+            }
+            change_sets_by_worktree
+                .entry(file.worktree.read(cx).id())
+                .or_insert_with(|| (file.worktree.clone(), Vec::new()))
+                .1
+                .push((change_set, buffer.text_snapshot(), file.path.clone()));
+        }
+
+        let reloads = change_sets_by_worktree
+            .into_values()
+            .map(|(worktree, change_sets)| self.reload_diff_bases(worktree, change_sets, cx))
+            .collect::<Vec<_>>();
+        cx.background_executor().spawn(async move {
+            for reload in reloads {
+                reload.await.log_err();
+            }
         })
-        .detach_and_log_err(cx);
     }
 
     fn local_worktree_entry_changed(
@@ -926,6 +1035,7 @@ impl BufferStore {
             shared_buffers: Default::default(),
             loading_buffers: Default::default(),
             loading_change_sets: Default::default(),
+            diff_base: DiffBase::default(),
             worktree_store,
         }
     }
@@ -949,6 +1059,7 @@ impl BufferStore {
             opened_buffers: Default::default(),
             loading_buffers: Default::default(),
             loading_change_sets: Default::default(),
+            diff_base: DiffBase::default(),
             shared_buffers: Default::default(),
             worktree_store,
         }
@@ -1035,7 +1146,9 @@ impl BufferStore {
             hash_map::Entry::Occupied(e) => e.get().clone(),
             hash_map::Entry::Vacant(entry) => {
                 let load = match &self.state {
-                    BufferStoreState::Local(this) => this.load_staged_text(&buffer, cx),
+                    BufferStoreState::Local(this) => {
+                        this.load_diff_base_text(&buffer, &self.diff_base, cx)
+                    }
                     BufferStoreState::Remote(this) => this.load_staged_text(buffer_id, cx),
                 };
 
@@ -2004,8 +2117,8 @@ impl BufferStore {
             return Ok(());
         };
         change_set.update(&mut cx, |change_set, cx| {
-            if let Some(staged_text) = request.payload.staged_text {
-                let _ = change_set.set_base_text(staged_text, buffer.read(cx).text_snapshot(), cx);
+            if let Some(base_text) = request.payload.base_text {
+                let _ = change_set.set_base_text(base_text, buffer.read(cx).text_snapshot(), cx);
             } else {
                 change_set.unset_base_text(buffer.read(cx).text_snapshot(), cx)
             }
@@ -11,7 +11,7 @@ use crate::{
     worktree_store::{WorktreeStore, WorktreeStoreEvent},
     yarn::YarnPathStore,
     CodeAction, Completion, CoreCompletion, Hover, InlayHint, ProjectItem as _, ProjectPath,
-    ProjectTransaction, ResolveState, Symbol, ToolchainStore,
+    ProjectTransaction, ResolveState, SemanticToken, SemanticTokens, Symbol, ToolchainStore,
 };
 use anyhow::{anyhow, Context as _, Result};
 use async_trait::async_trait;
@@ -94,6 +94,11 @@ pub use worktree::{
 
 const SERVER_LAUNCHING_BEFORE_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
 pub const SERVER_PROGRESS_THROTTLE_TIMEOUT: Duration = Duration::from_millis(100);
+const PULL_DIAGNOSTICS_DEBOUNCE: Duration = Duration::from_millis(125);
+/// The diagnostics of each task are published under an id counted up from this one, which the
+/// language registry, counting up from zero, never reaches. No language server has these ids, so
+/// they aren't listed among the running servers.
+const FIRST_TASK_DIAGNOSTICS_ID: usize = 1 << 30;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum FormatTrigger {
@@ -171,6 +176,14 @@ pub struct LocalLspStore {
         >,
     >,
     buffer_snapshots: HashMap<BufferId, HashMap<LanguageServerId, Vec<LspBufferSnapshot>>>, // buffer_id -> server_id -> vec of snapshots
+    semantic_tokens: HashMap<BufferId, BufferSemanticTokens>,
+    document_diagnostics: HashMap<LanguageServerId, HashMap<lsp::Url, DocumentDiagnostics>>,
+    document_diagnostic_pulls: HashMap<BufferId, Task<()>>,
+    /// Servers whose workspace diagnostics were pulled, to pull them again when the server asks for a refresh.
+    workspace_diagnostics_servers: HashSet<LanguageServerId>,
+    /// Diagnostics published from the output of tasks, by task label:
+    /// the id they are published under, and the paths that have them.
+    task_diagnostics: HashMap<String, (LanguageServerId, HashSet<PathBuf>)>,
     _subscription: gpui::Subscription,
     registered_buffers: HashMap<BufferId, usize>,
 }
@@ -819,6 +832,63 @@ impl LocalLspStore {
             })
             .detach();
 
+        language_server
+            .on_request::<lsp::request::SemanticTokensRefresh, _, _>({
+                let this = this.clone();
+                move |(), mut cx| {
+                    let this = this.clone();
+                    async move {
+                        this.update(&mut cx, |this, cx| {
+                            cx.emit(LspStoreEvent::RefreshSemanticTokens);
+                            this.downstream_client.as_ref().map(|(client, project_id)| {
+                                client.send(proto::RefreshSemanticTokens {
+                                    project_id: *project_id,
+                                })
+                            })
+                        })?
+                        .transpose()?;
+                        Ok(())
+                    }
+                }
+            })
+            .detach();
+
+        language_server
+            .on_request::<lsp::request::WorkspaceDiagnosticRefresh, _, _>({
+                let this = this.clone();
+                move |(), mut cx| {
+                    let this = this.clone();
+                    async move {
+                        this.update(&mut cx, |this, cx| {
+                            this.refresh_pulled_diagnostics(server_id, cx);
+                        })?;
+                        Ok(())
+                    }
+                }
+            })
+            .detach();
+
+        language_server
+            .on_request::<lsp::request::CodeLensRefresh, _, _>({
+                let this = this.clone();
+                move |(), mut cx| {
+                    let this = this.clone();
+                    async move {
+                        this.update(&mut cx, |this, cx| {
+                            cx.emit(LspStoreEvent::RefreshCodeLens);
+                            this.downstream_client.as_ref().map(|(client, project_id)| {
+                                client.send(proto::RefreshCodeLens {
+                                    project_id: *project_id,
+                                })
+                            })
+                        })?
+                        .transpose()?;
+                        Ok(())
+                    }
+                }
+            })
+            .detach();
+
         language_server
             .on_request::<lsp::request::ShowMessageRequest, _, _>({
                 let this = this.clone();
@@ -1957,6 +2027,8 @@ impl LocalLspStore {
     ) {
         buffer.update(cx, |buffer, cx| {
             self.buffer_snapshots.remove(&buffer.remote_id());
+            self.semantic_tokens.remove(&buffer.remote_id());
+            self.document_diagnostic_pulls.remove(&buffer.remote_id());
             for (_, language_server) in self.language_servers_for_buffer(buffer, cx) {
                 language_server
                     .notify::<lsp::notification::DidCloseTextDocument>(
@@ -1969,6 +2041,46 @@ impl LocalLspStore {
         });
     }
 
+    /// Records the diagnostics a server reported for a document and returns all of its diagnostics for it.
+    ///
+    /// Servers supporting pull diagnostics may still push some of them, so for those servers
+    /// both kinds are kept, and a report of one kind does not replace the diagnostics of the other.
+    fn merge_document_diagnostics(
+        &mut self,
+        server_id: LanguageServerId,
+        uri: &lsp::Url,
+        kind: DiagnosticSourceKind,
+        diagnostics: Vec<lsp::Diagnostic>,
+    ) -> Vec<lsp::Diagnostic> {
+        let supports_pull = match self.language_servers.get(&server_id) {
+            Some(LanguageServerState::Running { server, .. }) => {
+                server.capabilities().diagnostic_provider.is_some()
+            }
+            _ => false,
+        };
+        if !supports_pull {
+            return diagnostics;
+        }
+
+        let document = self
+            .document_diagnostics
+            .entry(server_id)
+            .or_default()
+            .entry(uri.clone())
+            .or_default();
+        match kind {
+            DiagnosticSourceKind::Pushed => document.pushed = diagnostics,
+            DiagnosticSourceKind::Pulled => document.pulled = diagnostics,
+        }
+        let mut merged = document.pushed.clone();
+        for diagnostic in &document.pulled {
+            if !document.pushed.contains(diagnostic) {
+                merged.push(diagnostic.clone());
+            }
+        }
+        merged
+    }
+
     fn buffer_snapshot_for_lsp_version(
         &mut self,
         buffer: &Model<Buffer>,
@@ -2778,6 +2890,8 @@ pub enum LspStoreEvent {
     },
     Notification(String),
     RefreshInlayHints,
+    RefreshSemanticTokens,
+    RefreshCodeLens,
     DiagnosticsUpdated {
         language_server_id: LanguageServerId,
         path: ProjectPath,
@@ -2831,10 +2945,15 @@ impl LspStore {
         client.add_model_request_handler(Self::handle_resolve_inlay_hint);
         client.add_model_request_handler(Self::handle_open_buffer_for_symbol);
         client.add_model_request_handler(Self::handle_refresh_inlay_hints);
+        client.add_model_request_handler(Self::handle_get_semantic_tokens);
+        client.add_model_request_handler(Self::handle_refresh_semantic_tokens);
+        client.add_model_request_handler(Self::handle_pull_workspace_diagnostics);
+        client.add_model_request_handler(Self::handle_refresh_code_lens);
         client.add_model_request_handler(Self::handle_on_type_formatting);
         client.add_model_request_handler(Self::handle_apply_additional_edits_for_completion);
         client.add_model_request_handler(Self::handle_register_buffer_with_language_servers);
         client.add_model_request_handler(Self::handle_lsp_command::<GetCodeActions>);
+        client.add_model_request_handler(Self::handle_lsp_command::<GetCodeLens>);
         client.add_model_request_handler(Self::handle_lsp_command::<GetCompletions>);
         client.add_model_request_handler(Self::handle_lsp_command::<GetHover>);
         client.add_model_request_handler(Self::handle_lsp_command::<GetDefinition>);
@@ -2842,6 +2961,9 @@ impl LspStore {
         client.add_model_request_handler(Self::handle_lsp_command::<GetTypeDefinition>);
         client.add_model_request_handler(Self::handle_lsp_command::<GetDocumentHighlights>);
         client.add_model_request_handler(Self::handle_lsp_command::<GetReferences>);
+        client.add_model_request_handler(Self::handle_lsp_command::<PrepareCallHierarchy>);
+        client.add_model_request_handler(Self::handle_lsp_command::<GetIncomingCalls>);
+        client.add_model_request_handler(Self::handle_lsp_command::<GetOutgoingCalls>);
         client.add_model_request_handler(Self::handle_lsp_command::<PrepareRename>);
         client.add_model_request_handler(Self::handle_lsp_command::<PerformRename>);
         client.add_model_request_handler(Self::handle_lsp_command::<lsp_ext_command::ExpandMacro>);
@@ -2945,6 +3067,11 @@ impl LspStore {
                 current_lsp_settings: ProjectSettings::get_global(cx).lsp.clone(),
                 buffers_being_formatted: Default::default(),
                 buffer_snapshots: Default::default(),
+                semantic_tokens: Default::default(),
+                document_diagnostics: Default::default(),
+                document_diagnostic_pulls: Default::default(),
+                workspace_diagnostics_servers: Default::default(),
+                task_diagnostics: Default::default(),
                 prettier_store,
                 environment,
                 http_client,
@@ -3206,7 +3333,8 @@ impl LspStore {
             }
             let refcount = local.registered_buffers.entry(buffer_id).or_insert(0);
             *refcount += 1;
-            if *refcount == 1 {
+            let is_first_registration = *refcount == 1;
+            if is_first_registration {
                 local.register_buffer_with_language_servers(buffer, cx);
             }
 
@@ -3225,6 +3353,9 @@ impl LspStore {
                 }
             })
             .detach();
+            if is_first_registration {
+                self.pull_document_diagnostics(buffer, cx);
+            }
         } else if let Some((upstream_client, upstream_project_id)) = self.upstream_client() {
             let buffer_id = buffer.read(cx).remote_id().to_proto();
             cx.background_executor()
@@ -4571,6 +4702,490 @@ impl LspStore {
         }
     }
 
+    pub fn semantic_tokens(
+        &mut self,
+        buffer_handle: Model<Buffer>,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<SemanticTokens>> {
+        if let Some((client, project_id)) = self.upstream_client() {
+            let request = proto::GetSemanticTokens {
+                project_id,
+                buffer_id: buffer_handle.read(cx).remote_id().into(),
+                version: serialize_version(&buffer_handle.read(cx).version()),
+            };
+            return cx.spawn(move |_, mut cx| async move {
+                let response = client
+                    .request(request)
+                    .await
+                    .context("semantic tokens proto request")?;
+                buffer_handle
+                    .update(&mut cx, |buffer, _| {
+                        buffer.wait_for_version(deserialize_version(&response.version))
+                    })?
+                    .await?;
+                deserialize_semantic_tokens(response)
+            });
+        }
+
+        let buffer = buffer_handle.read(cx);
+        let buffer_id = buffer.remote_id();
+        let Some(language_server) = self
+            .language_servers_for_local_buffer(buffer, cx)
+            .find(|(_, server)| server.capabilities().semantic_tokens_provider.is_some())
+            .map(|(_, server)| server.clone())
+        else {
+            return Task::ready(Ok(SemanticTokens::default()));
+        };
+        let Some(file) = File::from_dyn(buffer.file()).and_then(File::as_local) else {
+            return Task::ready(Ok(SemanticTokens::default()));
+        };
+        let Some(options) = semantic_tokens_options(&language_server.capabilities()) else {
+            return Task::ready(Ok(SemanticTokens::default()));
+        };
+        let supports_delta = match options.full {
+            Some(lsp::SemanticTokensFullOptions::Delta { delta }) => delta.unwrap_or(false),
+            Some(lsp::SemanticTokensFullOptions::Bool(true)) => false,
+            Some(lsp::SemanticTokensFullOptions::Bool(false)) | None => {
+                return Task::ready(Ok(SemanticTokens::default()));
+            }
+        };
+        let token_types = options
+            .legend
+            .token_types
+            .iter()
+            .map(|token_type| token_type.as_str().to_string())
+            .collect::<Arc<[String]>>();
+        let token_modifiers = options
+            .legend
+            .token_modifiers
+            .iter()
+            .map(|modifier| modifier.as_str().to_string())
+            .collect::<Arc<[String]>>();
+
+        let server_id = language_server.server_id();
+        let snapshot = buffer.text_snapshot();
+        let text_document =
+            lsp::TextDocumentIdentifier::new(lsp::Url::from_file_path(file.abs_path(cx)).unwrap());
+        let previous_result_id = self
+            .as_local()
+            .and_then(|local| local.semantic_tokens.get(&buffer_id))
+            .filter(|previous| supports_delta && previous.server_id == server_id)
+            .and_then(|previous| previous.result_id.clone());
+
+        // Send the request right away, so that it is made against the current version of the buffer.
+        let lsp_request = match previous_result_id.clone() {
+            Some(previous_result_id) => language_server
+                .request::<lsp::request::SemanticTokensFullDeltaRequest>(
+                    lsp::SemanticTokensDeltaParams {
+                        text_document,
+                        previous_result_id,
+                        work_done_progress_params: Default::default(),
+                        partial_result_params: Default::default(),
+                    },
+                )
+                .boxed_local(),
+            None => language_server
+                .request::<lsp::request::SemanticTokensFullRequest>(lsp::SemanticTokensParams {
+                    text_document,
+                    work_done_progress_params: Default::default(),
+                    partial_result_params: Default::default(),
+                })
+                .map(|response| {
+                    response.map(|response| {
+                        response.map(|response| match response {
+                            lsp::SemanticTokensResult::Tokens(tokens) => {
+                                lsp::SemanticTokensFullDeltaResult::Tokens(tokens)
+                            }
+                            lsp::SemanticTokensResult::Partial(partial) => {
+                                lsp::SemanticTokensFullDeltaResult::Tokens(lsp::SemanticTokens {
+                                    result_id: None,
+                                    data: partial.data,
+                                })
+                            }
+                        })
+                    })
+                })
+                .boxed_local(),
+        };
+
+        cx.spawn(move |this, mut cx| async move {
+            let response = lsp_request.await.context("semantic tokens LSP request")?;
+            let data = this.update(&mut cx, |this, _| {
+                let local = this
+                    .as_local_mut()
+                    .context("semantic tokens are only cached locally")?;
+                let (result_id, edits) = match response {
+                    Some(lsp::SemanticTokensFullDeltaResult::Tokens(tokens)) => {
+                        local.semantic_tokens.insert(
+                            buffer_id,
+                            BufferSemanticTokens {
+                                server_id,
+                                result_id: tokens.result_id,
+                                data: tokens.data.clone(),
+                            },
+                        );
+                        return anyhow::Ok(tokens.data);
+                    }
+                    Some(lsp::SemanticTokensFullDeltaResult::TokensDelta(delta)) => {
+                        (delta.result_id, delta.edits)
+                    }
+                    Some(lsp::SemanticTokensFullDeltaResult::PartialTokensDelta { edits }) => {
+                        (None, edits)
+                    }
+                    None => {
+                        local.semantic_tokens.remove(&buffer_id);
+                        return Ok(Vec::new());
+                    }
+                };
+
+                let previous = local
+                    .semantic_tokens
+                    .get_mut(&buffer_id)
+                    .filter(|previous| {
+                        previous.server_id == server_id && previous.result_id == previous_result_id
+                    });
+                let Some(previous) = previous else {
+                    local.semantic_tokens.remove(&buffer_id);
+                    return Err(anyhow!("semantic tokens delta for an outdated result"));
+                };
+                apply_semantic_token_edits(&mut previous.data, edits);
+                previous.result_id = result_id;
+                Ok(previous.data.clone())
+            })??;
+
+            Ok(SemanticTokens {
+                token_types,
+                token_modifiers,
+                tokens: semantic_tokens_from_lsp(&data, &snapshot),
+            })
+        })
+    }
+
+    /// Schedules pulling the diagnostics of a buffer from its language servers that support
+    /// <a href="https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#textDocument_diagnostic">textDocument/diagnostic</a>,
+    /// replacing the pull already scheduled for it, if any.
+    fn pull_document_diagnostics(&mut self, buffer: &Model<Buffer>, cx: &mut ModelContext<Self>) {
+        if !self.mode.is_local() {
+            return;
+        }
+        let buffer_id = buffer.read(cx).remote_id();
+        let weak_buffer = buffer.downgrade();
+        let pull = cx.spawn(move |this, mut cx| async move {
+            cx.background_executor()
+                .timer(PULL_DIAGNOSTICS_DEBOUNCE)
+                .await;
+            let Some(requests) = this
+                .update(&mut cx, |this, cx| {
+                    let buffer = weak_buffer.upgrade()?;
+                    Some(this.document_diagnostics_requests(&buffer, cx))
+                })
+                .ok()
+                .flatten()
+            else {
+                return;
+            };
+
+            for request in requests {
+                let Some(report) = request
+                    .response
+                    .await
+                    .context("pulling document diagnostics")
+                    .log_err()
+                else {
+                    continue;
+                };
+                this.update(&mut cx, |this, cx| {
+                    this.apply_document_diagnostic_report(
+                        request.server_id,
+                        &request.adapter,
+                        request.uri,
+                        Some(request.version),
+                        report,
+                        cx,
+                    );
+                })
+                .ok();
+            }
+        });
+        if let Some(local) = self.as_local_mut() {
+            local.document_diagnostic_pulls.insert(buffer_id, pull);
+        }
+    }
+
+    fn document_diagnostics_requests(
+        &self,
+        buffer: &Model<Buffer>,
+        cx: &AppContext,
+    ) -> Vec<DocumentDiagnosticsRequest> {
+        let buffer = buffer.read(cx);
+        let Some(local) = self.as_local() else {
+            return Vec::new();
+        };
+        let Some(file) = File::from_dyn(buffer.file()).and_then(File::as_local) else {
+            return Vec::new();
+        };
+        let Some(uri) = lsp::Url::from_file_path(file.abs_path(cx)).log_err() else {
+            return Vec::new();
+        };
+
+        local
+            .language_servers_for_buffer(buffer, cx)
+            .filter_map(|(adapter, server)| {
+                let options = diagnostic_options(&server.capabilities())?;
+                let server_id = server.server_id();
+                let version = local
+                    .buffer_snapshots
+                    .get(&buffer.remote_id())?
+                    .get(&server_id)?
+                    .last()?
+                    .version;
+                let previous_result_id = local
+                    .document_diagnostics
+                    .get(&server_id)
+                    .and_then(|documents| documents.get(&uri))
+                    .and_then(|document| document.result_id.clone());
+                let response = server
+                    .request::<lsp::request::DocumentDiagnosticRequest>(
+                        lsp::DocumentDiagnosticParams {
+                            text_document: lsp::TextDocumentIdentifier::new(uri.clone()),
+                            identifier: options.identifier,
+                            previous_result_id,
+                            work_done_progress_params: Default::default(),
+                            partial_result_params: Default::default(),
+                        },
+                    )
+                    .boxed_local();
+                Some(DocumentDiagnosticsRequest {
+                    server_id,
+                    adapter: adapter.clone(),
+                    uri: uri.clone(),
+                    version,
+                    response,
+                })
+            })
+            .collect()
+    }
+
+    fn apply_document_diagnostic_report(
+        &mut self,
+        server_id: LanguageServerId,
+        adapter: &CachedLspAdapter,
+        uri: lsp::Url,
+        version: Option<i32>,
+        report: lsp::DocumentDiagnosticReportResult,
+        cx: &mut ModelContext<Self>,
+    ) {
+        let (report, related_documents) = match report {
+            lsp::DocumentDiagnosticReportResult::Report(lsp::DocumentDiagnosticReport::Full(
+                report,
+            )) => (
+                Some(lsp::DocumentDiagnosticReportKind::Full(
+                    report.full_document_diagnostic_report,
+                )),
+                report.related_documents,
+            ),
+            lsp::DocumentDiagnosticReportResult::Report(
+                lsp::DocumentDiagnosticReport::Unchanged(report),
+            ) => (
+                Some(lsp::DocumentDiagnosticReportKind::Unchanged(
+                    report.unchanged_document_diagnostic_report,
+                )),
+                report.related_documents,
+            ),
+            lsp::DocumentDiagnosticReportResult::Partial(partial) => {
+                (None, partial.related_documents)
+            }
+        };
+
+        if let Some(report) = report {
+            self.apply_pulled_diagnostics(server_id, adapter, uri, version, report, cx);
+        }
+        for (uri, report) in related_documents.into_iter().flatten() {
+            self.apply_pulled_diagnostics(server_id, adapter, uri, None, report, cx);
+        }
+    }
+
+    fn apply_pulled_diagnostics(
+        &mut self,
+        server_id: LanguageServerId,
+        adapter: &CachedLspAdapter,
+        uri: lsp::Url,
+        version: Option<i32>,
+        report: lsp::DocumentDiagnosticReportKind,
+        cx: &mut ModelContext<Self>,
+    ) {
+        let Some(local) = self.as_local_mut() else {
+            return;
+        };
+        // The server may have been stopped while the request was in flight.
+        if !local.language_servers.contains_key(&server_id) {
+            return;
+        }
+        let document = local
+            .document_diagnostics
+            .entry(server_id)
+            .or_default()
+            .entry(uri.clone())
+            .or_default();
+        match report {
+            lsp::DocumentDiagnosticReportKind::Unchanged(report) => {
+                document.result_id = Some(report.result_id);
+            }
+            lsp::DocumentDiagnosticReportKind::Full(report) => {
+                document.result_id = report.result_id;
+                let mut params = lsp::PublishDiagnosticsParams {
+                    uri,
+                    diagnostics: report.items,
+                    version,
+                };
+                adapter.process_diagnostics(&mut params);
+                self.update_diagnostics_of_kind(
+                    server_id,
+                    params,
+                    DiagnosticSourceKind::Pulled,
+                    &adapter.disk_based_diagnostic_sources,
+                    cx,
+                )
+                .log_err();
+            }
+        }
+    }
+
+    /// Pulls the diagnostics of all files in the workspace from the language servers that support
+    /// <a href="https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#workspace_diagnostic">workspace/diagnostic</a>,
+    /// so that problems are reported for the files that are not open too.
+    pub fn pull_workspace_diagnostics(&mut self, cx: &mut ModelContext<Self>) -> Task<Result<()>> {
+        if let Some((client, project_id)) = self.upstream_client() {
+            let request = client.request(proto::PullWorkspaceDiagnostics { project_id });
+            return cx.background_executor().spawn(async move {
+                request.await?;
+                Ok(())
+            });
+        }
+
+        let Some(local) = self.as_local() else {
+            return Task::ready(Ok(()));
+        };
+        let server_ids = local
+            .language_servers
+            .iter()
+            .filter_map(|(server_id, state)| match state {
+                LanguageServerState::Running { server, .. } => {
+                    diagnostic_options(&server.capabilities())
+                        .filter(|options| options.workspace_diagnostics)
+                        .map(|_| *server_id)
+                }
+                LanguageServerState::Starting(_) => None,
+            })
+            .collect::<Vec<_>>();
+        let pulls = server_ids
+            .into_iter()
+            .map(|server_id| self.pull_workspace_diagnostics_from(server_id, cx))
+            .collect::<Vec<_>>();
+        cx.background_executor().spawn(async move {
+            for pull in pulls {
+                pull.await?;
+            }
+            Ok(())
+        })
+    }
+
+    fn pull_workspace_diagnostics_from(
+        &mut self,
+        server_id: LanguageServerId,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<()>> {
+        let Some(local) = self.as_local_mut() else {
+            return Task::ready(Ok(()));
+        };
+        let Some(LanguageServerState::Running {
+            adapter, server, ..
+        }) = local.language_servers.get(&server_id)
+        else {
+            return Task::ready(Ok(()));
+        };
+        let adapter = adapter.clone();
+        let Some(options) = diagnostic_options(&server.capabilities()) else {
+            return Task::ready(Ok(()));
+        };
+        let previous_result_ids = local
+            .document_diagnostics
+            .get(&server_id)
+            .into_iter()
+            .flatten()
+            .filter_map(|(uri, document)| {
+                Some(lsp::PreviousResultId {
+                    uri: uri.clone(),
+                    value: document.result_id.clone()?,
+                })
+            })
+            .collect();
+        let request = server.request::<lsp::request::WorkspaceDiagnosticRequest>(
+            lsp::WorkspaceDiagnosticParams {
+                identifier: options.identifier,
+                previous_result_ids,
+                work_done_progress_params: Default::default(),
+                partial_result_params: Default::default(),
+            },
+        );
+        local.workspace_diagnostics_servers.insert(server_id);
+
+        cx.spawn(move |this, mut cx| async move {
+            let items = match request.await.context("pulling workspace diagnostics")? {
+                lsp::WorkspaceDiagnosticReportResult::Report(report) => report.items,
+                lsp::WorkspaceDiagnosticReportResult::Partial(partial) => partial.items,
+            };
+            this.update(&mut cx, |this, cx| {
+                for item in items {
+                    let (uri, version, report) = match item {
+                        lsp::WorkspaceDocumentDiagnosticReport::Full(report) => (
+                            report.uri,
+                            report.version,
+                            lsp::DocumentDiagnosticReportKind::Full(
+                                report.full_document_diagnostic_report,
+                            ),
+                        ),
+                        lsp::WorkspaceDocumentDiagnosticReport::Unchanged(report) => (
+                            report.uri,
+                            report.version,
+                            lsp::DocumentDiagnosticReportKind::Unchanged(
+                                report.unchanged_document_diagnostic_report,
+                            ),
+                        ),
+                    };
+                    let version = version.and_then(|version| i32::try_from(version).ok());
+                    this.apply_pulled_diagnostics(server_id, &adapter, uri, version, report, cx);
+                }
+            })
+        })
+    }
+
+    /// Pulls all diagnostics of a server again, e.g. when it asked for a refresh or has just started.
+    fn refresh_pulled_diagnostics(
+        &mut self,
+        server_id: LanguageServerId,
+        cx: &mut ModelContext<Self>,
+    ) {
+        let Some(local) = self.as_local() else {
+            return;
+        };
+        let pull_workspace = local.workspace_diagnostics_servers.contains(&server_id);
+        let buffer_store = self.buffer_store.read(cx);
+        let buffers = local
+            .buffer_snapshots
+            .iter()
+            .filter(|(_, snapshots_by_server)| snapshots_by_server.contains_key(&server_id))
+            .filter_map(|(buffer_id, _)| buffer_store.get(*buffer_id))
+            .collect::<Vec<_>>();
+        for buffer in &buffers {
+            self.pull_document_diagnostics(buffer, cx);
+        }
+        if pull_workspace {
+            self.pull_workspace_diagnostics_from(server_id, cx)
+                .detach_and_log_err(cx);
+        }
+    }
+
     pub fn signature_help<T: ToPointUtf16>(
         &mut self,
         buffer: &Model<Buffer>,
@@ -4941,6 +5556,8 @@ impl LspStore {
         buffer: Model<Buffer>,
         cx: &mut ModelContext<Self>,
     ) -> Option<()> {
+        // The pull is debounced, so it is sent after the change notifications below.
+        self.pull_document_diagnostics(&buffer, cx);
         let buffer = buffer.read(cx);
         let file = File::from_dyn(buffer.file())?;
         let abs_path = file.as_local()?.abs_path(cx);
@@ -5352,6 +5969,36 @@ impl LspStore {
         Ok(())
     }
 
+    /// Replaces the diagnostics found in the output of the task with the given label,
+    /// clearing the ones from its previous run.
+    pub fn update_task_diagnostics(
+        &mut self,
+        task_label: &str,
+        diagnostics: HashMap<PathBuf, Vec<DiagnosticEntry<Unclipped<PointUtf16>>>>,
+        cx: &mut ModelContext<Self>,
+    ) -> Result<()> {
+        let local = self
+            .as_local_mut()
+            .context("task diagnostics can only be published in local projects")?;
+        let next_id = LanguageServerId(FIRST_TASK_DIAGNOSTICS_ID + local.task_diagnostics.len());
+        let (server_id, paths) = local
+            .task_diagnostics
+            .entry(task_label.to_string())
+            .or_insert_with(|| (next_id, HashSet::default()));
+        let server_id = *server_id;
+        let stale_paths = mem::replace(paths, diagnostics.keys().cloned().collect());
+
+        for abs_path in stale_paths {
+            if !diagnostics.contains_key(&abs_path) {
+                self.update_diagnostic_entries(server_id, abs_path, None, Vec::new(), cx)?;
+            }
+        }
+        for (abs_path, diagnostics) in diagnostics {
+            self.update_diagnostic_entries(server_id, abs_path, None, diagnostics, cx)?;
+        }
+        Ok(())
+    }
+
     fn update_worktree_diagnostics(
         &mut self,
         worktree_id: WorktreeId,
@@ -6595,6 +7242,65 @@ impl LspStore {
         Ok(proto::Ack {})
     }
 
+    async fn handle_refresh_semantic_tokens(
+        this: Model<Self>,
+        _: TypedEnvelope<proto::RefreshSemanticTokens>,
+        mut cx: AsyncAppContext,
+    ) -> Result<proto::Ack> {
+        this.update(&mut cx, |_, cx| {
+            cx.emit(LspStoreEvent::RefreshSemanticTokens);
+        })?;
+        Ok(proto::Ack {})
+    }
+
+    async fn handle_refresh_code_lens(
+        this: Model<Self>,
+        _: TypedEnvelope<proto::RefreshCodeLens>,
+        mut cx: AsyncAppContext,
+    ) -> Result<proto::Ack> {
+        this.update(&mut cx, |_, cx| {
+            cx.emit(LspStoreEvent::RefreshCodeLens);
+        })?;
+        Ok(proto::Ack {})
+    }
+
+    async fn handle_pull_workspace_diagnostics(
+        this: Model<Self>,
+        _: TypedEnvelope<proto::PullWorkspaceDiagnostics>,
+        mut cx: AsyncAppContext,
+    ) -> Result<proto::Ack> {
+        this.update(&mut cx, |this, cx| this.pull_workspace_diagnostics(cx))?
+            .await?;
+        Ok(proto::Ack {})
+    }
+
+    async fn handle_get_semantic_tokens(
+        this: Model<Self>,
+        envelope: TypedEnvelope<proto::GetSemanticTokens>,
+        mut cx: AsyncAppContext,
+    ) -> Result<proto::GetSemanticTokensResponse> {
+        let buffer_id = BufferId::new(envelope.payload.buffer_id)?;
+        let buffer = this.update(&mut cx, |this, cx| {
+            this.buffer_store.read(cx).get_existing(buffer_id)
+        })??;
+        buffer
+            .update(&mut cx, |buffer, _| {
+                buffer.wait_for_version(deserialize_version(&envelope.payload.version))
+            })?
+            .await
+            .with_context(|| format!("waiting for version for buffer {}", buffer.entity_id()))?;
+
+        let tokens = this
+            .update(&mut cx, |lsp_store, cx| {
+                lsp_store.semantic_tokens(buffer.clone(), cx)
+            })?
+            .await
+            .context("semantic tokens fetch")?;
+        buffer.update(&mut cx, |buffer, _| {
+            serialize_semantic_tokens(tokens, &buffer.version())
+        })
+    }
+
     async fn handle_inlay_hints(
         this: Model<Self>,
         envelope: TypedEnvelope<proto::InlayHints>,
@@ -7123,6 +7829,8 @@ impl LspStore {
             });
         }
         local.language_server_watched_paths.remove(&server_id);
+        local.document_diagnostics.remove(&server_id);
+        local.workspace_diagnostics_servers.remove(&server_id);
         let server_state = local.language_servers.remove(&server_id);
         cx.notify();
         cx.emit(LspStoreEvent::LanguageServerRemoved(server_id));
@@ -7227,15 +7935,38 @@ impl LspStore {
     }
 
     pub fn update_diagnostics(
+        &mut self,
+        language_server_id: LanguageServerId,
+        params: lsp::PublishDiagnosticsParams,
+        disk_based_sources: &[String],
+        cx: &mut ModelContext<Self>,
+    ) -> Result<()> {
+        self.update_diagnostics_of_kind(
+            language_server_id,
+            params,
+            DiagnosticSourceKind::Pushed,
+            disk_based_sources,
+            cx,
+        )
+    }
+
+    fn update_diagnostics_of_kind(
         &mut self,
         language_server_id: LanguageServerId,
         mut params: lsp::PublishDiagnosticsParams,
+        kind: DiagnosticSourceKind,
         disk_based_sources: &[String],
         cx: &mut ModelContext<Self>,
     ) -> Result<()> {
-        if !self.mode.is_local() {
+        let Some(local) = self.as_local_mut() else {
             anyhow::bail!("called update_diagnostics on remote");
-        }
+        };
+        params.diagnostics = local.merge_document_diagnostics(
+            language_server_id,
+            &params.uri,
+            kind,
+            mem::take(&mut params.diagnostics),
+        );
         let abs_path = params
             .uri
             .to_file_path()
@@ -7513,6 +8244,7 @@ impl LspStore {
                 });
             }
         });
+        self.refresh_pulled_diagnostics(server_id, cx);
 
         cx.notify();
     }
@@ -7872,6 +8604,154 @@ impl LspStore {
 
 impl EventEmitter<LspStoreEvent> for LspStore {}
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DiagnosticSourceKind {
+    /// Published by the server with `textDocument/publishDiagnostics`.
+    Pushed,
+    /// Requested from the server with `textDocument/diagnostic` or `workspace/diagnostic`.
+    Pulled,
+}
+
+/// The diagnostics a server that supports pull diagnostics reported for a document.
+#[derive(Default)]
+struct DocumentDiagnostics {
+    pushed: Vec<lsp::Diagnostic>,
+    pulled: Vec<lsp::Diagnostic>,
+    /// The id of the last pulled report, sent with the next pull so that the server can
+    /// answer that nothing changed.
+    result_id: Option<String>,
+}
+
+struct DocumentDiagnosticsRequest {
+    server_id: LanguageServerId,
+    adapter: Arc<CachedLspAdapter>,
+    uri: lsp::Url,
+    version: i32,
+    response: futures::future::LocalBoxFuture<'static, Result<lsp::DocumentDiagnosticReportResult>>,
+}
+
+fn diagnostic_options(capabilities: &lsp::ServerCapabilities) -> Option<lsp::DiagnosticOptions> {
+    match capabilities.diagnostic_provider.as_ref()? {
+        lsp::DiagnosticServerCapabilities::Options(options) => Some(options.clone()),
+        lsp::DiagnosticServerCapabilities::RegistrationOptions(options) => {
+            Some(options.diagnostic_options.clone())
+        }
+    }
+}
+
+/// The last semantic tokens a language server reported for a buffer, kept so that
+/// the next request can ask the server for a delta only.
+struct BufferSemanticTokens {
+    server_id: LanguageServerId,
+    result_id: Option<String>,
+    data: Vec<lsp::SemanticToken>,
+}
+
+fn semantic_tokens_options(
+    capabilities: &lsp::ServerCapabilities,
+) -> Option<lsp::SemanticTokensOptions> {
+    match capabilities.semantic_tokens_provider.as_ref()? {
+        lsp::SemanticTokensServerCapabilities::SemanticTokensOptions(options) => {
+            Some(options.clone())
+        }
+        lsp::SemanticTokensServerCapabilities::SemanticTokensRegistrationOptions(options) => {
+            Some(options.semantic_tokens_options.clone())
+        }
+    }
+}
+
+fn apply_semantic_token_edits(
+    data: &mut Vec<lsp::SemanticToken>,
+    mut edits: Vec<lsp::SemanticTokensEdit>,
+) {
+    // Edit offsets refer to the flat integer array of the previous result, five integers per token.
+    // Applying the edits back to front keeps the offsets of the remaining ones valid.
+    edits.sort_by_key(|edit| std::cmp::Reverse(edit.start));
+    for edit in edits {
+        let start = (edit.start as usize / 5).min(data.len());
+        let end = (start + edit.delete_count as usize / 5).min(data.len());
+        data.splice(start..end, edit.data.unwrap_or_default());
+    }
+}
+
+fn semantic_tokens_from_lsp(
+    data: &[lsp::SemanticToken],
+    snapshot: &TextBufferSnapshot,
+) -> Vec<SemanticToken> {
+    let mut row = 0;
+    let mut column = 0;
+    data.iter()
+        .map(|token| {
+            if token.delta_line > 0 {
+                row += token.delta_line;
+                column = 0;
+            }
+            column += token.delta_start;
+            let start =
+                snapshot.clip_point_utf16(Unclipped(PointUtf16::new(row, column)), Bias::Left);
+            let end = snapshot.clip_point_utf16(
+                Unclipped(PointUtf16::new(row, column + token.length)),
+                Bias::Left,
+            );
+            SemanticToken {
+                range: snapshot.anchor_after(start)..snapshot.anchor_before(end),
+                token_type: token.token_type,
+                token_modifiers: token.token_modifiers_bitset,
+            }
+        })
+        .collect()
+}
+
+fn serialize_semantic_tokens(
+    tokens: SemanticTokens,
+    version: &clock::Global,
+) -> proto::GetSemanticTokensResponse {
+    proto::GetSemanticTokensResponse {
+        token_types: tokens.token_types.to_vec(),
+        token_modifiers: tokens.token_modifiers.to_vec(),
+        tokens: tokens
+            .tokens
+            .into_iter()
+            .map(|token| proto::SemanticToken {
+                start: Some(serialize_anchor(&token.range.start)),
+                end: Some(serialize_anchor(&token.range.end)),
+                token_type: token.token_type,
+                token_modifiers: token.token_modifiers,
+            })
+            .collect(),
+        version: serialize_version(version),
+    }
+}
+
+fn deserialize_semantic_tokens(
+    response: proto::GetSemanticTokensResponse,
+) -> Result<SemanticTokens> {
+    let tokens = response
+        .tokens
+        .into_iter()
+        .map(|token| {
+            let start = token
+                .start
+                .and_then(deserialize_anchor)
+                .context("missing semantic token start")?;
+            let end = token
+                .end
+                .and_then(deserialize_anchor)
+                .context("missing semantic token end")?;
+            Ok(SemanticToken {
+                range: start..end,
+                token_type: token.token_type,
+                token_modifiers: token.token_modifiers,
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(SemanticTokens {
+        token_types: response.token_types.into(),
+        token_modifiers: response.token_modifiers.into(),
+        tokens,
+    })
+}
+
 fn remove_empty_hover_blocks(mut hover: Hover) -> Option<Hover> {
     hover
         .contents
@@ -39,7 +39,13 @@ use futures::{
 pub use image_store::{ImageItem, ImageStore};
 use image_store::{ImageItemEvent, ImageStoreEvent};
 
-use git::{blame::Blame, repository::GitRepository};
+use git::{
+    blame::Blame,
+    commit::{CommitFileDiff, CommitSummary},
+    conflict::ConflictStages,
+    repository::{AskPassRequest, DiffBase, GitRepository, RemoteOperation},
+    status::StageStatus,
+};
 use gpui::{
     AnyModel, AppContext, AsyncAppContext, BorrowAppContext, Context as _, EventEmitter, Hsla,
     Model, ModelContext, SharedString, Task, WeakModel, WindowContext,
@@ -85,6 +91,7 @@ use text::{Anchor, BufferId};
 use toolchain_store::EmptyToolchainStore;
 use util::{paths::compare_paths, ResultExt as _};
 use worktree::{CreatedEntry, Snapshot, Traversal};
+pub use worktree_store::GitRemoteOperationStatus;
 use worktree_store::{WorktreeStore, WorktreeStoreEvent};
 
 pub use fs::*;
@@ -245,6 +252,12 @@ pub enum Event {
     WorktreeRemoved(WorktreeId),
     WorktreeUpdatedEntries(WorktreeId, UpdatedEntriesSet),
     WorktreeUpdatedGitRepositories(WorktreeId),
+    /// The diff base changed, and the diffs of the open buffers were recalculated against it.
+    DiffBaseChanged,
+    /// A fetch, pull or push started, progressed or finished.
+    GitRemoteOperationsChanged,
+    /// A fetch, pull or push needs a username, password or passphrase.
+    GitAskPass(AskPassRequest),
     DiskBasedDiagnosticsStarted {
         language_server_id: LanguageServerId,
     },
@@ -270,6 +283,8 @@ pub enum Event {
     Reshared,
     Rejoined,
     RefreshInlayHints,
+    RefreshSemanticTokens,
+    RefreshCodeLens,
     RevealInProjectPanel(ProjectEntryId),
     SnippetEdit(BufferId, Vec<(lsp::Range, Snippet)>),
 }
@@ -394,6 +409,27 @@ pub struct CodeAction {
     pub lsp_action: lsp::CodeAction,
 }
 
+/// A code lens provided by a language server, e.g. a reference count or a "Run" command shown above an item.
+#[derive(Clone, Debug)]
+pub struct CodeLens {
+    /// The id of the language server that produced this code lens.
+    pub server_id: LanguageServerId,
+    /// The range of the buffer the code lens is shown for.
+    pub range: Range<Anchor>,
+    /// The raw code lens provided by the language server.
+    pub lsp_lens: lsp::CodeLens,
+}
+
+impl CodeLens {
+    pub fn title(&self) -> Option<&str> {
+        self.lsp_lens
+            .command
+            .as_ref()
+            .map(|command| command.title.as_str())
+            .filter(|title| !title.is_empty())
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ResolveState {
     Resolved,
@@ -447,6 +483,67 @@ pub struct LocationLink {
     pub target: Location,
 }
 
+/// A symbol that can be the subject of a call hierarchy query.
+#[derive(Debug, Clone)]
+pub struct CallHierarchyItem {
+    pub name: String,
+    pub kind: lsp::SymbolKind,
+    pub detail: Option<String>,
+    pub buffer: Model<Buffer>,
+    /// The full extent of the symbol, e.g. a function including its body.
+    pub range: Range<language::Anchor>,
+    /// The part of the symbol to reveal when navigating to it, e.g. a function's name.
+    pub selection_range: Range<language::Anchor>,
+    /// Opaque data attached by the language server, passed back when resolving calls.
+    pub data: Option<serde_json::Value>,
+}
+
+/// A caller (for incoming calls) or a callee (for outgoing calls) of a [`CallHierarchyItem`].
+#[derive(Debug, Clone)]
+pub struct CallHierarchyCall {
+    pub item: CallHierarchyItem,
+    /// The locations of the calls. These are in the caller's buffer: the item's own buffer
+    /// for incoming calls, the buffer of the queried item for outgoing calls.
+    pub call_sites: Vec<Location>,
+}
+
+/// Semantic tokens of a buffer, as reported by a language server.
+#[derive(Debug, Clone, Default)]
+pub struct SemanticTokens {
+    /// The legend of the language server, indexed by [`SemanticToken::token_type`].
+    pub token_types: Arc<[String]>,
+    /// The legend of the language server, indexed by bits of [`SemanticToken::token_modifiers`].
+    pub token_modifiers: Arc<[String]>,
+    /// Tokens, sorted by their position in the buffer.
+    pub tokens: Vec<SemanticToken>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SemanticToken {
+    pub range: Range<language::Anchor>,
+    pub token_type: u32,
+    pub token_modifiers: u32,
+}
+
+impl SemanticTokens {
+    pub fn token_type(&self, token: &SemanticToken) -> Option<&str> {
+        self.token_types
+            .get(token.token_type as usize)
+            .map(String::as_str)
+    }
+
+    pub fn token_modifiers<'a>(
+        &'a self,
+        token: &'a SemanticToken,
+    ) -> impl Iterator<Item = &'a str> + 'a {
+        self.token_modifiers
+            .iter()
+            .enumerate()
+            .filter(|(ix, _)| *ix < 32 && token.token_modifiers & (1 << ix) != 0)
+            .map(|(_, modifier)| modifier.as_str())
+    }
+}
+
 #[derive(Debug)]
 pub struct DocumentHighlight {
     pub range: Range<language::Anchor>,
@@ -861,6 +958,9 @@ impl Project {
             ssh_proto.add_model_request_handler(Self::handle_language_server_prompt_request);
             ssh_proto.add_model_message_handler(Self::handle_hide_toast);
             ssh_proto.add_model_request_handler(Self::handle_update_buffer_from_ssh);
+            ssh_proto.add_model_request_handler(WorktreeStore::handle_git_ask_pass);
+            ssh_proto
+                .add_model_message_handler(WorktreeStore::handle_git_remote_operation_progress);
             BufferStore::init(&ssh_proto);
             LspStore::init(&ssh_proto);
             SettingsObserver::init(&ssh_proto);
@@ -2185,6 +2285,8 @@ impl Project {
                 };
             }
             LspStoreEvent::RefreshInlayHints => cx.emit(Event::RefreshInlayHints),
+            LspStoreEvent::RefreshSemanticTokens => cx.emit(Event::RefreshSemanticTokens),
+            LspStoreEvent::RefreshCodeLens => cx.emit(Event::RefreshCodeLens),
             LspStoreEvent::LanguageServerPrompt(prompt) => {
                 cx.emit(Event::LanguageServerPrompt(prompt.clone()))
             }
@@ -2295,6 +2397,13 @@ impl Project {
             }
             WorktreeStoreEvent::WorktreeOrderChanged => cx.emit(Event::WorktreeOrderChanged),
             WorktreeStoreEvent::WorktreeUpdateSent(_) => {}
+            WorktreeStoreEvent::GitRemoteOperationsChanged => {
+                cx.emit(Event::GitRemoteOperationsChanged);
+                cx.notify();
+            }
+            WorktreeStoreEvent::GitAskPass(request) => {
+                cx.emit(Event::GitAskPass(request.clone()));
+            }
         }
     }
 
@@ -2718,6 +2827,51 @@ impl Project {
         )
     }
 
+    /// Resolves the call hierarchy items at the given position, which can then be used to
+    /// query their [incoming](Self::incoming_calls) and [outgoing](Self::outgoing_calls) calls.
+    pub fn prepare_call_hierarchy<T: ToPointUtf16>(
+        &mut self,
+        buffer: &Model<Buffer>,
+        position: T,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<Vec<CallHierarchyItem>>> {
+        let position = position.to_point_utf16(buffer.read(cx));
+        self.request_lsp(
+            buffer.clone(),
+            LanguageServerToQuery::Primary,
+            PrepareCallHierarchy { position },
+            cx,
+        )
+    }
+
+    /// Returns the callers of the given call hierarchy item.
+    pub fn incoming_calls(
+        &mut self,
+        item: CallHierarchyItem,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<Vec<CallHierarchyCall>>> {
+        self.request_lsp(
+            item.buffer.clone(),
+            LanguageServerToQuery::Primary,
+            GetIncomingCalls { item },
+            cx,
+        )
+    }
+
+    /// Returns the callees of the given call hierarchy item.
+    pub fn outgoing_calls(
+        &mut self,
+        item: CallHierarchyItem,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<Vec<CallHierarchyCall>>> {
+        self.request_lsp(
+            item.buffer.clone(),
+            LanguageServerToQuery::Primary,
+            GetOutgoingCalls { item },
+            cx,
+        )
+    }
+
     pub fn references<T: ToPointUtf16>(
         &mut self,
         buffer: &Model<Buffer>,
@@ -2878,6 +3032,19 @@ impl Project {
         })
     }
 
+    pub fn code_lens(
+        &mut self,
+        buffer_handle: &Model<Buffer>,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<Vec<CodeLens>>> {
+        self.request_lsp(
+            buffer_handle.clone(),
+            LanguageServerToQuery::Primary,
+            GetCodeLens,
+            cx,
+        )
+    }
+
     pub fn apply_code_action(
         &self,
         buffer_handle: Model<Buffer>,
@@ -2971,6 +3138,16 @@ impl Project {
         })
     }
 
+    pub fn semantic_tokens(
+        &mut self,
+        buffer_handle: Model<Buffer>,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<anyhow::Result<SemanticTokens>> {
+        self.lsp_store.update(cx, |lsp_store, cx| {
+            lsp_store.semantic_tokens(buffer_handle, cx)
+        })
+    }
+
     pub fn resolve_inlay_hint(
         &self,
         hint: InlayHint,
@@ -3437,6 +3614,12 @@ impl Project {
             .diagnostic_summaries(include_ignored, cx)
     }
 
+    /// Asks the language servers that support pull diagnostics for the diagnostics of the whole workspace.
+    pub fn pull_workspace_diagnostics(&mut self, cx: &mut ModelContext<Self>) -> Task<Result<()>> {
+        self.lsp_store
+            .update(cx, |lsp_store, cx| lsp_store.pull_workspace_diagnostics(cx))
+    }
+
     pub fn active_entry(&self) -> Option<ProjectEntryId> {
         self.active_entry
     }
@@ -3552,6 +3735,168 @@ impl Project {
             .update_or_create_branch(repository, new_branch, cx)
     }
 
+    pub fn diff_base<'a>(&self, cx: &'a AppContext) -> &'a DiffBase {
+        self.buffer_store.read(cx).diff_base()
+    }
+
+    /// Changes what buffer diffs compare the working copy against.
+    /// Only local projects support diff bases other than the index.
+    pub fn set_diff_base(&mut self, diff_base: DiffBase, cx: &mut ModelContext<Self>) {
+        if !self.is_local() {
+            return;
+        }
+        let reload = self.buffer_store.update(cx, |buffer_store, cx| {
+            buffer_store.set_diff_base(diff_base, cx)
+        });
+        cx.spawn(|this, mut cx| async move {
+            reload.await;
+            this.update(&mut cx, |_, cx| cx.emit(Event::DiffBaseChanged))
+        })
+        .detach_and_log_err(cx);
+    }
+
+    /// Returns the paths of a worktree whose contents differ from the diff base.
+    pub fn git_changed_paths(
+        &self,
+        worktree_id: WorktreeId,
+        cx: &AppContext,
+    ) -> Task<Result<Vec<Arc<Path>>>> {
+        let diff_base = self.diff_base(cx).clone();
+        self.worktree_store()
+            .read(cx)
+            .git_changed_paths(worktree_id, diff_base, cx)
+    }
+
+    /// Fetches, pulls or pushes the branch checked out in the repository at the root of a worktree.
+    /// Credential prompts are emitted as [`Event::GitAskPass`] events.
+    pub fn git_remote_operation(
+        &mut self,
+        worktree_id: WorktreeId,
+        operation: RemoteOperation,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<()>> {
+        self.worktree_store.update(cx, |worktree_store, cx| {
+            worktree_store.git_remote_operation(worktree_id, operation, cx)
+        })
+    }
+
+    /// The fetches, pulls and pushes in progress, oldest first.
+    pub fn git_remote_operations<'a>(
+        &'a self,
+        cx: &'a AppContext,
+    ) -> impl Iterator<Item = &'a GitRemoteOperationStatus> {
+        self.worktree_store.read(cx).git_remote_operations()
+    }
+
+    pub fn git_stage_statuses(
+        &self,
+        worktree_id: WorktreeId,
+        paths: Vec<Arc<Path>>,
+        cx: &AppContext,
+    ) -> Task<Result<Vec<(Arc<Path>, StageStatus)>>> {
+        self.worktree_store()
+            .read(cx)
+            .git_stage_statuses(worktree_id, paths, cx)
+    }
+
+    pub fn stage_paths(
+        &self,
+        worktree_id: WorktreeId,
+        paths: Vec<Arc<Path>>,
+        cx: &AppContext,
+    ) -> Task<Result<()>> {
+        self.worktree_store()
+            .read(cx)
+            .stage_paths(worktree_id, paths, cx)
+    }
+
+    pub fn unstage_paths(
+        &self,
+        worktree_id: WorktreeId,
+        paths: Vec<Arc<Path>>,
+        cx: &AppContext,
+    ) -> Task<Result<()>> {
+        self.worktree_store()
+            .read(cx)
+            .unstage_paths(worktree_id, paths, cx)
+    }
+
+    pub fn discard_paths(
+        &self,
+        worktree_id: WorktreeId,
+        paths: Vec<Arc<Path>>,
+        cx: &AppContext,
+    ) -> Task<Result<()>> {
+        self.worktree_store()
+            .read(cx)
+            .discard_paths(worktree_id, paths, cx)
+    }
+
+    pub fn set_index_text(
+        &self,
+        project_path: ProjectPath,
+        text: Option<String>,
+        cx: &AppContext,
+    ) -> Task<Result<()>> {
+        self.worktree_store()
+            .read(cx)
+            .set_index_text(project_path, text, cx)
+    }
+
+    pub fn unstage_hunks(
+        &self,
+        project_path: ProjectPath,
+        head_ranges: Vec<Range<usize>>,
+        cx: &AppContext,
+    ) -> Task<Result<()>> {
+        self.worktree_store()
+            .read(cx)
+            .unstage_hunks(project_path, head_ranges, cx)
+    }
+
+    pub fn commit(
+        &self,
+        changes: Vec<(WorktreeId, Vec<Arc<Path>>)>,
+        message: String,
+        cx: &AppContext,
+    ) -> Task<Result<()>> {
+        self.worktree_store().read(cx).commit(changes, message, cx)
+    }
+
+    pub fn git_log(
+        &self,
+        project_path: ProjectPath,
+        filter_by_path: bool,
+        skip: usize,
+        limit: usize,
+        cx: &AppContext,
+    ) -> Task<Result<Vec<CommitSummary>>> {
+        self.worktree_store()
+            .read(cx)
+            .git_log(project_path, filter_by_path, skip, limit, cx)
+    }
+
+    pub fn git_commit_diff(
+        &self,
+        project_path: ProjectPath,
+        sha: String,
+        cx: &AppContext,
+    ) -> Task<Result<Vec<CommitFileDiff>>> {
+        self.worktree_store()
+            .read(cx)
+            .git_commit_diff(project_path, sha, cx)
+    }
+
+    pub fn git_conflict_stages(
+        &self,
+        project_path: ProjectPath,
+        cx: &AppContext,
+    ) -> Task<Result<ConflictStages>> {
+        self.worktree_store()
+            .read(cx)
+            .git_conflict_stages(project_path, cx)
+    }
+
     pub fn blame_buffer(
         &self,
         buffer: &Model<Buffer>,
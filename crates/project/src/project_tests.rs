@@ -1,7 +1,11 @@
 use crate::{Event, *};
 use fs::FakeFs;
 use futures::{future, StreamExt};
-use git::diff::assert_hunks;
+use git::{
+    commit::{CommitFileDiff, CommitSummary},
+    diff::assert_hunks,
+    status::StageStatus,
+};
 use gpui::{AppContext, SemanticVersion, UpdateGlobal};
 use http_client::Url;
 use language::{
@@ -2150,6 +2154,101 @@ async fn test_diagnostics_from_multiple_language_servers(cx: &mut gpui::TestAppC
     });
 }
 
+#[gpui::test]
+async fn test_task_diagnostics_cleared_on_rerun(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree("/dir", json!({ "a.rs": "one two three", "b.rs": "four" }))
+        .await;
+
+    let project = Project::test(fs, ["/dir".as_ref()], cx).await;
+    let lsp_store = project.read_with(cx, |project, _| project.lsp_store.clone());
+
+    let error = |message: &str| DiagnosticEntry {
+        range: Unclipped(PointUtf16::new(0, 0))..Unclipped(PointUtf16::new(0, 3)),
+        diagnostic: Diagnostic {
+            severity: DiagnosticSeverity::ERROR,
+            is_primary: true,
+            message: message.to_string(),
+            ..Default::default()
+        },
+    };
+    let summaries = |lsp_store: &LspStore, cx: &AppContext| {
+        lsp_store
+            .diagnostic_summaries(false, cx)
+            .map(|(path, server_id, summary)| (path.path, server_id, summary.error_count))
+            .collect::<Vec<_>>()
+    };
+
+    lsp_store.update(cx, |lsp_store, cx| {
+        lsp_store
+            .update_task_diagnostics(
+                "build",
+                HashMap::from_iter([
+                    (PathBuf::from("/dir/a.rs"), vec![error("a1"), error("a2")]),
+                    (PathBuf::from("/dir/b.rs"), vec![error("b1")]),
+                ]),
+                cx,
+            )
+            .unwrap();
+        lsp_store
+            .update_task_diagnostics(
+                "lint",
+                HashMap::from_iter([(PathBuf::from("/dir/b.rs"), vec![error("b2")])]),
+                cx,
+            )
+            .unwrap();
+    });
+
+    let (build_id, lint_id) = lsp_store.read_with(cx, |lsp_store, cx| {
+        let mut summaries = summaries(lsp_store, cx);
+        summaries.sort();
+        let [(_, build_id, 2), (_, _, 1), (_, lint_id, 1)] = summaries[..] else {
+            panic!("unexpected summaries {summaries:?}");
+        };
+        // No language server is listed for the tasks.
+        assert_eq!(lsp_store.language_server_statuses().count(), 0);
+        (build_id, lint_id)
+    });
+    assert_ne!(build_id, lint_id);
+
+    // Running the task again replaces its diagnostics, leaving the other task's alone.
+    lsp_store.update(cx, |lsp_store, cx| {
+        lsp_store
+            .update_task_diagnostics(
+                "build",
+                HashMap::from_iter([(PathBuf::from("/dir/a.rs"), vec![error("a3")])]),
+                cx,
+            )
+            .unwrap();
+    });
+    lsp_store.read_with(cx, |lsp_store, cx| {
+        let mut summaries = summaries(lsp_store, cx);
+        summaries.sort();
+        assert_eq!(
+            summaries,
+            [
+                (Arc::from(Path::new("a.rs")), build_id, 1),
+                (Arc::from(Path::new("b.rs")), lint_id, 1),
+            ]
+        );
+    });
+
+    // A run without problems clears them.
+    lsp_store.update(cx, |lsp_store, cx| {
+        lsp_store
+            .update_task_diagnostics("build", HashMap::default(), cx)
+            .unwrap();
+        lsp_store
+            .update_task_diagnostics("lint", HashMap::default(), cx)
+            .unwrap();
+    });
+    lsp_store.read_with(cx, |lsp_store, cx| {
+        assert!(summaries(lsp_store, cx).is_empty());
+    });
+}
+
 #[gpui::test]
 async fn test_edits_from_lsp2_with_past_version(cx: &mut gpui::TestAppContext) {
     init_test(cx);
@@ -2632,6 +2731,565 @@ async fn test_definition(cx: &mut gpui::TestAppContext) {
     }
 }
 
+#[gpui::test]
+async fn test_call_hierarchy(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(
+        "/dir",
+        json!({
+            "a.rs": "fn a() { b() }\nfn b() {}",
+            "c.rs": "fn c() { crate::b(); crate::b() }",
+        }),
+    )
+    .await;
+
+    let project = Project::test(fs, ["/dir".as_ref()], cx).await;
+
+    let language_registry = project.read_with(cx, |project, _| project.languages().clone());
+    language_registry.add(rust_lang());
+    let mut fake_servers = language_registry.register_fake_lsp(
+        "Rust",
+        FakeLspAdapter {
+            capabilities: lsp::ServerCapabilities {
+                call_hierarchy_provider: Some(lsp::CallHierarchyServerCapability::Simple(true)),
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+    );
+
+    let (buffer, _handle) = project
+        .update(cx, |project, cx| {
+            project.open_local_buffer_with_lsp("/dir/a.rs", cx)
+        })
+        .await
+        .unwrap();
+
+    let fake_server = fake_servers.next().await.unwrap();
+    fn lsp_item(
+        name: &str,
+        path: &str,
+        line: u32,
+        data: serde_json::Value,
+    ) -> lsp::CallHierarchyItem {
+        lsp::CallHierarchyItem {
+            name: name.to_string(),
+            kind: lsp::SymbolKind::FUNCTION,
+            tags: None,
+            detail: None,
+            uri: lsp::Url::from_file_path(path).unwrap(),
+            range: lsp::Range::new(lsp::Position::new(line, 0), lsp::Position::new(line, 9)),
+            selection_range: lsp::Range::new(
+                lsp::Position::new(line, 3),
+                lsp::Position::new(line, 4),
+            ),
+            data: Some(data),
+        }
+    }
+
+    fake_server.handle_request::<lsp::request::CallHierarchyPrepare, _, _>(
+        |params, _| async move {
+            let params = params.text_document_position_params;
+            assert_eq!(
+                params.text_document.uri.to_file_path().unwrap(),
+                Path::new("/dir/a.rs"),
+            );
+            assert_eq!(params.position, lsp::Position::new(1, 3));
+            Ok(Some(vec![lsp_item(
+                "b",
+                "/dir/a.rs",
+                1,
+                json!({ "id": "b" }),
+            )]))
+        },
+    );
+    fake_server.handle_request::<lsp::request::CallHierarchyIncomingCalls, _, _>(
+        |params, _| async move {
+            assert_eq!(params.item.name, "b");
+            assert_eq!(params.item.data, Some(json!({ "id": "b" })));
+            Ok(Some(vec![
+                lsp::CallHierarchyIncomingCall {
+                    from: lsp_item("a", "/dir/a.rs", 0, json!({ "id": "a" })),
+                    from_ranges: vec![lsp::Range::new(
+                        lsp::Position::new(0, 9),
+                        lsp::Position::new(0, 10),
+                    )],
+                },
+                lsp::CallHierarchyIncomingCall {
+                    from: lsp_item("c", "/dir/c.rs", 0, json!({ "id": "c" })),
+                    from_ranges: vec![
+                        lsp::Range::new(lsp::Position::new(0, 16), lsp::Position::new(0, 17)),
+                        lsp::Range::new(lsp::Position::new(0, 28), lsp::Position::new(0, 29)),
+                    ],
+                },
+            ]))
+        },
+    );
+    fake_server.handle_request::<lsp::request::CallHierarchyOutgoingCalls, _, _>(
+        |params, _| async move {
+            assert_eq!(params.item.name, "a");
+            assert_eq!(params.item.data, Some(json!({ "id": "a" })));
+            Ok(Some(vec![lsp::CallHierarchyOutgoingCall {
+                to: lsp_item("b", "/dir/a.rs", 1, json!({ "id": "b" })),
+                from_ranges: vec![lsp::Range::new(
+                    lsp::Position::new(0, 9),
+                    lsp::Position::new(0, 10),
+                )],
+            }]))
+        },
+    );
+
+    let items = project
+        .update(cx, |project, cx| {
+            project.prepare_call_hierarchy(&buffer, Point::new(1, 3), cx)
+        })
+        .await
+        .unwrap();
+    assert_eq!(items.len(), 1);
+    let root = items[0].clone();
+    assert_eq!(root.name, "b");
+    assert_eq!(root.buffer, buffer);
+
+    let incoming_calls = project
+        .update(cx, |project, cx| project.incoming_calls(root, cx))
+        .await
+        .unwrap();
+    cx.update(|cx| {
+        let calls = incoming_calls
+            .iter()
+            .map(|call| {
+                let item_buffer = call.item.buffer.read(cx);
+                let call_sites = call
+                    .call_sites
+                    .iter()
+                    .map(|call_site| {
+                        let buffer = call_site.buffer.read(cx);
+                        buffer
+                            .text_for_range(call_site.range.clone())
+                            .collect::<String>()
+                    })
+                    .collect::<Vec<_>>();
+                (
+                    call.item.name.clone(),
+                    item_buffer.file().unwrap().path().to_path_buf(),
+                    call_sites,
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            calls,
+            [
+                (
+                    "a".to_string(),
+                    PathBuf::from("a.rs"),
+                    vec!["b".to_string()]
+                ),
+                (
+                    "c".to_string(),
+                    PathBuf::from("c.rs"),
+                    vec!["b".to_string(), "b".to_string()]
+                ),
+            ]
+        );
+    });
+
+    let caller = incoming_calls[0].item.clone();
+    let outgoing_calls = project
+        .update(cx, |project, cx| project.outgoing_calls(caller, cx))
+        .await
+        .unwrap();
+    assert_eq!(outgoing_calls.len(), 1);
+    cx.update(|cx| {
+        let call = &outgoing_calls[0];
+        assert_eq!(call.item.name, "b");
+        assert_eq!(call.item.buffer, buffer);
+        let buffer = buffer.read(cx);
+        assert_eq!(
+            call.item.selection_range.to_point(buffer),
+            Point::new(1, 3)..Point::new(1, 4)
+        );
+        assert_eq!(call.call_sites.len(), 1);
+        assert_eq!(call.call_sites[0].range.to_offset(buffer), 9..10);
+    });
+}
+
+#[gpui::test]
+async fn test_semantic_tokens(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(
+        "/dir",
+        json!({
+            "a.rs": "fn main() {\n    let mut x = 1;\n    x += 1;\n}",
+        }),
+    )
+    .await;
+
+    let project = Project::test(fs, ["/dir".as_ref()], cx).await;
+
+    let language_registry = project.read_with(cx, |project, _| project.languages().clone());
+    language_registry.add(rust_lang());
+    let mut fake_servers = language_registry.register_fake_lsp(
+        "Rust",
+        FakeLspAdapter {
+            capabilities: lsp::ServerCapabilities {
+                semantic_tokens_provider: Some(
+                    lsp::SemanticTokensServerCapabilities::SemanticTokensOptions(
+                        lsp::SemanticTokensOptions {
+                            legend: lsp::SemanticTokensLegend {
+                                token_types: vec![
+                                    lsp::SemanticTokenType::VARIABLE,
+                                    lsp::SemanticTokenType::FUNCTION,
+                                ],
+                                token_modifiers: vec![lsp::SemanticTokenModifier::new("mutable")],
+                            },
+                            full: Some(lsp::SemanticTokensFullOptions::Delta { delta: Some(true) }),
+                            ..Default::default()
+                        },
+                    ),
+                ),
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+    );
+
+    let (buffer, _handle) = project
+        .update(cx, |project, cx| {
+            project.open_local_buffer_with_lsp("/dir/a.rs", cx)
+        })
+        .await
+        .unwrap();
+
+    let fake_server = fake_servers.next().await.unwrap();
+    let token =
+        |delta_line, delta_start, length, token_type, token_modifiers_bitset| lsp::SemanticToken {
+            delta_line,
+            delta_start,
+            length,
+            token_type,
+            token_modifiers_bitset,
+        };
+
+    fake_server.handle_request::<lsp::request::SemanticTokensFullRequest, _, _>(
+        move |params, _| async move {
+            assert_eq!(
+                params.text_document.uri,
+                lsp::Url::from_file_path("/dir/a.rs").unwrap()
+            );
+            Ok(Some(lsp::SemanticTokensResult::Tokens(
+                lsp::SemanticTokens {
+                    result_id: Some("1".to_string()),
+                    data: vec![
+                        token(0, 3, 4, 1, 0),
+                        token(1, 12, 1, 0, 1),
+                        token(1, 4, 1, 0, 1),
+                    ],
+                },
+            )))
+        },
+    );
+    let describe = |tokens: &SemanticTokens, cx: &mut gpui::TestAppContext| {
+        buffer.read_with(cx, |buffer, _| {
+            tokens
+                .tokens
+                .iter()
+                .map(|token| {
+                    (
+                        buffer
+                            .text_for_range(token.range.clone())
+                            .collect::<String>(),
+                        tokens.token_type(token).unwrap().to_string(),
+                        tokens.token_modifiers(token).collect::<Vec<_>>().join(","),
+                    )
+                })
+                .collect::<Vec<_>>()
+        })
+    };
+
+    let tokens = project
+        .update(cx, |project, cx| {
+            project.semantic_tokens(buffer.clone(), cx)
+        })
+        .await
+        .unwrap();
+    assert_eq!(
+        describe(&tokens, cx),
+        vec![
+            ("main".to_string(), "function".to_string(), String::new()),
+            (
+                "x".to_string(),
+                "variable".to_string(),
+                "mutable".to_string()
+            ),
+            (
+                "x".to_string(),
+                "variable".to_string(),
+                "mutable".to_string()
+            ),
+        ]
+    );
+    buffer.read_with(cx, |buffer, _| {
+        assert_eq!(
+            tokens
+                .tokens
+                .iter()
+                .map(|token| token.range.to_point(buffer))
+                .collect::<Vec<_>>(),
+            vec![
+                Point::new(0, 3)..Point::new(0, 7),
+                Point::new(1, 12)..Point::new(1, 13),
+                Point::new(2, 4)..Point::new(2, 5),
+            ]
+        );
+    });
+
+    // Once the server returned a result id, only the changes are requested.
+    fake_server.handle_request::<lsp::request::SemanticTokensFullDeltaRequest, _, _>(
+        move |params, _| async move {
+            assert_eq!(params.previous_result_id, "1");
+            Ok(Some(lsp::SemanticTokensFullDeltaResult::TokensDelta(
+                lsp::SemanticTokensDelta {
+                    result_id: Some("2".to_string()),
+                    edits: vec![lsp::SemanticTokensEdit {
+                        start: 10,
+                        delete_count: 5,
+                        data: Some(vec![token(1, 4, 1, 0, 0)]),
+                    }],
+                },
+            )))
+        },
+    );
+    let tokens = project
+        .update(cx, |project, cx| {
+            project.semantic_tokens(buffer.clone(), cx)
+        })
+        .await
+        .unwrap();
+    assert_eq!(
+        describe(&tokens, cx),
+        vec![
+            ("main".to_string(), "function".to_string(), String::new()),
+            (
+                "x".to_string(),
+                "variable".to_string(),
+                "mutable".to_string()
+            ),
+            ("x".to_string(), "variable".to_string(), String::new()),
+        ]
+    );
+}
+
+#[gpui::test]
+async fn test_pull_diagnostics(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(
+        "/dir",
+        json!({
+            "a.rs": "let one = 1;\nlet two = 2;\n",
+        }),
+    )
+    .await;
+
+    let project = Project::test(fs, ["/dir".as_ref()], cx).await;
+
+    let language_registry = project.read_with(cx, |project, _| project.languages().clone());
+    language_registry.add(rust_lang());
+    let mut fake_servers = language_registry.register_fake_lsp(
+        "Rust",
+        FakeLspAdapter {
+            capabilities: lsp::ServerCapabilities {
+                diagnostic_provider: Some(lsp::DiagnosticServerCapabilities::Options(
+                    lsp::DiagnosticOptions {
+                        identifier: Some("rust".to_string()),
+                        ..Default::default()
+                    },
+                )),
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+    );
+
+    let (buffer, _handle) = project
+        .update(cx, |project, cx| {
+            project.open_local_buffer_with_lsp("/dir/a.rs", cx)
+        })
+        .await
+        .unwrap();
+    let fake_server = fake_servers.next().await.unwrap();
+    let uri = lsp::Url::from_file_path("/dir/a.rs").unwrap();
+    let diagnostic = |line, message: &str| lsp::Diagnostic {
+        range: lsp::Range::new(lsp::Position::new(line, 4), lsp::Position::new(line, 7)),
+        severity: Some(lsp::DiagnosticSeverity::ERROR),
+        message: message.to_string(),
+        ..Default::default()
+    };
+
+    let previous_result_ids = Arc::new(Mutex::new(Vec::new()));
+    fake_server.handle_request::<lsp::request::DocumentDiagnosticRequest, _, _>({
+        let previous_result_ids = previous_result_ids.clone();
+        let pulled = diagnostic(0, "pulled");
+        move |params, _| {
+            let previous_result_ids = previous_result_ids.clone();
+            let pulled = pulled.clone();
+            async move {
+                assert_eq!(params.identifier.as_deref(), Some("rust"));
+                previous_result_ids
+                    .lock()
+                    .push(params.previous_result_id.clone());
+                let report = match params.previous_result_id {
+                    None => lsp::DocumentDiagnosticReport::Full(
+                        lsp::RelatedFullDocumentDiagnosticReport {
+                            related_documents: None,
+                            full_document_diagnostic_report: lsp::FullDocumentDiagnosticReport {
+                                result_id: Some("1".to_string()),
+                                items: vec![pulled],
+                            },
+                        },
+                    ),
+                    Some(result_id) => lsp::DocumentDiagnosticReport::Unchanged(
+                        lsp::RelatedUnchangedDocumentDiagnosticReport {
+                            related_documents: None,
+                            unchanged_document_diagnostic_report:
+                                lsp::UnchangedDocumentDiagnosticReport { result_id },
+                        },
+                    ),
+                };
+                Ok(lsp::DocumentDiagnosticReportResult::Report(report))
+            }
+        }
+    });
+    cx.executor().advance_clock(Duration::from_secs(1));
+    cx.executor().run_until_parked();
+
+    // Pushed diagnostics are shown together with the pulled ones instead of replacing them.
+    fake_server.notify::<lsp::notification::PublishDiagnostics>(&lsp::PublishDiagnosticsParams {
+        uri: uri.clone(),
+        version: None,
+        diagnostics: vec![diagnostic(1, "pushed")],
+    });
+    cx.executor().run_until_parked();
+    let messages = |cx: &mut gpui::TestAppContext| {
+        buffer.read_with(cx, |buffer, _| {
+            buffer
+                .snapshot()
+                .diagnostics_in_range::<_, Point>(0..buffer.len(), false)
+                .map(|entry| entry.diagnostic.message)
+                .collect::<Vec<_>>()
+        })
+    };
+    assert_eq!(messages(cx), vec!["pulled", "pushed"]);
+
+    // Edits cause another pull, that the server can answer with an unchanged report.
+    buffer.update(cx, |buffer, cx| {
+        buffer.edit([(buffer.len()..buffer.len(), "\n")], None, cx);
+    });
+    cx.executor().advance_clock(Duration::from_secs(1));
+    cx.executor().run_until_parked();
+    assert_eq!(
+        *previous_result_ids.lock(),
+        vec![None, Some("1".to_string())]
+    );
+    assert_eq!(messages(cx), vec!["pulled", "pushed"]);
+}
+
+#[gpui::test]
+async fn test_code_lens(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(
+        "/dir",
+        json!({
+            "a.rs": "fn main() {}\n\nfn helper() {}",
+        }),
+    )
+    .await;
+
+    let project = Project::test(fs, ["/dir".as_ref()], cx).await;
+
+    let language_registry = project.read_with(cx, |project, _| project.languages().clone());
+    language_registry.add(rust_lang());
+    let mut fake_servers = language_registry.register_fake_lsp(
+        "Rust",
+        FakeLspAdapter {
+            capabilities: lsp::ServerCapabilities {
+                code_lens_provider: Some(lsp::CodeLensOptions {
+                    resolve_provider: Some(true),
+                }),
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+    );
+
+    let (buffer, _handle) = project
+        .update(cx, |project, cx| {
+            project.open_local_buffer_with_lsp("/dir/a.rs", cx)
+        })
+        .await
+        .unwrap();
+
+    let fake_server = fake_servers.next().await.unwrap();
+    fake_server.handle_request::<lsp::request::CodeLensRequest, _, _>(|params, _| async move {
+        assert_eq!(
+            params.text_document.uri,
+            lsp::Url::from_file_path("/dir/a.rs").unwrap()
+        );
+        Ok(Some(vec![
+            lsp::CodeLens {
+                range: lsp::Range::new(lsp::Position::new(0, 3), lsp::Position::new(0, 7)),
+                command: Some(lsp::Command {
+                    title: "Run".to_string(),
+                    command: "rust-analyzer.runSingle".to_string(),
+                    arguments: None,
+                }),
+                data: None,
+            },
+            lsp::CodeLens {
+                range: lsp::Range::new(lsp::Position::new(2, 3), lsp::Position::new(2, 9)),
+                command: None,
+                data: Some(json!({ "references": "helper" })),
+            },
+        ]))
+    });
+    // Lenses without a command are resolved before being returned.
+    fake_server.handle_request::<lsp::request::CodeLensResolve, _, _>(|lens, _| async move {
+        assert_eq!(lens.data, Some(json!({ "references": "helper" })));
+        Ok(lsp::CodeLens {
+            command: Some(lsp::Command {
+                title: "2 references".to_string(),
+                command: "rust-analyzer.showReferences".to_string(),
+                arguments: None,
+            }),
+            ..lens
+        })
+    });
+
+    let lenses = project
+        .update(cx, |project, cx| project.code_lens(&buffer, cx))
+        .await
+        .unwrap();
+    buffer.read_with(cx, |buffer, _| {
+        assert_eq!(
+            lenses
+                .iter()
+                .map(|lens| (lens.title(), lens.range.to_point(buffer)))
+                .collect::<Vec<_>>(),
+            vec![
+                (Some("Run"), Point::new(0, 3)..Point::new(0, 7)),
+                (Some("2 references"), Point::new(2, 3)..Point::new(2, 9)),
+            ]
+        );
+    });
+}
+
 #[gpui::test]
 async fn test_completions_without_edit_ranges(cx: &mut gpui::TestAppContext) {
     init_test(cx);
@@ -5652,6 +6310,402 @@ async fn test_unstaged_changes_for_buffer(cx: &mut gpui::TestAppContext) {
     });
 }
 
+#[gpui::test]
+async fn test_changing_diff_base(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree(
+        "/dir",
+        json!({
+            ".git": {},
+            "a.txt": "one\ntwo\nthree\n",
+            "b.txt": "b\n",
+        }),
+    )
+    .await;
+    fs.set_index_for_repo(
+        Path::new("/dir/.git"),
+        &[
+            (Path::new("a.txt"), "one\ntwo\nthree\n".into()),
+            (Path::new("b.txt"), "b\n".into()),
+        ],
+    );
+    fs.set_diff_base_for_repo(
+        Path::new("/dir/.git"),
+        git::repository::DiffBase::MergeBase("main".into()),
+        &[(Path::new("a.txt"), "one\nthree\n".into())],
+    );
+
+    let project = Project::test(fs.clone(), ["/dir".as_ref()], cx).await;
+    let worktree_id = project.read_with(cx, |project, cx| {
+        project.worktrees(cx).next().unwrap().read(cx).id()
+    });
+    let buffer = project
+        .update(cx, |project, cx| {
+            project.open_local_buffer("/dir/a.txt", cx)
+        })
+        .await
+        .unwrap();
+    let change_set = project
+        .update(cx, |project, cx| {
+            project.open_unstaged_changes(buffer.clone(), cx)
+        })
+        .await
+        .unwrap();
+    cx.run_until_parked();
+    change_set.update(cx, |change_set, cx| {
+        let snapshot = buffer.read(cx).snapshot();
+        assert_eq!(
+            change_set
+                .diff_hunks_intersecting_range(Anchor::MIN..Anchor::MAX, &snapshot)
+                .count(),
+            0
+        );
+    });
+
+    project.update(cx, |project, cx| {
+        project.set_diff_base(git::repository::DiffBase::MergeBase("main".into()), cx)
+    });
+    cx.run_until_parked();
+    change_set.update(cx, |change_set, cx| {
+        let snapshot = buffer.read(cx).snapshot();
+        assert_hunks(
+            change_set.diff_hunks_intersecting_range(Anchor::MIN..Anchor::MAX, &snapshot),
+            &snapshot,
+            &change_set.base_text.as_ref().unwrap().read(cx).text(),
+            &[(1..2, "", "two\n")],
+        );
+    });
+    let changed_paths = project
+        .read_with(cx, |project, cx| project.git_changed_paths(worktree_id, cx))
+        .await
+        .unwrap();
+    assert_eq!(changed_paths, [Arc::from(Path::new("a.txt"))]);
+
+    project.update(cx, |project, cx| {
+        project.set_diff_base(git::repository::DiffBase::Index, cx)
+    });
+    cx.run_until_parked();
+    change_set.update(cx, |change_set, cx| {
+        let snapshot = buffer.read(cx).snapshot();
+        assert_eq!(
+            change_set
+                .diff_hunks_intersecting_range(Anchor::MIN..Anchor::MAX, &snapshot)
+                .count(),
+            0
+        );
+    });
+}
+
+#[gpui::test]
+async fn test_staging_and_committing(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree(
+        "/dir",
+        json!({
+            ".git": {},
+            "a.txt": "a",
+            "b.txt": "b",
+            "c.txt": "c",
+        }),
+    )
+    .await;
+    fs.set_status_for_repo_via_git_operation(
+        Path::new("/dir/.git"),
+        &[
+            (Path::new("a.txt"), git::repository::GitFileStatus::Modified),
+            (Path::new("b.txt"), git::repository::GitFileStatus::Added),
+        ],
+    );
+
+    let project = Project::test(fs.clone(), ["/dir".as_ref()], cx).await;
+    cx.run_until_parked();
+    let worktree_id = project.read_with(cx, |project, cx| {
+        project.worktrees(cx).next().unwrap().read(cx).id()
+    });
+    let a_txt: Arc<Path> = Path::new("a.txt").into();
+    let b_txt: Arc<Path> = Path::new("b.txt").into();
+    let c_txt: Arc<Path> = Path::new("c.txt").into();
+    let all_paths = vec![a_txt.clone(), b_txt.clone(), c_txt.clone()];
+
+    let stage_statuses = project
+        .update(cx, |project, cx| {
+            project.git_stage_statuses(worktree_id, all_paths.clone(), cx)
+        })
+        .await
+        .unwrap();
+    assert_eq!(
+        stage_statuses,
+        [
+            (a_txt.clone(), StageStatus::Unstaged),
+            (b_txt.clone(), StageStatus::Unstaged),
+        ]
+    );
+
+    project
+        .update(cx, |project, cx| {
+            project.stage_paths(worktree_id, vec![a_txt.clone()], cx)
+        })
+        .await
+        .unwrap();
+    let stage_statuses = project
+        .update(cx, |project, cx| {
+            project.git_stage_statuses(worktree_id, all_paths.clone(), cx)
+        })
+        .await
+        .unwrap();
+    assert_eq!(
+        stage_statuses,
+        [
+            (a_txt.clone(), StageStatus::Staged),
+            (b_txt.clone(), StageStatus::Unstaged),
+        ]
+    );
+
+    project
+        .update(cx, |project, cx| {
+            project.commit(
+                vec![(worktree_id, vec![a_txt.clone()])],
+                "Update a".into(),
+                cx,
+            )
+        })
+        .await
+        .unwrap();
+    let stage_statuses = project
+        .update(cx, |project, cx| {
+            project.git_stage_statuses(worktree_id, all_paths.clone(), cx)
+        })
+        .await
+        .unwrap();
+    assert_eq!(stage_statuses, [(b_txt.clone(), StageStatus::Unstaged)]);
+    fs.with_git_state(Path::new("/dir/.git"), false, |state| {
+        assert_eq!(state.commit_messages, ["Update a"]);
+    });
+
+    let result = project
+        .update(cx, |project, cx| {
+            project.commit(
+                vec![(worktree_id, vec![b_txt.clone()])],
+                "Nothing".into(),
+                cx,
+            )
+        })
+        .await;
+    assert!(result.is_err(), "commit without staged changes should fail");
+}
+
+#[gpui::test]
+async fn test_committing_worktrees_in_one_repository(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree(
+        "/repo",
+        json!({
+            ".git": {},
+            "a": { "a.txt": "a" },
+            "b": { "b.txt": "b" },
+        }),
+    )
+    .await;
+    fs.set_status_for_repo_via_git_operation(
+        Path::new("/repo/.git"),
+        &[
+            (
+                Path::new("a/a.txt"),
+                git::repository::GitFileStatus::Modified,
+            ),
+            (
+                Path::new("b/b.txt"),
+                git::repository::GitFileStatus::Modified,
+            ),
+        ],
+    );
+
+    let project = Project::test(fs.clone(), ["/repo/a".as_ref(), "/repo/b".as_ref()], cx).await;
+    cx.run_until_parked();
+    let worktree_ids = project.read_with(cx, |project, cx| {
+        project
+            .worktrees(cx)
+            .map(|worktree| worktree.read(cx).id())
+            .collect::<Vec<_>>()
+    });
+    let changes = vec![
+        (worktree_ids[0], vec![Arc::from(Path::new("a.txt"))]),
+        (worktree_ids[1], vec![Arc::from(Path::new("b.txt"))]),
+    ];
+
+    for (worktree_id, paths) in &changes {
+        project
+            .update(cx, |project, cx| {
+                project.stage_paths(*worktree_id, paths.clone(), cx)
+            })
+            .await
+            .unwrap();
+    }
+    project
+        .update(cx, |project, cx| {
+            project.commit(changes.clone(), "Update a and b".into(), cx)
+        })
+        .await
+        .unwrap();
+
+    fs.with_git_state(Path::new("/repo/.git"), false, |state| {
+        assert_eq!(
+            state.commit_messages,
+            ["Update a and b"],
+            "both worktrees' changes should be in a single commit"
+        );
+        assert!(state.staged_paths.is_empty());
+        assert!(state.worktree_statuses.is_empty());
+    });
+}
+
+#[gpui::test]
+async fn test_unstaging_hunks(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree(
+        "/dir",
+        json!({
+            ".git": {},
+            "a.txt": "one\nTWO\nthree\nFOUR\n",
+            "b.txt": "b\n",
+        }),
+    )
+    .await;
+    fs.set_diff_base_for_repo(
+        Path::new("/dir/.git"),
+        git::repository::DiffBase::Head,
+        &[(Path::new("a.txt"), "one\ntwo\nthree\nfour\n".into())],
+    );
+    fs.set_index_for_repo(
+        Path::new("/dir/.git"),
+        &[
+            (Path::new("a.txt"), "one\nTWO\nthree\nFOUR\n".into()),
+            (Path::new("b.txt"), "b\n".into()),
+        ],
+    );
+
+    let project = Project::test(fs.clone(), ["/dir".as_ref()], cx).await;
+    cx.run_until_parked();
+    let worktree_id = project.read_with(cx, |project, cx| {
+        project.worktrees(cx).next().unwrap().read(cx).id()
+    });
+    let project_path = |path: &str| ProjectPath {
+        worktree_id,
+        path: Path::new(path).into(),
+    };
+
+    // Only the staged change overlapping "two\n" in HEAD is unstaged.
+    project
+        .update(cx, |project, cx| {
+            project.unstage_hunks(project_path("a.txt"), vec![4..8], cx)
+        })
+        .await
+        .unwrap();
+    fs.with_git_state(Path::new("/dir/.git"), false, |state| {
+        assert_eq!(
+            state.index_contents[Path::new("a.txt")],
+            "one\ntwo\nthree\nFOUR\n"
+        );
+    });
+
+    // Files that are not in HEAD are unstaged as a whole.
+    project
+        .update(cx, |project, cx| {
+            project.unstage_hunks(project_path("b.txt"), vec![0..0], cx)
+        })
+        .await
+        .unwrap();
+    fs.with_git_state(Path::new("/dir/.git"), false, |state| {
+        assert!(!state.index_contents.contains_key(Path::new("b.txt")));
+    });
+}
+
+#[gpui::test]
+async fn test_git_log(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree(
+        "/dir",
+        json!({
+            ".git": {},
+            "a.txt": "a2",
+            "b.txt": "b",
+        }),
+    )
+    .await;
+    let commit = |sha: &str, subject: &str, files: Vec<CommitFileDiff>| {
+        (
+            CommitSummary {
+                sha: sha.to_string().into(),
+                subject: subject.to_string().into(),
+                author_name: "Jane Doe".into(),
+                author_email: "jane@example.com".into(),
+                commit_timestamp: 0,
+            },
+            files,
+        )
+    };
+    let file = |path: &str, old_text: Option<&str>, new_text: Option<&str>| CommitFileDiff {
+        path: Path::new(path).into(),
+        old_text: old_text.map(str::to_string),
+        new_text: new_text.map(str::to_string),
+    };
+    fs.with_git_state(Path::new("/dir/.git"), false, |state| {
+        state.commits = vec![
+            commit("3", "Update a", vec![file("a.txt", Some("a1"), Some("a2"))]),
+            commit("2", "Add b", vec![file("b.txt", None, Some("b"))]),
+            commit("1", "Add a", vec![file("a.txt", None, Some("a1"))]),
+        ];
+    });
+
+    let project = Project::test(fs.clone(), ["/dir".as_ref()], cx).await;
+    cx.run_until_parked();
+    let worktree_id = project.read_with(cx, |project, cx| {
+        project.worktrees(cx).next().unwrap().read(cx).id()
+    });
+    let project_path = |path: &str| ProjectPath {
+        worktree_id,
+        path: Path::new(path).into(),
+    };
+    let mut log = |path: &str, filter_by_path: bool, skip: usize, limit: usize| {
+        let task = project.update(cx, |project, cx| {
+            project.git_log(project_path(path), filter_by_path, skip, limit, cx)
+        });
+        async move {
+            task.await
+                .unwrap()
+                .into_iter()
+                .map(|commit| commit.subject.to_string())
+                .collect::<Vec<_>>()
+        }
+    };
+
+    assert_eq!(
+        log("a.txt", false, 0, 10).await,
+        ["Update a", "Add b", "Add a"]
+    );
+    assert_eq!(log("a.txt", false, 1, 1).await, ["Add b"]);
+    assert_eq!(log("a.txt", true, 0, 10).await, ["Update a", "Add a"]);
+    assert_eq!(log("b.txt", true, 0, 10).await, ["Add b"]);
+
+    let files = project
+        .update(cx, |project, cx| {
+            project.git_commit_diff(project_path("a.txt"), "3".into(), cx)
+        })
+        .await
+        .unwrap();
+    assert_eq!(files, [file("a.txt", Some("a1"), Some("a2"))]);
+}
+
 async fn search(
     project: &Model<Project>,
     query: SearchQuery,
@@ -177,16 +177,36 @@ impl Inventory {
             .chain(global_tasks);
         let worktree_tasks = self
             .worktree_templates_from_settings(worktree)
-            .chain(language_tasks);
+            .chain(language_tasks)
+            .collect::<Vec<_>>();
+        // Tasks may only depend on the tasks defined next to them.
+        let mut templates_by_source = HashMap::<TaskSourceKind, TaskTemplates>::default();
+        for (kind, task) in &worktree_tasks {
+            templates_by_source
+                .entry(kind.clone())
+                .or_default()
+                .0
+                .push(task.clone());
+        }
 
         let new_resolved_tasks = worktree_tasks
+            .into_iter()
             .filter_map(|(kind, task)| {
                 let id_base = kind.to_id_base();
-                Some((
-                    kind,
-                    task.resolve_task(&id_base, task_context)?,
-                    not_used_score,
-                ))
+                let resolved_task = match templates_by_source.get(&kind)?.resolve_task(
+                    &task,
+                    &id_base,
+                    task_context,
+                ) {
+                    Ok(resolved_task) => resolved_task?,
+                    // Keep listing the task, so that spawning it reports what is wrong with its dependencies.
+                    Err(error) => {
+                        let mut resolved_task = task.resolve_task(&id_base, task_context)?;
+                        resolved_task.dependency_error = Some(format!("{error:#}"));
+                        resolved_task
+                    }
+                };
+                Some((kind, resolved_task, not_used_score))
             })
             .filter(|(_, resolved_task, _)| {
                 match task_labels_to_ids.entry(resolved_task.resolved_label.clone()) {
@@ -242,6 +262,25 @@ impl Inventory {
         self.last_scheduled_tasks.retain(|(_, task)| &task.id != id);
     }
 
+    /// Returns the templates defined in the same file as the tasks of the given source kind,
+    /// which their dependencies are looked up in.
+    pub fn templates_from_source(&self, task_source_kind: &TaskSourceKind) -> TaskTemplates {
+        let templates = match task_source_kind {
+            TaskSourceKind::AbsPath { .. } => Some(&self.templates_from_settings.global),
+            TaskSourceKind::Worktree {
+                id,
+                directory_in_worktree,
+                ..
+            } => self
+                .templates_from_settings
+                .worktree
+                .get(id)
+                .and_then(|templates| templates.get(directory_in_worktree.as_path())),
+            TaskSourceKind::UserInput | TaskSourceKind::Language { .. } => None,
+        };
+        TaskTemplates(templates.cloned().unwrap_or_default())
+    }
+
     fn global_templates_from_settings(
         &self,
     ) -> impl '_ + Iterator<Item = (TaskSourceKind, TaskTemplate)> {
@@ -851,6 +890,58 @@ mod tests {
         );
     }
 
+    #[gpui::test]
+    async fn test_tasks_with_broken_dependencies(cx: &mut TestAppContext) {
+        init_test(cx);
+        let inventory = cx.update(Inventory::new);
+        inventory.update(cx, |inventory, _| {
+            inventory
+                .update_file_based_tasks(
+                    None,
+                    Some(
+                        &json!([
+                            { "label": "build", "command": "cargo build" },
+                            { "label": "test", "command": "cargo test", "depends_on": ["build"] },
+                            { "label": "lint", "command": "cargo clippy", "depends_on": ["missing"] },
+                            { "label": "a", "command": "echo a", "depends_on": ["b"] },
+                            { "label": "b", "command": "echo b", "depends_on": ["a"] },
+                        ])
+                        .to_string(),
+                    ),
+                )
+                .unwrap();
+        });
+
+        let (_, current) = inventory.update(cx, |inventory, cx| {
+            inventory.used_and_current_resolved_tasks(None, None, &TaskContext::default(), cx)
+        });
+        let dependency_errors = current
+            .into_iter()
+            .map(|(_, task)| (task.resolved_label, task.dependency_error))
+            .sorted()
+            .collect::<Vec<_>>();
+        assert_eq!(
+            dependency_errors,
+            [
+                (
+                    "a".to_string(),
+                    Some("Task dependency cycle: a -> b -> a".to_string())
+                ),
+                (
+                    "b".to_string(),
+                    Some("Task dependency cycle: b -> a -> b".to_string())
+                ),
+                ("build".to_string(), None),
+                (
+                    "lint".to_string(),
+                    Some("Task \"lint\" depends on unknown task \"missing\"".to_string())
+                ),
+                ("test".to_string(), None),
+            ],
+            "tasks with broken dependencies should be listed along with the error"
+        );
+    }
+
     fn init_test(_cx: &mut TestAppContext) {
         if std::env::var("RUST_LOG").is_ok() {
             env_logger::try_init().ok();
@@ -3,7 +3,9 @@ use anyhow::{Context as _, Result};
 use collections::HashMap;
 use gpui::{AnyWindowHandle, AppContext, Context, Entity, Model, ModelContext, Task, WeakModel};
 use itertools::Itertools;
-use language::LanguageName;
+use language::{
+    Diagnostic, DiagnosticEntry, DiagnosticSeverity, LanguageName, PointUtf16, Unclipped,
+};
 use settings::{Settings, SettingsLocation};
 use smol::channel::bounded;
 use std::{
@@ -13,7 +15,7 @@ use std::{
     path::{Path, PathBuf},
     sync::Arc,
 };
-use task::{Shell, SpawnInTerminal};
+use task::{Problem, ProblemMatcher, ProblemSeverity, Shell, SpawnInTerminal};
 use terminal::{
     terminal_settings::{self, TerminalSettings, VenvSettings},
     TaskState, TaskStatus, Terminal, TerminalBuilder,
@@ -173,6 +175,19 @@ impl Project {
 
         let mut python_venv_activate_command = None;
 
+        // Diagnostics can only be published in local projects.
+        let problem_matchers = match &kind {
+            TerminalKind::Task(spawn_task)
+                if this.is_local() && !spawn_task.problem_matchers.is_empty() =>
+            {
+                Some((
+                    spawn_task.full_label.clone(),
+                    spawn_task.problem_matchers.clone(),
+                ))
+            }
+            _ => None,
+        };
+
         let (spawn_task, shell) = match kind {
             TerminalKind::Shell(_) => {
                 if let Some(python_venv_directory) = &python_venv_directory {
@@ -305,10 +320,59 @@ impl Project {
             if let Some(activate_command) = python_venv_activate_command {
                 this.activate_python_virtual_environment(activate_command, &terminal_handle, cx);
             }
+            if let Some((task_label, problem_matchers)) = problem_matchers {
+                this.publish_task_problems(
+                    task_label,
+                    problem_matchers,
+                    path.map(|path| path.to_path_buf()),
+                    &terminal_handle,
+                    cx,
+                );
+            }
             terminal_handle
         })
     }
 
+    /// Matches the output of the task running in the terminal against its problem matchers every time it finishes,
+    /// publishing the problems found as diagnostics. Relative paths are resolved against the task's working directory.
+    fn publish_task_problems(
+        &mut self,
+        task_label: String,
+        problem_matchers: Vec<ProblemMatcher>,
+        cwd: Option<PathBuf>,
+        terminal: &Model<Terminal>,
+        cx: &mut ModelContext<Self>,
+    ) {
+        cx.subscribe(
+            terminal,
+            move |project, terminal, event: &terminal::Event, cx| {
+                if !matches!(event, terminal::Event::TaskFinished { .. }) {
+                    return;
+                }
+                let Some(cwd) = cwd.clone().or_else(|| project.first_project_directory(cx)) else {
+                    return;
+                };
+                let output = terminal.read(cx).text();
+                let mut diagnostics = HashMap::<PathBuf, Vec<_>>::default();
+                for problem in problem_matchers
+                    .iter()
+                    .flat_map(|matcher| matcher.find_problems(output.lines()))
+                {
+                    let entries = diagnostics.entry(cwd.join(&problem.path)).or_default();
+                    let group_id = entries.len();
+                    entries.push(task_problem_diagnostic(problem, group_id));
+                }
+                project
+                    .lsp_store
+                    .update(cx, |lsp_store, cx| {
+                        lsp_store.update_task_diagnostics(&task_label, diagnostics, cx)
+                    })
+                    .log_err();
+            },
+        )
+        .detach();
+    }
+
     fn python_venv_directory(
         &self,
         abs_path: Arc<Path>,
@@ -453,6 +517,44 @@ impl Project {
     }
 }
 
+fn task_problem_diagnostic(
+    problem: Problem,
+    group_id: usize,
+) -> DiagnosticEntry<Unclipped<PointUtf16>> {
+    let start = PointUtf16::new(
+        problem.line.saturating_sub(1),
+        problem.column.saturating_sub(1),
+    );
+    let end = match (problem.end_line, problem.end_column) {
+        (Some(line), Some(column)) => {
+            PointUtf16::new(line.saturating_sub(1), column.saturating_sub(1))
+        }
+        (Some(line), None) => PointUtf16::new(line.saturating_sub(1), start.column),
+        (None, Some(column)) => PointUtf16::new(start.row, column.saturating_sub(1)),
+        (None, None) => start,
+    }
+    .max(start);
+    DiagnosticEntry {
+        range: Unclipped(start)..Unclipped(end),
+        diagnostic: Diagnostic {
+            source: problem.source,
+            code: problem.code,
+            severity: match problem.severity {
+                ProblemSeverity::Error => DiagnosticSeverity::ERROR,
+                ProblemSeverity::Warning => DiagnosticSeverity::WARNING,
+                ProblemSeverity::Info => DiagnosticSeverity::INFORMATION,
+                ProblemSeverity::Hint => DiagnosticSeverity::HINT,
+            },
+            message: problem.message,
+            group_id,
+            is_primary: true,
+            is_disk_based: true,
+            is_unnecessary: false,
+            data: None,
+        },
+    }
+}
+
 fn wrap_for_ssh(
     ssh_command: &SshCommand,
     command: Option<(&String, &Vec<String>)>,
@@ -1,4 +1,6 @@
 use std::{
+    collections::BTreeMap,
+    ops::Range,
     path::{Path, PathBuf},
     sync::{atomic::AtomicUsize, Arc},
 };
@@ -10,6 +12,15 @@ use futures::{
     future::{BoxFuture, Shared},
     FutureExt, SinkExt,
 };
+use git::{
+    commit::{CommitFileDiff, CommitSummary},
+    conflict::ConflictStages,
+    repository::{
+        AskPassRequest, DiffBase, GitRepository, RemoteDelegate, RemoteOperation, RepoPath,
+        UpstreamTracking,
+    },
+    status::StageStatus,
+};
 use gpui::{
     AppContext, AsyncAppContext, EntityId, EventEmitter, Model, ModelContext, Task, WeakModel,
 };
@@ -24,7 +35,7 @@ use smol::{
 };
 use text::ReplicaId;
 use util::{paths::SanitizedPath, ResultExt};
-use worktree::{Entry, ProjectEntryId, Worktree, WorktreeId, WorktreeSettings};
+use worktree::{Entry, LocalWorktree, ProjectEntryId, Worktree, WorktreeId, WorktreeSettings};
 
 use crate::{search::SearchQuery, LspStore, ProjectPath};
 
@@ -34,6 +45,17 @@ struct MatchingEntry {
     respond: oneshot::Sender<ProjectPath>,
 }
 
+/// Where a git operation on a set of worktree paths has to be performed.
+enum GitOperationTarget {
+    /// The worktree is local: the paths, grouped by the repository containing them.
+    Local(Vec<(Arc<dyn GitRepository>, Vec<(Arc<Path>, RepoPath)>)>),
+    /// The worktree is remote and the operation must be forwarded to its host.
+    Remote {
+        client: AnyProtoClient,
+        project_id: u64,
+    },
+}
+
 enum WorktreeStoreState {
     Local {
         fs: Arc<dyn Fs>,
@@ -54,6 +76,17 @@ pub struct WorktreeStore {
     loading_worktrees:
         HashMap<SanitizedPath, Shared<Task<Result<Model<Worktree>, Arc<anyhow::Error>>>>>,
     state: WorktreeStoreState,
+    next_git_remote_operation_id: u64,
+    git_remote_operations: BTreeMap<u64, GitRemoteOperationStatus>,
+}
+
+/// A fetch, pull or push running in one of the repositories of the project.
+#[derive(Clone, Debug)]
+pub struct GitRemoteOperationStatus {
+    pub operation: RemoteOperation,
+    /// The latest progress reported by git.
+    pub message: Option<String>,
+    delegate: RemoteDelegate,
 }
 
 pub enum WorktreeStoreEvent {
@@ -62,6 +95,8 @@ pub enum WorktreeStoreEvent {
     WorktreeReleased(EntityId, WorktreeId),
     WorktreeOrderChanged,
     WorktreeUpdateSent(Model<Worktree>),
+    GitRemoteOperationsChanged,
+    GitAskPass(AskPassRequest),
 }
 
 impl EventEmitter<WorktreeStoreEvent> for WorktreeStore {}
@@ -74,6 +109,17 @@ impl WorktreeStore {
         client.add_model_request_handler(Self::handle_expand_project_entry);
         client.add_model_request_handler(Self::handle_git_branches);
         client.add_model_request_handler(Self::handle_update_branch);
+        client.add_model_request_handler(Self::handle_git_stage_statuses);
+        client.add_model_request_handler(Self::handle_stage_git_paths);
+        client.add_model_request_handler(Self::handle_unstage_git_paths);
+        client.add_model_request_handler(Self::handle_discard_git_paths);
+        client.add_model_request_handler(Self::handle_set_git_index_text);
+        client.add_model_request_handler(Self::handle_unstage_git_hunks);
+        client.add_model_request_handler(Self::handle_commit_git_changes);
+        client.add_model_request_handler(Self::handle_git_log);
+        client.add_model_request_handler(Self::handle_git_commit_diff);
+        client.add_model_request_handler(Self::handle_git_conflict_stages);
+        client.add_model_request_handler(Self::handle_git_remote_operation);
     }
 
     pub fn local(retain_worktrees: bool, fs: Arc<dyn Fs>) -> Self {
@@ -85,6 +131,8 @@ impl WorktreeStore {
             worktrees_reordered: false,
             retain_worktrees,
             state: WorktreeStoreState::Local { fs },
+            next_git_remote_operation_id: 0,
+            git_remote_operations: BTreeMap::default(),
         }
     }
 
@@ -104,6 +152,8 @@ impl WorktreeStore {
                 upstream_client,
                 upstream_project_id,
             },
+            next_git_remote_operation_id: 0,
+            git_remote_operations: BTreeMap::default(),
         }
     }
 
@@ -915,6 +965,10 @@ impl WorktreeStore {
                             unix_timestamp: proto_branch
                                 .unix_timestamp
                                 .map(|timestamp| timestamp as i64),
+                            upstream: proto_branch.upstream.map(|upstream| UpstreamTracking {
+                                ahead: upstream.ahead,
+                                behind: upstream.behind,
+                            }),
                         })
                         .collect();
 
@@ -984,6 +1038,623 @@ impl WorktreeStore {
         }
     }
 
+    fn git_operation_target(
+        &self,
+        worktree_id: WorktreeId,
+        paths: &[Arc<Path>],
+        cx: &AppContext,
+    ) -> Result<GitOperationTarget> {
+        let worktree = self
+            .worktree_for_id(worktree_id, cx)
+            .context("No worktree found for git operation")?;
+        match worktree.read(cx) {
+            Worktree::Local(local_worktree) => Ok(GitOperationTarget::Local(
+                Self::group_paths_by_repository(local_worktree, paths)?,
+            )),
+            Worktree::Remote(remote_worktree) => Ok(GitOperationTarget::Remote {
+                client: remote_worktree.client(),
+                project_id: remote_worktree.project_id(),
+            }),
+        }
+    }
+
+    fn group_paths_by_repository(
+        local_worktree: &LocalWorktree,
+        paths: &[Arc<Path>],
+    ) -> Result<Vec<(Arc<dyn GitRepository>, Vec<(Arc<Path>, RepoPath)>)>> {
+        let mut repositories =
+            HashMap::<ProjectEntryId, (Arc<dyn GitRepository>, Vec<_>)>::default();
+        for path in paths {
+            let (repository_entry, local_repository) = local_worktree
+                .repo_for_path(path)
+                .with_context(|| format!("No repository found for path {path:?}"))?;
+            let repo_path = repository_entry.relativize(local_worktree, path)?;
+            repositories
+                .entry(repository_entry.work_directory_id())
+                .or_insert_with(|| (local_repository.repo().clone(), Vec::new()))
+                .1
+                .push((path.clone(), repo_path));
+        }
+        Ok(repositories.into_values().collect())
+    }
+
+    /// Returns how much of each of the given paths' changes are staged in their repository's index.
+    /// Paths without changes are omitted from the result.
+    pub fn git_stage_statuses(
+        &self,
+        worktree_id: WorktreeId,
+        paths: Vec<Arc<Path>>,
+        cx: &AppContext,
+    ) -> Task<Result<Vec<(Arc<Path>, StageStatus)>>> {
+        match self.git_operation_target(worktree_id, &paths, cx) {
+            Ok(GitOperationTarget::Local(repositories)) => {
+                cx.background_executor().spawn(async move {
+                    let mut stage_statuses = Vec::new();
+                    for (repo, paths) in repositories {
+                        let repo_paths = paths
+                            .iter()
+                            .map(|(_, repo_path)| repo_path.0.clone())
+                            .collect::<Vec<_>>();
+                        let status = repo.status(&repo_paths)?;
+                        stage_statuses.extend(paths.into_iter().filter_map(|(path, repo_path)| {
+                            Some((path, status.stage_status(&repo_path)?))
+                        }));
+                    }
+                    Ok(stage_statuses)
+                })
+            }
+            Ok(GitOperationTarget::Remote { client, project_id }) => {
+                let request = client.request(proto::GitStageStatuses {
+                    project_id,
+                    worktree_id: worktree_id.to_proto(),
+                    paths: paths_to_proto(&paths),
+                });
+                cx.background_executor().spawn(async move {
+                    let response = request.await?;
+                    Ok(response
+                        .entries
+                        .into_iter()
+                        .filter_map(|entry| {
+                            let status = match proto::GitStageStatus::from_i32(entry.status)? {
+                                proto::GitStageStatus::Unstaged => StageStatus::Unstaged,
+                                proto::GitStageStatus::PartiallyStaged => {
+                                    StageStatus::PartiallyStaged
+                                }
+                                proto::GitStageStatus::Staged => StageStatus::Staged,
+                            };
+                            Some((Arc::from(Path::new(&entry.path)), status))
+                        })
+                        .collect())
+                })
+            }
+            Err(error) => Task::ready(Err(error)),
+        }
+    }
+
+    /// Returns the paths of a worktree whose contents differ from the given diff base in their repository.
+    pub fn git_changed_paths(
+        &self,
+        worktree_id: WorktreeId,
+        diff_base: DiffBase,
+        cx: &AppContext,
+    ) -> Task<Result<Vec<Arc<Path>>>> {
+        let Some(worktree) = self.worktree_for_id(worktree_id, cx) else {
+            return Task::ready(Err(anyhow!("No worktree found for git operation")));
+        };
+        let Worktree::Local(local_worktree) = worktree.read(cx) else {
+            return Task::ready(Err(anyhow!(
+                "Remote worktrees don't support diff bases yet"
+            )));
+        };
+
+        let snapshot = local_worktree.snapshot();
+        let repositories = local_worktree
+            .repositories()
+            .filter_map(|(_, repository_entry)| {
+                let local_repository = local_worktree.get_local_repo(repository_entry)?;
+                Some((repository_entry.clone(), local_repository.repo().clone()))
+            })
+            .collect::<Vec<_>>();
+        cx.background_executor().spawn(async move {
+            let mut changed_paths = Vec::new();
+            for (repository_entry, repo) in repositories {
+                changed_paths.extend(
+                    repo.changed_paths(&diff_base)?
+                        .iter()
+                        .filter_map(|repo_path| {
+                            repository_entry.unrelativize(&snapshot, repo_path)
+                        }),
+                );
+            }
+            Ok(changed_paths)
+        })
+    }
+
+    /// Adds the working copy contents of the given paths to the index.
+    pub fn stage_paths(
+        &self,
+        worktree_id: WorktreeId,
+        paths: Vec<Arc<Path>>,
+        cx: &AppContext,
+    ) -> Task<Result<()>> {
+        self.run_git_path_operation(
+            worktree_id,
+            paths,
+            |repo, repo_paths| repo.stage_paths(repo_paths),
+            |project_id, worktree_id, paths, client| {
+                client.request(proto::StageGitPaths {
+                    project_id,
+                    worktree_id,
+                    paths,
+                })
+            },
+            cx,
+        )
+    }
+
+    /// Resets the index entries of the given paths to HEAD.
+    pub fn unstage_paths(
+        &self,
+        worktree_id: WorktreeId,
+        paths: Vec<Arc<Path>>,
+        cx: &AppContext,
+    ) -> Task<Result<()>> {
+        self.run_git_path_operation(
+            worktree_id,
+            paths,
+            |repo, repo_paths| repo.unstage_paths(repo_paths),
+            |project_id, worktree_id, paths, client| {
+                client.request(proto::UnstageGitPaths {
+                    project_id,
+                    worktree_id,
+                    paths,
+                })
+            },
+            cx,
+        )
+    }
+
+    /// Throws away the unstaged changes of the given paths.
+    pub fn discard_paths(
+        &self,
+        worktree_id: WorktreeId,
+        paths: Vec<Arc<Path>>,
+        cx: &AppContext,
+    ) -> Task<Result<()>> {
+        self.run_git_path_operation(
+            worktree_id,
+            paths,
+            |repo, repo_paths| repo.discard_paths(repo_paths),
+            |project_id, worktree_id, paths, client| {
+                client.request(proto::DiscardGitPaths {
+                    project_id,
+                    worktree_id,
+                    paths,
+                })
+            },
+            cx,
+        )
+    }
+
+    /// Creates a commit with the given message in every repository that contains one of the paths,
+    /// given by their worktrees.
+    /// Repositories are committed to once, even when several of the worktrees are in the same one,
+    /// since a commit includes everything staged in the repository's index.
+    pub fn commit(
+        &self,
+        changes: Vec<(WorktreeId, Vec<Arc<Path>>)>,
+        message: String,
+        cx: &AppContext,
+    ) -> Task<Result<()>> {
+        let mut repositories = HashMap::<PathBuf, Arc<dyn GitRepository>>::default();
+        let mut remote = None;
+        for (worktree_id, paths) in &changes {
+            match self.git_operation_target(*worktree_id, paths, cx) {
+                Ok(GitOperationTarget::Local(worktree_repositories)) => {
+                    for (repo, _) in worktree_repositories {
+                        repositories.entry(repo.dot_git_dir()).or_insert(repo);
+                    }
+                }
+                Ok(GitOperationTarget::Remote { client, project_id }) => {
+                    remote = Some((client, project_id));
+                }
+                Err(error) => return Task::ready(Err(error)),
+            }
+        }
+
+        if let Some((client, project_id)) = remote {
+            let request = client.request(proto::CommitGitChanges {
+                project_id,
+                worktrees: changes
+                    .iter()
+                    .map(|(worktree_id, paths)| proto::GitWorktreePaths {
+                        worktree_id: worktree_id.to_proto(),
+                        paths: paths_to_proto(paths),
+                    })
+                    .collect(),
+                message,
+            });
+            cx.background_executor().spawn(async move {
+                request.await?;
+                Ok(())
+            })
+        } else {
+            cx.background_executor().spawn(async move {
+                for repo in repositories.into_values() {
+                    repo.commit(&message)?;
+                }
+                Ok(())
+            })
+        }
+    }
+
+    fn run_git_path_operation<R>(
+        &self,
+        worktree_id: WorktreeId,
+        paths: Vec<Arc<Path>>,
+        local: impl Fn(&dyn GitRepository, &[RepoPath]) -> Result<()> + Send + 'static,
+        remote: impl FnOnce(u64, u64, Vec<String>, AnyProtoClient) -> R,
+        cx: &AppContext,
+    ) -> Task<Result<()>>
+    where
+        R: 'static + Send + std::future::Future<Output = Result<proto::Ack>>,
+    {
+        match self.git_operation_target(worktree_id, &paths, cx) {
+            Ok(GitOperationTarget::Local(repositories)) => {
+                cx.background_executor().spawn(async move {
+                    for (repo, paths) in repositories {
+                        let repo_paths = paths
+                            .into_iter()
+                            .map(|(_, repo_path)| repo_path)
+                            .collect::<Vec<_>>();
+                        local(repo.as_ref(), &repo_paths)?;
+                    }
+                    Ok(())
+                })
+            }
+            Ok(GitOperationTarget::Remote { client, project_id }) => {
+                let request = remote(
+                    project_id,
+                    worktree_id.to_proto(),
+                    paths_to_proto(&paths),
+                    client,
+                );
+                cx.background_executor().spawn(async move {
+                    request.await?;
+                    Ok(())
+                })
+            }
+            Err(error) => Task::ready(Err(error)),
+        }
+    }
+
+    /// Replaces the index contents of a single file, leaving its working copy untouched.
+    pub fn set_index_text(
+        &self,
+        project_path: ProjectPath,
+        text: Option<String>,
+        cx: &AppContext,
+    ) -> Task<Result<()>> {
+        let paths = [project_path.path.clone()];
+        match self.git_operation_target(project_path.worktree_id, &paths, cx) {
+            Ok(GitOperationTarget::Local(repositories)) => {
+                cx.background_executor().spawn(async move {
+                    let (repo, paths) = repositories
+                        .into_iter()
+                        .next()
+                        .context("No repository found for path")?;
+                    let (_, repo_path) = paths.into_iter().next().context("No path to update")?;
+                    repo.set_index_text(&repo_path, text)
+                })
+            }
+            Ok(GitOperationTarget::Remote { client, project_id }) => {
+                let request = client.request(proto::SetGitIndexText {
+                    project_id,
+                    worktree_id: project_path.worktree_id.to_proto(),
+                    path: project_path.path.to_string_lossy().to_string(),
+                    text,
+                });
+                cx.background_executor().spawn(async move {
+                    request.await?;
+                    Ok(())
+                })
+            }
+            Err(error) => Task::ready(Err(error)),
+        }
+    }
+
+    /// Unstages the staged changes of a single file that overlap the given byte ranges of its
+    /// HEAD text.
+    pub fn unstage_hunks(
+        &self,
+        project_path: ProjectPath,
+        head_ranges: Vec<Range<usize>>,
+        cx: &AppContext,
+    ) -> Task<Result<()>> {
+        let paths = [project_path.path.clone()];
+        match self.git_operation_target(project_path.worktree_id, &paths, cx) {
+            Ok(GitOperationTarget::Local(repositories)) => {
+                cx.background_executor().spawn(async move {
+                    let (repo, paths) = repositories
+                        .into_iter()
+                        .next()
+                        .context("No repository found for path")?;
+                    let (_, repo_path) = paths.into_iter().next().context("No path to update")?;
+                    repo.unstage_hunks(&repo_path, &head_ranges)
+                })
+            }
+            Ok(GitOperationTarget::Remote { client, project_id }) => {
+                let request = client.request(proto::UnstageGitHunks {
+                    project_id,
+                    worktree_id: project_path.worktree_id.to_proto(),
+                    path: project_path.path.to_string_lossy().to_string(),
+                    head_ranges: head_ranges
+                        .into_iter()
+                        .map(|range| proto::Range {
+                            start: range.start as u64,
+                            end: range.end as u64,
+                        })
+                        .collect(),
+                });
+                cx.background_executor().spawn(async move {
+                    request.await?;
+                    Ok(())
+                })
+            }
+            Err(error) => Task::ready(Err(error)),
+        }
+    }
+
+    /// Lists up to `limit` commits of the repository containing the given path, newest first,
+    /// after skipping the `skip` newest ones.
+    /// With `filter_by_path`, only the commits that changed the path itself are listed.
+    pub fn git_log(
+        &self,
+        project_path: ProjectPath,
+        filter_by_path: bool,
+        skip: usize,
+        limit: usize,
+        cx: &AppContext,
+    ) -> Task<Result<Vec<CommitSummary>>> {
+        let paths = [project_path.path.clone()];
+        match self.git_operation_target(project_path.worktree_id, &paths, cx) {
+            Ok(GitOperationTarget::Local(repositories)) => {
+                cx.background_executor().spawn(async move {
+                    let (repo, paths) = repositories
+                        .into_iter()
+                        .next()
+                        .context("No repository found for path")?;
+                    let (_, repo_path) = paths.into_iter().next().context("No path to log")?;
+                    repo.log(filter_by_path.then_some(&repo_path), skip, limit)
+                })
+            }
+            Ok(GitOperationTarget::Remote { client, project_id }) => {
+                let request = client.request(proto::GitLog {
+                    project_id,
+                    worktree_id: project_path.worktree_id.to_proto(),
+                    path: project_path.path.to_string_lossy().to_string(),
+                    filter_by_path,
+                    skip: skip as u64,
+                    limit: limit as u64,
+                });
+                cx.background_executor().spawn(async move {
+                    let response = request.await?;
+                    Ok(response
+                        .commits
+                        .into_iter()
+                        .map(|commit| CommitSummary {
+                            sha: commit.sha.into(),
+                            subject: commit.subject.into(),
+                            author_name: commit.author_name.into(),
+                            author_email: commit.author_email.into(),
+                            commit_timestamp: commit.commit_timestamp,
+                        })
+                        .collect())
+                })
+            }
+            Err(error) => Task::ready(Err(error)),
+        }
+    }
+
+    /// Returns the files changed by a commit of the repository containing the given path.
+    pub fn git_commit_diff(
+        &self,
+        project_path: ProjectPath,
+        sha: String,
+        cx: &AppContext,
+    ) -> Task<Result<Vec<CommitFileDiff>>> {
+        let paths = [project_path.path.clone()];
+        match self.git_operation_target(project_path.worktree_id, &paths, cx) {
+            Ok(GitOperationTarget::Local(repositories)) => {
+                cx.background_executor().spawn(async move {
+                    let (repo, _) = repositories
+                        .into_iter()
+                        .next()
+                        .context("No repository found for path")?;
+                    repo.commit_diff(&sha)
+                })
+            }
+            Ok(GitOperationTarget::Remote { client, project_id }) => {
+                let request = client.request(proto::GitCommitDiff {
+                    project_id,
+                    worktree_id: project_path.worktree_id.to_proto(),
+                    path: project_path.path.to_string_lossy().to_string(),
+                    sha,
+                });
+                cx.background_executor().spawn(async move {
+                    let response = request.await?;
+                    Ok(response
+                        .files
+                        .into_iter()
+                        .map(|file| CommitFileDiff {
+                            path: RepoPath::from(PathBuf::from(file.path)),
+                            old_text: file.old_text,
+                            new_text: file.new_text,
+                        })
+                        .collect())
+                })
+            }
+            Err(error) => Task::ready(Err(error)),
+        }
+    }
+
+    /// Loads the base, our and their versions of a conflicted file from its repository's index.
+    pub fn git_conflict_stages(
+        &self,
+        project_path: ProjectPath,
+        cx: &AppContext,
+    ) -> Task<Result<ConflictStages>> {
+        let paths = [project_path.path.clone()];
+        match self.git_operation_target(project_path.worktree_id, &paths, cx) {
+            Ok(GitOperationTarget::Local(repositories)) => {
+                cx.background_executor().spawn(async move {
+                    let (repo, paths) = repositories
+                        .into_iter()
+                        .next()
+                        .context("No repository found for path")?;
+                    let (_, repo_path) = paths.into_iter().next().context("No conflicted path")?;
+                    repo.conflict_stages(&repo_path)
+                })
+            }
+            Ok(GitOperationTarget::Remote { client, project_id }) => {
+                let request = client.request(proto::GitConflictStages {
+                    project_id,
+                    worktree_id: project_path.worktree_id.to_proto(),
+                    path: project_path.path.to_string_lossy().to_string(),
+                });
+                cx.background_executor().spawn(async move {
+                    let response = request.await?;
+                    Ok(ConflictStages {
+                        base: response.base,
+                        ours: response.ours,
+                        theirs: response.theirs,
+                    })
+                })
+            }
+            Err(error) => Task::ready(Err(error)),
+        }
+    }
+
+    /// The fetches, pulls and pushes in progress, oldest first.
+    pub fn git_remote_operations(&self) -> impl Iterator<Item = &GitRemoteOperationStatus> {
+        self.git_remote_operations.values()
+    }
+
+    /// Fetches, pulls or pushes the branch checked out in the repository at the root of a worktree.
+    /// Credential prompts are emitted as [`WorktreeStoreEvent::GitAskPass`] events.
+    pub fn git_remote_operation(
+        &mut self,
+        worktree_id: WorktreeId,
+        operation: RemoteOperation,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<()>> {
+        let operation_id = self.next_git_remote_operation_id;
+        self.next_git_remote_operation_id += 1;
+        let (delegate, ask_pass_rx, progress_rx) = RemoteDelegate::new();
+        let operation_task = match &self.state {
+            WorktreeStoreState::Local { .. } => {
+                self.local_git_remote_operation(worktree_id, operation, delegate.clone(), cx)
+            }
+            WorktreeStoreState::Remote {
+                upstream_client,
+                upstream_project_id,
+            } => {
+                // Credential prompts can only be routed back from the host of SSH projects.
+                if *upstream_project_id != SSH_PROJECT_ID {
+                    return Task::ready(Err(anyhow!(
+                        "Fetching, pulling and pushing are only supported in SSH remote projects"
+                    )));
+                }
+                // Prompts and progress are forwarded to the delegate by the message handlers.
+                let request = upstream_client.request(proto::GitRemoteOperation {
+                    project_id: *upstream_project_id,
+                    worktree_id: worktree_id.to_proto(),
+                    operation_id,
+                    operation: remote_operation_to_proto(operation).into(),
+                });
+                cx.background_executor().spawn(async move {
+                    request.await?;
+                    Ok(())
+                })
+            }
+        };
+        self.git_remote_operations.insert(
+            operation_id,
+            GitRemoteOperationStatus {
+                operation,
+                message: None,
+                delegate,
+            },
+        );
+        cx.emit(WorktreeStoreEvent::GitRemoteOperationsChanged);
+
+        cx.spawn(|this, mut cx| async move {
+            while let Ok(request) = ask_pass_rx.recv().await {
+                this.update(&mut cx, |_, cx| {
+                    cx.emit(WorktreeStoreEvent::GitAskPass(request))
+                })?;
+            }
+            anyhow::Ok(())
+        })
+        .detach();
+        cx.spawn(|this, mut cx| async move {
+            while let Ok(message) = progress_rx.recv().await {
+                this.update(&mut cx, |this, cx| {
+                    this.set_git_remote_operation_message(operation_id, message, cx)
+                })?;
+            }
+            anyhow::Ok(())
+        })
+        .detach();
+        cx.spawn(|this, mut cx| async move {
+            let result = operation_task.await;
+            this.update(&mut cx, |this, cx| {
+                this.git_remote_operations.remove(&operation_id);
+                cx.emit(WorktreeStoreEvent::GitRemoteOperationsChanged);
+            })?;
+            result
+        })
+    }
+
+    fn local_git_remote_operation(
+        &self,
+        worktree_id: WorktreeId,
+        operation: RemoteOperation,
+        delegate: RemoteDelegate,
+        cx: &AppContext,
+    ) -> Task<Result<()>> {
+        let Some(worktree) = self.worktree_for_id(worktree_id, cx) else {
+            return Task::ready(Err(anyhow!("No worktree found for git operation")));
+        };
+        let Worktree::Local(local_worktree) = worktree.read(cx) else {
+            return Task::ready(Err(anyhow!("Worktree is not local")));
+        };
+        let Some(repo) = local_worktree
+            .root_git_entry()
+            .and_then(|entry| local_worktree.get_local_repo(&entry))
+            .map(|repo| repo.repo().clone())
+        else {
+            return Task::ready(Err(anyhow!(
+                "No repository found for worktree {}",
+                local_worktree.abs_path().to_string_lossy()
+            )));
+        };
+        cx.background_executor()
+            .spawn(repo.remote_operation(operation, delegate))
+    }
+
+    fn set_git_remote_operation_message(
+        &mut self,
+        operation_id: u64,
+        message: String,
+        cx: &mut ModelContext<Self>,
+    ) {
+        if let Some(status) = self.git_remote_operations.get_mut(&operation_id) {
+            status.message = Some(message);
+            cx.emit(WorktreeStoreEvent::GitRemoteOperationsChanged);
+        }
+    }
+
     async fn filter_paths(
         fs: &Arc<dyn Fs>,
         mut input: Receiver<MatchingEntry>,
@@ -1132,6 +1803,10 @@ impl WorktreeStore {
                     is_head: branch.is_head,
                     name: branch.name.to_string(),
                     unix_timestamp: branch.unix_timestamp.map(|timestamp| timestamp as u64),
+                    upstream: branch.upstream.map(|upstream| proto::UpstreamTracking {
+                        ahead: upstream.ahead,
+                        behind: upstream.behind,
+                    }),
                 })
                 .collect(),
         })
@@ -1160,6 +1835,324 @@ impl WorktreeStore {
 
         Ok(proto::Ack {})
     }
+
+    pub async fn handle_git_stage_statuses(
+        this: Model<Self>,
+        envelope: TypedEnvelope<proto::GitStageStatuses>,
+        cx: AsyncAppContext,
+    ) -> Result<proto::GitStageStatusesResponse> {
+        let worktree_id = WorktreeId::from_proto(envelope.payload.worktree_id);
+        let paths = paths_from_proto(envelope.payload.paths);
+        let stage_statuses = this
+            .read_with(&cx, |this, cx| {
+                this.git_stage_statuses(worktree_id, paths, cx)
+            })?
+            .await?;
+        Ok(proto::GitStageStatusesResponse {
+            entries: stage_statuses
+                .into_iter()
+                .map(|(path, status)| proto::GitStageStatusEntry {
+                    path: path.to_string_lossy().to_string(),
+                    status: match status {
+                        StageStatus::Unstaged => proto::GitStageStatus::Unstaged,
+                        StageStatus::PartiallyStaged => proto::GitStageStatus::PartiallyStaged,
+                        StageStatus::Staged => proto::GitStageStatus::Staged,
+                    } as i32,
+                })
+                .collect(),
+        })
+    }
+
+    pub async fn handle_stage_git_paths(
+        this: Model<Self>,
+        envelope: TypedEnvelope<proto::StageGitPaths>,
+        cx: AsyncAppContext,
+    ) -> Result<proto::Ack> {
+        let worktree_id = WorktreeId::from_proto(envelope.payload.worktree_id);
+        let paths = paths_from_proto(envelope.payload.paths);
+        this.read_with(&cx, |this, cx| this.stage_paths(worktree_id, paths, cx))?
+            .await?;
+        Ok(proto::Ack {})
+    }
+
+    pub async fn handle_unstage_git_paths(
+        this: Model<Self>,
+        envelope: TypedEnvelope<proto::UnstageGitPaths>,
+        cx: AsyncAppContext,
+    ) -> Result<proto::Ack> {
+        let worktree_id = WorktreeId::from_proto(envelope.payload.worktree_id);
+        let paths = paths_from_proto(envelope.payload.paths);
+        this.read_with(&cx, |this, cx| this.unstage_paths(worktree_id, paths, cx))?
+            .await?;
+        Ok(proto::Ack {})
+    }
+
+    pub async fn handle_discard_git_paths(
+        this: Model<Self>,
+        envelope: TypedEnvelope<proto::DiscardGitPaths>,
+        cx: AsyncAppContext,
+    ) -> Result<proto::Ack> {
+        let worktree_id = WorktreeId::from_proto(envelope.payload.worktree_id);
+        let paths = paths_from_proto(envelope.payload.paths);
+        this.read_with(&cx, |this, cx| this.discard_paths(worktree_id, paths, cx))?
+            .await?;
+        Ok(proto::Ack {})
+    }
+
+    pub async fn handle_set_git_index_text(
+        this: Model<Self>,
+        envelope: TypedEnvelope<proto::SetGitIndexText>,
+        cx: AsyncAppContext,
+    ) -> Result<proto::Ack> {
+        let project_path = ProjectPath {
+            worktree_id: WorktreeId::from_proto(envelope.payload.worktree_id),
+            path: Path::new(&envelope.payload.path).into(),
+        };
+        let text = envelope.payload.text;
+        this.read_with(&cx, |this, cx| this.set_index_text(project_path, text, cx))?
+            .await?;
+        Ok(proto::Ack {})
+    }
+
+    pub async fn handle_unstage_git_hunks(
+        this: Model<Self>,
+        envelope: TypedEnvelope<proto::UnstageGitHunks>,
+        cx: AsyncAppContext,
+    ) -> Result<proto::Ack> {
+        let project_path = ProjectPath {
+            worktree_id: WorktreeId::from_proto(envelope.payload.worktree_id),
+            path: Path::new(&envelope.payload.path).into(),
+        };
+        let head_ranges = envelope
+            .payload
+            .head_ranges
+            .into_iter()
+            .map(|range| range.start as usize..range.end as usize)
+            .collect();
+        this.read_with(&cx, |this, cx| {
+            this.unstage_hunks(project_path, head_ranges, cx)
+        })?
+        .await?;
+        Ok(proto::Ack {})
+    }
+
+    pub async fn handle_commit_git_changes(
+        this: Model<Self>,
+        envelope: TypedEnvelope<proto::CommitGitChanges>,
+        cx: AsyncAppContext,
+    ) -> Result<proto::Ack> {
+        let changes = envelope
+            .payload
+            .worktrees
+            .into_iter()
+            .map(|worktree| {
+                (
+                    WorktreeId::from_proto(worktree.worktree_id),
+                    paths_from_proto(worktree.paths),
+                )
+            })
+            .collect();
+        let message = envelope.payload.message;
+        this.read_with(&cx, |this, cx| this.commit(changes, message, cx))?
+            .await?;
+        Ok(proto::Ack {})
+    }
+
+    pub async fn handle_git_log(
+        this: Model<Self>,
+        envelope: TypedEnvelope<proto::GitLog>,
+        cx: AsyncAppContext,
+    ) -> Result<proto::GitLogResponse> {
+        let project_path = ProjectPath {
+            worktree_id: WorktreeId::from_proto(envelope.payload.worktree_id),
+            path: Path::new(&envelope.payload.path).into(),
+        };
+        let commits = this
+            .read_with(&cx, |this, cx| {
+                this.git_log(
+                    project_path,
+                    envelope.payload.filter_by_path,
+                    envelope.payload.skip as usize,
+                    envelope.payload.limit as usize,
+                    cx,
+                )
+            })?
+            .await?;
+        Ok(proto::GitLogResponse {
+            commits: commits
+                .into_iter()
+                .map(|commit| proto::GitCommitSummary {
+                    sha: commit.sha.to_string(),
+                    subject: commit.subject.to_string(),
+                    author_name: commit.author_name.to_string(),
+                    author_email: commit.author_email.to_string(),
+                    commit_timestamp: commit.commit_timestamp,
+                })
+                .collect(),
+        })
+    }
+
+    pub async fn handle_git_commit_diff(
+        this: Model<Self>,
+        envelope: TypedEnvelope<proto::GitCommitDiff>,
+        cx: AsyncAppContext,
+    ) -> Result<proto::GitCommitDiffResponse> {
+        let project_path = ProjectPath {
+            worktree_id: WorktreeId::from_proto(envelope.payload.worktree_id),
+            path: Path::new(&envelope.payload.path).into(),
+        };
+        let sha = envelope.payload.sha;
+        let files = this
+            .read_with(&cx, |this, cx| this.git_commit_diff(project_path, sha, cx))?
+            .await?;
+        Ok(proto::GitCommitDiffResponse {
+            files: files
+                .into_iter()
+                .map(|file| proto::GitCommitFileDiff {
+                    path: file.path.to_string_lossy().to_string(),
+                    old_text: file.old_text,
+                    new_text: file.new_text,
+                })
+                .collect(),
+        })
+    }
+
+    pub async fn handle_git_conflict_stages(
+        this: Model<Self>,
+        envelope: TypedEnvelope<proto::GitConflictStages>,
+        cx: AsyncAppContext,
+    ) -> Result<proto::GitConflictStagesResponse> {
+        let project_path = ProjectPath {
+            worktree_id: WorktreeId::from_proto(envelope.payload.worktree_id),
+            path: Path::new(&envelope.payload.path).into(),
+        };
+        let stages = this
+            .read_with(&cx, |this, cx| this.git_conflict_stages(project_path, cx))?
+            .await?;
+        Ok(proto::GitConflictStagesResponse {
+            base: stages.base,
+            ours: stages.ours,
+            theirs: stages.theirs,
+        })
+    }
+
+    /// Runs a remote operation for a downstream client, which answers its credential prompts.
+    pub async fn handle_git_remote_operation(
+        this: Model<Self>,
+        envelope: TypedEnvelope<proto::GitRemoteOperation>,
+        mut cx: AsyncAppContext,
+    ) -> Result<proto::Ack> {
+        let worktree_id = WorktreeId::from_proto(envelope.payload.worktree_id);
+        let operation_id = envelope.payload.operation_id;
+        let operation = proto::GitRemoteOperationKind::from_i32(envelope.payload.operation)
+            .map(remote_operation_from_proto)
+            .context("Invalid git remote operation")?;
+        let (delegate, ask_pass_rx, progress_rx) = RemoteDelegate::new();
+        let (operation_task, downstream_client) = this.update(&mut cx, |this, cx| {
+            (
+                this.local_git_remote_operation(worktree_id, operation, delegate, cx),
+                this.downstream_client.clone(),
+            )
+        })?;
+        let (client, project_id) = downstream_client.context("project is not shared")?;
+
+        cx.background_executor()
+            .spawn({
+                let client = client.clone();
+                async move {
+                    while let Ok(request) = ask_pass_rx.recv().await {
+                        let response = client
+                            .request(proto::GitAskPass {
+                                project_id,
+                                operation_id,
+                                prompt: request.prompt.clone(),
+                            })
+                            .await;
+                        request.respond(response.log_err().and_then(|response| response.response));
+                    }
+                }
+            })
+            .detach();
+        cx.background_executor()
+            .spawn(async move {
+                while let Ok(message) = progress_rx.recv().await {
+                    client
+                        .send(proto::GitRemoteOperationProgress {
+                            project_id,
+                            operation_id,
+                            message,
+                        })
+                        .log_err();
+                }
+            })
+            .detach();
+
+        operation_task.await?;
+        Ok(proto::Ack {})
+    }
+
+    pub async fn handle_git_ask_pass(
+        this: Model<Self>,
+        envelope: TypedEnvelope<proto::GitAskPass>,
+        cx: AsyncAppContext,
+    ) -> Result<proto::GitAskPassResponse> {
+        let delegate = this
+            .read_with(&cx, |this, _| {
+                this.git_remote_operations
+                    .get(&envelope.payload.operation_id)
+                    .map(|status| status.delegate.clone())
+            })?
+            .context("No git operation found for credential prompt")?;
+        let (request, response_rx) = AskPassRequest::new(envelope.payload.prompt);
+        delegate.ask_pass_tx.send(request).await?;
+        Ok(proto::GitAskPassResponse {
+            response: response_rx.recv().await.ok().flatten(),
+        })
+    }
+
+    pub async fn handle_git_remote_operation_progress(
+        this: Model<Self>,
+        envelope: TypedEnvelope<proto::GitRemoteOperationProgress>,
+        mut cx: AsyncAppContext,
+    ) -> Result<()> {
+        this.update(&mut cx, |this, cx| {
+            this.set_git_remote_operation_message(
+                envelope.payload.operation_id,
+                envelope.payload.message,
+                cx,
+            )
+        })
+    }
+}
+
+fn remote_operation_to_proto(operation: RemoteOperation) -> proto::GitRemoteOperationKind {
+    match operation {
+        RemoteOperation::Fetch => proto::GitRemoteOperationKind::Fetch,
+        RemoteOperation::Pull => proto::GitRemoteOperationKind::Pull,
+        RemoteOperation::Push => proto::GitRemoteOperationKind::Push,
+    }
+}
+
+fn remote_operation_from_proto(operation: proto::GitRemoteOperationKind) -> RemoteOperation {
+    match operation {
+        proto::GitRemoteOperationKind::Fetch => RemoteOperation::Fetch,
+        proto::GitRemoteOperationKind::Pull => RemoteOperation::Pull,
+        proto::GitRemoteOperationKind::Push => RemoteOperation::Push,
+    }
+}
+
+fn paths_to_proto(paths: &[Arc<Path>]) -> Vec<String> {
+    paths
+        .iter()
+        .map(|path| path.to_string_lossy().to_string())
+        .collect()
+}
+
+fn paths_from_proto(paths: Vec<String>) -> Vec<Arc<Path>> {
+    paths
+        .into_iter()
+        .map(|path| Arc::from(Path::new(&path)))
+        .collect()
 }
 
 #[derive(Clone, Debug)]
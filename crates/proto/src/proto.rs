@@ -374,6 +374,37 @@ messages!(
     (SyncExtensionsResponse, Background),
     (InstallExtension, Background),
     (RegisterBufferWithLanguageServers, Background),
+    (GitStageStatuses, Background),
+    (GitStageStatusesResponse, Background),
+    (StageGitPaths, Background),
+    (UnstageGitPaths, Background),
+    (DiscardGitPaths, Background),
+    (SetGitIndexText, Background),
+    (UnstageGitHunks, Background),
+    (CommitGitChanges, Background),
+    (PrepareCallHierarchy, Background),
+    (PrepareCallHierarchyResponse, Background),
+    (GetIncomingCalls, Background),
+    (GetIncomingCallsResponse, Background),
+    (GetOutgoingCalls, Background),
+    (GetOutgoingCallsResponse, Background),
+    (GetSemanticTokens, Background),
+    (GetSemanticTokensResponse, Background),
+    (RefreshSemanticTokens, Foreground),
+    (GetCodeLens, Background),
+    (GetCodeLensResponse, Background),
+    (RefreshCodeLens, Foreground),
+    (GitLog, Background),
+    (GitLogResponse, Background),
+    (GitCommitDiff, Background),
+    (GitCommitDiffResponse, Background),
+    (GitConflictStages, Background),
+    (GitConflictStagesResponse, Background),
+    (PullWorkspaceDiagnostics, Background),
+    (GitRemoteOperation, Background),
+    (GitRemoteOperationProgress, Background),
+    (GitAskPass, Background),
+    (GitAskPassResponse, Background),
 );
 
 request_messages!(
@@ -501,6 +532,26 @@ request_messages!(
     (SyncExtensions, SyncExtensionsResponse),
     (InstallExtension, Ack),
     (RegisterBufferWithLanguageServers, Ack),
+    (GitStageStatuses, GitStageStatusesResponse),
+    (StageGitPaths, Ack),
+    (UnstageGitPaths, Ack),
+    (DiscardGitPaths, Ack),
+    (SetGitIndexText, Ack),
+    (UnstageGitHunks, Ack),
+    (CommitGitChanges, Ack),
+    (PrepareCallHierarchy, PrepareCallHierarchyResponse),
+    (GetIncomingCalls, GetIncomingCallsResponse),
+    (GetOutgoingCalls, GetOutgoingCallsResponse),
+    (GetSemanticTokens, GetSemanticTokensResponse),
+    (RefreshSemanticTokens, Ack),
+    (GetCodeLens, GetCodeLensResponse),
+    (RefreshCodeLens, Ack),
+    (GitLog, GitLogResponse),
+    (GitCommitDiff, GitCommitDiffResponse),
+    (GitConflictStages, GitConflictStagesResponse),
+    (PullWorkspaceDiagnostics, Ack),
+    (GitRemoteOperation, Ack),
+    (GitAskPass, GitAskPassResponse),
 );
 
 entity_messages!(
@@ -587,6 +638,27 @@ entity_messages!(
     GetPathMetadata,
     CancelLanguageServerWork,
     RegisterBufferWithLanguageServers,
+    GitStageStatuses,
+    StageGitPaths,
+    UnstageGitPaths,
+    DiscardGitPaths,
+    SetGitIndexText,
+    UnstageGitHunks,
+    CommitGitChanges,
+    PrepareCallHierarchy,
+    GetIncomingCalls,
+    GetOutgoingCalls,
+    GetSemanticTokens,
+    RefreshSemanticTokens,
+    GetCodeLens,
+    RefreshCodeLens,
+    GitLog,
+    GitCommitDiff,
+    GitConflictStages,
+    PullWorkspaceDiagnostics,
+    GitRemoteOperation,
+    GitRemoteOperationProgress,
+    GitAskPass,
 );
 
 entity_messages!(
@@ -3,6 +3,7 @@ use client::{Client, UserStore};
 use clock::FakeSystemClock;
 use extension::ExtensionHostProxy;
 use fs::{FakeFs, Fs};
+use git::repository::{RemoteOperation, UpstreamTracking};
 use gpui::{Context, Model, SemanticVersion, TestAppContext};
 use http_client::{BlockedHttpClient, FakeHttpClient};
 use language::{
@@ -20,7 +21,9 @@ use serde_json::json;
 use settings::{initial_server_settings_content, Settings, SettingsLocation, SettingsStore};
 use smol::stream::StreamExt;
 use std::{
+    cell::RefCell,
     path::{Path, PathBuf},
+    rc::Rc,
     sync::Arc,
 };
 
@@ -1226,6 +1229,100 @@ async fn test_remote_git_branches(cx: &mut TestAppContext, server_cx: &mut TestA
     assert_eq!(server_branch.as_ref(), "totally-new-branch");
 }
 
+#[gpui::test]
+async fn test_remote_git_remote_operations(
+    cx: &mut TestAppContext,
+    server_cx: &mut TestAppContext,
+) {
+    let fs = FakeFs::new(server_cx.executor());
+    fs.insert_tree(
+        "/code",
+        json!({
+            "project1": {
+                ".git": {},
+                "README.md": "# project 1",
+            },
+        }),
+    )
+    .await;
+
+    let (project, _headless_project) = init_test(&fs, cx, server_cx).await;
+    let dot_git = Path::new("/code/project1/.git");
+    fs.insert_branches(dot_git, &["main"]);
+    fs.with_git_state(dot_git, false, |state| {
+        state.upstreams.insert(
+            "main".to_string(),
+            UpstreamTracking {
+                ahead: 2,
+                behind: 1,
+            },
+        );
+        state.remote_password = Some("hunter2".to_string());
+    });
+
+    let (worktree, _) = project
+        .update(cx, |project, cx| {
+            project.find_or_create_worktree("/code/project1", true, cx)
+        })
+        .await
+        .unwrap();
+    let worktree_id = cx.update(|cx| worktree.read(cx).id());
+    cx.run_until_parked();
+
+    let branches = project
+        .update(cx, |project, cx| {
+            project.branches(ProjectPath::root_path(worktree_id), cx)
+        })
+        .await
+        .unwrap();
+    assert_eq!(
+        branches[0].upstream,
+        Some(UpstreamTracking {
+            ahead: 2,
+            behind: 1
+        })
+    );
+
+    // Answer the server's credential prompt from the client.
+    let prompts = Rc::new(RefCell::new(Vec::new()));
+    cx.update(|cx| {
+        let prompts = prompts.clone();
+        cx.subscribe(&project, move |_, event, _| {
+            if let project::Event::GitAskPass(request) = event {
+                prompts.borrow_mut().push(request.prompt.clone());
+                request.respond(Some("hunter2".to_string()));
+            }
+        })
+        .detach();
+    });
+
+    project
+        .update(cx, |project, cx| {
+            project.git_remote_operation(worktree_id, RemoteOperation::Push, cx)
+        })
+        .await
+        .unwrap();
+    cx.run_until_parked();
+
+    assert_eq!(prompts.borrow().as_slice(), ["Password: "]);
+    cx.update(|cx| assert_eq!(project.read(cx).git_remote_operations(cx).count(), 0));
+    let mut remote_operations = Vec::new();
+    fs.with_git_state(dot_git, false, |state| {
+        remote_operations = state.remote_operations.clone();
+    });
+    assert_eq!(remote_operations, [RemoteOperation::Push]);
+
+    // A wrong password fails the operation.
+    fs.with_git_state(dot_git, false, |state| {
+        state.remote_password = Some("something else".to_string());
+    });
+    let fetch = project.update(cx, |project, cx| {
+        project.git_remote_operation(worktree_id, RemoteOperation::Fetch, cx)
+    });
+    assert!(fetch.await.is_err());
+    assert_eq!(prompts.borrow().len(), 2);
+}
+
 pub async fn init_test(
     server_fs: &Arc<FakeFs>,
     cx: &mut TestAppContext,
@@ -6,7 +6,7 @@ use futures::{
     future::Shared,
     stream,
 };
-use gpui::{AppContext, Model, Task, WindowContext};
+use gpui::{AppContext, Model, Task, ViewContext, WindowContext};
 use language::LanguageName;
 pub use native_kernel::*;
 
@@ -141,6 +141,13 @@ pub fn python_env_kernel_specifications(
     }
 }
 
+/// A view that owns a kernel and receives the messages the kernel sends back,
+/// such as an editor's REPL [`crate::Session`] or a notebook.
+pub trait KernelSession: Sized {
+    fn route(&mut self, message: &JupyterMessage, cx: &mut ViewContext<Self>);
+    fn kernel_errored(&mut self, error_message: String, cx: &mut ViewContext<Self>);
+}
+
 pub trait RunningKernel: Send + Debug {
     fn request_tx(&self) -> mpsc::Sender<JupyterMessage>;
     fn working_directory(&self) -> &PathBuf;
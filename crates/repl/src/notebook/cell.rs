@@ -3,10 +3,13 @@ use std::sync::Arc;
 
 use editor::{Editor, EditorMode, MultiBuffer};
 use futures::future::Shared;
-use gpui::{prelude::*, AppContext, Hsla, Task, TextStyleRefinement, View};
+use gpui::{
+    prelude::*, AnyElement, AppContext, EventEmitter, Hsla, Task, TextStyleRefinement, View,
+};
 use language::{Buffer, Language, LanguageRegistry};
 use markdown_preview::{markdown_parser::parse_markdown, markdown_renderer::render_markdown_block};
 use nbformat::v4::{CellId, CellMetadata, CellType};
+use runtimelib::{ExecuteInput, JupyterMessageContent, MimeBundle};
 use settings::Settings as _;
 use theme::ThemeSettings;
 use ui::{prelude::*, IconButtonShape};
@@ -14,7 +17,7 @@ use util::ResultExt;
 
 use crate::{
     notebook::{CODE_BLOCK_INSET, GUTTER_WIDTH},
-    outputs::{plain::TerminalOutput, user_error::ErrorView, Output},
+    outputs::{plain::TerminalOutput, user_error::ErrorView, ExecutionStatus, Output},
 };
 
 #[derive(Copy, Clone, PartialEq, PartialOrd)]
@@ -114,7 +117,7 @@ impl Cell {
                 id,
                 metadata,
                 source,
-                ..
+                attachments,
             } => {
                 let source = source.join("");
 
@@ -144,6 +147,7 @@ impl Cell {
                         id: id.clone(),
                         metadata: metadata.clone(),
                         source: source.clone(),
+                        attachments: attachments.clone(),
                         parsed_markdown: None,
                         selected: false,
                         cell_position: None,
@@ -183,7 +187,6 @@ impl Cell {
                         ..Default::default()
                     };
 
-                    editor.set_text(text, cx);
                     editor.set_show_gutter(false, cx);
                     editor.set_text_style_refinement(refinement);
 
@@ -207,6 +210,9 @@ impl Cell {
                     source: source.join(""),
                     editor: editor_view,
                     outputs: convert_outputs(outputs, cx),
+                    notebook_outputs: outputs.clone(),
+                    execution_status: ExecutionStatus::Unknown,
+                    clear_on_next_output: false,
                     selected: false,
                     language_task,
                     cell_position: None,
@@ -225,6 +231,72 @@ impl Cell {
             })),
         }
     }
+
+    /// Creates an empty cell of the given type, with a fresh id.
+    pub fn new_notebook_cell(cell_type: CellType) -> nbformat::v4::Cell {
+        let id = uuid::Uuid::new_v4().to_string();
+        let cell = match cell_type {
+            CellType::Code => serde_json::json!({
+                "cell_type": "code",
+                "id": id,
+                "metadata": {},
+                "execution_count": null,
+                "source": [],
+                "outputs": [],
+            }),
+            CellType::Markdown => serde_json::json!({
+                "cell_type": "markdown",
+                "id": id,
+                "metadata": {},
+                "source": [],
+            }),
+            CellType::Raw => serde_json::json!({
+                "cell_type": "raw",
+                "id": id,
+                "metadata": {},
+                "source": [],
+            }),
+        };
+        serde_json::from_value(cell).expect("empty notebook cells are valid")
+    }
+
+    /// Converts the cell back to its nbformat representation, with the current source and outputs.
+    pub fn to_notebook_cell(&self, cx: &AppContext) -> nbformat::v4::Cell {
+        match self {
+            Cell::Code(cell) => {
+                let cell = cell.read(cx);
+                nbformat::v4::Cell::Code {
+                    id: cell.id.clone(),
+                    metadata: cell.metadata.clone(),
+                    execution_count: cell.execution_count,
+                    source: source_lines(&cell.editor.read(cx).text(cx)),
+                    outputs: cell.notebook_outputs.clone(),
+                }
+            }
+            Cell::Markdown(cell) => {
+                let cell = cell.read(cx);
+                nbformat::v4::Cell::Markdown {
+                    id: cell.id.clone(),
+                    metadata: cell.metadata.clone(),
+                    source: source_lines(&cell.source),
+                    attachments: cell.attachments.clone(),
+                }
+            }
+            Cell::Raw(cell) => {
+                let cell = cell.read(cx);
+                nbformat::v4::Cell::Raw {
+                    id: cell.id.clone(),
+                    metadata: cell.metadata.clone(),
+                    source: source_lines(&cell.source),
+                }
+            }
+        }
+    }
+}
+
+/// Splits a cell source into lines the way Jupyter stores them: every line but the last keeps its newline.
+pub(crate) fn source_lines(source: &str) -> Vec<String> {
+    source.split_inclusive('\n').map(str::to_string).collect()
 }
 
 pub trait RenderableCell: Render {
@@ -321,6 +393,7 @@ pub struct MarkdownCell {
     id: CellId,
     metadata: CellMetadata,
     source: String,
+    attachments: Option<serde_json::Value>,
     parsed_markdown: Option<markdown_preview::markdown_elements::ParsedMarkdown>,
     markdown_parsing_task: Task<()>,
     selected: bool,
@@ -419,21 +492,215 @@ pub struct CodeCell {
     source: String,
     editor: View<editor::Editor>,
     outputs: Vec<Output>,
+    /// The outputs as stored in the notebook file, written back on save.
+    notebook_outputs: Vec<nbformat::v4::Output>,
+    execution_status: ExecutionStatus,
+    /// The kernel asked to clear the outputs once the next one arrives.
+    clear_on_next_output: bool,
     selected: bool,
     cell_position: Option<CellPosition>,
     language_task: Task<()>,
 }
 
 impl CodeCell {
+    pub fn editor(&self) -> &View<Editor> {
+        &self.editor
+    }
+
     pub fn is_dirty(&self, cx: &AppContext) -> bool {
         self.editor.read(cx).buffer().read(cx).is_dirty(cx)
     }
+
+    /// Marks the cell's source as saved, so that it is no longer reported as dirty.
+    pub fn did_save(&self, cx: &mut AppContext) {
+        if let Some(buffer) = self.editor.read(cx).buffer().read(cx).as_singleton() {
+            buffer.update(cx, |buffer, cx| {
+                let version = buffer.version();
+                buffer.did_save(version, None, cx);
+            });
+        }
+    }
+
     pub fn has_outputs(&self) -> bool {
         !self.outputs.is_empty()
     }
 
     pub fn clear_outputs(&mut self) {
         self.outputs.clear();
+        self.notebook_outputs.clear();
+        self.clear_on_next_output = false;
+    }
+
+    /// Clears the outputs of the previous run before the cell's source gets sent to the kernel.
+    pub fn start_execution(&mut self, status: ExecutionStatus, cx: &mut ViewContext<Self>) {
+        self.clear_outputs();
+        self.execution_count = None;
+        self.execution_status = status;
+        cx.notify();
+    }
+
+    pub fn set_execution_status(&mut self, status: ExecutionStatus, cx: &mut ViewContext<Self>) {
+        self.execution_status = status;
+        cx.notify();
+    }
+
+    /// Applies a message the kernel sent while executing this cell to both the rendered outputs
+    /// and the ones saved to the notebook. Returns whether the cell as saved to disk changed.
+    pub fn handle_message(
+        &mut self,
+        content: &JupyterMessageContent,
+        cx: &mut ViewContext<Self>,
+    ) -> bool {
+        let changed = match content {
+            JupyterMessageContent::ExecuteInput(input) => {
+                self.execution_count = execution_count(input);
+                true
+            }
+            JupyterMessageContent::Status(status) => {
+                self.execution_status = match status.execution_state {
+                    runtimelib::ExecutionState::Busy => ExecutionStatus::Executing,
+                    runtimelib::ExecutionState::Idle => ExecutionStatus::Finished,
+                };
+                false
+            }
+            JupyterMessageContent::ClearOutput(options) => {
+                if options.wait {
+                    self.clear_on_next_output = true;
+                } else {
+                    self.clear_outputs();
+                }
+                !options.wait
+            }
+            JupyterMessageContent::ExecuteResult(_)
+            | JupyterMessageContent::DisplayData(_)
+            | JupyterMessageContent::StreamContent(_)
+            | JupyterMessageContent::ErrorOutput(_) => {
+                if self.clear_on_next_output {
+                    self.clear_outputs();
+                }
+                self.push_output(content, cx);
+                true
+            }
+            _ => false,
+        };
+        cx.notify();
+        changed
+    }
+
+    fn push_output(&mut self, content: &JupyterMessageContent, cx: &mut ViewContext<Self>) {
+        if let Some(output) = notebook_output(content) {
+            self.push_notebook_output(output);
+        }
+
+        let output = match content {
+            JupyterMessageContent::ExecuteResult(result) => Output::new(
+                &result.data,
+                result.transient.as_ref().and_then(|t| t.display_id.clone()),
+                cx,
+            ),
+            JupyterMessageContent::DisplayData(result) => Output::new(
+                &result.data,
+                result.transient.as_ref().and_then(|t| t.display_id.clone()),
+                cx,
+            ),
+            JupyterMessageContent::StreamContent(result) => {
+                if let Some(Output::Stream { content }) = self.outputs.last() {
+                    content.update(cx, |terminal, cx| {
+                        terminal.append_text(&result.text, cx);
+                        cx.notify();
+                    });
+                    return;
+                }
+                Output::Stream {
+                    content: cx.new_view(|cx| TerminalOutput::from(&result.text, cx)),
+                }
+            }
+            JupyterMessageContent::ErrorOutput(result) => Output::ErrorOutput(ErrorView {
+                ename: result.ename.clone(),
+                evalue: result.evalue.clone(),
+                traceback: cx.new_view(|cx| TerminalOutput::from(&result.traceback.join("\n"), cx)),
+            }),
+            _ => return,
+        };
+        self.outputs.push(output);
+    }
+
+    fn push_notebook_output(&mut self, output: nbformat::v4::Output) {
+        // Consecutive chunks of the same stream are stored as one output, like Jupyter does.
+        if let nbformat::v4::Output::Stream {
+            name: new_name,
+            text: new_text,
+        } = &output
+        {
+            if let Some(nbformat::v4::Output::Stream { name, text }) =
+                self.notebook_outputs.last_mut()
+            {
+                if name == new_name {
+                    text.0.push_str(&new_text.0);
+                    return;
+                }
+            }
+        }
+        self.notebook_outputs.push(output);
+    }
+
+    /// Replaces the rendered outputs that were displayed with the given display id.
+    pub fn update_display_data(
+        &mut self,
+        data: &MimeBundle,
+        display_id: &str,
+        cx: &mut ViewContext<Self>,
+    ) {
+        let mut updated = false;
+        for output in &mut self.outputs {
+            if output.display_id().as_deref() == Some(display_id) {
+                *output = Output::new(data, Some(display_id.to_owned()), cx);
+                updated = true;
+            }
+        }
+        if updated {
+            cx.notify();
+        }
+    }
+
+    /// Shows the execution count, and the execution status while the cell is queued or running.
+    fn render_execution_info(&self) -> Option<AnyElement> {
+        let status = match &self.execution_status {
+            ExecutionStatus::Queued => Some(("Queued...".to_string(), Color::Muted)),
+            ExecutionStatus::ConnectingToKernel => {
+                Some(("Connecting to kernel...".to_string(), Color::Muted))
+            }
+            ExecutionStatus::Executing => Some(("Executing...".to_string(), Color::Muted)),
+            ExecutionStatus::Restarting => Some(("Kernel restarting...".to_string(), Color::Muted)),
+            ExecutionStatus::KernelErrored(error) => {
+                Some((format!("Kernel error: {}", error), Color::Error))
+            }
+            ExecutionStatus::Unknown
+            | ExecutionStatus::Finished
+            | ExecutionStatus::ShuttingDown
+            | ExecutionStatus::Shutdown => None,
+        };
+        let execution_count = self.execution_count();
+        if status.is_none() && execution_count.is_none() {
+            return None;
+        }
+
+        Some(
+            v_flex()
+                .flex_none()
+                .pr_2()
+                .when_some(execution_count, |this, count| {
+                    this.child(
+                        Label::new(format!("[{}]", count))
+                            .size(LabelSize::XSmall)
+                            .color(Color::Muted),
+                    )
+                })
+                .when_some(status, |this, (label, color)| {
+                    this.child(Label::new(label).size(LabelSize::XSmall).color(color))
+                })
+                .into_any_element(),
+        )
     }
 
     fn output_control(&self) -> Option<CellControlType> {
@@ -510,10 +777,9 @@ impl RenderableCell for CodeCell {
             CellControl::new("rerun-cell", CellControlType::RerunCell)
         } else {
             CellControl::new("run-cell", CellControlType::RunCell)
-                .on_click(cx.listener(move |this, _, cx| this.run(cx)))
         };
 
-        Some(cell_control)
+        Some(cell_control.on_click(cx.listener(move |this, _, cx| this.run(cx))))
     }
 
     fn selected(&self) -> bool {
@@ -537,7 +803,7 @@ impl RenderableCell for CodeCell {
 
 impl RunnableCell for CodeCell {
     fn run(&mut self, cx: &mut ViewContext<Self>) {
-        println!("Running code cell: {}", self.id);
+        cx.emit(CodeCellEvent::Run);
     }
 
     fn execution_count(&self) -> Option<i32> {
@@ -551,6 +817,39 @@ impl RunnableCell for CodeCell {
     }
 }
 
+pub enum CodeCellEvent {
+    /// The cell's run control was clicked, the notebook owning the kernel executes it.
+    Run,
+}
+
+impl EventEmitter<CodeCellEvent> for CodeCell {}
+
+/// Reads the execution count the kernel assigned to a cell from its `execute_input` message.
+fn execution_count(input: &ExecuteInput) -> Option<i32> {
+    let input = serde_json::to_value(input).ok()?;
+    input.get("execution_count")?.as_i64()?.try_into().ok()
+}
+
+/// Converts an output message of the kernel to the output stored in the notebook file.
+/// The message contents use the same fields as nbformat, apart from the `output_type` tag.
+fn notebook_output(content: &JupyterMessageContent) -> Option<nbformat::v4::Output> {
+    let (output_type, content) = match content {
+        JupyterMessageContent::ExecuteResult(result) => {
+            ("execute_result", serde_json::to_value(result))
+        }
+        JupyterMessageContent::DisplayData(data) => ("display_data", serde_json::to_value(data)),
+        JupyterMessageContent::StreamContent(stream) => ("stream", serde_json::to_value(stream)),
+        JupyterMessageContent::ErrorOutput(error) => ("error", serde_json::to_value(error)),
+        _ => return None,
+    };
+    let mut content = content.log_err()?;
+    let fields = content.as_object_mut()?;
+    // Transient data, such as display ids, is not meant to be persisted.
+    fields.remove("transient");
+    fields.insert("output_type".to_string(), output_type.into());
+    serde_json::from_value(content).log_err()
+}
+
 impl Render for CodeCell {
     fn render(&mut self, cx: &mut ViewContext<Self>) -> impl IntoElement {
         v_flex()
@@ -605,6 +904,7 @@ impl Render for CodeCell {
                                 .border_1()
                                 // .border_color(cx.theme().colors().border)
                                 // .bg(cx.theme().colors().editor_background)
+                                .children(self.render_execution_info())
                                 .child(div().w_full().children(self.outputs.iter().map(
                                     |output| {
                                         let content = match output {
@@ -1,40 +1,60 @@
 #![allow(unused, dead_code)]
 use std::future::Future;
+use std::time::Duration;
 use std::{path::PathBuf, sync::Arc};
 
-use anyhow::{Context as _, Result};
+use anyhow::{anyhow, Context as _, Result};
 use client::proto::ViewId;
 use collections::HashMap;
+use editor::EditorEvent;
 use feature_flags::{FeatureFlagAppExt as _, NotebookFeatureFlag};
+use fs::{Fs, MTime};
 use futures::future::Shared;
-use futures::FutureExt;
+use futures::{FutureExt, StreamExt as _};
 use gpui::{
     actions, list, prelude::*, AnyElement, AppContext, EventEmitter, FocusHandle, FocusableView,
-    ListScrollEvent, ListState, Model, Point, Task, View,
+    ListScrollEvent, ListState, Model, ModelContext, Point, Subscription, Task, View,
 };
 use language::{Language, LanguageRegistry};
 use project::{Project, ProjectEntryId, ProjectPath};
+use runtimelib::{
+    ExecuteRequest, InterruptRequest, JupyterMessage, JupyterMessageContent, ShutdownRequest,
+};
+use serde::Serialize as _;
 use ui::{prelude::*, Tooltip};
+use util::ResultExt as _;
 use workspace::item::{ItemEvent, TabContentParams};
 use workspace::searchable::SearchableItemHandle;
 use workspace::{Item, ItemHandle, ProjectItem, ToolbarItemLocation};
 use workspace::{ToolbarItemEvent, ToolbarItemView};
 
-use super::{Cell, CellPosition, RenderableCell};
+use super::{Cell, CellPosition, CodeCellEvent, RenderableCell};
+use crate::kernels::{
+    Kernel, KernelSession, KernelSpecification, NativeRunningKernel, RemoteRunningKernel,
+};
+use crate::outputs::ExecutionStatus;
+use crate::repl_store::ReplStore;
 
 use nbformat::v4::CellId;
+use nbformat::v4::CellType;
 use nbformat::v4::Metadata as NotebookMetadata;
 
 actions!(
     notebook,
     [
         OpenNotebook,
+        RunCell,
         RunAll,
+        RunCellsAbove,
+        RunCellsBelow,
+        InterruptKernel,
+        RestartKernel,
         ClearOutputs,
         MoveCellUp,
         MoveCellDown,
         AddMarkdownBlock,
         AddCodeBlock,
+        DeleteCell,
     ]
 );
 
@@ -46,6 +66,8 @@ pub(crate) const GUTTER_WIDTH: f32 = 19.0;
 pub(crate) const CODE_BLOCK_INSET: f32 = MEDIUM_SPACING_SIZE;
 pub(crate) const CONTROL_SIZE: f32 = 20.0;
 
+const FILE_WATCH_LATENCY: Duration = Duration::from_millis(100);
+
 pub fn init(cx: &mut AppContext) {
     if cx.has_flag::<NotebookFeatureFlag>() || std::env::var("LOCAL_NOTEBOOK_DEV").is_ok() {
         workspace::register_project_item::<NotebookEditor>(cx);
@@ -77,6 +99,15 @@ pub struct NotebookEditor {
     selected_cell_index: usize,
     cell_order: Vec<CellId>,
     cell_map: HashMap<CellId, Cell>,
+    notebook_language: Shared<Task<Option<Arc<Language>>>>,
+    // Cells were added, removed, moved or had their outputs cleared since the last save.
+    has_structural_changes: bool,
+    // The file changed on disk while there were unsaved changes.
+    has_conflict: bool,
+    cell_subscriptions: HashMap<CellId, Vec<Subscription>>,
+    kernel: Kernel,
+    executions: Executions,
+    _subscriptions: Vec<Subscription>,
 }
 
 impl NotebookEditor {
@@ -93,49 +124,38 @@ impl NotebookEditor {
         let notebook_language = notebook_item.read(cx).notebook_language();
         let notebook_language = cx.spawn(|_, _| notebook_language).shared();
 
-        let mut cell_order = vec![]; // Vec<CellId>
-        let mut cell_map = HashMap::default(); // HashMap<CellId, Cell>
-
-        for (index, cell) in notebook_item
-            .read(cx)
-            .notebook
-            .clone()
-            .cells
-            .iter()
-            .enumerate()
-        {
-            let cell_id = cell.id();
-            cell_order.push(cell_id.clone());
-            cell_map.insert(
-                cell_id.clone(),
-                Cell::load(cell, &languages, notebook_language.clone(), cx),
-            );
-        }
-
         let view = cx.view().downgrade();
-        let cell_count = cell_order.len();
 
         let this = cx.view();
-        let cell_list = ListState::new(
-            cell_count,
-            gpui::ListAlignment::Top,
-            px(1000.),
-            move |ix, cx| {
-                view.upgrade()
-                    .and_then(|notebook_handle| {
-                        notebook_handle.update(cx, |notebook, cx| {
-                            notebook
-                                .cell_order
-                                .get(ix)
-                                .and_then(|cell_id| notebook.cell_map.get(cell_id))
-                                .map(|cell| notebook.render_cell(ix, cell, cx).into_any_element())
-                        })
+        let cell_list = ListState::new(0, gpui::ListAlignment::Top, px(1000.), move |ix, cx| {
+            view.upgrade()
+                .and_then(|notebook_handle| {
+                    notebook_handle.update(cx, |notebook, cx| {
+                        notebook
+                            .cell_order
+                            .get(ix)
+                            .and_then(|cell_id| notebook.cell_map.get(cell_id))
+                            .map(|cell| notebook.render_cell(ix, cell, cx).into_any_element())
                     })
-                    .unwrap_or_else(|| div().into_any())
+                })
+                .unwrap_or_else(|| div().into_any())
+        });
+
+        let subscriptions = vec![cx.subscribe(
+            &notebook_item,
+            |this, _, event: &NotebookItemEvent, cx| match event {
+                NotebookItemEvent::ChangedOnDisk => {
+                    if this.is_dirty(cx) {
+                        this.has_conflict = true;
+                        cx.emit(());
+                    } else {
+                        this.reload(this.project.clone(), cx).detach_and_log_err(cx);
+                    }
+                }
             },
-        );
+        )];
 
-        Self {
+        let mut this = Self {
             project,
             languages: languages.clone(),
             focus_handle,
@@ -143,9 +163,119 @@ impl NotebookEditor {
             remote_id: None,
             cell_list,
             selected_cell_index: 0,
-            cell_order: cell_order.clone(),
-            cell_map: cell_map.clone(),
+            cell_order: Vec::new(),
+            cell_map: HashMap::default(),
+            notebook_language,
+            has_structural_changes: false,
+            has_conflict: false,
+            cell_subscriptions: HashMap::default(),
+            kernel: Kernel::Shutdown,
+            executions: Executions::default(),
+            _subscriptions: subscriptions,
+        };
+        this.load_cells(cx);
+        this
+    }
+
+    /// Replaces all cells with the ones of the notebook item, e.g. after it was reloaded from disk.
+    fn load_cells(&mut self, cx: &mut ViewContext<Self>) {
+        let cells = self.notebook_item.read(cx).notebook.cells.clone();
+        self.cell_order.clear();
+        self.cell_map.clear();
+        self.cell_subscriptions.clear();
+        for cell in &cells {
+            self.insert_cell(self.cell_order.len(), cell, cx);
         }
+        self.cell_list.reset(self.cell_order.len());
+        self.selected_cell_index = self
+            .selected_cell_index
+            .min(self.cell_order.len().saturating_sub(1));
+        self.has_structural_changes = false;
+        self.has_conflict = false;
+        cx.emit(());
+        cx.notify();
+    }
+
+    fn insert_cell(&mut self, index: usize, cell: &nbformat::v4::Cell, cx: &mut ViewContext<Self>) {
+        let cell_id = cell.id().clone();
+        let cell = Cell::load(cell, &self.languages, self.notebook_language.clone(), cx);
+        if let Cell::Code(code_cell) = &cell {
+            let editor = code_cell.read(cx).editor().clone();
+            let run_cell_id = cell_id.clone();
+            self.cell_subscriptions.insert(
+                cell_id.clone(),
+                vec![
+                    cx.subscribe(&editor, |_, _, event: &EditorEvent, cx| {
+                        if let EditorEvent::DirtyChanged = event {
+                            cx.emit(());
+                        }
+                    }),
+                    cx.subscribe(
+                        code_cell,
+                        move |this, _, event: &CodeCellEvent, cx| match event {
+                            CodeCellEvent::Run => {
+                                if let Some(index) =
+                                    this.cell_order.iter().position(|id| *id == run_cell_id)
+                                {
+                                    this.set_selected_index(index, false, cx);
+                                }
+                                this.execute_cells(vec![run_cell_id.clone()], cx);
+                            }
+                        },
+                    ),
+                ],
+            );
+        }
+        self.cell_order.insert(index, cell_id.clone());
+        self.cell_map.insert(cell_id, cell);
+    }
+
+    fn mark_structural_change(&mut self, cx: &mut ViewContext<Self>) {
+        self.has_structural_changes = true;
+        cx.emit(());
+        cx.notify();
+    }
+
+    /// Builds the notebook to save: the metadata of the loaded notebook, with the current cells.
+    fn to_notebook(&self, cx: &AppContext) -> nbformat::v4::Notebook {
+        let mut notebook = self.notebook_item.read(cx).notebook.clone();
+        notebook.cells = self
+            .cell_order
+            .iter()
+            .filter_map(|cell_id| self.cell_map.get(cell_id))
+            .map(|cell| cell.to_notebook_cell(cx))
+            .collect();
+        notebook
+    }
+
+    fn save_to_path(
+        &mut self,
+        project: Model<Project>,
+        abs_path: PathBuf,
+        cx: &mut ViewContext<Self>,
+    ) -> Task<Result<()>> {
+        let fs = project.read(cx).fs().clone();
+        let notebook = self.to_notebook(cx);
+        cx.spawn(|this, mut cx| async move {
+            let content = serialize_notebook(&notebook)?;
+            fs.atomic_write(abs_path.clone(), content).await?;
+            let mtime = fs.metadata(&abs_path).await?.map(|metadata| metadata.mtime);
+            this.update(&mut cx, |this, cx| {
+                this.notebook_item.update(cx, |item, _| {
+                    item.notebook = notebook;
+                    item.mtime = mtime;
+                });
+                for cell in this.cell_map.values() {
+                    if let Cell::Code(code_cell) = cell {
+                        code_cell.update(cx, |code_cell, cx| code_cell.did_save(cx));
+                    }
+                }
+                this.has_structural_changes = false;
+                this.has_conflict = false;
+                cx.emit(());
+                cx.notify();
+            })
+        })
     }
 
     fn has_outputs(&self, cx: &ViewContext<Self>) -> bool {
@@ -159,6 +289,9 @@ impl NotebookEditor {
     }
 
     fn clear_outputs(&mut self, cx: &mut ViewContext<Self>) {
+        if !self.has_outputs(cx) {
+            return;
+        }
         for cell in self.cell_map.values() {
             if let Cell::Code(code_cell) = cell {
                 code_cell.update(cx, |cell, _cx| {
@@ -166,10 +299,232 @@ impl NotebookEditor {
                 });
             }
         }
+        self.mark_structural_change(cx);
+    }
+
+    fn run_cell(&mut self, cx: &mut ViewContext<Self>) {
+        if let Some(cell_id) = self.cell_order.get(self.selected_cell_index).cloned() {
+            self.execute_cells(vec![cell_id], cx);
+        }
     }
 
     fn run_cells(&mut self, cx: &mut ViewContext<Self>) {
-        println!("Cells would all run here, if that was implemented!");
+        self.execute_cells(self.cell_order.clone(), cx);
+    }
+
+    /// Runs the cells above the selected one, without the selected cell.
+    fn run_cells_above(&mut self, cx: &mut ViewContext<Self>) {
+        let end = self.selected_cell_index.min(self.cell_order.len());
+        self.execute_cells(self.cell_order[..end].to_vec(), cx);
+    }
+
+    /// Runs the selected cell and the cells below it.
+    fn run_cells_below(&mut self, cx: &mut ViewContext<Self>) {
+        let start = self.selected_cell_index.min(self.cell_order.len());
+        self.execute_cells(self.cell_order[start..].to_vec(), cx);
+    }
+
+    /// Sends the source of the given code cells to the kernel in order, starting the kernel if needed.
+    /// Other cell types are skipped.
+    fn execute_cells(&mut self, cell_ids: Vec<CellId>, cx: &mut ViewContext<Self>) {
+        let cells = cell_ids
+            .into_iter()
+            .filter_map(|cell_id| match self.cell_map.get(&cell_id)? {
+                Cell::Code(code_cell) => Some((cell_id, code_cell.clone())),
+                Cell::Markdown(_) | Cell::Raw(_) => None,
+            })
+            .collect::<Vec<_>>();
+        if cells.is_empty() {
+            return;
+        }
+
+        if let Kernel::Shutdown | Kernel::ErroredLaunch(_) = self.kernel {
+            self.start_kernel(cx);
+        }
+
+        let status = match &self.kernel {
+            Kernel::Restarting => ExecutionStatus::Restarting,
+            Kernel::RunningKernel(_) => ExecutionStatus::Queued,
+            Kernel::StartingKernel(_) => ExecutionStatus::ConnectingToKernel,
+            Kernel::ErroredLaunch(error) => ExecutionStatus::KernelErrored(error.clone()),
+            Kernel::ShuttingDown => ExecutionStatus::ShuttingDown,
+            Kernel::Shutdown => ExecutionStatus::Shutdown,
+        };
+
+        let mut messages = Vec::with_capacity(cells.len());
+        for (cell_id, code_cell) in cells {
+            let code = code_cell.read(cx).editor().read(cx).text(cx);
+            let message: JupyterMessage = ExecuteRequest {
+                code,
+                ..ExecuteRequest::default()
+            }
+            .into();
+            self.executions.insert(&message, cell_id);
+            code_cell.update(cx, |code_cell, cx| {
+                code_cell.start_execution(status.clone(), cx)
+            });
+            messages.push(message);
+        }
+        self.mark_structural_change(cx);
+
+        match &self.kernel {
+            Kernel::RunningKernel(_) => {
+                for message in messages {
+                    self.send(message);
+                }
+            }
+            // Sent once the kernel starts, see `start_kernel`
+            Kernel::StartingKernel(_) | Kernel::Restarting => {
+                for message in messages {
+                    self.executions.queue(message);
+                }
+            }
+            Kernel::ErroredLaunch(_) | Kernel::ShuttingDown | Kernel::Shutdown => {
+                for message in messages {
+                    self.executions.remove(&message.header.msg_id);
+                }
+            }
+        }
+    }
+
+    fn send(&mut self, message: JupyterMessage) {
+        if let Kernel::RunningKernel(kernel) = &mut self.kernel {
+            kernel.request_tx().try_send(message).ok();
+        }
+    }
+
+    /// Picks the kernel named in the notebook metadata, falling back to the one selected for
+    /// the notebook's language.
+    fn kernel_specification(&self, cx: &AppContext) -> Option<KernelSpecification> {
+        let notebook_item = self.notebook_item.read(cx);
+        let worktree_id = notebook_item.project_path.worktree_id;
+        let store = ReplStore::global(cx).read(cx);
+
+        let kernel_name = notebook_item
+            .notebook
+            .metadata
+            .kernelspec
+            .as_ref()
+            .map(|kernelspec| kernelspec.name.clone());
+        if let Some(kernel_name) = kernel_name {
+            let found_by_name = store
+                .kernel_specifications_for_worktree(worktree_id)
+                .find(|kernel_specification| kernel_specification.name().as_ref() == kernel_name)
+                .cloned();
+            if found_by_name.is_some() {
+                return found_by_name;
+            }
+        }
+
+        let language = self.notebook_language.clone().now_or_never().flatten();
+        store.active_kernelspec(worktree_id, language, cx)
+    }
+
+    fn start_kernel(&mut self, cx: &mut ViewContext<Self>) {
+        let Some(kernel_specification) = self.kernel_specification(cx) else {
+            let language_name = self
+                .notebook_item
+                .read(cx)
+                .language_name()
+                .unwrap_or_else(|| "this notebook".to_string());
+            self.kernel_errored(format!("No kernel found for {}", language_name), cx);
+            return;
+        };
+
+        let entity_id = cx.entity_id();
+        let working_directory = self
+            .notebook_item
+            .read(cx)
+            .path
+            .parent()
+            .map(|parent| parent.to_path_buf())
+            .unwrap_or_else(std::env::temp_dir);
+        let fs = self.project.read(cx).fs().clone();
+        let notebook_view = cx.view().clone();
+
+        let kernel = match kernel_specification {
+            KernelSpecification::Jupyter(kernel_specification)
+            | KernelSpecification::PythonEnv(kernel_specification) => NativeRunningKernel::new(
+                kernel_specification,
+                entity_id,
+                working_directory,
+                fs,
+                notebook_view,
+                cx,
+            ),
+            KernelSpecification::Remote(remote_kernel_specification) => RemoteRunningKernel::new(
+                remote_kernel_specification,
+                working_directory,
+                notebook_view,
+                cx,
+            ),
+        };
+
+        let pending_kernel = cx
+            .spawn(|this, mut cx| async move {
+                let kernel = kernel.await;
+                this.update(&mut cx, |this, cx| match kernel {
+                    Ok(kernel) => {
+                        this.kernel = Kernel::RunningKernel(kernel);
+                        for message in this.executions.take_unsent() {
+                            this.send(message);
+                        }
+                        cx.notify();
+                    }
+                    Err(err) => this.kernel_errored(err.to_string(), cx),
+                })
+                .ok();
+            })
+            .shared();
+
+        self.kernel = Kernel::StartingKernel(pending_kernel);
+        cx.notify();
+    }
+
+    fn interrupt_kernel(&mut self, cx: &mut ViewContext<Self>) {
+        if let Kernel::RunningKernel(_) = self.kernel {
+            self.send(InterruptRequest {}.into());
+        }
+    }
+
+    /// Restarts the kernel, keeping the outputs. Executions that did not finish are dropped, while
+    /// the ones requested during the restart run once the new kernel starts.
+    fn restart_kernel(&mut self, cx: &mut ViewContext<Self>) {
+        let kernel = std::mem::replace(&mut self.kernel, Kernel::Restarting);
+        self.finish_executions(ExecutionStatus::Unknown, cx);
+
+        match kernel {
+            Kernel::Restarting => {}
+            Kernel::RunningKernel(mut kernel) => {
+                let mut request_tx = kernel.request_tx().clone();
+                let forced = kernel.force_shutdown(cx);
+
+                cx.spawn(|this, mut cx| async move {
+                    let message: JupyterMessage = ShutdownRequest { restart: true }.into();
+                    request_tx.try_send(message).ok();
+
+                    // Wait for the kernel to shut down, then force kill it if it has not
+                    cx.background_executor().timer(Duration::from_secs(1)).await;
+                    forced.await.log_err();
+
+                    this.update(&mut cx, |this, cx| this.start_kernel(cx)).ok();
+                })
+                .detach();
+            }
+            _ => self.start_kernel(cx),
+        }
+        cx.notify();
+    }
+
+    /// Forgets about the pending executions, showing the given status on their cells.
+    fn finish_executions(&mut self, status: ExecutionStatus, cx: &mut ViewContext<Self>) {
+        for cell_id in self.executions.clear() {
+            if let Some(Cell::Code(code_cell)) = self.cell_map.get(&cell_id) {
+                code_cell.update(cx, |code_cell, cx| {
+                    code_cell.set_execution_status(status.clone(), cx)
+                });
+            }
+        }
     }
 
     fn open_notebook(&mut self, _: &OpenNotebook, _cx: &mut ViewContext<Self>) {
@@ -177,19 +532,61 @@ impl NotebookEditor {
     }
 
     fn move_cell_up(&mut self, cx: &mut ViewContext<Self>) {
-        println!("Move cell up triggered");
+        let index = self.selected_cell_index;
+        if index == 0 || index >= self.cell_order.len() {
+            return;
+        }
+        self.cell_order.swap(index - 1, index);
+        self.cell_list.splice(index - 1..index + 1, 2);
+        self.set_selected_index(index - 1, true, cx);
+        self.mark_structural_change(cx);
     }
 
     fn move_cell_down(&mut self, cx: &mut ViewContext<Self>) {
-        println!("Move cell down triggered");
+        let index = self.selected_cell_index;
+        if index + 1 >= self.cell_order.len() {
+            return;
+        }
+        self.cell_order.swap(index, index + 1);
+        self.cell_list.splice(index..index + 2, 2);
+        self.set_selected_index(index + 1, true, cx);
+        self.mark_structural_change(cx);
     }
 
     fn add_markdown_block(&mut self, cx: &mut ViewContext<Self>) {
-        println!("Add markdown block triggered");
+        self.add_cell(CellType::Markdown, cx);
     }
 
     fn add_code_block(&mut self, cx: &mut ViewContext<Self>) {
-        println!("Add code block triggered");
+        self.add_cell(CellType::Code, cx);
+    }
+
+    /// Inserts an empty cell below the selected one and selects it.
+    fn add_cell(&mut self, cell_type: CellType, cx: &mut ViewContext<Self>) {
+        let index = if self.cell_order.is_empty() {
+            0
+        } else {
+            self.selected_cell_index + 1
+        };
+        let cell = Cell::new_notebook_cell(cell_type);
+        self.insert_cell(index, &cell, cx);
+        self.cell_list.splice(index..index, 1);
+        self.set_selected_index(index, true, cx);
+        self.mark_structural_change(cx);
+    }
+
+    fn delete_cell(&mut self, cx: &mut ViewContext<Self>) {
+        let index = self.selected_cell_index;
+        if index >= self.cell_order.len() {
+            return;
+        }
+        let cell_id = self.cell_order.remove(index);
+        self.cell_map.remove(&cell_id);
+        self.cell_subscriptions.remove(&cell_id);
+        self.cell_list.splice(index..index + 1, 0);
+        let index = index.min(self.cell_order.len().saturating_sub(1));
+        self.set_selected_index(index, true, cx);
+        self.mark_structural_change(cx);
     }
 
     fn cell_count(&self) -> usize {
@@ -285,6 +682,7 @@ impl NotebookEditor {
 
     fn render_notebook_controls(&self, cx: &ViewContext<Self>) -> impl IntoElement {
         let has_outputs = self.has_outputs(cx);
+        let is_running = matches!(self.kernel, Kernel::RunningKernel(_));
 
         v_flex()
             .max_w(px(CONTROL_SIZE + 4.0))
@@ -308,6 +706,33 @@ impl NotebookEditor {
                                         cx.dispatch_action(Box::new(RunAll));
                                     }),
                             )
+                            .child(
+                                Self::render_notebook_control(
+                                    "interrupt-kernel",
+                                    IconName::Stop,
+                                    cx,
+                                )
+                                .disabled(!is_running)
+                                .tooltip(move |cx| {
+                                    Tooltip::for_action("Interrupt kernel", &InterruptKernel, cx)
+                                })
+                                .on_click(|_, cx| {
+                                    cx.dispatch_action(Box::new(InterruptKernel));
+                                }),
+                            )
+                            .child(
+                                Self::render_notebook_control(
+                                    "restart-kernel",
+                                    IconName::RotateCw,
+                                    cx,
+                                )
+                                .tooltip(move |cx| {
+                                    Tooltip::for_action("Restart kernel", &RestartKernel, cx)
+                                })
+                                .on_click(|_, cx| {
+                                    cx.dispatch_action(Box::new(RestartKernel));
+                                }),
+                            )
                             .child(
                                 Self::render_notebook_control(
                                     "clear-all-outputs",
@@ -445,11 +870,17 @@ impl Render for NotebookEditor {
             .track_focus(&self.focus_handle)
             .on_action(cx.listener(|this, &OpenNotebook, cx| this.open_notebook(&OpenNotebook, cx)))
             .on_action(cx.listener(|this, &ClearOutputs, cx| this.clear_outputs(cx)))
+            .on_action(cx.listener(|this, &RunCell, cx| this.run_cell(cx)))
             .on_action(cx.listener(|this, &RunAll, cx| this.run_cells(cx)))
+            .on_action(cx.listener(|this, &RunCellsAbove, cx| this.run_cells_above(cx)))
+            .on_action(cx.listener(|this, &RunCellsBelow, cx| this.run_cells_below(cx)))
+            .on_action(cx.listener(|this, &InterruptKernel, cx| this.interrupt_kernel(cx)))
+            .on_action(cx.listener(|this, &RestartKernel, cx| this.restart_kernel(cx)))
             .on_action(cx.listener(|this, &MoveCellUp, cx| this.move_cell_up(cx)))
             .on_action(cx.listener(|this, &MoveCellDown, cx| this.move_cell_down(cx)))
             .on_action(cx.listener(|this, &AddMarkdownBlock, cx| this.add_markdown_block(cx)))
             .on_action(cx.listener(|this, &AddCodeBlock, cx| this.add_code_block(cx)))
+            .on_action(cx.listener(|this, &DeleteCell, cx| this.delete_cell(cx)))
             .on_action(cx.listener(Self::select_next))
             .on_action(cx.listener(Self::select_previous))
             .on_action(cx.listener(Self::select_first))
@@ -484,12 +915,20 @@ pub struct NotebookItem {
     path: PathBuf,
     project_path: ProjectPath,
     languages: Arc<LanguageRegistry>,
-    // Raw notebook data
+    // Raw notebook data, as last loaded from or saved to disk
     notebook: nbformat::v4::Notebook,
     // Store our version of the notebook in memory (cell_order, cell_map)
-    id: ProjectEntryId,
+    id: Option<ProjectEntryId>,
+    mtime: Option<MTime>,
+    _watch_task: Task<()>,
 }
 
+pub enum NotebookItemEvent {
+    ChangedOnDisk,
+}
+
+impl EventEmitter<NotebookItemEvent> for NotebookItem {}
+
 impl project::ProjectItem for NotebookItem {
     fn try_open(
         project: &Model<Project>,
@@ -507,36 +946,25 @@ impl project::ProjectItem for NotebookItem {
                     .read_with(&cx, |project, cx| project.absolute_path(&path, cx))?
                     .ok_or_else(|| anyhow::anyhow!("Failed to find the absolute path"))?;
 
-                // todo: watch for changes to the file
-                let file_content = fs.load(&abs_path.as_path()).await?;
-                let notebook = nbformat::parse_notebook(&file_content);
-
-                let notebook = match notebook {
-                    Ok(nbformat::Notebook::V4(notebook)) => notebook,
-                    // 4.1 - 4.4 are converted to 4.5
-                    Ok(nbformat::Notebook::Legacy(legacy_notebook)) => {
-                        // TODO: Decide if we want to mutate the notebook by including Cell IDs
-                        // and any other conversions
-                        let notebook = nbformat::upgrade_legacy_notebook(legacy_notebook)?;
-                        notebook
-                    }
-                    // Bad notebooks and notebooks v4.0 and below are not supported
-                    Err(e) => {
-                        anyhow::bail!("Failed to parse notebook: {:?}", e);
-                    }
-                };
+                let (notebook, mtime) = load_notebook(fs.as_ref(), &abs_path).await?;
 
                 let id = project
                     .update(&mut cx, |project, cx| project.entry_for_path(&path, cx))?
                     .context("Entry not found")?
                     .id;
 
-                cx.new_model(|_| NotebookItem {
-                    path: abs_path,
-                    project_path: path,
-                    languages,
-                    notebook,
-                    id,
+                cx.new_model(|cx| {
+                    let mut item = NotebookItem {
+                        path: abs_path,
+                        project_path: path,
+                        languages,
+                        notebook,
+                        id: Some(id),
+                        mtime,
+                        _watch_task: Task::ready(()),
+                    };
+                    item.watch_file(fs, cx);
+                    item
                 })
             }))
         } else {
@@ -545,7 +973,7 @@ impl project::ProjectItem for NotebookItem {
     }
 
     fn entry_id(&self, _: &AppContext) -> Option<ProjectEntryId> {
-        Some(self.id)
+        self.id
     }
 
     fn project_path(&self, _: &AppContext) -> Option<ProjectPath> {
@@ -558,6 +986,28 @@ impl project::ProjectItem for NotebookItem {
 }
 
 impl NotebookItem {
+    /// Notifies about changes made to the file by other programs, ignoring the ones from our own saves.
+    fn watch_file(&mut self, fs: Arc<dyn Fs>, cx: &mut ModelContext<Self>) {
+        let path = self.path.clone();
+        self._watch_task = cx.spawn(|this, mut cx| async move {
+            let (mut events, _watcher) = fs.watch(&path, FILE_WATCH_LATENCY).await;
+            while events.next().await.is_some() {
+                let Ok(Some(metadata)) = fs.metadata(&path).await else {
+                    continue;
+                };
+                let changed = this.update(&mut cx, |this, cx| {
+                    if this.mtime != Some(metadata.mtime) {
+                        this.mtime = Some(metadata.mtime);
+                        cx.emit(NotebookItemEvent::ChangedOnDisk);
+                    }
+                });
+                if changed.is_err() {
+                    break;
+                }
+            }
+        });
+    }
+
     pub fn language_name(&self) -> Option<String> {
         self.notebook
             .metadata
@@ -588,6 +1038,140 @@ impl NotebookItem {
 
 impl EventEmitter<()> for NotebookEditor {}
 
+impl KernelSession for NotebookEditor {
+    fn route(&mut self, message: &JupyterMessage, cx: &mut ViewContext<Self>) {
+        let Some(parent_message_id) = message
+            .parent_header
+            .as_ref()
+            .map(|header| header.msg_id.clone())
+        else {
+            return;
+        };
+
+        match &message.content {
+            JupyterMessageContent::Status(status) => {
+                self.kernel.set_execution_state(&status.execution_state);
+                cx.notify();
+            }
+            JupyterMessageContent::KernelInfoReply(reply) => {
+                self.kernel.set_kernel_info(reply);
+                cx.notify();
+            }
+            JupyterMessageContent::UpdateDisplayData(update) => {
+                let Some(display_id) = update.transient.display_id.as_deref() else {
+                    return;
+                };
+                for cell in self.cell_map.values() {
+                    if let Cell::Code(code_cell) = cell {
+                        code_cell.update(cx, |code_cell, cx| {
+                            code_cell.update_display_data(&update.data, display_id, cx)
+                        });
+                    }
+                }
+                return;
+            }
+            _ => {}
+        }
+
+        let Some(cell_id) = self.executions.cell_id(&parent_message_id).cloned() else {
+            return;
+        };
+        let Some(Cell::Code(code_cell)) = self.cell_map.get(&cell_id) else {
+            self.executions.remove(&parent_message_id);
+            return;
+        };
+        let changed = code_cell.update(cx, |code_cell, cx| {
+            code_cell.handle_message(&message.content, cx)
+        });
+        if let JupyterMessageContent::Status(status) = &message.content {
+            if let runtimelib::ExecutionState::Idle = status.execution_state {
+                self.executions.remove(&parent_message_id);
+            }
+        }
+        if changed {
+            self.mark_structural_change(cx);
+        }
+    }
+
+    fn kernel_errored(&mut self, error_message: String, cx: &mut ViewContext<Self>) {
+        self.finish_executions(ExecutionStatus::KernelErrored(error_message.clone()), cx);
+        self.kernel = Kernel::ErroredLaunch(error_message);
+        cx.notify();
+    }
+}
+
+/// The cell run by each execute request, by message id, along with the requests waiting for the
+/// kernel to start.
+#[derive(Default)]
+struct Executions {
+    cell_ids: HashMap<String, CellId>,
+    unsent: Vec<JupyterMessage>,
+}
+
+impl Executions {
+    fn insert(&mut self, message: &JupyterMessage, cell_id: CellId) {
+        self.cell_ids.insert(message.header.msg_id.clone(), cell_id);
+    }
+
+    /// Holds on to a request until [`Self::take_unsent`] is called.
+    fn queue(&mut self, message: JupyterMessage) {
+        self.unsent.push(message);
+    }
+
+    fn take_unsent(&mut self) -> Vec<JupyterMessage> {
+        std::mem::take(&mut self.unsent)
+    }
+
+    fn cell_id(&self, message_id: &str) -> Option<&CellId> {
+        self.cell_ids.get(message_id)
+    }
+
+    fn remove(&mut self, message_id: &str) -> Option<CellId> {
+        self.cell_ids.remove(message_id)
+    }
+
+    /// Forgets every execution, including the unsent ones, returning the cells they ran.
+    fn clear(&mut self) -> Vec<CellId> {
+        self.unsent.clear();
+        self.cell_ids.drain().map(|(_, cell_id)| cell_id).collect()
+    }
+}
+
+async fn load_notebook(
+    fs: &dyn Fs,
+    abs_path: &std::path::Path,
+) -> Result<(nbformat::v4::Notebook, Option<MTime>)> {
+    let file_content = fs.load(abs_path).await?;
+    let mtime = fs.metadata(abs_path).await?.map(|metadata| metadata.mtime);
+    let notebook = match nbformat::parse_notebook(&file_content) {
+        Ok(nbformat::Notebook::V4(notebook)) => notebook,
+        // 4.1 - 4.4 are converted to 4.5
+        Ok(nbformat::Notebook::Legacy(legacy_notebook)) => {
+            // TODO: Decide if we want to mutate the notebook by including Cell IDs
+            // and any other conversions
+            nbformat::upgrade_legacy_notebook(legacy_notebook)?
+        }
+        // Bad notebooks and notebooks v4.0 and below are not supported
+        Err(e) => {
+            anyhow::bail!("Failed to parse notebook: {:?}", e);
+        }
+    };
+    Ok((notebook, mtime))
+}
+
+/// Serializes a notebook the way Jupyter writes it: nbformat 4 JSON with sorted keys,
+/// a one-space indentation and a trailing newline, to keep diffs against Jupyter-saved files small.
+fn serialize_notebook(notebook: &nbformat::v4::Notebook) -> Result<String> {
+    // Going through `Value` sorts the keys, including the ones of unknown metadata.
+    let value = serde_json::to_value(notebook)?;
+    let mut content = Vec::new();
+    let formatter = serde_json::ser::PrettyFormatter::with_indent(b" ");
+    let mut serializer = serde_json::Serializer::with_formatter(&mut content, formatter);
+    value.serialize(&mut serializer)?;
+    content.push(b'\n');
+    Ok(String::from_utf8(content)?)
+}
+
 // pub struct NotebookControls {
 //     pane_focused: bool,
 //     active_item: Option<Box<dyn ItemHandle>>,
@@ -695,46 +1279,81 @@ impl Item for NotebookEditor {
         // TODO
     }
 
-    // TODO
     fn can_save(&self, _cx: &AppContext) -> bool {
-        false
+        true
     }
-    // TODO
+
     fn save(
         &mut self,
         _format: bool,
-        _project: Model<Project>,
-        _cx: &mut ViewContext<Self>,
+        project: Model<Project>,
+        cx: &mut ViewContext<Self>,
     ) -> Task<Result<()>> {
-        unimplemented!("save() must be implemented if can_save() returns true")
+        let abs_path = self.notebook_item.read(cx).path.clone();
+        self.save_to_path(project, abs_path, cx)
     }
 
-    // TODO
     fn save_as(
         &mut self,
-        _project: Model<Project>,
-        _path: ProjectPath,
-        _cx: &mut ViewContext<Self>,
+        project: Model<Project>,
+        path: ProjectPath,
+        cx: &mut ViewContext<Self>,
     ) -> Task<Result<()>> {
-        unimplemented!("save_as() must be implemented if can_save() returns true")
+        let Some(abs_path) = project.read(cx).absolute_path(&path, cx) else {
+            return Task::ready(Err(anyhow!("Failed to find the absolute path")));
+        };
+        let fs = project.read(cx).fs().clone();
+        let save = self.save_to_path(project.clone(), abs_path.clone(), cx);
+        cx.spawn(|this, mut cx| async move {
+            save.await?;
+            this.update(&mut cx, |this, cx| {
+                let id = project
+                    .read(cx)
+                    .entry_for_path(&path, cx)
+                    .map(|entry| entry.id);
+                this.notebook_item.update(cx, |item, cx| {
+                    item.path = abs_path;
+                    item.project_path = path;
+                    item.id = id;
+                    item.watch_file(fs, cx);
+                });
+                cx.emit(());
+            })
+        })
     }
-    // TODO
-    fn reload(
-        &mut self,
-        _project: Model<Project>,
-        _cx: &mut ViewContext<Self>,
-    ) -> Task<Result<()>> {
-        unimplemented!("reload() must be implemented if can_save() returns true")
+
+    fn reload(&mut self, project: Model<Project>, cx: &mut ViewContext<Self>) -> Task<Result<()>> {
+        let fs = project.read(cx).fs().clone();
+        let abs_path = self.notebook_item.read(cx).path.clone();
+        cx.spawn(|this, mut cx| async move {
+            let (notebook, mtime) = load_notebook(fs.as_ref(), &abs_path).await?;
+            this.update(&mut cx, |this, cx| {
+                this.notebook_item.update(cx, |item, _| {
+                    item.notebook = notebook;
+                    item.mtime = mtime;
+                });
+                this.load_cells(cx);
+            })
+        })
     }
 
     fn is_dirty(&self, cx: &AppContext) -> bool {
-        self.cell_map.values().any(|cell| {
-            if let Cell::Code(code_cell) = cell {
-                code_cell.read(cx).is_dirty(cx)
-            } else {
-                false
-            }
-        })
+        self.has_structural_changes
+            || self.cell_map.values().any(|cell| {
+                if let Cell::Code(code_cell) = cell {
+                    code_cell.read(cx).is_dirty(cx)
+                } else {
+                    false
+                }
+            })
+    }
+
+    fn has_conflict(&self, _cx: &AppContext) -> bool {
+        self.has_conflict
+    }
+
+    fn to_item_events(_: &(), mut f: impl FnMut(ItemEvent)) {
+        f(ItemEvent::UpdateTab)
     }
 }
 
@@ -755,3 +1374,122 @@ impl ProjectItem for NotebookEditor {
         Self::new(project, item, cx)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+
+    #[test]
+    fn test_serialize_notebook_round_trip() {
+        let content = indoc! {r##"
+            {
+             "cells": [
+              {
+               "cell_type": "code",
+               "execution_count": 1,
+               "id": "a1",
+               "metadata": {
+                "custom_tool": {"pinned": true}
+               },
+               "outputs": [
+                {
+                 "name": "stdout",
+                 "output_type": "stream",
+                 "text": [
+                  "hello\n"
+                 ]
+                }
+               ],
+               "source": [
+                "print('hello')\n",
+                "x = 1"
+               ]
+              },
+              {
+               "cell_type": "markdown",
+               "id": "b2",
+               "metadata": {},
+               "source": [
+                "# Title"
+               ]
+              }
+             ],
+             "metadata": {
+              "custom_extension": {"setting": 3},
+              "language_info": {
+               "name": "python"
+              }
+             },
+             "nbformat": 4,
+             "nbformat_minor": 5
+            }
+        "##};
+        let Ok(nbformat::Notebook::V4(mut notebook)) = nbformat::parse_notebook(content) else {
+            panic!("failed to parse notebook");
+        };
+        notebook.cells.reverse();
+
+        let serialized = serialize_notebook(&notebook).unwrap();
+        assert!(serialized.starts_with("{\n \"cells\": [\n  {\n"));
+        assert!(serialized.ends_with("}\n"));
+
+        let value: serde_json::Value = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(value["cells"][0]["id"], "b2");
+        assert_eq!(value["cells"][1]["id"], "a1");
+        assert_eq!(
+            value["cells"][1]["source"],
+            serde_json::json!(["print('hello')\n", "x = 1"])
+        );
+        assert_eq!(value["cells"][1]["outputs"][0]["output_type"], "stream");
+        assert_eq!(
+            value["cells"][1]["metadata"]["custom_tool"],
+            serde_json::json!({"pinned": true})
+        );
+        assert_eq!(
+            value["metadata"]["custom_extension"],
+            serde_json::json!({"setting": 3})
+        );
+        assert_eq!(value["nbformat"], 4);
+        assert_eq!(value["nbformat_minor"], 5);
+    }
+
+    #[test]
+    fn test_execute_during_restart() {
+        let cell_id = |id: &str| serde_json::from_value::<CellId>(serde_json::json!(id)).unwrap();
+        let execute = |code: &str| -> JupyterMessage {
+            ExecuteRequest {
+                code: code.to_string(),
+                ..ExecuteRequest::default()
+            }
+            .into()
+        };
+        let mut executions = Executions::default();
+
+        // A cell was sent to the kernel before the restart, which forgets it.
+        let in_flight = execute("a = 1");
+        executions.insert(&in_flight, cell_id("a1"));
+        assert_eq!(executions.clear(), [cell_id("a1")]);
+
+        // A cell run during the restart waits for the new kernel.
+        let during_restart = execute("b = 2");
+        let message_id = during_restart.header.msg_id.clone();
+        executions.insert(&during_restart, cell_id("b2"));
+        executions.queue(during_restart);
+        let unsent = executions.take_unsent();
+        assert_eq!(unsent.len(), 1);
+        assert_eq!(unsent[0].header.msg_id, message_id);
+        assert!(executions.take_unsent().is_empty());
+
+        // Once sent, its outputs are shown in its cell.
+        assert_eq!(executions.cell_id(&message_id), Some(&cell_id("b2")));
+        assert_eq!(executions.cell_id(&in_flight.header.msg_id), None);
+    }
+
+    #[test]
+    fn test_source_lines() {
+        assert_eq!(source_lines(""), Vec::<String>::new());
+        assert_eq!(source_lines("a = 1"), vec!["a = 1"]);
+        assert_eq!(source_lines("a = 1\nb = 2\n"), vec!["a = 1\n", "b = 2\n"]);
+    }
+}
@@ -4,7 +4,7 @@ use gpui::App;
 use http_client::HttpClientWithUrl;
 use language::language_settings::AllLanguageSettings;
 use project::Project;
-use semantic_index::{OpenAiEmbeddingModel, OpenAiEmbeddingProvider, SemanticDb};
+use semantic_index::{OpenAiEmbeddingModel, OpenAiEmbeddingProvider, SearchMode, SemanticDb};
 use settings::SettingsStore;
 use std::{
     path::{Path, PathBuf},
@@ -99,7 +99,7 @@ fn main() {
                 .update(|cx| {
                     let project_index = project_index.read(cx);
                     let query = "converting an anchor to a point";
-                    project_index.search(vec![query.into()], 4, cx)
+                    project_index.search(vec![query.into()], 4, SearchMode::Hybrid, cx)
                 })
                 .unwrap()
                 .await
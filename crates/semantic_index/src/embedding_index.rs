@@ -2,6 +2,7 @@ use crate::{
     chunking::{self, Chunk},
     embedding::{Embedding, EmbeddingProvider, TextToEmbed},
     indexing::{IndexingEntryHandle, IndexingEntrySet},
+    lexical_index::{LexicalIndex, TokenizedFile},
 };
 use anyhow::{anyhow, Context as _, Result};
 use collections::Bound;
@@ -18,7 +19,7 @@ use project::{Entry, UpdatedEntriesSet, Worktree};
 use serde::{Deserialize, Serialize};
 use smol::channel;
 use smol::future::FutureExt;
-use std::{cmp::Ordering, future::Future, iter, path::Path, sync::Arc, time::Duration};
+use std::{cmp::Ordering, future::Future, iter, ops::Range, path::Path, sync::Arc, time::Duration};
 use util::ResultExt;
 use worktree::Snapshot;
 
@@ -26,6 +27,7 @@ pub struct EmbeddingIndex {
     worktree: Model<Worktree>,
     db_connection: heed::Env,
     db: heed::Database<Str, SerdeBincode<EmbeddedFile>>,
+    lexical_index: LexicalIndex,
     fs: Arc<dyn Fs>,
     language_registry: Arc<LanguageRegistry>,
     embedding_provider: Arc<dyn EmbeddingProvider>,
@@ -38,6 +40,7 @@ impl EmbeddingIndex {
         fs: Arc<dyn Fs>,
         db_connection: heed::Env,
         embedding_db: heed::Database<Str, SerdeBincode<EmbeddedFile>>,
+        lexical_index: LexicalIndex,
         language_registry: Arc<LanguageRegistry>,
         embedding_provider: Arc<dyn EmbeddingProvider>,
         entry_ids_being_indexed: Arc<IndexingEntrySet>,
@@ -47,6 +50,7 @@ impl EmbeddingIndex {
             fs,
             db_connection,
             db: embedding_db,
+            lexical_index,
             language_registry,
             embedding_provider,
             entry_ids_being_indexed,
@@ -57,6 +61,10 @@ impl EmbeddingIndex {
         &self.db
     }
 
+    pub fn lexical_index(&self) -> &LexicalIndex {
+        &self.lexical_index
+    }
+
     pub fn index_entries_changed_on_disk(
         &self,
         cx: &AppContext,
@@ -68,11 +76,17 @@ impl EmbeddingIndex {
         let worktree = self.worktree.read(cx).snapshot();
         let worktree_abs_path = worktree.abs_path().clone();
         let scan = self.scan_entries(worktree, cx);
+        let tokenize = self.tokenize_files(
+            worktree_abs_path.clone(),
+            scan.unindexed_lexical_entries,
+            cx,
+        );
         let chunk = self.chunk_files(worktree_abs_path, scan.updated_entries, cx);
         let embed = Self::embed_files(self.embedding_provider.clone(), chunk.files, cx);
-        let persist = self.persist_embeddings(scan.deleted_entry_ranges, embed.files, cx);
+        let persist =
+            self.persist_embeddings(scan.deleted_entry_ranges, embed.files, tokenize.files, cx);
         async move {
-            futures::try_join!(scan.task, chunk.task, embed.task, persist)?;
+            futures::try_join!(scan.task, chunk.task, embed.task, tokenize.task, persist)?;
             Ok(())
         }
         .boxed()
@@ -90,11 +104,17 @@ impl EmbeddingIndex {
         let worktree = self.worktree.read(cx).snapshot();
         let worktree_abs_path = worktree.abs_path().clone();
         let scan = self.scan_updated_entries(worktree, updated_entries.clone(), cx);
+        let tokenize = self.tokenize_files(
+            worktree_abs_path.clone(),
+            scan.unindexed_lexical_entries,
+            cx,
+        );
         let chunk = self.chunk_files(worktree_abs_path, scan.updated_entries, cx);
         let embed = Self::embed_files(self.embedding_provider.clone(), chunk.files, cx);
-        let persist = self.persist_embeddings(scan.deleted_entry_ranges, embed.files, cx);
+        let persist =
+            self.persist_embeddings(scan.deleted_entry_ranges, embed.files, tokenize.files, cx);
         async move {
-            futures::try_join!(scan.task, chunk.task, embed.task, persist)?;
+            futures::try_join!(scan.task, chunk.task, embed.task, tokenize.task, persist)?;
             Ok(())
         }
         .boxed()
@@ -103,8 +123,10 @@ impl EmbeddingIndex {
     fn scan_entries(&self, worktree: Snapshot, cx: &AppContext) -> ScanEntries {
         let (updated_entries_tx, updated_entries_rx) = channel::bounded(512);
         let (deleted_entry_ranges_tx, deleted_entry_ranges_rx) = channel::bounded(128);
+        let (unindexed_lexical_entries_tx, unindexed_lexical_entries_rx) = channel::bounded(512);
         let db_connection = self.db_connection.clone();
         let db = self.db;
+        let lexical_index = self.lexical_index;
         let entries_being_indexed = self.entry_ids_being_indexed.clone();
         let task = cx.background_executor().spawn(async move {
             let txn = db_connection
@@ -123,6 +145,7 @@ impl EmbeddingIndex {
                 let entry_db_key = db_key_for_path(&entry.path);
 
                 let mut saved_mtime = None;
+                let mut unindexed_chunk_ranges = None;
                 while let Some(db_entry) = db_entries.peek() {
                     match db_entry {
                         Ok((db_path, db_embedded_file)) => match (*db_path).cmp(&entry_db_key) {
@@ -146,6 +169,19 @@ impl EmbeddingIndex {
                                         .await?;
                                 }
                                 saved_mtime = db_embedded_file.mtime;
+                                // Files embedded before the lexical index existed only need
+                                // their terms indexed, which their saved chunks are enough for.
+                                if !lexical_index.contains(&txn, &entry_db_key)? {
+                                    unindexed_chunk_ranges = Some(
+                                        db_embedded_file
+                                            .chunks
+                                            .iter()
+                                            .map(|embedded_chunk| {
+                                                embedded_chunk.chunk.range.clone()
+                                            })
+                                            .collect::<Vec<_>>(),
+                                    );
+                                }
                                 db_entries.next();
                                 break;
                             }
@@ -160,6 +196,11 @@ impl EmbeddingIndex {
                 if entry.mtime != saved_mtime {
                     let handle = entries_being_indexed.insert(entry.id);
                     updated_entries_tx.send((entry.clone(), handle)).await?;
+                } else if let Some(chunk_ranges) = unindexed_chunk_ranges {
+                    let handle = entries_being_indexed.insert(entry.id);
+                    unindexed_lexical_entries_tx
+                        .send((entry.clone(), chunk_ranges, handle))
+                        .await?;
                 }
             }
 
@@ -176,6 +217,7 @@ impl EmbeddingIndex {
         ScanEntries {
             updated_entries: updated_entries_rx,
             deleted_entry_ranges: deleted_entry_ranges_rx,
+            unindexed_lexical_entries: unindexed_lexical_entries_rx,
             task,
         }
     }
@@ -188,6 +230,8 @@ impl EmbeddingIndex {
     ) -> ScanEntries {
         let (updated_entries_tx, updated_entries_rx) = channel::bounded(512);
         let (deleted_entry_ranges_tx, deleted_entry_ranges_rx) = channel::bounded(128);
+        // Updated entries are embedded again, which indexes their terms too.
+        let (_, unindexed_lexical_entries_rx) = channel::bounded(1);
         let entries_being_indexed = self.entry_ids_being_indexed.clone();
         let task = cx.background_executor().spawn(async move {
             for (path, entry_id, status) in updated_entries.iter() {
@@ -220,6 +264,47 @@ impl EmbeddingIndex {
         ScanEntries {
             updated_entries: updated_entries_rx,
             deleted_entry_ranges: deleted_entry_ranges_rx,
+            unindexed_lexical_entries: unindexed_lexical_entries_rx,
+            task,
+        }
+    }
+
+    /// Tokenizes the saved chunks of files that are already embedded, without embedding them again.
+    fn tokenize_files(
+        &self,
+        worktree_abs_path: Arc<Path>,
+        entries: channel::Receiver<(Entry, Vec<Range<usize>>, IndexingEntryHandle)>,
+        cx: &AppContext,
+    ) -> TokenizeFiles {
+        let fs = self.fs.clone();
+        let (tokenized_files_tx, tokenized_files_rx) = channel::bounded(512);
+        let task = cx.background_executor().spawn(async move {
+            while let Ok((entry, chunk_ranges, handle)) = entries.recv().await {
+                let entry_abs_path = worktree_abs_path.join(&entry.path);
+                let Some(text) = fs.load(&entry_abs_path).await.ok() else {
+                    continue;
+                };
+                // The file may have changed without its mtime changing.
+                if chunk_ranges
+                    .iter()
+                    .any(|range| text.get(range.clone()).is_none())
+                {
+                    continue;
+                }
+                let tokenized_file = TokenizedFile::new(&text, chunk_ranges);
+                if tokenized_files_tx
+                    .send((entry.path, tokenized_file, handle))
+                    .await
+                    .is_err()
+                {
+                    break;
+                }
+            }
+            Ok(())
+        });
+
+        TokenizeFiles {
+            files: tokenized_files_rx,
             task,
         }
     }
@@ -341,8 +426,15 @@ impl EmbeddingIndex {
                     }
 
                     if embedded_all_chunks {
+                        let tokenized_file = TokenizedFile::new(
+                            &chunked_file.text,
+                            embedded_file
+                                .chunks
+                                .iter()
+                                .map(|embedded_chunk| embedded_chunk.chunk.range.clone()),
+                        );
                         embedded_files_tx
-                            .send((embedded_file, chunked_file.handle))
+                            .send((embedded_file, tokenized_file, chunked_file.handle))
                             .await?;
                     }
                 }
@@ -359,11 +451,13 @@ impl EmbeddingIndex {
     fn persist_embeddings(
         &self,
         mut deleted_entry_ranges: channel::Receiver<(Bound<String>, Bound<String>)>,
-        mut embedded_files: channel::Receiver<(EmbeddedFile, IndexingEntryHandle)>,
+        mut embedded_files: channel::Receiver<(EmbeddedFile, TokenizedFile, IndexingEntryHandle)>,
+        mut tokenized_files: channel::Receiver<(Arc<Path>, TokenizedFile, IndexingEntryHandle)>,
         cx: &AppContext,
     ) -> Task<Result<()>> {
         let db_connection = self.db_connection.clone();
         let db = self.db;
+        let lexical_index = self.lexical_index;
 
         cx.background_executor().spawn(async move {
             loop {
@@ -376,15 +470,26 @@ impl EmbeddingIndex {
                             let end = deletion_range.1.as_ref().map(|end| end.as_str());
                             log::debug!("deleting embeddings in range {:?}", &(start, end));
                             db.delete_range(&mut txn, &(start, end))?;
+                            lexical_index.delete_range(&mut txn, &(start, end))?;
                             txn.commit()?;
                         }
                     },
                     file = embedded_files.next() => {
-                        if let Some((file, _)) = file {
+                        if let Some((file, tokenized_file, _)) = file {
                             let mut txn = db_connection.write_txn()?;
                             log::debug!("saving embedding for file {:?}", file.path);
                             let key = db_key_for_path(&file.path);
                             db.put(&mut txn, &key, &file)?;
+                            lexical_index.insert(&mut txn, &key, &file.path, tokenized_file)?;
+                            txn.commit()?;
+                        }
+                    },
+                    file = tokenized_files.next() => {
+                        if let Some((path, tokenized_file, _)) = file {
+                            let mut txn = db_connection.write_txn()?;
+                            log::debug!("saving terms for file {:?}", path);
+                            let key = db_key_for_path(&path);
+                            lexical_index.insert(&mut txn, &key, &path, tokenized_file)?;
                             txn.commit()?;
                         }
                     },
@@ -435,6 +540,14 @@ impl EmbeddingIndex {
 struct ScanEntries {
     updated_entries: channel::Receiver<(Entry, IndexingEntryHandle)>,
     deleted_entry_ranges: channel::Receiver<(Bound<String>, Bound<String>)>,
+    /// Files whose embeddings are up to date, but whose terms are not indexed, with the ranges
+    /// of their saved chunks.
+    unindexed_lexical_entries: channel::Receiver<(Entry, Vec<Range<usize>>, IndexingEntryHandle)>,
+    task: Task<Result<()>>,
+}
+
+struct TokenizeFiles {
+    files: channel::Receiver<(Arc<Path>, TokenizedFile, IndexingEntryHandle)>,
     task: Task<Result<()>>,
 }
 
@@ -452,7 +565,7 @@ pub struct ChunkedFile {
 }
 
 pub struct EmbedFiles {
-    pub files: channel::Receiver<(EmbeddedFile, IndexingEntryHandle)>,
+    pub files: channel::Receiver<(EmbeddedFile, TokenizedFile, IndexingEntryHandle)>,
     pub task: Task<Result<()>>,
 }
 
@@ -0,0 +1,398 @@
+use anyhow::Result;
+use collections::HashMap;
+use heed::{
+    types::{DecodeIgnore, SerdeBincode, Str},
+    RoTxn, RwTxn,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    ops::{Bound, Range},
+    path::Path,
+    sync::Arc,
+};
+
+/// Longer terms are not indexed, which keeps the database keys short.
+const MAX_TERM_LEN: usize = 64;
+
+/// The key under which the postings database stores the [`CorpusStats`]. Terms are never empty,
+/// so it can't conflict with a posting's key.
+const STATS_KEY: &str = "\0stats";
+
+/// BM25 parameters: how quickly repeated occurrences of a term stop increasing a chunk's score,
+/// and how much the score is normalized by the length of the chunk.
+const BM25_K1: f32 = 1.2;
+const BM25_B: f32 = 0.75;
+
+/// An inverted index of the terms in each embedded chunk, used to score chunks with BM25.
+///
+/// Postings are keyed by the term followed by a hash of the file's key, so that all the chunks
+/// containing a term can be found with a prefix scan. The files database records the terms of
+/// each file, so that its postings can be removed when it changes. The corpus totals are kept up
+/// to date in the postings database as files are inserted and deleted.
+#[derive(Clone, Copy)]
+pub struct LexicalIndex {
+    postings: heed::Database<Str, SerdeBincode<Posting>>,
+    files: heed::Database<Str, SerdeBincode<LexicalFile>>,
+}
+
+/// The chunks of a single file that contain a term.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Posting {
+    pub path: Arc<Path>,
+    pub chunks: Vec<PostingChunk>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PostingChunk {
+    pub range: Range<usize>,
+    /// How many times the term occurs in the chunk.
+    pub term_count: u32,
+    /// How many terms the chunk contains in total.
+    pub chunk_term_count: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LexicalFile {
+    chunk_count: u32,
+    term_count: u64,
+    terms: Vec<String>,
+}
+
+/// The totals BM25 needs to weigh terms and normalize chunk lengths.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct CorpusStats {
+    pub chunk_count: u64,
+    pub term_count: u64,
+}
+
+/// The terms of each chunk of a file, ready to be written to the index.
+pub struct TokenizedFile {
+    chunks: Vec<TokenizedChunk>,
+}
+
+struct TokenizedChunk {
+    range: Range<usize>,
+    term_counts: HashMap<String, u32>,
+    term_count: u32,
+}
+
+impl TokenizedFile {
+    pub fn new(text: &str, chunk_ranges: impl IntoIterator<Item = Range<usize>>) -> Self {
+        let chunks = chunk_ranges
+            .into_iter()
+            .map(|range| {
+                let mut term_counts = HashMap::<String, u32>::default();
+                let mut term_count = 0;
+                for term in tokenize(&text[range.clone()]) {
+                    *term_counts.entry(term).or_default() += 1;
+                    term_count += 1;
+                }
+                TokenizedChunk {
+                    range,
+                    term_counts,
+                    term_count,
+                }
+            })
+            .collect();
+        Self { chunks }
+    }
+}
+
+impl LexicalIndex {
+    pub fn new(
+        postings: heed::Database<Str, SerdeBincode<Posting>>,
+        files: heed::Database<Str, SerdeBincode<LexicalFile>>,
+    ) -> Self {
+        Self { postings, files }
+    }
+
+    pub fn contains(&self, txn: &RoTxn<'_>, key: &str) -> Result<bool> {
+        Ok(self
+            .files
+            .remap_data_type::<DecodeIgnore>()
+            .get(txn, key)?
+            .is_some())
+    }
+
+    /// Replaces the terms stored for the file with the given key.
+    pub fn insert(
+        &self,
+        txn: &mut RwTxn<'_>,
+        key: &str,
+        path: &Arc<Path>,
+        file: TokenizedFile,
+    ) -> Result<()> {
+        self.delete(txn, key)?;
+
+        let mut chunks_by_term = HashMap::<String, Vec<PostingChunk>>::default();
+        let mut term_count = 0;
+        for chunk in &file.chunks {
+            term_count += chunk.term_count as u64;
+            for (term, count) in &chunk.term_counts {
+                chunks_by_term
+                    .entry(term.clone())
+                    .or_default()
+                    .push(PostingChunk {
+                        range: chunk.range.clone(),
+                        term_count: *count,
+                        chunk_term_count: chunk.term_count,
+                    });
+            }
+        }
+
+        let terms = chunks_by_term.keys().cloned().collect();
+        for (term, chunks) in chunks_by_term {
+            let posting = Posting {
+                path: path.clone(),
+                chunks,
+            };
+            self.postings.put(txn, &posting_key(&term, key), &posting)?;
+        }
+        self.files.put(
+            txn,
+            key,
+            &LexicalFile {
+                chunk_count: file.chunks.len() as u32,
+                term_count,
+                terms,
+            },
+        )?;
+
+        let mut stats = self.stats(txn)?;
+        stats.chunk_count += file.chunks.len() as u64;
+        stats.term_count += term_count;
+        self.put_stats(txn, &stats)
+    }
+
+    pub fn delete(&self, txn: &mut RwTxn<'_>, key: &str) -> Result<()> {
+        if let Some(file) = self.files.get(txn, key)? {
+            for term in &file.terms {
+                self.postings.delete(txn, &posting_key(term, key))?;
+            }
+            self.files.delete(txn, key)?;
+
+            let mut stats = self.stats(txn)?;
+            stats.chunk_count = stats.chunk_count.saturating_sub(file.chunk_count as u64);
+            stats.term_count = stats.term_count.saturating_sub(file.term_count);
+            self.put_stats(txn, &stats)?;
+        }
+        Ok(())
+    }
+
+    pub fn delete_range(
+        &self,
+        txn: &mut RwTxn<'_>,
+        range: &(Bound<&str>, Bound<&str>),
+    ) -> Result<()> {
+        let keys = self
+            .files
+            .range(txn, range)?
+            .map(|entry| Ok(entry?.0.to_string()))
+            .collect::<Result<Vec<_>>>()?;
+        for key in keys {
+            self.delete(txn, &key)?;
+        }
+        Ok(())
+    }
+
+    pub fn stats(&self, txn: &RoTxn<'_>) -> Result<CorpusStats> {
+        Ok(self
+            .postings
+            .remap_data_type::<SerdeBincode<CorpusStats>>()
+            .get(txn, STATS_KEY)?
+            .unwrap_or_default())
+    }
+
+    fn put_stats(&self, txn: &mut RwTxn<'_>, stats: &CorpusStats) -> Result<()> {
+        self.postings
+            .remap_data_type::<SerdeBincode<CorpusStats>>()
+            .put(txn, STATS_KEY, stats)?;
+        Ok(())
+    }
+
+    /// Every file containing the given term, which must come from [`tokenize`].
+    pub fn postings(&self, txn: &RoTxn<'_>, term: &str) -> Result<Vec<Posting>> {
+        let prefix = format!("{term}\0");
+        self.postings
+            .prefix_iter(txn, &prefix)?
+            .map(|entry| Ok(entry?.1))
+            .collect()
+    }
+}
+
+impl CorpusStats {
+    /// How informative a term is, given the number of chunks that contain it.
+    pub fn inverse_document_frequency(&self, document_frequency: u64) -> f32 {
+        let chunk_count = self.chunk_count as f32;
+        let document_frequency = document_frequency as f32;
+        (1. + (chunk_count - document_frequency + 0.5) / (document_frequency + 0.5)).ln()
+    }
+
+    fn average_chunk_term_count(&self) -> f32 {
+        if self.chunk_count == 0 {
+            1.
+        } else {
+            (self.term_count as f32 / self.chunk_count as f32).max(1.)
+        }
+    }
+}
+
+impl PostingChunk {
+    /// The BM25 score of this chunk for a term with the given inverse document frequency.
+    pub fn score(&self, inverse_document_frequency: f32, stats: &CorpusStats) -> f32 {
+        let term_count = self.term_count as f32;
+        let length_ratio = self.chunk_term_count as f32 / stats.average_chunk_term_count();
+        let length_normalization = 1. - BM25_B + BM25_B * length_ratio;
+        inverse_document_frequency * term_count * (BM25_K1 + 1.)
+            / (term_count + BM25_K1 * length_normalization)
+    }
+}
+
+/// Splits text into lowercase terms: each identifier-like word, plus the parts of the words
+/// written in camelCase or snake_case, so that `parseConfig` matches a search for `parse_config`.
+pub fn tokenize(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric() && c != '_')
+        .filter(|word| !word.is_empty())
+        .flat_map(|word| {
+            let parts = word_parts(word);
+            let mut terms = vec![word.to_lowercase()];
+            if parts != [word] {
+                terms.extend(parts.into_iter().map(str::to_lowercase));
+            }
+            terms
+        })
+        .filter(|term| term.len() <= MAX_TERM_LEN)
+}
+
+fn word_parts(word: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    for segment in word.split('_').filter(|segment| !segment.is_empty()) {
+        let chars = segment.char_indices().collect::<Vec<_>>();
+        let mut start = 0;
+        for (ix, &(offset, char)) in chars.iter().enumerate().skip(1) {
+            let prev = chars[ix - 1].1;
+            let next = chars.get(ix + 1).map(|(_, next)| *next);
+            if is_part_boundary(prev, char, next) {
+                parts.push(&segment[start..offset]);
+                start = offset;
+            }
+        }
+        parts.push(&segment[start..]);
+    }
+    parts
+}
+
+/// Whether a new part starts at `char`, as in `parse|Config` and `HTTP|Server`.
+fn is_part_boundary(prev: char, char: char, next: Option<char>) -> bool {
+    char.is_uppercase()
+        && (prev.is_lowercase()
+            || prev.is_numeric()
+            || (prev.is_uppercase() && next.map_or(false, char::is_lowercase)))
+}
+
+fn posting_key(term: &str, file_key: &str) -> String {
+    let file_hash = blake3::hash(file_key.as_bytes()).to_hex();
+    format!("{term}\0{}", &file_hash[..16])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokenize() {
+        assert_eq!(
+            tokenize("let parseConfig = HTTPServer::new(max_retries_2);").collect::<Vec<_>>(),
+            [
+                "let",
+                "parseconfig",
+                "parse",
+                "config",
+                "httpserver",
+                "http",
+                "server",
+                "new",
+                "max_retries_2",
+                "max",
+                "retries",
+                "2",
+            ]
+        );
+        assert_eq!(
+            tokenize("error: `_private` not found").collect::<Vec<_>>(),
+            ["error", "_private", "private", "not", "found"]
+        );
+        assert_eq!(tokenize(&"a".repeat(MAX_TERM_LEN + 1)).count(), 0);
+    }
+
+    #[test]
+    fn test_stats() {
+        let dir = tempfile::tempdir().unwrap();
+        let env = unsafe { heed::EnvOpenOptions::new().max_dbs(2).open(dir.path()) }.unwrap();
+        let mut txn = env.write_txn().unwrap();
+        let index = LexicalIndex::new(
+            env.create_database(&mut txn, Some("postings")).unwrap(),
+            env.create_database(&mut txn, Some("files")).unwrap(),
+        );
+
+        let text = "fn parse() {}\nfn print() {}";
+        let path = Arc::from(Path::new("a.rs"));
+        index
+            .insert(
+                &mut txn,
+                "a",
+                &path,
+                TokenizedFile::new(text, [0..13, 14..27]),
+            )
+            .unwrap();
+        index
+            .insert(&mut txn, "b", &path, TokenizedFile::new(text, [0..13]))
+            .unwrap();
+        assert_eq!(
+            index.stats(&txn).unwrap(),
+            CorpusStats {
+                chunk_count: 3,
+                term_count: 6,
+            }
+        );
+
+        // Reinserting a file replaces its totals.
+        index
+            .insert(&mut txn, "a", &path, TokenizedFile::new(text, [0..13]))
+            .unwrap();
+        index.delete(&mut txn, "b").unwrap();
+        assert_eq!(
+            index.stats(&txn).unwrap(),
+            CorpusStats {
+                chunk_count: 1,
+                term_count: 2,
+            }
+        );
+        assert_eq!(index.postings(&txn, "parse").unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_bm25_score() {
+        let stats = CorpusStats {
+            chunk_count: 10,
+            term_count: 1000,
+        };
+        let rare = stats.inverse_document_frequency(1);
+        let common = stats.inverse_document_frequency(9);
+        assert!(rare > common);
+
+        let chunk = |term_count, chunk_term_count| PostingChunk {
+            range: 0..1,
+            term_count,
+            chunk_term_count,
+        };
+        // More occurrences score higher, with diminishing returns.
+        let once = chunk(1, 100).score(rare, &stats);
+        let twice = chunk(2, 100).score(rare, &stats);
+        let thrice = chunk(3, 100).score(rare, &stats);
+        assert!(twice > once);
+        assert!(thrice - twice < twice - once);
+        // Occurrences in shorter chunks score higher.
+        assert!(chunk(1, 50).score(rare, &stats) > once);
+    }
+}
@@ -1,10 +1,11 @@
 use crate::{
     embedding::{EmbeddingProvider, TextToEmbed},
+    lexical_index::{self, CorpusStats, Posting},
     summary_index::FileSummary,
     worktree_index::{WorktreeIndex, WorktreeIndexHandle},
 };
 use anyhow::{anyhow, Context, Result};
-use collections::HashMap;
+use collections::{HashMap, HashSet};
 use fs::Fs;
 use futures::{stream::StreamExt, FutureExt};
 use gpui::{
@@ -51,6 +52,25 @@ pub struct WorktreeSearchResult {
     pub score: f32,
 }
 
+/// How [`ProjectIndex::search`] ranks the chunks of the project.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchMode {
+    /// By the similarity of their embeddings to the embedding of the query.
+    Semantic,
+    /// By the BM25 score of the query's words, which favors exact identifiers and messages.
+    Lexical,
+    /// By fusing the rankings of the semantic and lexical searches.
+    #[default]
+    Hybrid,
+}
+
+/// How many results each search contributes to a hybrid search, per requested result.
+const HYBRID_CANDIDATES_PER_RESULT: usize = 4;
+
+/// The rank offset of reciprocal rank fusion, which dampens the weight of the top results.
+const RRF_K: f32 = 60.;
+
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub enum Status {
     Idle,
@@ -229,6 +249,37 @@ impl ProjectIndex {
     }
 
     pub fn search(
+        &self,
+        queries: Vec<String>,
+        limit: usize,
+        mode: SearchMode,
+        cx: &AppContext,
+    ) -> Task<Result<Vec<SearchResult>>> {
+        match mode {
+            SearchMode::Semantic => self.semantic_search(queries, limit, cx),
+            SearchMode::Lexical => self.lexical_search(queries, limit, cx),
+            SearchMode::Hybrid => {
+                let candidate_limit = limit * HYBRID_CANDIDATES_PER_RESULT;
+                let semantic_search = self.semantic_search(queries.clone(), candidate_limit, cx);
+                let lexical_search = self.lexical_search(queries, candidate_limit, cx);
+                cx.spawn(|_| async move {
+                    let (semantic_results, lexical_results) =
+                        futures::future::join(semantic_search, lexical_search).await;
+                    let lexical_results = lexical_results?;
+                    // Embedding the queries can fail, e.g. when the provider is unreachable,
+                    // while the lexical index is always available.
+                    let semantic_results = semantic_results
+                        .map_err(|error| {
+                            log::error!("semantic search failed, using lexical results: {error:#}")
+                        })
+                        .unwrap_or_default();
+                    Ok(fuse_results(semantic_results, lexical_results, limit))
+                })
+            }
+        }
+    }
+
+    fn semantic_search(
         &self,
         queries: Vec<String>,
         limit: usize,
@@ -371,6 +422,89 @@ impl ProjectIndex {
         })
     }
 
+    fn lexical_search(
+        &self,
+        queries: Vec<String>,
+        limit: usize,
+        cx: &AppContext,
+    ) -> Task<Result<Vec<SearchResult>>> {
+        let query_terms = queries
+            .iter()
+            .map(|query| lexical_index::tokenize(query).collect::<HashSet<_>>())
+            .collect::<Vec<_>>();
+        let terms = query_terms
+            .iter()
+            .flatten()
+            .cloned()
+            .collect::<HashSet<_>>();
+
+        let mut worktree_search_tasks = Vec::new();
+        for worktree_index in self.worktree_indices.values() {
+            let worktree_index = worktree_index.clone();
+            let terms = terms.clone();
+            worktree_search_tasks.push(cx.spawn(|cx| async move {
+                let index = match worktree_index {
+                    WorktreeIndexHandle::Loading { index } => {
+                        index.clone().await.map_err(|error| anyhow!(error))?
+                    }
+                    WorktreeIndexHandle::Loaded { index } => index.clone(),
+                };
+
+                index
+                    .read_with(&cx, |index, cx| {
+                        let worktree_id = index.worktree().read(cx).id();
+                        let db_connection = index.db_connection().clone();
+                        let lexical_index = *index.embedding_index().lexical_index();
+                        cx.background_executor().spawn(async move {
+                            let txn = db_connection
+                                .read_txn()
+                                .context("failed to create read transaction")?;
+                            let stats = lexical_index.stats(&txn)?;
+                            let mut postings_by_term = HashMap::default();
+                            for term in terms {
+                                let postings = lexical_index.postings(&txn, &term)?;
+                                postings_by_term.insert(term, postings);
+                            }
+                            anyhow::Ok(WorktreeLexicalMatches {
+                                worktree_id,
+                                stats,
+                                postings_by_term,
+                            })
+                        })
+                    })?
+                    .await
+            }));
+        }
+
+        let project = self.project.clone();
+        cx.spawn(|cx| async move {
+            let mut worktree_matches = Vec::new();
+            for search_task in futures::future::join_all(worktree_search_tasks).await {
+                worktree_matches.extend(search_task.log_err());
+            }
+
+            let results = cx
+                .background_executor()
+                .spawn(async move { score_lexical_matches(&query_terms, &worktree_matches, limit) })
+                .await;
+
+            project.read_with(&cx, |project, cx| {
+                results
+                    .into_iter()
+                    .filter_map(|result| {
+                        Some(SearchResult {
+                            worktree: project.worktree_for_id(result.worktree_id, cx)?,
+                            path: result.path,
+                            range: result.range,
+                            score: result.score,
+                            query_index: result.query_index,
+                        })
+                    })
+                    .collect()
+            })
+        })
+    }
+
     #[cfg(test)]
     pub fn path_count(&self, cx: &AppContext) -> Result<u64> {
         let mut result = 0;
@@ -549,3 +683,105 @@ impl ProjectIndex {
 }
 
 impl EventEmitter<Status> for ProjectIndex {}
+
+struct WorktreeLexicalMatches {
+    worktree_id: WorktreeId,
+    stats: CorpusStats,
+    postings_by_term: HashMap<String, Vec<Posting>>,
+}
+
+/// Scores the chunks containing the queries' terms with BM25. The statistics of all worktrees are
+/// combined, so that scores are comparable across worktrees.
+fn score_lexical_matches(
+    query_terms: &[HashSet<String>],
+    worktree_matches: &[WorktreeLexicalMatches],
+    limit: usize,
+) -> Vec<WorktreeSearchResult> {
+    let mut stats = CorpusStats::default();
+    let mut document_frequencies = HashMap::<&str, u64>::default();
+    for matches in worktree_matches {
+        stats.chunk_count += matches.stats.chunk_count;
+        stats.term_count += matches.stats.term_count;
+        for (term, postings) in &matches.postings_by_term {
+            *document_frequencies.entry(term.as_str()).or_default() += postings
+                .iter()
+                .map(|posting| posting.chunks.len() as u64)
+                .sum::<u64>();
+        }
+    }
+
+    let mut scores_by_chunk = HashMap::<(WorktreeId, Arc<Path>, Range<usize>), Vec<f32>>::default();
+    for matches in worktree_matches {
+        for (term, postings) in &matches.postings_by_term {
+            let inverse_document_frequency =
+                stats.inverse_document_frequency(document_frequencies[term.as_str()]);
+            for posting in postings {
+                for chunk in &posting.chunks {
+                    let score = chunk.score(inverse_document_frequency, &stats);
+                    let query_scores = scores_by_chunk
+                        .entry((
+                            matches.worktree_id,
+                            posting.path.clone(),
+                            chunk.range.clone(),
+                        ))
+                        .or_insert_with(|| vec![0.; query_terms.len()]);
+                    for (query_index, terms) in query_terms.iter().enumerate() {
+                        if terms.contains(term) {
+                            query_scores[query_index] += score;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    let mut results = scores_by_chunk
+        .into_iter()
+        .filter_map(|((worktree_id, path, range), query_scores)| {
+            let (query_index, score) = query_scores
+                .into_iter()
+                .enumerate()
+                .max_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap_or(Ordering::Equal))?;
+            Some(WorktreeSearchResult {
+                worktree_id,
+                path,
+                range,
+                query_index,
+                score,
+            })
+        })
+        .collect::<Vec<_>>();
+    results.sort_unstable_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(Ordering::Equal));
+    results.truncate(limit);
+    results
+}
+
+/// Combines the results of a semantic and a lexical search with reciprocal rank fusion, which
+/// only relies on the order of each list, since their scores aren't comparable. The fused scores
+/// are scaled so that a chunk ranked first by both searches scores 1.
+fn fuse_results(
+    semantic_results: Vec<SearchResult>,
+    lexical_results: Vec<SearchResult>,
+    limit: usize,
+) -> Vec<SearchResult> {
+    let mut results_by_chunk =
+        HashMap::<(EntityId, Arc<Path>, Range<usize>), SearchResult>::default();
+    for results in [semantic_results, lexical_results] {
+        for (rank, result) in results.into_iter().enumerate() {
+            let score = (RRF_K + 1.) / (RRF_K + 1. + rank as f32) / 2.;
+            results_by_chunk
+                .entry((
+                    result.worktree.entity_id(),
+                    result.path.clone(),
+                    result.range.clone(),
+                ))
+                .and_modify(|fused_result| fused_result.score += score)
+                .or_insert(SearchResult { score, ..result });
+        }
+    }
+
+    let mut results = results_by_chunk.into_values().collect::<Vec<_>>();
+    results.sort_unstable_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(Ordering::Equal));
+    results.truncate(limit);
+    results
+}
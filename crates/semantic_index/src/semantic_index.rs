@@ -2,6 +2,7 @@ mod chunking;
 mod embedding;
 mod embedding_index;
 mod indexing;
+mod lexical_index;
 mod project_index;
 mod project_index_debug_view;
 mod summary_backlog;
@@ -24,7 +25,7 @@ use util::ResultExt as _;
 use workspace::Workspace;
 
 pub use embedding::*;
-pub use project_index::{LoadedSearchResult, ProjectIndex, SearchResult, Status};
+pub use project_index::{LoadedSearchResult, ProjectIndex, SearchMode, SearchResult, Status};
 pub use project_index_debug_view::ProjectIndexDebugView;
 pub use summary_index::FileSummary;
 
@@ -49,7 +50,9 @@ impl SemanticDb {
                 unsafe {
                     heed::EnvOpenOptions::new()
                         .map_size(1024 * 1024 * 1024)
-                        .max_dbs(3000)
+                        // Each worktree has five databases: its embeddings, its lexical index's
+                        // postings and files, and its summaries' digests and summaries.
+                        .max_dbs(5000)
                         .open(db_path)
                 }
             })
@@ -403,7 +406,7 @@ mod tests {
             .update(|cx| {
                 let project_index = project_index.read(cx);
                 let query = "garbage in, garbage out";
-                project_index.search(vec![query.into()], 4, cx)
+                project_index.search(vec![query.into()], 4, SearchMode::Semantic, cx)
             })
             .await
             .unwrap();
@@ -440,6 +443,104 @@ mod tests {
         assert!(content.contains("garbage in, garbage out"));
     }
 
+    #[gpui::test]
+    async fn test_lexical_search(cx: &mut TestAppContext) {
+        cx.executor().allow_parking();
+
+        init_test(cx);
+
+        cx.update(|cx| {
+            // This functionality is staff-flagged.
+            cx.update_flags(true, vec![]);
+        });
+
+        let temp_dir = tempfile::tempdir().unwrap();
+
+        // Every chunk has the same embedding, so only the lexical index can tell them apart.
+        const UNEMBEDDABLE_QUERY: &str = "unembeddable unexpected token";
+        let mut semantic_index = SemanticDb::new(
+            temp_dir.path().into(),
+            Arc::new(TestEmbeddingProvider::new(16, |text| {
+                if text == UNEMBEDDABLE_QUERY {
+                    Err(anyhow!("embedding provider is unavailable"))
+                } else {
+                    Ok(Embedding::new(vec![0.6, 0.8]))
+                }
+            })),
+            &mut cx.to_async(),
+        )
+        .await
+        .unwrap();
+
+        let fs = FakeFs::new(cx.executor());
+        let project_path = Path::new("/fake_project");
+
+        fs.insert_tree(
+            project_path,
+            json!({
+                "config.txt": "Settings are read by parseConfig when the app starts.",
+                "errors.txt": "Failed to parse the configuration: unexpected token.",
+                "notes.txt": "Nothing to see here.",
+            }),
+        )
+        .await;
+
+        let project = Project::test(fs, [project_path], cx).await;
+
+        let project_index =
+            cx.update(|cx| semantic_index.create_project_index(project.clone(), cx));
+
+        cx.run_until_parked();
+        while cx
+            .update(|cx| semantic_index.remaining_summaries(&project.downgrade(), cx))
+            .unwrap()
+            > 0
+        {
+            cx.run_until_parked();
+        }
+
+        let search = |query: &str, mode, cx: &mut TestAppContext| {
+            cx.update(|cx| {
+                project_index
+                    .read(cx)
+                    .search(vec![query.into()], 4, mode, cx)
+            })
+        };
+
+        let results = search("parse_config", SearchMode::Lexical, cx)
+            .await
+            .unwrap();
+        assert_eq!(
+            results
+                .iter()
+                .map(|result| result.path.to_string_lossy().into_owned())
+                .collect::<Vec<_>>(),
+            ["config.txt", "errors.txt"]
+        );
+
+        let results = search("unexpected token", SearchMode::Lexical, cx)
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].path.as_ref(), Path::new("errors.txt"));
+
+        let results = search("unexpected token", SearchMode::Hybrid, cx)
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0].path.as_ref(), Path::new("errors.txt"));
+
+        // Hybrid search falls back to the lexical results when the query can't be embedded.
+        let results = search(UNEMBEDDABLE_QUERY, SearchMode::Hybrid, cx)
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].path.as_ref(), Path::new("errors.txt"));
+        assert!(search(UNEMBEDDABLE_QUERY, SearchMode::Semantic, cx)
+            .await
+            .is_err());
+    }
+
     #[gpui::test]
     async fn test_embed_files(cx: &mut TestAppContext) {
         cx.executor().allow_parking();
@@ -498,7 +599,7 @@ mod tests {
 
         let mut embedded_files_rx = embed_files_task.files;
         let mut embedded_files = Vec::new();
-        while let Some((embedded_file, _)) = embedded_files_rx.next().await {
+        while let Some((embedded_file, _, _)) = embedded_files_rx.next().await {
             embedded_files.push(embedded_file);
         }
 
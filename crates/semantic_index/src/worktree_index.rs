@@ -1,6 +1,7 @@
 use crate::embedding::EmbeddingProvider;
 use crate::embedding_index::EmbeddingIndex;
 use crate::indexing::IndexingEntrySet;
+use crate::lexical_index::LexicalIndex;
 use crate::summary_index::SummaryIndex;
 use anyhow::Result;
 use feature_flags::{AutoCommand, FeatureFlagAppExt};
@@ -63,12 +64,31 @@ impl WorktreeIndex {
                         let embedding_index = {
                             let db_name = worktree_abs_path.to_string_lossy();
                             let db = db_connection.create_database(&mut txn, Some(&db_name))?;
+                            let lexical_index = {
+                                // Prefixed like the summary databases below, so the names don't
+                                // conflict with the embeddings database, named after the abs path.
+                                let postings_db_name = format!(
+                                    "lexical-postings-{}",
+                                    worktree_abs_path.to_string_lossy()
+                                );
+                                let files_db_name = format!(
+                                    "lexical-files-{}",
+                                    worktree_abs_path.to_string_lossy()
+                                );
+                                LexicalIndex::new(
+                                    db_connection
+                                        .create_database(&mut txn, Some(&postings_db_name))?,
+                                    db_connection
+                                        .create_database(&mut txn, Some(&files_db_name))?,
+                                )
+                            };
 
                             EmbeddingIndex::new(
                                 worktree_for_index,
                                 embedding_fs,
                                 db_connection.clone(),
                                 db,
+                                lexical_index,
                                 language_registry,
                                 embedding_provider,
                                 Arc::clone(&entries_being_indexed),
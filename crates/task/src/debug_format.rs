@@ -0,0 +1,361 @@
+use std::path::PathBuf;
+
+use collections::{HashMap, HashSet};
+use schemars::{gen::SchemaSettings, JsonSchema};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::{
+    task_template::{
+        substitute_all_template_variables_in_map, substitute_all_template_variables_in_str,
+        substitute_all_template_variables_in_vec,
+    },
+    TaskContext, VariableName,
+};
+
+/// How a debug session gets hold of the program to debug.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DebugRequestKind {
+    /// Start the program (default).
+    #[default]
+    Launch,
+    /// Attach to a program that is already running.
+    Attach,
+}
+
+/// A template of a debug session, like a `launch` configuration of VS Code.
+/// Uses the same [`VariableName`] substitutions as [`crate::TaskTemplate`] in all of its strings,
+/// and needs to be resolved with a [`TaskContext`] before starting a session.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct DebugTemplate {
+    /// Human readable name of the configuration to display in the UI.
+    pub label: String,
+    /// Command that starts the debug adapter, which talks the Debug Adapter Protocol over its stdio.
+    pub adapter: String,
+    /// Arguments to the debug adapter command.
+    #[serde(default)]
+    pub adapter_args: Vec<String>,
+    /// Whether to launch the program or attach to a running one.
+    #[serde(default)]
+    pub request: DebugRequestKind,
+    /// Program to launch, passed to the debug adapter as `program`.
+    #[serde(default)]
+    pub program: Option<String>,
+    /// Arguments to the program, passed to the debug adapter as `args`.
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// Current working directory of the debug adapter and the program, defaults to current project root.
+    #[serde(default)]
+    pub cwd: Option<String>,
+    /// Env overrides for the program, passed to the debug adapter as `env`.
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    /// Process to attach to, passed to the debug adapter as `processId`.
+    #[serde(default)]
+    pub process_id: Option<u32>,
+    /// Any other arguments of the `launch` or `attach` request, which are specific to each debug adapter.
+    #[serde(default)]
+    pub configuration: Map<String, Value>,
+}
+
+/// A group of debug templates defined in a JSON file.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct DebugTemplates(pub Vec<DebugTemplate>);
+
+impl DebugTemplates {
+    /// Generates JSON schema of debug JSON template format.
+    pub fn generate_json_schema() -> serde_json_lenient::Value {
+        let schema = SchemaSettings::draft07()
+            .with(|settings| settings.option_add_null_type = false)
+            .into_generator()
+            .into_root_schema_for::<Self>();
+
+        serde_json_lenient::to_value(schema).unwrap()
+    }
+}
+
+/// A final form of the [`DebugTemplate`], resolved with a particular [`TaskContext`] and ready to start a debug session.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ResolvedDebugTemplate {
+    /// Human readable name of the session, after all substitutions are made.
+    pub label: String,
+    /// Command that starts the debug adapter.
+    pub adapter: String,
+    /// Arguments to the debug adapter command.
+    pub adapter_args: Vec<String>,
+    /// Whether to launch the program or attach to a running one.
+    pub request: DebugRequestKind,
+    /// Arguments of the `launch` or `attach` request.
+    pub request_args: Value,
+    /// Current working directory to start the debug adapter in.
+    pub cwd: Option<PathBuf>,
+}
+
+impl DebugTemplate {
+    /// Replaces all task variables in the template with the values from the [`TaskContext`] given.
+    ///
+    /// Returns `None` if the template has no label or adapter, or uses an unknown Zed variable.
+    pub fn resolve(&self, cx: &TaskContext) -> Option<ResolvedDebugTemplate> {
+        if self.label.trim().is_empty() || self.adapter.trim().is_empty() {
+            return None;
+        }
+
+        let mut variable_names = HashMap::default();
+        let mut substituted_variables = HashSet::default();
+        let task_variables = cx
+            .task_variables
+            .0
+            .iter()
+            .map(|(key, value)| {
+                let key_string = key.to_string();
+                if !variable_names.contains_key(&key_string) {
+                    variable_names.insert(key_string.clone(), key.clone());
+                }
+                (key_string, value.as_str())
+            })
+            .collect::<HashMap<_, _>>();
+        let mut substitute = |template: &str| {
+            substitute_all_template_variables_in_str(
+                template,
+                &task_variables,
+                &variable_names,
+                &mut substituted_variables,
+            )
+        };
+
+        let label = substitute(&self.label)?;
+        let adapter = substitute(&self.adapter)?;
+        let cwd = match self.cwd.as_deref() {
+            Some(cwd) => Some(PathBuf::from(substitute(cwd)?)),
+            None => None,
+        }
+        .or(cx.cwd.clone());
+        let program = match self.program.as_deref() {
+            Some(program) => Some(substitute(program)?),
+            None => None,
+        };
+
+        let mut request_args = Map::new();
+        for (key, value) in &self.configuration {
+            request_args.insert(
+                key.clone(),
+                substitute_all_template_variables_in_value(
+                    value,
+                    &task_variables,
+                    &variable_names,
+                    &mut substituted_variables,
+                )?,
+            );
+        }
+        if let Some(program) = program {
+            request_args.insert("program".into(), program.into());
+        }
+        if !self.args.is_empty() {
+            let args = substitute_all_template_variables_in_vec(
+                &self.args,
+                &task_variables,
+                &variable_names,
+                &mut substituted_variables,
+            )?;
+            request_args.insert("args".into(), args.into());
+        }
+        if let Some(cwd) = &cwd {
+            request_args.insert("cwd".into(), cwd.to_string_lossy().into_owned().into());
+        }
+        if !self.env.is_empty() {
+            let env = substitute_all_template_variables_in_map(
+                &self.env,
+                &task_variables,
+                &variable_names,
+                &mut substituted_variables,
+            )?;
+            request_args.insert(
+                "env".into(),
+                env.into_iter()
+                    .map(|(key, value)| (key, Value::String(value)))
+                    .collect::<Map<_, _>>()
+                    .into(),
+            );
+        }
+        if let Some(process_id) = self.process_id {
+            request_args.insert("processId".into(), process_id.into());
+        }
+
+        let adapter_args = substitute_all_template_variables_in_vec(
+            &self.adapter_args,
+            &task_variables,
+            &variable_names,
+            &mut substituted_variables,
+        )?;
+
+        Some(ResolvedDebugTemplate {
+            label,
+            adapter,
+            adapter_args,
+            request: self.request,
+            request_args: Value::Object(request_args),
+            cwd,
+        })
+    }
+}
+
+fn substitute_all_template_variables_in_value(
+    value: &Value,
+    task_variables: &HashMap<String, &str>,
+    variable_names: &HashMap<String, VariableName>,
+    substituted_variables: &mut HashSet<VariableName>,
+) -> Option<Value> {
+    Some(match value {
+        Value::String(template) => Value::String(substitute_all_template_variables_in_str(
+            template,
+            task_variables,
+            variable_names,
+            substituted_variables,
+        )?),
+        Value::Array(values) => Value::Array(
+            values
+                .iter()
+                .map(|value| {
+                    substitute_all_template_variables_in_value(
+                        value,
+                        task_variables,
+                        variable_names,
+                        substituted_variables,
+                    )
+                })
+                .collect::<Option<_>>()?,
+        ),
+        Value::Object(values) => Value::Object(
+            values
+                .iter()
+                .map(|(key, value)| {
+                    Some((
+                        key.clone(),
+                        substitute_all_template_variables_in_value(
+                            value,
+                            task_variables,
+                            variable_names,
+                            substituted_variables,
+                        )?,
+                    ))
+                })
+                .collect::<Option<_>>()?,
+        ),
+        value => value.clone(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::TaskVariables;
+
+    use super::*;
+
+    fn context() -> TaskContext {
+        let mut task_variables = TaskVariables::default();
+        task_variables.insert(VariableName::WorktreeRoot, "/project".into());
+        task_variables.insert(VariableName::File, "/project/src/main.py".into());
+        TaskContext {
+            cwd: Some(PathBuf::from("/project")),
+            task_variables,
+            project_env: HashMap::default(),
+        }
+    }
+
+    #[test]
+    fn test_parse_debug_templates() {
+        let templates: DebugTemplates = serde_json_lenient::from_str(
+            r#"[
+                // Comments are allowed, like in tasks.json
+                {
+                    "label": "Debug current file",
+                    "adapter": "python3",
+                    "adapter_args": ["-m", "debugpy.adapter"],
+                    "program": "$ZED_FILE",
+                    "configuration": { "justMyCode": false }
+                },
+                {
+                    "label": "Attach",
+                    "adapter": "lldb-dap",
+                    "request": "attach",
+                    "process_id": 42
+                }
+            ]"#,
+        )
+        .unwrap();
+        assert_eq!(templates.0.len(), 2);
+        assert_eq!(templates.0[0].request, DebugRequestKind::Launch);
+        assert_eq!(templates.0[0].program.as_deref(), Some("$ZED_FILE"));
+        assert_eq!(templates.0[0].configuration["justMyCode"], json!(false));
+        assert_eq!(templates.0[1].request, DebugRequestKind::Attach);
+        assert_eq!(templates.0[1].process_id, Some(42));
+    }
+
+    #[test]
+    fn test_resolve_debug_template() {
+        let template = DebugTemplate {
+            label: "Debug $ZED_FILE".into(),
+            adapter: "python3".into(),
+            adapter_args: vec!["-m".into(), "debugpy.adapter".into()],
+            program: Some("$ZED_FILE".into()),
+            args: vec!["--root=$ZED_WORKTREE_ROOT".into()],
+            env: HashMap::from_iter([("ROOT".into(), "${ZED_WORKTREE_ROOT}".into())]),
+            configuration: json!({
+                "justMyCode": false,
+                "pathMappings": [{ "localRoot": "$ZED_WORKTREE_ROOT", "remoteRoot": "/app" }],
+            })
+            .as_object()
+            .unwrap()
+            .clone(),
+            ..DebugTemplate::default()
+        };
+
+        let resolved = template.resolve(&context()).unwrap();
+        assert_eq!(resolved.label, "Debug /project/src/main.py");
+        assert_eq!(resolved.adapter_args, ["-m", "debugpy.adapter"]);
+        assert_eq!(resolved.request, DebugRequestKind::Launch);
+        assert_eq!(resolved.cwd, Some(PathBuf::from("/project")));
+        assert_eq!(
+            resolved.request_args,
+            json!({
+                "justMyCode": false,
+                "pathMappings": [{ "localRoot": "/project", "remoteRoot": "/app" }],
+                "program": "/project/src/main.py",
+                "args": ["--root=/project"],
+                "cwd": "/project",
+                "env": { "ROOT": "/project" },
+            })
+        );
+    }
+
+    #[test]
+    fn test_resolve_invalid_debug_templates() {
+        let template = DebugTemplate {
+            label: "Debug".into(),
+            adapter: "lldb-dap".into(),
+            ..DebugTemplate::default()
+        };
+        assert!(template.resolve(&context()).is_some());
+
+        for invalid in [
+            DebugTemplate {
+                label: " ".into(),
+                ..template.clone()
+            },
+            DebugTemplate {
+                adapter: "".into(),
+                ..template.clone()
+            },
+            DebugTemplate {
+                program: Some("$ZED_UNKNOWN_VARIABLE".into()),
+                ..template.clone()
+            },
+        ] {
+            assert_eq!(invalid.resolve(&context()), None, "{invalid:?}");
+        }
+    }
+}
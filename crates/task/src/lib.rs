@@ -1,6 +1,8 @@
 //! Baseline interface of Tasks in Zed: all tasks in Zed are intended to use those for implementing their own logic.
 #![deny(missing_docs)]
 
+mod debug_format;
+mod problem_matcher;
 pub mod static_source;
 mod task_template;
 mod vscode_format;
@@ -13,7 +15,11 @@ use std::borrow::Cow;
 use std::path::PathBuf;
 use std::str::FromStr;
 
-pub use task_template::{HideStrategy, RevealStrategy, TaskTemplate, TaskTemplates};
+pub use debug_format::{DebugRequestKind, DebugTemplate, DebugTemplates, ResolvedDebugTemplate};
+pub use problem_matcher::{
+    Problem, ProblemMatcher, ProblemMatcherDefinition, ProblemPattern, ProblemSeverity,
+};
+pub use task_template::{DependsOrder, HideStrategy, RevealStrategy, TaskTemplate, TaskTemplates};
 pub use vscode_format::VsCodeTaskFile;
 pub use zed_actions::RevealTarget;
 
@@ -58,6 +64,8 @@ pub struct SpawnInTerminal {
     pub show_summary: bool,
     /// Whether to show the command line in the task output.
     pub show_command: bool,
+    /// Problem matchers to find problems in the task's output with, once it finishes.
+    pub problem_matchers: Vec<ProblemMatcher>,
 }
 
 /// A final form of the [`TaskTemplate`], that got resolved with a particualar [`TaskContext`] and now is ready to spawn the actual task.
@@ -75,6 +83,11 @@ pub struct ResolvedTask {
     pub resolved_label: String,
     /// Variables that were substituted during the task template resolution.
     substituted_variables: HashSet<VariableName>,
+    /// Tasks that need to finish successfully before this task is spawned.
+    pub dependencies: TaskDependencies,
+    /// Why the tasks listed in `depends_on` could not be resolved, in which case the task is still
+    /// listed, but cannot be spawned.
+    pub dependency_error: Option<String>,
     /// Further actions that need to take place after the resolved task is spawned,
     /// with all task variables resolved.
     pub resolved: Option<SpawnInTerminal>,
@@ -100,6 +113,15 @@ impl ResolvedTask {
     }
 }
 
+/// Resolved tasks that need to finish successfully before a [`ResolvedTask`] is spawned.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TaskDependencies {
+    /// Whether the dependencies are spawned all at once, or one after another.
+    pub order: DependsOrder,
+    /// The dependencies, in the order they are listed in the task template, each with its own dependencies.
+    pub tasks: Vec<ResolvedTask>,
+}
+
 /// Variables, available for use in [`TaskContext`] when a Zed's [`TaskTemplate`] gets resolved into a [`ResolvedTask`].
 /// Name of the variable must be a valid shell variable identifier, which generally means that it is
 /// a word  consisting only  of alphanumeric characters and underscores,
@@ -0,0 +1,483 @@
+//! Problem matchers find problems, like compiler errors and warnings, in the output of tasks.
+//!
+//! A matcher is a list of patterns, each matching one line of output: a problem is found when all of them
+//! match consecutive lines, collecting the details of the problem from the capture groups of the patterns.
+//! The format follows VS Code's problem matchers, see <https://code.visualstudio.com/docs/editor/tasks#_defining-a-problem-matcher>.
+use std::path::PathBuf;
+
+use regex::{Captures, Regex};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Either a built-in problem matcher, referenced by its name, or a custom one.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(untagged)]
+pub enum ProblemMatcherDefinition {
+    /// The name of a built-in problem matcher: `$rustc`, `$tsc`, `$gcc`, `$eslint-compact` or `$eslint-stylish`.
+    Builtin(String),
+    /// A custom problem matcher.
+    Custom(ProblemMatcher),
+}
+
+impl ProblemMatcherDefinition {
+    /// Returns the problem matcher defined, or `None` for unknown built-in matchers.
+    pub fn resolve(&self) -> Option<ProblemMatcher> {
+        match self {
+            Self::Builtin(name) => ProblemMatcher::builtin(name),
+            Self::Custom(matcher) => Some(matcher.clone()),
+        }
+    }
+}
+
+/// A matcher for the problems reported in the output of a task.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct ProblemMatcher {
+    /// The name of the tool reporting the problems, shown as the source of the diagnostics.
+    #[serde(default)]
+    pub owner: Option<String>,
+    /// The severity of the problems whose patterns do not capture one, defaults to `error`.
+    #[serde(default)]
+    pub severity: Option<ProblemSeverity>,
+    /// The patterns matching consecutive lines of output, that together describe a problem.
+    pub pattern: Vec<ProblemPattern>,
+}
+
+/// A regular expression matching a line of task output, and the capture groups holding the details of the problem.
+/// Group indices start at 1.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct ProblemPattern {
+    /// The regular expression to match the line with.
+    pub regexp: String,
+    /// The group with the path of the file with the problem, resolved against the task's working directory if relative.
+    #[serde(default)]
+    pub file: Option<usize>,
+    /// The group with the 1-based line of the problem.
+    #[serde(default)]
+    pub line: Option<usize>,
+    /// The group with the 1-based column of the problem.
+    #[serde(default)]
+    pub column: Option<usize>,
+    /// The group with the 1-based line the problem ends at.
+    #[serde(default)]
+    pub end_line: Option<usize>,
+    /// The group with the 1-based column the problem ends at.
+    #[serde(default)]
+    pub end_column: Option<usize>,
+    /// The group with the severity of the problem, like `error` or `warning`.
+    #[serde(default)]
+    pub severity: Option<usize>,
+    /// The group with the code of the problem, like the compiler's error code.
+    #[serde(default)]
+    pub code: Option<usize>,
+    /// The group with the message of the problem.
+    #[serde(default)]
+    pub message: Option<usize>,
+    /// Whether this last pattern of a matcher may match multiple lines in a row, each being a separate problem.
+    #[serde(default, rename = "loop")]
+    pub repeat: bool,
+}
+
+/// The severity of a [`Problem`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ProblemSeverity {
+    /// An error.
+    Error,
+    /// A warning.
+    Warning,
+    /// An informational message.
+    Info,
+    /// A hint.
+    Hint,
+}
+
+impl ProblemSeverity {
+    /// Parses the severity reported by a tool, like `error`, `Warning` or `note`.
+    pub fn parse(severity: &str) -> Option<Self> {
+        let severity = severity.trim().to_lowercase();
+        if severity.starts_with("err") || severity.starts_with("fatal") {
+            Some(Self::Error)
+        } else if severity.starts_with("warn") {
+            Some(Self::Warning)
+        } else if severity.starts_with("info") || severity == "note" {
+            Some(Self::Info)
+        } else if severity == "hint" || severity == "help" {
+            Some(Self::Hint)
+        } else {
+            None
+        }
+    }
+}
+
+/// A problem found in the output of a task.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Problem {
+    /// The path of the file with the problem, as printed by the task.
+    pub path: PathBuf,
+    /// The 1-based line of the problem.
+    pub line: u32,
+    /// The 1-based column of the problem.
+    pub column: u32,
+    /// The 1-based line the problem ends at, if reported.
+    pub end_line: Option<u32>,
+    /// The 1-based column the problem ends at, if reported.
+    pub end_column: Option<u32>,
+    /// The severity of the problem.
+    pub severity: ProblemSeverity,
+    /// The code of the problem, like the compiler's error code.
+    pub code: Option<String>,
+    /// The description of the problem.
+    pub message: String,
+    /// The tool that reported the problem.
+    pub source: Option<String>,
+}
+
+impl ProblemMatcher {
+    /// Returns the built-in problem matcher with the given name, like `$rustc`.
+    pub fn builtin(name: &str) -> Option<Self> {
+        let matcher = match name {
+            "$rustc" => Self {
+                owner: Some("rustc".to_string()),
+                severity: None,
+                pattern: vec![
+                    ProblemPattern {
+                        regexp: r"^(warning|warn|error)(?:\[(\S+?)\])?: (.*)$".to_string(),
+                        severity: Some(1),
+                        code: Some(2),
+                        message: Some(3),
+                        ..ProblemPattern::default()
+                    },
+                    ProblemPattern {
+                        regexp: r"^\s*-->\s*(.+?):(\d+):(\d+)\s*$".to_string(),
+                        file: Some(1),
+                        line: Some(2),
+                        column: Some(3),
+                        ..ProblemPattern::default()
+                    },
+                ],
+            },
+            "$tsc" => Self {
+                owner: Some("typescript".to_string()),
+                severity: None,
+                pattern: vec![ProblemPattern {
+                    regexp: r"^([^\s].*?)[\(:](\d+)[,:](\d+)(?:\):\s+|\s+-\s+)(error|warning|info)\s+TS(\d+)\s*:\s*(.*)$".to_string(),
+                    file: Some(1),
+                    line: Some(2),
+                    column: Some(3),
+                    severity: Some(4),
+                    code: Some(5),
+                    message: Some(6),
+                    ..ProblemPattern::default()
+                }],
+            },
+            "$gcc" => Self {
+                owner: Some("gcc".to_string()),
+                severity: None,
+                pattern: vec![ProblemPattern {
+                    regexp: r"^(.+?):(\d+):(\d+):\s+(?:fatal\s+)?(warning|error|note):\s+(.*)$"
+                        .to_string(),
+                    file: Some(1),
+                    line: Some(2),
+                    column: Some(3),
+                    severity: Some(4),
+                    message: Some(5),
+                    ..ProblemPattern::default()
+                }],
+            },
+            "$eslint-compact" => Self {
+                owner: Some("eslint".to_string()),
+                severity: None,
+                pattern: vec![ProblemPattern {
+                    regexp: r"^(.+):\sline\s(\d+),\scol\s(\d+),\s(Error|Warning|Info)\s-\s(.+)\s\((.+)\)$".to_string(),
+                    file: Some(1),
+                    line: Some(2),
+                    column: Some(3),
+                    severity: Some(4),
+                    message: Some(5),
+                    code: Some(6),
+                    ..ProblemPattern::default()
+                }],
+            },
+            "$eslint-stylish" => Self {
+                owner: Some("eslint".to_string()),
+                severity: None,
+                pattern: vec![
+                    ProblemPattern {
+                        regexp: r"^((?:[a-zA-Z]:)?[^\s].*)$".to_string(),
+                        file: Some(1),
+                        ..ProblemPattern::default()
+                    },
+                    ProblemPattern {
+                        regexp: r"^\s+(\d+):(\d+)\s+(error|warning|info)\s+(.+?)(?:\s\s+(\S+))?$"
+                            .to_string(),
+                        line: Some(1),
+                        column: Some(2),
+                        severity: Some(3),
+                        message: Some(4),
+                        code: Some(5),
+                        repeat: true,
+                        ..ProblemPattern::default()
+                    },
+                ],
+            },
+            _ => return None,
+        };
+        Some(matcher)
+    }
+
+    /// Finds all problems in the given lines of task output.
+    pub fn find_problems<'a>(&self, lines: impl IntoIterator<Item = &'a str>) -> Vec<Problem> {
+        let patterns = self
+            .pattern
+            .iter()
+            .filter_map(|pattern| match Regex::new(&pattern.regexp) {
+                Ok(regex) => Some((regex, pattern)),
+                Err(error) => {
+                    log::error!(
+                        "invalid problem matcher pattern {:?}: {error}",
+                        pattern.regexp
+                    );
+                    None
+                }
+            })
+            .collect::<Vec<_>>();
+        if patterns.is_empty() || patterns.len() != self.pattern.len() {
+            return Vec::new();
+        }
+
+        let lines = lines.into_iter().collect::<Vec<_>>();
+        let (last_regex, last_pattern) = patterns.last().expect("checked for no patterns above");
+        let mut problems = Vec::new();
+        let mut ix = 0;
+        'lines: while ix < lines.len() {
+            let mut details = ProblemDetails::default();
+            for (offset, (regex, pattern)) in patterns[..patterns.len() - 1].iter().enumerate() {
+                match lines.get(ix + offset).and_then(|line| regex.captures(line)) {
+                    Some(captures) => details.capture(pattern, &captures),
+                    None => {
+                        ix += 1;
+                        continue 'lines;
+                    }
+                }
+            }
+
+            let mut last_ix = ix + patterns.len() - 1;
+            let mut matched = false;
+            while let Some(captures) = lines
+                .get(last_ix)
+                .and_then(|line| last_regex.captures(line))
+            {
+                let mut details = details.clone();
+                details.capture(last_pattern, &captures);
+                problems.extend(details.into_problem(self));
+                matched = true;
+                last_ix += 1;
+                if !last_pattern.repeat {
+                    break;
+                }
+            }
+            ix = if matched { last_ix } else { ix + 1 };
+        }
+        problems
+    }
+}
+
+/// The details of a problem captured by the patterns matched so far.
+#[derive(Clone, Default)]
+struct ProblemDetails {
+    file: Option<String>,
+    line: Option<String>,
+    column: Option<String>,
+    end_line: Option<String>,
+    end_column: Option<String>,
+    severity: Option<String>,
+    code: Option<String>,
+    message: Option<String>,
+}
+
+impl ProblemDetails {
+    fn capture(&mut self, pattern: &ProblemPattern, captures: &Captures) {
+        let group = |index: Option<usize>| {
+            captures
+                .get(index?)
+                .map(|capture| capture.as_str().to_string())
+        };
+        let fields = [
+            (&mut self.file, pattern.file),
+            (&mut self.line, pattern.line),
+            (&mut self.column, pattern.column),
+            (&mut self.end_line, pattern.end_line),
+            (&mut self.end_column, pattern.end_column),
+            (&mut self.severity, pattern.severity),
+            (&mut self.code, pattern.code),
+            (&mut self.message, pattern.message),
+        ];
+        for (field, index) in fields {
+            if let Some(value) = group(index) {
+                *field = Some(value);
+            }
+        }
+    }
+
+    fn into_problem(self, matcher: &ProblemMatcher) -> Option<Problem> {
+        let number = |value: Option<String>| value?.trim().parse::<u32>().ok();
+        let file = self.file?;
+        let message = self.message?;
+        if file.trim().is_empty() || message.trim().is_empty() {
+            return None;
+        }
+        Some(Problem {
+            path: PathBuf::from(file.trim()),
+            line: number(self.line).unwrap_or(1).max(1),
+            column: number(self.column).unwrap_or(1).max(1),
+            end_line: number(self.end_line),
+            end_column: number(self.end_column),
+            severity: self
+                .severity
+                .as_deref()
+                .and_then(ProblemSeverity::parse)
+                .or(matcher.severity)
+                .unwrap_or(ProblemSeverity::Error),
+            code: self.code.filter(|code| !code.is_empty()),
+            message: message.trim().to_string(),
+            source: matcher.owner.clone(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn find_problems(matcher: &str, output: &str) -> Vec<Problem> {
+        ProblemMatcher::builtin(matcher)
+            .unwrap()
+            .find_problems(output.lines())
+    }
+
+    #[test]
+    fn test_rustc_problems() {
+        let output = r#"
+   Compiling example v0.1.0 (/work/example)
+warning: unused variable: `x`
+ --> src/main.rs:2:9
+  |
+2 |     let x = 1;
+  |         ^ help: if this is intentional, prefix it with an underscore: `_x`
+error[E0308]: mismatched types
+  --> src/lib.rs:10:5
+   |
+warning: `example` (bin "example") generated 1 warning
+error: could not compile `example` (bin "example") due to 1 previous error
+"#;
+        let problems = find_problems("$rustc", output);
+        assert_eq!(
+            problems,
+            vec![
+                Problem {
+                    path: PathBuf::from("src/main.rs"),
+                    line: 2,
+                    column: 9,
+                    end_line: None,
+                    end_column: None,
+                    severity: ProblemSeverity::Warning,
+                    code: None,
+                    message: "unused variable: `x`".to_string(),
+                    source: Some("rustc".to_string()),
+                },
+                Problem {
+                    path: PathBuf::from("src/lib.rs"),
+                    line: 10,
+                    column: 5,
+                    end_line: None,
+                    end_column: None,
+                    severity: ProblemSeverity::Error,
+                    code: Some("E0308".to_string()),
+                    message: "mismatched types".to_string(),
+                    source: Some("rustc".to_string()),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_single_line_problems() {
+        let problems = find_problems(
+            "$tsc",
+            "src/index.ts(3,7): error TS2322: Type 'string' is not assignable to type 'number'.",
+        );
+        assert_eq!(problems.len(), 1);
+        assert_eq!(problems[0].path, PathBuf::from("src/index.ts"));
+        assert_eq!((problems[0].line, problems[0].column), (3, 7));
+        assert_eq!(problems[0].code.as_deref(), Some("2322"));
+
+        let problems = find_problems(
+            "$gcc",
+            "main.c:5:12: warning: unused variable 'y' [-Wunused-variable]\nmain.c: In function 'main':",
+        );
+        assert_eq!(problems.len(), 1);
+        assert_eq!(problems[0].severity, ProblemSeverity::Warning);
+        assert_eq!(
+            problems[0].message,
+            "unused variable 'y' [-Wunused-variable]"
+        );
+
+        let problems = find_problems(
+            "$eslint-compact",
+            "/work/app.js: line 1, col 10, Error - 'foo' is defined but never used. (no-unused-vars)",
+        );
+        assert_eq!(problems.len(), 1);
+        assert_eq!(problems[0].code.as_deref(), Some("no-unused-vars"));
+    }
+
+    #[test]
+    fn test_looping_problems() {
+        let output = "
+/work/app.js
+  1:10  error    'foo' is defined but never used  no-unused-vars
+  2:1   warning  Unexpected console statement     no-console
+
+/work/other.js
+  4:2  error  Missing semicolon  semi
+
+✖ 3 problems (2 errors, 1 warning)
+";
+        let problems = find_problems("$eslint-stylish", output);
+        let summary = problems
+            .iter()
+            .map(|problem| {
+                (
+                    problem.path.to_string_lossy().to_string(),
+                    problem.line,
+                    problem.severity,
+                    problem.code.clone().unwrap_or_default(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            summary,
+            vec![
+                (
+                    "/work/app.js".to_string(),
+                    1,
+                    ProblemSeverity::Error,
+                    "no-unused-vars".to_string()
+                ),
+                (
+                    "/work/app.js".to_string(),
+                    2,
+                    ProblemSeverity::Warning,
+                    "no-console".to_string()
+                ),
+                (
+                    "/work/other.js".to_string(),
+                    4,
+                    ProblemSeverity::Error,
+                    "semi".to_string()
+                ),
+            ]
+        );
+    }
+}
@@ -9,8 +9,8 @@ use sha2::{Digest, Sha256};
 use util::{truncate_and_remove_front, ResultExt};
 
 use crate::{
-    ResolvedTask, RevealTarget, Shell, SpawnInTerminal, TaskContext, TaskId, VariableName,
-    ZED_VARIABLE_NAME_PREFIX,
+    ProblemMatcherDefinition, ResolvedTask, RevealTarget, Shell, SpawnInTerminal, TaskContext,
+    TaskDependencies, TaskId, VariableName, ZED_VARIABLE_NAME_PREFIX,
 };
 
 /// A template definition of a Zed task to run.
@@ -70,6 +70,30 @@ pub struct TaskTemplate {
     /// Whether to show the command line in the task output.
     #[serde(default = "default_true")]
     pub show_command: bool,
+    /// Labels of the tasks that need to finish successfully before this task is spawned.
+    /// Tasks with dependencies may omit the command, to only run their dependencies.
+    #[serde(default)]
+    pub depends_on: Vec<String>,
+    /// How to run the tasks listed in `depends_on`:
+    /// * `parallel` — spawn all of them at once (default)
+    /// * `sequence` — spawn them one after another, in the order listed, each once the previous one succeeded
+    #[serde(default)]
+    pub depends_order: DependsOrder,
+    /// Problem matchers that turn the task's output into project diagnostics, once the task finishes.
+    /// Either names of the built-in matchers, like `$rustc`, or custom matchers with their own patterns.
+    #[serde(default)]
+    pub problem_matchers: Vec<ProblemMatcherDefinition>,
+}
+
+/// How to run the dependencies of a task.
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DependsOrder {
+    /// Spawn all dependencies at once.
+    #[default]
+    Parallel,
+    /// Spawn the dependencies one after another, each once the previous one succeeded.
+    Sequence,
 }
 
 /// What to do with the terminal pane and tab, after the command was started.
@@ -112,6 +136,67 @@ impl TaskTemplates {
 
         serde_json_lenient::to_value(schema).unwrap()
     }
+
+    /// Resolves the given template with the [`TaskContext`] given, along with all tasks it depends on,
+    /// which are looked up by their labels among the templates in this group.
+    ///
+    /// Returns `None` if the template itself does not resolve, see [`TaskTemplate::resolve_task`],
+    /// and an error if any of its dependencies is unknown, does not resolve or depends on the task itself.
+    pub fn resolve_task(
+        &self,
+        template: &TaskTemplate,
+        id_base: &str,
+        cx: &TaskContext,
+    ) -> anyhow::Result<Option<ResolvedTask>> {
+        self.resolve_task_with_dependencies(template, id_base, cx, &mut Vec::new())
+    }
+
+    fn resolve_task_with_dependencies<'a>(
+        &'a self,
+        template: &'a TaskTemplate,
+        id_base: &str,
+        cx: &TaskContext,
+        dependents: &mut Vec<&'a str>,
+    ) -> anyhow::Result<Option<ResolvedTask>> {
+        let Some(mut resolved_task) = template.resolve_task(id_base, cx) else {
+            return Ok(None);
+        };
+        if template.depends_on.is_empty() {
+            return Ok(Some(resolved_task));
+        }
+
+        dependents.push(&template.label);
+        let mut tasks = Vec::with_capacity(template.depends_on.len());
+        for label in &template.depends_on {
+            if dependents.contains(&label.as_str()) {
+                bail!(
+                    "Task dependency cycle: {} -> {label}",
+                    dependents.join(" -> ")
+                );
+            }
+            let dependency = self
+                .0
+                .iter()
+                .find(|candidate| &candidate.label == label)
+                .with_context(|| {
+                    format!(
+                        "Task {:?} depends on unknown task {label:?}",
+                        template.label
+                    )
+                })?;
+            let resolved_dependency = self
+                .resolve_task_with_dependencies(dependency, id_base, cx, dependents)?
+                .with_context(|| format!("Failed to resolve task dependency {label:?}"))?;
+            tasks.push(resolved_dependency);
+        }
+        dependents.pop();
+
+        resolved_task.dependencies = TaskDependencies {
+            order: template.depends_order,
+            tasks,
+        };
+        Ok(Some(resolved_task))
+    }
 }
 
 impl TaskTemplate {
@@ -122,7 +207,9 @@ impl TaskTemplate {
     /// Every [`ResolvedTask`] gets a [`TaskId`], based on the `id_base` (to avoid collision with various task sources),
     /// and hashes of its template and [`TaskContext`], see [`ResolvedTask`] fields' documentation for more details.
     pub fn resolve_task(&self, id_base: &str, cx: &TaskContext) -> Option<ResolvedTask> {
-        if self.label.trim().is_empty() || self.command.trim().is_empty() {
+        if self.label.trim().is_empty()
+            || (self.command.trim().is_empty() && self.depends_on.is_empty())
+        {
             return None;
         }
 
@@ -217,11 +304,25 @@ impl TaskTemplate {
             env
         };
 
+        let problem_matchers = self
+            .problem_matchers
+            .iter()
+            .filter_map(|definition| {
+                let matcher = definition.resolve();
+                if matcher.is_none() {
+                    log::warn!("Unknown problem matcher {definition:?} in task {full_label:?}");
+                }
+                matcher
+            })
+            .collect();
+
         Some(ResolvedTask {
             id: id.clone(),
             substituted_variables,
             original_task: self.clone(),
             resolved_label: full_label.clone(),
+            dependencies: TaskDependencies::default(),
+            dependency_error: None,
             resolved: Some(SpawnInTerminal {
                 id,
                 cwd,
@@ -246,11 +347,25 @@ impl TaskTemplate {
                 shell: self.shell.clone(),
                 show_summary: self.show_summary,
                 show_command: self.show_command,
+                problem_matchers,
             }),
         })
     }
 }
 
+#[cfg(any(test, feature = "test-support"))]
+impl TaskTemplate {
+    /// A template running the command given, after the tasks with the `depends_on` labels finish.
+    pub fn test(label: &str, command: &str, depends_on: &[&str]) -> Self {
+        Self {
+            label: label.to_string(),
+            command: command.to_string(),
+            depends_on: depends_on.iter().map(|label| label.to_string()).collect(),
+            ..Self::default()
+        }
+    }
+}
+
 const MAX_DISPLAY_VARIABLE_LENGTH: usize = 15;
 
 fn truncate_variables(task_variables: &HashMap<String, &str>) -> HashMap<String, String> {
@@ -272,7 +387,7 @@ fn to_hex_hash(object: impl Serialize) -> anyhow::Result<String> {
     Ok(hex::encode(hasher.finalize()))
 }
 
-fn substitute_all_template_variables_in_str<A: AsRef<str>>(
+pub(crate) fn substitute_all_template_variables_in_str<A: AsRef<str>>(
     template_str: &str,
     task_variables: &HashMap<String, A>,
     variable_names: &HashMap<String, VariableName>,
@@ -309,7 +424,7 @@ fn substitute_all_template_variables_in_str<A: AsRef<str>>(
     Some(substituted_string.into_owned())
 }
 
-fn substitute_all_template_variables_in_vec(
+pub(crate) fn substitute_all_template_variables_in_vec(
     template_strs: &[String],
     task_variables: &HashMap<String, &str>,
     variable_names: &HashMap<String, VariableName>,
@@ -328,7 +443,7 @@ fn substitute_all_template_variables_in_vec(
     Some(expanded)
 }
 
-fn substitute_all_template_variables_in_map(
+pub(crate) fn substitute_all_template_variables_in_map(
     keys_and_values: &HashMap<String, String>,
     task_variables: &HashMap<String, &str>,
     variable_names: &HashMap<String, VariableName>,
@@ -396,6 +511,66 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_resolving_task_dependencies() {
+        let task = TaskTemplate::test;
+        let templates = TaskTemplates(vec![
+            task("build", "cargo build", &[]),
+            task("lint", "cargo clippy", &["build"]),
+            TaskTemplate {
+                depends_order: DependsOrder::Sequence,
+                ..task("check all", "", &["lint", "build"])
+            },
+            task("a", "echo a", &["b"]),
+            task("b", "echo b", &["a"]),
+            task("broken", "echo broken", &["missing"]),
+        ]);
+        let resolve = |label: &str| {
+            let template = templates.0.iter().find(|task| task.label == label).unwrap();
+            templates.resolve_task(template, TEST_ID_BASE, &TaskContext::default())
+        };
+
+        let check_all = resolve("check all").unwrap().unwrap();
+        assert_eq!(
+            check_all.resolved.as_ref().unwrap().command,
+            "",
+            "tasks with dependencies do not need a command"
+        );
+        assert_eq!(check_all.dependencies.order, DependsOrder::Sequence);
+        let dependency_labels = check_all
+            .dependencies
+            .tasks
+            .iter()
+            .map(|task| task.resolved_label.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(dependency_labels, ["lint", "build"]);
+        let lint = &check_all.dependencies.tasks[0];
+        assert_eq!(lint.dependencies.order, DependsOrder::Parallel);
+        assert_eq!(lint.dependencies.tasks.len(), 1);
+        assert_eq!(lint.dependencies.tasks[0].resolved_label, "build");
+        assert!(check_all.dependencies.tasks[1]
+            .dependencies
+            .tasks
+            .is_empty());
+
+        let cycle_error = resolve("a").unwrap_err().to_string();
+        assert!(
+            cycle_error.contains("a -> b -> a"),
+            "unexpected error: {cycle_error}"
+        );
+        assert!(resolve("broken").is_err());
+        assert_eq!(
+            TaskTemplates::default()
+                .resolve_task(
+                    &task("", "echo", &[]),
+                    TEST_ID_BASE,
+                    &TaskContext::default()
+                )
+                .unwrap(),
+            None
+        );
+    }
+
     #[test]
     fn test_template_cwd_resolution() {
         let task_without_cwd = TaskTemplate {
@@ -1,9 +1,12 @@
-use anyhow::bail;
+use anyhow::{anyhow, bail};
 use collections::HashMap;
 use serde::Deserialize;
 use util::ResultExt;
 
-use crate::{TaskTemplate, TaskTemplates, VariableName};
+use crate::{
+    DependsOrder, ProblemMatcher, ProblemMatcherDefinition, ProblemPattern, ProblemSeverity,
+    TaskTemplate, TaskTemplates, VariableName,
+};
 
 #[derive(Clone, Debug, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
@@ -41,6 +44,97 @@ enum Command {
     },
 }
 
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(untagged)]
+enum OneOrMany<T> {
+    One(T),
+    Many(Vec<T>),
+}
+
+impl<T> OneOrMany<T> {
+    fn into_vec(self) -> Vec<T> {
+        match self {
+            Self::One(item) => vec![item],
+            Self::Many(items) => items,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(untagged)]
+enum VsCodeProblemMatcher {
+    Name(String),
+    Definition(VsCodeProblemMatcherDefinition),
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+struct VsCodeProblemMatcherDefinition {
+    base: Option<String>,
+    owner: Option<String>,
+    severity: Option<String>,
+    pattern: Option<OneOrMany<VsCodeProblemPattern>>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+struct VsCodeProblemPattern {
+    regexp: String,
+    file: Option<usize>,
+    line: Option<usize>,
+    column: Option<usize>,
+    end_line: Option<usize>,
+    end_column: Option<usize>,
+    severity: Option<usize>,
+    code: Option<usize>,
+    message: Option<usize>,
+    #[serde(default, rename = "loop")]
+    repeat: bool,
+}
+
+impl VsCodeProblemMatcher {
+    /// Converts the matcher into Zed's format, skipping the built-in matchers Zed does not know.
+    /// Matchers extending a built-in one with `base` are replaced by the built-in matcher.
+    fn into_zed_format(self) -> Option<ProblemMatcherDefinition> {
+        match self {
+            Self::Name(name) => Self::builtin(name),
+            Self::Definition(VsCodeProblemMatcherDefinition {
+                pattern: Some(pattern),
+                owner,
+                severity,
+                ..
+            }) => Some(ProblemMatcherDefinition::Custom(ProblemMatcher {
+                owner,
+                severity: severity.as_deref().and_then(ProblemSeverity::parse),
+                pattern: pattern
+                    .into_vec()
+                    .into_iter()
+                    .map(|pattern| ProblemPattern {
+                        regexp: pattern.regexp,
+                        file: pattern.file,
+                        line: pattern.line,
+                        column: pattern.column,
+                        end_line: pattern.end_line,
+                        end_column: pattern.end_column,
+                        severity: pattern.severity,
+                        code: pattern.code,
+                        message: pattern.message,
+                        repeat: pattern.repeat,
+                    })
+                    .collect(),
+            })),
+            Self::Definition(VsCodeProblemMatcherDefinition {
+                base: Some(base), ..
+            }) => Self::builtin(base),
+            Self::Definition(_) => None,
+        }
+    }
+
+    fn builtin(name: String) -> Option<ProblemMatcherDefinition> {
+        ProblemMatcher::builtin(&name).map(|_| ProblemMatcherDefinition::Builtin(name))
+    }
+}
+
 type VsCodeEnvVariable = String;
 type ZedEnvVariable = String;
 
@@ -85,21 +179,54 @@ impl EnvVariableReplacer {
 
 impl VsCodeTaskDefinition {
     fn into_zed_format(self, replacer: &EnvVariableReplacer) -> anyhow::Result<TaskTemplate> {
-        if self.other_attributes.contains_key("dependsOn") {
-            bail!("Encountered unsupported `dependsOn` key during deserialization");
-        }
-        // `type` might not be set in e.g. tasks that use `dependsOn`; we still want to deserialize the whole object though (hence command is an Option),
-        // as that way we can provide more specific description of why deserialization failed.
-        // E.g. if the command is missing due to `dependsOn` presence, we can check other_attributes first before doing this (and provide nice error message)
-        // catch-all if on value.command presence.
-        let Some(command) = self.command else {
-            bail!("Missing `type` field in task");
+        let depends_on = match self.other_attributes.get("dependsOn") {
+            None => Vec::new(),
+            Some(serde_json_lenient::Value::String(label)) => vec![label.clone()],
+            Some(serde_json_lenient::Value::Array(labels)) => labels
+                .iter()
+                .map(|label| {
+                    label.as_str().map(str::to_owned).ok_or_else(|| {
+                        anyhow!("Only task labels are supported in `dependsOn`, got {label}")
+                    })
+                })
+                .collect::<anyhow::Result<_>>()?,
+            Some(other) => bail!("Unsupported `dependsOn` value: {other}"),
         };
-
-        let (command, args) = match command {
-            Command::Npm { script } => ("npm".to_owned(), vec!["run".to_string(), script]),
-            Command::Shell { command, args } => (command, args),
-            Command::Gulp { task } => ("gulp".to_owned(), vec![task]),
+        let depends_order = match self
+            .other_attributes
+            .get("dependsOrder")
+            .and_then(|order| order.as_str())
+        {
+            Some("sequence") => DependsOrder::Sequence,
+            _ => DependsOrder::Parallel,
+        };
+        // Problem matchers Zed does not support are skipped, the task is still useful without them.
+        let problem_matchers = self
+            .other_attributes
+            .get("problemMatcher")
+            .and_then(|problem_matcher| {
+                serde_json_lenient::from_value::<OneOrMany<VsCodeProblemMatcher>>(
+                    problem_matcher.clone(),
+                )
+                .map_err(|e| anyhow!("Unsupported `problemMatcher` value: {e}"))
+                .log_err()
+            })
+            .map(|problem_matchers| {
+                problem_matchers
+                    .into_vec()
+                    .into_iter()
+                    .filter_map(VsCodeProblemMatcher::into_zed_format)
+                    .collect()
+            })
+            .unwrap_or_default();
+        // `type` might not be set in e.g. tasks that use `dependsOn`; we still want to deserialize the whole object though (hence command is an Option).
+        // Such tasks only run their dependencies.
+        let (command, args) = match self.command {
+            Some(Command::Npm { script }) => ("npm".to_owned(), vec!["run".to_string(), script]),
+            Some(Command::Shell { command, args }) => (command, args),
+            Some(Command::Gulp { task }) => ("gulp".to_owned(), vec![task]),
+            None if !depends_on.is_empty() => (String::new(), Vec::new()),
+            None => bail!("Missing `type` field in task"),
         };
         // Per VSC docs, only `command`, `args` and `options` support variable substitution.
         let command = replacer.replace(&command);
@@ -108,6 +235,9 @@ impl VsCodeTaskDefinition {
             label: self.label,
             command,
             args,
+            depends_on,
+            depends_order,
+            problem_matchers,
             ..Default::default()
         };
         if let Some(options) = self.options {
@@ -155,11 +285,15 @@ mod tests {
 
     use crate::{
         vscode_format::{Command, VsCodeTaskDefinition},
-        TaskTemplate, TaskTemplates, VsCodeTaskFile,
+        ProblemMatcherDefinition, TaskTemplate, TaskTemplates, VsCodeTaskFile,
     };
 
     use super::EnvVariableReplacer;
 
+    fn builtin(name: &str) -> Vec<ProblemMatcherDefinition> {
+        vec![ProblemMatcherDefinition::Builtin(name.to_string())]
+    }
+
     fn compare_without_other_attributes(lhs: VsCodeTaskDefinition, rhs: VsCodeTaskDefinition) {
         assert_eq!(
             VsCodeTaskDefinition {
@@ -257,6 +391,7 @@ mod tests {
                 label: "gulp: tests".to_string(),
                 command: "npm".to_string(),
                 args: vec!["run".to_string(), "build:tests:notypecheck".to_string()],
+                problem_matchers: builtin("$tsc"),
                 ..Default::default()
             },
             TaskTemplate {
@@ -274,12 +409,14 @@ mod tests {
                 label: "npm: build:compiler".to_string(),
                 command: "npm".to_string(),
                 args: vec!["run".to_string(), "build:compiler".to_string()],
+                problem_matchers: builtin("$tsc"),
                 ..Default::default()
             },
             TaskTemplate {
                 label: "npm: build:tests".to_string(),
                 command: "npm".to_string(),
                 args: vec!["run".to_string(), "build:tests:notypecheck".to_string()],
+                problem_matchers: builtin("$tsc"),
                 ..Default::default()
             },
         ];
@@ -366,22 +503,41 @@ mod tests {
                 label: "Build Extension".to_string(),
                 command: "npm".to_string(),
                 args: vec!["run".to_string(), "build".to_string()],
+                problem_matchers: builtin("$tsc"),
                 ..Default::default()
             },
             TaskTemplate {
                 label: "Build Server".to_string(),
                 command: "cargo build --package rust-analyzer".to_string(),
+                problem_matchers: builtin("$rustc"),
                 ..Default::default()
             },
             TaskTemplate {
                 label: "Build Server (Release)".to_string(),
                 command: "cargo build --release --package rust-analyzer".to_string(),
+                problem_matchers: builtin("$rustc"),
                 ..Default::default()
             },
             TaskTemplate {
                 label: "Pretest".to_string(),
                 command: "npm".to_string(),
                 args: vec!["run".to_string(), "pretest".to_string()],
+                problem_matchers: builtin("$tsc"),
+                ..Default::default()
+            },
+            TaskTemplate {
+                label: "Build Server and Extension".to_string(),
+                depends_on: vec!["Build Server".to_string(), "Build Extension".to_string()],
+                problem_matchers: builtin("$rustc"),
+                ..Default::default()
+            },
+            TaskTemplate {
+                label: "Build Server (Release) and Extension".to_string(),
+                depends_on: vec![
+                    "Build Server (Release)".to_string(),
+                    "Build Extension".to_string(),
+                ],
+                problem_matchers: builtin("$rustc"),
                 ..Default::default()
             },
         ];
@@ -0,0 +1,441 @@
+//! The thread moving bytes between the PTY and the terminal.
+//!
+//! This replaces Alacritty's event loop, whose parser drops the sequences it doesn't handle, so
+//! that the output can also be parsed for shell integration sequences. Both parsers are fed the
+//! output byte by byte, and each sequence is recorded as soon as it ends, with the cursor where the
+//! output preceding it left it.
+
+use std::{
+    borrow::Cow,
+    collections::VecDeque,
+    io::{self, ErrorKind, Read, Write},
+    sync::{
+        mpsc::{self, Receiver, Sender},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+    time::Instant,
+};
+
+use alacritty_terminal::{
+    event::{Event as AlacTermEvent, EventListener, Notify, OnResize},
+    event_loop::Msg,
+    sync::FairMutex,
+    tty::{ChildEvent, EventedPty, EventedReadWrite, Pty},
+    vte::ansi::Processor,
+    Term,
+};
+use parking_lot::Mutex;
+use polling::{Event, Events, PollMode, Poller};
+
+use crate::shell_integration::{SequenceParser, ShellIntegration};
+
+/// The key the PTY's reads and writes are polled with. Alacritty polls for the child's events with
+/// another key.
+const PTY_READ_WRITE_KEY: usize = 0;
+const READ_BUFFER_SIZE: usize = 0x10_0000;
+/// How much output is parsed while holding the terminal, before letting it be rendered.
+const MAX_LOCKED_READ: usize = u16::MAX as usize;
+
+pub(crate) struct EventLoop<T: EventListener> {
+    poll: Arc<Poller>,
+    pty: Pty,
+    rx: Receiver<Msg>,
+    tx: Sender<Msg>,
+    term: Arc<FairMutex<Term<T>>>,
+    shell_integration: Arc<Mutex<ShellIntegration>>,
+    event_proxy: T,
+    /// Whether the output written before the child exited is still parsed.
+    drain_on_exit: bool,
+}
+
+/// Sends messages to the event loop, waking it up.
+pub(crate) struct Notifier {
+    tx: Sender<Msg>,
+    poll: Arc<Poller>,
+}
+
+/// The input waiting to be written to the PTY.
+#[derive(Default)]
+struct WriteQueue {
+    chunks: VecDeque<Cow<'static, [u8]>>,
+    /// How much of the first chunk was written.
+    written: usize,
+}
+
+struct State {
+    processor: Processor,
+    sequence_parser: SequenceParser,
+    write_queue: WriteQueue,
+}
+
+impl<T: EventListener + Send + 'static> EventLoop<T> {
+    pub fn new(
+        term: Arc<FairMutex<Term<T>>>,
+        shell_integration: Arc<Mutex<ShellIntegration>>,
+        event_proxy: T,
+        pty: Pty,
+        drain_on_exit: bool,
+    ) -> io::Result<Self> {
+        let (tx, rx) = mpsc::channel();
+        Ok(Self {
+            poll: Arc::new(Poller::new()?),
+            pty,
+            rx,
+            tx,
+            term,
+            shell_integration,
+            event_proxy,
+            drain_on_exit,
+        })
+    }
+
+    pub fn notifier(&self) -> Notifier {
+        Notifier {
+            tx: self.tx.clone(),
+            poll: self.poll.clone(),
+        }
+    }
+
+    pub fn spawn(mut self) -> JoinHandle<()> {
+        thread::Builder::new()
+            .name("terminal pty".to_string())
+            .spawn(move || {
+                if let Err(error) = self.run() {
+                    log::error!("terminal event loop failed: {error}");
+                }
+                self.pty.deregister(&self.poll).ok();
+            })
+            .expect("failed to spawn the terminal event loop")
+    }
+
+    fn run(&mut self) -> io::Result<()> {
+        let mut state = State {
+            processor: Processor::new(),
+            sequence_parser: SequenceParser::new(),
+            write_queue: WriteQueue::default(),
+        };
+        let mut buf = vec![0; READ_BUFFER_SIZE];
+        let mut interest = Event::readable(PTY_READ_WRITE_KEY);
+        // SAFETY: The PTY is deregistered before it is dropped.
+        unsafe { self.pty.register(&self.poll, interest, PollMode::Level)? };
+
+        let mut events = Events::new();
+        loop {
+            // Wake up when a synchronized update times out.
+            let sync_deadline = state.processor.sync_timeout().sync_timeout();
+            let timeout =
+                sync_deadline.map(|deadline| deadline.saturating_duration_since(Instant::now()));
+
+            events.clear();
+            if let Err(error) = self.poll.wait(&mut events, timeout) {
+                if error.kind() == ErrorKind::Interrupted {
+                    continue;
+                }
+                return Err(error);
+            }
+
+            if sync_deadline.is_some_and(|deadline| deadline <= Instant::now()) {
+                state.processor.stop_sync(&mut *self.term.lock());
+                self.event_proxy.send_event(AlacTermEvent::Wakeup);
+            }
+
+            if !self.drain_messages(&mut state) {
+                return Ok(());
+            }
+
+            for event in events.iter() {
+                if event.key != PTY_READ_WRITE_KEY {
+                    if let Some(ChildEvent::Exited(exit_code)) = self.pty.next_child_event() {
+                        if let Some(exit_code) = exit_code {
+                            self.event_proxy
+                                .send_event(AlacTermEvent::ChildExit(exit_code));
+                        }
+                        if self.drain_on_exit {
+                            self.pty_read(&mut state, &mut buf).ok();
+                        }
+                        self.term.lock().exit();
+                        self.event_proxy.send_event(AlacTermEvent::Wakeup);
+                        return Ok(());
+                    }
+                    continue;
+                }
+
+                // Don't read from or write to a PTY that was closed.
+                if event.is_interrupt() {
+                    continue;
+                }
+                if event.readable {
+                    if let Err(error) = self.pty_read(&mut state, &mut buf) {
+                        // Reading fails with `EIO` once the child hangs up on Linux, the
+                        // child's exit follows.
+                        #[cfg(target_os = "linux")]
+                        if error.raw_os_error() == Some(libc::EIO) {
+                            continue;
+                        }
+                        return Err(error);
+                    }
+                }
+                if event.writable {
+                    self.pty_write(&mut state.write_queue)?;
+                }
+            }
+
+            let needs_write = !state.write_queue.chunks.is_empty();
+            if needs_write != interest.writable {
+                interest.writable = needs_write;
+                self.pty.reregister(&self.poll, interest, PollMode::Level)?;
+            }
+        }
+    }
+
+    /// Handles the pending messages, returning whether the event loop should keep running.
+    fn drain_messages(&mut self, state: &mut State) -> bool {
+        while let Ok(msg) = self.rx.try_recv() {
+            match msg {
+                Msg::Input(input) => state.write_queue.chunks.push_back(input),
+                Msg::Resize(window_size) => self.pty.on_resize(window_size),
+                Msg::Shutdown => return false,
+            }
+        }
+        true
+    }
+
+    fn pty_read(&mut self, state: &mut State, buf: &mut [u8]) -> io::Result<()> {
+        let mut unprocessed = 0;
+        let mut processed = 0;
+        // Reserve the next lock of the terminal for parsing.
+        let _lease = self.term.lease();
+        let mut term = None;
+        loop {
+            match self.pty.reader().read(&mut buf[unprocessed..]) {
+                // Received on macOS and Windows once there is nothing more to read.
+                Ok(0) if unprocessed == 0 => break,
+                Ok(len) => unprocessed += len,
+                Err(error) => match error.kind() {
+                    ErrorKind::Interrupted | ErrorKind::WouldBlock => {
+                        if unprocessed == 0 {
+                            break;
+                        }
+                    }
+                    _ => return Err(error),
+                },
+            }
+
+            let term = match &mut term {
+                Some(term) => term,
+                None => term.insert(match self.term.try_lock_unfair() {
+                    Some(term) => term,
+                    // Block once the buffer is full, otherwise keep reading until it's free.
+                    None if unprocessed >= buf.len() => self.term.lock_unfair(),
+                    None => continue,
+                }),
+            };
+
+            let mut shell_integration = self.shell_integration.lock();
+            for &byte in &buf[..unprocessed] {
+                state.processor.advance(&mut **term, byte);
+                shell_integration.count_scrolled_lines(&**term);
+                if let Some(sequence) = state.sequence_parser.advance(byte) {
+                    shell_integration.record(sequence, &**term);
+                }
+            }
+            drop(shell_integration);
+
+            processed += unprocessed;
+            unprocessed = 0;
+            if processed >= MAX_LOCKED_READ {
+                break;
+            }
+        }
+
+        if processed > 0 {
+            self.event_proxy.send_event(AlacTermEvent::Wakeup);
+        }
+        Ok(())
+    }
+
+    fn pty_write(&mut self, write_queue: &mut WriteQueue) -> io::Result<()> {
+        while let Some(chunk) = write_queue.chunks.front() {
+            match self.pty.writer().write(&chunk[write_queue.written..]) {
+                Ok(0) => break,
+                Ok(len) => {
+                    write_queue.written += len;
+                    if write_queue.written == chunk.len() {
+                        write_queue.chunks.pop_front();
+                        write_queue.written = 0;
+                    }
+                }
+                Err(error) => match error.kind() {
+                    ErrorKind::Interrupted | ErrorKind::WouldBlock => break,
+                    _ => return Err(error),
+                },
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Notifier {
+    pub fn send(&self, msg: Msg) {
+        if self.tx.send(msg).is_ok() {
+            self.poll.notify().ok();
+        }
+    }
+}
+
+impl Notify for Notifier {
+    fn notify<B: Into<Cow<'static, [u8]>>>(&self, bytes: B) {
+        let bytes = bytes.into();
+        if !bytes.is_empty() {
+            self.send(Msg::Input(bytes));
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::{collections::HashMap, time::Duration};
+
+    use alacritty_terminal::{
+        grid::Dimensions,
+        index::{Column, Line},
+        term::Config,
+        tty::{self, Options, Shell},
+    };
+    use futures::channel::mpsc::{unbounded, UnboundedReceiver};
+    use gpui::{px, size};
+
+    use super::*;
+    use crate::{CommandMark, CommandStatus, TerminalSize, ZedListener};
+
+    struct TestLoop {
+        term: Arc<FairMutex<Term<ZedListener>>>,
+        shell_integration: Arc<Mutex<ShellIntegration>>,
+        events: UnboundedReceiver<AlacTermEvent>,
+        notifier: Notifier,
+        thread: JoinHandle<()>,
+    }
+
+    /// Runs the event loop for a shell running the given script.
+    fn spawn_event_loop(script: &str) -> TestLoop {
+        let (events_tx, events) = unbounded();
+        let term = Arc::new(FairMutex::new(Term::new(
+            Config::default(),
+            &TerminalSize::default(),
+            ZedListener(events_tx.clone()),
+        )));
+        let shell_integration = Arc::new(Mutex::new(ShellIntegration::default()));
+        let options = Options {
+            shell: Some(Shell::new(
+                "/bin/sh".to_string(),
+                vec!["-c".to_string(), script.to_string()],
+            )),
+            working_directory: None,
+            hold: false,
+            env: HashMap::default(),
+        };
+        let pty = tty::new(&options, TerminalSize::default().into(), 0).unwrap();
+        let event_loop = EventLoop::new(
+            term.clone(),
+            shell_integration.clone(),
+            ZedListener(events_tx),
+            pty,
+            true,
+        )
+        .unwrap();
+        TestLoop {
+            term,
+            shell_integration,
+            events,
+            notifier: event_loop.notifier(),
+            thread: event_loop.spawn(),
+        }
+    }
+
+    fn wait_until(description: &str, mut condition: impl FnMut() -> bool) {
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while !condition() {
+            assert!(
+                Instant::now() < deadline,
+                "timed out waiting for {description}"
+            );
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    fn screen_text(term: &FairMutex<Term<ZedListener>>) -> String {
+        let term = term.lock();
+        (0..term.screen_lines() as i32)
+            .map(|line| {
+                term.grid()[Line(line)][..Column(term.columns())]
+                    .iter()
+                    .map(|cell| cell.c)
+                    .collect::<String>()
+                    .trim_end()
+                    .to_string()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+            .trim_end()
+            .to_string()
+    }
+
+    #[test]
+    fn test_read_and_write() {
+        let test_loop = spawn_event_loop(
+            r#"printf '\033]133;A\007$ \033]133;B\007'; read line; printf '\033]133;C\007%s\n\033]133;D;3\007' "$line"; exec cat"#,
+        );
+        wait_until("the prompt", || screen_text(&test_loop.term) == "$");
+
+        test_loop.notifier.notify(&b"echo\n"[..]);
+        wait_until("the command to finish", || {
+            test_loop.shell_integration.lock().last_output().is_some()
+        });
+        assert_eq!(screen_text(&test_loop.term), "$ echo\necho");
+        let shell_integration = test_loop.shell_integration.lock();
+        assert_eq!(
+            shell_integration
+                .command_marks(0..10, 0)
+                .collect::<Vec<_>>(),
+            [CommandMark {
+                line: Line(0),
+                status: CommandStatus::Failed,
+            }]
+        );
+        assert_eq!(shell_integration.last_output(), Some(1..2));
+        drop(shell_integration);
+
+        test_loop.notifier.send(Msg::Shutdown);
+        wait_until("the event loop to stop", || test_loop.thread.is_finished());
+    }
+
+    #[test]
+    fn test_resize() {
+        let test_loop = spawn_event_loop("read line; stty size; exec cat");
+        let terminal_size = TerminalSize::new(px(10.), px(5.), size(px(100.), px(50.)));
+        test_loop.notifier.send(Msg::Resize(terminal_size.into()));
+        test_loop.notifier.notify(&b"\n"[..]);
+        wait_until("the new size", || {
+            screen_text(&test_loop.term).contains("5 20")
+        });
+
+        test_loop.notifier.send(Msg::Shutdown);
+        wait_until("the event loop to stop", || test_loop.thread.is_finished());
+    }
+
+    #[test]
+    fn test_child_exit() {
+        let mut test_loop = spawn_event_loop("printf 'last words'; exit 7");
+        wait_until("the event loop to stop", || test_loop.thread.is_finished());
+        assert_eq!(screen_text(&test_loop.term), "last words");
+
+        let mut exit_code = None;
+        while let Ok(Some(event)) = test_loop.events.try_next() {
+            if let AlacTermEvent::ChildExit(code) = event {
+                exit_code = Some(code);
+            }
+        }
+        assert_eq!(exit_code, Some(7));
+    }
+}
@@ -0,0 +1,262 @@
+//! Converts the content of the terminal to text with the escape sequences reproducing its colors,
+//! so that it can be restored into another terminal.
+
+use std::{cmp, fmt::Write as _, ops::Range};
+
+use alacritty_terminal::{
+    event::EventListener,
+    grid::Dimensions,
+    index::{Column, Line, Point},
+    term::{
+        cell::{Cell, Flags},
+        TermMode,
+    },
+    vte::ansi::{ClearMode, Color, Handler, NamedColor, Processor},
+    Term,
+};
+
+/// The cell flags set with Select Graphic Rendition sequences, and their parameters.
+const SGR_FLAGS: &[(Flags, &str)] = &[
+    (Flags::BOLD, "1"),
+    (Flags::DIM, "2"),
+    (Flags::ITALIC, "3"),
+    (Flags::UNDERLINE, "4"),
+    (Flags::DOUBLE_UNDERLINE, "21"),
+    (Flags::UNDERCURL, "4:3"),
+    (Flags::DOTTED_UNDERLINE, "4:4"),
+    (Flags::DASHED_UNDERLINE, "4:5"),
+    (Flags::INVERSE, "7"),
+    (Flags::HIDDEN, "8"),
+    (Flags::STRIKEOUT, "9"),
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Style {
+    fg: Color,
+    bg: Color,
+    flags: Flags,
+}
+
+impl Default for Style {
+    fn default() -> Self {
+        Self {
+            fg: Color::Named(NamedColor::Foreground),
+            bg: Color::Named(NamedColor::Background),
+            flags: Flags::empty(),
+        }
+    }
+}
+
+impl Style {
+    fn of(cell: &Cell) -> Self {
+        Self {
+            fg: cell.fg,
+            bg: cell.bg,
+            flags: SGR_FLAGS
+                .iter()
+                .filter(|(flag, _)| cell.flags.contains(*flag))
+                .fold(Flags::empty(), |flags, (flag, _)| flags | *flag),
+        }
+    }
+
+    fn write(&self, output: &mut String) {
+        output.push_str("\x1b[0");
+        for (flag, parameter) in SGR_FLAGS {
+            if self.flags.contains(*flag) {
+                output.push(';');
+                output.push_str(parameter);
+            }
+        }
+        write_color(output, self.fg, false);
+        write_color(output, self.bg, true);
+        output.push('m');
+    }
+}
+
+fn write_color(output: &mut String, color: Color, background: bool) {
+    match color {
+        Color::Named(color) => {
+            let color = color as usize;
+            let parameter = match color {
+                0..=7 if background => 40 + color,
+                0..=7 => 30 + color,
+                8..=15 if background => 100 + color - 8,
+                8..=15 => 90 + color - 8,
+                // The default colors.
+                _ => return,
+            };
+            write!(output, ";{parameter}").ok();
+        }
+        Color::Indexed(index) => {
+            write!(output, ";{};5;{index}", if background { 48 } else { 38 }).ok();
+        }
+        Color::Spec(rgb) => {
+            write!(
+                output,
+                ";{};2;{};{};{}",
+                if background { 48 } else { 38 },
+                rgb.r,
+                rgb.g,
+                rgb.b
+            )
+            .ok();
+        }
+    }
+}
+
+fn is_blank(cell: &Cell) -> bool {
+    cell.c == ' ' && cell.zerowidth().is_none() && Style::of(cell) == Style::default()
+}
+
+/// Serializes the given lines of the terminal, ending each line that is not wrapped with a newline.
+pub(crate) fn serialize_lines<T: EventListener>(term: &Term<T>, lines: Range<Line>) -> String {
+    let mut output = String::new();
+    let mut style = Style::default();
+    let columns = term.columns();
+    for line in (lines.start.0..lines.end.0).map(Line) {
+        let cells = &term.grid()[line][..Column(columns)];
+        let wrapped = cells
+            .last()
+            .is_some_and(|cell| cell.flags.contains(Flags::WRAPLINE));
+        let len = if wrapped {
+            cells.len()
+        } else {
+            cells
+                .iter()
+                .rposition(|cell| !is_blank(cell))
+                .map_or(0, |ix| ix + 1)
+        };
+
+        for cell in &cells[..len] {
+            if cell
+                .flags
+                .intersects(Flags::WIDE_CHAR_SPACER | Flags::LEADING_WIDE_CHAR_SPACER)
+            {
+                continue;
+            }
+            let cell_style = Style::of(cell);
+            if cell_style != style {
+                cell_style.write(&mut output);
+                style = cell_style;
+            }
+            output.push(cell.c);
+            if let Some(zerowidth) = cell.zerowidth() {
+                output.extend(zerowidth);
+            }
+        }
+
+        if !wrapped {
+            // Don't let the background color spill over the next line.
+            if style != Style::default() {
+                output.push_str("\x1b[0m");
+                style = Style::default();
+            }
+            output.push_str("\r\n");
+        }
+    }
+    if style != Style::default() {
+        output.push_str("\x1b[0m");
+    }
+    output
+}
+
+/// Serializes the content above the cursor, up to the given number of lines, or returns `None` if
+/// the terminal is showing the alternate screen.
+pub(crate) fn scrollback<T: EventListener>(term: &Term<T>, max_lines: usize) -> Option<String> {
+    if term.mode().contains(TermMode::ALT_SCREEN) {
+        return None;
+    }
+
+    let end = term.grid().cursor.point.line;
+    let max_lines = max_lines.min(term.total_lines()) as i32;
+    let start = cmp::max(term.topmost_line(), Line(end.0 - max_lines));
+    Some(serialize_lines(term, start..end))
+}
+
+/// Writes the serialized scrollback above the output the shell has written so far, returning
+/// whether it was restored.
+pub(crate) fn restore<T: EventListener>(term: &mut Term<T>, scrollback: &str) -> bool {
+    if scrollback.is_empty() || term.mode().contains(TermMode::ALT_SCREEN) {
+        return false;
+    }
+
+    let cursor = term.grid().cursor.clone();
+    let shell_output = serialize_lines(term, term.topmost_line()..Line(cursor.point.line.0 + 1));
+    let shell_output = shell_output.strip_suffix("\r\n").unwrap_or(&shell_output);
+
+    term.clear_screen(ClearMode::Saved);
+    term.grid_mut().reset_region(..);
+    term.grid_mut().cursor.point = Point::new(Line(0), Column(0));
+    write_content(term, scrollback);
+    if !scrollback.ends_with('\n') {
+        write_content(term, "\r\n");
+    }
+    write_content(term, shell_output);
+
+    // Keep the cursor where the shell expects it on the line, with the style it set.
+    let line = term.grid().cursor.point.line;
+    term.grid_mut().cursor = cursor;
+    term.grid_mut().cursor.point.line = line;
+    true
+}
+
+/// Writes serialized content into the terminal, at its cursor.
+pub(crate) fn write_content<T: EventListener>(term: &mut Term<T>, content: &str) {
+    let mut processor: Processor = Processor::new();
+    for byte in content.bytes() {
+        processor.advance(&mut *term, byte);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alacritty_terminal::{event::VoidListener, term::Config};
+    use gpui::{px, size};
+
+    use super::*;
+    use crate::TerminalSize;
+
+    #[test]
+    fn test_serialize_written_content() {
+        let terminal_size = TerminalSize::new(px(10.), px(5.), size(px(50.), px(100.)));
+        let mut term = Term::new(Config::default(), &terminal_size, VoidListener);
+        let content = "plain\r\n\x1b[0;1;31mbold red\x1b[0m and\r\n0123456789wrapped\r\n\x1b[0;38;2;1;2;3;48;5;4mcolors\x1b[0m\r\n";
+        write_content(&mut term, content);
+
+        let cursor_line = term.grid().cursor.point.line;
+        assert_eq!(serialize_lines(&term, Line(0)..cursor_line), content);
+    }
+
+    #[test]
+    fn test_restore_scrollback() {
+        let terminal_size = TerminalSize::new(px(10.), px(5.), size(px(50.), px(100.)));
+        let mut term = Term::new(Config::default(), &terminal_size, VoidListener);
+        let lines = (0..30)
+            .map(|ix| format!("line {ix}\r\n"))
+            .collect::<Vec<_>>();
+        write_content(&mut term, &lines.concat());
+        write_content(&mut term, "$ ");
+
+        // Only the last lines above the cursor are kept.
+        let saved = scrollback(&term, 8).unwrap();
+        assert_eq!(saved, lines[22..].concat());
+        assert_eq!(scrollback(&term, 100).unwrap(), lines.concat());
+
+        let mut restored = Term::new(Config::default(), &terminal_size, VoidListener);
+        write_content(&mut restored, "\x1b[0;1mshell\x1b[0m\r\n$ ");
+        assert!(restore(&mut restored, &saved));
+
+        // The scrollback is written above the shell's output, which keeps its cursor.
+        let cursor = restored.grid().cursor.point;
+        assert_eq!(cursor, Point::new(Line(9), Column(2)));
+        assert_eq!(
+            serialize_lines(&restored, restored.topmost_line()..Line(cursor.line.0 + 1)),
+            format!("{saved}\x1b[0;1mshell\x1b[0m\r\n$\r\n")
+        );
+
+        // Nothing is saved or restored while the alternate screen is shown.
+        write_content(&mut restored, "\x1b[?1049h");
+        assert_eq!(scrollback(&restored, 8), None);
+        assert!(!restore(&mut restored, &saved));
+    }
+}
@@ -0,0 +1,675 @@
+//! Shell integration through the `OSC 133` semantic prompt sequences.
+//!
+//! Shells configured to emit these sequences mark where their prompt starts, where the output of
+//! the entered command starts and with which exit code the command finished. They may also report
+//! their working directory with `OSC 7`. The `OSC 633` sequences of VS Code's shell integration
+//! scripts are understood as well. Alacritty ignores all of these, so the event loop parses the
+//! output for them too, recording the cursor position once the output preceding each sequence has
+//! been parsed into the terminal.
+
+use std::{ops::Range, path::PathBuf};
+
+use alacritty_terminal::{
+    event::EventListener,
+    grid::Dimensions,
+    index::{Column, Line},
+    term::{
+        cell::{Cell, Flags},
+        TermMode,
+    },
+    vte::{Parser, Perform},
+    Term,
+};
+use collections::VecDeque;
+
+/// How many commands are remembered per terminal.
+const MAX_COMMANDS: usize = 1000;
+
+/// A shell integration sequence written by the shell.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum ShellSequence {
+    /// `OSC 133 ; A`, written before the prompt.
+    PromptStart,
+    /// `OSC 133 ; B`, written after the prompt, where the user starts typing the command.
+    CommandStart,
+    /// `OSC 133 ; C`, written after the command is entered, before its output.
+    OutputStart,
+    /// `OSC 133 ; D [; exit code]`, written after the command finished.
+    CommandFinished { exit_code: Option<i32> },
+    /// `OSC 7 ; file://host/path` or `OSC 633 ; P ; Cwd=path`, reporting the working directory
+    /// of the shell.
+    WorkingDirectory(PathBuf),
+}
+
+impl ShellSequence {
+    fn parse(params: &[&[u8]]) -> Option<Self> {
+        let params = params
+            .iter()
+            .map(|param| std::str::from_utf8(param).ok())
+            .collect::<Option<Vec<_>>>()?;
+        match params.as_slice() {
+            ["133" | "633", mark, arguments @ ..] => match *mark {
+                "A" => Some(Self::PromptStart),
+                "B" => Some(Self::CommandStart),
+                "C" => Some(Self::OutputStart),
+                "D" => Some(Self::CommandFinished {
+                    exit_code: arguments
+                        .first()
+                        .and_then(|exit_code| exit_code.parse().ok()),
+                }),
+                "P" => {
+                    // The value may contain semicolons, which split it into several parameters.
+                    let property = arguments.join(";");
+                    let path = property.strip_prefix("Cwd=")?;
+                    unescape_property_value(path)
+                        .map(|path| Self::WorkingDirectory(PathBuf::from(path)))
+                }
+                _ => None,
+            },
+            ["7", url @ ..] => parse_file_url(&url.join(";")).map(Self::WorkingDirectory),
+            _ => None,
+        }
+    }
+}
+
+fn parse_file_url(url: &str) -> Option<PathBuf> {
+    let url = url.strip_prefix("file://")?;
+    // Skip the host name, shells report the host they run on.
+    let path = percent_decode(&url[url.find('/')?..])?;
+    #[cfg(windows)]
+    let path = match path.strip_prefix('/') {
+        Some(path) if path.as_bytes().get(1) == Some(&b':') => path.to_string(),
+        _ => path,
+    };
+    Some(PathBuf::from(path))
+}
+
+fn percent_decode(text: &str) -> Option<String> {
+    let mut decoded = Vec::with_capacity(text.len());
+    let mut bytes = text.bytes();
+    while let Some(byte) = bytes.next() {
+        if byte == b'%' {
+            let high = (bytes.next()? as char).to_digit(16)?;
+            let low = (bytes.next()? as char).to_digit(16)?;
+            decoded.push((high * 16 + low) as u8);
+        } else {
+            decoded.push(byte);
+        }
+    }
+    String::from_utf8(decoded).ok()
+}
+
+/// Decodes the `\\` and `\xHH` escapes of `OSC 633` property values.
+fn unescape_property_value(value: &str) -> Option<String> {
+    let mut decoded = Vec::with_capacity(value.len());
+    let mut bytes = value.bytes();
+    while let Some(byte) = bytes.next() {
+        if byte == b'\\' {
+            match bytes.next()? {
+                b'\\' => decoded.push(b'\\'),
+                b'x' => {
+                    let high = (bytes.next()? as char).to_digit(16)?;
+                    let low = (bytes.next()? as char).to_digit(16)?;
+                    decoded.push((high * 16 + low) as u8);
+                }
+                _ => return None,
+            }
+        } else {
+            decoded.push(byte);
+        }
+    }
+    String::from_utf8(decoded).ok()
+}
+
+/// Finds the shell integration sequences in the output of the PTY, which is fed to it a byte at a
+/// time, like to the terminal's own parser.
+pub(crate) struct SequenceParser {
+    parser: Parser,
+    performer: SequencePerformer,
+}
+
+#[derive(Default)]
+struct SequencePerformer {
+    sequence: Option<ShellSequence>,
+}
+
+impl Perform for SequencePerformer {
+    fn osc_dispatch(&mut self, params: &[&[u8]], _bell_terminated: bool) {
+        self.sequence = ShellSequence::parse(params);
+    }
+}
+
+impl SequenceParser {
+    pub fn new() -> Self {
+        Self {
+            parser: Parser::new(),
+            performer: SequencePerformer::default(),
+        }
+    }
+
+    /// Parses the next byte of output, returning the sequence it ends.
+    pub fn advance(&mut self, byte: u8) -> Option<ShellSequence> {
+        self.parser.advance(&mut self.performer, byte);
+        self.performer.sequence.take()
+    }
+}
+
+/// A prompt displayed in the terminal, with the status of the command entered at it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CommandMark {
+    pub line: Line,
+    pub status: CommandStatus,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CommandStatus {
+    /// The command is being typed or is still running.
+    Pending,
+    Succeeded,
+    Failed,
+    /// The command finished without the shell reporting its exit code.
+    Unknown,
+}
+
+/// A command run in the shell. Lines are counted from the top of the scrollback.
+#[derive(Debug)]
+struct ShellCommand {
+    prompt_line: usize,
+    output_start_line: Option<usize>,
+    /// The line after the output, once the command finished.
+    output_end_line: Option<usize>,
+    exit_code: Option<i32>,
+    working_directory: Option<PathBuf>,
+}
+
+impl ShellCommand {
+    fn status(&self) -> CommandStatus {
+        match (self.output_end_line, self.exit_code) {
+            (None, _) => CommandStatus::Pending,
+            (Some(_), Some(0)) => CommandStatus::Succeeded,
+            (Some(_), Some(_)) => CommandStatus::Failed,
+            (Some(_), None) => CommandStatus::Unknown,
+        }
+    }
+
+    fn map_lines(&mut self, mut f: impl FnMut(usize) -> usize) {
+        self.prompt_line = f(self.prompt_line);
+        self.output_start_line = self.output_start_line.map(&mut f);
+        self.output_end_line = self.output_end_line.map(&mut f);
+    }
+}
+
+/// The commands run in a terminal, as reported by its shell.
+///
+/// Commands are only tracked on the primary screen. They must be reconciled with the terminal
+/// before their lines are used, since those shift once the scrollback is full.
+#[derive(Default)]
+pub(crate) struct ShellIntegration {
+    commands: VecDeque<ShellCommand>,
+    working_directory: Option<PathBuf>,
+    /// How many lines scrolled into the scrollback, including the ones it dropped since.
+    scrolled_lines: usize,
+    /// How many lines the scrollback dropped, as of the last reconciliation.
+    dropped_lines: usize,
+    /// The newest line of the scrollback, see [`line_address`].
+    newest_scrollback_line: Option<usize>,
+    /// The logical line of the cursor before the terminal resized.
+    resize_cursor_line: usize,
+}
+
+impl ShellIntegration {
+    /// Records a sequence at the terminal's cursor, after the output preceding it was parsed.
+    pub fn record<T: EventListener>(&mut self, sequence: ShellSequence, term: &Term<T>) {
+        if let ShellSequence::WorkingDirectory(path) = sequence {
+            if let Some(command) = self
+                .commands
+                .back_mut()
+                .filter(|command| command.output_start_line.is_none())
+            {
+                command.working_directory = Some(path.clone());
+            }
+            self.working_directory = Some(path);
+            return;
+        }
+        if term.mode().contains(TermMode::ALT_SCREEN) {
+            return;
+        }
+
+        self.reconcile(term);
+        let cursor = term.grid().cursor.point;
+        let line = absolute_line(cursor.line, term.history_size());
+        match sequence {
+            ShellSequence::PromptStart => {
+                // A prompt without a command was interrupted, or redrawn.
+                if self
+                    .commands
+                    .back()
+                    .is_some_and(|command| command.output_start_line.is_none())
+                {
+                    self.commands.pop_back();
+                }
+                // Commands below the cursor were overwritten.
+                while self
+                    .commands
+                    .back()
+                    .is_some_and(|command| command.prompt_line >= line)
+                {
+                    self.commands.pop_back();
+                }
+                self.commands.push_back(ShellCommand {
+                    prompt_line: line,
+                    output_start_line: None,
+                    output_end_line: None,
+                    exit_code: None,
+                    working_directory: self.working_directory.clone(),
+                });
+                if self.commands.len() > MAX_COMMANDS {
+                    self.commands.pop_front();
+                }
+            }
+            ShellSequence::CommandStart => {}
+            ShellSequence::OutputStart => {
+                if let Some(command) = self
+                    .commands
+                    .back_mut()
+                    .filter(|command| command.output_start_line.is_none())
+                {
+                    command.output_start_line = Some(line);
+                }
+            }
+            ShellSequence::CommandFinished { exit_code } => {
+                if let Some(command) = self
+                    .commands
+                    .back_mut()
+                    .filter(|command| command.output_end_line.is_none())
+                {
+                    if let Some(output_start_line) = command.output_start_line {
+                        let end_line = if cursor.column.0 > 0 { line + 1 } else { line };
+                        command.output_end_line = Some(end_line.max(output_start_line));
+                        command.exit_code = exit_code;
+                    }
+                }
+            }
+            ShellSequence::WorkingDirectory(_) => {}
+        }
+    }
+
+    /// Counts the lines scrolled into the scrollback by the output parsed since the last call.
+    ///
+    /// Must be called after every byte of output, since a byte scrolls at most a screen of lines,
+    /// which is how far the previous newest line of the scrollback is looked for.
+    pub fn count_scrolled_lines<T: EventListener>(&mut self, term: &Term<T>) {
+        if term.mode().contains(TermMode::ALT_SCREEN) {
+            return;
+        }
+        let newest_line = newest_scrollback_line(term);
+        if newest_line == self.newest_scrollback_line {
+            return;
+        }
+
+        let history_size = term.history_size();
+        let scrolled_lines = self
+            .newest_scrollback_line
+            .and_then(|previous_newest_line| {
+                (0..history_size.min(term.screen_lines() + 1)).find(|&offset| {
+                    line_address(term, Line(-1 - offset as i32)) == previous_newest_line
+                })
+            })
+            // The scrollback was cleared or was empty, or dropped the line already.
+            .unwrap_or(history_size);
+        self.scrolled_lines =
+            (self.scrolled_lines + scrolled_lines).max(self.dropped_lines + history_size);
+        self.newest_scrollback_line = newest_line;
+    }
+
+    /// Shifts the commands by the lines dropped from the top of the scrollback since the last call.
+    pub fn reconcile<T: EventListener>(&mut self, term: &Term<T>) {
+        if term.mode().contains(TermMode::ALT_SCREEN) {
+            return;
+        }
+
+        let dropped_lines = self.scrolled_lines.saturating_sub(term.history_size());
+        let newly_dropped_lines = dropped_lines.saturating_sub(self.dropped_lines);
+        self.dropped_lines = dropped_lines;
+        if newly_dropped_lines > 0 {
+            self.commands
+                .retain(|command| command.prompt_line >= newly_dropped_lines);
+            for command in &mut self.commands {
+                command.map_lines(|line| line - newly_dropped_lines);
+            }
+        }
+    }
+
+    /// Converts the lines of the commands to logical lines, before the terminal resizes and
+    /// reflows its content.
+    pub fn start_resize<T: EventListener>(&mut self, term: &Term<T>) {
+        if term.mode().contains(TermMode::ALT_SCREEN) {
+            // The primary screen reflows out of our reach.
+            self.clear();
+            return;
+        }
+
+        self.reconcile(term);
+        let logical_lines = logical_lines(term);
+        let logical_line_count = logical_lines.last().map_or(0, |line| line + 1);
+        self.resize_cursor_line = cursor_logical_line(term, &logical_lines);
+        for command in &mut self.commands {
+            command.map_lines(|line| {
+                logical_lines
+                    .get(line)
+                    .copied()
+                    .unwrap_or(logical_line_count + line - logical_lines.len())
+            });
+        }
+    }
+
+    /// Converts the logical lines of the commands back, after the terminal resized and reflowed
+    /// its content.
+    pub fn finish_resize<T: EventListener>(&mut self, term: &Term<T>) {
+        // Resizing moves lines between the screen and the scrollback without scrolling.
+        if !term.mode().contains(TermMode::ALT_SCREEN) {
+            self.scrolled_lines = self.dropped_lines + term.history_size();
+            self.newest_scrollback_line = newest_scrollback_line(term);
+        }
+        if self.commands.is_empty() {
+            return;
+        }
+
+        let logical_lines = logical_lines(term);
+        // The cursor keeps its content, so it only moves up by the lines dropped from a full
+        // scrollback to make room for the lines pushed into it.
+        let dropped_lines = self
+            .resize_cursor_line
+            .saturating_sub(cursor_logical_line(term, &logical_lines));
+        self.commands
+            .retain(|command| command.prompt_line >= dropped_lines);
+        let logical_line_starts = logical_lines
+            .iter()
+            .enumerate()
+            .filter(|(line, logical_line)| *line == 0 || logical_lines[line - 1] != **logical_line)
+            .map(|(line, _)| line)
+            .collect::<Vec<_>>();
+        for command in &mut self.commands {
+            command.map_lines(|logical_line| {
+                let logical_line = logical_line - dropped_lines;
+                logical_line_starts
+                    .get(logical_line)
+                    .copied()
+                    .unwrap_or(logical_lines.len() + logical_line - logical_line_starts.len())
+            });
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.commands.clear();
+    }
+
+    /// The prompt closest above the given line.
+    pub fn previous_prompt(&self, line: usize) -> Option<usize> {
+        self.commands
+            .iter()
+            .rev()
+            .map(|command| command.prompt_line)
+            .find(|prompt_line| *prompt_line < line)
+    }
+
+    /// The prompt closest below the given line.
+    pub fn next_prompt(&self, line: usize) -> Option<usize> {
+        self.commands
+            .iter()
+            .map(|command| command.prompt_line)
+            .find(|prompt_line| *prompt_line > line)
+    }
+
+    /// The lines of the output of the last finished command that printed anything.
+    pub fn last_output(&self) -> Option<Range<usize>> {
+        self.commands.iter().rev().find_map(|command| {
+            let output = command.output_start_line?..command.output_end_line?;
+            (!output.is_empty()).then_some(output)
+        })
+    }
+
+    /// The working directory of the command displayed at the given line.
+    pub fn working_directory_at(&self, line: usize) -> Option<PathBuf> {
+        self.commands
+            .iter()
+            .rev()
+            .find(|command| command.prompt_line <= line)?
+            .working_directory
+            .clone()
+    }
+
+    /// The prompts displayed in the given lines.
+    pub fn command_marks(
+        &self,
+        lines: Range<usize>,
+        history_size: usize,
+    ) -> impl Iterator<Item = CommandMark> + '_ {
+        self.commands
+            .iter()
+            .filter(move |command| lines.contains(&command.prompt_line))
+            .map(move |command| CommandMark {
+                line: grid_line(command.prompt_line, history_size),
+                status: command.status(),
+            })
+    }
+}
+
+/// Converts a line of the terminal's grid to a line counted from the top of the scrollback.
+pub(crate) fn absolute_line(line: Line, history_size: usize) -> usize {
+    (line.0 + history_size as i32).max(0) as usize
+}
+
+/// Converts a line counted from the top of the scrollback to a line of the terminal's grid.
+pub(crate) fn grid_line(line: usize, history_size: usize) -> Line {
+    Line(line as i32 - history_size as i32)
+}
+
+/// Identifies a line of the terminal's grid by the address of its cells, which move along with it
+/// when it scrolls, until the terminal resizes.
+fn line_address<T: EventListener>(term: &Term<T>, line: Line) -> usize {
+    &term.grid()[line][Column(0)] as *const Cell as usize
+}
+
+fn newest_scrollback_line<T: EventListener>(term: &Term<T>) -> Option<usize> {
+    (term.history_size() > 0).then(|| line_address(term, Line(-1)))
+}
+
+fn cursor_logical_line<T: EventListener>(term: &Term<T>, logical_lines: &[usize]) -> usize {
+    let cursor_line = absolute_line(term.grid().cursor.point.line, term.history_size());
+    logical_lines.get(cursor_line).copied().unwrap_or_default()
+}
+
+/// The logical line of every line of the terminal, joining the lines wrapped by the terminal.
+fn logical_lines<T: EventListener>(term: &Term<T>) -> Vec<usize> {
+    let history_size = term.history_size();
+    let last_column = term.last_column();
+    let mut logical_line = 0;
+    (0..term.total_lines())
+        .map(|line| {
+            let current_logical_line = logical_line;
+            let wrapped = term.grid()[grid_line(line, history_size)][last_column]
+                .flags
+                .contains(Flags::WRAPLINE);
+            if !wrapped {
+                logical_line += 1;
+            }
+            current_logical_line
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use alacritty_terminal::{event::VoidListener, term::Config, vte::ansi::Processor};
+    use gpui::{px, size, Pixels};
+
+    use super::*;
+    use crate::TerminalSize;
+
+    fn parse_all(parser: &mut SequenceParser, bytes: &[u8]) -> Vec<ShellSequence> {
+        bytes
+            .iter()
+            .filter_map(|&byte| parser.advance(byte))
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_sequences() {
+        let mut parser = SequenceParser::new();
+        let output = b"\x1b]7;file://host/home/user/my%20project\x07\x1b]133;A\x07$ \x1b]133;B\x07ls\r\n\x1b]133;C\x1b\\Cargo.toml\r\n\x1b]133;D;1\x07\x1b]0;title\x07\x1b]133;D\x07";
+        assert_eq!(
+            parse_all(&mut parser, output),
+            vec![
+                ShellSequence::WorkingDirectory(PathBuf::from("/home/user/my project")),
+                ShellSequence::PromptStart,
+                ShellSequence::CommandStart,
+                ShellSequence::OutputStart,
+                ShellSequence::CommandFinished { exit_code: Some(1) },
+                ShellSequence::CommandFinished { exit_code: None },
+            ]
+        );
+
+        let output = b"\x1b]633;P;Cwd=/home/user/a\\x3bb\\\\c\x07\x1b]633;A\x07$ \x1b]633;B\x07\x1b]633;E;ls;nonce\x07\x1b]633;C\x07\x1b]633;D;0\x07";
+        assert_eq!(
+            parse_all(&mut parser, output),
+            vec![
+                ShellSequence::WorkingDirectory(PathBuf::from("/home/user/a;b\\c")),
+                ShellSequence::PromptStart,
+                ShellSequence::CommandStart,
+                ShellSequence::OutputStart,
+                ShellSequence::CommandFinished { exit_code: Some(0) },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_split_sequences() {
+        let mut parser = SequenceParser::new();
+        assert_eq!(parse_all(&mut parser, b"output\x1b]13"), Vec::new());
+        assert_eq!(parse_all(&mut parser, b"3;D;"), Vec::new());
+        assert_eq!(
+            parse_all(&mut parser, b"0\x07$ "),
+            vec![ShellSequence::CommandFinished { exit_code: Some(0) }]
+        );
+
+        // Cancelled sequences are ignored.
+        assert_eq!(parse_all(&mut parser, b"\x1b]133;A\x18$ "), Vec::new());
+    }
+
+    /// A terminal of 10 lines of 10 columns, keeping 20 lines of scrollback.
+    fn test_terminal() -> Term<VoidListener> {
+        let config = Config {
+            scrolling_history: 20,
+            ..Config::default()
+        };
+        Term::new(config, &terminal_size(px(50.), px(100.)), VoidListener)
+    }
+
+    fn terminal_size(width: Pixels, height: Pixels) -> TerminalSize {
+        TerminalSize::new(px(10.), px(5.), size(width, height))
+    }
+
+    /// Parses the output like the event loop does.
+    fn write(
+        term: &mut Term<VoidListener>,
+        shell_integration: &mut ShellIntegration,
+        output: &str,
+    ) {
+        let mut processor: Processor = Processor::new();
+        let mut parser = SequenceParser::new();
+        for byte in output.bytes() {
+            processor.advance(&mut *term, byte);
+            shell_integration.count_scrolled_lines(term);
+            if let Some(sequence) = parser.advance(byte) {
+                shell_integration.record(sequence, term);
+            }
+        }
+    }
+
+    fn command(output_lines: usize) -> String {
+        format!(
+            "\x1b]133;A\x07$ \x1b]133;B\x07cmd\r\n\x1b]133;C\x07{}\x1b]133;D;0\x07",
+            "out\r\n".repeat(output_lines)
+        )
+    }
+
+    fn prompt_lines(shell_integration: &ShellIntegration) -> Vec<usize> {
+        shell_integration
+            .commands
+            .iter()
+            .map(|command| command.prompt_line)
+            .collect()
+    }
+
+    #[test]
+    fn test_commands_after_scrollback_overflow() {
+        let mut term = test_terminal();
+        let mut shell_integration = ShellIntegration::default();
+        write(&mut term, &mut shell_integration, &command(2));
+        write(&mut term, &mut shell_integration, &command(2));
+        assert_eq!(prompt_lines(&shell_integration), [0, 3]);
+        assert_eq!(shell_integration.last_output(), Some(4..6));
+
+        // 23 lines scrolled into the scrollback, which dropped the first 3 of them.
+        write(&mut term, &mut shell_integration, &"line\r\n".repeat(26));
+        assert_eq!(term.history_size(), 20);
+        shell_integration.reconcile(&term);
+        assert_eq!(prompt_lines(&shell_integration), [0]);
+        assert_eq!(shell_integration.last_output(), Some(1..3));
+
+        write(&mut term, &mut shell_integration, "\x1b]133;A\x07$ ");
+        assert_eq!(prompt_lines(&shell_integration), [0, 29]);
+
+        // Clearing the scrollback drops the commands in it.
+        write(&mut term, &mut shell_integration, "\x1b[3J");
+        shell_integration.reconcile(&term);
+        assert_eq!(prompt_lines(&shell_integration), [9]);
+
+        write(&mut term, &mut shell_integration, &"line\r\n".repeat(3));
+        shell_integration.reconcile(&term);
+        assert_eq!(prompt_lines(&shell_integration), [9]);
+        assert_eq!(term.history_size(), 3);
+    }
+
+    #[test]
+    fn test_commands_after_resize() {
+        let mut term = test_terminal();
+        let mut shell_integration = ShellIntegration::default();
+        write(&mut term, &mut shell_integration, "abcdefgh\r\n");
+        write(&mut term, &mut shell_integration, &command(1));
+        assert_eq!(prompt_lines(&shell_integration), [1]);
+        assert_eq!(shell_integration.last_output(), Some(2..3));
+
+        let resize = |term: &mut Term<VoidListener>,
+                      shell_integration: &mut ShellIntegration,
+                      width: Pixels,
+                      height: Pixels| {
+            shell_integration.start_resize(term);
+            term.resize(terminal_size(width, height));
+            shell_integration.finish_resize(term);
+        };
+
+        // The first line wraps once the terminal is 5 columns wide.
+        resize(&mut term, &mut shell_integration, px(25.), px(100.));
+        assert_eq!(prompt_lines(&shell_integration), [2]);
+        assert_eq!(shell_integration.last_output(), Some(3..4));
+
+        resize(&mut term, &mut shell_integration, px(50.), px(100.));
+        assert_eq!(prompt_lines(&shell_integration), [1]);
+        assert_eq!(shell_integration.last_output(), Some(2..3));
+
+        // Lines moved between the screen and the scrollback keep their place from its top.
+        resize(&mut term, &mut shell_integration, px(50.), px(20.));
+        assert!(term.history_size() > 0);
+        write(&mut term, &mut shell_integration, "more\r\n");
+        shell_integration.reconcile(&term);
+        assert_eq!(prompt_lines(&shell_integration), [1]);
+
+        resize(&mut term, &mut shell_integration, px(50.), px(100.));
+        write(&mut term, &mut shell_integration, "more\r\n");
+        shell_integration.reconcile(&term);
+        assert_eq!(prompt_lines(&shell_integration), [1]);
+        assert_eq!(shell_integration.last_output(), Some(2..3));
+    }
+}
@@ -2,12 +2,15 @@ pub mod mappings;
 
 pub use alacritty_terminal;
 
+mod event_loop;
 mod pty_info;
+mod scrollback;
+mod shell_integration;
 pub mod terminal_settings;
 
 use alacritty_terminal::{
     event::{Event as AlacTermEvent, EventListener, Notify, WindowSize},
-    event_loop::{EventLoop, Msg, Notifier},
+    event_loop::Msg,
     grid::{Dimensions, Scroll as AlacScroll},
     index::{Boundary, Column, Direction as AlacDirection, Line, Point as AlacPoint},
     selection::{Selection, SelectionRange, SelectionType},
@@ -37,10 +40,14 @@ use mappings::mouse::{
 };
 
 use collections::{HashMap, VecDeque};
+use event_loop::{EventLoop, Notifier};
 use futures::StreamExt;
+use parking_lot::Mutex;
 use pty_info::PtyProcessInfo;
 use serde::{Deserialize, Serialize};
 use settings::Settings;
+use shell_integration::{absolute_line, grid_line, ShellIntegration};
+pub use shell_integration::{CommandMark, CommandStatus};
 use smol::channel::{Receiver, Sender};
 use task::{HideStrategy, Shell, TaskId};
 use terminal_settings::{AlternateScroll, CursorShape, TerminalSettings};
@@ -79,6 +86,10 @@ actions!(
         ScrollPageDown,
         ScrollToTop,
         ScrollToBottom,
+        ScrollToPreviousPrompt,
+        ScrollToNextPrompt,
+        SelectLastCommandOutput,
+        CopyLastCommandOutput,
         ToggleViMode,
     ]
 );
@@ -108,6 +119,11 @@ pub enum Event {
     SelectionsChanged,
     NewNavigationTarget(Option<MaybeNavigationTarget>),
     Open(MaybeNavigationTarget),
+    /// The task running in the terminal stopped, either by exiting or with the terminal being shut down.
+    TaskFinished {
+        id: TaskId,
+        success: bool,
+    },
 }
 
 #[derive(Clone, Debug)]
@@ -141,6 +157,10 @@ enum InternalEvent {
     // Adjusted mouse position, should open
     FindHyperlink(Point<Pixels>, bool),
     Copy,
+    // Shell integration events
+    ScrollToPreviousPrompt,
+    ScrollToNextPrompt,
+    SelectLastCommandOutput,
     // Vi mode events
     ToggleViMode,
     ViMotion(ViMotion),
@@ -435,25 +455,28 @@ impl TerminalBuilder {
 
         let pty_info = PtyProcessInfo::new(&pty);
 
+        let shell_integration = Arc::new(Mutex::new(ShellIntegration::default()));
+
         //And connect them together
         let event_loop = EventLoop::new(
             term.clone(),
+            shell_integration.clone(),
             ZedListener(events_tx.clone()),
             pty,
             pty_options.hold,
-            false,
         )?;
 
         //Kick things off
-        let pty_tx = event_loop.channel();
+        let pty_tx = event_loop.notifier();
         let _io_thread = event_loop.spawn(); // DANGER
 
         let terminal = Terminal {
             task,
-            pty_tx: Notifier(pty_tx),
+            pty_tx,
             completion_tx,
             term,
             term_config: config,
+            shell_integration,
             title_override: terminal_title_override,
             events: VecDeque::with_capacity(10), //Should never get this high.
             last_content: Default::default(),
@@ -571,6 +594,8 @@ pub struct TerminalContent {
     pub cursor_char: char,
     pub size: TerminalSize,
     pub last_hovered_word: Option<HoveredWord>,
+    /// The prompts in view, as reported by the shell.
+    pub command_marks: Vec<CommandMark>,
 }
 
 #[derive(Clone)]
@@ -595,6 +620,7 @@ impl Default for TerminalContent {
             cursor_char: Default::default(),
             size: Default::default(),
             last_hovered_word: None,
+            command_marks: Vec::new(),
         }
     }
 }
@@ -610,6 +636,7 @@ pub struct Terminal {
     completion_tx: Sender<()>,
     term: Arc<FairMutex<Term<ZedListener>>>,
     term_config: Config,
+    shell_integration: Arc<Mutex<ShellIntegration>>,
     events: VecDeque<InternalEvent>,
     /// This is only used for mouse mode cell change detection
     last_mouse: Option<(AlacPoint, AlacDirection)>,
@@ -756,11 +783,16 @@ impl Terminal {
 
                 self.last_content.size = new_size;
 
-                self.pty_tx.0.send(Msg::Resize(new_size.into())).ok();
+                self.pty_tx.send(Msg::Resize(new_size.into()));
 
+                let mut shell_integration = self.shell_integration.lock();
+                shell_integration.start_resize(term);
                 term.resize(new_size);
+                shell_integration.finish_resize(term);
             }
             InternalEvent::Clear => {
+                self.shell_integration.lock().clear();
+
                 // Clear back buffer
                 term.clear_screen(ClearMode::Saved);
 
@@ -872,6 +904,44 @@ impl Terminal {
                     cx.write_to_clipboard(ClipboardItem::new_string(txt))
                 }
             }
+            InternalEvent::ScrollToPreviousPrompt => {
+                let viewport_top = term.history_size() - term.grid().display_offset();
+                let prompt_line = self.shell_integration.lock().previous_prompt(viewport_top);
+                if let Some(prompt_line) = prompt_line {
+                    term.scroll_display(AlacScroll::Delta((viewport_top - prompt_line) as i32));
+                    self.refresh_hovered_word();
+                }
+            }
+            InternalEvent::ScrollToNextPrompt => {
+                let viewport_top = term.history_size() - term.grid().display_offset();
+                let prompt_line = self.shell_integration.lock().next_prompt(viewport_top);
+                match prompt_line {
+                    Some(prompt_line) => term
+                        .scroll_display(AlacScroll::Delta(-((prompt_line - viewport_top) as i32))),
+                    None => term.scroll_display(AlacScroll::Bottom),
+                }
+                self.refresh_hovered_word();
+            }
+            InternalEvent::SelectLastCommandOutput => {
+                let output = self.shell_integration.lock().last_output();
+                if let Some(output) = output {
+                    let history_size = term.history_size();
+                    let start = AlacPoint::new(grid_line(output.start, history_size), Column(0));
+                    let end =
+                        AlacPoint::new(grid_line(output.end - 1, history_size), term.last_column());
+                    term.selection = Some(make_selection(&(start..=end)));
+                    term.scroll_to_point(start);
+                    self.refresh_hovered_word();
+
+                    #[cfg(any(target_os = "linux", target_os = "freebsd"))]
+                    if let Some(selection_text) = term.selection_to_string() {
+                        cx.write_to_primary(ClipboardItem::new_string(selection_text));
+                    }
+
+                    self.selection_head = Some(end);
+                    cx.emit(Event::SelectionsChanged)
+                }
+            }
             InternalEvent::ScrollToAlacPoint(point) => {
                 term.scroll_to_point(*point);
                 self.refresh_hovered_word();
@@ -957,9 +1027,16 @@ impl Terminal {
                             let target = if is_url {
                                 MaybeNavigationTarget::Url(maybe_url_or_path)
                             } else {
+                                let command_dir = if self.is_ssh_terminal {
+                                    None
+                                } else {
+                                    self.shell_integration.lock().working_directory_at(
+                                        absolute_line(point.line, term.history_size()),
+                                    )
+                                };
                                 MaybeNavigationTarget::PathLike(PathLikeTarget {
                                     maybe_path: maybe_url_or_path,
-                                    terminal_dir: self.working_directory(),
+                                    terminal_dir: command_dir.or_else(|| self.working_directory()),
                                 })
                             };
                             cx.emit(Event::Open(target));
@@ -1047,6 +1124,14 @@ impl Terminal {
         terminal.screen_lines()
     }
 
+    /// The text of the whole terminal, including the scrollback, with wrapped lines joined.
+    pub fn text(&self) -> String {
+        let term = self.term.lock_unfair();
+        let start = AlacPoint::new(term.topmost_line(), Column(0));
+        let end = AlacPoint::new(term.bottommost_line(), term.last_column());
+        term.bounds_to_string(start, end)
+    }
+
     //To test:
     //- Activate match on terminal (scrolling and selection)
     //- Editor search snapping behavior
@@ -1136,6 +1221,27 @@ impl Terminal {
             .push_back(InternalEvent::Scroll(AlacScroll::Bottom));
     }
 
+    /// Scrolls to the closest prompt above the top of the view, as reported by the shell.
+    pub fn scroll_to_previous_prompt(&mut self) {
+        self.events.push_back(InternalEvent::ScrollToPreviousPrompt);
+    }
+
+    /// Scrolls to the closest prompt below the top of the view, or to the bottom if there is none.
+    pub fn scroll_to_next_prompt(&mut self) {
+        self.events.push_back(InternalEvent::ScrollToNextPrompt);
+    }
+
+    /// Selects the output of the last command that printed anything, as reported by the shell.
+    pub fn select_last_command_output(&mut self) {
+        self.events
+            .push_back(InternalEvent::SelectLastCommandOutput);
+    }
+
+    pub fn copy_last_command_output(&mut self) {
+        self.select_last_command_output();
+        self.copy();
+    }
+
     ///Resize the terminal and the PTY.
     pub fn set_size(&mut self, new_size: TerminalSize) {
         if self.last_content.size != new_size {
@@ -1303,16 +1409,37 @@ impl Terminal {
     pub fn sync(&mut self, cx: &mut ModelContext<Self>) {
         let term = self.term.clone();
         let mut terminal = term.lock_unfair();
+        self.shell_integration.lock().reconcile(&terminal);
         //Note that the ordering of events matters for event processing
         while let Some(e) = self.events.pop_front() {
             self.process_terminal_event(&e, &mut terminal, cx)
         }
 
-        self.last_content = Self::make_content(&terminal, &self.last_content);
+        self.last_content = Self::make_content(
+            &terminal,
+            &self.shell_integration.lock(),
+            &self.last_content,
+        );
     }
 
-    fn make_content(term: &Term<ZedListener>, last_content: &TerminalContent) -> TerminalContent {
+    fn make_content(
+        term: &Term<ZedListener>,
+        shell_integration: &ShellIntegration,
+        last_content: &TerminalContent,
+    ) -> TerminalContent {
         let content = term.renderable_content();
+        let command_marks = if content.mode.contains(TermMode::ALT_SCREEN) {
+            Vec::new()
+        } else {
+            let history_size = term.history_size();
+            let viewport_top = history_size - content.display_offset;
+            shell_integration
+                .command_marks(
+                    viewport_top..viewport_top + term.screen_lines(),
+                    history_size,
+                )
+                .collect()
+        };
         TerminalContent {
             cells: content
                 .display_iter
@@ -1336,6 +1463,20 @@ impl Terminal {
             cursor_char: term.grid()[content.cursor.point].c,
             size: last_content.size,
             last_hovered_word: last_content.last_hovered_word.clone(),
+            command_marks,
+        }
+    }
+
+    /// Serializes the content above the cursor with its colors, up to the given number of lines,
+    /// or returns `None` if the terminal is showing the alternate screen.
+    pub fn scrollback(&self, max_lines: usize) -> Option<String> {
+        scrollback::scrollback(&*self.term.lock(), max_lines)
+    }
+
+    /// Restores the serialized content above the output the shell has written so far.
+    pub fn restore_scrollback(&mut self, scrollback: &str) {
+        if scrollback::restore(&mut *self.term.lock(), scrollback) {
+            self.shell_integration.lock().clear();
         }
     }
 
@@ -1781,6 +1922,10 @@ impl Terminal {
             unsafe { append_text_to_term(&mut self.term.lock(), &lines_to_show) };
         }
 
+        cx.emit(Event::TaskFinished {
+            id: task.id.clone(),
+            success: finished_successfully,
+        });
         match task.hide {
             HideStrategy::Never => {}
             HideStrategy::Always => {
@@ -1853,7 +1998,7 @@ unsafe fn append_text_to_term(term: &mut Term<ZedListener>, text_lines: &[&str])
 
 impl Drop for Terminal {
     fn drop(&mut self) {
-        self.pty_tx.0.send(Msg::Shutdown).ok();
+        self.pty_tx.send(Msg::Shutdown);
     }
 }
 
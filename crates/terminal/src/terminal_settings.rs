@@ -46,6 +46,7 @@ pub struct TerminalSettings {
     pub default_height: Pixels,
     pub detect_venv: VenvSettings,
     pub max_scroll_history_lines: Option<usize>,
+    pub max_persisted_scroll_history_lines: usize,
     pub toolbar: Toolbar,
 }
 
@@ -185,6 +186,12 @@ pub struct TerminalSettingsContent {
     ///
     /// Default: 10_000
     pub max_scroll_history_lines: Option<usize>,
+    /// The maximum number of lines of the scrollback history to save with the workspace,
+    /// restored above the prompt of the terminal when the workspace is reopened.
+    /// 0 disables saving the scrollback history.
+    ///
+    /// Default: 1_000
+    pub max_persisted_scroll_history_lines: Option<usize>,
     /// Toolbar related settings
     pub toolbar: Option<ToolbarContent>,
 }
@@ -21,7 +21,7 @@ use terminal::{
         },
     },
     terminal_settings::TerminalSettings,
-    HoveredWord, IndexedCell, Terminal, TerminalContent, TerminalSize,
+    CommandStatus, HoveredWord, IndexedCell, Terminal, TerminalContent, TerminalSize,
 };
 use theme::{ActiveTheme, Theme, ThemeSettings};
 use ui::{ParentElement, Tooltip};
@@ -45,6 +45,8 @@ pub struct LayoutState {
     display_offset: usize,
     hyperlink_tooltip: Option<AnyElement>,
     gutter: Pixels,
+    /// The displayed lines of the prompts reported by the shell, with the color of their marker.
+    command_marks: Vec<(i32, Hsla)>,
     last_hovered_word: Option<HoveredWord>,
     block_below_cursor_element: Option<AnyElement>,
 }
@@ -735,11 +737,26 @@ impl Element for TerminalElement {
                     cursor_char,
                     selection,
                     cursor,
+                    command_marks,
                     ..
                 } = &self.terminal.read(cx).last_content;
                 let mode = *mode;
                 let display_offset = *display_offset;
 
+                let command_marks = command_marks
+                    .iter()
+                    .map(|mark| {
+                        let color = match mark.status {
+                            CommandStatus::Succeeded => theme.status().success,
+                            CommandStatus::Failed => theme.status().error,
+                            CommandStatus::Pending | CommandStatus::Unknown => {
+                                theme.status().ignored
+                            }
+                        };
+                        (mark.line.0 + display_offset as i32, color)
+                    })
+                    .collect();
+
                 // searches, highlights to a single range representations
                 let mut relative_highlighted_ranges = Vec::new();
                 for search_match in search_matches {
@@ -855,6 +872,7 @@ impl Element for TerminalElement {
                     display_offset,
                     hyperlink_tooltip,
                     gutter,
+                    command_marks,
                     last_hovered_word,
                     block_below_cursor_element,
                 }
@@ -919,6 +937,18 @@ impl Element for TerminalElement {
                         rect.paint(origin, &layout.dimensions, cx);
                     }
 
+                    let line_height = layout.dimensions.line_height;
+                    for (line, color) in &layout.command_marks {
+                        let marker_bounds = Bounds::new(
+                            point(
+                                bounds.origin.x + layout.gutter * 0.3,
+                                origin.y + line_height * (*line as f32) + line_height * 0.15,
+                            ),
+                            size(layout.gutter * 0.3, line_height * 0.7),
+                        );
+                        cx.paint_quad(fill(marker_bounds, *color));
+                    }
+
                     for (relative_highlighted_range, color) in
                         layout.relative_highlighted_ranges.iter()
                     {
@@ -438,6 +438,7 @@ impl TerminalPanel {
         if let ControlFlow::Break(_) =
             Self::fill_command(is_local, spawn_in_terminal, &mut spawn_task)
         {
+            self.fail_task(&spawn_in_terminal.id, cx);
             return;
         }
         let spawn_task = spawn_task;
@@ -446,15 +447,13 @@ impl TerminalPanel {
         let use_new_terminal = spawn_in_terminal.use_new_terminal;
 
         if allow_concurrent_runs && use_new_terminal {
-            self.spawn_in_new_terminal(spawn_task, cx)
-                .detach_and_log_err(cx);
+            self.spawn_task_in_new_terminal(spawn_task, cx);
             return;
         }
 
         let terminals_for_task = self.terminals_for_task(&spawn_in_terminal.full_label, cx);
         if terminals_for_task.is_empty() {
-            self.spawn_in_new_terminal(spawn_task, cx)
-                .detach_and_log_err(cx);
+            self.spawn_task_in_new_terminal(spawn_task, cx);
             return;
         }
         let (existing_item_index, task_pane, existing_terminal) = terminals_for_task
@@ -487,9 +486,7 @@ impl TerminalPanel {
                             let Ok(Some(new_terminal_task)) =
                                 terminal_panel.update(&mut cx, |terminal_panel, cx| {
                                     if use_new_terminal {
-                                        terminal_panel
-                                            .spawn_in_new_terminal(spawn_task, cx)
-                                            .detach_and_log_err(cx);
+                                        terminal_panel.spawn_task_in_new_terminal(spawn_task, cx);
                                         None
                                     } else {
                                         Some(terminal_panel.replace_terminal(
@@ -590,6 +587,35 @@ impl TerminalPanel {
         ControlFlow::Continue(())
     }
 
+    /// Spawns the task in a new terminal, reporting it as failed if the terminal cannot be created.
+    fn spawn_task_in_new_terminal(
+        &mut self,
+        spawn_task: SpawnInTerminal,
+        cx: &mut ViewContext<Self>,
+    ) {
+        let id = spawn_task.id.clone();
+        let new_terminal = self.spawn_in_new_terminal(spawn_task, cx);
+        cx.spawn(|terminal_panel, mut cx| async move {
+            if let Err(error) = new_terminal.await {
+                terminal_panel
+                    .update(&mut cx, |terminal_panel, cx| {
+                        terminal_panel.fail_task(&id, cx)
+                    })
+                    .ok();
+                return Err(error);
+            }
+            Ok(())
+        })
+        .detach_and_log_err(cx);
+    }
+
+    /// Lets the tasks that depend on a task which could not be spawned know that it failed.
+    fn fail_task(&self, id: &TaskId, cx: &mut ViewContext<Self>) {
+        self.workspace
+            .update(cx, |workspace, _| workspace.task_finished(id, false))
+            .ok();
+    }
+
     pub fn spawn_in_new_terminal(
         &mut self,
         spawn_task: SpawnInTerminal,
@@ -826,6 +852,7 @@ impl TerminalPanel {
         terminal_to_replace: View<TerminalView>,
         cx: &mut ViewContext<Self>,
     ) -> Task<Option<()>> {
+        let id = spawn_task.id.clone();
         let reveal = spawn_task.reveal;
         let reveal_target = spawn_task.reveal_target;
         let window = cx.window_handle();
@@ -845,7 +872,13 @@ impl TerminalPanel {
                 })
                 .ok()?
                 .await
-                .log_err()?;
+                .log_err();
+            let Some(new_terminal) = new_terminal else {
+                task_workspace
+                    .update(&mut cx, |workspace, _| workspace.task_finished(&id, false))
+                    .ok();
+                return None;
+            };
             terminal_to_replace
                 .update(&mut cx, |terminal_to_replace, cx| {
                     terminal_to_replace.set_terminal(new_terminal, cx);
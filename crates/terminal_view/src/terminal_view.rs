@@ -6,10 +6,10 @@ use collections::HashSet;
 use editor::{actions::SelectAll, scroll::Autoscroll, Editor};
 use futures::{stream::FuturesUnordered, StreamExt};
 use gpui::{
-    anchored, deferred, div, impl_actions, AnyElement, AppContext, DismissEvent, EventEmitter,
-    FocusHandle, FocusableView, KeyContext, KeyDownEvent, Keystroke, Model, MouseButton,
-    MouseDownEvent, Pixels, Render, ScrollWheelEvent, Styled, Subscription, Task, View,
-    VisualContext, WeakModel, WeakView,
+    anchored, deferred, div, impl_actions, AnyElement, AnyWindowHandle, AppContext, DismissEvent,
+    EventEmitter, FocusHandle, FocusableView, KeyContext, KeyDownEvent, Keystroke, Model,
+    MouseButton, MouseDownEvent, Pixels, Render, ScrollWheelEvent, Styled, Subscription, Task,
+    View, VisualContext, WeakModel, WeakView,
 };
 use language::Bias;
 use persistence::TERMINAL_DB;
@@ -20,9 +20,10 @@ use terminal::{
         term::{search::RegexSearch, TermMode},
     },
     terminal_settings::{CursorShape, TerminalBlink, TerminalSettings, WorkingDirectory},
-    Clear, Copy, Event, MaybeNavigationTarget, Paste, ScrollLineDown, ScrollLineUp, ScrollPageDown,
-    ScrollPageUp, ScrollToBottom, ScrollToTop, ShowCharacterPalette, TaskStatus, Terminal,
-    TerminalSize, ToggleViMode,
+    Clear, Copy, CopyLastCommandOutput, Event, MaybeNavigationTarget, Paste, ScrollLineDown,
+    ScrollLineUp, ScrollPageDown, ScrollPageUp, ScrollToBottom, ScrollToNextPrompt,
+    ScrollToPreviousPrompt, ScrollToTop, SelectLastCommandOutput, ShowCharacterPalette, TaskStatus,
+    Terminal, TerminalSize, ToggleViMode,
 };
 use terminal_element::{is_blank, TerminalElement};
 use terminal_panel::TerminalPanel;
@@ -32,8 +33,8 @@ use workspace::{
     item::{BreadcrumbText, Item, ItemEvent, SerializableItem, TabContentParams},
     register_serializable_item,
     searchable::{SearchEvent, SearchOptions, SearchableItem, SearchableItemHandle},
-    CloseActiveItem, NewCenterTerminal, NewTerminal, OpenVisible, ToolbarItemLocation, Workspace,
-    WorkspaceId,
+    CloseActiveItem, ItemId, NewCenterTerminal, NewTerminal, OpenVisible, ToolbarItemLocation,
+    Workspace, WorkspaceId,
 };
 
 use anyhow::Context;
@@ -57,6 +58,9 @@ const REGEX_SPECIAL_CHARS: &[char] = &[
 
 const CURSOR_BLINK_INTERVAL: Duration = Duration::from_millis(500);
 
+/// How often the scrollback of a terminal with new output is saved to the database.
+const SCROLLBACK_SAVE_INTERVAL: Duration = Duration::from_secs(30);
+
 const GIT_DIFF_PATH_PREFIXES: &[char] = &['a', 'b'];
 
 ///Event to transmit the scroll from the element to the view
@@ -112,6 +116,7 @@ pub struct TerminalView {
     show_breadcrumbs: bool,
     block_below_cursor: Option<Rc<BlockProperties>>,
     scroll_top: Pixels,
+    scrollback_save_scheduled: bool,
     _subscriptions: Vec<Subscription>,
     _terminal_subscriptions: Vec<Subscription>,
 }
@@ -158,6 +163,23 @@ impl TerminalView {
         let cursor_shape = TerminalSettings::get_global(cx)
             .cursor_shape
             .unwrap_or_default();
+        let app_quit = cx.on_app_quit({
+            let terminal_view = cx.view().downgrade();
+            move |cx| {
+                let save_scrollback = terminal_view.upgrade().and_then(|terminal_view| {
+                    let item_id = terminal_view.entity_id().as_u64();
+                    terminal_view.read(cx).save_scrollback(item_id, cx)
+                });
+                async move {
+                    if let Some(save_scrollback) = save_scrollback {
+                        save_scrollback.await.log_err();
+                    }
+                }
+            }
+        });
+        let release = cx.on_release(|terminal_view, window, cx| {
+            terminal_view.fail_running_task(window, cx);
+        });
 
         Self {
             terminal,
@@ -176,9 +198,12 @@ impl TerminalView {
             show_breadcrumbs: TerminalSettings::get_global(cx).toolbar.breadcrumbs,
             block_below_cursor: None,
             scroll_top: Pixels::ZERO,
+            scrollback_save_scheduled: false,
             _subscriptions: vec![
                 focus_in,
                 focus_out,
+                app_quit,
+                release,
                 cx.observe_global::<SettingsStore>(Self::settings_changed),
             ],
             _terminal_subscriptions: terminal_subscriptions,
@@ -415,6 +440,38 @@ impl TerminalView {
         cx.notify();
     }
 
+    fn scroll_to_previous_prompt(
+        &mut self,
+        _: &ScrollToPreviousPrompt,
+        cx: &mut ViewContext<Self>,
+    ) {
+        self.terminal
+            .update(cx, |term, _| term.scroll_to_previous_prompt());
+        cx.notify();
+    }
+
+    fn scroll_to_next_prompt(&mut self, _: &ScrollToNextPrompt, cx: &mut ViewContext<Self>) {
+        self.terminal
+            .update(cx, |term, _| term.scroll_to_next_prompt());
+        cx.notify();
+    }
+
+    fn select_last_command_output(
+        &mut self,
+        _: &SelectLastCommandOutput,
+        cx: &mut ViewContext<Self>,
+    ) {
+        self.terminal
+            .update(cx, |term, _| term.select_last_command_output());
+        cx.notify();
+    }
+
+    fn copy_last_command_output(&mut self, _: &CopyLastCommandOutput, cx: &mut ViewContext<Self>) {
+        self.terminal
+            .update(cx, |term, _| term.copy_last_command_output());
+        cx.notify();
+    }
+
     fn toggle_vi_mode(&mut self, _: &ToggleViMode, cx: &mut ViewContext<Self>) {
         self.terminal.update(cx, |term, _| term.toggle_vi_mode());
         cx.notify();
@@ -606,6 +663,73 @@ impl TerminalView {
         dispatch_context
     }
 
+    /// Saves the scrollback of the terminal, to restore it when the workspace is reopened.
+    fn save_scrollback(
+        &self,
+        item_id: ItemId,
+        cx: &AppContext,
+    ) -> Option<Task<anyhow::Result<()>>> {
+        let workspace_id = self.workspace_id?;
+        let terminal = self.terminal.read(cx);
+        if terminal.task().is_some() {
+            return None;
+        }
+
+        let max_lines = TerminalSettings::get_global(cx).max_persisted_scroll_history_lines;
+        let scrollback = if max_lines == 0 {
+            None
+        } else {
+            // Keep the scrollback saved before the alternate screen was shown.
+            let scrollback = terminal.scrollback(max_lines)?;
+            (!scrollback.is_empty()).then_some(scrollback)
+        };
+        Some(cx.background_executor().spawn(TERMINAL_DB.save_scrollback(
+            item_id,
+            workspace_id,
+            scrollback,
+        )))
+    }
+
+    fn schedule_scrollback_save(&mut self, item_id: ItemId, cx: &mut ViewContext<Self>) {
+        if self.scrollback_save_scheduled {
+            return;
+        }
+
+        self.scrollback_save_scheduled = true;
+        cx.spawn(|terminal_view, mut cx| async move {
+            cx.background_executor()
+                .timer(SCROLLBACK_SAVE_INTERVAL)
+                .await;
+            terminal_view
+                .update(&mut cx, |terminal_view, cx| {
+                    terminal_view.scrollback_save_scheduled = false;
+                    if let Some(save_scrollback) = terminal_view.save_scrollback(item_id, cx) {
+                        save_scrollback.detach_and_log_err(cx);
+                    }
+                })
+                .ok();
+        })
+        .detach();
+    }
+
+    /// Closing the terminal stops its task without an exit status, so the tasks depending on it
+    /// are told that it failed.
+    fn fail_running_task(&self, window: AnyWindowHandle, cx: &mut AppContext) {
+        let Some(task) = self.terminal.read(cx).task() else {
+            return;
+        };
+        if task.status != TaskStatus::Running {
+            return;
+        }
+        let id = task.id.clone();
+        let workspace = self.workspace.clone();
+        window
+            .update(cx, |_, cx| {
+                workspace.update(cx, |workspace, _| workspace.task_finished(&id, false))
+            })
+            .ok();
+    }
+
     fn set_terminal(&mut self, terminal: Model<Terminal>, cx: &mut ViewContext<TerminalView>) {
         self._terminal_subscriptions =
             subscribe_for_terminal_events(&terminal, self.workspace.clone(), cx);
@@ -764,6 +888,11 @@ fn subscribe_for_terminal_events(
             },
             Event::BreadcrumbsChanged => cx.emit(ItemEvent::UpdateBreadcrumbs),
             Event::CloseTerminal => cx.emit(ItemEvent::CloseItem),
+            Event::TaskFinished { id, success } => {
+                workspace
+                    .update(cx, |workspace, _| workspace.task_finished(id, *success))
+                    .ok();
+            }
             Event::SelectionsChanged => {
                 cx.invalidate_character_coordinates();
                 cx.emit(SearchEvent::ActiveMatchChanged)
@@ -957,6 +1086,10 @@ impl Render for TerminalView {
             .on_action(cx.listener(TerminalView::scroll_page_down))
             .on_action(cx.listener(TerminalView::scroll_to_top))
             .on_action(cx.listener(TerminalView::scroll_to_bottom))
+            .on_action(cx.listener(TerminalView::scroll_to_previous_prompt))
+            .on_action(cx.listener(TerminalView::scroll_to_next_prompt))
+            .on_action(cx.listener(TerminalView::select_last_command_output))
+            .on_action(cx.listener(TerminalView::copy_last_command_output))
             .on_action(cx.listener(TerminalView::toggle_vi_mode))
             .on_action(cx.listener(TerminalView::show_character_palette))
             .on_action(cx.listener(TerminalView::select_all))
@@ -1179,7 +1312,7 @@ impl SerializableItem for TerminalView {
         &mut self,
         _workspace: &mut Workspace,
         item_id: workspace::ItemId,
-        _closing: bool,
+        closing: bool,
         cx: &mut ViewContext<Self>,
     ) -> Option<Task<gpui::Result<()>>> {
         let terminal = self.terminal().read(cx);
@@ -1187,15 +1320,33 @@ impl SerializableItem for TerminalView {
             return None;
         }
 
-        if let Some((cwd, workspace_id)) = terminal.working_directory().zip(self.workspace_id) {
-            Some(cx.background_executor().spawn(async move {
-                TERMINAL_DB
-                    .save_working_directory(item_id, workspace_id, cwd)
-                    .await
-            }))
+        let save_working_directory =
+            terminal
+                .working_directory()
+                .zip(self.workspace_id)
+                .map(|(cwd, workspace_id)| {
+                    TERMINAL_DB.save_working_directory(item_id, workspace_id, cwd)
+                });
+        // Saving the whole scrollback on every output would be too costly.
+        let save_scrollback = if closing {
+            self.save_scrollback(item_id, cx)
         } else {
+            self.schedule_scrollback_save(item_id, cx);
             None
+        };
+        if save_working_directory.is_none() && save_scrollback.is_none() {
+            return None;
         }
+
+        Some(cx.background_executor().spawn(async move {
+            if let Some(save_working_directory) = save_working_directory {
+                save_working_directory.await?;
+            }
+            if let Some(save_scrollback) = save_scrollback {
+                save_scrollback.await?;
+            }
+            Ok(())
+        }))
     }
 
     fn should_serialize(&self, event: &Self::Event) -> bool {
@@ -1230,12 +1381,30 @@ impl SerializableItem for TerminalView {
                 })
                 .ok()
                 .flatten();
+            let scrollback = cx
+                .update(|cx| {
+                    if TerminalSettings::get_global(cx).max_persisted_scroll_history_lines == 0 {
+                        return None;
+                    }
+                    TERMINAL_DB
+                        .get_scrollback(item_id, workspace_id)
+                        .log_err()
+                        .flatten()
+                        .filter(|scrollback| !scrollback.is_empty())
+                })
+                .ok()
+                .flatten();
 
             let terminal = project
                 .update(&mut cx, |project, cx| {
                     project.create_terminal(TerminalKind::Shell(cwd), window, cx)
                 })?
                 .await?;
+            if let Some(scrollback) = scrollback {
+                terminal.update(&mut cx, |terminal, _| {
+                    terminal.restore_scrollback(&scrollback)
+                })?;
+            }
             cx.update(|cx| {
                 cx.new_view(|cx| {
                     TerminalView::new(
@@ -15,9 +15,10 @@ use feature_flags::{FeatureFlagAppExt, ZedPro};
 use gpui::{
     actions, div, px, Action, AnyElement, AppContext, Decorations, Element, InteractiveElement,
     Interactivity, IntoElement, Model, MouseButton, ParentElement, Render, Stateful,
-    StatefulInteractiveElement, Styled, Subscription, View, ViewContext, VisualContext, WeakView,
+    StatefulInteractiveElement, Styled, Subscription, Task, View, ViewContext, VisualContext,
+    WeakView,
 };
-use project::{Project, RepositoryEntry};
+use project::{Project, ProjectPath, RepositoryEntry};
 use rpc::proto;
 use settings::Settings as _;
 use smallvec::SmallVec;
@@ -68,6 +69,9 @@ pub struct TitleBar {
     workspace: WeakView<Workspace>,
     should_move: bool,
     application_menu: Option<View<ApplicationMenu>>,
+    /// How far the checked out branch is ahead of and behind its upstream, e.g. "↑1 ↓2".
+    branch_upstream_summary: Option<String>,
+    _refresh_branch_upstream: Task<()>,
     _subscriptions: Vec<Subscription>,
 }
 
@@ -245,11 +249,17 @@ impl TitleBar {
             }),
         );
         subscriptions.push(cx.observe(&project, |_, _, cx| cx.notify()));
+        subscriptions.push(cx.subscribe(&project, |this, _, event, cx| match event {
+            project::Event::WorktreeAdded(_)
+            | project::Event::WorktreeUpdatedGitRepositories(_)
+            | project::Event::GitRemoteOperationsChanged => this.refresh_branch_upstream(cx),
+            _ => {}
+        }));
         subscriptions.push(cx.observe(&active_call, |this, _, cx| this.active_call_changed(cx)));
         subscriptions.push(cx.observe_window_activation(Self::window_activation_changed));
         subscriptions.push(cx.observe(&user_store, |_, _, cx| cx.notify()));
 
-        Self {
+        let mut this = Self {
             platform_style,
             content: div().id(id.into()),
             children: SmallVec::new(),
@@ -259,8 +269,12 @@ impl TitleBar {
             project,
             user_store,
             client,
+            branch_upstream_summary: None,
+            _refresh_branch_upstream: Task::ready(()),
             _subscriptions: subscriptions,
-        }
+        };
+        this.refresh_branch_upstream(cx);
+        this
     }
 
     #[cfg(not(target_os = "windows"))]
@@ -438,6 +452,27 @@ impl TitleBar {
             }))
     }
 
+    fn refresh_branch_upstream(&mut self, cx: &mut ViewContext<Self>) {
+        let project = self.project.read(cx);
+        let Some(worktree) = project.visible_worktrees(cx).next() else {
+            return;
+        };
+        let branches = project.branches(ProjectPath::root_path(worktree.read(cx).id()), cx);
+        self._refresh_branch_upstream = cx.spawn(|this, mut cx| async move {
+            let summary = branches.await.ok().and_then(|branches| {
+                let head = branches.into_iter().find(|branch| branch.is_head)?;
+                head.upstream?.summary()
+            });
+            this.update(&mut cx, |this, cx| {
+                if this.branch_upstream_summary != summary {
+                    this.branch_upstream_summary = summary;
+                    cx.notify();
+                }
+            })
+            .ok();
+        });
+    }
+
     pub fn render_project_branch(&self, cx: &mut ViewContext<Self>) -> Option<impl IntoElement> {
         let entry = {
             let mut names_and_branches =
@@ -453,8 +488,12 @@ impl TitleBar {
             .as_ref()
             .and_then(RepositoryEntry::branch)
             .map(|branch| util::truncate_and_trailoff(&branch, MAX_BRANCH_NAME_LENGTH))?;
+        let label = match &self.branch_upstream_summary {
+            Some(summary) => format!("{branch_name} {summary}"),
+            None => branch_name,
+        };
         Some(
-            Button::new("project_branch_trigger", branch_name)
+            Button::new("project_branch_trigger", label)
                 .color(Color::Muted)
                 .style(ButtonStyle::Subtle)
                 .label_size(LabelSize::Small)
@@ -162,6 +162,8 @@ pub enum IconName {
     CursorIBeam,
     Dash,
     DatabaseZap,
+    Debug,
+    DebugBreakpoint,
     Delete,
     Diff,
     Disconnected,
@@ -228,6 +230,7 @@ pub enum IconName {
     PageUp,
     PanelLeft,
     PanelRight,
+    Pause,
     Pencil,
     Person,
     PhoneIncoming,
@@ -2,7 +2,7 @@ use anyhow::{anyhow, Context, Result};
 use fuzzy::{StringMatch, StringMatchCandidate};
 use git::repository::Branch;
 use gpui::{
-    rems, AnyElement, AppContext, AsyncAppContext, DismissEvent, EventEmitter, FocusHandle,
+    rems, Action, AnyElement, AppContext, AsyncAppContext, DismissEvent, EventEmitter, FocusHandle,
     FocusableView, InteractiveElement, IntoElement, ParentElement, Render, SharedString, Styled,
     Subscription, Task, View, ViewContext, VisualContext, WeakView, WindowContext,
 };
@@ -13,7 +13,10 @@ use ui::{prelude::*, HighlightedLabel, ListItem, ListItemSpacing};
 use util::ResultExt;
 use workspace::notifications::DetachAndPromptErr;
 use workspace::{ModalView, Workspace};
-use zed_actions::branches::OpenRecent;
+use zed_actions::{
+    branches::OpenRecent,
+    git::{Fetch, Pull, Push},
+};
 
 pub fn init(cx: &mut AppContext) {
     cx.observe_new_views(|workspace: &mut Workspace, _| {
@@ -290,7 +293,19 @@ impl PickerDelegate for BranchListDelegate {
                             .copied()
                             .collect();
 
-                        parent.child(HighlightedLabel::new(shortened_branch_name, highlights))
+                        let upstream_summary = self
+                            .all_branches
+                            .iter()
+                            .find(|candidate| candidate.name.as_ref() == branch.string)
+                            .and_then(|branch| branch.upstream?.summary());
+
+                        parent
+                            .child(HighlightedLabel::new(shortened_branch_name, highlights))
+                            .end_slot::<Label>(upstream_summary.map(|summary| {
+                                Label::new(summary)
+                                    .size(LabelSize::Small)
+                                    .color(Color::Muted)
+                            }))
                     }
                     BranchEntry::NewBranch { name } => {
                         parent.child(Label::new(format!("Create branch '{name}'")))
@@ -322,4 +337,29 @@ impl PickerDelegate for BranchListDelegate {
         };
         Some(v_flex().mt_1().child(label).into_any_element())
     }
+
+    fn render_footer(&self, cx: &mut ViewContext<Picker<Self>>) -> Option<AnyElement> {
+        let button = |id: &'static str, label: &'static str, action: Box<dyn Action>| {
+            Button::new(id, label)
+                .label_size(LabelSize::Small)
+                .on_click(cx.listener(move |_, _, cx| {
+                    cx.dispatch_action(action.boxed_clone());
+                    cx.emit(DismissEvent);
+                }))
+        };
+
+        Some(
+            h_flex()
+                .w_full()
+                .border_t_1()
+                .border_color(cx.theme().colors().border_variant)
+                .p_1()
+                .gap_1()
+                .justify_end()
+                .child(button("vcs-menu-fetch", "Fetch", Box::new(Fetch)))
+                .child(button("vcs-menu-pull", "Pull", Box::new(Pull)))
+                .child(button("vcs-menu-push", "Push", Box::new(Push)))
+                .into_any_element(),
+        )
+    }
 }
@@ -1,6 +1,8 @@
 use std::{
     iter::Peekable,
     ops::{Deref, Range},
+    path::PathBuf,
+    process::Stdio,
     str::Chars,
     sync::OnceLock,
     time::Instant,
@@ -13,7 +15,10 @@ use editor::{
     display_map::ToDisplayPoint,
     Bias, Editor, ToPoint,
 };
-use gpui::{actions, impl_actions, Action, AppContext, Global, ViewContext, WindowContext};
+use futures::AsyncWriteExt;
+use gpui::{
+    actions, impl_actions, Action, AppContext, Global, Keystroke, ViewContext, WindowContext,
+};
 use language::Point;
 use multi_buffer::MultiBufferRow;
 use regex::Regex;
@@ -56,13 +61,49 @@ pub struct WithCount {
     action: WrappedAction,
 }
 
+/// Replays keys in normal mode at the start of each line (`:normal`).
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct NormalCommand {
+    range: Option<CommandRange>,
+    keys: String,
+}
+
+/// Inserts the contents of a file, or the output of a shell command, below a line (`:read`).
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct ReadCommand {
+    range: Option<CommandRange>,
+    source: ReadSource,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+enum ReadSource {
+    File(String),
+    Shell(String),
+}
+
+/// Replaces lines with the output of a shell command they are piped into (`:{range}!`).
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct FilterCommand {
+    range: CommandRange,
+    command: String,
+}
+
 #[derive(Debug)]
 struct WrappedAction(Box<dyn Action>);
 
 actions!(vim, [VisualCommand, CountCommand]);
 impl_actions!(
     vim,
-    [GoToLine, YankCommand, WithRange, WithCount, OnMatchingLines]
+    [
+        GoToLine,
+        YankCommand,
+        WithRange,
+        WithCount,
+        OnMatchingLines,
+        NormalCommand,
+        ReadCommand,
+        FilterCommand
+    ]
 );
 
 impl<'de> Deserialize<'de> for WrappedAction {
@@ -213,7 +254,19 @@ pub fn register(editor: &mut Editor, cx: &mut ViewContext<Vim>) {
 
     Vim::action(editor, cx, |vim, action: &OnMatchingLines, cx| {
         action.run(vim, cx)
-    })
+    });
+
+    Vim::action(editor, cx, |vim, action: &NormalCommand, cx| {
+        action.run(vim, cx)
+    });
+
+    Vim::action(editor, cx, |vim, action: &ReadCommand, cx| {
+        action.run(vim, cx)
+    });
+
+    Vim::action(editor, cx, |vim, action: &FilterCommand, cx| {
+        action.run(vim, cx)
+    });
 }
 
 #[derive(Default)]
@@ -777,6 +830,15 @@ pub fn command_interceptor(mut input: &str, cx: &AppContext) -> Option<CommandIn
             }
             .boxed_clone(),
         )
+    } else if let Some(normal) = NormalCommand::parse(query, range.clone()) {
+        Some(normal.boxed_clone())
+    } else if let Some(read) = ReadCommand::parse(query, range.clone()) {
+        Some(read.boxed_clone())
+    } else if let Some(filter) = range
+        .clone()
+        .and_then(|range| FilterCommand::parse(query, range))
+    {
+        Some(filter.boxed_clone())
     } else if query.starts_with('s') {
         let mut substitute = "substitute".chars().peekable();
         let mut query = query.chars().peekable();
@@ -1061,9 +1123,359 @@ impl OnMatchingLines {
     }
 }
 
+/// Strips the name of an ex command, which can be abbreviated down to `min_len` characters, from
+/// the start of the query.
+fn strip_command_name<'a>(query: &'a str, name: &str, min_len: usize) -> Option<&'a str> {
+    let len = query
+        .find(|c: char| !c.is_ascii_alphabetic())
+        .unwrap_or(query.len());
+    if len < min_len || !name.starts_with(&query[..len]) {
+        return None;
+    }
+    Some(&query[len..])
+}
+
+fn notify_err<T>(vim: &Vim, result: Result<T>, cx: &mut ViewContext<Vim>) -> Option<T> {
+    let Some(workspace) = vim.workspace(cx) else {
+        return result.log_err();
+    };
+    workspace.update(cx, |workspace, cx| result.notify_err(workspace, cx))
+}
+
+fn resolve_range(
+    range: &CommandRange,
+    vim: &mut Vim,
+    cx: &mut ViewContext<Vim>,
+) -> Option<Range<MultiBufferRow>> {
+    let result = vim.update_editor(cx, |vim, editor, cx| range.buffer_range(vim, editor, cx))?;
+    notify_err(vim, result, cx)
+}
+
+/// The directory shell commands are run in, and relative paths are resolved against.
+fn project_directory(vim: &Vim, cx: &mut ViewContext<Vim>) -> Result<Option<PathBuf>> {
+    let workspace = vim.workspace(cx).ok_or_else(|| anyhow!("no workspace"))?;
+    let project = workspace.read(cx).project().read(cx);
+    if !project.is_local() {
+        return Err(anyhow!(
+            "external commands are only supported in local projects"
+        ));
+    }
+    Ok(project.first_project_directory(cx))
+}
+
+/// Runs the command with the user's shell, writing the input to its stdin, and returns its output.
+async fn run_shell_command(
+    command: String,
+    directory: Option<PathBuf>,
+    input: Option<String>,
+) -> Result<String> {
+    #[cfg(not(target_os = "windows"))]
+    let mut process = {
+        let shell = std::env::var("SHELL").unwrap_or_else(|_| "/bin/sh".to_string());
+        let mut process = util::command::new_smol_command(shell);
+        process.arg("-c").arg(&command);
+        process
+    };
+    #[cfg(target_os = "windows")]
+    let mut process = {
+        let mut process = util::command::new_smol_command("cmd");
+        process.arg("/C").arg(&command);
+        process
+    };
+    if let Some(directory) = directory {
+        process.current_dir(directory);
+    }
+    process
+        .stdin(if input.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+
+    let mut child = process.spawn()?;
+    let stdin = child.stdin.take();
+    let write_input = async move {
+        if let Some((mut stdin, input)) = stdin.zip(input) {
+            // The command doesn't have to read all of its input.
+            stdin.write_all(input.as_bytes()).await.ok();
+        }
+    };
+    let (_, output) = futures::join!(write_input, child.output());
+    let output = output?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "`{command}` failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+impl NormalCommand {
+    fn parse(query: &str, range: Option<CommandRange>) -> Option<Self> {
+        let rest = strip_command_name(query, "normal", 4)?;
+        // There are no mappings to skip, so `:normal!` is the same as `:normal`.
+        let rest = rest.strip_prefix('!').unwrap_or(rest);
+        if !rest.starts_with(char::is_whitespace) {
+            return None;
+        }
+        let keys = rest.trim_start();
+        if keys.is_empty() {
+            return None;
+        }
+        Some(Self {
+            range,
+            keys: keys.to_string(),
+        })
+    }
+
+    fn keystrokes(&self) -> Vec<Keystroke> {
+        self.keys
+            .chars()
+            .filter_map(|c| {
+                let source = match c {
+                    ' ' => "space".to_string(),
+                    c if c.is_uppercase() => format!("shift-{}", c.to_lowercase()),
+                    c => c.to_string(),
+                };
+                Keystroke::parse(&source).log_err()
+            })
+            .collect()
+    }
+
+    pub fn run(&self, vim: &mut Vim, cx: &mut ViewContext<Vim>) {
+        let positions = if let Some(range) = &self.range {
+            let Some(range) = resolve_range(range, vim, cx) else {
+                return;
+            };
+            vim.update_editor(cx, |_, editor, cx| {
+                let snapshot = editor.buffer().read(cx).snapshot(cx);
+                (range.start.0..=range.end.0)
+                    .map(|row| snapshot.anchor_before(Point::new(row, 0)))
+                    .collect::<Vec<_>>()
+            })
+        } else {
+            // Without a range the keys are replayed at each cursor, which includes
+            // each matching line under `:global`.
+            vim.update_editor(cx, |_, editor, cx| {
+                let snapshot = editor.buffer().read(cx).snapshot(cx);
+                editor
+                    .selections
+                    .all::<Point>(cx)
+                    .into_iter()
+                    .map(|selection| snapshot.anchor_before(selection.head()))
+                    .collect::<Vec<_>>()
+            })
+        };
+        let Some(positions) = positions else {
+            return;
+        };
+
+        let keystrokes = self.keystrokes();
+        vim.switch_mode(Mode::Normal, true, cx);
+        cx.spawn(|vim, mut cx| async move {
+            let escape = Keystroke::parse("escape")?;
+            vim.update(&mut cx, |vim, cx| {
+                vim.update_editor(cx, |_, editor, cx| {
+                    editor.start_transaction_at(Instant::now(), cx);
+                });
+            })?;
+            for position in positions {
+                vim.update(&mut cx, |vim, cx| {
+                    vim.update_editor(cx, |_, editor, cx| {
+                        editor.change_selections(None, cx, |s| {
+                            s.select_anchor_ranges([position..position]);
+                        });
+                    });
+                })?;
+                for keystroke in &keystrokes {
+                    cx.update(|cx| cx.dispatch_keystroke(keystroke.clone()))?;
+                }
+                // Like Vim, finish as if escape was typed, to leave insert mode or abandon
+                // an incomplete command.
+                let pending = vim.update(&mut cx, |vim, _| {
+                    vim.mode != Mode::Normal || vim.active_operator().is_some()
+                })?;
+                if pending {
+                    cx.update(|cx| cx.dispatch_keystroke(escape.clone()))?;
+                }
+            }
+            vim.update(&mut cx, |vim, cx| {
+                vim.update_editor(cx, |_, editor, cx| {
+                    editor.end_transaction_at(Instant::now(), cx);
+                });
+            })
+        })
+        .detach_and_log_err(cx);
+    }
+}
+
+impl ReadCommand {
+    fn parse(query: &str, range: Option<CommandRange>) -> Option<Self> {
+        let rest = strip_command_name(query, "read", 1)?;
+        if !rest.starts_with(|c: char| c.is_whitespace() || c == '!') {
+            return None;
+        }
+        let argument = rest.trim_start();
+        let (argument, is_shell_command) = match argument.strip_prefix('!') {
+            Some(command) => (command.trim_start(), true),
+            None => (argument, false),
+        };
+        if argument.is_empty() {
+            return None;
+        }
+        let source = if is_shell_command {
+            ReadSource::Shell(argument.to_string())
+        } else {
+            ReadSource::File(argument.to_string())
+        };
+        Some(Self { range, source })
+    }
+
+    pub fn run(&self, vim: &mut Vim, cx: &mut ViewContext<Vim>) {
+        let row = if let Some(range) = &self.range {
+            let Some(range) = resolve_range(range, vim, cx) else {
+                return;
+            };
+            Some(range.end)
+        } else {
+            None
+        };
+        let Some(line_end) = vim.update_editor(cx, |_, editor, cx| {
+            let snapshot = editor.buffer().read(cx).snapshot(cx);
+            let row = row.unwrap_or_else(|| {
+                MultiBufferRow(editor.selections.newest::<Point>(cx).head().row)
+            });
+            snapshot.anchor_before(Point::new(row.0, snapshot.line_len(row)))
+        }) else {
+            return;
+        };
+        let Some(directory) = notify_err(vim, project_directory(vim, cx), cx) else {
+            return;
+        };
+        vim.switch_mode(Mode::Normal, true, cx);
+
+        let text = match &self.source {
+            ReadSource::File(path) => {
+                let Some(workspace) = vim.workspace(cx) else {
+                    return;
+                };
+                let fs = workspace.read(cx).project().read(cx).fs().clone();
+                let path = match directory {
+                    Some(directory) => directory.join(path),
+                    None => PathBuf::from(path),
+                };
+                cx.background_executor()
+                    .spawn(async move { fs.load(&path).await })
+            }
+            ReadSource::Shell(command) => {
+                cx.background_executor()
+                    .spawn(run_shell_command(command.clone(), directory, None))
+            }
+        };
+        cx.spawn(|vim, mut cx| async move {
+            let text = text.await;
+            vim.update(&mut cx, |vim, cx| {
+                let Some(text) = notify_err(vim, text, cx) else {
+                    return;
+                };
+                if text.is_empty() {
+                    return;
+                }
+                vim.update_editor(cx, |_, editor, cx| {
+                    let text = format!("\n{}", text.strip_suffix('\n').unwrap_or(&text));
+                    editor.transact(cx, |editor, cx| {
+                        editor.edit([(line_end..line_end, text)], cx);
+                        let snapshot = editor.buffer().read(cx).snapshot(cx);
+                        let start = Point::new(line_end.to_point(&snapshot).row + 1, 0);
+                        editor.change_selections(None, cx, |s| s.select_ranges([start..start]));
+                    });
+                });
+            })
+        })
+        .detach_and_log_err(cx);
+    }
+}
+
+impl FilterCommand {
+    fn parse(query: &str, range: CommandRange) -> Option<Self> {
+        let command = query.strip_prefix('!')?.trim_start();
+        if command.is_empty() {
+            return None;
+        }
+        Some(Self {
+            range,
+            command: command.to_string(),
+        })
+    }
+
+    pub fn run(&self, vim: &mut Vim, cx: &mut ViewContext<Vim>) {
+        let Some(range) = resolve_range(&self.range, vim, cx) else {
+            return;
+        };
+        let Some((lines, mut input)) = vim.update_editor(cx, |_, editor, cx| {
+            let snapshot = editor.buffer().read(cx).snapshot(cx);
+            let start = Point::new(range.start.0, 0);
+            let end = if range.end < snapshot.max_row() {
+                Point::new(range.end.0 + 1, 0)
+            } else {
+                snapshot.max_point()
+            };
+            let input = snapshot.text_for_range(start..end).collect::<String>();
+            (
+                snapshot.anchor_before(start)..snapshot.anchor_after(end),
+                input,
+            )
+        }) else {
+            return;
+        };
+        let Some(directory) = notify_err(vim, project_directory(vim, cx), cx) else {
+            return;
+        };
+        vim.switch_mode(Mode::Normal, true, cx);
+
+        // Commands expect their input to end with a newline, even when the last line doesn't.
+        let missing_newline = !input.ends_with('\n');
+        if missing_newline {
+            input.push('\n');
+        }
+        let output = cx.background_executor().spawn(run_shell_command(
+            self.command.clone(),
+            directory,
+            Some(input),
+        ));
+        cx.spawn(|vim, mut cx| async move {
+            let output = output.await;
+            vim.update(&mut cx, |vim, cx| {
+                let Some(mut output) = notify_err(vim, output, cx) else {
+                    return;
+                };
+                if missing_newline {
+                    if output.ends_with('\n') {
+                        output.pop();
+                    }
+                } else if !output.is_empty() && !output.ends_with('\n') {
+                    output.push('\n');
+                }
+                vim.update_editor(cx, |_, editor, cx| {
+                    editor.transact(cx, |editor, cx| {
+                        editor.edit([(lines.clone(), output)], cx);
+                        editor.change_selections(None, cx, |s| {
+                            s.select_anchor_ranges([lines.start..lines.start]);
+                        });
+                    });
+                });
+            })
+        })
+        .detach_and_log_err(cx);
+    }
+}
+
 #[cfg(test)]
 mod test {
-    use std::path::Path;
+    use std::{path::Path, time::Duration};
 
     use crate::{
         state::Mode,
@@ -1373,4 +1785,185 @@ mod test {
             a
             ˇa"});
     }
+
+    #[gpui::test]
+    async fn test_command_normal(cx: &mut TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state(
+            indoc! {"
+            ˇa
+            b
+            c"},
+            Mode::Normal,
+        );
+        cx.simulate_keystrokes(": 2 , 3 n o r m space A ; enter");
+        cx.assert_state(
+            indoc! {"
+            a
+            b;
+            cˇ;"},
+            Mode::Normal,
+        );
+
+        // An incomplete command is abandoned at the end of each line.
+        cx.simulate_keystrokes(": % n o r m a l space x d enter");
+        cx.assert_state(
+            indoc! {"
+
+            ;
+            ˇ;"},
+            Mode::Normal,
+        );
+    }
+
+    #[gpui::test]
+    async fn test_command_normal_on_matching_lines(cx: &mut TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state(
+            indoc! {"
+            ˇa
+            b
+            a"},
+            Mode::Normal,
+        );
+        cx.simulate_keystrokes(": g / a / n o r m space I - enter");
+        cx.assert_state(
+            indoc! {"
+            -a
+            b
+            ˇ-a"},
+            Mode::Normal,
+        );
+    }
+
+    #[gpui::test]
+    async fn test_command_read(cx: &mut TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        let fs = cx.workspace(|workspace, cx| workspace.project().read(cx).fs().clone());
+        fs.as_fake()
+            .insert_file("/root/dir/read.txt", "one\ntwo\n".as_bytes().to_vec())
+            .await;
+
+        cx.set_state(
+            indoc! {"
+            ˇa
+            b"},
+            Mode::Normal,
+        );
+        cx.simulate_keystrokes(": r space d i r / r e a d . t x t enter");
+        cx.assert_state(
+            indoc! {"
+            a
+            ˇone
+            two
+            b"},
+            Mode::Normal,
+        );
+
+        cx.simulate_keystrokes(": $ r e a d space d i r / r e a d . t x t enter");
+        cx.assert_state(
+            indoc! {"
+            a
+            one
+            two
+            b
+            ˇone
+            two"},
+            Mode::Normal,
+        );
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    #[gpui::test]
+    async fn test_command_filter(cx: &mut TestAppContext) {
+        let mut cx = NeovimBackedTestContext::new(cx).await;
+        run_shell_commands_outside_worktree(&mut cx);
+
+        cx.set_shared_state(indoc! {"
+            ˇc
+            b
+            a"})
+            .await;
+        cx.simulate_shared_keystrokes(": % ! s o r t enter").await;
+        wait_for_shell_command(&mut cx, |cx| cx.buffer_text() != "c\nb\na");
+        cx.shared_state().await.assert_eq(indoc! {"
+            ˇa
+            b
+            c"});
+
+        cx.set_shared_state(indoc! {"
+            ˇa
+            b
+            c
+            d"})
+            .await;
+        cx.simulate_shared_keystrokes(": 2 , 3 ! t r space a - z space A - Z enter")
+            .await;
+        wait_for_shell_command(&mut cx, |cx| cx.buffer_text() != "a\nb\nc\nd");
+        cx.shared_state().await.assert_eq(indoc! {"
+            a
+            ˇB
+            C
+            d"});
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    #[gpui::test]
+    async fn test_command_filter_failure(cx: &mut TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+        run_shell_commands_outside_worktree(&mut cx);
+
+        // Unlike Vim, the lines are kept when the command fails, rather than replaced with its
+        // output.
+        cx.set_state(
+            indoc! {"
+            ˇa
+            b"},
+            Mode::Normal,
+        );
+        cx.simulate_keystrokes(": % ! e c h o space x space ; space e x i t space 1 enter");
+        wait_for_shell_command(&mut cx, |cx| {
+            !cx.workspace(|workspace, _| workspace.notification_ids().is_empty())
+        });
+        cx.assert_state(
+            indoc! {"
+            ˇa
+            b"},
+            Mode::Normal,
+        );
+    }
+
+    /// The test worktree only exists in the fake file system, so remove it to run shell commands
+    /// in the current directory instead.
+    fn run_shell_commands_outside_worktree(cx: &mut VimTestContext) {
+        cx.workspace(|workspace, cx| {
+            workspace.project().update(cx, |project, cx| {
+                let worktree_ids = project
+                    .worktrees(cx)
+                    .map(|worktree| worktree.read(cx).id())
+                    .collect::<Vec<_>>();
+                for worktree_id in worktree_ids {
+                    project.remove_worktree(worktree_id, cx);
+                }
+            })
+        });
+    }
+
+    /// Shell commands run in a real process, so wait for it rather than for the executor to park.
+    fn wait_for_shell_command(
+        cx: &mut VimTestContext,
+        mut finished: impl FnMut(&mut VimTestContext) -> bool,
+    ) {
+        for _ in 0..500 {
+            cx.run_until_parked();
+            if finished(cx) {
+                return;
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        panic!("the shell command did not finish");
+    }
 }
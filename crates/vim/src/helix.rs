@@ -1,15 +1,89 @@
-use editor::{movement, scroll::Autoscroll, DisplayPoint, Editor};
-use gpui::{actions, Action};
-use language::{CharClassifier, CharKind};
+mod prompt;
+mod select;
+
+use std::sync::Arc;
+
+use editor::{display_map::DisplaySnapshot, movement, scroll::Autoscroll, DisplayPoint, Editor};
+use gpui::{actions, impl_actions, Action};
+use language::{CharClassifier, CharKind, Point, Selection, SelectionGoal};
+use schemars::JsonSchema;
+use serde::Deserialize;
 use ui::ViewContext;
 
-use crate::{motion::Motion, state::Mode, Vim};
+use crate::{
+    motion::Motion,
+    object::Object,
+    state::{Mode, Operator},
+    Vim,
+};
+
+/// Pastes the register after each selection, or before it.
+#[derive(Clone, Deserialize, JsonSchema, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct HelixPaste {
+    #[serde(default)]
+    before: bool,
+}
+
+impl_actions!(vim, [HelixPaste]);
 
-actions!(vim, [HelixNormalAfter, HelixDelete]);
+actions!(
+    vim,
+    [
+        HelixNormalAfter,
+        HelixDelete,
+        HelixChange,
+        HelixYank,
+        HelixInsert,
+        HelixAppend
+    ]
+);
 
 pub fn register(editor: &mut Editor, cx: &mut ViewContext<Vim>) {
     Vim::action(editor, cx, Vim::helix_normal_after);
     Vim::action(editor, cx, Vim::helix_delete);
+    Vim::action(editor, cx, Vim::helix_change);
+    Vim::action(editor, cx, Vim::helix_yank);
+    Vim::action(editor, cx, Vim::helix_paste);
+    Vim::action(editor, cx, Vim::helix_insert);
+    Vim::action(editor, cx, Vim::helix_append);
+    select::register(editor, cx);
+}
+
+/// Returns the positions of the characters at the anchor and at the cursor of a selection.
+/// Helix selections always contain the character under the cursor, so an empty selection
+/// stands for the character after it.
+pub(crate) fn anchor_and_cursor(
+    map: &DisplaySnapshot,
+    selection: &Selection<DisplayPoint>,
+) -> (DisplayPoint, DisplayPoint) {
+    if selection.is_empty() {
+        (selection.head(), selection.head())
+    } else if selection.reversed {
+        (movement::left(map, selection.end), selection.start)
+    } else {
+        (selection.start, movement::left(map, selection.end))
+    }
+}
+
+/// Selects the characters from the anchor to the cursor, both included.
+pub(crate) fn select_from_anchor(
+    map: &DisplaySnapshot,
+    selection: &mut Selection<DisplayPoint>,
+    anchor: DisplayPoint,
+    cursor: DisplayPoint,
+    goal: SelectionGoal,
+) {
+    if cursor >= anchor {
+        selection.start = anchor;
+        selection.end = movement::right(map, cursor);
+        selection.reversed = false;
+    } else {
+        selection.start = cursor;
+        selection.end = movement::right(map, anchor);
+        selection.reversed = true;
+    }
+    selection.goal = goal;
 }
 
 impl Vim {
@@ -36,6 +110,7 @@ impl Vim {
     fn helix_find_range_forward(
         &mut self,
         times: Option<usize>,
+        extend: bool,
         cx: &mut ViewContext<Self>,
         mut is_boundary: impl FnMut(char, char, &CharClassifier) -> bool,
     ) {
@@ -43,6 +118,7 @@ impl Vim {
             editor.change_selections(Some(Autoscroll::fit()), cx, |s| {
                 s.move_with(|map, selection| {
                     let times = times.unwrap_or(1);
+                    let (anchor, _) = anchor_and_cursor(map, selection);
 
                     if selection.head() == map.max_point() {
                         return;
@@ -80,6 +156,11 @@ impl Vim {
                         }
                         last_selection = selection.clone();
                     }
+
+                    if extend {
+                        let (_, cursor) = anchor_and_cursor(map, selection);
+                        select_from_anchor(map, selection, anchor, cursor, selection.goal);
+                    }
                 });
             });
         });
@@ -88,6 +169,7 @@ impl Vim {
     fn helix_find_range_backward(
         &mut self,
         times: Option<usize>,
+        extend: bool,
         cx: &mut ViewContext<Self>,
         mut is_boundary: impl FnMut(char, char, &CharClassifier) -> bool,
     ) {
@@ -95,6 +177,7 @@ impl Vim {
             editor.change_selections(Some(Autoscroll::fit()), cx, |s| {
                 s.move_with(|map, selection| {
                     let times = times.unwrap_or(1);
+                    let (anchor, _) = anchor_and_cursor(map, selection);
 
                     if selection.head() == DisplayPoint::zero() {
                         return;
@@ -136,6 +219,11 @@ impl Vim {
                         }
                         last_selection = selection.clone();
                     }
+
+                    if extend {
+                        let (_, cursor) = anchor_and_cursor(map, selection);
+                        select_from_anchor(map, selection, anchor, cursor, selection.goal);
+                    }
                 });
             })
         });
@@ -168,15 +256,42 @@ impl Vim {
         });
     }
 
+    /// Moves the cursor of each selection with the motion. When `from_cursor` is set the
+    /// selection starts at the previous cursor, otherwise it keeps its anchor.
+    fn helix_extend_with_motion(
+        &mut self,
+        motion: Motion,
+        times: Option<usize>,
+        from_cursor: bool,
+        cx: &mut ViewContext<Self>,
+    ) {
+        self.update_editor(cx, |_, editor, cx| {
+            let text_layout_details = editor.text_layout_details(cx);
+            editor.change_selections(Some(Autoscroll::fit()), cx, |s| {
+                s.move_with(|map, selection| {
+                    let (anchor, cursor) = anchor_and_cursor(map, selection);
+                    let Some((point, goal)) =
+                        motion.move_point(map, cursor, selection.goal, times, &text_layout_details)
+                    else {
+                        return;
+                    };
+                    let anchor = if from_cursor { cursor } else { anchor };
+                    select_from_anchor(map, selection, anchor, point, goal);
+                })
+            });
+        });
+    }
+
     pub fn helix_move_cursor(
         &mut self,
         motion: Motion,
         times: Option<usize>,
         cx: &mut ViewContext<Self>,
     ) {
+        let extend = self.mode == Mode::HelixSelect;
         match motion {
             Motion::NextWordStart { ignore_punctuation } => {
-                self.helix_find_range_forward(times, cx, |left, right, classifier| {
+                self.helix_find_range_forward(times, extend, cx, |left, right, classifier| {
                     let left_kind = classifier.kind_with(left, ignore_punctuation);
                     let right_kind = classifier.kind_with(right, ignore_punctuation);
                     let at_newline = right == '\n';
@@ -188,7 +303,7 @@ impl Vim {
                 })
             }
             Motion::NextWordEnd { ignore_punctuation } => {
-                self.helix_find_range_forward(times, cx, |left, right, classifier| {
+                self.helix_find_range_forward(times, extend, cx, |left, right, classifier| {
                     let left_kind = classifier.kind_with(left, ignore_punctuation);
                     let right_kind = classifier.kind_with(right, ignore_punctuation);
                     let at_newline = right == '\n';
@@ -200,7 +315,7 @@ impl Vim {
                 })
             }
             Motion::PreviousWordStart { ignore_punctuation } => {
-                self.helix_find_range_backward(times, cx, |left, right, classifier| {
+                self.helix_find_range_backward(times, extend, cx, |left, right, classifier| {
                     let left_kind = classifier.kind_with(left, ignore_punctuation);
                     let right_kind = classifier.kind_with(right, ignore_punctuation);
                     let at_newline = right == '\n';
@@ -212,7 +327,7 @@ impl Vim {
                 })
             }
             Motion::PreviousWordEnd { ignore_punctuation } => {
-                self.helix_find_range_backward(times, cx, |left, right, classifier| {
+                self.helix_find_range_backward(times, extend, cx, |left, right, classifier| {
                     let left_kind = classifier.kind_with(left, ignore_punctuation);
                     let right_kind = classifier.kind_with(right, ignore_punctuation);
                     let at_newline = right == '\n';
@@ -224,10 +339,82 @@ impl Vim {
                     found
                 })
             }
+            Motion::FindForward { .. } | Motion::FindBackward { .. } => {
+                self.helix_extend_with_motion(motion, times, !extend, cx)
+            }
+            _ if extend => self.helix_extend_with_motion(motion, times, false, cx),
             _ => self.helix_move_and_collapse(motion, times, cx),
         }
     }
 
+    /// Makes the cursors one character wide, so that they act on the character under them.
+    pub(crate) fn helix_widen_cursors(&mut self, cx: &mut ViewContext<Self>) {
+        self.update_editor(cx, |_, editor, cx| {
+            editor.change_selections(None, cx, |s| {
+                s.move_with(|map, selection| {
+                    if selection.is_empty() && !selection.reversed {
+                        selection.end = movement::right(map, selection.end);
+                    }
+                });
+            });
+        });
+    }
+
+    pub(crate) fn helix_object(&mut self, object: Object, cx: &mut ViewContext<Self>) {
+        let Some(Operator::Object { around }) = self.active_operator() else {
+            // The surround operators of match mode take the object as their target.
+            self.normal_object(object, cx);
+            return;
+        };
+        self.pop_operator(cx);
+        self.update_editor(cx, |_, editor, cx| {
+            editor.change_selections(Some(Autoscroll::fit()), cx, |s| {
+                s.move_with(|map, selection| {
+                    let (_, cursor) = anchor_and_cursor(map, selection);
+                    let mut cursor_selection = selection.clone();
+                    cursor_selection.collapse_to(cursor, selection.goal);
+                    if let Some(range) = object.range(map, cursor_selection, around) {
+                        if !range.is_empty() {
+                            selection.start = range.start;
+                            selection.end = range.end;
+                            selection.reversed = false;
+                            selection.goal = SelectionGoal::None;
+                        }
+                    }
+                });
+            });
+        });
+    }
+
+    fn helix_insert(&mut self, _: &HelixInsert, cx: &mut ViewContext<Self>) {
+        self.start_recording(cx);
+        self.update_editor(cx, |_, editor, cx| {
+            editor.change_selections(None, cx, |s| {
+                s.move_with(|_, selection| {
+                    selection.collapse_to(selection.start, SelectionGoal::None)
+                });
+            });
+        });
+        self.switch_mode(Mode::Insert, false, cx);
+    }
+
+    fn helix_append(&mut self, _: &HelixAppend, cx: &mut ViewContext<Self>) {
+        self.start_recording(cx);
+        self.update_editor(cx, |_, editor, cx| {
+            editor.change_selections(None, cx, |s| {
+                s.move_with(|map, selection| {
+                    let end = if selection.is_empty() {
+                        movement::right(map, selection.end)
+                    } else {
+                        selection.end
+                    };
+                    selection.collapse_to(end, SelectionGoal::None)
+                });
+            });
+        });
+        self.switch_mode(Mode::Insert, false, cx);
+    }
+
     pub fn helix_delete(&mut self, _: &HelixDelete, cx: &mut ViewContext<Self>) {
         self.store_visual_marks(cx);
         self.update_editor(cx, |vim, editor, cx| {
@@ -247,6 +434,128 @@ impl Vim {
             vim.copy_selections_content(editor, false, cx);
             editor.insert("", cx);
         });
+        if self.mode == Mode::HelixSelect {
+            self.switch_mode(Mode::HelixNormal, true, cx);
+        }
+    }
+
+    pub fn helix_change(&mut self, _: &HelixChange, cx: &mut ViewContext<Self>) {
+        self.start_recording(cx);
+        self.helix_widen_cursors(cx);
+        self.update_editor(cx, |vim, editor, cx| {
+            vim.copy_selections_content(editor, false, cx);
+            editor.insert("", cx);
+        });
+        self.switch_mode(Mode::Insert, false, cx);
+    }
+
+    pub fn helix_yank(&mut self, _: &HelixYank, cx: &mut ViewContext<Self>) {
+        self.helix_widen_cursors(cx);
+        self.update_editor(cx, |vim, editor, cx| {
+            vim.yank_selections_content(editor, false, cx);
+        });
+    }
+
+    pub fn helix_paste(&mut self, action: &HelixPaste, cx: &mut ViewContext<Self>) {
+        self.record_current_action(cx);
+        let count = Vim::take_count(cx).unwrap_or(1);
+        let selected_register = self.selected_register.take();
+        self.update_editor(cx, |_, editor, cx| {
+            let Some(register) = Vim::update_globals(cx, |globals, cx| {
+                globals.read_register(selected_register, Some(editor), cx)
+            })
+            .filter(|register| !register.text.is_empty()) else {
+                return;
+            };
+            let text = register.text.repeat(count);
+            let linewise = text.ends_with('\n');
+
+            editor.transact(cx, |editor, cx| {
+                let (map, selections) = editor.selections.all_display(cx);
+                let max_point = map.buffer_snapshot.max_point();
+                let mut edits = Vec::new();
+                for selection in &selections {
+                    let end = if selection.is_empty() {
+                        movement::right(&map, selection.end)
+                    } else {
+                        selection.end
+                    };
+                    let start = selection.start.to_point(&map);
+                    let end = end.to_point(&map);
+                    let (point, text) = match (action.before, linewise) {
+                        (true, false) => (start, text.clone()),
+                        (false, false) => (end, text.clone()),
+                        (true, true) => (Point::new(start.row, 0), text.clone()),
+                        (false, true) => {
+                            // Paste after the line containing the last selected character.
+                            let row = if end.column == 0 && end.row > start.row {
+                                end.row
+                            } else {
+                                end.row + 1
+                            };
+                            if row > max_point.row {
+                                let text = format!("\n{}", text.trim_end_matches('\n'));
+                                (max_point, text)
+                            } else {
+                                (Point::new(row, 0), text.clone())
+                            }
+                        }
+                    };
+                    edits.push((point, text));
+                }
+
+                // Select the pasted text, without the newline that separates a pasted line
+                // from the end of the buffer.
+                let mut offset_delta = 0isize;
+                let mut ranges = Vec::new();
+                for (point, text) in &edits {
+                    let offset = map.buffer_snapshot.point_to_offset(*point) as isize;
+                    let start = (offset + offset_delta) as usize;
+                    let trimmed_start = start + (text.len() - text.trim_start_matches('\n').len());
+                    ranges.push(trimmed_start..start + text.len());
+                    offset_delta += text.len() as isize;
+                }
+                editor.edit(
+                    edits.into_iter().map(|(point, text)| (point..point, text)),
+                    cx,
+                );
+                editor.change_selections(Some(Autoscroll::fit()), cx, |s| s.select_ranges(ranges));
+            });
+        });
+    }
+
+    pub(crate) fn helix_replace(&mut self, text: Arc<str>, cx: &mut ViewContext<Self>) {
+        self.stop_recording(cx);
+        self.helix_widen_cursors(cx);
+        self.update_editor(cx, |_, editor, cx| {
+            editor.transact(cx, |editor, cx| {
+                let snapshot = editor.buffer().read(cx).snapshot(cx);
+                let selections = editor.selections.all::<usize>(cx);
+                let mut offset_delta = 0isize;
+                let mut edits = Vec::new();
+                let mut ranges = Vec::new();
+                for selection in selections {
+                    let range = selection.range();
+                    let replacement = snapshot
+                        .text_for_range(range.clone())
+                        .flat_map(|chunk| chunk.chars())
+                        .map(|c| if c == '\n' { "\n" } else { &*text })
+                        .collect::<String>();
+                    let start = (range.start as isize + offset_delta) as usize;
+                    let end = start + replacement.len();
+                    offset_delta += replacement.len() as isize - range.len() as isize;
+                    ranges.push(if selection.reversed {
+                        end..start
+                    } else {
+                        start..end
+                    });
+                    edits.push((range, replacement));
+                }
+                editor.edit(edits, cx);
+                editor.change_selections(None, cx, |s| s.select_ranges(ranges));
+            });
+        });
+        self.pop_operator(cx);
     }
 }
 
@@ -254,11 +563,11 @@ impl Vim {
 mod test {
     use indoc::indoc;
 
-    use crate::{state::Mode, test::VimTestContext};
+    use crate::{state::Mode, test::HelixTestContext};
 
     #[gpui::test]
     async fn test_next_word_start(cx: &mut gpui::TestAppContext) {
-        let mut cx = VimTestContext::new(cx, true).await;
+        let mut cx = HelixTestContext::new(cx).await;
         // «
         // ˇ
         // »
@@ -293,7 +602,7 @@ mod test {
 
     #[gpui::test]
     async fn test_delete(cx: &mut gpui::TestAppContext) {
-        let mut cx = VimTestContext::new(cx, true).await;
+        let mut cx = HelixTestContext::new(cx).await;
 
         // test delete a selection
         cx.set_state(
@@ -328,7 +637,7 @@ mod test {
 
     #[gpui::test]
     async fn test_delete_character_end_of_line(cx: &mut gpui::TestAppContext) {
-        let mut cx = VimTestContext::new(cx, true).await;
+        let mut cx = HelixTestContext::new(cx).await;
 
         cx.set_state(
             indoc! {"
@@ -350,7 +659,7 @@ mod test {
 
     #[gpui::test]
     async fn test_delete_character_end_of_buffer(cx: &mut gpui::TestAppContext) {
-        let mut cx = VimTestContext::new(cx, true).await;
+        let mut cx = HelixTestContext::new(cx).await;
 
         cx.set_state(
             indoc! {"
@@ -370,4 +679,56 @@ mod test {
             Mode::HelixNormal,
         );
     }
+
+    #[gpui::test]
+    async fn test_helix_conformance(cx: &mut gpui::TestAppContext) {
+        let mut cx = HelixTestContext::new(cx).await;
+
+        // Cases in the notation of Helix's integration tests: the state before, the keys and
+        // the state after.
+        let cases = [
+            // Movement
+            ("#[h|]#ello world\n", "w", "#[hello |]#world\n"),
+            ("#[h|]#ello world\n", "fo", "#[hello|]# world\n"),
+            ("a\n#[b|]#\n", "gg", "#[a|]#\nb\n"),
+            ("#[(|]#a)\n", "mm", "(a#[)|]#\n"),
+            // Select mode
+            ("#[h|]#ello world\n", "vll", "#[hel|]#lo world\n"),
+            ("#[h|]#ello world foo\n", "vww", "#[hello world |]#foo\n"),
+            // Selection manipulation
+            ("#[|a]#b\nc\n", "x", "#[ab\n|]#c\n"),
+            ("#[|a]#b\nc\n", "xx", "#[ab\nc\n|]#"),
+            ("#[a|]#\nb\nc\n", "2x", "#[a\nb\n|]#c\n"),
+            ("a#[b|]#c\n", "%", "#[abc\n|]#"),
+            ("#[abc|]#\n", ";", "ab#[c|]#\n"),
+            ("#[abc|]#\n", "<A-;>", "#[|abc]#\n"),
+            ("#[ ab |]#\n", "_", " #[ab|]# \n"),
+            ("#(a|)# #[b|]#\n", ",", "a #[b|]#\n"),
+            ("#(a|)# #[b|]#\n", "<A-,>", "#[a|]# b\n"),
+            ("#[one two one|]#\n", "sone<ret>", "#[one|]# two #(one|)#\n"),
+            ("#[a,b,c|]#\n", "S,<ret>", "#[a|]#,#(b|)#,#(c|)#\n"),
+            ("#[a\nb\n|]#c\n", "<A-s>", "#[a|]#\n#(b|)#\nc\n"),
+            (
+                "#(ab|)# #[cd|]# #(ae|)#\n",
+                "Ka<ret>",
+                "#[ab|]# cd #(ae|)#\n",
+            ),
+            (
+                "#(ab|)# #[cd|]# #(ae|)#\n",
+                "<A-K>a<ret>",
+                "ab #[cd|]# ae\n",
+            ),
+            ("he#[l|]#lo world\n", "miw", "#[hello|]# world\n"),
+            // Changes
+            ("#[ab|]#c\n", "d", "#[c|]#\n"),
+            ("#[ab|]#c\n", "cx<esc>", "x#[c|]#\n"),
+            ("#[abc|]#\n", "rx", "#[xxx|]#\n"),
+            ("#[aBc|]#\n", "~", "#[AbC|]#\n"),
+            ("#[ab|]#c\n", "yp", "ab#[ab|]#c\n"),
+            ("a#[b|]#c\n", "yP", "a#[b|]#bc\n"),
+        ];
+        for (before, keys, after) in cases {
+            cx.assert_helix(before, keys, after);
+        }
+    }
 }
@@ -0,0 +1,140 @@
+use editor::{Editor, EditorEvent};
+use gpui::{
+    AppContext, DismissEvent, EventEmitter, FocusHandle, FocusableView, Subscription, View,
+    WeakView,
+};
+use regex::Regex;
+use ui::prelude::*;
+use workspace::ModalView;
+
+use crate::Vim;
+
+/// What the Helix commands that prompt for a regex do with its matches.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum RegexOperation {
+    /// Selects the matches within the selections (`s`).
+    Select,
+    /// Splits the selections on the matches (`S`).
+    Split,
+    /// Keeps the selections that match (`K`).
+    Keep,
+    /// Removes the selections that match (`alt-K`).
+    Remove,
+}
+
+impl RegexOperation {
+    fn label(&self) -> &'static str {
+        match self {
+            RegexOperation::Select => "select:",
+            RegexOperation::Split => "split:",
+            RegexOperation::Keep => "keep:",
+            RegexOperation::Remove => "remove:",
+        }
+    }
+}
+
+pub(crate) struct RegexPrompt {
+    operation: RegexOperation,
+    query_editor: View<Editor>,
+    vim: WeakView<Vim>,
+    error: Option<SharedString>,
+    _subscription: Subscription,
+}
+
+impl ModalView for RegexPrompt {}
+
+impl EventEmitter<DismissEvent> for RegexPrompt {}
+
+impl FocusableView for RegexPrompt {
+    fn focus_handle(&self, cx: &AppContext) -> FocusHandle {
+        self.query_editor.focus_handle(cx)
+    }
+}
+
+impl RegexPrompt {
+    pub(crate) fn new(
+        operation: RegexOperation,
+        vim: WeakView<Vim>,
+        cx: &mut ViewContext<Self>,
+    ) -> Self {
+        let query_editor = cx.new_view(|cx| {
+            let mut editor = Editor::single_line(cx);
+            editor.set_placeholder_text("Regex", cx);
+            editor
+        });
+        let subscription = cx.subscribe(&query_editor, Self::on_query_editor_event);
+        Self {
+            operation,
+            query_editor,
+            vim,
+            error: None,
+            _subscription: subscription,
+        }
+    }
+
+    fn on_query_editor_event(
+        &mut self,
+        _: View<Editor>,
+        event: &EditorEvent,
+        cx: &mut ViewContext<Self>,
+    ) {
+        match event {
+            EditorEvent::Blurred => cx.emit(DismissEvent),
+            EditorEvent::BufferEdited { .. } => {
+                if self.error.take().is_some() {
+                    cx.notify();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn cancel(&mut self, _: &menu::Cancel, cx: &mut ViewContext<Self>) {
+        cx.emit(DismissEvent);
+    }
+
+    fn confirm(&mut self, _: &menu::Confirm, cx: &mut ViewContext<Self>) {
+        let query = self.query_editor.read(cx).text(cx);
+        let regex = match Regex::new(&query) {
+            Ok(regex) => regex,
+            Err(error) => {
+                self.error = Some(error.to_string().into());
+                cx.notify();
+                return;
+            }
+        };
+
+        let operation = self.operation;
+        cx.emit(DismissEvent);
+        self.vim
+            .update(cx, |vim, cx| vim.helix_apply_regex(operation, &regex, cx))
+            .ok();
+    }
+}
+
+impl Render for RegexPrompt {
+    fn render(&mut self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        v_flex()
+            .w(rems(34.))
+            .elevation_2(cx)
+            .key_context("HelixRegexPrompt")
+            .on_action(cx.listener(Self::cancel))
+            .on_action(cx.listener(Self::confirm))
+            .child(
+                h_flex()
+                    .px_2()
+                    .py_1()
+                    .gap_2()
+                    .child(Label::new(self.operation.label()).color(Color::Muted))
+                    .child(self.query_editor.clone()),
+            )
+            .children(self.error.clone().map(|error| {
+                div()
+                    .border_t_1()
+                    .border_color(cx.theme().colors().border_variant)
+                    .px_2()
+                    .py_1()
+                    .child(Label::new(error).color(Color::Error))
+            }))
+    }
+}
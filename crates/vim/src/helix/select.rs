@@ -0,0 +1,257 @@
+use std::sync::LazyLock;
+
+use editor::{display_map::ToDisplayPoint, scroll::Autoscroll, Bias, Editor};
+use gpui::actions;
+use language::{Point, SelectionGoal};
+use regex::Regex;
+use ui::ViewContext;
+
+use super::{
+    anchor_and_cursor,
+    prompt::{RegexOperation, RegexPrompt},
+    select_from_anchor,
+};
+use crate::Vim;
+
+actions!(
+    vim,
+    [
+        HelixCollapseSelection,
+        HelixFlipSelections,
+        HelixKeepPrimarySelection,
+        HelixRemovePrimarySelection,
+        HelixSelectLine,
+        HelixExtendToLineBounds,
+        HelixTrimSelections,
+        HelixSelectRegex,
+        HelixSplitSelection,
+        HelixSplitSelectionOnNewline,
+        HelixKeepSelections,
+        HelixRemoveSelections
+    ]
+);
+
+pub(crate) fn register(editor: &mut Editor, cx: &mut ViewContext<Vim>) {
+    Vim::action(editor, cx, Vim::helix_collapse_selection);
+    Vim::action(editor, cx, Vim::helix_flip_selections);
+    Vim::action(editor, cx, Vim::helix_keep_primary_selection);
+    Vim::action(editor, cx, Vim::helix_remove_primary_selection);
+    Vim::action(editor, cx, |vim, _: &HelixSelectLine, cx| {
+        let count = Vim::take_count(cx).unwrap_or(1);
+        vim.helix_select_lines(Some(count), cx)
+    });
+    Vim::action(editor, cx, |vim, _: &HelixExtendToLineBounds, cx| {
+        vim.helix_select_lines(None, cx)
+    });
+    Vim::action(editor, cx, Vim::helix_trim_selections);
+    Vim::action(editor, cx, |vim, _: &HelixSelectRegex, cx| {
+        vim.helix_open_regex_prompt(RegexOperation::Select, cx)
+    });
+    Vim::action(editor, cx, |vim, _: &HelixSplitSelection, cx| {
+        vim.helix_open_regex_prompt(RegexOperation::Split, cx)
+    });
+    Vim::action(editor, cx, |vim, _: &HelixKeepSelections, cx| {
+        vim.helix_open_regex_prompt(RegexOperation::Keep, cx)
+    });
+    Vim::action(editor, cx, |vim, _: &HelixRemoveSelections, cx| {
+        vim.helix_open_regex_prompt(RegexOperation::Remove, cx)
+    });
+    Vim::action(editor, cx, |vim, _: &HelixSplitSelectionOnNewline, cx| {
+        static NEWLINE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\r?\n").unwrap());
+        vim.helix_apply_regex(RegexOperation::Split, &NEWLINE, cx)
+    });
+}
+
+impl Vim {
+    fn helix_collapse_selection(&mut self, _: &HelixCollapseSelection, cx: &mut ViewContext<Self>) {
+        self.update_editor(cx, |_, editor, cx| {
+            editor.change_selections(None, cx, |s| {
+                s.move_with(|map, selection| {
+                    let (_, cursor) = anchor_and_cursor(map, selection);
+                    selection.collapse_to(cursor, selection.goal);
+                });
+            });
+        });
+    }
+
+    fn helix_flip_selections(&mut self, _: &HelixFlipSelections, cx: &mut ViewContext<Self>) {
+        self.update_editor(cx, |_, editor, cx| {
+            editor.change_selections(None, cx, |s| {
+                s.move_with(|map, selection| {
+                    let (anchor, cursor) = anchor_and_cursor(map, selection);
+                    select_from_anchor(map, selection, cursor, anchor, SelectionGoal::None);
+                });
+            });
+        });
+    }
+
+    fn helix_keep_primary_selection(
+        &mut self,
+        _: &HelixKeepPrimarySelection,
+        cx: &mut ViewContext<Self>,
+    ) {
+        self.update_editor(cx, |_, editor, cx| {
+            editor.change_selections(None, cx, |s| {
+                let primary = s.newest_anchor().clone();
+                s.select_anchors(vec![primary]);
+            });
+        });
+    }
+
+    fn helix_remove_primary_selection(
+        &mut self,
+        _: &HelixRemovePrimarySelection,
+        cx: &mut ViewContext<Self>,
+    ) {
+        self.update_editor(cx, |_, editor, cx| {
+            editor.change_selections(None, cx, |s| {
+                let primary_id = s.newest_anchor().id;
+                let others = s
+                    .disjoint_anchors()
+                    .iter()
+                    .filter(|selection| selection.id != primary_id)
+                    .cloned()
+                    .collect::<Vec<_>>();
+                if !others.is_empty() {
+                    s.select_anchors(others);
+                }
+            });
+        });
+    }
+
+    /// Extends the selections to whole lines. With a count, selections that already cover
+    /// whole lines are extended by that many lines below them.
+    fn helix_select_lines(&mut self, count: Option<usize>, cx: &mut ViewContext<Self>) {
+        self.update_editor(cx, |_, editor, cx| {
+            editor.change_selections(Some(Autoscroll::fit()), cx, |s| {
+                s.move_with(|map, selection| {
+                    let start = selection.start.to_point(map);
+                    let end = selection.end.to_point(map);
+                    let max_point = map.buffer_snapshot.max_point();
+
+                    let ends_at_line_start = end.column == 0 && end.row > start.row;
+                    // The row at whose start the selection of whole lines ends.
+                    let mut end_row = if ends_at_line_start {
+                        end.row
+                    } else {
+                        end.row + 1
+                    };
+                    if let Some(count) = count {
+                        let covers_lines = start.column == 0 && ends_at_line_start;
+                        end_row += if covers_lines { count } else { count - 1 } as u32;
+                    }
+
+                    let end = if end_row > max_point.row {
+                        max_point
+                    } else {
+                        Point::new(end_row, 0)
+                    };
+                    selection.start = Point::new(start.row, 0).to_display_point(map);
+                    selection.end = end.to_display_point(map);
+                    selection.reversed = false;
+                    selection.goal = SelectionGoal::None;
+                });
+            });
+        });
+    }
+
+    fn helix_trim_selections(&mut self, _: &HelixTrimSelections, cx: &mut ViewContext<Self>) {
+        self.update_editor(cx, |_, editor, cx| {
+            editor.change_selections(None, cx, |s| {
+                s.move_with(|map, selection| {
+                    if selection.is_empty() {
+                        return;
+                    }
+                    let start = selection.start.to_offset(map, Bias::Left);
+                    let end = selection.end.to_offset(map, Bias::Left);
+                    let text = map
+                        .buffer_snapshot
+                        .text_for_range(start..end)
+                        .collect::<String>();
+                    let trimmed_start = start + text.len() - text.trim_start().len();
+                    let trimmed_end =
+                        (end - (text.len() - text.trim_end().len())).max(trimmed_start);
+
+                    let to_display_point = |offset| {
+                        map.buffer_snapshot
+                            .offset_to_point(offset)
+                            .to_display_point(map)
+                    };
+                    selection.start = to_display_point(trimmed_start);
+                    selection.end = to_display_point(trimmed_end);
+                });
+            });
+        });
+    }
+
+    fn helix_open_regex_prompt(&mut self, operation: RegexOperation, cx: &mut ViewContext<Self>) {
+        let Some(workspace) = self.workspace(cx) else {
+            return;
+        };
+        let vim = cx.view().downgrade();
+        workspace.update(cx, |workspace, cx| {
+            workspace.toggle_modal(cx, |cx| RegexPrompt::new(operation, vim, cx));
+        });
+    }
+
+    /// Selects, splits or filters the selections with the regex. The selections are left
+    /// unchanged when none would remain.
+    pub(crate) fn helix_apply_regex(
+        &mut self,
+        operation: RegexOperation,
+        regex: &Regex,
+        cx: &mut ViewContext<Self>,
+    ) {
+        self.update_editor(cx, |_, editor, cx| {
+            let snapshot = editor.buffer().read(cx).snapshot(cx);
+            let mut ranges = Vec::new();
+            for selection in editor.selections.all::<usize>(cx) {
+                let mut range = selection.range();
+                if range.is_empty() {
+                    range.end = snapshot.clip_offset(range.end + 1, Bias::Right);
+                }
+                let text = snapshot.text_for_range(range.clone()).collect::<String>();
+
+                match operation {
+                    RegexOperation::Select => {
+                        ranges.extend(
+                            regex
+                                .find_iter(&text)
+                                .filter(|found| !found.is_empty())
+                                .map(|found| {
+                                    range.start + found.start()..range.start + found.end()
+                                }),
+                        );
+                    }
+                    RegexOperation::Split => {
+                        let mut start = 0;
+                        for found in regex.find_iter(&text) {
+                            if found.start() > start {
+                                ranges.push(range.start + start..range.start + found.start());
+                            }
+                            start = found.end();
+                        }
+                        if start < text.len() {
+                            ranges.push(range.start + start..range.end);
+                        }
+                    }
+                    RegexOperation::Keep | RegexOperation::Remove => {
+                        if regex.is_match(&text) == (operation == RegexOperation::Keep) {
+                            ranges.push(if selection.reversed {
+                                range.end..range.start
+                            } else {
+                                range
+                            });
+                        }
+                    }
+                }
+            }
+
+            if !ranges.is_empty() {
+                // Like in Helix, the first of the new selections becomes the primary one.
+                ranges.rotate_left(1);
+                editor.change_selections(Some(Autoscroll::fit()), cx, |s| s.select_ranges(ranges));
+            }
+        });
+    }
+}
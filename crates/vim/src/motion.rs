@@ -530,7 +530,7 @@ impl Vim {
                     }
                 }
 
-                Mode::HelixNormal => {}
+                Mode::HelixNormal | Mode::HelixSelect => {}
             }
         }
 
@@ -561,7 +561,9 @@ impl Vim {
                 self.visual_motion(motion.clone(), count, cx)
             }
 
-            Mode::HelixNormal => self.helix_normal_motion(motion.clone(), count, cx),
+            Mode::HelixNormal | Mode::HelixSelect => {
+                self.helix_normal_motion(motion.clone(), count, cx)
+            }
         }
         self.clear_operator(cx);
         if let Some(operator) = waiting_operator {
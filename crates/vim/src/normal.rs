@@ -146,6 +146,7 @@ pub(crate) fn register(editor: &mut Editor, cx: &mut ViewContext<Vim>) {
         });
     });
 
+    mark::register(editor, cx);
     repeat::register(editor, cx);
     scroll::register(editor, cx);
     search::register(editor, cx);
@@ -1,20 +1,31 @@
-use std::{ops::Range, sync::Arc};
+use std::{ops::Range, path::Path, sync::Arc};
 
 use editor::{
     display_map::{DisplaySnapshot, ToDisplayPoint},
     movement,
     scroll::Autoscroll,
-    Anchor, Bias, DisplayPoint,
+    Anchor, Bias, DisplayPoint, Editor,
 };
-use gpui::ViewContext;
-use language::SelectionGoal;
+use gpui::{Task, ViewContext};
+use language::{Point, SelectionGoal};
+use workspace::pane::{GoBack, NavigationMode};
 
 use crate::{
     motion::{self, Motion},
-    state::Mode,
+    state::{GlobalMark, Mode},
     Vim,
 };
 
+pub(crate) fn register(editor: &mut Editor, cx: &mut ViewContext<Vim>) {
+    Vim::action(editor, cx, |vim, _: &GoBack, cx| vim.go_back(cx));
+}
+
+/// Marks named with an uppercase letter are shared by all the editors, and remember their file.
+fn is_global_mark(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(|c| c.is_ascii_uppercase()) && chars.next().is_none()
+}
+
 impl Vim {
     pub fn create_mark(&mut self, text: Arc<str>, tail: bool, cx: &mut ViewContext<Self>) {
         let Some(anchors) = self.update_editor(cx, |_, editor, _| {
@@ -27,10 +38,136 @@ impl Vim {
         }) else {
             return;
         };
+        if is_global_mark(&text) {
+            if let Some(mark) = anchors
+                .last()
+                .and_then(|anchor| self.global_mark_at(*anchor, cx))
+            {
+                Vim::globals(cx).global_marks.insert(text.to_string(), mark);
+                self.clear_operator(cx);
+                return;
+            }
+        }
         self.marks.insert(text.to_string(), anchors);
         self.clear_operator(cx);
     }
 
+    /// Returns a global mark at the given position, if the editor shows a file on disk there.
+    fn global_mark_at(&mut self, anchor: Anchor, cx: &mut ViewContext<Self>) -> Option<GlobalMark> {
+        self.update_editor(cx, |_, editor, cx| {
+            let (buffer, anchor) = editor
+                .buffer()
+                .read(cx)
+                .text_anchor_for_position(anchor, cx)?;
+            let path = buffer.read(cx).file()?.as_local()?.abs_path(cx);
+            let point = buffer.read(cx).summary_for_anchor::<Point>(&anchor);
+            Some(GlobalMark {
+                path: path.into(),
+                point,
+                buffer: Some((buffer.downgrade(), anchor)),
+            })
+        })
+        .flatten()
+    }
+
+    /// Returns the position of a global mark in this editor. When the mark is in another file,
+    /// that file is opened instead.
+    fn global_mark_anchors(
+        &mut self,
+        name: &str,
+        line: bool,
+        cx: &mut ViewContext<Self>,
+    ) -> Option<Vec<Anchor>> {
+        let mark = Vim::globals(cx).global_marks.get(name).cloned()?;
+        let point = mark.point(cx);
+        let anchor = self
+            .update_editor(cx, |_, editor, cx| {
+                let buffer = editor.buffer().read(cx).as_singleton()?;
+                let path = buffer.read(cx).file()?.as_local()?.abs_path(cx);
+                if path.as_path() != mark.path.as_ref() {
+                    return None;
+                }
+                let snapshot = editor.buffer().read(cx).snapshot(cx);
+                Some(snapshot.anchor_before(snapshot.clip_point(point, Bias::Left)))
+            })
+            .flatten();
+        if anchor.is_none() && self.active_operator().is_none() {
+            self.open_global_mark(&mark.path, point, line, cx)
+                .detach_and_log_err(cx);
+        }
+        anchor.map(|anchor| vec![anchor])
+    }
+
+    fn open_global_mark(
+        &mut self,
+        path: &Path,
+        point: Point,
+        line: bool,
+        cx: &mut ViewContext<Self>,
+    ) -> Task<anyhow::Result<()>> {
+        let Some(workspace) = self.workspace(cx) else {
+            return Task::ready(Ok(()));
+        };
+        let open = workspace.update(cx, |workspace, cx| {
+            workspace.open_abs_path(path.to_path_buf(), false, cx)
+        });
+        cx.spawn(|_, mut cx| async move {
+            let Some(editor) = open.await?.downcast::<Editor>() else {
+                return Ok(());
+            };
+            editor.update(&mut cx, |editor, cx| {
+                let map = editor.snapshot(cx);
+                let mut point = map.buffer_snapshot.clip_point(point, Bias::Left);
+                if line {
+                    let display_point = motion::first_non_whitespace(
+                        &map.display_snapshot,
+                        false,
+                        point.to_display_point(&map.display_snapshot),
+                    );
+                    point = display_point.to_point(&map.display_snapshot);
+                }
+                editor.change_selections(Some(Autoscroll::center()), cx, |s| {
+                    s.select_ranges([point..point])
+                });
+            })
+        })
+    }
+
+    /// Once the pane's history is exhausted, `ctrl-o` continues into the jump list of the
+    /// previous session.
+    fn go_back(&mut self, cx: &mut ViewContext<Self>) {
+        let Some(pane) = self
+            .workspace(cx)
+            .map(|workspace| workspace.read(cx).active_pane().clone())
+        else {
+            cx.propagate();
+            return;
+        };
+        if pane.read(cx).can_navigate_backward() {
+            cx.propagate();
+            return;
+        }
+        let Some((path, point)) = Vim::globals(cx).previous_session_jumps.pop() else {
+            cx.propagate();
+            return;
+        };
+
+        // Opening the file while going back records the current location in the forward
+        // history, so that `ctrl-i` returns to it.
+        pane.update(cx, |pane, _| {
+            pane.nav_history_mut().set_mode(NavigationMode::GoingBack)
+        });
+        let open = self.open_global_mark(&path, point, false, cx);
+        cx.spawn(|_, mut cx| async move {
+            let result = open.await;
+            pane.update(&mut cx, |pane, _| {
+                pane.nav_history_mut().set_mode(NavigationMode::Normal)
+            })?;
+            result
+        })
+        .detach_and_log_err(cx);
+    }
+
     // When handling an action, you must create visual marks if you will switch to normal
     // mode without the default selection behavior.
     pub(crate) fn store_visual_marks(&mut self, cx: &mut ViewContext<Self>) {
@@ -85,6 +222,9 @@ impl Vim {
                     .collect::<Vec<Anchor>>()
             }),
             "." => self.change_list.last().cloned(),
+            _ if is_global_mark(&text) && !self.marks.contains_key(&*text) => {
+                self.global_mark_anchors(&text, line, cx)
+            }
             _ => self.marks.get(&*text).cloned(),
         };
 
@@ -187,8 +187,10 @@ impl Vim {
                 let prior_mode = self.search.prior_mode;
                 let prior_operator = self.search.prior_operator.take();
 
-                let query = search_bar.query(cx).into();
-                Vim::globals(cx).registers.insert('/', query);
+                let query = search_bar.query(cx);
+                let globals = Vim::globals(cx);
+                globals.push_search_history(query.clone());
+                globals.registers.insert('/', query.into());
                 Some((prior_selections, prior_mode, prior_operator))
             })
         });
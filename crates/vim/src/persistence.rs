@@ -0,0 +1,176 @@
+use std::path::PathBuf;
+
+use anyhow::Result;
+use db::{define_connection, query, sqlez_macros::sql};
+
+const COMMAND_HISTORY: &str = "command";
+const SEARCH_HISTORY: &str = "search";
+
+/// The state that is kept between sessions, like Vim's viminfo file.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub(crate) struct PersistedState {
+    /// The name, text and serialized clipboard selections of each register.
+    pub registers: Vec<(String, String, Option<String>)>,
+    /// The name, path, row and column of each global mark.
+    pub global_marks: Vec<(String, PathBuf, u32, u32)>,
+    /// The path, row and column of each entry of the jump list, from the oldest.
+    pub jumps: Vec<(PathBuf, u32, u32)>,
+    pub command_history: Vec<String>,
+    pub search_history: Vec<String>,
+}
+
+define_connection! {
+    pub static ref VIM_DB: VimDb<()> =
+        &[sql!(
+            CREATE TABLE vim_registers (
+                name TEXT PRIMARY KEY,
+                text TEXT NOT NULL,
+                clipboard_selections TEXT
+            ) STRICT;
+
+            CREATE TABLE vim_global_marks (
+                name TEXT PRIMARY KEY,
+                path BLOB NOT NULL,
+                point_row INTEGER NOT NULL,
+                point_column INTEGER NOT NULL
+            ) STRICT;
+
+            CREATE TABLE vim_history (
+                kind TEXT NOT NULL,
+                position INTEGER NOT NULL,
+                entry TEXT NOT NULL,
+                PRIMARY KEY(kind, position)
+            ) STRICT;
+        ),
+        sql!(
+            CREATE TABLE vim_jumps (
+                position INTEGER PRIMARY KEY,
+                path BLOB NOT NULL,
+                point_row INTEGER NOT NULL,
+                point_column INTEGER NOT NULL
+            ) STRICT;
+        )];
+}
+
+impl VimDb {
+    query! {
+        fn get_registers() -> Result<Vec<(String, String, Option<String>)>> {
+            SELECT name, text, clipboard_selections FROM vim_registers
+        }
+    }
+
+    query! {
+        fn get_global_marks() -> Result<Vec<(String, PathBuf, u32, u32)>> {
+            SELECT name, path, point_row, point_column FROM vim_global_marks
+        }
+    }
+
+    query! {
+        fn get_jumps() -> Result<Vec<(PathBuf, u32, u32)>> {
+            SELECT path, point_row, point_column FROM vim_jumps
+            ORDER BY position
+        }
+    }
+
+    query! {
+        fn get_history(kind: &str) -> Result<Vec<String>> {
+            SELECT entry FROM vim_history
+            WHERE kind = ?
+            ORDER BY position
+        }
+    }
+
+    pub(crate) fn load(&self) -> Result<PersistedState> {
+        Ok(PersistedState {
+            registers: self.get_registers()?,
+            global_marks: self.get_global_marks()?,
+            jumps: self.get_jumps()?,
+            command_history: self.get_history(COMMAND_HISTORY)?,
+            search_history: self.get_history(SEARCH_HISTORY)?,
+        })
+    }
+
+    /// Replaces the previously saved state.
+    pub(crate) async fn save(&self, state: PersistedState) -> Result<()> {
+        self.write(move |conn| {
+            conn.with_savepoint("save_vim_state", || {
+                conn.exec(sql!(
+                    DELETE FROM vim_registers;
+                    DELETE FROM vim_global_marks;
+                    DELETE FROM vim_jumps;
+                    DELETE FROM vim_history;
+                ))?()?;
+
+                let mut insert_register = conn.exec_bound(sql!(
+                    INSERT INTO vim_registers(name, text, clipboard_selections) VALUES (?, ?, ?)
+                ))?;
+                for register in state.registers {
+                    insert_register(register)?;
+                }
+
+                let mut insert_mark = conn.exec_bound(sql!(
+                    INSERT INTO vim_global_marks(name, path, point_row, point_column)
+                    VALUES (?, ?, ?, ?)
+                ))?;
+                for mark in state.global_marks {
+                    insert_mark(mark)?;
+                }
+
+                let mut insert_jump = conn.exec_bound(sql!(
+                    INSERT INTO vim_jumps(position, path, point_row, point_column)
+                    VALUES (?, ?, ?, ?)
+                ))?;
+                for (position, (path, row, column)) in state.jumps.into_iter().enumerate() {
+                    insert_jump((position, path, row, column))?;
+                }
+
+                let mut insert_entry = conn.exec_bound(sql!(
+                    INSERT INTO vim_history(kind, position, entry) VALUES (?, ?, ?)
+                ))?;
+                for (position, entry) in state.command_history.into_iter().enumerate() {
+                    insert_entry((COMMAND_HISTORY, position, entry))?;
+                }
+                for (position, entry) in state.search_history.into_iter().enumerate() {
+                    insert_entry((SEARCH_HISTORY, position, entry))?;
+                }
+                Ok(())
+            })
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[gpui::test]
+    async fn test_save_and_load_state() {
+        let db = VimDb(db::open_test_db("test_save_and_load_state").await);
+        assert_eq!(db.load().unwrap(), PersistedState::default());
+
+        let state = PersistedState {
+            registers: vec![
+                ("a".to_string(), "one".to_string(), None),
+                ("1".to_string(), "two\n".to_string(), Some("[]".to_string())),
+            ],
+            global_marks: vec![("A".to_string(), PathBuf::from("/root/file.rs"), 3, 4)],
+            jumps: vec![
+                (PathBuf::from("/root/file.rs"), 10, 0),
+                (PathBuf::from("/root/other.rs"), 2, 5),
+                (PathBuf::from("/root/file.rs"), 1, 1),
+            ],
+            command_history: vec!["w".to_string(), "%s/a/b".to_string()],
+            search_history: vec!["foo".to_string()],
+        };
+        db.save(state.clone()).await.unwrap();
+        assert_eq!(db.load().unwrap(), state);
+
+        let state = PersistedState {
+            command_history: vec!["q".to_string()],
+            ..Default::default()
+        };
+        db.save(state.clone()).await.unwrap();
+        assert_eq!(db.load().unwrap(), state);
+    }
+}
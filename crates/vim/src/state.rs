@@ -1,21 +1,25 @@
 use std::borrow::BorrowMut;
+use std::path::{Path, PathBuf};
 use std::{fmt::Display, ops::Range, sync::Arc};
 
 use crate::command::command_interceptor;
 use crate::normal::repeat::Replayer;
+use crate::persistence::{PersistedState, VIM_DB};
 use crate::surrounds::SurroundsType;
 use crate::{motion::Motion, object::Object};
 use crate::{UseSystemClipboard, Vim, VimSettings};
 use collections::HashMap;
 use command_palette_hooks::{CommandPaletteFilter, CommandPaletteInterceptor};
-use editor::{Anchor, ClipboardSelection, Editor};
+use editor::{Anchor, ClipboardSelection, Editor, NavigationData, ToPoint as _};
 use gpui::{
-    Action, AppContext, BorrowAppContext, ClipboardEntry, ClipboardItem, Global, View, WeakView,
+    Action, AppContext, BorrowAppContext, ClipboardEntry, ClipboardItem, Global, View, WeakModel,
+    WeakView,
 };
-use language::Point;
+use language::{Buffer, Point};
 use serde::{Deserialize, Serialize};
 use settings::{Settings, SettingsStore};
 use ui::{SharedString, ViewContext};
+use util::ResultExt;
 use workspace::searchable::Direction;
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
@@ -27,6 +31,7 @@ pub enum Mode {
     VisualLine,
     VisualBlock,
     HelixNormal,
+    HelixSelect,
 }
 
 impl Display for Mode {
@@ -39,6 +44,7 @@ impl Display for Mode {
             Mode::VisualLine => write!(f, "VISUAL LINE"),
             Mode::VisualBlock => write!(f, "VISUAL BLOCK"),
             Mode::HelixNormal => write!(f, "HELIX NORMAL"),
+            Mode::HelixSelect => write!(f, "HELIX SELECT"),
         }
     }
 }
@@ -48,9 +54,13 @@ impl Mode {
         match self {
             Mode::Normal | Mode::Insert | Mode::Replace => false,
             Mode::Visual | Mode::VisualLine | Mode::VisualBlock => true,
-            Mode::HelixNormal => false,
+            Mode::HelixNormal | Mode::HelixSelect => false,
         }
     }
+
+    pub fn is_helix(&self) -> bool {
+        matches!(self, Mode::HelixNormal | Mode::HelixSelect)
+    }
 }
 
 impl Default for Mode {
@@ -147,6 +157,37 @@ impl From<String> for Register {
     }
 }
 
+/// A mark set with an uppercase letter, which can be jumped to from any file.
+#[derive(Clone, Debug)]
+pub struct GlobalMark {
+    pub path: Arc<Path>,
+    pub point: Point,
+    /// The buffer the mark was set in during this session, so that it follows the edits.
+    pub buffer: Option<(WeakModel<Buffer>, language::Anchor)>,
+}
+
+impl GlobalMark {
+    pub fn point(&self, cx: &AppContext) -> Point {
+        self.buffer
+            .as_ref()
+            .and_then(|(buffer, anchor)| {
+                Some(
+                    buffer
+                        .upgrade()?
+                        .read(cx)
+                        .summary_for_anchor::<Point>(anchor),
+                )
+            })
+            .unwrap_or(self.point)
+    }
+}
+
+/// The number of command and search history entries that are kept.
+const MAX_HISTORY_LEN: usize = 50;
+
+/// The number of jump list entries that are kept between sessions, as in Vim.
+const MAX_PERSISTED_JUMPS: usize = 100;
+
 #[derive(Default, Clone)]
 pub struct VimGlobals {
     pub last_find: Option<Motion>,
@@ -175,6 +216,13 @@ pub struct VimGlobals {
     pub registers: HashMap<char, Register>,
     pub recordings: HashMap<char, Vec<ReplayableAction>>,
 
+    pub global_marks: HashMap<String, GlobalMark>,
+    /// The jump list of the previous session, from the oldest entry, which `ctrl-o` continues
+    /// into once the pane's own history is exhausted.
+    pub previous_session_jumps: Vec<(Arc<Path>, Point)>,
+    pub command_history: Vec<String>,
+    pub search_history: Vec<String>,
+
     pub focused_vim: Option<WeakView<Vim>>,
 }
 impl Global for VimGlobals {}
@@ -183,6 +231,25 @@ impl VimGlobals {
     pub(crate) fn register(cx: &mut AppContext) {
         cx.set_global(VimGlobals::default());
 
+        cx.spawn(|mut cx| async move {
+            let state = cx
+                .background_executor()
+                .spawn(async move { VIM_DB.load() })
+                .await?;
+            cx.update(|cx| Vim::globals(cx).restore(state))
+        })
+        .detach_and_log_err(cx);
+
+        cx.on_app_quit(|cx| {
+            let state = Vim::enabled(cx).then(|| cx.global::<VimGlobals>().persisted_state(cx));
+            async move {
+                if let Some(state) = state {
+                    VIM_DB.save(state).await.log_err();
+                }
+            }
+        })
+        .detach();
+
         cx.observe_keystrokes(|event, cx| {
             let Some(action) = event.action.as_ref().map(|action| action.boxed_clone()) else {
                 return;
@@ -198,6 +265,12 @@ impl VimGlobals {
                 });
                 CommandPaletteInterceptor::update_global(cx, |interceptor, _| {
                     interceptor.set(Box::new(command_interceptor));
+                    interceptor.set_run_handler(Box::new(|query, cx| {
+                        let command = query.strip_prefix(':').unwrap_or(query).trim();
+                        if !command.is_empty() {
+                            Vim::globals(cx).push_command_history(command.to_string());
+                        }
+                    }));
                 });
             } else {
                 *Vim::globals(cx) = VimGlobals::default();
@@ -306,7 +379,8 @@ impl VimGlobals {
         };
         let lower = register.to_lowercase().next().unwrap_or(register);
         match lower {
-            '_' | ':' | '.' | '#' | '=' => None,
+            ':' => self.command_history.last().cloned().map(Register::from),
+            '_' | '.' | '#' | '=' => None,
             '+' => cx.read_from_clipboard().map(|item| item.into()),
             '*' => {
                 #[cfg(any(target_os = "linux", target_os = "freebsd"))]
@@ -337,6 +411,148 @@ impl VimGlobals {
         }
     }
 
+    pub(crate) fn push_command_history(&mut self, command: String) {
+        push_history(&mut self.command_history, command);
+    }
+
+    pub(crate) fn push_search_history(&mut self, query: String) {
+        push_history(&mut self.search_history, query);
+    }
+
+    /// The registers, global marks, jump list and history to keep for the next session.
+    pub(crate) fn persisted_state(&self, cx: &AppContext) -> PersistedState {
+        let mut registers = self
+            .registers
+            .iter()
+            .filter(|(name, _)| name.is_ascii_alphanumeric() || **name == '-')
+            .map(|(name, register)| {
+                (
+                    name.to_string(),
+                    register.text.to_string(),
+                    register
+                        .clipboard_selections
+                        .as_ref()
+                        .and_then(|selections| serde_json::to_string(selections).log_err()),
+                )
+            })
+            .collect::<Vec<_>>();
+        registers.sort();
+
+        let mut global_marks = self
+            .global_marks
+            .iter()
+            .map(|(name, mark)| {
+                let point = mark.point(cx);
+                (
+                    name.clone(),
+                    mark.path.to_path_buf(),
+                    point.row,
+                    point.column,
+                )
+            })
+            .collect::<Vec<_>>();
+        global_marks.sort();
+
+        let mut jumps = self
+            .previous_session_jumps
+            .iter()
+            .map(|(path, point)| (path.to_path_buf(), point.row, point.column))
+            .collect::<Vec<_>>();
+        jumps.extend(self.current_jumps(cx));
+        jumps.drain(..jumps.len().saturating_sub(MAX_PERSISTED_JUMPS));
+
+        PersistedState {
+            registers,
+            global_marks,
+            jumps,
+            command_history: self.command_history.clone(),
+            search_history: self.search_history.clone(),
+        }
+    }
+
+    /// The entries of the focused workspace's jump list, from the oldest.
+    fn current_jumps(&self, cx: &AppContext) -> Vec<(PathBuf, u32, u32)> {
+        let Some(workspace) = self
+            .focused_vim
+            .as_ref()
+            .and_then(|vim| vim.upgrade())
+            .and_then(|vim| vim.read(cx).editor())
+            .and_then(|editor| editor.read(cx).workspace())
+        else {
+            return Vec::new();
+        };
+        let workspace = workspace.read(cx);
+        let project = workspace.project().read(cx);
+        let mut jumps = Vec::new();
+        workspace
+            .active_pane()
+            .read(cx)
+            .nav_history()
+            .for_each_backward_entry(cx, |entry, (project_path, abs_path)| {
+                let Some(data) = entry
+                    .data
+                    .as_ref()
+                    .and_then(|data| data.downcast_ref::<NavigationData>())
+                else {
+                    return;
+                };
+                let Some(path) = abs_path.or_else(|| project.absolute_path(&project_path, cx))
+                else {
+                    return;
+                };
+                let point = entry
+                    .item
+                    .upgrade()
+                    .and_then(|item| item.downcast::<Editor>())
+                    .map(|editor| {
+                        let buffer = editor.read(cx).buffer().read(cx).snapshot(cx);
+                        data.cursor_anchor().to_point(&buffer)
+                    })
+                    .unwrap_or_else(|| data.cursor_position());
+                jumps.push((path, point.row, point.column));
+            });
+        jumps
+    }
+
+    /// Restores the state of the previous session, keeping anything set since startup.
+    pub(crate) fn restore(&mut self, state: PersistedState) {
+        for (name, text, clipboard_selections) in state.registers {
+            let Some(name) = name.chars().next() else {
+                continue;
+            };
+            self.registers.entry(name).or_insert_with(|| Register {
+                text: text.into(),
+                clipboard_selections: clipboard_selections
+                    .and_then(|selections| serde_json::from_str(&selections).log_err()),
+            });
+        }
+        for (name, path, row, column) in state.global_marks {
+            self.global_marks.entry(name).or_insert_with(|| GlobalMark {
+                path: Arc::from(path),
+                point: Point::new(row, column),
+                buffer: None,
+            });
+        }
+        if self.previous_session_jumps.is_empty() {
+            self.previous_session_jumps = state
+                .jumps
+                .into_iter()
+                .map(|(path, row, column)| (Arc::from(path), Point::new(row, column)))
+                .collect();
+        }
+        if self.command_history.is_empty() {
+            self.command_history = state.command_history;
+        }
+        if self.search_history.is_empty() {
+            self.search_history = state.search_history;
+        }
+        if let Some(query) = self.search_history.last() {
+            self.registers
+                .entry('/')
+                .or_insert_with(|| query.clone().into());
+        }
+    }
+
     fn system_clipboard_is_newer(&self, cx: &ViewContext<Editor>) -> bool {
         cx.read_from_clipboard().is_some_and(|item| {
             if let Some(last_state) = &self.last_yank {
@@ -399,6 +615,14 @@ impl VimGlobals {
     }
 }
 
+fn push_history(history: &mut Vec<String>, entry: String) {
+    history.retain(|existing| *existing != entry);
+    history.push(entry);
+    if history.len() > MAX_HISTORY_LEN {
+        history.drain(..history.len() - MAX_HISTORY_LEN);
+    }
+}
+
 impl Vim {
     pub fn globals(cx: &mut AppContext) -> &mut VimGlobals {
         cx.global_mut::<VimGlobals>()
@@ -497,7 +721,9 @@ impl Operator {
 
     pub fn is_waiting(&self, mode: Mode) -> bool {
         match self {
-            Operator::AddSurrounds { target } => target.is_some() || mode.is_visual(),
+            Operator::AddSurrounds { target } => {
+                target.is_some() || mode.is_visual() || mode.is_helix()
+            }
             Operator::FindForward { .. }
             | Operator::Mark
             | Operator::Jump { .. }
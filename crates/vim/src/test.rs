@@ -1,3 +1,4 @@
+mod helix_test_context;
 mod neovim_backed_test_context;
 mod neovim_connection;
 mod vim_test_context;
@@ -9,15 +10,17 @@ use command_palette::CommandPalette;
 use editor::{actions::DeleteLine, display_map::DisplayRow, DisplayPoint};
 use futures::StreamExt;
 use gpui::{KeyBinding, Modifiers, MouseButton, TestAppContext};
+pub use helix_test_context::*;
 pub use neovim_backed_test_context::*;
 use settings::SettingsStore;
 pub use vim_test_context::*;
 
 use indoc::indoc;
+use language::Point;
 use search::BufferSearchBar;
 use workspace::WorkspaceSettings;
 
-use crate::{insert::NormalBefore, motion, state::Mode};
+use crate::{insert::NormalBefore, motion, state::Mode, Vim};
 
 #[gpui::test]
 async fn test_initially_disabled(cx: &mut gpui::TestAppContext) {
@@ -1603,3 +1606,21 @@ async fn test_ctrl_o_dot(cx: &mut gpui::TestAppContext) {
     cx.simulate_shared_keystrokes("l l escape .").await;
     cx.shared_state().await.assert_eq("hellˇllo world.");
 }
+
+#[gpui::test]
+async fn test_uppercase_marks(cx: &mut TestAppContext) {
+    let mut cx = VimTestContext::new(cx, true).await;
+
+    cx.set_state("line one\n  line ˇtwo\nline three", Mode::Normal);
+    cx.simulate_keystrokes("m shift-a g g shift-o n e w escape");
+    cx.assert_state("neˇw\nline one\n  line two\nline three", Mode::Normal);
+    cx.update(|cx| {
+        let mark = Vim::globals(cx).global_marks.get("A").cloned().unwrap();
+        assert_eq!(mark.point(cx), Point::new(2, 7));
+    });
+
+    cx.simulate_keystrokes("' shift-a");
+    cx.assert_state("new\nline one\n  ˇline two\nline three", Mode::Normal);
+    cx.simulate_keystrokes("g g ` shift-a");
+    cx.assert_state("new\nline one\n  line ˇtwo\nline three", Mode::Normal);
+}
@@ -0,0 +1,207 @@
+use std::ops::{Deref, DerefMut, Range};
+
+use gpui::UpdateGlobal;
+use settings::SettingsStore;
+use vim_mode_setting::HelixModeSetting;
+
+use super::VimTestContext;
+use crate::state::Mode;
+
+/// A test context for Helix mode, which runs test cases written in the notation of Helix's
+/// own integration tests, so that they can be copied over to check that Zed behaves the same.
+///
+/// In that notation, `#[` and `]#` delimit the primary selection, `#(` and `)#` delimit the
+/// other selections, and `|` marks the side of each selection that holds the cursor. Keys are
+/// written the way Helix writes them, like `x`, `<A-s>` or `sfoo<ret>`.
+pub struct HelixTestContext {
+    cx: VimTestContext,
+}
+
+impl HelixTestContext {
+    pub async fn new(cx: &mut gpui::TestAppContext) -> HelixTestContext {
+        let mut cx = VimTestContext::new(cx, true).await;
+        cx.update(|cx| {
+            SettingsStore::update_global(cx, |store, cx| {
+                store.update_user_settings::<HelixModeSetting>(cx, |s| *s = Some(true));
+            });
+            settings::KeymapFile::load_asset("keymaps/helix.json", cx).unwrap();
+        });
+        Self { cx }
+    }
+
+    pub fn set_helix_state(&mut self, marked_text: &str) {
+        let (text, mut selections) = parse_helix_state(marked_text);
+        self.cx.set_state(
+            &util::test::generate_marked_text(&text, &[0..0], true),
+            Mode::HelixNormal,
+        );
+        // The newest selection is the primary one.
+        let primary = selections.remove(0);
+        selections.push(primary);
+        self.update_editor(|editor, cx| {
+            editor.change_selections(None, cx, |s| s.select_ranges(selections))
+        });
+    }
+
+    pub fn helix_state(&mut self) -> String {
+        let text = self.buffer_text();
+        let (selections, primary_id) = self.update_editor(|editor, cx| {
+            (
+                editor.selections.all::<usize>(cx),
+                editor.selections.newest_anchor().id,
+            )
+        });
+
+        let mut marked_text = String::new();
+        let mut offset = 0;
+        for selection in selections {
+            let mut range = selection.range();
+            // Helix selections always contain at least the character under the cursor.
+            if range.is_empty() {
+                if let Some(ch) = text[range.end..].chars().next() {
+                    range.end += ch.len_utf8();
+                }
+            }
+            let (open, close) = if selection.id == primary_id {
+                ("#[", "]#")
+            } else {
+                ("#(", ")#")
+            };
+            marked_text.push_str(&text[offset..range.start]);
+            marked_text.push_str(open);
+            if selection.reversed {
+                marked_text.push('|');
+            }
+            marked_text.push_str(&text[range.clone()]);
+            if !selection.reversed {
+                marked_text.push('|');
+            }
+            marked_text.push_str(close);
+            offset = range.end;
+        }
+        marked_text.push_str(&text[offset..]);
+        marked_text
+    }
+
+    #[track_caller]
+    pub fn assert_helix_state(&mut self, marked_text: &str) {
+        let state = self.helix_state();
+        assert_eq!(state, marked_text, "{}", self.assertion_context());
+    }
+
+    /// Runs a test case from Helix: sets the state, types the keys and checks the result.
+    #[track_caller]
+    pub fn assert_helix(&mut self, before: &str, keys: &str, after: &str) {
+        self.set_helix_state(before);
+        self.simulate_keystrokes(&helix_keys(keys));
+        let state = self.helix_state();
+        assert_eq!(
+            state,
+            after,
+            "typing {keys:?} on {before:?}\n{}",
+            self.assertion_context()
+        );
+    }
+}
+
+/// Returns the text and the selections, with the primary selection first.
+fn parse_helix_state(marked_text: &str) -> (String, Vec<Range<usize>>) {
+    let mut text = String::new();
+    let mut selections = Vec::new();
+    let mut current: Option<(usize, Option<usize>, bool)> = None;
+    let mut chars = marked_text.chars().peekable();
+    while let Some(ch) = chars.next() {
+        match (ch, chars.peek(), current) {
+            ('#', Some(&open @ ('[' | '(')), None) => {
+                chars.next();
+                current = Some((text.len(), None, open == '['));
+            }
+            (close @ (']' | ')'), Some(&'#'), Some((start, head, primary)))
+                if (close == ']') == primary =>
+            {
+                chars.next();
+                let end = text.len();
+                let range = if head == Some(start) && start != end {
+                    end..start
+                } else {
+                    start..end
+                };
+                if primary {
+                    selections.insert(0, range);
+                } else {
+                    selections.push(range);
+                }
+                current = None;
+            }
+            ('|', _, Some((start, None, primary))) => {
+                current = Some((start, Some(text.len()), primary));
+            }
+            _ => text.push(ch),
+        }
+    }
+    assert!(current.is_none(), "unclosed selection in {marked_text:?}");
+    assert!(
+        !selections.is_empty(),
+        "no primary selection in {marked_text:?}"
+    );
+    (text, selections)
+}
+
+/// Converts keys written in Helix's notation to Zed keystrokes.
+fn helix_keys(keys: &str) -> String {
+    fn key(name: &str) -> String {
+        match name {
+            "esc" => "escape".into(),
+            "ret" => "enter".into(),
+            "space" | " " => "space".into(),
+            "lt" => "<".into(),
+            "gt" => ">".into(),
+            "minus" => "-".into(),
+            "tab" | "backspace" | "del" | "home" | "end" | "left" | "right" | "up" | "down" => {
+                name.into()
+            }
+            _ => {
+                if let Some(rest) = name.strip_prefix("A-") {
+                    format!("alt-{}", key(rest))
+                } else if let Some(rest) = name.strip_prefix("C-") {
+                    format!("ctrl-{}", key(rest))
+                } else if let Some(rest) = name.strip_prefix("S-") {
+                    format!("shift-{}", key(rest))
+                } else if name.chars().all(|ch| ch.is_ascii_uppercase()) {
+                    format!("shift-{}", name.to_ascii_lowercase())
+                } else {
+                    name.into()
+                }
+            }
+        }
+    }
+
+    let mut keystrokes = Vec::new();
+    let mut rest = keys;
+    while let Some(ch) = rest.chars().next() {
+        if ch == '<' {
+            if let Some(end) = rest.find('>') {
+                keystrokes.push(key(&rest[1..end]));
+                rest = &rest[end + 1..];
+                continue;
+            }
+        }
+        keystrokes.push(key(&rest[..ch.len_utf8()]));
+        rest = &rest[ch.len_utf8()..];
+    }
+    keystrokes.join(" ")
+}
+
+impl Deref for HelixTestContext {
+    type Target = VimTestContext;
+
+    fn deref(&self) -> &Self::Target {
+        &self.cx
+    }
+}
+
+impl DerefMut for HelixTestContext {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.cx
+    }
+}
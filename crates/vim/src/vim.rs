@@ -13,6 +13,7 @@ mod mode_indicator;
 mod motion;
 mod normal;
 mod object;
+mod persistence;
 mod replace;
 mod rewrap;
 mod state;
@@ -43,7 +44,7 @@ use std::{mem, ops::Range, sync::Arc};
 use surrounds::SurroundsType;
 use theme::ThemeSettings;
 use ui::{px, IntoElement, VisualContext};
-use vim_mode_setting::VimModeSetting;
+use vim_mode_setting::{HelixModeSetting, VimModeSetting};
 use workspace::{self, Pane, ResizeIntent, Workspace};
 
 use crate::state::ReplayableAction;
@@ -242,10 +243,11 @@ impl Vim {
 
     pub fn new(cx: &mut ViewContext<Editor>) -> View<Self> {
         let editor = cx.view().clone();
+        let mode = Vim::normal_mode(cx);
 
         cx.new_view(|cx| Vim {
-            mode: Mode::Normal,
-            last_mode: Mode::Normal,
+            mode,
+            last_mode: mode,
             temp_mode: false,
             exit_temporary_mode: false,
             operator_stack: Vec::new(),
@@ -278,9 +280,20 @@ impl Vim {
         }
 
         let mut was_enabled = Vim::enabled(cx);
+        let mut was_helix = HelixModeSetting::get_global(cx).0;
         let mut was_toggle = VimSettings::get_global(cx).toggle_relative_line_numbers;
         cx.observe_global::<SettingsStore>(move |editor, cx| {
             let enabled = Vim::enabled(cx);
+            let helix = HelixModeSetting::get_global(cx).0;
+            if enabled && was_enabled && helix != was_helix {
+                if let Some(vim) = editor.addon::<VimAddon>().map(|vim| vim.view.clone()) {
+                    vim.update(cx, |vim, cx| {
+                        let mode = Vim::normal_mode(cx);
+                        vim.switch_mode(mode, false, cx)
+                    });
+                }
+            }
+            was_helix = helix;
             let toggle = VimSettings::get_global(cx).toggle_relative_line_numbers;
             if enabled && was_enabled && (toggle != was_toggle) {
                 if toggle {
@@ -397,7 +410,16 @@ impl Vim {
     }
 
     pub fn enabled(cx: &mut AppContext) -> bool {
-        VimModeSetting::get_global(cx).0
+        VimModeSetting::get_global(cx).0 || HelixModeSetting::get_global(cx).0
+    }
+
+    /// The mode that escape returns to: Helix's normal mode when Helix mode is enabled.
+    pub fn normal_mode(cx: &AppContext) -> Mode {
+        if HelixModeSetting::get_global(cx).0 {
+            Mode::HelixNormal
+        } else {
+            Mode::Normal
+        }
     }
 
     /// Called whenever an keystroke is typed so vim can observe all actions
@@ -509,6 +531,12 @@ impl Vim {
         {
             self.temp_mode = false;
         }
+        // The vim actions shared with Helix mode return to the normal mode.
+        let mode = if mode == Mode::Normal {
+            Vim::normal_mode(cx)
+        } else {
+            mode
+        };
 
         let last_mode = self.mode;
         let prior_mode = self.last_mode;
@@ -646,9 +674,11 @@ impl Vim {
                 }
             }
             Mode::Replace => CursorShape::Underline,
-            Mode::HelixNormal | Mode::Visual | Mode::VisualLine | Mode::VisualBlock => {
-                CursorShape::Block
-            }
+            Mode::HelixNormal
+            | Mode::HelixSelect
+            | Mode::Visual
+            | Mode::VisualLine
+            | Mode::VisualBlock => CursorShape::Block,
             Mode::Insert => CursorShape::Bar,
         }
     }
@@ -664,6 +694,7 @@ impl Vim {
             }
             Mode::Normal
             | Mode::HelixNormal
+            | Mode::HelixSelect
             | Mode::Replace
             | Mode::Visual
             | Mode::VisualLine
@@ -682,7 +713,8 @@ impl Vim {
             | Mode::VisualLine
             | Mode::VisualBlock
             | Mode::Replace
-            | Mode::HelixNormal => false,
+            | Mode::HelixNormal
+            | Mode::HelixSelect => false,
             Mode::Normal => true,
         }
     }
@@ -694,6 +726,7 @@ impl Vim {
             Mode::Insert => "insert",
             Mode::Replace => "replace",
             Mode::HelixNormal => "helix_normal",
+            Mode::HelixSelect => "helix_select",
         }
         .to_string();
 
@@ -1022,7 +1055,7 @@ impl Vim {
                     })
                 });
             }
-            Mode::Insert | Mode::Replace | Mode::HelixNormal => {}
+            Mode::Insert | Mode::Replace | Mode::HelixNormal | Mode::HelixSelect => {}
         }
     }
 
@@ -1098,6 +1131,7 @@ impl Vim {
                 Mode::Visual | Mode::VisualLine | Mode::VisualBlock => {
                     self.visual_replace(text, cx)
                 }
+                Mode::HelixNormal | Mode::HelixSelect => self.helix_replace(text, cx),
                 _ => self.clear_operator(cx),
             },
             Some(Operator::Digraph { first_char }) => {
@@ -1125,10 +1159,15 @@ impl Vim {
                     self.add_surrounds(text, SurroundsType::Selection, cx);
                     self.clear_operator(cx);
                 }
+                Mode::HelixNormal | Mode::HelixSelect => {
+                    self.helix_widen_cursors(cx);
+                    self.add_surrounds(text, SurroundsType::Selection, cx);
+                    self.clear_operator(cx);
+                }
                 _ => self.clear_operator(cx),
             },
             Some(Operator::ChangeSurrounds { target }) => match self.mode {
-                Mode::Normal => {
+                Mode::Normal | Mode::HelixNormal | Mode::HelixSelect => {
                     if let Some(target) = target {
                         self.change_surrounds(text, target, cx);
                         self.clear_operator(cx);
@@ -1137,7 +1176,7 @@ impl Vim {
                 _ => self.clear_operator(cx),
             },
             Some(Operator::DeleteSurrounds) => match self.mode {
-                Mode::Normal => {
+                Mode::Normal | Mode::HelixNormal | Mode::HelixSelect => {
                     self.delete_surrounds(text, cx);
                     self.clear_operator(cx);
                 }
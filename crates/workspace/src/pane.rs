@@ -3129,6 +3129,26 @@ impl NavHistory {
             })
     }
 
+    /// Calls `f` with the entries that going back would visit, from the oldest to the most
+    /// recent one.
+    pub fn for_each_backward_entry(
+        &self,
+        cx: &AppContext,
+        mut f: impl FnMut(&NavigationEntry, (ProjectPath, Option<PathBuf>)),
+    ) {
+        let borrowed_history = self.0.lock();
+        for entry in &borrowed_history.backward_stack {
+            if let Some(project_and_abs_path) = borrowed_history.paths_by_item.get(&entry.item.id())
+            {
+                f(entry, project_and_abs_path.clone());
+            } else if let Some(item) = entry.item.upgrade() {
+                if let Some(path) = item.project_path(cx) {
+                    f(entry, (path, None));
+                }
+            }
+        }
+    }
+
     pub fn set_mode(&mut self, mode: NavigationMode) {
         self.0.lock().mode = mode;
     }
@@ -1,7 +1,21 @@
+use std::{cell::RefCell, rc::Rc};
+
+use anyhow::Result;
+use collections::HashMap;
+use futures::{
+    channel::oneshot,
+    future::{self, LocalBoxFuture, Shared},
+    FutureExt,
+};
+use gpui::{AsyncWindowContext, WeakView};
 use project::TaskSourceKind;
 use remote::ConnectionState;
-use task::{ResolvedTask, TaskContext, TaskTemplate};
+use task::{
+    DependsOrder, ResolvedTask, SpawnInTerminal, TaskContext, TaskDependencies, TaskId,
+    TaskTemplate,
+};
 use ui::ViewContext;
+use util::ResultExt;
 
 use crate::Workspace;
 
@@ -26,16 +40,24 @@ pub fn schedule_task(
         }
     }
 
-    if let Some(spawn_in_terminal) =
-        task_to_resolve.resolve_task(&task_source_kind.to_id_base(), task_cx)
-    {
-        schedule_resolved_task(
+    let task_templates = workspace
+        .project
+        .read(cx)
+        .task_store()
+        .read(cx)
+        .task_inventory()
+        .map(|inventory| inventory.read(cx).templates_from_source(&task_source_kind))
+        .unwrap_or_default();
+    match task_templates.resolve_task(task_to_resolve, &task_source_kind.to_id_base(), task_cx) {
+        Ok(Some(spawn_in_terminal)) => schedule_resolved_task(
             workspace,
             task_source_kind,
             spawn_in_terminal,
             omit_history,
             cx,
-        );
+        ),
+        Ok(None) => {}
+        Err(error) => workspace.show_error(&error, cx),
     }
 }
 
@@ -46,6 +68,10 @@ pub fn schedule_resolved_task(
     omit_history: bool,
     cx: &mut ViewContext<Workspace>,
 ) {
+    if let Some(error) = &resolved_task.dependency_error {
+        workspace.show_error(error, cx);
+        return;
+    }
     if let Some(spawn_in_terminal) = resolved_task.resolved.take() {
         if !omit_history {
             resolved_task.resolved = Some(spawn_in_terminal.clone());
@@ -54,14 +80,275 @@ pub fn schedule_resolved_task(
                     project.task_store().read(cx).task_inventory().cloned()
                 {
                     task_inventory.update(cx, |inventory, _| {
-                        inventory.task_scheduled(task_source_kind, resolved_task);
+                        inventory.task_scheduled(task_source_kind, resolved_task.clone());
                     })
                 }
             });
         }
 
+        if resolved_task.dependencies.tasks.is_empty() {
+            spawn_task(spawn_in_terminal, cx);
+        } else {
+            let dependencies = resolved_task.dependencies;
+            cx.spawn(|workspace, mut cx| async move {
+                let runs = TaskRuns::default();
+                if run_dependencies(workspace.clone(), dependencies, runs, cx.clone()).await {
+                    workspace.update(&mut cx, |_, cx| spawn_task(spawn_in_terminal, cx))?;
+                } else {
+                    log::warn!(
+                        "Not spawning task {:?}, as some of its dependencies failed",
+                        spawn_in_terminal.full_label
+                    );
+                }
+                anyhow::Ok(())
+            })
+            .detach_and_log_err(cx);
+        }
+    }
+}
+
+impl Workspace {
+    /// Reports that a spawned task finished, letting the tasks that depend on it continue.
+    pub fn task_finished(&mut self, id: &TaskId, success: bool) {
+        for waiter in self
+            .task_completion_waiters
+            .remove(id)
+            .into_iter()
+            .flatten()
+        {
+            waiter.send(success).ok();
+        }
+    }
+}
+
+/// Tasks without a command only group their dependencies, so there is nothing to spawn for them.
+fn spawn_task(spawn_in_terminal: SpawnInTerminal, cx: &mut ViewContext<Workspace>) {
+    if !spawn_in_terminal.command.is_empty() {
         cx.emit(crate::Event::SpawnTask {
             action: Box::new(spawn_in_terminal),
         });
     }
 }
+
+/// The dependencies already started for a scheduled task, by their ids, each resolving to whether it
+/// finished successfully, so that a dependency shared by several tasks only runs once.
+type TaskRuns = Rc<RefCell<HashMap<TaskId, Shared<LocalBoxFuture<'static, bool>>>>>;
+
+/// Runs the given dependencies in their order, resolving to whether all of them finished successfully.
+async fn run_dependencies(
+    workspace: WeakView<Workspace>,
+    dependencies: TaskDependencies,
+    runs: TaskRuns,
+    cx: AsyncWindowContext,
+) -> bool {
+    match dependencies.order {
+        DependsOrder::Sequence => {
+            for task in dependencies.tasks {
+                if !run_task_and_wait(workspace.clone(), task, runs.clone(), cx.clone()).await {
+                    return false;
+                }
+            }
+            true
+        }
+        DependsOrder::Parallel => future::join_all(
+            dependencies
+                .tasks
+                .into_iter()
+                .map(|task| run_task_and_wait(workspace.clone(), task, runs.clone(), cx.clone())),
+        )
+        .await
+        .into_iter()
+        .all(|success| success),
+    }
+}
+
+/// Runs the task after its own dependencies, unless it was already started, resolving to whether it
+/// finished successfully.
+fn run_task_and_wait(
+    workspace: WeakView<Workspace>,
+    task: ResolvedTask,
+    runs: TaskRuns,
+    cx: AsyncWindowContext,
+) -> Shared<LocalBoxFuture<'static, bool>> {
+    if let Some(run) = runs.borrow().get(&task.id) {
+        return run.clone();
+    }
+    let id = task.id.clone();
+    let run = run_task(workspace, task, runs.clone(), cx)
+        .map(|result| result.log_err().unwrap_or(false))
+        .boxed_local()
+        .shared();
+    runs.borrow_mut().insert(id, run.clone());
+    run
+}
+
+async fn run_task(
+    workspace: WeakView<Workspace>,
+    task: ResolvedTask,
+    runs: TaskRuns,
+    mut cx: AsyncWindowContext,
+) -> Result<bool> {
+    if !run_dependencies(workspace.clone(), task.dependencies, runs, cx.clone()).await {
+        return Ok(false);
+    }
+    let Some(spawn_in_terminal) = task.resolved else {
+        return Ok(true);
+    };
+    if spawn_in_terminal.command.is_empty() {
+        return Ok(true);
+    }
+
+    let (tx, rx) = oneshot::channel();
+    workspace.update(&mut cx, |workspace, cx| {
+        workspace
+            .task_completion_waiters
+            .entry(spawn_in_terminal.id.clone())
+            .or_default()
+            .push(tx);
+        spawn_task(spawn_in_terminal, cx);
+    })?;
+    Ok(rx.await.unwrap_or(false))
+}
+
+#[cfg(test)]
+mod tests {
+    use fs::FakeFs;
+    use gpui::{TestAppContext, View, VisualTestContext};
+    use project::Project;
+    use task::TaskTemplates;
+
+    use super::*;
+    use crate::{tests::init_test, Event};
+
+    #[gpui::test]
+    async fn test_failed_dependency_stops_the_chain(cx: &mut TestAppContext) {
+        init_test(cx);
+        let fs = FakeFs::new(cx.executor());
+        let project = Project::test(fs, [], cx).await;
+        let (workspace, cx) = cx.add_window_view(|cx| Workspace::test_new(project, cx));
+        let spawned = subscribe_to_spawned_tasks(&workspace, cx);
+
+        let task = TaskTemplate::test;
+        let templates = TaskTemplates(vec![
+            task("fails", "false", &[]),
+            task("never spawned", "echo never", &[]),
+            TaskTemplate {
+                depends_order: DependsOrder::Sequence,
+                ..task("test", "cargo test", &["fails", "never spawned"])
+            },
+        ]);
+        let resolved_task = templates
+            .resolve_task(&templates.0[2], "test", &TaskContext::default())
+            .unwrap()
+            .unwrap();
+        workspace.update(cx, |workspace, cx| {
+            schedule_resolved_task(
+                workspace,
+                TaskSourceKind::UserInput,
+                resolved_task,
+                true,
+                cx,
+            )
+        });
+        cx.run_until_parked();
+
+        let spawned_labels = || labels(&spawned);
+        assert_eq!(spawned_labels(), ["fails"]);
+        let failed_id = spawned.borrow()[0].1.clone();
+        workspace.update(cx, |workspace, _| {
+            assert_eq!(workspace.task_completion_waiters.len(), 1);
+            workspace.task_finished(&failed_id, false);
+        });
+        cx.run_until_parked();
+
+        assert_eq!(
+            spawned_labels(),
+            ["fails"],
+            "neither the remaining dependency nor the task itself should spawn after a failure"
+        );
+        workspace.update(cx, |workspace, _| {
+            assert!(workspace.task_completion_waiters.is_empty());
+        });
+    }
+
+    #[gpui::test]
+    async fn test_shared_dependency_runs_once(cx: &mut TestAppContext) {
+        init_test(cx);
+        let fs = FakeFs::new(cx.executor());
+        let project = Project::test(fs, [], cx).await;
+        let (workspace, cx) = cx.add_window_view(|cx| Workspace::test_new(project, cx));
+        let spawned = subscribe_to_spawned_tasks(&workspace, cx);
+
+        let task = TaskTemplate::test;
+        let templates = TaskTemplates(vec![
+            task("build", "cargo build", &[]),
+            task("lint", "cargo clippy", &["build"]),
+            task("all", "echo done", &["lint", "build"]),
+        ]);
+        let resolved_task = templates
+            .resolve_task(&templates.0[2], "test", &TaskContext::default())
+            .unwrap()
+            .unwrap();
+        workspace.update(cx, |workspace, cx| {
+            schedule_resolved_task(
+                workspace,
+                TaskSourceKind::UserInput,
+                resolved_task,
+                true,
+                cx,
+            )
+        });
+        cx.run_until_parked();
+
+        let spawned_labels = || labels(&spawned);
+        assert_eq!(
+            spawned_labels(),
+            ["build"],
+            "both \"lint\" and \"all\" should wait for the same build"
+        );
+        let build_id = spawned.borrow()[0].1.clone();
+        workspace.update(cx, |workspace, _| {
+            assert_eq!(workspace.task_completion_waiters.len(), 1);
+            assert_eq!(workspace.task_completion_waiters[&build_id].len(), 1);
+            workspace.task_finished(&build_id, true);
+        });
+        cx.run_until_parked();
+
+        assert_eq!(spawned_labels(), ["build", "lint"]);
+        let lint_id = spawned.borrow()[1].1.clone();
+        workspace.update(cx, |workspace, _| workspace.task_finished(&lint_id, true));
+        cx.run_until_parked();
+
+        assert_eq!(spawned_labels(), ["build", "lint", "all"]);
+        workspace.update(cx, |workspace, _| {
+            assert!(workspace.task_completion_waiters.is_empty());
+        });
+    }
+
+    fn subscribe_to_spawned_tasks(
+        workspace: &View<Workspace>,
+        cx: &mut VisualTestContext,
+    ) -> Rc<RefCell<Vec<(String, TaskId)>>> {
+        let spawned = Rc::new(RefCell::new(Vec::new()));
+        cx.update(|cx| {
+            let spawned = spawned.clone();
+            cx.subscribe(workspace, move |_, event, _| {
+                if let Event::SpawnTask { action } = event {
+                    spawned
+                        .borrow_mut()
+                        .push((action.label.clone(), action.id.clone()));
+                }
+            })
+            .detach();
+        });
+        spawned
+    }
+
+    fn labels(spawned: &Rc<RefCell<Vec<(String, TaskId)>>>) -> Vec<String> {
+        spawned
+            .borrow()
+            .iter()
+            .map(|(label, _)| label.clone())
+            .collect()
+    }
+}
@@ -20,7 +20,7 @@ use futures::{
 use fuzzy::CharBag;
 use git::GitHostingProviderRegistry;
 use git::{
-    repository::{GitFileStatus, GitRepository, RepoPath},
+    repository::{DiffBase, GitFileStatus, GitRepository, RepoPath},
     status::GitStatus,
     COOKIES, DOT_GIT, FSMONITOR_DAEMON, GITIGNORE,
 };
@@ -238,6 +238,17 @@ impl RepositoryEntry {
             relativize_path(path)
         }
     }
+
+    /// unrelativize is the inverse of relativize: it returns the project path of the given path
+    /// relative to the root folder of the repository, or `None` if it lies outside of the project.
+    pub fn unrelativize(&self, worktree: &Snapshot, path: &RepoPath) -> Option<Arc<Path>> {
+        let entry = worktree.entry_for_id(self.work_directory.0)?;
+        let path = match &self.location_in_repo {
+            Some(location_in_repo) => path.strip_prefix(location_in_repo).ok()?,
+            None => path.as_path(),
+        };
+        Some(entry.path.join(path).into())
+    }
 }
 
 impl From<&RepositoryEntry> for proto::RepositoryEntry {
@@ -709,7 +720,13 @@ impl Worktree {
         }
     }
 
-    pub fn load_staged_file(&self, path: &Path, cx: &AppContext) -> Task<Result<Option<String>>> {
+    /// Loads the contents of a file in the given diff base of its repository.
+    pub fn load_diff_base_file(
+        &self,
+        path: &Path,
+        base: DiffBase,
+        cx: &AppContext,
+    ) -> Task<Result<Option<String>>> {
         match self {
             Worktree::Local(this) => {
                 let path = Arc::from(path);
@@ -720,7 +737,9 @@ impl Worktree {
                             if let Some(git_repo) =
                                 snapshot.git_repositories.get(&*repo.work_directory)
                             {
-                                return Ok(git_repo.repo_ptr.load_index_text(&repo_path));
+                                return Ok(git_repo
+                                    .repo_ptr
+                                    .load_diff_base_text(&base, &repo_path));
                             }
                         }
                     }
@@ -47,7 +47,7 @@ use terminal_view::terminal_panel::{self, TerminalPanel};
 use theme::ActiveTheme;
 use util::{asset_str, ResultExt};
 use uuid::Uuid;
-use vim_mode_setting::VimModeSetting;
+use vim_mode_setting::{HelixModeSetting, VimModeSetting};
 use welcome::{BaseKeymap, MultibufferHint};
 use workspace::notifications::NotificationId;
 use workspace::CloseIntent;
@@ -319,6 +319,7 @@ fn initialize_panels(prompt_builder: Arc<PromptBuilder>, cx: &mut ViewContext<Wo
         );
         let assistant_panel =
             assistant::AssistantPanel::load(workspace_handle.clone(), prompt_builder, cx.clone());
+        let debug_panel = debugger_ui::DebugPanel::load(workspace_handle.clone(), cx.clone());
 
         let (
             project_panel,
@@ -328,6 +329,7 @@ fn initialize_panels(prompt_builder: Arc<PromptBuilder>, cx: &mut ViewContext<Wo
             chat_panel,
             notification_panel,
             assistant_panel,
+            debug_panel,
         ) = futures::try_join!(
             project_panel,
             outline_panel,
@@ -336,6 +338,7 @@ fn initialize_panels(prompt_builder: Arc<PromptBuilder>, cx: &mut ViewContext<Wo
             chat_panel,
             notification_panel,
             assistant_panel,
+            debug_panel,
         )?;
 
         workspace_handle.update(&mut cx, |workspace, cx| {
@@ -346,6 +349,7 @@ fn initialize_panels(prompt_builder: Arc<PromptBuilder>, cx: &mut ViewContext<Wo
             workspace.add_panel(chat_panel, cx);
             workspace.add_panel(notification_panel, cx);
             workspace.add_panel(assistant_panel, cx);
+            workspace.add_panel(debug_panel, cx);
         })?;
 
         let git_ui_enabled = git_ui_feature_flag.await;
@@ -902,18 +906,25 @@ pub fn handle_keymap_file_changes(
 ) {
     BaseKeymap::register(cx);
     VimModeSetting::register(cx);
+    HelixModeSetting::register(cx);
 
     let (base_keymap_tx, mut base_keymap_rx) = mpsc::unbounded();
     let (keyboard_layout_tx, mut keyboard_layout_rx) = mpsc::unbounded();
     let mut old_base_keymap = *BaseKeymap::get_global(cx);
     let mut old_vim_enabled = VimModeSetting::get_global(cx).0;
+    let mut old_helix_enabled = HelixModeSetting::get_global(cx).0;
     cx.observe_global::<SettingsStore>(move |cx| {
         let new_base_keymap = *BaseKeymap::get_global(cx);
         let new_vim_enabled = VimModeSetting::get_global(cx).0;
+        let new_helix_enabled = HelixModeSetting::get_global(cx).0;
 
-        if new_base_keymap != old_base_keymap || new_vim_enabled != old_vim_enabled {
+        if new_base_keymap != old_base_keymap
+            || new_vim_enabled != old_vim_enabled
+            || new_helix_enabled != old_helix_enabled
+        {
             old_base_keymap = new_base_keymap;
             old_vim_enabled = new_vim_enabled;
+            old_helix_enabled = new_helix_enabled;
             base_keymap_tx.unbounded_send(()).unwrap();
         }
     })
@@ -972,9 +983,13 @@ pub fn load_default_keymap(cx: &mut AppContext) {
     }
 
     KeymapFile::load_asset(DEFAULT_KEYMAP_PATH, cx).unwrap();
-    if VimModeSetting::get_global(cx).0 {
+    let helix_enabled = HelixModeSetting::get_global(cx).0;
+    if VimModeSetting::get_global(cx).0 || helix_enabled {
         KeymapFile::load_asset("keymaps/vim.json", cx).unwrap();
     }
+    if helix_enabled {
+        KeymapFile::load_asset("keymaps/helix.json", cx).unwrap();
+    }
 
     if let Some(asset_path) = base_keymap.asset_path() {
         KeymapFile::load_asset(asset_path, cx).unwrap();
@@ -51,6 +51,12 @@ pub mod branches {
     actions!(branches, [OpenRecent]);
 }
 
+pub mod git {
+    use gpui::actions;
+
+    actions!(git, [Fetch, Pull, Push]);
+}
+
 pub mod command_palette {
     use gpui::actions;
 